@@ -57,3 +57,7 @@ pub mod ignore_configured_endpoint_urls;
 
 /// Default endpoint URL provider chain
 pub mod endpoint_url;
+
+/// Default proxy provider chain
+#[cfg(feature = "client-hyper")]
+pub mod proxy;
@@ -0,0 +1,73 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::provider_config::ProviderConfig;
+use aws_smithy_runtime::client::http::proxy::ProxyConfig;
+use aws_smithy_types::error::display::DisplayErrorContext;
+use aws_types::os_shim_internal::Env;
+
+/// Load the proxy configuration from the environment
+///
+/// This checks the following environment variables. For each of them, the lowercase form takes
+/// precedence over the uppercase form.
+/// 1. `HTTP_PROXY`: the proxy for `http` requests
+/// 2. `HTTPS_PROXY`: the proxy for `https` requests
+/// 3. `ALL_PROXY`: the proxy for requests without a more specific proxy
+/// 4. `NO_PROXY`: a comma separated list of hosts, domains, and CIDR blocks that bypass the proxy
+///
+/// If no proxy is configured, the provider will return None. If invalid values are found, the
+/// provider will return None and an error will be logged.
+pub fn proxy_config_provider(provider_config: &ProviderConfig) -> Option<ProxyConfig> {
+    from_env(&provider_config.env())
+}
+
+pub(crate) fn from_env(env: &Env) -> Option<ProxyConfig> {
+    ProxyConfig::from_env_lookup(|name| env.get(name).ok())
+        .map_err(
+            |err| tracing::warn!(err = %DisplayErrorContext(&err), "invalid proxy configuration"),
+        )
+        .ok()
+        .filter(|proxy| !proxy.is_disabled())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::default_provider::proxy::proxy_config_provider;
+    use crate::provider_config::ProviderConfig;
+    use aws_types::os_shim_internal::Env;
+    use tracing_test::traced_test;
+
+    #[test]
+    fn no_proxy_configured() {
+        let conf = ProviderConfig::empty().with_env(Env::from_slice(&[("NO_PROXY", "localhost")]));
+        assert_eq!(None, proxy_config_provider(&conf));
+    }
+
+    #[test]
+    fn proxy_from_env() {
+        let conf = ProviderConfig::empty().with_env(Env::from_slice(&[
+            ("HTTPS_PROXY", "http://proxy.corp:3128"),
+            ("no_proxy", ".internal.corp,169.254.169.254"),
+        ]));
+        let proxy = proxy_config_provider(&conf).expect("proxy is configured");
+        assert_eq!(None, proxy.http_proxy());
+        assert_eq!(
+            "http://proxy.corp:3128/",
+            proxy.https_proxy().unwrap().to_string()
+        );
+        assert!(proxy.is_bypassed("sts.internal.corp"));
+        assert!(proxy.is_bypassed("169.254.169.254"));
+        assert!(!proxy.is_bypassed("sts.us-east-1.amazonaws.com"));
+    }
+
+    #[test]
+    #[traced_test]
+    fn log_error_on_invalid_value() {
+        let conf = ProviderConfig::empty()
+            .with_env(Env::from_slice(&[("HTTPS_PROXY", "socks5://proxy.corp")]));
+        assert_eq!(None, proxy_config_provider(&conf));
+        assert!(logs_contain("invalid proxy configuration"));
+    }
+}
@@ -278,6 +278,7 @@ mod loader {
         env: Option<Env>,
         fs: Option<Fs>,
         behavior_version: Option<BehaviorVersion>,
+        proxy_from_env: bool,
     }
 
     impl ConfigLoader {
@@ -381,6 +382,8 @@ mod loader {
         /// If you wish to use a separate HTTP client for credentials providers when creating clients,
         /// then override the HTTP client set with this function on the client-specific `Config`s.
         ///
+        /// [`proxy_from_env`](Self::proxy_from_env) has no effect on an HTTP client set with this function.
+        ///
         /// ## Examples
         ///
         /// ```no_run
@@ -661,6 +664,31 @@ mod loader {
             self
         }
 
+        /// Send requests through the proxy configured in the environment.
+        ///
+        /// When enabled, no HTTP client is set, and the `rustls` feature is enabled, the default HTTP
+        /// client sends requests through the proxy configured with the `HTTP_PROXY`, `HTTPS_PROXY`,
+        /// `ALL_PROXY`, and `NO_PROXY` environment variables. See
+        /// [`proxy_config_provider`](crate::default_provider::proxy::proxy_config_provider).
+        /// Requests to link-local addresses, such as the instance metadata service and the ECS
+        /// container credentials endpoint, are never proxied.
+        ///
+        /// Defaults to `false`.
+        ///
+        /// # Examples
+        /// ```no_run
+        /// # async fn create_config() {
+        /// let config = aws_config::from_env()
+        ///     .proxy_from_env(true)
+        ///     .load()
+        ///     .await;
+        /// # }
+        /// ```
+        pub fn proxy_from_env(mut self, proxy_from_env: bool) -> Self {
+            self.proxy_from_env = proxy_from_env;
+            self
+        }
+
         /// Override the [`StalledStreamProtectionConfig`] used to build [`SdkConfig`].
         ///
         /// This configures stalled stream protection. When enabled, download streams
@@ -721,13 +749,23 @@ mod loader {
                 default_async_sleep()
             };
 
+            let env = match &self.provider_config {
+                Some(provider_config) => provider_config.env(),
+                None => self.env.unwrap_or_default(),
+            };
+            let http_client = match self.http_client {
+                Some(http_client) => Some(http_client),
+                None if self.proxy_from_env => proxied_http_client(&env),
+                None => None,
+            };
+
             let conf = self
                 .provider_config
                 .unwrap_or_else(|| {
                     let mut config = ProviderConfig::init(time_source.clone(), sleep_impl.clone())
                         .with_fs(self.fs.unwrap_or_default())
-                        .with_env(self.env.unwrap_or_default());
-                    if let Some(http_client) = self.http_client.clone() {
+                        .with_env(env);
+                    if let Some(http_client) = http_client.clone() {
                         config = config.with_http_client(http_client);
                     }
                     config
@@ -856,7 +894,7 @@ mod loader {
             builder.set_endpoint_url(endpoint_url);
 
             builder.set_behavior_version(self.behavior_version);
-            builder.set_http_client(self.http_client);
+            builder.set_http_client(http_client);
             builder.set_app_name(app_name);
 
             let identity_cache = match self.identity_cache {
@@ -880,6 +918,23 @@ mod loader {
         }
     }
 
+    /// Creates the default HTTP client with the proxy configured in `env`, if there is one
+    #[cfg(feature = "rustls")]
+    fn proxied_http_client(env: &Env) -> Option<SharedHttpClient> {
+        let proxy = crate::default_provider::proxy::from_env(env)?;
+        tracing::debug!(proxy = ?proxy, "using the proxy configured in the environment");
+        Some(
+            aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder::new()
+                .proxy(proxy)
+                .build_https(),
+        )
+    }
+
+    #[cfg(not(feature = "rustls"))]
+    fn proxied_http_client(_env: &Env) -> Option<SharedHttpClient> {
+        None
+    }
+
     #[cfg(test)]
     impl ConfigLoader {
         pub(crate) fn env(mut self, env: Env) -> Self {
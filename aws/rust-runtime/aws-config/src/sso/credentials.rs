@@ -33,6 +33,9 @@ use std::convert::TryInto;
 /// Two different values will be tried for `<hash>` in order:
 /// 1. The configured [`session_name`](Builder::session_name).
 /// 2. The configured [`start_url`](Builder::start_url).
+///
+/// Credentials of this provider can be kept in a persistent identity store, keyed by the account
+/// ID, role name, region, and start URL.
 #[derive(Debug)]
 pub struct SsoCredentialsProvider {
     fs: Fs,
@@ -99,6 +102,15 @@ impl ProvideCredentials for SsoCredentialsProvider {
     {
         future::ProvideCredentials::new(self.credentials())
     }
+
+    fn persistent_cache_key(&self) -> Option<String> {
+        let config = &self.sso_provider_config;
+        // The start URL goes last since it's the only part that may contain a `:`
+        Some(format!(
+            "sso:{}:{}:{}:{}",
+            config.account_id, config.role_name, config.region, config.start_url
+        ))
+    }
 }
 
 /// Builder for [`SsoCredentialsProvider`]
@@ -290,3 +302,30 @@ async fn load_sso_credentials(
         "SSO",
     ))
 }
+
+#[cfg(test)]
+mod test {
+    use super::SsoCredentialsProvider;
+    use aws_credential_types::provider::ProvideCredentials;
+    use aws_types::region::Region;
+
+    #[test]
+    fn persistent_cache_key_identifies_the_role() {
+        let provider = |account_id: &str, role_name: &str| {
+            SsoCredentialsProvider::builder()
+                .account_id(account_id)
+                .role_name(role_name)
+                .region(Region::new("us-east-1"))
+                .start_url("https://d-123.awsapps.com/start")
+                .build()
+        };
+        assert_eq!(
+            Some("sso:123456789012:Admin:us-east-1:https://d-123.awsapps.com/start".to_string()),
+            provider("123456789012", "Admin").persistent_cache_key()
+        );
+        assert_ne!(
+            provider("123456789012", "Admin").persistent_cache_key(),
+            provider("123456789012", "ReadOnly").persistent_cache_key()
+        );
+    }
+}
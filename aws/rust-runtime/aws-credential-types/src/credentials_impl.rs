@@ -10,6 +10,8 @@ use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use zeroize::Zeroizing;
 
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::identity::store::IdentityCodec;
 use aws_smithy_runtime_api::client::identity::Identity;
 
 /// AWS SDK Credentials
@@ -202,9 +204,73 @@ impl From<Credentials> for Identity {
     }
 }
 
+/// [`IdentityCodec`] for [`Credentials`], for persisting them in an identity store.
+///
+/// Credentials loaded from a store report `IdentityStore` as their provider name.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct CredentialsCodec;
+
+const STORED_CREDENTIALS: &str = "IdentityStore";
+
+impl CredentialsCodec {
+    /// Creates a new `CredentialsCodec`.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl IdentityCodec for CredentialsCodec {
+    fn encode(&self, identity: &Identity) -> Option<Vec<u8>> {
+        let creds = identity.data::<Credentials>()?;
+        let mut data = Vec::new();
+        for field in [creds.access_key_id(), creds.secret_access_key()]
+            .into_iter()
+            .chain(creds.session_token())
+        {
+            let field = field.as_bytes();
+            data.extend_from_slice(&u32::try_from(field.len()).ok()?.to_be_bytes());
+            data.extend_from_slice(field);
+        }
+        Some(data)
+    }
+
+    fn decode(&self, mut data: &[u8], expiration: SystemTime) -> Result<Identity, BoxError> {
+        let mut fields = Vec::with_capacity(3);
+        while !data.is_empty() {
+            if data.len() < 4 || fields.len() == 3 {
+                return Err("the stored credentials are malformed".into());
+            }
+            let (len, rest) = data.split_at(4);
+            let len = u32::from_be_bytes(len.try_into().expect("4 bytes")) as usize;
+            if rest.len() < len {
+                return Err("the stored credentials are truncated".into());
+            }
+            let (field, rest) = rest.split_at(len);
+            fields.push(std::str::from_utf8(field)?.to_string());
+            data = rest;
+        }
+        let mut fields = fields.into_iter();
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(access_key_id), Some(secret_access_key), session_token) => Ok(Credentials::new(
+                access_key_id,
+                secret_access_key,
+                session_token,
+                Some(expiration),
+                STORED_CREDENTIALS,
+            )
+            .into()),
+            _ => Err("the stored credentials are missing a key".into()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use super::CredentialsCodec;
     use crate::Credentials;
+    use aws_smithy_runtime_api::client::identity::store::IdentityCodec;
+    use aws_smithy_runtime_api::client::identity::Identity;
     use std::time::{Duration, UNIX_EPOCH};
 
     #[test]
@@ -221,4 +287,34 @@ mod test {
             r#"Credentials { provider_name: "debug tester", access_key_id: "akid", secret_access_key: "** redacted **", expires_after: "2009-02-13T23:31:30Z" }"#
         );
     }
+
+    #[test]
+    fn codec_round_trips_credentials() {
+        let codec = CredentialsCodec::new();
+        let expiration = UNIX_EPOCH + Duration::from_secs(1234567890);
+        for session_token in [None, Some("token".to_string()), Some(String::new())] {
+            let creds = Credentials::new("akid", "secret", session_token, None, "test");
+            let data = codec.encode(&creds.clone().into()).unwrap();
+            let identity = codec.decode(&data, expiration).unwrap();
+            assert_eq!(Some(expiration), identity.expiration());
+            let decoded = identity.data::<Credentials>().unwrap();
+            assert_eq!(creds.access_key_id(), decoded.access_key_id());
+            assert_eq!(creds.secret_access_key(), decoded.secret_access_key());
+            assert_eq!(creds.session_token(), decoded.session_token());
+        }
+    }
+
+    #[test]
+    fn codec_rejects_malformed_data() {
+        let codec = CredentialsCodec::new();
+        let data = codec
+            .encode(&Credentials::new("akid", "secret", None, None, "test").into())
+            .unwrap();
+        assert!(codec.decode(&data[..data.len() - 1], UNIX_EPOCH).is_err());
+        assert!(codec.decode(&data[..10], UNIX_EPOCH).is_err());
+        assert!(codec.decode(&[], UNIX_EPOCH).is_err());
+
+        // Other identities aren't encoded
+        assert!(codec.encode(&Identity::new("token", None)).is_none());
+    }
 }
@@ -101,6 +101,16 @@ pub trait ProvideCredentials: Send + Sync + std::fmt::Debug {
     fn fallback_on_interrupt(&self) -> Option<Credentials> {
         None
     }
+
+    /// Returns a key that identifies this provider across processes.
+    ///
+    /// Identity caches with a persistent store keep the credentials of this provider under this
+    /// key. See [`ResolveIdentity::persistent_cache_key`] for what the key must include.
+    ///
+    /// By default this returns `None`, and credentials of this provider aren't persisted.
+    fn persistent_cache_key(&self) -> Option<String> {
+        None
+    }
 }
 
 impl ProvideCredentials for Credentials {
@@ -119,6 +129,10 @@ impl ProvideCredentials for Arc<dyn ProvideCredentials> {
     {
         self.as_ref().provide_credentials()
     }
+
+    fn persistent_cache_key(&self) -> Option<String> {
+        self.as_ref().persistent_cache_key()
+    }
 }
 
 /// Credentials Provider wrapper that may be shared
@@ -157,6 +171,10 @@ impl ProvideCredentials for SharedCredentialsProvider {
     {
         self.0.provide_credentials()
     }
+
+    fn persistent_cache_key(&self) -> Option<String> {
+        self.0.persistent_cache_key()
+    }
 }
 
 impl Storable for SharedCredentialsProvider {
@@ -179,6 +197,10 @@ impl ResolveIdentity for SharedCredentialsProvider {
     fn cache_partition(&self) -> Option<IdentityCachePartition> {
         Some(self.1)
     }
+
+    fn persistent_cache_key(&self) -> Option<String> {
+        ProvideCredentials::persistent_cache_key(self)
+    }
 }
 
 #[cfg(test)]
@@ -199,4 +221,38 @@ mod tests {
 
         assert!(partition.unwrap() == identity_partition);
     }
+
+    #[test]
+    fn forwards_persistent_cache_key() {
+        #[derive(Debug)]
+        struct Persistent;
+        impl ProvideCredentials for Persistent {
+            fn provide_credentials<'a>(&'a self) -> crate::provider::future::ProvideCredentials<'a>
+            where
+                Self: 'a,
+            {
+                crate::provider::future::ProvideCredentials::ready(Ok(Credentials::new(
+                    "AKID", "SECRET", None, None, "test",
+                )))
+            }
+
+            fn persistent_cache_key(&self) -> Option<String> {
+                Some("persistent".into())
+            }
+        }
+
+        let identity_resolver = SharedIdentityResolver::new(SharedCredentialsProvider::new(
+            Arc::new(Persistent) as Arc<dyn ProvideCredentials>,
+        ));
+        assert_eq!(
+            Some("persistent".to_string()),
+            identity_resolver.persistent_cache_key()
+        );
+
+        let creds = Credentials::new("AKID", "SECRET", None, None, "test");
+        assert_eq!(
+            None,
+            ResolveIdentity::persistent_cache_key(&SharedCredentialsProvider::new(creds))
+        );
+    }
 }
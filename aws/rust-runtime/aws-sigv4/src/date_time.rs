@@ -7,7 +7,7 @@
 #![allow(dead_code)]
 
 use std::time::SystemTime;
-use time::{OffsetDateTime, Time};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
 
 /// Truncates the subseconds from the given `SystemTime` to zero.
 pub(crate) fn truncate_subsecs(time: SystemTime) -> SystemTime {
@@ -45,6 +45,32 @@ pub(crate) fn format_date_time(time: SystemTime) -> String {
     )
 }
 
+/// Parses a `YYYYMMDD'T'HHMMSS'Z'` formatted date time, as found in the `X-Amz-Date` header.
+///
+/// Returns `None` if the input isn't in exactly that format.
+pub(crate) fn parse_date_time(date_time: &str) -> Option<SystemTime> {
+    let bytes = date_time.as_bytes();
+    if bytes.len() != 16 || bytes[8] != b'T' || bytes[15] != b'Z' {
+        return None;
+    }
+    let digits = |start: usize, end: usize| -> Option<u32> {
+        let digits = &date_time[start..end];
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    let month = Month::try_from(u8::try_from(digits(4, 6)?).ok()?).ok()?;
+    let date = Date::from_calendar_date(digits(0, 4)? as i32, month, digits(6, 8)? as u8).ok()?;
+    let time = Time::from_hms(
+        digits(9, 11)? as u8,
+        digits(11, 13)? as u8,
+        digits(13, 15)? as u8,
+    )
+    .ok()?;
+    Some(PrimitiveDateTime::new(date, time).assume_utc().into())
+}
+
 /// Parse functions that are only needed for unit tests.
 #[cfg(test)]
 pub(crate) mod test_parsers {
@@ -129,6 +155,16 @@ mod tests {
         assert_eq!("20150830T123600Z", format_date_time(time));
     }
 
+    #[test]
+    fn parse_x_amz_date() {
+        let expected = test_parsers::parse_date_time("20150830T123600Z").unwrap();
+        assert_eq!(Some(expected), super::parse_date_time("20150830T123600Z"));
+        assert_eq!(None, super::parse_date_time("20150830T123600"));
+        assert_eq!(None, super::parse_date_time("2015083+T123600Z"));
+        assert_eq!(None, super::parse_date_time("20151330T123600Z"));
+        assert_eq!(None, super::parse_date_time("20150830T126000Z"));
+    }
+
     #[test]
     fn date_roundtrip() {
         let time = parse_date("20150830").unwrap();
@@ -61,6 +61,7 @@ mod settings;
 mod sign;
 mod uri_path_normalization;
 mod url_escape;
+mod verify;
 
 #[cfg(test)]
 pub(crate) mod test;
@@ -77,6 +78,10 @@ pub use settings::{
 };
 pub use sign::{sign, SignableBody, SignableRequest, SigningInstructions};
 use std::time::SystemTime;
+pub use verify::{
+    parse_signature, verify, verify_signing_time, ParsedSignature, VerificationError,
+    VerificationSettings,
+};
 
 // Individual Debug impls are responsible for redacting sensitive fields.
 #[derive(Debug)]
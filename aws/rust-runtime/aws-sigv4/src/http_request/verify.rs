@@ -0,0 +1,901 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Verification of SigV4-signed HTTP requests.
+//!
+//! Verification reuses the same canonical request construction as signing. The signature
+//! values are first extracted from the request with [`parse_signature`], which gives a server
+//! the access key ID it needs to look up the signer's credentials. Then [`verify`] rebuilds the
+//! canonical request from the headers that the client claims to have signed, recomputes the
+//! signature with those credentials, and compares it against the one the client sent.
+
+use super::canonical_request::{header, param, CanonicalRequest, StringToSign, HMAC_256};
+use super::error::CanonicalRequestError;
+use super::{
+    PayloadChecksumKind, PercentEncodingMode, SessionTokenMode, SignableBody, SignableRequest,
+    SignatureLocation, SigningParams, SigningSettings, UriPathNormalizationMode,
+};
+use crate::date_time::{format_date, parse_date_time};
+use crate::sign::v4;
+use aws_credential_types::Credentials;
+use aws_smithy_http::query_writer::QueryWriter;
+use aws_smithy_runtime_api::client::identity::Identity;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+const AUTHORIZATION: &str = "authorization";
+const HOST: &str = "host";
+const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// Settings used when verifying a signed request.
+///
+/// These must match the [`SigningSettings`] the client used when signing. The defaults match the
+/// defaults of [`SigningSettings`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct VerificationSettings {
+    /// How the request URL was encoded by the client when signing.
+    pub percent_encoding_mode: PercentEncodingMode,
+
+    /// Whether the client normalized the URI path when signing.
+    pub uri_path_normalization_mode: UriPathNormalizationMode,
+
+    /// The name of the session token header or query param, if the client used an alternative
+    /// to `x-amz-security-token` or `X-Amz-Security-Token`.
+    pub session_token_name_override: Option<&'static str>,
+
+    /// The maximum difference allowed between the time the request was signed and the time it
+    /// is verified.
+    ///
+    /// For presigned requests, this tolerance is applied on both ends of the validity window.
+    pub max_clock_skew: Duration,
+}
+
+impl Default for VerificationSettings {
+    fn default() -> Self {
+        Self {
+            percent_encoding_mode: PercentEncodingMode::Double,
+            uri_path_normalization_mode: UriPathNormalizationMode::Enabled,
+            session_token_name_override: None,
+            max_clock_skew: DEFAULT_MAX_CLOCK_SKEW,
+        }
+    }
+}
+
+/// The signature values extracted from a signed request by [`parse_signature`].
+#[derive(Clone, PartialEq)]
+pub struct ParsedSignature {
+    location: SignatureLocation,
+    access_key_id: String,
+    time: SystemTime,
+    region: String,
+    service: String,
+    signed_headers: String,
+    signature: String,
+    expires_in: Option<Duration>,
+}
+
+impl ParsedSignature {
+    /// Where the signature was found in the request.
+    pub fn location(&self) -> SignatureLocation {
+        self.location
+    }
+
+    /// The access key ID of the credentials that signed the request.
+    pub fn access_key_id(&self) -> &str {
+        &self.access_key_id
+    }
+
+    /// The time the request was signed at.
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    /// The region from the credential scope.
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    /// The service signing name from the credential scope.
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// The names of the signed headers, in canonical (lowercase, sorted) form.
+    pub fn signed_headers(&self) -> impl Iterator<Item = &str> {
+        self.signed_headers.split(';')
+    }
+
+    /// For presigned requests, how long the request is valid for.
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.expires_in
+    }
+}
+
+impl fmt::Debug for ParsedSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParsedSignature")
+            .field("location", &self.location)
+            .field("access_key_id", &self.access_key_id)
+            .field("time", &self.time)
+            .field("region", &self.region)
+            .field("service", &self.service)
+            .field("signed_headers", &self.signed_headers)
+            .field("signature", &"** REDACTED **")
+            .field("expires_in", &self.expires_in)
+            .finish()
+    }
+}
+
+/// Extracts the SigV4 signature values from a request.
+///
+/// The signature is read from the `Authorization` header if present, and from the
+/// `X-Amz-*` query params of a presigned request otherwise.
+pub fn parse_signature(
+    request: &SignableRequest<'_>,
+) -> Result<ParsedSignature, VerificationError> {
+    match find_header(request, AUTHORIZATION) {
+        Some(authorization) => parse_authorization_header(request, authorization),
+        None => parse_query_params(request),
+    }
+}
+
+/// Verifies that `request` was signed with `credentials`.
+///
+/// `signature` must have been parsed from the same request with [`parse_signature`]. `now` is
+/// compared against the signing time, using the clock skew tolerance from `settings`.
+///
+/// If the request carries an `x-amz-content-sha256` header, its value is used as the payload
+/// hash, and checked against the body when the body is given as [`SignableBody::Bytes`].
+/// Otherwise, the payload hash is computed from the request body.
+pub fn verify(
+    request: &SignableRequest<'_>,
+    signature: &ParsedSignature,
+    credentials: &Credentials,
+    settings: &VerificationSettings,
+    now: SystemTime,
+) -> Result<(), VerificationError> {
+    if credentials.access_key_id() != signature.access_key_id {
+        return Err(VerificationError::invalid_signature());
+    }
+    verify_signing_time(signature, settings, now)?;
+
+    let signed_headers: Vec<&str> = signature.signed_headers().collect();
+    if !signed_headers.contains(&HOST)
+        || (find_header(request, HOST).is_none() && request.uri().authority().is_none())
+    {
+        return Err(VerificationError::host_not_signed());
+    }
+
+    // Only the headers the client claims to have signed take part in the canonical request. When
+    // signing with headers, the date and the session token are added back while building the
+    // canonical request.
+    let token_header = settings
+        .session_token_name_override
+        .unwrap_or(header::X_AMZ_SECURITY_TOKEN);
+    let headers = request.headers().iter().copied().filter(|(name, _)| {
+        let name = name.to_ascii_lowercase();
+        signed_headers.contains(&name.as_str())
+            && (signature.location == SignatureLocation::QueryParams
+                || (name != header::X_AMZ_DATE && name != token_header))
+    });
+    let uri = match signature.location {
+        SignatureLocation::Headers => Cow::Owned(request.uri().to_string()),
+        SignatureLocation::QueryParams => Cow::Owned(strip_signing_params(request, settings)),
+    };
+    let body = payload(request)?;
+    let unsigned = SignableRequest::new(request.method(), uri, headers, body)
+        .map_err(|_| VerificationError::malformed("the request URI is invalid"))?;
+
+    let identity = Identity::from(credentials.clone());
+    let signing_settings = SigningSettings {
+        percent_encoding_mode: settings.percent_encoding_mode,
+        payload_checksum_kind: PayloadChecksumKind::NoHeader,
+        signature_location: signature.location,
+        expires_in: signature.expires_in,
+        excluded_headers: None,
+        uri_path_normalization_mode: settings.uri_path_normalization_mode,
+        session_token_mode: SessionTokenMode::Include,
+        session_token_name_override: settings.session_token_name_override,
+    };
+    let params: SigningParams<'_> = v4::SigningParams {
+        identity: &identity,
+        region: &signature.region,
+        name: &signature.service,
+        time: signature.time,
+        settings: signing_settings,
+    }
+    .into();
+
+    let creq = CanonicalRequest::from(&unsigned, &params)?;
+    if creq.values.signed_headers().as_str() != signature.signed_headers {
+        return Err(VerificationError::signed_headers_mismatch());
+    }
+    let encoded_creq = v4::sha256_hex_string(creq.to_string().as_bytes());
+    let string_to_sign = StringToSign::new_v4(
+        signature.time,
+        &signature.region,
+        &signature.service,
+        &encoded_creq,
+    )
+    .to_string();
+    let signing_key = v4::generate_signing_key(
+        credentials.secret_access_key(),
+        signature.time,
+        &signature.region,
+        &signature.service,
+    );
+    let expected = v4::calculate_signature(signing_key, string_to_sign.as_bytes());
+    tracing::trace!(canonical_request = %creq, string_to_sign = %string_to_sign, "recomputed signature for verification");
+
+    if constant_time_eq(expected.as_bytes(), signature.signature.as_bytes()) {
+        Ok(())
+    } else {
+        Err(VerificationError::invalid_signature())
+    }
+}
+
+fn find_header<'a>(request: &'a SignableRequest<'_>, name: &str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| *value)
+}
+
+fn find_param<'a>(params: &'a [(Cow<'a, str>, Cow<'a, str>)], name: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_ref())
+}
+
+// Authorization: algorithm Credential=access key ID/credential scope, SignedHeaders=SignedHeaders, Signature=signature
+fn parse_authorization_header(
+    request: &SignableRequest<'_>,
+    authorization: &str,
+) -> Result<ParsedSignature, VerificationError> {
+    let (algorithm, components) = authorization
+        .trim()
+        .split_once(' ')
+        .ok_or_else(|| VerificationError::malformed("the authorization header is malformed"))?;
+    if algorithm != HMAC_256 {
+        return Err(VerificationError::unsupported_algorithm());
+    }
+
+    let (mut credential, mut signed_headers, mut signature) = (None, None, None);
+    for component in components.split(',') {
+        match component.trim().split_once('=') {
+            Some(("Credential", value)) => credential = Some(value),
+            Some(("SignedHeaders", value)) => signed_headers = Some(value),
+            Some(("Signature", value)) => signature = Some(value),
+            _ => {}
+        }
+    }
+    let missing =
+        || VerificationError::malformed("the authorization header is missing a component");
+    let date_time = find_header(request, header::X_AMZ_DATE)
+        .ok_or_else(|| VerificationError::malformed("the x-amz-date header is missing"))?;
+
+    ParsedSignature::new(
+        SignatureLocation::Headers,
+        credential.ok_or_else(missing)?,
+        date_time,
+        signed_headers.ok_or_else(missing)?,
+        signature.ok_or_else(missing)?,
+        None,
+    )
+}
+
+fn parse_query_params(request: &SignableRequest<'_>) -> Result<ParsedSignature, VerificationError> {
+    let query = request.uri().query().unwrap_or_default();
+    let params: Vec<_> = form_urlencoded::parse(query.as_bytes()).collect();
+    let algorithm =
+        find_param(&params, param::X_AMZ_ALGORITHM).ok_or_else(VerificationError::missing)?;
+    if algorithm != HMAC_256 {
+        return Err(VerificationError::unsupported_algorithm());
+    }
+
+    let required = |name: &'static str| {
+        find_param(&params, name).ok_or_else(|| VerificationError::missing_param(name))
+    };
+    let expires_in = required(param::X_AMZ_EXPIRES)?
+        .parse::<u64>()
+        .map_err(|_| VerificationError::malformed("the X-Amz-Expires param is not a number"))?;
+    if expires_in > MAX_EXPIRES_IN_SECS {
+        return Err(VerificationError::malformed(format!(
+            "the X-Amz-Expires param must not exceed {MAX_EXPIRES_IN_SECS} seconds"
+        )));
+    }
+
+    ParsedSignature::new(
+        SignatureLocation::QueryParams,
+        required(param::X_AMZ_CREDENTIAL)?,
+        required(param::X_AMZ_DATE)?,
+        required(param::X_AMZ_SIGNED_HEADERS)?,
+        required(param::X_AMZ_SIGNATURE)?,
+        Some(Duration::from_secs(expires_in)),
+    )
+}
+
+impl ParsedSignature {
+    fn new(
+        location: SignatureLocation,
+        credential: &str,
+        date_time: &str,
+        signed_headers: &str,
+        signature: &str,
+        expires_in: Option<Duration>,
+    ) -> Result<Self, VerificationError> {
+        // Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request
+        let malformed_credential =
+            || VerificationError::malformed("the credential scope is malformed");
+        let mut scope = credential.split('/');
+        let (access_key_id, date, region, service, terminator) = (
+            scope.next().ok_or_else(malformed_credential)?,
+            scope.next().ok_or_else(malformed_credential)?,
+            scope.next().ok_or_else(malformed_credential)?,
+            scope.next().ok_or_else(malformed_credential)?,
+            scope.next().ok_or_else(malformed_credential)?,
+        );
+        if scope.next().is_some() || terminator != "aws4_request" || access_key_id.is_empty() {
+            return Err(malformed_credential());
+        }
+
+        let time = parse_date_time(date_time)
+            .ok_or_else(|| VerificationError::malformed("the signing date is malformed"))?;
+        if format_date(time) != date {
+            return Err(VerificationError::malformed(
+                "the credential scope date does not match the signing date",
+            ));
+        }
+
+        Ok(Self {
+            location,
+            access_key_id: access_key_id.into(),
+            time,
+            region: region.into(),
+            service: service.into(),
+            signed_headers: signed_headers.into(),
+            signature: signature.into(),
+            expires_in,
+        })
+    }
+}
+
+/// The longest a presigned request can be valid for: seven days.
+const MAX_EXPIRES_IN_SECS: u64 = 604800;
+
+/// Checks that `now` is within the time window `signature` is valid for, using the clock skew
+/// tolerance from `settings`.
+///
+/// [`verify`] does this too, but this check doesn't need the credentials or the body of the
+/// request, so it can be used to reject stale requests before reading their body.
+pub fn verify_signing_time(
+    signature: &ParsedSignature,
+    settings: &VerificationSettings,
+    now: SystemTime,
+) -> Result<(), VerificationError> {
+    let not_before = signature
+        .time
+        .checked_sub(settings.max_clock_skew)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let not_after = signature
+        .time
+        .checked_add(signature.expires_in.unwrap_or_default())
+        .and_then(|time| time.checked_add(settings.max_clock_skew))
+        .ok_or_else(|| {
+            VerificationError::malformed("the signature validity window is too large")
+        })?;
+    if now < not_before {
+        Err(VerificationError::signed_in_future())
+    } else if now > not_after {
+        Err(VerificationError::expired())
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the request URI without the query params added by presigning.
+fn strip_signing_params(request: &SignableRequest<'_>, settings: &VerificationSettings) -> String {
+    let signing_params = [
+        param::X_AMZ_ALGORITHM,
+        param::X_AMZ_CREDENTIAL,
+        param::X_AMZ_DATE,
+        param::X_AMZ_EXPIRES,
+        param::X_AMZ_SIGNED_HEADERS,
+        param::X_AMZ_SIGNATURE,
+        settings
+            .session_token_name_override
+            .unwrap_or(param::X_AMZ_SECURITY_TOKEN),
+    ];
+    let query = request.uri().query().unwrap_or_default();
+    let mut writer = QueryWriter::new(request.uri());
+    writer.clear_params();
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        if !signing_params.contains(&key.as_ref()) {
+            writer.insert(&key, &value);
+        }
+    }
+    writer.build_uri().to_string()
+}
+
+fn payload<'a>(request: &'a SignableRequest<'_>) -> Result<SignableBody<'a>, VerificationError> {
+    let content_sha256 = match find_header(request, header::X_AMZ_CONTENT_SHA_256) {
+        Some(content_sha256) => content_sha256,
+        None => return Ok(request.body().clone()),
+    };
+    match request.body() {
+        // A hex-encoded digest must match the body that was actually received
+        SignableBody::Bytes(data)
+            if content_sha256.len() == 64
+                && content_sha256.bytes().all(|b| b.is_ascii_hexdigit())
+                && v4::sha256_hex_string(data) != content_sha256 =>
+        {
+            Err(VerificationError::payload_hash_mismatch())
+        }
+        _ => Ok(SignableBody::Precomputed(content_sha256.into())),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[derive(Debug)]
+enum VerificationErrorKind {
+    Missing,
+    Malformed { reason: Cow<'static, str> },
+    UnsupportedAlgorithm,
+    HostNotSigned,
+    SignedInFuture,
+    Expired,
+    SignedHeadersMismatch,
+    PayloadHashMismatch,
+    InvalidSignature,
+    FailedToCreateCanonicalRequest { source: CanonicalRequestError },
+}
+
+/// Error verifying a signed request
+#[derive(Debug)]
+pub struct VerificationError {
+    kind: VerificationErrorKind,
+}
+
+impl VerificationError {
+    fn missing() -> Self {
+        Self {
+            kind: VerificationErrorKind::Missing,
+        }
+    }
+
+    fn missing_param(name: &'static str) -> Self {
+        Self::malformed(format!("the {name} param is missing"))
+    }
+
+    fn malformed(reason: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            kind: VerificationErrorKind::Malformed {
+                reason: reason.into(),
+            },
+        }
+    }
+
+    fn unsupported_algorithm() -> Self {
+        Self {
+            kind: VerificationErrorKind::UnsupportedAlgorithm,
+        }
+    }
+
+    fn host_not_signed() -> Self {
+        Self {
+            kind: VerificationErrorKind::HostNotSigned,
+        }
+    }
+
+    fn signed_in_future() -> Self {
+        Self {
+            kind: VerificationErrorKind::SignedInFuture,
+        }
+    }
+
+    fn expired() -> Self {
+        Self {
+            kind: VerificationErrorKind::Expired,
+        }
+    }
+
+    fn signed_headers_mismatch() -> Self {
+        Self {
+            kind: VerificationErrorKind::SignedHeadersMismatch,
+        }
+    }
+
+    fn payload_hash_mismatch() -> Self {
+        Self {
+            kind: VerificationErrorKind::PayloadHashMismatch,
+        }
+    }
+
+    fn invalid_signature() -> Self {
+        Self {
+            kind: VerificationErrorKind::InvalidSignature,
+        }
+    }
+
+    /// Returns true if the request didn't carry a signature at all.
+    pub fn is_missing(&self) -> bool {
+        matches!(self.kind, VerificationErrorKind::Missing)
+    }
+
+    /// Returns true if the request was signed outside the accepted time window.
+    pub fn is_time_skewed(&self) -> bool {
+        matches!(
+            self.kind,
+            VerificationErrorKind::SignedInFuture | VerificationErrorKind::Expired
+        )
+    }
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use VerificationErrorKind::*;
+        match &self.kind {
+            Missing => write!(f, "the request is not signed"),
+            Malformed { reason } => write!(f, "the request signature is malformed: {reason}"),
+            UnsupportedAlgorithm => write!(f, "only {HMAC_256} signatures are supported"),
+            HostNotSigned => write!(f, "the host header must be signed"),
+            SignedInFuture => write!(f, "the request was signed too far in the future"),
+            Expired => write!(f, "the request signature has expired"),
+            SignedHeadersMismatch => {
+                write!(f, "the signed headers do not match the request headers")
+            }
+            PayloadHashMismatch => {
+                write!(f, "the x-amz-content-sha256 header does not match the body")
+            }
+            InvalidSignature => write!(f, "the request signature does not match"),
+            FailedToCreateCanonicalRequest { .. } => {
+                write!(f, "failed to create canonical request")
+            }
+        }
+    }
+}
+
+impl Error for VerificationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            VerificationErrorKind::FailedToCreateCanonicalRequest { source } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<CanonicalRequestError> for VerificationError {
+    fn from(source: CanonicalRequestError) -> Self {
+        Self {
+            kind: VerificationErrorKind::FailedToCreateCanonicalRequest { source },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date_time::test_parsers::parse_date_time;
+    use crate::http_request::test;
+    use crate::http_request::{sign, SigningInstructions};
+    use aws_credential_types::Credentials;
+    use http0::Request;
+
+    fn signing_time() -> SystemTime {
+        parse_date_time("20150830T123600Z").unwrap()
+    }
+
+    fn sign_request(
+        request: &mut Request<&'static str>,
+        credentials: &Credentials,
+        settings: SigningSettings,
+    ) {
+        let identity = credentials.clone().into();
+        let params = v4::SigningParams {
+            identity: &identity,
+            region: "us-east-1",
+            name: "service",
+            time: signing_time(),
+            settings,
+        }
+        .into();
+        let signable = SignableRequest::new(
+            request.method().as_str(),
+            request.uri().to_string(),
+            request
+                .headers()
+                .iter()
+                .map(|(k, v)| (k.as_str(), std::str::from_utf8(v.as_bytes()).unwrap())),
+            SignableBody::Bytes(request.body().as_bytes()),
+        )
+        .unwrap();
+        let instructions: SigningInstructions = sign(signable, &params).unwrap().into_parts().0;
+        instructions.apply_to_request_http0x(request);
+    }
+
+    fn check(
+        request: &Request<&'static str>,
+        credentials: &Credentials,
+        now: SystemTime,
+    ) -> Result<ParsedSignature, VerificationError> {
+        let signable = SignableRequest::new(
+            request.method().as_str(),
+            request.uri().to_string(),
+            request
+                .headers()
+                .iter()
+                .map(|(k, v)| (k.as_str(), std::str::from_utf8(v.as_bytes()).unwrap())),
+            SignableBody::Bytes(request.body().as_bytes()),
+        )
+        .unwrap();
+        let signature = parse_signature(&signable)?;
+        verify(
+            &signable,
+            &signature,
+            credentials,
+            &VerificationSettings::default(),
+            now,
+        )?;
+        Ok(signature)
+    }
+
+    fn request() -> Request<&'static str> {
+        Request::builder()
+            .method("POST")
+            .uri("https://example.amazonaws.com/some/path?foo=bar&baz=qux")
+            .header("content-type", "application/json")
+            .body("{\"some\":\"body\"}")
+            .unwrap()
+    }
+
+    #[test]
+    fn verify_test_suite_request() {
+        let test_request = test::v4::test_signed_request("get-vanilla");
+        let signable = SignableRequest::from(&test_request);
+        let signature = parse_signature(&signable).unwrap();
+        assert_eq!("AKIDEXAMPLE", signature.access_key_id());
+        assert_eq!("us-east-1", signature.region());
+        assert_eq!("service", signature.service());
+        assert_eq!(
+            vec!["host", "x-amz-date"],
+            signature.signed_headers().collect::<Vec<_>>()
+        );
+
+        let credentials = Credentials::from_keys(
+            "AKIDEXAMPLE",
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            None,
+        );
+        verify(
+            &signable,
+            &signature,
+            &credentials,
+            &VerificationSettings::default(),
+            signing_time(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn verify_signed_headers() {
+        let credentials = Credentials::for_tests();
+        let mut request = request();
+        sign_request(&mut request, &credentials, SigningSettings::default());
+
+        let signature = check(&request, &credentials, signing_time()).unwrap();
+        assert_eq!(SignatureLocation::Headers, signature.location());
+        assert_eq!(None, signature.expires_in());
+    }
+
+    #[test]
+    fn verify_signed_headers_with_session_token() {
+        let credentials = Credentials::for_tests_with_session_token();
+        let mut request = request();
+        sign_request(&mut request, &credentials, SigningSettings::default());
+        check(&request, &credentials, signing_time()).unwrap();
+
+        // The session token must match the one known to the server
+        let err = check(&request, &Credentials::for_tests(), signing_time()).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            VerificationErrorKind::SignedHeadersMismatch
+        ));
+    }
+
+    #[test]
+    fn verify_signed_headers_with_content_sha256() {
+        let credentials = Credentials::for_tests();
+        let mut request = request();
+        let settings = SigningSettings {
+            payload_checksum_kind: PayloadChecksumKind::XAmzSha256,
+            ..Default::default()
+        };
+        sign_request(&mut request, &credentials, settings);
+        check(&request, &credentials, signing_time()).unwrap();
+
+        *request.body_mut() = "tampered";
+        let err = check(&request, &credentials, signing_time()).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            VerificationErrorKind::PayloadHashMismatch
+        ));
+    }
+
+    #[test]
+    fn verify_presigned_request() {
+        let credentials = Credentials::for_tests_with_session_token();
+        let mut request = request();
+        let settings = SigningSettings {
+            signature_location: SignatureLocation::QueryParams,
+            expires_in: Some(Duration::from_secs(900)),
+            ..Default::default()
+        };
+        sign_request(&mut request, &credentials, settings);
+
+        let signature = check(&request, &credentials, signing_time()).unwrap();
+        assert_eq!(SignatureLocation::QueryParams, signature.location());
+        assert_eq!(Some(Duration::from_secs(900)), signature.expires_in());
+
+        // Still valid towards the end of the expiry window
+        check(
+            &request,
+            &credentials,
+            signing_time() + Duration::from_secs(900),
+        )
+        .unwrap();
+        let err = check(
+            &request,
+            &credentials,
+            signing_time() + Duration::from_secs(900) + DEFAULT_MAX_CLOCK_SKEW * 2,
+        )
+        .unwrap_err();
+        assert!(err.is_time_skewed());
+    }
+
+    #[test]
+    fn reject_expiry_above_maximum() {
+        let credentials = Credentials::for_tests();
+        let presigned = |expires_in| {
+            let mut request = request();
+            let settings = SigningSettings {
+                signature_location: SignatureLocation::QueryParams,
+                expires_in: Some(Duration::from_secs(expires_in)),
+                ..Default::default()
+            };
+            sign_request(&mut request, &credentials, settings);
+            request
+        };
+
+        check(
+            &presigned(MAX_EXPIRES_IN_SECS),
+            &credentials,
+            signing_time(),
+        )
+        .unwrap();
+        let err = check(
+            &presigned(MAX_EXPIRES_IN_SECS + 1),
+            &credentials,
+            signing_time(),
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind, VerificationErrorKind::Malformed { .. }));
+    }
+
+    #[test]
+    fn reject_overflowing_validity_window() {
+        let signature = ParsedSignature::new(
+            SignatureLocation::QueryParams,
+            "ANOTREAL/20150830/us-east-1/service/aws4_request",
+            "20150830T123600Z",
+            "host",
+            "signature",
+            Some(Duration::from_secs(u64::MAX)),
+        )
+        .unwrap();
+        let err = verify_signing_time(&signature, &VerificationSettings::default(), signing_time())
+            .unwrap_err();
+        assert!(matches!(err.kind, VerificationErrorKind::Malformed { .. }));
+    }
+
+    #[test]
+    fn reject_tampered_requests() {
+        let credentials = Credentials::for_tests();
+        let signed_request = || {
+            let mut request = request();
+            sign_request(&mut request, &credentials, SigningSettings::default());
+            request
+        };
+
+        let mut tampered = signed_request();
+        *tampered.body_mut() = "{\"some\":\"other body\"}";
+        let err = check(&tampered, &credentials, signing_time()).unwrap_err();
+        assert!(matches!(err.kind, VerificationErrorKind::InvalidSignature));
+
+        let mut tampered = signed_request();
+        *tampered.uri_mut() = "https://example.amazonaws.com/some/path?foo=baz&baz=qux"
+            .parse()
+            .unwrap();
+        let err = check(&tampered, &credentials, signing_time()).unwrap_err();
+        assert!(matches!(err.kind, VerificationErrorKind::InvalidSignature));
+
+        let mut tampered = signed_request();
+        tampered
+            .headers_mut()
+            .insert("content-type", "text/plain".parse().unwrap());
+        let err = check(&tampered, &credentials, signing_time()).unwrap_err();
+        assert!(matches!(err.kind, VerificationErrorKind::InvalidSignature));
+
+        let other_secret = Credentials::from_keys("ANOTREAL", "someothersecret", None);
+        let err = check(&signed_request(), &other_secret, signing_time()).unwrap_err();
+        assert!(matches!(err.kind, VerificationErrorKind::InvalidSignature));
+    }
+
+    #[test]
+    fn reject_clock_skew() {
+        let credentials = Credentials::for_tests();
+        let mut request = request();
+        sign_request(&mut request, &credentials, SigningSettings::default());
+
+        check(
+            &request,
+            &credentials,
+            signing_time() + DEFAULT_MAX_CLOCK_SKEW,
+        )
+        .unwrap();
+        check(
+            &request,
+            &credentials,
+            signing_time() - DEFAULT_MAX_CLOCK_SKEW,
+        )
+        .unwrap();
+
+        let late = signing_time() + DEFAULT_MAX_CLOCK_SKEW + Duration::from_secs(1);
+        let err = check(&request, &credentials, late).unwrap_err();
+        assert!(matches!(err.kind, VerificationErrorKind::Expired));
+
+        let early = signing_time() - DEFAULT_MAX_CLOCK_SKEW - Duration::from_secs(1);
+        let err = check(&request, &credentials, early).unwrap_err();
+        assert!(matches!(err.kind, VerificationErrorKind::SignedInFuture));
+    }
+
+    #[test]
+    fn reject_unsigned_and_malformed_requests() {
+        let credentials = Credentials::for_tests();
+        let err = check(&request(), &credentials, signing_time()).unwrap_err();
+        assert!(err.is_missing());
+
+        let mut request = request();
+        sign_request(&mut request, &credentials, SigningSettings::default());
+        let authorization = request.headers()["authorization"].to_str().unwrap();
+        let without_signature = authorization
+            .split(", Signature=")
+            .next()
+            .unwrap()
+            .to_owned();
+        request
+            .headers_mut()
+            .insert("authorization", without_signature.parse().unwrap());
+        let err = check(&request, &credentials, signing_time()).unwrap_err();
+        assert!(matches!(err.kind, VerificationErrorKind::Malformed { .. }));
+
+        request.headers_mut().insert(
+            "authorization",
+            "AWS4-ECDSA-P256-SHA256 Credential=".parse().unwrap(),
+        );
+        let err = check(&request, &credentials, signing_time()).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            VerificationErrorKind::UnsupportedAlgorithm
+        ));
+    }
+}
@@ -6,8 +6,11 @@
 use aws_config::retry::RetryConfig;
 use aws_sdk_s3::operation::list_objects_v2::builders::ListObjectsV2FluentBuilder;
 use aws_sdk_s3::Client;
+use aws_smithy_async::rt::sleep::AsyncSleep;
+use aws_smithy_async::time::TimeSource;
 use aws_smithy_types::timeout::TimeoutConfig;
-use aws_smithy_wasm::wasi::WasiHttpClientBuilder;
+use aws_smithy_wasm::wasi::{WasiHttpClientBuilder, WasiSleep, WasiTimeSource};
+use std::time::Duration;
 
 pub(crate) async fn get_default_wasi_config() -> aws_config::SdkConfig {
     let http_client = WasiHttpClientBuilder::new().build();
@@ -17,6 +20,8 @@ pub(crate) async fn get_default_wasi_config() -> aws_config::SdkConfig {
         .retry_config(RetryConfig::disabled())
         .no_credentials()
         .http_client(http_client)
+        .sleep_impl(WasiSleep::new())
+        .time_source(WasiTimeSource::new())
         .load()
         .await
 }
@@ -52,3 +57,32 @@ pub async fn test_operation_construction() {
         &Some("nara-national-archives-catalog".to_string())
     );
 }
+
+#[tokio::test]
+pub async fn test_wasi_sleep_and_time_source() {
+    let time_source = WasiTimeSource::new();
+    let before = time_source.now();
+    WasiSleep::new().sleep(Duration::from_millis(10)).await;
+    let elapsed = time_source.now().duration_since(before).unwrap();
+    assert!(elapsed >= Duration::from_millis(10), "{elapsed:?}");
+}
+
+// Nothing listens on port 1, so the host fails to connect and the
+// resulting WASI error code is surfaced as an IO connector error
+#[tokio::test]
+pub async fn test_connect_failure_is_io_error() {
+    let shared_config = get_default_wasi_config().await;
+    let config = aws_sdk_s3::config::Builder::from(&shared_config)
+        .endpoint_url("http://127.0.0.1:1")
+        .force_path_style(true)
+        .build();
+    let client = Client::from_conf(config);
+    let err = client
+        .list_objects_v2()
+        .bucket("test-bucket")
+        .send()
+        .await
+        .expect_err("nothing is listening");
+    let dispatch_failure = err.as_dispatch_failure().expect("dispatch failure");
+    assert!(dispatch_failure.is_io(), "{dispatch_failure:?}");
+}
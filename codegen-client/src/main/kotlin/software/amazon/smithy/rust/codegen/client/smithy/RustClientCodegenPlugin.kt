@@ -21,6 +21,7 @@ import software.amazon.smithy.rust.codegen.client.smithy.customize.RequiredCusto
 import software.amazon.smithy.rust.codegen.client.smithy.endpoint.EndpointParamsDecorator
 import software.amazon.smithy.rust.codegen.client.smithy.endpoint.EndpointsDecorator
 import software.amazon.smithy.rust.codegen.client.smithy.generators.client.FluentClientDecorator
+import software.amazon.smithy.rust.codegen.client.smithy.generators.config.HedgingDecorator
 import software.amazon.smithy.rust.codegen.client.smithy.generators.config.StalledStreamProtectionDecorator
 import software.amazon.smithy.rust.codegen.client.testutil.ClientDecoratableBuildPlugin
 import software.amazon.smithy.rust.codegen.core.rustlang.Attribute.Companion.NonExhaustive
@@ -70,6 +71,7 @@ class RustClientCodegenPlugin : ClientDecoratableBuildPlugin() {
                 SensitiveOutputDecorator(),
                 IdempotencyTokenDecorator(),
                 StalledStreamProtectionDecorator(),
+                HedgingDecorator(),
                 *decorator,
             )
 
@@ -0,0 +1,137 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+package software.amazon.smithy.rust.codegen.client.smithy.generators.config
+
+import software.amazon.smithy.model.shapes.OperationShape
+import software.amazon.smithy.model.traits.IdempotentTrait
+import software.amazon.smithy.model.traits.ReadonlyTrait
+import software.amazon.smithy.rust.codegen.client.smithy.ClientCodegenContext
+import software.amazon.smithy.rust.codegen.client.smithy.configReexport
+import software.amazon.smithy.rust.codegen.client.smithy.customize.ClientCodegenDecorator
+import software.amazon.smithy.rust.codegen.client.smithy.generators.OperationCustomization
+import software.amazon.smithy.rust.codegen.client.smithy.generators.OperationSection
+import software.amazon.smithy.rust.codegen.core.rustlang.Writable
+import software.amazon.smithy.rust.codegen.core.rustlang.rustTemplate
+import software.amazon.smithy.rust.codegen.core.rustlang.writable
+import software.amazon.smithy.rust.codegen.core.smithy.RuntimeType
+import software.amazon.smithy.rust.codegen.core.smithy.RuntimeType.Companion.preludeScope
+import software.amazon.smithy.rust.codegen.core.smithy.customize.NamedCustomization
+import software.amazon.smithy.rust.codegen.core.util.hasTrait
+
+class HedgingDecorator : ClientCodegenDecorator {
+    override val name: String = "Hedging"
+    override val order: Byte = 0
+
+    override fun configCustomizations(
+        codegenContext: ClientCodegenContext,
+        baseCustomizations: List<ConfigCustomization>,
+    ): List<ConfigCustomization> {
+        return baseCustomizations + HedgingConfigCustomization(codegenContext)
+    }
+
+    override fun operationCustomizations(
+        codegenContext: ClientCodegenContext,
+        operation: OperationShape,
+        baseCustomizations: List<OperationCustomization>,
+    ): List<OperationCustomization> {
+        return baseCustomizations + HedgingOperationCustomization(codegenContext, operation)
+    }
+}
+
+/**
+ * Add a `hedging_config` field to Service config.
+ */
+class HedgingConfigCustomization(codegenContext: ClientCodegenContext) : NamedCustomization<ServiceConfig>() {
+    private val rc = codegenContext.runtimeConfig
+    private val codegenScope =
+        arrayOf(
+            *preludeScope,
+            "HedgingConfig" to configReexport(RuntimeType.smithyRuntimeApiClient(rc).resolve("client::hedging::HedgingConfig")),
+        )
+
+    override fun section(section: ServiceConfig): Writable {
+        return when (section) {
+            ServiceConfig.ConfigImpl ->
+                writable {
+                    rustTemplate(
+                        """
+                        /// Return a reference to the hedging configuration contained in this config, if any.
+                        pub fn hedging_config(&self) -> #{Option}<&#{HedgingConfig}> {
+                            self.config.load::<#{HedgingConfig}>()
+                        }
+                        """,
+                        *codegenScope,
+                    )
+                }
+            ServiceConfig.BuilderImpl ->
+                writable {
+                    rustTemplate(
+                        """
+                        /// Set the [`HedgingConfig`](#{HedgingConfig}) to send hedged requests
+                        /// for read-only and idempotent operations.
+                        pub fn hedging_config(mut self, hedging_config: #{HedgingConfig}) -> Self {
+                            self.set_hedging_config(#{Some}(hedging_config));
+                            self
+                        }
+                        """,
+                        *codegenScope,
+                    )
+
+                    rustTemplate(
+                        """
+                        /// Set the [`HedgingConfig`](#{HedgingConfig}) to send hedged requests
+                        /// for read-only and idempotent operations.
+                        pub fn set_hedging_config(&mut self, hedging_config: #{Option}<#{HedgingConfig}>) -> &mut Self {
+                            self.config.store_or_unset(hedging_config);
+                            self
+                        }
+                        """,
+                        *codegenScope,
+                    )
+                }
+
+            is ServiceConfig.BuilderFromConfigBag ->
+                writable {
+                    rustTemplate(
+                        "${section.builder}.set_hedging_config(${section.configBag}.load::<#{HedgingConfig}>().cloned());",
+                        *codegenScope,
+                    )
+                }
+
+            else -> emptySection
+        }
+    }
+}
+
+/**
+ * Marks operations with the `@readonly` or `@idempotent` traits so that they can be hedged.
+ */
+class HedgingOperationCustomization(
+    codegenContext: ClientCodegenContext,
+    private val operationShape: OperationShape,
+) : OperationCustomization() {
+    private val rc = codegenContext.runtimeConfig
+
+    override fun section(section: OperationSection): Writable =
+        writable {
+            val idempotency =
+                when {
+                    operationShape.hasTrait<ReadonlyTrait>() -> "ReadOnly"
+                    operationShape.hasTrait<IdempotentTrait>() -> "Idempotent"
+                    else -> return@writable
+                }
+
+            if (section is OperationSection.AdditionalRuntimePluginConfig) {
+                rustTemplate(
+                    """
+                    ${section.newLayerName}.store_put(#{OperationIdempotency}::$idempotency);
+                    """,
+                    "OperationIdempotency" to
+                        RuntimeType.smithyRuntimeApiClient(rc).resolve("client::hedging::OperationIdempotency"),
+                )
+            }
+        }
+}
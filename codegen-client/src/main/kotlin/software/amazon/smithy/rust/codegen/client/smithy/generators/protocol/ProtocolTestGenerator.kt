@@ -30,6 +30,7 @@ import software.amazon.smithy.rust.codegen.core.rustlang.Writable
 import software.amazon.smithy.rust.codegen.core.rustlang.escape
 import software.amazon.smithy.rust.codegen.core.rustlang.rust
 import software.amazon.smithy.rust.codegen.core.rustlang.rustBlock
+import software.amazon.smithy.rust.codegen.core.rustlang.rustInline
 import software.amazon.smithy.rust.codegen.core.rustlang.rustTemplate
 import software.amazon.smithy.rust.codegen.core.rustlang.withBlock
 import software.amazon.smithy.rust.codegen.core.rustlang.writable
@@ -313,14 +314,29 @@ class DefaultProtocolTestGenerator(
         testCase.headers.forEach { (key, value) ->
             writeWithNoFormatting(".header(${key.dq()}, ${value.dq()})")
         }
-        rust(
+        val body =
+            testCase.body.orNull()?.let { body ->
+                if (testCase.bodyMediaType.orNull() == "application/cbor") {
+                    // CBOR protocol test bodies are base64-encoded binary payloads.
+                    writable {
+                        rustInline(
+                            "#T(${body.dq()}).expect(\"protocol test body should be valid base64\")",
+                            RT.base64Decode(rc),
+                        )
+                    }
+                } else {
+                    writable { rustInline(body.dq().replace("#", "##")) }
+                }
+            } ?: writable { rustInline("vec![]") }
+        rustTemplate(
             """
             .status(${testCase.code})
-            .body(#T::from(${testCase.body.orNull()?.dq()?.replace("#", "##") ?: "vec![]"}))
+            .body(#{SdkBody}::from(#{body}))
             .unwrap()
             ).unwrap();
             """,
-            RT.sdkBody(runtimeConfig = rc),
+            "SdkBody" to RT.sdkBody(runtimeConfig = rc),
+            "body" to body,
         )
         rustTemplate(
             """
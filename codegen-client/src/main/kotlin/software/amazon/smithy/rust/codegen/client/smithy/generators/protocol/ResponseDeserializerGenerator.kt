@@ -6,6 +6,7 @@
 package software.amazon.smithy.rust.codegen.client.smithy.generators.protocol
 
 import software.amazon.smithy.model.shapes.OperationShape
+import software.amazon.smithy.model.traits.ReadonlyTrait
 import software.amazon.smithy.rust.codegen.client.smithy.ClientCodegenContext
 import software.amazon.smithy.rust.codegen.client.smithy.generators.OperationCustomization
 import software.amazon.smithy.rust.codegen.client.smithy.generators.OperationSection
@@ -19,6 +20,7 @@ import software.amazon.smithy.rust.codegen.core.smithy.customize.writeCustomizat
 import software.amazon.smithy.rust.codegen.core.smithy.protocols.Protocol
 import software.amazon.smithy.rust.codegen.core.smithy.protocols.ProtocolFunctions
 import software.amazon.smithy.rust.codegen.core.util.hasStreamingMember
+import software.amazon.smithy.rust.codegen.core.util.hasTrait
 import software.amazon.smithy.rust.codegen.core.util.outputShape
 
 class ResponseDeserializerGenerator(
@@ -49,6 +51,7 @@ class ResponseDeserializerGenerator(
             "SdkError" to RuntimeType.sdkError(runtimeConfig),
             "debug_span" to RuntimeType.Tracing.resolve("debug_span"),
             "type_erase_result" to typeEraseResult(),
+            "type_erase_result_with_clone" to typeEraseResultWithClone(),
         )
     }
 
@@ -153,9 +156,16 @@ class ResponseDeserializerGenerator(
             } else {
                 #{parse_response}(status, headers, body)
             };
-            #{type_erase_result}(parse_result)
+            #{type_erase}(parse_result)
             """,
             *codegenScope,
+            // Outputs of read-only operations are cloneable so that they can be cached
+            "type_erase" to
+                if (operationShape.hasTrait<ReadonlyTrait>()) {
+                    typeEraseResultWithClone()
+                } else {
+                    typeEraseResult()
+                },
             "parse_error" to parserGenerator.parseErrorFn(operationShape, customizations),
             "parse_response" to parserGenerator.parseResponseFn(operationShape, customizations),
             "BeforeParseResponse" to
@@ -182,4 +192,22 @@ class ResponseDeserializerGenerator(
                 *codegenScope,
             )
         }
+
+    private fun typeEraseResultWithClone(): RuntimeType =
+        ProtocolFunctions.crossOperationFn("type_erase_result_with_clone") { fnName ->
+            rustTemplate(
+                """
+                pub(crate) fn $fnName<O, E>(result: #{Result}<O, E>) -> #{Result}<#{Output}, #{OrchestratorError}<#{Error}>>
+                where
+                    O: ::std::fmt::Debug + #{Clone} + #{Send} + #{Sync} + 'static,
+                    E: ::std::error::Error + std::fmt::Debug + #{Send} + #{Sync} + 'static,
+                {
+                    result.map(|output| #{Output}::erase_with_clone(output))
+                        .map_err(|error| #{Error}::erase(error))
+                        .map_err(#{Into}::into)
+                }
+                """,
+                *codegenScope,
+            )
+        }
 }
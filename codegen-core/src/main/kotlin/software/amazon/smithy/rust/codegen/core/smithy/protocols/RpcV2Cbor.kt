@@ -0,0 +1,156 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+package software.amazon.smithy.rust.codegen.core.smithy.protocols
+
+import software.amazon.smithy.model.Model
+import software.amazon.smithy.model.pattern.UriPattern
+import software.amazon.smithy.model.shapes.MemberShape
+import software.amazon.smithy.model.shapes.OperationShape
+import software.amazon.smithy.model.shapes.ToShapeId
+import software.amazon.smithy.model.traits.HttpTrait
+import software.amazon.smithy.model.traits.TimestampFormatTrait
+import software.amazon.smithy.rust.codegen.core.rustlang.rustTemplate
+import software.amazon.smithy.rust.codegen.core.smithy.CodegenContext
+import software.amazon.smithy.rust.codegen.core.smithy.RuntimeType
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.parse.CborParserGenerator
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.parse.StructuredDataParserGenerator
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.serialize.CborSerializerGenerator
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.serialize.StructuredDataSerializerGenerator
+import software.amazon.smithy.rust.codegen.core.smithy.traits.SyntheticInputTrait
+import software.amazon.smithy.rust.codegen.core.smithy.traits.SyntheticOutputTrait
+import software.amazon.smithy.rust.codegen.core.util.UNREACHABLE
+import software.amazon.smithy.rust.codegen.core.util.getTrait
+import software.amazon.smithy.rust.codegen.core.util.inputShape
+import software.amazon.smithy.rust.codegen.core.util.outputShape
+
+/**
+ * The `smithy-protocol` header value identifying the Smithy RPC v2 CBOR protocol.
+ */
+const val RPC_V2_CBOR_PROTOCOL_HEADER_VALUE = "rpc-v2-cbor"
+
+/**
+ * All members of RPC v2 CBOR inputs, outputs and errors are bound to the body, and every operation is sent with a
+ * `POST` request to `/service/{ServiceName}/operation/{OperationName}`.
+ *
+ * https://smithy.io/2.0/additional-specs/protocols/smithy-rpc-v2.html
+ */
+class RpcV2CborHttpBindingResolver(
+    private val model: Model,
+    private val serviceName: String,
+) : HttpBindingResolver {
+    private fun bindings(shape: ToShapeId): List<HttpBindingDescriptor> =
+        model.expectShape(shape.toShapeId()).members().map {
+            HttpBindingDescriptor(it, HttpLocation.DOCUMENT, "document")
+        }
+
+    override fun httpTrait(operationShape: OperationShape): HttpTrait =
+        HttpTrait.builder()
+            .code(200)
+            .method("POST")
+            .uri(UriPattern.parse("/service/$serviceName/operation/${operationShape.id.name}"))
+            .build()
+
+    override fun requestBindings(operationShape: OperationShape): List<HttpBindingDescriptor> =
+        bindings(operationShape.inputShape)
+
+    override fun responseBindings(operationShape: OperationShape): List<HttpBindingDescriptor> =
+        bindings(operationShape.outputShape)
+
+    override fun errorResponseBindings(errorShape: ToShapeId): List<HttpBindingDescriptor> = bindings(errorShape)
+
+    /**
+     * Requests for operations without a modeled input have no body, and therefore no `Content-Type` header.
+     */
+    override fun requestContentType(operationShape: OperationShape): String? =
+        if (operationShape.inputShape(model).getTrait<SyntheticInputTrait>()?.originalId == null) {
+            null
+        } else {
+            "application/cbor"
+        }
+
+    /**
+     * Responses for operations without a modeled output have no body, and therefore no `Content-Type` header.
+     */
+    override fun responseContentType(operationShape: OperationShape): String? =
+        if (operationShape.outputShape(model).getTrait<SyntheticOutputTrait>()?.originalId == null) {
+            null
+        } else {
+            "application/cbor"
+        }
+
+    override fun eventStreamMessageContentType(memberShape: MemberShape): String? =
+        ProtocolContentTypes.eventStreamMemberContentType(model, memberShape, "application/cbor")
+}
+
+open class RpcV2Cbor(val codegenContext: CodegenContext) : Protocol {
+    private val runtimeConfig = codegenContext.runtimeConfig
+    private val smithyCbor = RuntimeType.smithyCbor(runtimeConfig)
+    private val errorScope =
+        arrayOf(
+            "Decoder" to smithyCbor.resolve("Decoder"),
+            "DeserializeError" to smithyCbor.resolve("DeserializeError"),
+            "decode_map" to smithyCbor.resolve("decode::decode_map"),
+            "ErrorMetadataBuilder" to RuntimeType.errorMetadataBuilder(runtimeConfig),
+            "Headers" to RuntimeType.headers(runtimeConfig),
+            "set_optional" to smithyCbor.resolve("decode::set_optional"),
+        )
+
+    override val httpBindingResolver: HttpBindingResolver =
+        RpcV2CborHttpBindingResolver(codegenContext.model, codegenContext.serviceShape.id.name)
+
+    override val defaultTimestampFormat: TimestampFormatTrait.Format = TimestampFormatTrait.Format.EPOCH_SECONDS
+
+    override fun additionalRequestHeaders(operationShape: OperationShape): List<Pair<String, String>> =
+        listOf("smithy-protocol" to RPC_V2_CBOR_PROTOCOL_HEADER_VALUE, "Accept" to "application/cbor")
+
+    override fun structuredDataParser(): StructuredDataParserGenerator =
+        CborParserGenerator(codegenContext, httpBindingResolver)
+
+    override fun structuredDataSerializer(): StructuredDataSerializerGenerator =
+        CborSerializerGenerator(codegenContext, httpBindingResolver)
+
+    /**
+     * Errors are identified by the `__type` member of the response body, which holds the shape ID of the error. Like
+     * the JSON protocols, any namespace prefix and `:`-separated suffix is stripped from it to obtain the error code.
+     */
+    override fun parseHttpErrorMetadata(operationShape: OperationShape): RuntimeType =
+        ProtocolFunctions.crossOperationFn("parse_http_error_metadata") { fnName ->
+            rustTemplate(
+                """
+                pub fn $fnName(_response_status: u16, _response_headers: &#{Headers}, response_body: &[u8]) -> Result<#{ErrorMetadataBuilder}, #{DeserializeError}> {
+                    let builder = #{ErrorMetadataBuilder}::default();
+                    if response_body.is_empty() {
+                        return Ok(builder);
+                    }
+                    let decoder = &mut #{Decoder}::new(response_body);
+                    let builder = #{decode_map}(decoder, builder, |builder, decoder| {
+                        match decoder.str()?.as_ref() {
+                            "__type" => {
+                                let error_type = decoder.str()?;
+                                let error_type = error_type.split(':').next().unwrap_or_default();
+                                let code = error_type.rsplit('##').next().unwrap_or_default();
+                                Ok(builder.code(code))
+                            }
+                            "message" | "Message" | "errorMessage" => {
+                                #{set_optional}(builder, decoder, |builder, decoder| Ok(builder.message(decoder.str()?)))
+                            }
+                            _ => {
+                                decoder.skip()?;
+                                Ok(builder)
+                            }
+                        }
+                    })?;
+                    decoder.expect_end()?;
+                    Ok(builder)
+                }
+                """,
+                *errorScope,
+            )
+        }
+
+    override fun parseEventStreamErrorMetadata(operationShape: OperationShape): RuntimeType =
+        UNREACHABLE("event streams are not supported by the RPC v2 CBOR protocol")
+}
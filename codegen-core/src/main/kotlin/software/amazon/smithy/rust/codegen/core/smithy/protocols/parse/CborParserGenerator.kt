@@ -0,0 +1,469 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+package software.amazon.smithy.rust.codegen.core.smithy.protocols.parse
+
+import software.amazon.smithy.codegen.core.Symbol
+import software.amazon.smithy.model.shapes.BlobShape
+import software.amazon.smithy.model.shapes.BooleanShape
+import software.amazon.smithy.model.shapes.ByteShape
+import software.amazon.smithy.model.shapes.CollectionShape
+import software.amazon.smithy.model.shapes.DocumentShape
+import software.amazon.smithy.model.shapes.DoubleShape
+import software.amazon.smithy.model.shapes.FloatShape
+import software.amazon.smithy.model.shapes.IntegerShape
+import software.amazon.smithy.model.shapes.LongShape
+import software.amazon.smithy.model.shapes.MapShape
+import software.amazon.smithy.model.shapes.MemberShape
+import software.amazon.smithy.model.shapes.OperationShape
+import software.amazon.smithy.model.shapes.Shape
+import software.amazon.smithy.model.shapes.ShortShape
+import software.amazon.smithy.model.shapes.StringShape
+import software.amazon.smithy.model.shapes.StructureShape
+import software.amazon.smithy.model.shapes.TimestampShape
+import software.amazon.smithy.model.shapes.UnionShape
+import software.amazon.smithy.model.traits.EnumTrait
+import software.amazon.smithy.model.traits.SparseTrait
+import software.amazon.smithy.rust.codegen.core.rustlang.RustWriter
+import software.amazon.smithy.rust.codegen.core.rustlang.Writable
+import software.amazon.smithy.rust.codegen.core.rustlang.rust
+import software.amazon.smithy.rust.codegen.core.rustlang.rustBlock
+import software.amazon.smithy.rust.codegen.core.rustlang.rustBlockTemplate
+import software.amazon.smithy.rust.codegen.core.rustlang.rustTemplate
+import software.amazon.smithy.rust.codegen.core.rustlang.withBlock
+import software.amazon.smithy.rust.codegen.core.rustlang.withBlockTemplate
+import software.amazon.smithy.rust.codegen.core.smithy.CodegenContext
+import software.amazon.smithy.rust.codegen.core.smithy.CodegenTarget
+import software.amazon.smithy.rust.codegen.core.smithy.RuntimeType
+import software.amazon.smithy.rust.codegen.core.smithy.RuntimeType.Companion.preludeScope
+import software.amazon.smithy.rust.codegen.core.smithy.customize.NamedCustomization
+import software.amazon.smithy.rust.codegen.core.smithy.customize.Section
+import software.amazon.smithy.rust.codegen.core.smithy.generators.UnionGenerator
+import software.amazon.smithy.rust.codegen.core.smithy.generators.renderUnknownVariant
+import software.amazon.smithy.rust.codegen.core.smithy.generators.setterName
+import software.amazon.smithy.rust.codegen.core.smithy.isOptional
+import software.amazon.smithy.rust.codegen.core.smithy.isRustBoxed
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.HttpBindingResolver
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.HttpLocation
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.ProtocolFunctions
+import software.amazon.smithy.rust.codegen.core.util.PANIC
+import software.amazon.smithy.rust.codegen.core.util.UNREACHABLE
+import software.amazon.smithy.rust.codegen.core.util.dq
+import software.amazon.smithy.rust.codegen.core.util.hasTrait
+import software.amazon.smithy.rust.codegen.core.util.inputShape
+import software.amazon.smithy.rust.codegen.core.util.isTargetUnit
+import software.amazon.smithy.rust.codegen.core.util.outputShape
+
+/**
+ * Class describing a CBOR parser section that can be used in a customization.
+ */
+sealed class CborParserSection(name: String) : Section(name) {
+    data class BeforeBoxingDeserializedMember(val shape: MemberShape) :
+        CborParserSection("BeforeBoxingDeserializedMember")
+}
+
+/**
+ * Customization for the CBOR parser.
+ */
+typealias CborParserCustomization = NamedCustomization<CborParserSection>
+
+/**
+ * Generates parsers for the Smithy RPC v2 CBOR protocol.
+ *
+ * Unlike [JsonParserGenerator], which parses from a stream of tokens, the generated functions decode values directly
+ * from an `aws_smithy_cbor::Decoder`. Every generated function decodes a single, non-null data item: `null` values
+ * of structure members are skipped with `set_optional` before the member's parser is called, and `null` items of
+ * collections and maps are handled by the collection's parser.
+ */
+class CborParserGenerator(
+    private val codegenContext: CodegenContext,
+    private val httpBindingResolver: HttpBindingResolver,
+    /** See [JsonParserGenerator]. */
+    private val returnSymbolToParse: (Shape) -> ReturnSymbolToParse = { shape ->
+        ReturnSymbolToParse(codegenContext.symbolProvider.toSymbol(shape), false)
+    },
+    private val customizations: List<CborParserCustomization> = listOf(),
+) : StructuredDataParserGenerator {
+    private val model = codegenContext.model
+    private val symbolProvider = codegenContext.symbolProvider
+    private val runtimeConfig = codegenContext.runtimeConfig
+    private val codegenTarget = codegenContext.target
+    private val smithyCbor = RuntimeType.smithyCbor(runtimeConfig)
+    private val protocolFunctions = ProtocolFunctions(codegenContext)
+    private val builderInstantiator = codegenContext.builderInstantiator()
+    private val codegenScope =
+        arrayOf(
+            "Decoder" to smithyCbor.resolve("Decoder"),
+            "Error" to smithyCbor.resolve("DeserializeError"),
+            "HashMap" to RuntimeType.HashMap,
+            "Type" to smithyCbor.resolve("data::Type"),
+            "decode_list" to smithyCbor.resolve("decode::decode_list"),
+            "decode_map" to smithyCbor.resolve("decode::decode_map"),
+            "set_optional" to smithyCbor.resolve("decode::set_optional"),
+            *preludeScope,
+        )
+
+    /**
+     * Reusable structure parser implementation that can be used to generate parsing code for
+     * operation, error and structure shapes.
+     * We still generate the parser symbol even if there are no included members because the server
+     * generation requires parsers for all input structures.
+     */
+    private fun structureParser(
+        shape: Shape,
+        builderSymbol: Symbol,
+        includedMembers: List<MemberShape>,
+        fnNameSuffix: String? = null,
+    ): RuntimeType {
+        return protocolFunctions.deserializeFn(shape, fnNameSuffix) { fnName ->
+            rustBlockTemplate(
+                "pub(crate) fn $fnName(value: &[u8], builder: #{Builder}) -> Result<#{Builder}, #{Error}>",
+                "Builder" to builderSymbol,
+                *codegenScope,
+            ) {
+                rustTemplate(
+                    """
+                    // An empty body is equivalent to an empty map.
+                    if value.is_empty() {
+                        return Ok(builder);
+                    }
+                    let decoder = &mut #{Decoder}::new(value);
+                    """,
+                    *codegenScope,
+                )
+                withBlock("let builder =", "?;") {
+                    decodeStructInner(includedMembers)
+                }
+                rust(
+                    """
+                    decoder.expect_end()?;
+                    Ok(builder)
+                    """,
+                )
+            }
+        }
+    }
+
+    override fun payloadParser(member: MemberShape): RuntimeType =
+        UNREACHABLE("the RPC v2 CBOR protocol does not support `@httpPayload`")
+
+    override fun operationParser(operationShape: OperationShape): RuntimeType? {
+        // Don't generate an operation CBOR deserializer if there is no CBOR body.
+        val httpDocumentMembers = httpBindingResolver.responseMembers(operationShape, HttpLocation.DOCUMENT)
+        if (httpDocumentMembers.isEmpty()) {
+            return null
+        }
+        val outputShape = operationShape.outputShape(model)
+        return structureParser(operationShape, symbolProvider.symbolForBuilder(outputShape), httpDocumentMembers)
+    }
+
+    override fun errorParser(errorShape: StructureShape): RuntimeType? {
+        if (errorShape.members().isEmpty()) {
+            return null
+        }
+        return structureParser(
+            errorShape,
+            symbolProvider.symbolForBuilder(errorShape),
+            errorShape.members().toList(),
+            fnNameSuffix = "cbor_err",
+        )
+    }
+
+    override fun serverInputParser(operationShape: OperationShape): RuntimeType? {
+        val includedMembers = httpBindingResolver.requestMembers(operationShape, HttpLocation.DOCUMENT)
+        if (includedMembers.isEmpty()) {
+            return null
+        }
+        val inputShape = operationShape.inputShape(model)
+        return structureParser(operationShape, symbolProvider.symbolForBuilder(inputShape), includedMembers)
+    }
+
+    /**
+     * Renders an expression decoding a map into `builder`, setting the [members] it finds and skipping all other
+     * entries.
+     */
+    private fun RustWriter.decodeStructInner(members: Collection<MemberShape>) {
+        rustBlockTemplate("#{decode_map}(decoder, builder, |builder, decoder|", *codegenScope) {
+            if (members.isEmpty()) {
+                rust(
+                    """
+                    decoder.skip()?;
+                    decoder.skip()?;
+                    Ok(builder)
+                    """,
+                )
+                return@rustBlockTemplate
+            }
+            rustBlock("Ok(match decoder.str()?.as_ref()") {
+                for (member in members) {
+                    val setter = member.setterName()
+                    val wrapInSome =
+                        when (codegenTarget) {
+                            CodegenTarget.CLIENT -> true
+                            CodegenTarget.SERVER -> symbolProvider.toSymbol(member).isOptional()
+                        }
+                    withBlockTemplate(
+                        "${member.memberName.dq()} => #{set_optional}(builder, decoder, |builder, decoder| Ok(builder.$setter(",
+                        ")))?,",
+                        *codegenScope,
+                    ) {
+                        if (wrapInSome) {
+                            withBlock("Some(", ")") { decodeMember(member) }
+                        } else {
+                            decodeMember(member)
+                        }
+                    }
+                }
+                rust(
+                    """
+                    _ => {
+                        decoder.skip()?;
+                        builder
+                    }
+                    """,
+                )
+            }
+            rust(")")
+        }
+        rust(")")
+    }
+
+    /**
+     * Renders an expression decoding the (non-null) value of [memberShape].
+     */
+    private fun RustWriter.decodeMember(memberShape: MemberShape) {
+        val symbol = symbolProvider.toSymbol(memberShape)
+        if (symbol.isRustBoxed()) {
+            rust("Box::new(")
+        }
+        when (val target = model.expectShape(memberShape.target)) {
+            is StringShape -> decodeString(target)
+            is BooleanShape -> rust("decoder.boolean()?")
+            is ByteShape -> rust("decoder.byte()?")
+            is ShortShape -> rust("decoder.short()?")
+            is IntegerShape -> rust("decoder.integer()?")
+            is LongShape -> rust("decoder.long()?")
+            is FloatShape -> rust("decoder.float()?")
+            is DoubleShape -> rust("decoder.double()?")
+            is BlobShape -> rust("decoder.blob()?")
+            is TimestampShape -> rust("decoder.timestamp()?")
+            is DocumentShape -> rust("decoder.document()?")
+            is CollectionShape -> rust("#T(decoder)?", collectionParser(target))
+            is MapShape -> rust("#T(decoder)?", mapParser(target))
+            is StructureShape -> rust("#T(decoder)?", structParser(target))
+            is UnionShape -> rust("#T(decoder)?", unionParser(target))
+            else -> PANIC("unexpected shape: $target")
+        }
+        if (symbol.isRustBoxed()) {
+            for (customization in customizations) {
+                customization.section(CborParserSection.BeforeBoxingDeserializedMember(memberShape))(this)
+            }
+            rust(")")
+        }
+    }
+
+    private fun RustWriter.decodeString(target: StringShape) {
+        if (target.hasTrait<EnumTrait>() && !returnSymbolToParse(target).isUnconstrained) {
+            rust("#T::from(decoder.str()?.as_ref())", symbolProvider.toSymbol(target))
+        } else {
+            rust("decoder.string()?")
+        }
+    }
+
+    /**
+     * Renders the statements handling a `null` item of a collection or map named [name]. Sparse collections hold
+     * `None`, while dense collections skip it in clients and reject it in servers.
+     */
+    private fun RustWriter.decodeNullableItem(
+        isSparse: Boolean,
+        name: String,
+        insertNull: String,
+        insertValue: Writable,
+    ) {
+        rustBlockTemplate("if matches!(decoder.datatype()?, #{Type}::Null | #{Type}::Undefined)", *codegenScope) {
+            rust("decoder.null()?;")
+            if (isSparse) {
+                rust(insertNull)
+            } else {
+                when (codegenTarget) {
+                    CodegenTarget.CLIENT -> {}
+                    CodegenTarget.SERVER ->
+                        rustTemplate(
+                            """return Err(#{Error}::custom("dense $name cannot contain null values", Some(decoder.position())));""",
+                            *codegenScope,
+                        )
+                }
+            }
+        }
+        rustBlock("else") {
+            insertValue()
+        }
+    }
+
+    private fun collectionParser(shape: CollectionShape): RuntimeType {
+        val isSparse = shape.hasTrait<SparseTrait>()
+        val (returnSymbol, returnUnconstrainedType) = returnSymbolToParse(shape)
+        return protocolFunctions.deserializeFn(shape) { fnName ->
+            rustBlockTemplate(
+                "pub(crate) fn $fnName(decoder: &mut #{Decoder}) -> Result<#{ReturnType}, #{Error}>",
+                "ReturnType" to returnSymbol,
+                *codegenScope,
+            ) {
+                rustBlockTemplate("let items = #{decode_list}(decoder, Vec::new(), |mut items, decoder|", *codegenScope) {
+                    decodeNullableItem(isSparse, "list", "items.push(None);") {
+                        withBlock("items.push(", ");") {
+                            if (isSparse) {
+                                withBlock("Some(", ")") { decodeMember(shape.member) }
+                            } else {
+                                decodeMember(shape.member)
+                            }
+                        }
+                    }
+                    rust("Ok(items)")
+                }
+                rust(")?;")
+                if (returnUnconstrainedType) {
+                    rust("Ok(#T(items))", returnSymbol)
+                } else {
+                    rust("Ok(items)")
+                }
+            }
+        }
+    }
+
+    private fun mapParser(shape: MapShape): RuntimeType {
+        val keyTarget = model.expectShape(shape.key.target) as StringShape
+        val isSparse = shape.hasTrait<SparseTrait>()
+        val returnSymbolToParse = returnSymbolToParse(shape)
+        return protocolFunctions.deserializeFn(shape) { fnName ->
+            rustBlockTemplate(
+                "pub(crate) fn $fnName(decoder: &mut #{Decoder}) -> Result<#{ReturnType}, #{Error}>",
+                "ReturnType" to returnSymbolToParse.symbol,
+                *codegenScope,
+            ) {
+                rustBlockTemplate("let map = #{decode_map}(decoder, #{HashMap}::new(), |mut map, decoder|", *codegenScope) {
+                    withBlock("let key =", ";") {
+                        decodeString(keyTarget)
+                    }
+                    decodeNullableItem(isSparse, "map", "map.insert(key, None);") {
+                        withBlock("map.insert(key, ", ");") {
+                            if (isSparse) {
+                                withBlock("Some(", ")") { decodeMember(shape.value) }
+                            } else {
+                                decodeMember(shape.value)
+                            }
+                        }
+                    }
+                    rust("Ok(map)")
+                }
+                rust(")?;")
+                if (returnSymbolToParse.isUnconstrained) {
+                    rust("Ok(#T(map))", returnSymbolToParse.symbol)
+                } else {
+                    rust("Ok(map)")
+                }
+            }
+        }
+    }
+
+    private fun structParser(shape: StructureShape): RuntimeType {
+        val returnSymbolToParse = returnSymbolToParse(shape)
+        return protocolFunctions.deserializeFn(shape) { fnName ->
+            rustBlockTemplate(
+                "pub(crate) fn $fnName(decoder: &mut #{Decoder}) -> Result<#{ReturnType}, #{Error}>",
+                "ReturnType" to returnSymbolToParse.symbol,
+                *codegenScope,
+            ) {
+                rustTemplate(
+                    "let builder = #{Builder}::default();",
+                    "Builder" to symbolProvider.symbolForBuilder(shape),
+                )
+                withBlock("let builder =", "?;") {
+                    decodeStructInner(shape.members())
+                }
+                val builder =
+                    builderInstantiator.finalizeBuilder("builder", shape) {
+                        rustTemplate("""|err| #{Error}::custom(err.to_string(), None)""", *codegenScope)
+                    }
+                rust("Ok(#T)", builder)
+            }
+        }
+    }
+
+    private fun unionParser(shape: UnionShape): RuntimeType {
+        val returnSymbolToParse = returnSymbolToParse(shape)
+        return protocolFunctions.deserializeFn(shape) { fnName ->
+            rustBlockTemplate(
+                "pub(crate) fn $fnName(decoder: &mut #{Decoder}) -> Result<#{Union}, #{Error}>",
+                "Union" to returnSymbolToParse.symbol,
+                *codegenScope,
+            ) {
+                rustTemplate(
+                    """
+                    let start = decoder.position();
+                    let variant = #{decode_map}(decoder, None, |variant, decoder| {
+                        let key = decoder.str()?;
+                        // `__type` may be sent by servers, and `null` values do not set a variant.
+                        if key == "__type" || matches!(decoder.datatype()?, #{Type}::Null | #{Type}::Undefined) {
+                            decoder.skip()?;
+                            return Ok(variant);
+                        }
+                        if variant.is_some() {
+                            return Err(#{Error}::mixed_union_variants(decoder.position()));
+                        }
+                    """,
+                    *codegenScope,
+                )
+                rustBlock("Ok(Some(match key.as_ref()") {
+                    for (member in shape.members()) {
+                        val variantName = symbolProvider.toMemberName(member)
+                        if (member.isTargetUnit()) {
+                            rustTemplate(
+                                """
+                                ${member.memberName.dq()} => {
+                                    decoder.skip()?;
+                                    #{Union}::$variantName
+                                }
+                                """,
+                                "Union" to returnSymbolToParse.symbol,
+                            )
+                        } else {
+                            withBlock("${member.memberName.dq()} => #T::$variantName(", "),", returnSymbolToParse.symbol) {
+                                decodeMember(member)
+                            }
+                        }
+                    }
+                    when (codegenTarget.renderUnknownVariant()) {
+                        // In client mode, resolve an unknown union variant to the unknown variant.
+                        true ->
+                            rustTemplate(
+                                """
+                                _ => {
+                                    decoder.skip()?;
+                                    #{Union}::${UnionGenerator.UNKNOWN_VARIANT_NAME}
+                                }
+                                """,
+                                "Union" to returnSymbolToParse.symbol,
+                            )
+                        // In server mode, use strict parsing.
+                        // Consultation: https://github.com/awslabs/smithy/issues/1222
+                        false ->
+                            rustTemplate(
+                                "unknown => return Err(#{Error}::unknown_union_variant(unknown, decoder.position())),",
+                                *codegenScope,
+                            )
+                    }
+                }
+                rust(
+                    """
+                    ))
+                    })?;
+                    """,
+                )
+                rustTemplate("variant.ok_or_else(|| #{Error}::empty_union(start))", *codegenScope)
+            }
+        }
+    }
+}
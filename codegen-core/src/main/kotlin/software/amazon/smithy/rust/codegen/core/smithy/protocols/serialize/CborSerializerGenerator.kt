@@ -0,0 +1,453 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+package software.amazon.smithy.rust.codegen.core.smithy.protocols.serialize
+
+import software.amazon.smithy.model.shapes.BlobShape
+import software.amazon.smithy.model.shapes.BooleanShape
+import software.amazon.smithy.model.shapes.ByteShape
+import software.amazon.smithy.model.shapes.CollectionShape
+import software.amazon.smithy.model.shapes.DocumentShape
+import software.amazon.smithy.model.shapes.DoubleShape
+import software.amazon.smithy.model.shapes.FloatShape
+import software.amazon.smithy.model.shapes.IntegerShape
+import software.amazon.smithy.model.shapes.LongShape
+import software.amazon.smithy.model.shapes.MapShape
+import software.amazon.smithy.model.shapes.MemberShape
+import software.amazon.smithy.model.shapes.OperationShape
+import software.amazon.smithy.model.shapes.Shape
+import software.amazon.smithy.model.shapes.ShapeId
+import software.amazon.smithy.model.shapes.ShortShape
+import software.amazon.smithy.model.shapes.StringShape
+import software.amazon.smithy.model.shapes.StructureShape
+import software.amazon.smithy.model.shapes.TimestampShape
+import software.amazon.smithy.model.shapes.UnionShape
+import software.amazon.smithy.rust.codegen.core.rustlang.Attribute
+import software.amazon.smithy.rust.codegen.core.rustlang.RustWriter
+import software.amazon.smithy.rust.codegen.core.rustlang.rust
+import software.amazon.smithy.rust.codegen.core.rustlang.rustBlock
+import software.amazon.smithy.rust.codegen.core.rustlang.rustBlockTemplate
+import software.amazon.smithy.rust.codegen.core.rustlang.rustTemplate
+import software.amazon.smithy.rust.codegen.core.rustlang.withBlock
+import software.amazon.smithy.rust.codegen.core.rustlang.writable
+import software.amazon.smithy.rust.codegen.core.smithy.CodegenContext
+import software.amazon.smithy.rust.codegen.core.smithy.RuntimeType
+import software.amazon.smithy.rust.codegen.core.smithy.RuntimeType.Companion.preludeScope
+import software.amazon.smithy.rust.codegen.core.smithy.customize.NamedCustomization
+import software.amazon.smithy.rust.codegen.core.smithy.customize.Section
+import software.amazon.smithy.rust.codegen.core.smithy.generators.UnionGenerator
+import software.amazon.smithy.rust.codegen.core.smithy.generators.renderUnknownVariant
+import software.amazon.smithy.rust.codegen.core.smithy.generators.serializationError
+import software.amazon.smithy.rust.codegen.core.smithy.isOptional
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.HttpBindingResolver
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.HttpLocation
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.ProtocolFunctions
+import software.amazon.smithy.rust.codegen.core.smithy.traits.SyntheticInputTrait
+import software.amazon.smithy.rust.codegen.core.smithy.traits.SyntheticOutputTrait
+import software.amazon.smithy.rust.codegen.core.util.PANIC
+import software.amazon.smithy.rust.codegen.core.util.UNREACHABLE
+import software.amazon.smithy.rust.codegen.core.util.dq
+import software.amazon.smithy.rust.codegen.core.util.expectTrait
+import software.amazon.smithy.rust.codegen.core.util.inputShape
+import software.amazon.smithy.rust.codegen.core.util.isTargetUnit
+import software.amazon.smithy.rust.codegen.core.util.outputShape
+
+/**
+ * Class describing a CBOR serializer section that can be used in a customization.
+ */
+sealed class CborSerializerSection(name: String) : Section(name) {
+    /** Mutate the server error map prior to it being ended. Eg: this can be used to inject `__type` to record the error type. */
+    data class ServerError(val structureShape: StructureShape, val encoderBindingName: String) :
+        CborSerializerSection("ServerError")
+
+    /** Manipulate the serializer context for a map or collection prior to it being serialized. **/
+    data class BeforeIteratingOverMapOrCollection(val shape: Shape, val context: CborSerializerGenerator.Context<Shape>) :
+        CborSerializerSection("BeforeIteratingOverMapOrCollection")
+
+    /** Manipulate the serializer context for a non-null member prior to it being serialized. **/
+    data class BeforeSerializingNonNullMember(val shape: Shape, val context: CborSerializerGenerator.MemberContext) :
+        CborSerializerSection("BeforeSerializingNonNullMember")
+}
+
+/**
+ * Customization for the CBOR serializer.
+ */
+typealias CborSerializerCustomization = NamedCustomization<CborSerializerSection>
+
+/**
+ * Generates serializers for the Smithy RPC v2 CBOR protocol.
+ *
+ * Structures are encoded as maps of indefinite length keyed by member name, so that unset members can simply be
+ * omitted. Collections and maps are encoded with definite lengths, and unions as single-entry maps.
+ */
+class CborSerializerGenerator(
+    codegenContext: CodegenContext,
+    private val httpBindingResolver: HttpBindingResolver,
+    private val customizations: List<CborSerializerCustomization> = listOf(),
+) : StructuredDataSerializerGenerator {
+    data class Context<out T : Shape>(
+        /** Expression yielding the `Encoder` the value is written to */
+        val encoderBindingName: String,
+        /** Expression representing the value to write to the `Encoder` */
+        var valueExpression: ValueExpression,
+        val shape: T,
+    )
+
+    data class MemberContext(
+        /** Expression yielding the `Encoder` positioned to write the value, e.g. after writing its map key */
+        val writerExpression: String,
+        /** Expression representing the value to write to the `Encoder` */
+        var valueExpression: ValueExpression,
+        val shape: MemberShape,
+        /** Whether to serialize null values if the type is optional */
+        val writeNulls: Boolean = false,
+    ) {
+        companion object {
+            fun collectionMember(
+                context: Context<CollectionShape>,
+                itemName: String,
+            ): MemberContext =
+                MemberContext(
+                    context.encoderBindingName,
+                    ValueExpression.Reference(itemName),
+                    context.shape.member,
+                    writeNulls = true,
+                )
+
+            fun mapMember(
+                context: Context<MapShape>,
+                key: String,
+                value: String,
+            ): MemberContext =
+                MemberContext(
+                    "${context.encoderBindingName}.str($key)",
+                    ValueExpression.Reference(value),
+                    context.shape.value,
+                    writeNulls = true,
+                )
+
+            fun structMember(
+                context: StructContext,
+                member: MemberShape,
+                memberName: String,
+            ): MemberContext =
+                MemberContext(
+                    "${context.encoderBindingName}.str(${member.memberName.dq()})",
+                    ValueExpression.Value("${context.localName}.$memberName"),
+                    member,
+                )
+
+            fun unionMember(
+                context: Context<UnionShape>,
+                variantReference: String,
+                member: MemberShape,
+            ): MemberContext =
+                MemberContext(
+                    "${context.encoderBindingName}.str(${member.memberName.dq()})",
+                    ValueExpression.Reference(variantReference),
+                    member,
+                )
+        }
+    }
+
+    data class StructContext(
+        /** Expression yielding a `&mut Encoder` */
+        val encoderBindingName: String,
+        /** Name of the variable that holds the struct */
+        val localName: String,
+        val shape: StructureShape,
+    )
+
+    private val model = codegenContext.model
+    private val symbolProvider = codegenContext.symbolProvider
+    private val codegenTarget = codegenContext.target
+    private val runtimeConfig = codegenContext.runtimeConfig
+    private val protocolFunctions = ProtocolFunctions(codegenContext)
+    private val codegenScope =
+        arrayOf(
+            *preludeScope,
+            "Error" to runtimeConfig.serializationError(),
+            "Encoder" to RuntimeType.smithyCbor(runtimeConfig).resolve("Encoder"),
+            "SdkBody" to RuntimeType.sdkBody(runtimeConfig),
+            "ByteSlab" to RuntimeType.ByteSlab,
+        )
+    private val serializerUtil = SerializerUtil(model, symbolProvider)
+
+    /**
+     * Reusable structure serializer implementation that can be used to generate serializing code for
+     * operation outputs or errors.
+     * This function is only used by the server, the client uses directly [serializeStructure].
+     */
+    private fun serverSerializer(
+        structureShape: StructureShape,
+        includedMembers: List<MemberShape>,
+        error: Boolean,
+    ): RuntimeType {
+        val suffix =
+            when (error) {
+                true -> "error"
+                else -> "output"
+            }
+        return protocolFunctions.serializeFn(structureShape, fnNameSuffix = suffix) { fnName ->
+            rustBlockTemplate(
+                "pub fn $fnName(value: &#{target}) -> Result<#{Vec}<u8>, #{Error}>",
+                *codegenScope,
+                "target" to symbolProvider.toSymbol(structureShape),
+            ) {
+                rustTemplate("let mut encoder = #{Encoder}::new(#{Vec}::new());", *codegenScope)
+                // Errors add members to the structure's map, so the map is written here rather than in
+                // [serializeStructure].
+                rust("encoder.begin_map();")
+                serializeStructure(StructContext("&mut encoder", "value", structureShape), includedMembers, writeMap = false)
+                if (error) {
+                    customizations.forEach {
+                        it.section(CborSerializerSection.ServerError(structureShape, "encoder"))(this)
+                    }
+                }
+                rust("encoder.end();")
+                rust("Ok(encoder.into_writer())")
+            }
+        }
+    }
+
+    override fun payloadSerializer(member: MemberShape): RuntimeType =
+        UNREACHABLE("the RPC v2 CBOR protocol does not support `@httpPayload`")
+
+    override fun unsetStructure(structure: StructureShape): RuntimeType =
+        UNREACHABLE("the RPC v2 CBOR protocol does not support `@httpPayload`")
+
+    override fun unsetUnion(union: UnionShape): RuntimeType =
+        UNREACHABLE("the RPC v2 CBOR protocol does not support `@httpPayload`")
+
+    override fun operationInputSerializer(operationShape: OperationShape): RuntimeType? {
+        // Don't send a body if there was no operation input shape in the original (untransformed) model. Operations
+        // with an input shape always send a map, even if it has no members.
+        val syntheticInputTrait = operationShape.inputShape(model).expectTrait<SyntheticInputTrait>()
+        if (syntheticInputTrait.originalId == null) {
+            return null
+        }
+
+        val httpDocumentMembers = httpBindingResolver.requestMembers(operationShape, HttpLocation.DOCUMENT)
+        val inputShape = operationShape.inputShape(model)
+        return protocolFunctions.serializeFn(operationShape, fnNameSuffix = "input") { fnName ->
+            rustBlockTemplate(
+                "pub fn $fnName(input: &#{target}) -> Result<#{SdkBody}, #{Error}>",
+                *codegenScope, "target" to symbolProvider.toSymbol(inputShape),
+            ) {
+                rustTemplate("let mut encoder = #{Encoder}::new(#{Vec}::new());", *codegenScope)
+                serializeStructure(StructContext("&mut encoder", "input", inputShape), httpDocumentMembers)
+                rustTemplate("Ok(#{SdkBody}::from(encoder.into_writer()))", *codegenScope)
+            }
+        }
+    }
+
+    override fun documentSerializer(): RuntimeType {
+        return ProtocolFunctions.crossOperationFn("serialize_document") { fnName ->
+            rustTemplate(
+                """
+                pub fn $fnName(input: &#{Document}) -> #{ByteSlab} {
+                    let mut encoder = #{Encoder}::new(#{Vec}::new());
+                    encoder.document(input);
+                    encoder.into_writer()
+                }
+                """,
+                "Document" to RuntimeType.document(runtimeConfig), *codegenScope,
+            )
+        }
+    }
+
+    override fun operationOutputSerializer(operationShape: OperationShape): RuntimeType? {
+        // Don't generate an operation CBOR serializer if there was no operation output shape in the
+        // original (untransformed) model.
+        val syntheticOutputTrait = operationShape.outputShape(model).expectTrait<SyntheticOutputTrait>()
+        if (syntheticOutputTrait.originalId == null) {
+            return null
+        }
+
+        val httpDocumentMembers = httpBindingResolver.responseMembers(operationShape, HttpLocation.DOCUMENT)
+        val outputShape = operationShape.outputShape(model)
+        return serverSerializer(outputShape, httpDocumentMembers, error = false)
+    }
+
+    override fun serverErrorSerializer(shape: ShapeId): RuntimeType {
+        val errorShape = model.expectShape(shape, StructureShape::class.java)
+        val includedMembers =
+            httpBindingResolver.errorResponseBindings(shape).filter { it.location == HttpLocation.DOCUMENT }
+                .map { it.member }
+        return serverSerializer(errorShape, includedMembers, error = true)
+    }
+
+    private fun RustWriter.serializeStructure(
+        context: StructContext,
+        includedMembers: List<MemberShape>? = null,
+        writeMap: Boolean = true,
+    ) {
+        val structureSerializer =
+            protocolFunctions.serializeFn(context.shape, fnNameSuffix = if (writeMap) null else "members") { fnName ->
+                val inner = context.copy(encoderBindingName = "encoder", localName = "input")
+                val members = includedMembers ?: inner.shape.members()
+                val allowUnusedVariables =
+                    writable {
+                        if (members.isEmpty()) {
+                            Attribute.AllowUnusedVariables.render(this)
+                        }
+                    }
+                rustBlockTemplate(
+                    """
+                    pub fn $fnName(
+                        encoder: &mut #{Encoder},
+                        #{AllowUnusedVariables:W} input: &#{StructureSymbol},
+                    ) -> Result<(), #{Error}>
+                    """,
+                    "StructureSymbol" to symbolProvider.toSymbol(context.shape),
+                    "AllowUnusedVariables" to allowUnusedVariables,
+                    *codegenScope,
+                ) {
+                    if (writeMap) {
+                        rust("encoder.begin_map();")
+                    }
+                    for (member in members) {
+                        serializeMember(MemberContext.structMember(inner, member, symbolProvider.toMemberName(member)))
+                    }
+                    if (writeMap) {
+                        rust("encoder.end();")
+                    }
+                    rust("Ok(())")
+                }
+            }
+        rust("#T(${context.encoderBindingName}, ${context.localName})?;", structureSerializer)
+    }
+
+    private fun RustWriter.serializeMember(context: MemberContext) {
+        val targetShape = model.expectShape(context.shape.target)
+        if (symbolProvider.toSymbol(context.shape).isOptional()) {
+            safeName().also { local ->
+                rustBlock("if let Some($local) = ${context.valueExpression.asRef()}") {
+                    context.valueExpression = ValueExpression.Reference(local)
+                    for (customization in customizations) {
+                        customization.section(
+                            CborSerializerSection.BeforeSerializingNonNullMember(targetShape, context),
+                        )(this)
+                    }
+                    serializeMemberValue(context, targetShape)
+                }
+                if (context.writeNulls) {
+                    rustBlock("else") {
+                        rust("${context.writerExpression}.null();")
+                    }
+                }
+            }
+        } else {
+            for (customization in customizations) {
+                customization.section(CborSerializerSection.BeforeSerializingNonNullMember(targetShape, context))(
+                    this,
+                )
+            }
+
+            with(serializerUtil) {
+                ignoreDefaultsForNumbersAndBools(context.shape, context.valueExpression) {
+                    serializeMemberValue(context, targetShape)
+                }
+            }
+        }
+    }
+
+    private fun RustWriter.serializeMemberValue(
+        context: MemberContext,
+        target: Shape,
+    ) {
+        val writer = context.writerExpression
+        val value = context.valueExpression
+
+        when (target) {
+            is StringShape -> rust("$writer.str(${value.name}.as_str());")
+            is BooleanShape -> rust("$writer.boolean(${value.asValue()});")
+            is ByteShape -> rust("$writer.byte(${value.asValue()});")
+            is ShortShape -> rust("$writer.short(${value.asValue()});")
+            is IntegerShape -> rust("$writer.integer(${value.asValue()});")
+            is LongShape -> rust("$writer.long(${value.asValue()});")
+            is FloatShape -> rust("$writer.float(${value.asValue()});")
+            is DoubleShape -> rust("$writer.double(${value.asValue()});")
+            is BlobShape -> rust("$writer.blob(${value.asRef()});")
+            is TimestampShape -> rust("$writer.timestamp(${value.asRef()});")
+            is DocumentShape -> rust("$writer.document(${value.asRef()});")
+            is CollectionShape -> serializeCollection(context, Context("encoder", value, target))
+            is MapShape -> serializeMap(context, Context("encoder", value, target))
+            is StructureShape -> serializeStructure(StructContext(writer, value.asRef(), target))
+            is UnionShape -> serializeUnion(Context(writer, value, target))
+            else -> PANIC("unexpected shape: $target")
+        }
+    }
+
+    private fun RustWriter.serializeCollection(
+        memberContext: MemberContext,
+        context: Context<CollectionShape>,
+    ) {
+        val itemName = safeName("item")
+        for (customization in customizations) {
+            customization.section(CborSerializerSection.BeforeIteratingOverMapOrCollection(context.shape, context))(this)
+        }
+        rust("${memberContext.writerExpression}.array((${context.valueExpression.asRef()}).len() as u64);")
+        rustBlock("for $itemName in ${context.valueExpression.asRef()}") {
+            serializeMember(MemberContext.collectionMember(context, itemName))
+        }
+    }
+
+    private fun RustWriter.serializeMap(
+        memberContext: MemberContext,
+        context: Context<MapShape>,
+    ) {
+        val keyName = safeName("key")
+        val valueName = safeName("value")
+        for (customization in customizations) {
+            customization.section(CborSerializerSection.BeforeIteratingOverMapOrCollection(context.shape, context))(
+                this,
+            )
+        }
+        rust("${memberContext.writerExpression}.map((${context.valueExpression.asRef()}).len() as u64);")
+        rustBlock("for ($keyName, $valueName) in ${context.valueExpression.asRef()}") {
+            serializeMember(MemberContext.mapMember(context, "$keyName.as_str()", valueName))
+        }
+    }
+
+    private fun RustWriter.serializeUnion(context: Context<UnionShape>) {
+        val unionSymbol = symbolProvider.toSymbol(context.shape)
+        val unionSerializer =
+            protocolFunctions.serializeFn(context.shape) { fnName ->
+                rustBlockTemplate(
+                    "pub fn $fnName(encoder: &mut #{Encoder}, input: &#{Input}) -> Result<(), #{Error}>",
+                    "Input" to unionSymbol,
+                    *codegenScope,
+                ) {
+                    val inner = context.copy(encoderBindingName = "encoder")
+                    rust("encoder.map(1);")
+                    rustBlock("match input") {
+                        for (member in context.shape.members()) {
+                            if (member.isTargetUnit()) {
+                                // Unit variants are written as empty maps.
+                                rust(
+                                    "#T::${symbolProvider.toMemberName(member)} => { encoder.str(${member.memberName.dq()}).begin_map().end(); },",
+                                    unionSymbol,
+                                )
+                            } else {
+                                withBlock("#T::${symbolProvider.toMemberName(member)}(inner) => {", "},", unionSymbol) {
+                                    serializeMember(MemberContext.unionMember(inner, "inner", member))
+                                }
+                            }
+                        }
+                        if (codegenTarget.renderUnknownVariant()) {
+                            rustTemplate(
+                                "#{Union}::${UnionGenerator.UNKNOWN_VARIANT_NAME} => return Err(#{Error}::unknown_variant(${unionSymbol.name.dq()}))",
+                                "Union" to unionSymbol,
+                                *codegenScope,
+                            )
+                        }
+                    }
+                    rust("Ok(())")
+                }
+            }
+        rust("#T(${context.encoderBindingName}, ${context.valueExpression.asRef()})?;", unionSerializer)
+    }
+}
@@ -105,6 +105,7 @@ class PythonApplicationGenerator(
         renderAppDefault(writer)
         renderAppClone(writer)
         renderPyAppTrait(writer)
+        renderOperationIds(writer)
         renderPyMethods(writer)
     }
 
@@ -118,9 +119,10 @@ class PythonApplicationGenerator(
             /// :rtype None:
             pub struct App {
                 handlers: #{HashMap}<String, #{SmithyPython}::PyHandler>,
-                middlewares: Vec<#{SmithyPython}::PyMiddlewareHandler>,
+                middlewares: #{SmithyPython}::PyMiddlewares,
                 context: Option<#{pyo3}::PyObject>,
                 workers: #{parking_lot}::Mutex<Vec<#{pyo3}::PyObject>>,
+                asgi_mounts: Vec<#{SmithyPython}::AsgiMount>,
             }
             """,
             *codegenScope,
@@ -137,6 +139,7 @@ class PythonApplicationGenerator(
                         middlewares: self.middlewares.clone(),
                         context: self.context.clone(),
                         workers: #{parking_lot}::Mutex::new(vec![]),
+                        asgi_mounts: self.asgi_mounts.clone(),
                     }
                 }
             }
@@ -152,9 +155,10 @@ class PythonApplicationGenerator(
                 fn default() -> Self {
                     Self {
                         handlers: Default::default(),
-                        middlewares: vec![],
+                        middlewares: Default::default(),
                         context: None,
                         workers: #{parking_lot}::Mutex::new(vec![]),
+                        asgi_mounts: vec![],
                     }
                 }
             }
@@ -182,6 +186,9 @@ class PythonApplicationGenerator(
                 fn handlers(&mut self) -> &mut #{HashMap}<String, #{SmithyPython}::PyHandler> {
                     &mut self.handlers
                 }
+                fn asgi_mounts(&self) -> &[#{SmithyPython}::AsgiMount] {
+                    &self.asgi_mounts
+                }
                 """,
                 *codegenScope,
             )
@@ -200,7 +207,12 @@ class PythonApplicationGenerator(
             ) {
                 rustTemplate(
                     """
-                    let builder = crate::service::$serviceName::builder_without_plugins();
+                    // Middlewares scoped to some operations are applied to those operations by a plugin.
+                    let middleware_locals = #{pyo3_asyncio}::TaskLocals::new(event_loop);
+                    let builder = crate::service::$serviceName::builder_with_plugins(
+                        self.middlewares.plugin(middleware_locals.clone()),
+                        #{SmithyServer}::plugin::IdentityPlugin,
+                    );
                     """,
                     *codegenScope,
                 )
@@ -219,22 +231,8 @@ class PythonApplicationGenerator(
                 }
                 rustTemplate(
                     """
-                    let mut service = #{tower}::util::BoxCloneService::new(builder.build().expect("one or more operations do not have a registered handler; this is a bug in the Python code generator, please file a bug report under https://github.com/smithy-lang/smithy-rs/issues"));
-
-                    {
-                        use #{tower}::Layer;
-                        #{tracing}::trace!("adding middlewares to rust python router");
-                        let mut middlewares = self.middlewares.clone();
-                        // Reverse the middlewares, so they run with same order as they defined
-                        middlewares.reverse();
-                        for handler in middlewares {
-                            #{tracing}::trace!(name = &handler.name, "adding python middleware");
-                            let locals = #{pyo3_asyncio}::TaskLocals::new(event_loop);
-                            let layer = #{SmithyPython}::PyMiddlewareLayer::<#{Protocol}>::new(handler, locals);
-                            service = #{tower}::util::BoxCloneService::new(layer.layer(service));
-                        }
-                    }
-                    Ok(service)
+                    let service = #{tower}::util::BoxCloneService::new(builder.build().expect("one or more operations do not have a registered handler; this is a bug in the Python code generator, please file a bug report under https://github.com/smithy-lang/smithy-rs/issues"));
+                    Ok(self.middlewares.apply::<#{Protocol}>(service, &middleware_locals))
                     """,
                     "Protocol" to protocol.markerStruct(),
                     *codegenScope,
@@ -243,6 +241,38 @@ class PythonApplicationGenerator(
         }
     }
 
+    private fun renderOperationIds(writer: RustWriter) {
+        writer.rustBlockTemplate("impl App", *codegenScope) {
+            rustBlockTemplate(
+                """
+                /// Maps the names operations are registered with to their shape IDs, to scope middlewares.
+                fn operation_ids(names: Option<Vec<String>>) -> #{pyo3}::PyResult<Option<Vec<#{SmithyServer}::shape_id::ShapeId>>>
+                """,
+                *codegenScope,
+            ) {
+                rustBlockTemplate("names.map(|names| names.iter().map(|name| match name.as_str()", *codegenScope) {
+                    for (operation in operations) {
+                        val fnName = RustReservedWords.escapeIfNeeded(symbolProvider.toSymbol(operation).name.toSnakeCase())
+                        val structName = symbolProvider.toSymbol(operation).name.toPascalCase()
+                        rustTemplate(
+                            """
+                            "$fnName" => Ok(<crate::operation_shape::$structName as #{SmithyServer}::operation::OperationShape>::ID),
+                            """,
+                            *codegenScope,
+                        )
+                    }
+                    rustTemplate(
+                        """
+                        _ => Err(#{pyo3}::exceptions::PyValueError::new_err(format!("unknown operation `{name}`"))),
+                        """,
+                        *codegenScope,
+                    )
+                }
+                rust(").collect()).transpose()")
+            }
+        }
+    }
+
     private fun renderPyMethods(writer: RustWriter) {
         writer.rustBlockTemplate(
             """
@@ -256,6 +286,8 @@ class PythonApplicationGenerator(
             val middlewareNext = PythonType.Callable(listOf(middlewareRequest), PythonType.Awaitable(middlewareResponse))
             val middlewareFunc = PythonType.Callable(listOf(middlewareRequest, middlewareNext), PythonType.Awaitable(middlewareResponse))
             val tlsConfig = PythonType.Opaque("TlsConfig", libName, rustNamespace = "crate::tls")
+            val asgiApp = PythonType.Opaque("AsgiApp", libName, rustNamespace = "crate::asgi")
+            val operationNames = PythonType.Optional(PythonType.List(PythonType.Str))
 
             rustTemplate(
                 """
@@ -276,20 +308,82 @@ class PythonApplicationGenerator(
 
                 /// Register a Python function to be executed inside a Tower middleware layer.
                 ///
+                /// The middleware runs for every request, unless `operations` names the operations it runs for.
+                ///
                 /// :param func ${middlewareFunc.renderAsDocstring()}:
+                /// :param operations ${operationNames.renderAsDocstring()}:
                 /// :rtype ${PythonType.None.renderAsDocstring()}:
-                ##[pyo3(text_signature = "(${'$'}self, func)")]
-                pub fn middleware(&mut self, py: #{pyo3}::Python, func: #{pyo3}::PyObject) -> #{pyo3}::PyResult<()> {
+                ##[pyo3(text_signature = "(${'$'}self, func, operations=None)")]
+                pub fn middleware(
+                    &mut self,
+                    py: #{pyo3}::Python,
+                    func: #{pyo3}::PyObject,
+                    operations: Option<Vec<String>>,
+                ) -> #{pyo3}::PyResult<()> {
                     let handler = #{SmithyPython}::PyMiddlewareHandler::new(py, func)?;
+                    let operations = Self::operation_ids(operations)?;
                     #{tracing}::trace!(
                         name = &handler.name,
                         is_coroutine = handler.is_coroutine,
+                        ?operations,
                         "registering middleware function",
                     );
-                    self.middlewares.push(handler);
+                    self.middlewares.push(#{SmithyPython}::middleware::PyMiddleware::Python(handler), operations);
                     Ok(())
                 }
 
+                /// Register a native Rust Tower layer, exported by an extension module as a capsule,
+                /// which runs without holding the GIL.
+                ///
+                /// The layer runs for every request, unless `operations` names the operations it runs for.
+                ///
+                /// The extension module must be built with the same Rust compiler as this application, against
+                /// the same versions of `aws-smithy-http-server-python` and of its dependencies, with the same
+                /// features. The layer can't use APIs that need to run inside a Tokio runtime, as the extension
+                /// module has its own copy of Tokio.
+                ///
+                /// :param layer ${PythonType.Any.renderAsDocstring()}:
+                /// :param operations ${operationNames.renderAsDocstring()}:
+                /// :rtype ${PythonType.None.renderAsDocstring()}:
+                ##[pyo3(text_signature = "(${'$'}self, layer, operations=None)")]
+                pub fn layer(
+                    &mut self,
+                    layer: &#{pyo3}::PyAny,
+                    operations: Option<Vec<String>>,
+                ) -> #{pyo3}::PyResult<()> {
+                    // SAFETY: registering a layer from an incompatible extension module is documented as
+                    // unsupported, and capsules whose name shows they were created by an incompatible build
+                    // are rejected.
+                    let layer = unsafe { #{SmithyPython}::middleware::PyNativeLayer::from_capsule(layer) }?;
+                    let operations = Self::operation_ids(operations)?;
+                    #{tracing}::trace!(name = layer.name(), ?operations, "registering native layer");
+                    self.middlewares.push(#{SmithyPython}::middleware::PyMiddleware::Native(layer), operations);
+                    Ok(())
+                }
+
+                /// Mount an ASGI application on the paths under `path` that don't match any operation.
+                ///
+                /// The mounted application receives the lifespan events of this application, with the
+                /// registered context under the `"context"` key of the lifespan state.
+                ///
+                /// :param path ${PythonType.Str.renderAsDocstring()}:
+                /// :param app ${PythonType.Any.renderAsDocstring()}:
+                /// :rtype ${PythonType.None.renderAsDocstring()}:
+                ##[pyo3(text_signature = "(${'$'}self, path, app)")]
+                pub fn mount_asgi(&mut self, path: String, app: #{pyo3}::PyObject) {
+                    #{tracing}::trace!(path, "mounting asgi application");
+                    self.asgi_mounts.push(#{SmithyPython}::AsgiMount::new(path, app));
+                }
+
+                /// Create an ASGI application serving this application, to run it with an ASGI server
+                /// or mount it inside of another ASGI application.
+                ///
+                /// :rtype ${asgiApp.renderAsDocstring()}:
+                ##[pyo3(text_signature = "(${'$'}self)")]
+                pub fn asgi(&self) -> #{SmithyPython}::PyAsgiApp {
+                    #{SmithyPython}::PyAsgiApp::new(self.clone())
+                }
+
                 /// Main entrypoint: start the server on multiple workers.
                 ///
                 /// :param address ${PythonType.Optional(PythonType.Str).renderAsDocstring()}:
@@ -52,6 +52,7 @@ class PythonServerModuleGenerator(
                 renderPyLogging()
                 renderPyMiddlewareTypes()
                 renderPyTlsTypes()
+                renderPyAsgiTypes()
                 renderPyLambdaTypes()
                 renderPyApplicationType()
                 renderCodegenVersion()
@@ -213,6 +214,22 @@ class PythonServerModuleGenerator(
         )
     }
 
+    private fun RustWriter.renderPyAsgiTypes() {
+        rustTemplate(
+            """
+            let asgi = #{pyo3}::types::PyModule::new(py, "asgi")?;
+            asgi.add_class::<#{SmithyPython}::PyAsgiApp>()?;
+            pyo3::py_run!(
+                py,
+                asgi,
+                "import sys; sys.modules['$libName.asgi'] = asgi"
+            );
+            m.add_submodule(asgi)?;
+            """,
+            *codegenScope,
+        )
+    }
+
     private fun RustWriter.renderPyLambdaTypes() {
         rustTemplate(
             """
@@ -0,0 +1,46 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+package software.amazon.smithy.rust.codegen.server.smithy.customizations
+
+import software.amazon.smithy.model.shapes.BlobShape
+import software.amazon.smithy.model.shapes.ByteShape
+import software.amazon.smithy.model.shapes.IntegerShape
+import software.amazon.smithy.model.shapes.LongShape
+import software.amazon.smithy.model.shapes.ShortShape
+import software.amazon.smithy.rust.codegen.core.rustlang.Writable
+import software.amazon.smithy.rust.codegen.core.rustlang.writable
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.serialize.CborSerializerCustomization
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.serialize.CborSerializerSection
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.serialize.ValueExpression
+import software.amazon.smithy.rust.codegen.server.smithy.ServerCodegenContext
+import software.amazon.smithy.rust.codegen.server.smithy.workingWithPublicConstrainedWrapperTupleType
+
+/**
+ * A customization to, just before we serialize a _constrained_ shape in a CBOR serializer, unwrap the wrapper
+ * newtype and take a shared reference to the actual unconstrained value within it.
+ */
+class BeforeSerializingMemberCborCustomization(private val codegenContext: ServerCodegenContext) :
+    CborSerializerCustomization() {
+    override fun section(section: CborSerializerSection): Writable =
+        when (section) {
+            is CborSerializerSection.BeforeSerializingNonNullMember ->
+                writable {
+                    if (workingWithPublicConstrainedWrapperTupleType(
+                            section.shape,
+                            codegenContext.model,
+                            codegenContext.settings.codegenConfig.publicConstrainedTypes,
+                        )
+                    ) {
+                        if (section.shape is IntegerShape || section.shape is ShortShape || section.shape is LongShape || section.shape is ByteShape || section.shape is BlobShape) {
+                            section.context.valueExpression =
+                                ValueExpression.Reference("&${section.context.valueExpression.name}.0")
+                        }
+                    }
+                }
+
+            else -> emptySection
+        }
+}
@@ -5,7 +5,11 @@
 
 package software.amazon.smithy.rust.codegen.server.smithy.generators
 
+import software.amazon.smithy.model.shapes.BlobShape
 import software.amazon.smithy.model.shapes.OperationShape
+import software.amazon.smithy.model.shapes.StringShape
+import software.amazon.smithy.model.traits.HttpPayloadTrait
+import software.amazon.smithy.model.traits.LengthTrait
 import software.amazon.smithy.rust.codegen.core.rustlang.RustWriter
 import software.amazon.smithy.rust.codegen.core.rustlang.Writable
 import software.amazon.smithy.rust.codegen.core.rustlang.documentShape
@@ -14,6 +18,11 @@ import software.amazon.smithy.rust.codegen.core.rustlang.rustTemplate
 import software.amazon.smithy.rust.codegen.core.rustlang.writable
 import software.amazon.smithy.rust.codegen.core.smithy.CodegenContext
 import software.amazon.smithy.rust.codegen.core.util.dq
+import software.amazon.smithy.rust.codegen.core.util.hasStreamingMember
+import software.amazon.smithy.rust.codegen.core.util.hasTrait
+import software.amazon.smithy.rust.codegen.core.util.inputShape
+import software.amazon.smithy.rust.codegen.core.util.isInputEventStream
+import software.amazon.smithy.rust.codegen.core.util.orNull
 import software.amazon.smithy.rust.codegen.core.util.toPascalCase
 import software.amazon.smithy.rust.codegen.server.smithy.ServerCargoDependency
 
@@ -44,6 +53,29 @@ class ServerOperationGenerator(
             }
         }
 
+    /**
+     * Returns the largest request body in bytes that the `@length` trait on the member bound to the request payload
+     * allows, if the model constrains it.
+     */
+    private fun maxRequestBodySize(): Long? {
+        val payload = operation.inputShape(model).members().find { it.hasTrait<HttpPayloadTrait>() } ?: return null
+        val max = payload.getMemberTrait(model, LengthTrait::class.java).orNull()?.max?.orNull() ?: return null
+        return when (model.expectShape(payload.target)) {
+            is BlobShape -> max
+            // The length of strings is counted in characters, which take up to 4 bytes in UTF-8.
+            is StringShape -> if (max > Long.MAX_VALUE / 4) null else max * 4
+            else -> null
+        }
+    }
+
+    /** Returns the `RequestBody` variant describing how the operation reads its request body. */
+    private fun requestBody(): String =
+        when {
+            operation.isInputEventStream(model) -> "EventStream"
+            operation.inputShape(model).hasStreamingMember(model) -> "Streaming"
+            else -> "Buffered"
+        }
+
     fun render(writer: RustWriter) {
         writer.documentShape(operation, model)
 
@@ -76,6 +108,11 @@ class ServerOperationGenerator(
                     #{ResponseValue:W}
                 }
             }
+
+            impl #{SmithyHttpServer}::body_limit::BodyLimits for $operationName {
+                const MAX_REQUEST_BODY_SIZE: Option<u64> = ${maxRequestBodySize()?.let { "Some($it)" } ?: "None"};
+                const REQUEST_BODY: #{SmithyHttpServer}::body_limit::RequestBody = #{SmithyHttpServer}::body_limit::RequestBody::${requestBody()};
+            }
             """,
             "Error" to operationError(),
             "RequestValue" to requestFmt.value,
@@ -9,6 +9,9 @@ import software.amazon.smithy.model.knowledge.TopDownIndex
 import software.amazon.smithy.model.neighbor.Walker
 import software.amazon.smithy.model.shapes.OperationShape
 import software.amazon.smithy.model.shapes.StringShape
+import software.amazon.smithy.model.shapes.StructureShape
+import software.amazon.smithy.model.traits.CorsTrait
+import software.amazon.smithy.model.traits.HttpHeaderTrait
 import software.amazon.smithy.model.traits.PatternTrait
 import software.amazon.smithy.rust.codegen.core.rustlang.RustReservedWords
 import software.amazon.smithy.rust.codegen.core.rustlang.RustWriter
@@ -19,8 +22,12 @@ import software.amazon.smithy.rust.codegen.core.rustlang.rust
 import software.amazon.smithy.rust.codegen.core.rustlang.rustTemplate
 import software.amazon.smithy.rust.codegen.core.rustlang.writable
 import software.amazon.smithy.rust.codegen.core.smithy.RuntimeType
+import software.amazon.smithy.rust.codegen.core.util.dq
+import software.amazon.smithy.rust.codegen.core.util.getTrait
 import software.amazon.smithy.rust.codegen.core.util.hasTrait
+import software.amazon.smithy.rust.codegen.core.util.inputShape
 import software.amazon.smithy.rust.codegen.core.util.letIf
+import software.amazon.smithy.rust.codegen.core.util.outputShape
 import software.amazon.smithy.rust.codegen.core.util.toPascalCase
 import software.amazon.smithy.rust.codegen.core.util.toSnakeCase
 import software.amazon.smithy.rust.codegen.server.smithy.ServerCargoDependency
@@ -338,6 +345,7 @@ class ServerServiceGenerator(
                         #{Router}::from_iter([#{RoutesArrayElements:W}])
                     };
                     let svc = #{SmithyHttpServer}::routing::RoutingService::new(router);
+                    #{Cors:W}
                     let svc = svc.map(|s| s.layer(self.layer));
                     Ok($serviceName { svc })
                 }
@@ -348,6 +356,40 @@ class ServerServiceGenerator(
                 "NullabilityChecks" to nullabilityChecks,
                 "RoutesArrayElements" to routesArrayElements,
                 "PatternInitializations" to patternInitializations(),
+                "Cors" to corsConfiguration(),
+            )
+        }
+
+    /**
+     * Renders the configuration of the `RoutingService` from the [`@cors`](https://smithy.io/2.0/spec/http-bindings.html#cors-trait)
+     * trait, if the service has it. Clients may send the headers bound to operation inputs, and read the headers bound
+     * to operation outputs and errors, in addition to the headers listed by the trait.
+     */
+    private fun corsConfiguration(): Writable =
+        writable {
+            val corsTrait = service.getTrait<CorsTrait>() ?: return@writable
+            fun headerNames(shapes: List<StructureShape>) =
+                shapes.flatMap { it.members() }.mapNotNull { it.getTrait<HttpHeaderTrait>()?.value?.lowercase() }
+
+            val errors =
+                (operations.flatMap { it.errors } + service.errors).distinct()
+                    .map { model.expectShape(it, StructureShape::class.java) }
+            val allowedHeaders =
+                (corsTrait.additionalAllowedHeaders.map { it.lowercase() } + headerNames(operations.map { it.inputShape(model) }))
+                    .toSortedSet()
+            val exposedHeaders =
+                (corsTrait.additionalExposedHeaders.map { it.lowercase() } + headerNames(operations.map { it.outputShape(model) } + errors))
+                    .toSortedSet()
+            rustTemplate(
+                """
+                let svc = svc.with_cors(#{SmithyHttpServer}::routing::cors::Cors::from_model(
+                    ${corsTrait.origin.dq()},
+                    ${corsTrait.maxAge},
+                    &[${allowedHeaders.joinToString { it.dq() }}],
+                    &[${exposedHeaders.joinToString { it.dq() }}],
+                ));
+                """,
+                *codegenScope,
             )
         }
 
@@ -413,9 +455,9 @@ class ServerServiceGenerator(
                     >
                 {
                     let router = #{Router}::from_iter([#{Pairs:W}]);
-                    let svc = self
-                        .layer
-                        .layer(#{SmithyHttpServer}::routing::RoutingService::new(router));
+                    let svc = #{SmithyHttpServer}::routing::RoutingService::new(router);
+                    #{Cors:W}
+                    let svc = self.layer.layer(svc);
                     $serviceName { svc }
                 }
                 """,
@@ -423,6 +465,7 @@ class ServerServiceGenerator(
                 "Protocol" to protocol.markerStruct(),
                 "Router" to protocol.routerType(),
                 "Pairs" to pairs,
+                "Cors" to corsConfiguration(),
             )
         }
 
@@ -651,6 +694,16 @@ class ServerServiceGenerator(
                             #{SmithyHttpServer}::routing::Route::new,
                         ))
                     }
+
+                    /// Answers [CORS](#{SmithyHttpServer}::routing::cors) preflight requests for the operations of the service, and
+                    /// adds the CORS headers to the responses of requests from allowed origins, as configured by `cors`.
+                    ///
+                    /// This replaces the configuration given by the `@cors` trait of the model, if any.
+                    pub fn with_cors(self, cors: #{SmithyHttpServer}::routing::cors::Cors) -> Self {
+                        $serviceName {
+                            svc: self.svc.with_cors(cors),
+                        }
+                    }
                 }
 
                 impl<S, R> #{Tower}::Service<R> for $serviceName<S>
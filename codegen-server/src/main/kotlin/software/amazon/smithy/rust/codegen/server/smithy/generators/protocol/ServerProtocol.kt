@@ -21,7 +21,11 @@ import software.amazon.smithy.rust.codegen.core.smithy.protocols.HttpBindingReso
 import software.amazon.smithy.rust.codegen.core.smithy.protocols.Protocol
 import software.amazon.smithy.rust.codegen.core.smithy.protocols.RestJson
 import software.amazon.smithy.rust.codegen.core.smithy.protocols.RestXml
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.RpcV2Cbor
 import software.amazon.smithy.rust.codegen.core.smithy.protocols.awsJsonFieldName
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.parse.CborParserCustomization
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.parse.CborParserGenerator
+import software.amazon.smithy.rust.codegen.core.smithy.protocols.parse.CborParserSection
 import software.amazon.smithy.rust.codegen.core.smithy.protocols.parse.JsonParserCustomization
 import software.amazon.smithy.rust.codegen.core.smithy.protocols.parse.JsonParserGenerator
 import software.amazon.smithy.rust.codegen.core.smithy.protocols.parse.JsonParserSection
@@ -36,6 +40,7 @@ import software.amazon.smithy.rust.codegen.server.smithy.canReachConstrainedShap
 import software.amazon.smithy.rust.codegen.server.smithy.generators.http.RestRequestSpecGenerator
 import software.amazon.smithy.rust.codegen.server.smithy.protocols.ServerAwsJsonSerializerGenerator
 import software.amazon.smithy.rust.codegen.server.smithy.protocols.ServerRestJsonSerializerGenerator
+import software.amazon.smithy.rust.codegen.server.smithy.protocols.ServerRpcV2CborSerializerGenerator
 import software.amazon.smithy.rust.codegen.server.smithy.targetCanReachConstrainedShape
 
 interface ServerProtocol : Protocol {
@@ -254,6 +259,52 @@ class ServerRestXmlProtocol(
     override fun serverContentTypeCheckNoModeledInput() = true
 }
 
+class ServerRpcV2CborProtocol(
+    private val serverCodegenContext: ServerCodegenContext,
+) : RpcV2Cbor(serverCodegenContext), ServerProtocol {
+    val runtimeConfig = codegenContext.runtimeConfig
+
+    override val protocolModulePath = "rpc_v2_cbor"
+
+    override fun structuredDataParser(): StructuredDataParserGenerator =
+        CborParserGenerator(
+            serverCodegenContext,
+            httpBindingResolver,
+            returnSymbolToParseFn(serverCodegenContext),
+            listOf(
+                ServerRequestBeforeBoxingDeserializedMemberConvertToMaybeConstrainedCborParserCustomization(
+                    serverCodegenContext,
+                ),
+            ),
+        )
+
+    override fun structuredDataSerializer(): StructuredDataSerializerGenerator =
+        ServerRpcV2CborSerializerGenerator(serverCodegenContext, httpBindingResolver)
+
+    override fun markerStruct() = ServerRuntimeType.protocol("RpcV2Cbor", protocolModulePath, runtimeConfig)
+
+    override fun routerType() =
+        ServerCargoDependency.smithyHttpServer(runtimeConfig).toType()
+            .resolve("protocol::rpc_v2_cbor::router::RpcV2CborRouter")
+
+    /**
+     * Returns the service and operation names, which are matched against the last segments of the request's URI
+     * path: `/service/{ServiceName}/operation/{OperationName}`.
+     */
+    override fun serverRouterRequestSpec(
+        operationShape: OperationShape,
+        operationName: String,
+        serviceName: String,
+        requestSpecModule: RuntimeType,
+    ) = writable {
+        rust("""String::from("$serviceName.$operationName")""")
+    }
+
+    override fun serverRouterRequestSpecType(requestSpecModule: RuntimeType): RuntimeType = RuntimeType.String
+
+    override fun serverRouterRuntimeConstructor() = "new_rpc_v2_cbor_router"
+}
+
 /**
  * A customization to, just before we box a recursive member that we've deserialized into `Option<T>`, convert it into
  * `MaybeConstrained` if the target shape can reach a constrained shape.
@@ -276,3 +327,25 @@ class ServerRequestBeforeBoxingDeserializedMemberConvertToMaybeConstrainedJsonPa
             else -> emptySection
         }
 }
+
+/**
+ * The CBOR equivalent of [ServerRequestBeforeBoxingDeserializedMemberConvertToMaybeConstrainedJsonParserCustomization].
+ * The CBOR parser decodes non-null values, so the value is converted directly rather than mapped over.
+ */
+class ServerRequestBeforeBoxingDeserializedMemberConvertToMaybeConstrainedCborParserCustomization(
+    val codegenContext: ServerCodegenContext,
+) : CborParserCustomization() {
+    override fun section(section: CborParserSection): Writable =
+        when (section) {
+            is CborParserSection.BeforeBoxingDeserializedMember ->
+                writable {
+                    // We're only interested in _structure_ member shapes that can reach constrained shapes.
+                    if (
+                        codegenContext.model.expectShape(section.shape.container) is StructureShape &&
+                        section.shape.targetCanReachConstrainedShape(codegenContext.model, codegenContext.symbolProvider)
+                    ) {
+                        rust(".into()")
+                    }
+                }
+        }
+}
@@ -15,6 +15,7 @@ import software.amazon.smithy.model.shapes.OperationShape
 import software.amazon.smithy.model.shapes.ShapeId
 import software.amazon.smithy.model.shapes.StructureShape
 import software.amazon.smithy.model.traits.ErrorTrait
+import software.amazon.smithy.protocol.traits.Rpcv2CborTrait
 import software.amazon.smithy.protocoltests.traits.AppliesTo
 import software.amazon.smithy.protocoltests.traits.HttpMalformedRequestTestCase
 import software.amazon.smithy.protocoltests.traits.HttpMalformedRequestTestsTrait
@@ -109,6 +110,7 @@ class ServerProtocolTestGenerator(
             "SmithyHttpServer" to ServerCargoDependency.smithyHttpServer(codegenContext.runtimeConfig).toType(),
             "AssertEq" to RuntimeType.PrettyAssertions.resolve("assert_eq!"),
             "Router" to ServerRuntimeType.router(codegenContext.runtimeConfig),
+            "Base64Decode" to RuntimeType.base64Decode(codegenContext.runtimeConfig),
         )
 
     sealed class TestCase {
@@ -423,7 +425,10 @@ class ServerProtocolTestGenerator(
         rustTemplate(
             """
             .body(${
-                if (body != null) {
+                if (body != null && codegenContext.protocol == Rpcv2CborTrait.ID) {
+                    // RPC v2 CBOR protocol tests encode their binary bodies in base64.
+                    "#{SmithyHttpServer}::body::Body::from(#{Base64Decode}(${body.dq()}).expect(\"protocol test body should be valid base64\"))"
+                } else if (body != null) {
                     // The `replace` is necessary to fix the malformed request test `RestJsonInvalidJsonBody`.
                     // https://github.com/awslabs/smithy/blob/887ae4f6d118e55937105583a07deb90d8fabe1c/smithy-aws-protocol-tests/model/restJson1/malformedRequests/malformed-request-body.smithy#L47
                     //
@@ -492,7 +497,10 @@ class ServerProtocolTestGenerator(
             """
             ##[allow(unused_mut)]
             let (sender, mut receiver) = #{Tokio}::sync::mpsc::channel(1);
-            let config = crate::service::${serviceName}Config::builder().build();
+            // Only the limits given by the model apply to the request body
+            let config = crate::service::${serviceName}Config::builder()
+                .http_plugin(#{SmithyHttpServer}::body_limit::BodyLimitPlugin::new(u64::MAX))
+                .build();
             let service = crate::service::$serviceName::builder::<#{Hyper}::body::Body, _, _, _>(config)
                 .$operationName(move |input: $inputT| {
                     let sender = sender.clone();
@@ -27,6 +27,7 @@ import software.amazon.smithy.model.traits.HttpErrorTrait
 import software.amazon.smithy.model.traits.HttpPayloadTrait
 import software.amazon.smithy.model.traits.HttpTrait
 import software.amazon.smithy.model.traits.MediaTypeTrait
+import software.amazon.smithy.protocol.traits.Rpcv2CborTrait
 import software.amazon.smithy.rust.codegen.core.rustlang.Attribute
 import software.amazon.smithy.rust.codegen.core.rustlang.RustType
 import software.amazon.smithy.rust.codegen.core.rustlang.RustWriter
@@ -1313,6 +1314,9 @@ class ServerHttpBoundProtocolTraitImplGenerator(
             RestXmlTrait.ID -> {
                 RuntimeType.smithyXml(runtimeConfig).resolve("decode::XmlDecodeError").toSymbol()
             }
+            Rpcv2CborTrait.ID -> {
+                RuntimeType.smithyCbor(runtimeConfig).resolve("DeserializeError").toSymbol()
+            }
             else -> {
                 TODO("Protocol ${codegenContext.protocol} not supported yet")
             }
@@ -23,6 +23,18 @@ import software.amazon.smithy.rust.codegen.server.typescript.smithy.TsServerCarg
 
 /**
  * Generates a Typescript compatible application and server that can be configured from Typescript.
+ *
+ * The generated `App` is a napi-rs class, constructed from the Node.js handlers of the operations and an optional
+ * context object which is passed to every handler:
+ *
+ * ```typescript
+ * const app = new App(new HandlerImpl(), { db: new Database() });
+ * app.run({ port: 13734, workers: 4 });
+ * ```
+ *
+ * The `App` implements the `TsApp` trait of the runtime crate, which provides the server, worker management, TLS
+ * and Lambda entrypoints: the generated code only builds the service, routing each operation to the adaptor
+ * rendered by [TsServerOperationHandlerGenerator].
  */
 class TsApplicationGenerator(
     codegenContext: CodegenContext,
@@ -44,48 +56,44 @@ class TsApplicationGenerator(
     private val codegenScope =
         arrayOf(
             "SmithyServer" to ServerCargoDependency.smithyHttpServer(runtimeConfig).toType(),
+            "SmithyTs" to TsServerCargoDependency.smithyHttpServerTs(runtimeConfig).toType(),
             "napi" to TsServerCargoDependency.Napi.toType(),
             "napi_derive" to TsServerCargoDependency.NapiDerive.toType(),
-            "tokio" to TsServerCargoDependency.Tokio.toType(),
-            "tracing" to TsServerCargoDependency.Tracing.toType(),
             "tower" to TsServerCargoDependency.Tower.toType(),
-            "tower_http" to TsServerCargoDependency.TowerHttp.toType(),
-            "num_cpus" to TsServerCargoDependency.NumCpus.toType(),
-            "hyper" to TsServerCargoDependency.Hyper.toType(),
-            "HashMap" to RuntimeType.HashMap,
-            "parking_lot" to TsServerCargoDependency.ParkingLot.toType(),
-            "http" to RuntimeType.Http,
-            "socket2" to TsServerCargoDependency.Socket2.toType(),
         )
 
     fun render(writer: RustWriter) {
-        writer.write("use napi_derive::napi;")
+        writer.rustTemplate(
+            """
+            use #{napi_derive}::napi;
+            // napi-rs only injects the environment in arguments of type `Env`, which must be imported.
+            use #{napi}::Env;
+            """,
+            *codegenScope,
+        )
         renderHandlers(writer)
         renderApp(writer)
-
-        // TODO(https://github.com/napi-rs/napi-rs/issues/1377) Move these to be part of the runtime crate.
-        renderSocket(writer)
-        renderServer(writer)
+        renderTsAppTrait(writer)
     }
 
     fun renderHandlers(writer: RustWriter) {
-        Attribute(derive(RuntimeType.Clone)).render(writer)
+        writer.rust("/// The handlers of the operations, calling the Node.js business logic.")
+        Attribute(derive(RuntimeType.Clone, RuntimeType.Debug)).render(writer)
         writer.rustBlock("""pub struct Handlers""") {
             operations.map { operation ->
                 val operationName = symbolProvider.toSymbol(operation).name
                 val input = "crate::input::${operationName}Input"
+                val output = "crate::output::${operationName}Output"
                 val fnName = operationName.toSnakeCase()
                 rustTemplate(
                     """
-                    pub(crate) $fnName: #{napi}::threadsafe_function::ThreadsafeFunction<
-                        $input,
-                        #{napi}::threadsafe_function::ErrorStrategy::Fatal
-                    >,
+                    pub(crate) $fnName: #{SmithyTs}::TsHandler<$input, $output>,
                     """,
                     *codegenScope,
                 )
             }
         }
+        writer.rust("/// The Node.js handlers of the operations.")
         Attribute("""napi(object)""").render(writer)
         writer.rustBlock("pub struct TsHandlers") {
             operations.map { operation ->
@@ -95,7 +103,7 @@ class TsApplicationGenerator(
                 val fnName = operationName.toSnakeCase()
                 rustTemplate(
                     """
-                    ##[napi(ts_type = "(input: $input) => Promise<$output>")]
+                    ##[napi(ts_type = "(input: $input, context: any) => Promise<$output>")]
                     pub $fnName: #{napi}::JsFunction,
                     """,
                     *codegenScope,
@@ -121,197 +129,94 @@ class TsApplicationGenerator(
     }
 
     private fun renderAppCreate(writer: RustWriter) {
+        writer.rust(
+            """
+            /// Create the application from the handlers of the operations, and a context object passed
+            /// to every handler.
+            """,
+        )
         Attribute("napi(constructor)").render(writer)
         writer.rustBlockTemplate(
-            """pub fn create(ts_handlers: TsHandlers) -> #{napi}::Result<Self>""",
+            """
+            pub fn create(
+                env: Env,
+                ts_handlers: TsHandlers,
+                ##[napi(ts_arg_type = "object")] context: Option<#{napi}::JsUnknown>,
+            ) -> #{napi}::Result<Self>
+            """,
             *codegenScope,
         ) {
+            rust("let handlers = Handlers {")
             operations.map { operation ->
-                val operationName = symbolProvider.toSymbol(operation).name
-                val input = "crate::input::${operationName}Input"
-                val fnName = operationName.toSnakeCase()
+                val fnName = symbolProvider.toSymbol(operation).name.toSnakeCase()
                 rustTemplate(
-                    """
-                    let $fnName: #{napi}::threadsafe_function::ThreadsafeFunction<
-                        $input, #{napi}::threadsafe_function::ErrorStrategy::Fatal
-                    > = ts_handlers.$fnName.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
-                    """,
+                    "    $fnName: #{SmithyTs}::TsHandler::new(&env, ts_handlers.$fnName, context.as_ref())?,",
                     *codegenScope,
                 )
             }
-            rust("let handlers = Handlers {")
-            operations.map { operation ->
-                val operationName = symbolProvider.toSymbol(operation).name.toSnakeCase()
-                rust("    $operationName: $operationName.clone(),")
-            }
             rust("};")
-            writer.rust("Ok(Self{ handlers })")
+            writer.rust("Ok(Self { handlers })")
         }
     }
 
     private fun renderAppStart(writer: RustWriter) {
-        Attribute("napi").render(writer)
-        writer.rustBlockTemplate(
-            """pub fn start(&self, socket: &TsSocket) -> #{napi}::Result<()>""",
-            *codegenScope,
-        ) {
-            rustTemplate(
-                """
-                let plugins = #{SmithyServer}::plugin::PluginPipeline::new();
-                let builder = crate::service::$serviceName::builder_with_plugins(plugins);
-                """,
-                *codegenScope,
-            )
-            operations.map { operation ->
-                val operationName = symbolProvider.toSymbol(operation).name.toSnakeCase()
-                rust("let builder = builder.$operationName(crate::ts_operation_adaptor::$operationName);")
-            }
-            rustTemplate(
-                """
-                let app = builder.build().expect("failed to build instance of $serviceName")
-                    .layer(&#{SmithyServer}::AddExtensionLayer::new(self.handlers.clone()));
-                let service = #{tower}::util::BoxCloneService::new(app);
-                start_hyper_worker(socket, service).expect("failed to start the hyper server");
-                Ok(())
-                """,
-                *codegenScope,
-            )
-        }
-    }
-
-    private fun renderSocket(writer: RustWriter) {
-        Attribute("napi").render(writer)
-        Attribute(derive(RuntimeType.Debug)).render(writer)
         writer.rustTemplate(
             """
-            pub struct TsSocket(#{socket2}::Socket);
+            /// Start a server on a clone of `socket`, in the background. This doesn't manage
+            /// multiple workers, use `run` instead to start them.
+            ##[napi]
+            pub fn start(
+                &mut self,
+                socket: &#{SmithyTs}::TsSocket,
+                tls: Option<#{SmithyTs}::tls::TsTlsConfig>,
+            ) -> #{napi}::Result<()> {
+                #{SmithyTs}::TsApp::start_hyper_worker(self, socket, tls)
+            }
+
+            /// Start the server on multiple workers, see `TsApp::run_server`.
+            ##[napi]
+            pub fn run(&mut self, options: Option<#{SmithyTs}::TsRunOptions>) -> #{napi}::Result<()> {
+                #{SmithyTs}::TsApp::run_server(self, options)
+            }
+
+            /// Start the Lambda handler, in the background.
+            ##[napi]
+            pub fn run_lambda(&mut self) -> #{napi}::Result<()> {
+                #{SmithyTs}::TsApp::run_lambda_handler(self)
+            }
             """,
             *codegenScope,
         )
+    }
 
-        Attribute("napi").render(writer)
-        writer.rustBlock("impl TsSocket") {
-            writer.rust(
-                """
-                /// Create a new UNIX `Socket` from an address, port and backlog.
-                /// If not specified, the backlog defaults to 1024 connections.
-                """.trimIndent(),
-            )
-            Attribute("napi(constructor)").render(writer)
-            writer.rustBlockTemplate(
-                """pub fn new(address: String, port: i32, backlog: Option<i32>) -> #{napi}::Result<Self>""".trimIndent(),
+    private fun renderTsAppTrait(writer: RustWriter) {
+        writer.rustBlockTemplate("impl #{SmithyTs}::TsApp for App", *codegenScope) {
+            rustBlockTemplate(
+                "fn build_service(&mut self) -> #{napi}::Result<#{SmithyTs}::Service>",
                 *codegenScope,
             ) {
-                writer.rustTemplate(
+                rustTemplate(
                     """
-                    let socket = Self::new_socket(address, port, backlog)
-                        .map_err(|e| #{napi}::Error::from_reason(e.to_string()))?;
-                    Ok(Self(socket))
+                    let builder = crate::service::$serviceName::builder_with_plugins(
+                        #{SmithyServer}::plugin::IdentityPlugin,
+                        #{SmithyServer}::plugin::IdentityPlugin,
+                    );
                     """,
                     *codegenScope,
                 )
-            }
-            writer.rustBlockTemplate(
-                """pub fn new_socket(address: String, port: i32, backlog: Option<i32>) -> Result<#{socket2}::Socket, Box<dyn std::error::Error>> """.trimIndent(),
-                *codegenScope,
-            ) {
-                writer.rustTemplate(
-                    """
-                    let address: std::net::SocketAddr = format!("{}:{}", address, port).parse()?;
-                    let domain = if address.is_ipv6() {
-                        #{socket2}::Domain::IPV6
-                    } else {
-                        #{socket2}::Domain::IPV4
-                    };
-                    let socket = #{socket2}::Socket::new(domain, #{socket2}::Type::STREAM, Some(#{socket2}::Protocol::TCP))?;
-                    // Set value for the `SO_REUSEPORT` and `SO_REUSEADDR` options on this socket.
-                    // This indicates that further calls to `bind` may allow reuse of local
-                    // addresses. For IPv4 sockets this means that a socket may bind even when
-                    // there's a socket already listening on this port.
-                    socket.set_reuse_port(true)?;
-                    socket.set_reuse_address(true)?;
-                    socket.bind(&address.into())?;
-                    socket.listen(backlog.unwrap_or(1024))?;
-                    Ok(socket)
-                    """.trimIndent(),
-                    *codegenScope,
-                )
-            }
-
-            writer.rust(
-                """
-                /// Clone the inner socket allowing it to be shared between multiple
-                /// Nodejs processes.
-                """.trimIndent(),
-            )
-            Attribute("napi").render(writer)
-            writer.rustBlockTemplate(
-                """pub fn try_clone(&self) -> #{napi}::Result<Self>""".trimIndent(),
-                *codegenScope,
-            ) {
-                writer.rustTemplate(
+                operations.map { operation ->
+                    val operationName = symbolProvider.toSymbol(operation).name.toSnakeCase()
+                    rust("let builder = builder.$operationName(crate::ts_operation_adaptor::$operationName);")
+                }
+                rustTemplate(
                     """
-                    Ok(TsSocket(
-                        self.0
-                            .try_clone()
-                            .map_err(|e| #{napi}::Error::from_reason(e.to_string()))?,
-                    ))
-                    """.trimIndent(),
+                    let app = builder.build().expect("one or more operations do not have a registered handler; this is a bug in the Typescript code generator, please file a bug report under https://github.com/smithy-lang/smithy-rs/issues")
+                        .layer(&#{SmithyServer}::AddExtensionLayer::new(self.handlers.clone()));
+                    Ok(#{tower}::util::BoxCloneService::new(app))
+                    """,
                     *codegenScope,
                 )
             }
         }
-
-        writer.rustBlock("impl TsSocket") {
-            writer.rustTemplate(
-                """
-                pub fn to_raw_socket(&self) -> #{napi}::Result<#{socket2}::Socket> {
-                self.0
-                 .try_clone()
-                 .map_err(|e| #{napi}::Error::from_reason(e.to_string()))
-                }
-
-                """.trimIndent(),
-                *codegenScope,
-            )
-        }
-    }
-
-    private fun renderServer(writer: RustWriter) {
-        writer.rustBlockTemplate(
-            """
-            pub fn start_hyper_worker(
-            socket: &TsSocket,
-            app: #{tower}::util::BoxCloneService<
-                #{http}::Request<#{SmithyServer}::body::Body>,
-                #{http}::Response<#{SmithyServer}::body::BoxBody>,
-                std::convert::Infallible,
-            >,
-                ) -> #{napi}::Result<()>
-            """.trimIndent(),
-            *codegenScope,
-        ) {
-            writer.rustTemplate(
-                """
-                let server = #{hyper}::Server::from_tcp(
-                        socket
-                            .to_raw_socket()?
-                            .try_into()
-                            .expect("Unable to convert socket2::Socket into std::net::TcpListener"),
-                )
-                .expect("Unable to create hyper server from shared socket")
-                .serve(#{SmithyServer}::routing::IntoMakeService::new(app));
-
-                let handle = #{tokio}::runtime::Handle::current();
-                handle.spawn(async move {
-                    // Process each socket concurrently.
-                    server.await
-                });
-
-                Ok(())
-                """.trimIndent(),
-                *codegenScope,
-            )
-        }
     }
 }
@@ -0,0 +1,60 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! The CBOR data model.
+
+/// The type of the next data item in a CBOR stream, as returned by
+/// [`Decoder::datatype`](crate::Decoder::datatype).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Type {
+    /// A boolean.
+    Bool,
+    /// The `null` simple value.
+    Null,
+    /// The `undefined` simple value.
+    Undefined,
+    /// An unsigned integer (major type 0).
+    UnsignedInt,
+    /// A negative integer (major type 1).
+    NegativeInt,
+    /// A half, single or double precision floating point number.
+    Float,
+    /// A byte string, of definite or indefinite length.
+    Bytes,
+    /// A text string, of definite or indefinite length.
+    String,
+    /// An array, of definite or indefinite length.
+    Array,
+    /// A map, of definite or indefinite length.
+    Map,
+    /// A tagged data item.
+    Tag,
+    /// The "break" stop code ending an indefinite length item.
+    Break,
+    /// Any other simple value, or a reserved initial byte.
+    Unknown,
+}
+
+impl Type {
+    pub(crate) fn new(ty: minicbor::data::Type) -> Self {
+        use minicbor::data::Type as T;
+        match ty {
+            T::Bool => Self::Bool,
+            T::Null => Self::Null,
+            T::Undefined => Self::Undefined,
+            T::U8 | T::U16 | T::U32 | T::U64 => Self::UnsignedInt,
+            T::I8 | T::I16 | T::I32 | T::I64 | T::Int => Self::NegativeInt,
+            T::F16 | T::F32 | T::F64 => Self::Float,
+            T::Bytes | T::BytesIndef => Self::Bytes,
+            T::String | T::StringIndef => Self::String,
+            T::Array | T::ArrayIndef => Self::Array,
+            T::Map | T::MapIndef => Self::Map,
+            T::Tag => Self::Tag,
+            T::Break => Self::Break,
+            T::Simple | T::Unknown(_) => Self::Unknown,
+        }
+    }
+}
@@ -0,0 +1,525 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Decoding of CBOR encoded Smithy values.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+
+use aws_smithy_types::{Blob, DateTime, Document, Number};
+use minicbor::data::Tag;
+
+use crate::data::Type;
+
+#[derive(Debug)]
+enum DeserializeErrorKind {
+    Decode(minicbor::decode::Error),
+    Custom {
+        message: Cow<'static, str>,
+        at: Option<usize>,
+    },
+}
+
+/// An error that occurred while decoding a CBOR value.
+#[derive(Debug)]
+pub struct DeserializeError {
+    kind: DeserializeErrorKind,
+}
+
+impl DeserializeError {
+    fn new(kind: DeserializeErrorKind) -> Self {
+        Self { kind }
+    }
+
+    /// Returns a custom error, optionally located at the byte offset `at`.
+    pub fn custom(message: impl Into<Cow<'static, str>>, at: Option<usize>) -> Self {
+        Self::new(DeserializeErrorKind::Custom {
+            message: message.into(),
+            at,
+        })
+    }
+
+    /// An unexpected type was encountered at the byte offset `at`.
+    pub fn unexpected_type(expected: &'static str, found: Type, at: usize) -> Self {
+        Self::custom(format!("expected {expected}, found {found:?}"), Some(at))
+    }
+
+    /// An unknown union variant was encountered. Servers reject unknown union variants.
+    pub fn unknown_union_variant(variant_name: &str, at: usize) -> Self {
+        Self::custom(
+            format!("encountered unknown union variant {variant_name}"),
+            Some(at),
+        )
+    }
+
+    /// More than one union variant was set.
+    pub fn mixed_union_variants(at: usize) -> Self {
+        Self::custom("encountered mixed variants in union", Some(at))
+    }
+
+    /// A union was decoded without any variant set.
+    pub fn empty_union(at: usize) -> Self {
+        Self::custom("encountered union with no variant set", Some(at))
+    }
+
+    /// The input was expected to end, but there are remaining bytes after the byte offset `at`.
+    pub fn expected_end_of_stream(at: usize) -> Self {
+        Self::custom(
+            "expected end of stream but more data is available",
+            Some(at),
+        )
+    }
+}
+
+impl From<minicbor::decode::Error> for DeserializeError {
+    fn from(err: minicbor::decode::Error) -> Self {
+        Self::new(DeserializeErrorKind::Decode(err))
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DeserializeErrorKind::Decode(err) => write!(f, "failed to decode CBOR: {err}"),
+            DeserializeErrorKind::Custom {
+                message,
+                at: Some(at),
+            } => write!(f, "failed to decode CBOR at offset {at}: {message}"),
+            DeserializeErrorKind::Custom { message, at: None } => {
+                write!(f, "failed to decode CBOR: {message}")
+            }
+        }
+    }
+}
+
+impl StdError for DeserializeError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match &self.kind {
+            DeserializeErrorKind::Decode(err) => Some(err),
+            DeserializeErrorKind::Custom { .. } => None,
+        }
+    }
+}
+
+/// A CBOR decoder reading Smithy values from a byte slice.
+///
+/// Every method decodes the data item at the current position and advances past it, unless it
+/// fails.
+#[derive(Debug, Clone)]
+pub struct Decoder<'b> {
+    decoder: minicbor::Decoder<'b>,
+}
+
+impl<'b> Decoder<'b> {
+    /// Creates a decoder reading from the start of `bytes`.
+    pub fn new(bytes: &'b [u8]) -> Self {
+        Self {
+            decoder: minicbor::Decoder::new(bytes),
+        }
+    }
+
+    /// The current byte offset in the input.
+    pub fn position(&self) -> usize {
+        self.decoder.position()
+    }
+
+    /// Sets the current byte offset in the input.
+    pub fn set_position(&mut self, position: usize) {
+        self.decoder.set_position(position)
+    }
+
+    /// Returns `true` if the whole input has been consumed.
+    pub fn is_at_end(&self) -> bool {
+        self.position() >= self.decoder.input().len()
+    }
+
+    /// Returns an error if the whole input hasn't been consumed.
+    pub fn expect_end(&self) -> Result<(), DeserializeError> {
+        if self.is_at_end() {
+            Ok(())
+        } else {
+            Err(DeserializeError::expected_end_of_stream(self.position()))
+        }
+    }
+
+    /// Returns the type of the next data item, without consuming it.
+    pub fn datatype(&self) -> Result<Type, DeserializeError> {
+        Ok(Type::new(self.decoder.datatype()?))
+    }
+
+    /// Skips over the next data item, including all the items it contains.
+    pub fn skip(&mut self) -> Result<(), DeserializeError> {
+        Ok(self.decoder.skip()?)
+    }
+
+    /// Decodes a `null`, also accepting `undefined`.
+    pub fn null(&mut self) -> Result<(), DeserializeError> {
+        match self.datatype()? {
+            Type::Undefined => Ok(self.decoder.undefined()?),
+            _ => Ok(self.decoder.null()?),
+        }
+    }
+
+    /// Decodes a text string. Indefinite length strings are concatenated into an owned string.
+    pub fn str(&mut self) -> Result<Cow<'b, str>, DeserializeError> {
+        match self.decoder.datatype()? {
+            minicbor::data::Type::StringIndef => {
+                let mut string = String::new();
+                for chunk in self.decoder.str_iter()? {
+                    string.push_str(chunk?);
+                }
+                Ok(Cow::Owned(string))
+            }
+            _ => Ok(Cow::Borrowed(self.decoder.str()?)),
+        }
+    }
+
+    /// Decodes a text string into an owned [`String`].
+    pub fn string(&mut self) -> Result<String, DeserializeError> {
+        self.str().map(Cow::into_owned)
+    }
+
+    /// Decodes a boolean.
+    pub fn boolean(&mut self) -> Result<bool, DeserializeError> {
+        Ok(self.decoder.bool()?)
+    }
+
+    /// Decodes an integer which must fit in an `i8`.
+    pub fn byte(&mut self) -> Result<i8, DeserializeError> {
+        Ok(self.decoder.i8()?)
+    }
+
+    /// Decodes an integer which must fit in an `i16`.
+    pub fn short(&mut self) -> Result<i16, DeserializeError> {
+        Ok(self.decoder.i16()?)
+    }
+
+    /// Decodes an integer which must fit in an `i32`.
+    pub fn integer(&mut self) -> Result<i32, DeserializeError> {
+        Ok(self.decoder.i32()?)
+    }
+
+    /// Decodes an integer which must fit in an `i64`.
+    pub fn long(&mut self) -> Result<i64, DeserializeError> {
+        Ok(self.decoder.i64()?)
+    }
+
+    /// Decodes a floating point number of any precision into an `f32`.
+    ///
+    /// Double precision values are rounded to the nearest single precision value.
+    pub fn float(&mut self) -> Result<f32, DeserializeError> {
+        match self.decoder.datatype()? {
+            minicbor::data::Type::F64 => Ok(self.decoder.f64()? as f32),
+            _ => Ok(self.decoder.f32()?),
+        }
+    }
+
+    /// Decodes a floating point number of any precision into an `f64`.
+    pub fn double(&mut self) -> Result<f64, DeserializeError> {
+        Ok(self.decoder.f64()?)
+    }
+
+    /// Decodes a byte string into a [`Blob`]. Indefinite length byte strings are concatenated.
+    pub fn blob(&mut self) -> Result<Blob, DeserializeError> {
+        match self.decoder.datatype()? {
+            minicbor::data::Type::BytesIndef => {
+                let mut bytes = Vec::new();
+                for chunk in self.decoder.bytes_iter()? {
+                    bytes.extend_from_slice(chunk?);
+                }
+                Ok(Blob::new(bytes))
+            }
+            _ => Ok(Blob::new(self.decoder.bytes()?)),
+        }
+    }
+
+    /// Decodes a timestamp, which is an integer or floating point number of seconds since the
+    /// Unix epoch, tagged with the epoch-based date/time tag (1).
+    pub fn timestamp(&mut self) -> Result<DateTime, DeserializeError> {
+        let position = self.position();
+        match self.decoder.tag()? {
+            Tag::Timestamp => {}
+            tag => {
+                return Err(DeserializeError::custom(
+                    format!("expected timestamp tag (1), found {tag:?}"),
+                    Some(position),
+                ))
+            }
+        }
+        let position = self.position();
+        match self.datatype()? {
+            Type::UnsignedInt | Type::NegativeInt => Ok(DateTime::from_secs(self.long()?)),
+            Type::Float => Ok(DateTime::from_secs_f64(self.double()?)),
+            found => Err(DeserializeError::unexpected_type(
+                "a number of epoch seconds",
+                found,
+                position,
+            )),
+        }
+    }
+
+    /// Decodes the header of a map, returning its number of entries, or `None` if it has an
+    /// indefinite length.
+    ///
+    /// Prefer [`decode_map`] to iterate over the entries of a map.
+    pub fn map(&mut self) -> Result<Option<u64>, DeserializeError> {
+        Ok(self.decoder.map()?)
+    }
+
+    /// Decodes the header of an array, returning its number of items, or `None` if it has an
+    /// indefinite length.
+    ///
+    /// Prefer [`decode_list`] to iterate over the items of an array.
+    pub fn list(&mut self) -> Result<Option<u64>, DeserializeError> {
+        Ok(self.decoder.array()?)
+    }
+
+    /// Decodes any data item into a [`Document`].
+    ///
+    /// Integers are decoded into [`Number::PosInt`] or [`Number::NegInt`], and floating point
+    /// numbers into [`Number::Float`]. Map keys must be text strings.
+    pub fn document(&mut self) -> Result<Document, DeserializeError> {
+        let position = self.position();
+        Ok(match self.datatype()? {
+            Type::Null | Type::Undefined => {
+                self.null()?;
+                Document::Null
+            }
+            Type::Bool => Document::Bool(self.boolean()?),
+            Type::UnsignedInt => Document::Number(Number::PosInt(self.decoder.u64()?)),
+            Type::NegativeInt => Document::Number(Number::NegInt(self.long()?)),
+            Type::Float => Document::Number(Number::Float(self.double()?)),
+            Type::String => Document::String(self.string()?),
+            Type::Array => Document::Array(decode_list(self, Vec::new(), |mut items, decoder| {
+                items.push(decoder.document()?);
+                Ok(items)
+            })?),
+            Type::Map => {
+                Document::Object(decode_map(self, HashMap::new(), |mut entries, decoder| {
+                    let key = decoder.string()?;
+                    entries.insert(key, decoder.document()?);
+                    Ok(entries)
+                })?)
+            }
+            found => {
+                return Err(DeserializeError::unexpected_type(
+                    "a document",
+                    found,
+                    position,
+                ))
+            }
+        })
+    }
+}
+
+/// Decodes a map of definite or indefinite length, calling `entry` to decode each of its
+/// entries.
+///
+/// `entry` must decode both the key and the value of the entry. The value returned by the previous
+/// call to `entry`, or `init` for the first entry, is passed to the next call.
+pub fn decode_map<'b, T>(
+    decoder: &mut Decoder<'b>,
+    init: T,
+    mut entry: impl FnMut(T, &mut Decoder<'b>) -> Result<T, DeserializeError>,
+) -> Result<T, DeserializeError> {
+    let mut acc = init;
+    match decoder.map()? {
+        Some(len) => {
+            for _ in 0..len {
+                acc = entry(acc, decoder)?;
+            }
+        }
+        None => {
+            while decoder.datatype()? != Type::Break {
+                acc = entry(acc, decoder)?;
+            }
+            // Consume the break.
+            decoder.skip()?;
+        }
+    }
+    Ok(acc)
+}
+
+/// Decodes an array of definite or indefinite length, calling `item` to decode each of its
+/// items.
+///
+/// The value returned by the previous call to `item`, or `init` for the first item, is passed to
+/// the next call.
+pub fn decode_list<'b, T>(
+    decoder: &mut Decoder<'b>,
+    init: T,
+    mut item: impl FnMut(T, &mut Decoder<'b>) -> Result<T, DeserializeError>,
+) -> Result<T, DeserializeError> {
+    let mut acc = init;
+    match decoder.list()? {
+        Some(len) => {
+            for _ in 0..len {
+                acc = item(acc, decoder)?;
+            }
+        }
+        None => {
+            while decoder.datatype()? != Type::Break {
+                acc = item(acc, decoder)?;
+            }
+            decoder.skip()?;
+        }
+    }
+    Ok(acc)
+}
+
+/// Calls `f` to decode an optional member into `builder`, unless the next data item is `null`,
+/// in which case it is skipped and `builder` is returned as is.
+pub fn set_optional<'b, B>(
+    builder: B,
+    decoder: &mut Decoder<'b>,
+    f: impl FnOnce(B, &mut Decoder<'b>) -> Result<B, DeserializeError>,
+) -> Result<B, DeserializeError> {
+    match decoder.datatype()? {
+        Type::Null | Type::Undefined => {
+            decoder.null()?;
+            Ok(builder)
+        }
+        _ => f(builder, decoder),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Encoder;
+
+    #[test]
+    fn decodes_indefinite_strings_and_blobs() {
+        // (_ "hello", " world")
+        let bytes = [
+            0x7f, 0x65, b'h', b'e', b'l', b'l', b'o', 0x66, b' ', b'w', b'o', b'r', b'l', b'd',
+            0xff,
+        ];
+        let mut decoder = Decoder::new(&bytes);
+        assert_eq!("hello world", decoder.str().unwrap());
+        assert!(decoder.is_at_end());
+
+        // (_ h'0102', h'03')
+        let bytes = [0x5f, 0x42, 0x01, 0x02, 0x41, 0x03, 0xff];
+        assert_eq!(
+            Blob::new(vec![1, 2, 3]),
+            Decoder::new(&bytes).blob().unwrap()
+        );
+    }
+
+    #[test]
+    fn upcasts_integers_and_floats() {
+        // 1 encoded in a single byte can be decoded into any integer type.
+        assert_eq!(1, Decoder::new(&[0x01]).long().unwrap());
+        assert_eq!(1, Decoder::new(&[0x01]).byte().unwrap());
+        // -500 doesn't fit in a byte.
+        assert!(Decoder::new(&[0x39, 0x01, 0xf3]).byte().is_err());
+        assert_eq!(-500, Decoder::new(&[0x39, 0x01, 0xf3]).short().unwrap());
+
+        // 1.5 as a half precision float.
+        assert_eq!(1.5, Decoder::new(&[0xf9, 0x3e, 0x00]).float().unwrap());
+        assert_eq!(1.5, Decoder::new(&[0xf9, 0x3e, 0x00]).double().unwrap());
+        // 1.5 as a double precision float.
+        let bytes = [0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0];
+        assert_eq!(1.5, Decoder::new(&bytes).float().unwrap());
+    }
+
+    #[test]
+    fn decodes_timestamps() {
+        // 1(1363896240)
+        let bytes = [0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0];
+        assert_eq!(
+            DateTime::from_secs(1363896240),
+            Decoder::new(&bytes).timestamp().unwrap()
+        );
+        // 1(1363896240.5)
+        let bytes = [0xc1, 0xfb, 0x41, 0xd4, 0x52, 0xd9, 0xec, 0x20, 0x00, 0x00];
+        assert_eq!(
+            DateTime::from_fractional_secs(1363896240, 0.5),
+            Decoder::new(&bytes).timestamp().unwrap()
+        );
+        // An untagged number isn't a timestamp.
+        assert!(Decoder::new(&[0x1a, 0x51, 0x4b, 0x67, 0xb0])
+            .timestamp()
+            .is_err());
+    }
+
+    #[test]
+    fn decodes_definite_and_indefinite_collections() {
+        let count = |decoder: &mut Decoder<'_>| {
+            decode_list(decoder, 0, |count, decoder| {
+                decoder.integer()?;
+                Ok(count + 1)
+            })
+        };
+        // [1, 2]
+        assert_eq!(2, count(&mut Decoder::new(&[0x82, 0x01, 0x02])).unwrap());
+        // [_ 1, 2]
+        let mut indefinite = Decoder::new(&[0x9f, 0x01, 0x02, 0xff]);
+        assert_eq!(2, count(&mut indefinite).unwrap());
+        assert!(indefinite.is_at_end());
+
+        // {_ "a": null, "b": 1}
+        let bytes = [0xbf, 0x61, b'a', 0xf6, 0x61, b'b', 0x01, 0xff];
+        let entries = decode_map(
+            &mut Decoder::new(&bytes),
+            Vec::new(),
+            |mut entries, decoder| {
+                let key = decoder.string()?;
+                set_optional(entries.len(), decoder, |_, decoder| {
+                    decoder.integer().map(|v| v as usize)
+                })?;
+                entries.push(key);
+                Ok(entries)
+            },
+        )
+        .unwrap();
+        assert_eq!(vec!["a".to_string(), "b".to_string()], entries);
+    }
+
+    #[test]
+    fn document_round_trip() {
+        let document = Document::Object(HashMap::from([
+            ("null".to_string(), Document::Null),
+            ("bool".to_string(), Document::Bool(true)),
+            (
+                "pos".to_string(),
+                Document::Number(Number::PosInt(u64::MAX)),
+            ),
+            ("neg".to_string(), Document::Number(Number::NegInt(-42))),
+            ("float".to_string(), Document::Number(Number::Float(0.25))),
+            (
+                "list".to_string(),
+                Document::Array(vec![Document::String("a".into())]),
+            ),
+        ]));
+        let mut encoder = Encoder::new(Vec::new());
+        encoder.document(&document);
+        let bytes = encoder.into_writer();
+        let mut decoder = Decoder::new(&bytes);
+        assert_eq!(document, decoder.document().unwrap());
+        assert!(decoder.is_at_end());
+    }
+
+    #[test]
+    fn errors_carry_context() {
+        let err = Decoder::new(&[0x01]).string().unwrap_err();
+        assert!(
+            err.to_string().starts_with("failed to decode CBOR"),
+            "{err}"
+        );
+        assert!(err.source().is_some());
+
+        let mut decoder = Decoder::new(&[0x01, 0x02]);
+        decoder.integer().unwrap();
+        let err = decoder.expect_end().unwrap_err();
+        assert_eq!(
+            "failed to decode CBOR at offset 1: expected end of stream but more data is available",
+            err.to_string()
+        );
+    }
+}
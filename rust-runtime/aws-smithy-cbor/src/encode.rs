@@ -0,0 +1,176 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Encoding of Smithy values into CBOR.
+
+use aws_smithy_types::{Blob, DateTime, Document, Number};
+use minicbor::data::Tag;
+
+/// Writing to a `Vec<u8>` can't fail, so every encoding operation is infallible.
+macro_rules! delegate_method {
+    ($($(#[$doc:meta])* $wrapper_name:ident => $encoder_name:ident($($param_name:ident : $param_type:ty),*);)+) => {
+        $(
+            $(#[$doc])*
+            pub fn $wrapper_name(&mut self, $($param_name: $param_type),*) -> &mut Self {
+                self.encoder.$encoder_name($($param_name),*).expect(INFALLIBLE_WRITE);
+                self
+            }
+        )+
+    };
+}
+
+const INFALLIBLE_WRITE: &str = "write failed";
+
+/// A CBOR encoder writing Smithy values into a `Vec<u8>`.
+///
+/// Structures are encoded as maps of indefinite length, started with [`Encoder::begin_map`] and
+/// ended with [`Encoder::end`], so that members which are not set can be omitted without
+/// counting them upfront.
+#[derive(Debug)]
+pub struct Encoder {
+    encoder: minicbor::Encoder<Vec<u8>>,
+}
+
+impl Encoder {
+    /// Creates an encoder appending to `writer`.
+    pub fn new(writer: Vec<u8>) -> Self {
+        Self {
+            encoder: minicbor::Encoder::new(writer),
+        }
+    }
+
+    delegate_method! {
+        /// Writes a text string.
+        str => str(x: &str);
+        /// Writes a boolean.
+        boolean => bool(x: bool);
+        /// Writes a byte.
+        byte => i8(x: i8);
+        /// Writes a short.
+        short => i16(x: i16);
+        /// Writes an integer.
+        integer => i32(x: i32);
+        /// Writes a long.
+        long => i64(x: i64);
+        /// Writes a single precision floating point number.
+        float => f32(x: f32);
+        /// Writes a double precision floating point number.
+        double => f64(x: f64);
+        /// Writes `null`.
+        null => null();
+        /// Writes the header of an array of `len` items.
+        array => array(len: u64);
+        /// Writes the header of a map of `len` entries.
+        map => map(len: u64);
+        /// Starts a map of indefinite length, which must be ended with [`Encoder::end`].
+        begin_map => begin_map();
+        /// Writes the "break" stop code ending the current indefinite length item.
+        end => end();
+    }
+
+    /// Writes a [`Blob`] as a byte string.
+    pub fn blob(&mut self, x: &Blob) -> &mut Self {
+        self.encoder.bytes(x.as_ref()).expect(INFALLIBLE_WRITE);
+        self
+    }
+
+    /// Writes a [`DateTime`] as a double precision number of seconds since the Unix epoch,
+    /// tagged with the epoch-based date/time tag (1).
+    pub fn timestamp(&mut self, x: &DateTime) -> &mut Self {
+        self.encoder
+            .tag(Tag::Timestamp)
+            .expect(INFALLIBLE_WRITE)
+            .f64(x.as_secs_f64())
+            .expect(INFALLIBLE_WRITE);
+        self
+    }
+
+    /// Writes a [`Number`], using the CBOR integer or floating point type matching its variant.
+    pub fn number(&mut self, x: Number) -> &mut Self {
+        match x {
+            Number::PosInt(value) => self.encoder.u64(value),
+            Number::NegInt(value) => self.encoder.i64(value),
+            Number::Float(value) => self.encoder.f64(value),
+        }
+        .expect(INFALLIBLE_WRITE);
+        self
+    }
+
+    /// Writes a [`Document`]. Objects are written as maps and arrays as arrays, both of definite
+    /// length.
+    pub fn document(&mut self, x: &Document) -> &mut Self {
+        match x {
+            Document::Object(entries) => {
+                self.map(entries.len() as u64);
+                for (key, value) in entries {
+                    self.str(key).document(value);
+                }
+                self
+            }
+            Document::Array(items) => {
+                self.array(items.len() as u64);
+                for item in items {
+                    self.document(item);
+                }
+                self
+            }
+            Document::Number(number) => self.number(*number),
+            Document::String(string) => self.str(string),
+            Document::Bool(boolean) => self.boolean(*boolean),
+            Document::Null => self.null(),
+        }
+    }
+
+    /// Returns the bytes written so far.
+    pub fn into_writer(self) -> Vec<u8> {
+        self.encoder.into_writer()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(f: impl FnOnce(&mut Encoder) -> &mut Encoder) -> Vec<u8> {
+        let mut encoder = Encoder::new(Vec::new());
+        f(&mut encoder);
+        encoder.into_writer()
+    }
+
+    #[test]
+    fn integers_use_the_shortest_encoding() {
+        assert_eq!(vec![0x01], encode(|e| e.long(1)));
+        assert_eq!(vec![0x20], encode(|e| e.byte(-1)));
+        assert_eq!(vec![0x19, 0x01, 0xf4], encode(|e| e.integer(500)));
+    }
+
+    #[test]
+    fn encodes_structures_as_indefinite_maps() {
+        let bytes = encode(|e| e.begin_map().str("a").boolean(true).end());
+        assert_eq!(vec![0xbf, 0x61, b'a', 0xf5, 0xff], bytes);
+    }
+
+    #[test]
+    fn encodes_blobs_and_timestamps() {
+        let bytes = encode(|e| e.blob(&Blob::new(vec![1, 2])));
+        assert_eq!(vec![0x42, 0x01, 0x02], bytes);
+
+        let bytes = encode(|e| e.timestamp(&DateTime::from_fractional_secs(1363896240, 0.5)));
+        assert_eq!(
+            vec![0xc1, 0xfb, 0x41, 0xd4, 0x52, 0xd9, 0xec, 0x20, 0x00, 0x00],
+            bytes
+        );
+    }
+
+    #[test]
+    fn encodes_numbers() {
+        assert_eq!(vec![0x18, 0x2a], encode(|e| e.number(Number::PosInt(42))));
+        assert_eq!(vec![0x38, 0x29], encode(|e| e.number(Number::NegInt(-42))));
+        assert_eq!(
+            vec![0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0],
+            encode(|e| e.number(Number::Float(1.5)))
+        );
+    }
+}
@@ -0,0 +1,29 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+/* Automatically managed default lints */
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
+/* End of automatically managed default lints */
+#![warn(
+    missing_docs,
+    rustdoc::missing_crate_level_docs,
+    unreachable_pub,
+    rust_2018_idioms
+)]
+
+//! CBOR abstractions for Smithy.
+//!
+//! These are the primitives used by the code generated for the [RPC v2 CBOR] protocol. Values are
+//! encoded with [`Encoder`] and decoded with [`Decoder`], using the CBOR representation the
+//! protocol specifies for each Smithy type.
+//!
+//! [RPC v2 CBOR]: https://smithy.io/2.0/additional-specs/protocols/smithy-rpc-v2.html
+
+pub mod data;
+pub mod decode;
+pub mod encode;
+
+pub use decode::{Decoder, DeserializeError};
+pub use encode::Encoder;
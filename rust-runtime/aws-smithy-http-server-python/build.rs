@@ -0,0 +1,27 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+fn generate_build_vars(output_path: &Path) {
+    let rustc_version = rustc_version::version_meta()
+        .expect("Could not retrieve rustc version")
+        .short_version_string;
+    let mut f =
+        File::create(output_path.join("build_env.rs")).expect("Could not create build environment");
+    f.write_all(format!("const RUSTC_VERSION: &str = {:?};", rustc_version).as_bytes())
+        .expect("Unable to write rustc version");
+    f.flush().expect("failed to flush");
+}
+
+fn main() {
+    let out_dir = env::var_os("OUT_DIR").expect("OUT_DIR not specified");
+    let out_path = Path::new(&out_dir).to_owned();
+
+    generate_build_vars(&out_path);
+}
@@ -0,0 +1,343 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use aws_smithy_http_server::{body::Body, request::connect_info::ConnectInfo};
+use bytes::Bytes;
+use http::{Request, Version};
+use hyper::body::HttpBody;
+use parking_lot::Mutex;
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBytes, PyTuple},
+};
+use pyo3_asyncio::TaskLocals;
+use tower::ServiceExt;
+
+use super::{
+    headers_from_py, headers_into_py, message, message_body, message_more_body, message_type,
+    AsgiMount,
+};
+use crate::server::{PyApp, Service};
+
+type BuildService = Box<dyn FnMut(Python, &PyAny) -> PyResult<Service> + Send>;
+
+/// An ASGI application serving a Smithy application, which can be run by any ASGI server or
+/// mounted inside of another ASGI application.
+///
+/// The service of the Smithy application is built, and its context set up, when the ASGI server
+/// sends `lifespan.startup`, or on the first request if the ASGI server doesn't support the
+/// lifespan protocol.
+///
+/// :rtype None:
+#[pyclass(name = "AsgiApp")]
+pub struct PyAsgiApp {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    build: Mutex<BuildService>,
+    service: Mutex<Option<Service>>,
+    context: Option<PyObject>,
+    mounts: Vec<AsgiMount>,
+}
+
+impl PyAsgiApp {
+    /// Creates an ASGI application serving `app`.
+    pub fn new<A>(mut app: A) -> Self
+    where
+        A: PyApp + Send + 'static,
+    {
+        let context = app.context().clone();
+        let mounts = app.asgi_mounts().to_vec();
+        Self {
+            inner: Arc::new(Inner {
+                build: Mutex::new(Box::new(move |py, event_loop| {
+                    app.build_and_configure_service(py, event_loop)
+                })),
+                service: Mutex::new(None),
+                context,
+                mounts,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Debug for PyAsgiApp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PyAsgiApp")
+            .field("mounts", &self.inner.mounts)
+            .finish_non_exhaustive()
+    }
+}
+
+#[pymethods]
+impl PyAsgiApp {
+    /// Handle an ASGI `http` or `lifespan` scope.
+    ///
+    /// :param scope typing.Dict[str, typing.Any]:
+    /// :param receive typing.Callable[[], typing.Awaitable[typing.Dict[str, typing.Any]]]:
+    /// :param send typing.Callable[[typing.Dict[str, typing.Any]], typing.Awaitable[None]]:
+    /// :rtype typing.Awaitable[None]:
+    fn __call__<'p>(
+        &self,
+        py: Python<'p>,
+        scope: &PyAny,
+        receive: PyObject,
+        send: PyObject,
+    ) -> PyResult<&'p PyAny> {
+        let locals = pyo3_asyncio::tokio::get_current_locals(py)?;
+        let inner = self.inner.clone();
+        match message_type(scope)?.as_str() {
+            "http" => {
+                let request = request_from_scope(scope)?;
+                pyo3_asyncio::tokio::future_into_py_with_locals(
+                    py,
+                    locals.clone(),
+                    inner.http(locals, request, receive, send),
+                )
+            }
+            "lifespan" => pyo3_asyncio::tokio::future_into_py_with_locals(
+                py,
+                locals.clone(),
+                inner.lifespan(locals, receive, send),
+            ),
+            ty => Err(PyValueError::new_err(format!(
+                "unsupported asgi scope type `{ty}`"
+            ))),
+        }
+    }
+}
+
+impl Inner {
+    /// Returns the service of the application, building it on the event loop of `locals` first
+    /// if needed.
+    fn service(&self, locals: &TaskLocals) -> PyResult<Service> {
+        let mut service = self.service.lock();
+        if let Some(service) = &*service {
+            return Ok(service.clone());
+        }
+        let built = Python::with_gil(|py| (self.build.lock())(py, locals.event_loop(py)))?;
+        *service = Some(built.clone());
+        Ok(built)
+    }
+
+    async fn startup(&self, locals: &TaskLocals) -> PyResult<()> {
+        self.service(locals)?;
+        let context = Python::with_gil(|py| self.context.clone().unwrap_or_else(|| py.None()));
+        for mount in &self.mounts {
+            mount.startup(locals, &context).await?;
+        }
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> PyResult<()> {
+        let mut result = Ok(());
+        for mount in &self.mounts {
+            if let Err(err) = mount.shutdown().await {
+                result = result.and(Err(err));
+            }
+        }
+        self.service.lock().take();
+        result
+    }
+
+    async fn lifespan(
+        self: Arc<Self>,
+        locals: TaskLocals,
+        receive: PyObject,
+        send: PyObject,
+    ) -> PyResult<()> {
+        loop {
+            let event = call(&locals, &receive, ()).await?;
+            let ty = Python::with_gil(|py| message_type(event.as_ref(py)))?;
+            let (result, done) = match ty.as_str() {
+                "lifespan.startup" => (self.startup(&locals).await, false),
+                "lifespan.shutdown" => (self.shutdown().await, true),
+                _ => continue,
+            };
+            let reply = Python::with_gil(|py| match result {
+                Ok(()) => message(py, &format!("{ty}.complete"), vec![]),
+                Err(err) => message(
+                    py,
+                    &format!("{ty}.failed"),
+                    vec![("message", err.to_string().into_py(py))],
+                ),
+            });
+            call(&locals, &send, (reply,)).await?;
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn http(
+        self: Arc<Self>,
+        locals: TaskLocals,
+        request: Request<()>,
+        receive: PyObject,
+        send: PyObject,
+    ) -> PyResult<()> {
+        let service = self.service(&locals)?;
+        let (body_tx, body) = Body::channel();
+        let receive_body = tokio::spawn(receive_body(locals.clone(), receive, body_tx));
+
+        let response = service
+            .oneshot(request.map(|_| body))
+            .await
+            .unwrap_or_else(|err| match err {});
+        receive_body.abort();
+
+        let (parts, mut body) = response.into_parts();
+        let start = Python::with_gil(|py| {
+            message(
+                py,
+                "http.response.start",
+                vec![
+                    ("status", parts.status.as_u16().into_py(py)),
+                    ("headers", headers_into_py(py, &parts.headers)),
+                ],
+            )
+        });
+        call(&locals, &send, (start,)).await?;
+        loop {
+            let (chunk, more_body) = match body.data().await {
+                Some(Ok(chunk)) => (chunk, true),
+                Some(Err(err)) => {
+                    // Finish the response so that the ASGI server doesn't wait for more of it.
+                    call(&locals, &send, (response_body(Bytes::new(), false),)).await?;
+                    return Err(PyValueError::new_err(format!(
+                        "unable to read response body: {err}"
+                    )));
+                }
+                None => (Bytes::new(), false),
+            };
+            call(&locals, &send, (response_body(chunk, more_body),)).await?;
+            if !more_body {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Calls the Python awaitable callable `callable` on the event loop of `locals` and awaits it.
+async fn call(
+    locals: &TaskLocals,
+    callable: &PyObject,
+    args: impl IntoPy<Py<PyTuple>>,
+) -> PyResult<PyObject> {
+    let future = Python::with_gil(|py| {
+        let awaitable = callable.call1(py, args)?;
+        pyo3_asyncio::into_future_with_locals(locals, awaitable.as_ref(py))
+    })?;
+    future.await
+}
+
+fn response_body(chunk: Bytes, more_body: bool) -> PyObject {
+    Python::with_gil(|py| {
+        message(
+            py,
+            "http.response.body",
+            vec![
+                ("body", PyBytes::new(py, &chunk).into()),
+                ("more_body", more_body.into_py(py)),
+            ],
+        )
+    })
+}
+
+/// Feeds the `http.request` events received from the ASGI server to the request body.
+async fn receive_body(
+    locals: TaskLocals,
+    receive: PyObject,
+    mut body: hyper::body::Sender,
+) -> PyResult<()> {
+    loop {
+        let event = call(&locals, &receive, ()).await?;
+        let event = Python::with_gil(|py| -> PyResult<_> {
+            let event = event.as_ref(py);
+            match message_type(event)?.as_str() {
+                "http.request" => Ok(Some((
+                    message_body(event, "body")?,
+                    message_more_body(event)?,
+                ))),
+                _ => Ok(None),
+            }
+        })?;
+        match event {
+            Some((chunk, more_body)) => {
+                if !chunk.is_empty() && body.send_data(chunk).await.is_err() {
+                    return Ok(());
+                }
+                if !more_body {
+                    return Ok(());
+                }
+            }
+            // `http.disconnect`
+            None => {
+                body.abort();
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Builds the request of an ASGI `http` scope.
+fn request_from_scope(scope: &PyAny) -> PyResult<Request<()>> {
+    let method: &str = scope.get_item("method")?.extract()?;
+    let root_path: &str = match scope.get_item("root_path") {
+        Ok(root_path) => root_path.extract()?,
+        Err(_) => "",
+    };
+    let raw_path = match scope.get_item("raw_path") {
+        Ok(raw_path) if !raw_path.is_none() => raw_path.downcast::<PyBytes>()?.as_bytes(),
+        _ => scope.get_item("path")?.extract::<&str>()?.as_bytes(),
+    };
+    // The Smithy router routes on the path under the path the application is mounted on.
+    let path = raw_path
+        .strip_prefix(root_path.as_bytes())
+        .unwrap_or(raw_path);
+    let query = message_body(scope, "query_string")?;
+    let mut uri = String::from_utf8_lossy(path).into_owned();
+    if !uri.starts_with('/') {
+        uri.insert(0, '/');
+    }
+    if !query.is_empty() {
+        uri.push('?');
+        uri.push_str(&String::from_utf8_lossy(&query));
+    }
+    let version = match scope
+        .get_item("http_version")
+        .and_then(|v| v.extract::<&str>())
+    {
+        Ok("1.0") => Version::HTTP_10,
+        Ok("2") => Version::HTTP_2,
+        _ => Version::HTTP_11,
+    };
+
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .version(version)
+        .body(())
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    if let Ok(headers) = scope.get_item("headers") {
+        *request.headers_mut() = headers_from_py(headers)?;
+    }
+    let client = scope
+        .get_item("client")
+        .and_then(|client| client.extract::<Option<(String, u16)>>());
+    if let Ok(Some((host, port))) = client {
+        if let Ok(ip) = host.parse() {
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::new(ip, port)));
+        }
+    }
+    Ok(request)
+}
@@ -0,0 +1,371 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! [ASGI] interoperability.
+//!
+//! A Smithy application can be served by any ASGI server, or be mounted inside of an existing ASGI
+//! application, with [PyAsgiApp]:
+//!
+//! ```python
+//! app = App()
+//! ...
+//! asgi_app = app.asgi()  # e.g. `uvicorn service:asgi_app`
+//! ```
+//!
+//! The other way around, ASGI applications can be mounted on the paths that don't match any
+//! operation of a Smithy application with [AsgiMount]:
+//!
+//! ```python
+//! app.mount_asgi("/admin", admin_app)
+//! ```
+//!
+//! The ASGI lifespan protocol is mapped to the context setup of the Smithy application:
+//! mounted applications receive `lifespan.startup` once the application's service is built with
+//! its context, and `lifespan.shutdown` when the worker shuts down. The context is available to
+//! mounted applications under the `"context"` key of the lifespan `state`.
+//!
+//! [ASGI]: https://asgi.readthedocs.io/en/latest/specs/main.html
+
+mod app;
+pub(crate) mod mount;
+
+use std::future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use aws_smithy_http_server::request::connect_info::ConnectInfo;
+use bytes::Bytes;
+use http::{HeaderMap, Method, Uri, Version};
+use pyo3::{
+    exceptions::{PyOSError, PyValueError},
+    prelude::*,
+    types::{IntoPyDict, PyBytes, PyDict, PyList},
+};
+use pyo3_asyncio::TaskLocals;
+use tokio::{
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+};
+
+pub use app::PyAsgiApp;
+pub use mount::{AsgiFallback, AsgiFallbackLayer, AsgiMount};
+
+/// Version of the ASGI specification implemented.
+const ASGI_SPEC_VERSION: &str = "2.3";
+
+/// Returns the `asgi` key of the ASGI scopes.
+fn asgi_version(py: Python) -> PyObject {
+    [("version", "3.0"), ("spec_version", ASGI_SPEC_VERSION)]
+        .into_py_dict(py)
+        .into()
+}
+
+/// Builds an ASGI event message of type `ty`.
+fn message(py: Python, ty: &str, fields: Vec<(&str, PyObject)>) -> PyObject {
+    let message = fields.into_py_dict(py);
+    message
+        .set_item("type", ty)
+        .expect("setting an item of a new dictionary can't fail");
+    message.into()
+}
+
+/// Returns the type of an ASGI event message.
+fn message_type(message: &PyAny) -> PyResult<String> {
+    message.get_item("type")?.extract()
+}
+
+/// Returns the bytes under `key` of an ASGI event message, which are empty if the key is missing.
+fn message_body(message: &PyAny, key: &str) -> PyResult<Bytes> {
+    let message: &PyDict = message.downcast()?;
+    match message.get_item(key) {
+        Some(body) => Ok(Bytes::copy_from_slice(
+            body.downcast::<PyBytes>()?.as_bytes(),
+        )),
+        None => Ok(Bytes::new()),
+    }
+}
+
+/// Returns whether an ASGI event message says more messages of the same type will follow.
+fn message_more_body(message: &PyAny) -> PyResult<bool> {
+    let message: &PyDict = message.downcast()?;
+    message
+        .get_item("more_body")
+        .map_or(Ok(false), |more_body| more_body.is_true())
+}
+
+/// Converts the headers of an ASGI scope or event message into a [HeaderMap].
+fn headers_from_py(headers: &PyAny) -> PyResult<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for header in headers.iter()? {
+        let header = header?;
+        let name: &[u8] = header.get_item(0)?.extract()?;
+        let value: &[u8] = header.get_item(1)?.extract()?;
+        map.append(
+            http::header::HeaderName::from_bytes(name)
+                .map_err(|e| PyValueError::new_err(e.to_string()))?,
+            http::header::HeaderValue::from_bytes(value)
+                .map_err(|e| PyValueError::new_err(e.to_string()))?,
+        );
+    }
+    Ok(map)
+}
+
+/// Converts a [HeaderMap] into the list of `[name, value]` byte pairs used by ASGI.
+fn headers_into_py(py: Python, headers: &HeaderMap) -> PyObject {
+    PyList::new(
+        py,
+        headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    PyBytes::new(py, name.as_str().as_bytes()),
+                    PyBytes::new(py, value.as_bytes()),
+                )
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into()
+}
+
+/// Returns the ASGI `http_version` of `version`.
+fn http_version(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_10 => "1.0",
+        Version::HTTP_2 => "2",
+        _ => "1.1",
+    }
+}
+
+/// Percent-decodes the path of a request, as ASGI scopes carry the decoded path.
+fn decode_path(path: &str) -> String {
+    fn hex(byte: u8) -> Option<u8> {
+        (byte as char).to_digit(16).map(|digit| digit as u8)
+    }
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match (
+            bytes[idx],
+            bytes.get(idx + 1).copied().and_then(hex),
+            bytes.get(idx + 2).copied().and_then(hex),
+        ) {
+            (b'%', Some(high), Some(low)) => {
+                decoded.push(high << 4 | low);
+                idx += 3;
+            }
+            (byte, _, _) => {
+                decoded.push(byte);
+                idx += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The parts of an HTTP request that make up an ASGI `http` scope.
+#[derive(Debug, Clone)]
+struct RequestHead {
+    method: Method,
+    uri: Uri,
+    version: Version,
+    headers: HeaderMap,
+    client: Option<SocketAddr>,
+}
+
+impl RequestHead {
+    fn new(parts: &http::request::Parts) -> Self {
+        Self {
+            method: parts.method.clone(),
+            uri: parts.uri.clone(),
+            version: parts.version,
+            headers: parts.headers.clone(),
+            client: parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| *addr),
+        }
+    }
+
+    /// Builds the ASGI `http` scope of this request, for an application mounted on `root_path`.
+    fn scope(&self, py: Python, root_path: &str, state: Option<&PyObject>) -> PyObject {
+        let scope = [
+            ("asgi", asgi_version(py)),
+            ("http_version", http_version(self.version).into_py(py)),
+            ("method", self.method.as_str().into_py(py)),
+            (
+                "scheme",
+                self.uri.scheme_str().unwrap_or("http").into_py(py),
+            ),
+            ("path", decode_path(self.uri.path()).into_py(py)),
+            (
+                "raw_path",
+                PyBytes::new(py, self.uri.path().as_bytes()).into(),
+            ),
+            (
+                "query_string",
+                PyBytes::new(py, self.uri.query().unwrap_or_default().as_bytes()).into(),
+            ),
+            ("root_path", root_path.into_py(py)),
+            ("headers", headers_into_py(py, &self.headers)),
+            (
+                "client",
+                self.client
+                    .map(|addr| (addr.ip().to_string(), addr.port()))
+                    .into_py(py),
+            ),
+            ("server", py.None()),
+        ];
+        let scope = message(py, "http", scope.into_iter().collect());
+        if let Some(state) = state {
+            // Every request gets a shallow copy of the lifespan state, as required by ASGI.
+            let state = state
+                .call_method0(py, "copy")
+                .expect("the lifespan state is a dictionary");
+            scope
+                .as_ref(py)
+                .set_item("state", state)
+                .expect("setting an item of a new dictionary can't fail");
+        }
+        scope
+    }
+}
+
+/// What [AsgiReceive] returns once no more event messages will be sent to the application.
+#[derive(Debug, Clone, Copy)]
+enum OnClosed {
+    /// Returns `http.disconnect`.
+    Disconnect,
+    /// Never returns.
+    Pending,
+}
+
+/// The `receive` awaitable callable passed to ASGI applications called by Rust.
+#[pyclass]
+struct AsgiReceive {
+    messages: Arc<Mutex<mpsc::Receiver<PyObject>>>,
+    on_closed: OnClosed,
+}
+
+impl AsgiReceive {
+    fn new(messages: mpsc::Receiver<PyObject>, on_closed: OnClosed) -> Self {
+        Self {
+            messages: Arc::new(Mutex::new(messages)),
+            on_closed,
+        }
+    }
+}
+
+#[pymethods]
+impl AsgiReceive {
+    fn __call__<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let messages = self.messages.clone();
+        let on_closed = self.on_closed;
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            if let Some(message) = messages.lock().await.recv().await {
+                return Ok(message);
+            }
+            match on_closed {
+                OnClosed::Disconnect => Ok(Python::with_gil(|py| {
+                    message(py, "http.disconnect", vec![])
+                })),
+                OnClosed::Pending => future::pending().await,
+            }
+        })
+    }
+}
+
+/// The `send` awaitable callable passed to ASGI applications called by Rust.
+#[pyclass]
+struct AsgiSend {
+    messages: mpsc::Sender<PyObject>,
+}
+
+#[pymethods]
+impl AsgiSend {
+    fn __call__<'p>(&self, py: Python<'p>, message: PyObject) -> PyResult<&'p PyAny> {
+        let messages = self.messages.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            messages
+                .send(message)
+                .await
+                .map_err(|_| PyOSError::new_err("the ASGI connection is closed"))
+        })
+    }
+}
+
+/// An ASGI application called by Rust, with the channels its `receive` and `send` callables are
+/// connected to.
+struct AsgiCall {
+    receive: mpsc::Sender<PyObject>,
+    send: mpsc::Receiver<PyObject>,
+    app: Option<JoinHandle<PyResult<PyObject>>>,
+}
+
+/// What an ASGI application called by Rust did next.
+enum Next {
+    /// The application sent an event message.
+    Message(PyObject),
+    /// The application returned, or raised an exception.
+    Returned(PyResult<()>),
+}
+
+impl AsgiCall {
+    /// Calls `app` with `scope` and runs it on the event loop of `locals`.
+    fn spawn(
+        app: &PyObject,
+        locals: &TaskLocals,
+        scope: PyObject,
+        on_closed: OnClosed,
+    ) -> PyResult<Self> {
+        let (receive, receive_rx) = mpsc::channel(1);
+        let (send_tx, send) = mpsc::channel(1);
+        let future = Python::with_gil(|py| {
+            let coroutine = app.call1(
+                py,
+                (
+                    scope,
+                    AsgiReceive::new(receive_rx, on_closed),
+                    AsgiSend { messages: send_tx },
+                ),
+            )?;
+            pyo3_asyncio::into_future_with_locals(locals, coroutine.as_ref(py))
+        })?;
+        Ok(Self {
+            receive,
+            send,
+            app: Some(tokio::spawn(future)),
+        })
+    }
+
+    /// Sends an event message to the application, unless it has returned.
+    async fn send(&self, message: PyObject) {
+        // The application may have returned without receiving the message, which is fine.
+        let _ = self.receive.send(message).await;
+    }
+
+    /// Waits for the application to send an event message or to return.
+    async fn next(&mut self) -> Next {
+        let Some(app) = self.app.as_mut() else {
+            return self
+                .send
+                .try_recv()
+                .map_or(Next::Returned(Ok(())), Next::Message);
+        };
+        tokio::select! {
+            // Messages sent before the application returned come first.
+            biased;
+            Some(message) = self.send.recv() => Next::Message(message),
+            result = app => {
+                self.app = None;
+                Next::Returned(match result {
+                    Ok(result) => result.map(|_| ()),
+                    Err(join_error) => Err(PyOSError::new_err(join_error.to_string())),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,472 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::convert::Infallible;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use aws_smithy_http_server::{
+    body::{boxed, Body, BoxBody},
+    extension::RuntimeErrorExtension,
+};
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream, StreamExt};
+use http::{Request, Response, StatusCode};
+use hyper::body::HttpBody;
+use pyo3::{exceptions::PyRuntimeError, prelude::*, types::IntoPyDict, types::PyBytes};
+use pyo3_asyncio::TaskLocals;
+use tokio::sync::{mpsc, Mutex};
+use tower::{Layer, Service};
+
+use super::{
+    asgi_version, headers_from_py, message, message_body, message_more_body, message_type,
+    AsgiCall, Next, OnClosed, RequestHead,
+};
+use crate::util::error::rich_py_err;
+
+/// How long a mounted application is given to handle `lifespan.shutdown`.
+const LIFESPAN_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An ASGI application mounted on the paths of a Smithy application that don't match any
+/// operation.
+///
+/// Requests whose path is `path`, or is under `path`, are routed to the mounted application if
+/// the Smithy router doesn't find an operation for them. The mounted application gets `path` as
+/// the `root_path` of the request scope.
+#[derive(Clone)]
+pub struct AsgiMount {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    path: String,
+    app: PyObject,
+    lifespan: Mutex<Lifespan>,
+}
+
+/// The state of the lifespan protocol of a mounted application.
+enum Lifespan {
+    NotStarted,
+    /// The application returned, or raised an exception, instead of handling `lifespan.startup`.
+    Unsupported,
+    Failed(String),
+    Running {
+        call: AsgiCall,
+        state: PyObject,
+    },
+    Stopped,
+}
+
+impl AsgiMount {
+    /// Mounts the ASGI application `app` on `path`.
+    pub fn new(path: impl AsRef<str>, app: PyObject) -> Self {
+        let path = format!("/{}", path.as_ref().trim_matches('/'));
+        let path = if path == "/" { String::new() } else { path };
+        Self {
+            inner: Arc::new(Inner {
+                path,
+                app,
+                lifespan: Mutex::new(Lifespan::NotStarted),
+            }),
+        }
+    }
+
+    /// Returns the path the application is mounted on, which is empty for the root path.
+    pub fn path(&self) -> &str {
+        &self.inner.path
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        match path.strip_prefix(self.path()) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
+    }
+
+    /// Runs the lifespan startup of the application on the event loop of `locals`, exposing
+    /// `context` in the lifespan state.
+    ///
+    /// Startup runs once, later calls return the outcome of the first one.
+    pub(crate) async fn startup(&self, locals: &TaskLocals, context: &PyObject) -> PyResult<()> {
+        let mut lifespan = self.inner.lifespan.lock().await;
+        match &*lifespan {
+            Lifespan::NotStarted => {}
+            Lifespan::Failed(reason) => return Err(self.startup_failed(reason)),
+            _ => return Ok(()),
+        }
+
+        let (scope, state) = Python::with_gil(|py| {
+            let state: PyObject = [("context", context.clone_ref(py))].into_py_dict(py).into();
+            let scope = message(
+                py,
+                "lifespan",
+                vec![("asgi", asgi_version(py)), ("state", state.clone_ref(py))],
+            );
+            (scope, state)
+        });
+        let mut call = match AsgiCall::spawn(&self.inner.app, locals, scope, OnClosed::Pending) {
+            Ok(call) => call,
+            Err(err) => {
+                *lifespan = Lifespan::Failed(err.to_string());
+                return Err(err);
+            }
+        };
+        call.send(Python::with_gil(|py| {
+            message(py, "lifespan.startup", vec![])
+        }))
+        .await;
+        match call.next().await {
+            Next::Message(reply) => {
+                let reply = Python::with_gil(|py| -> PyResult<_> {
+                    let reply = reply.as_ref(py);
+                    Ok((
+                        message_type(reply)?,
+                        reply.get_item("message").and_then(|m| m.extract()),
+                    ))
+                });
+                match reply {
+                    Ok((ty, _)) if ty == "lifespan.startup.complete" => {
+                        tracing::debug!(path = self.path(), "mounted asgi application started");
+                        *lifespan = Lifespan::Running { call, state };
+                        Ok(())
+                    }
+                    Ok((ty, reason)) => {
+                        let reason = match reason {
+                            Ok(reason) if ty == "lifespan.startup.failed" => reason,
+                            _ => format!("unexpected `{ty}` event"),
+                        };
+                        let err = self.startup_failed(&reason);
+                        *lifespan = Lifespan::Failed(reason);
+                        Err(err)
+                    }
+                    Err(err) => {
+                        *lifespan = Lifespan::Failed(err.to_string());
+                        Err(err)
+                    }
+                }
+            }
+            Next::Returned(result) => {
+                if let Err(err) = result {
+                    tracing::debug!(
+                        path = self.path(),
+                        error = ?rich_py_err(err),
+                        "mounted asgi application does not support the lifespan protocol"
+                    );
+                }
+                *lifespan = Lifespan::Unsupported;
+                Ok(())
+            }
+        }
+    }
+
+    fn startup_failed(&self, reason: &str) -> PyErr {
+        PyRuntimeError::new_err(format!(
+            "the asgi application mounted on `{}` failed to start: {reason}",
+            self.path()
+        ))
+    }
+
+    /// Starts the application in the background. See [AsgiMount::startup].
+    pub(crate) fn spawn_startup(&self, locals: TaskLocals, context: PyObject) {
+        let mount = self.clone();
+        pyo3_asyncio::tokio::get_runtime().spawn(async move {
+            if let Err(err) = mount.startup(&locals, &context).await {
+                tracing::error!(path = mount.path(), error = ?rich_py_err(err), "unable to start mounted asgi application");
+            }
+        });
+    }
+
+    /// Runs the lifespan shutdown of the application, if it was started.
+    pub(crate) async fn shutdown(&self) -> PyResult<()> {
+        let mut lifespan = self.inner.lifespan.lock().await;
+        let Lifespan::Running { mut call, .. } = mem::replace(&mut *lifespan, Lifespan::Stopped)
+        else {
+            return Ok(());
+        };
+        call.send(Python::with_gil(|py| {
+            message(py, "lifespan.shutdown", vec![])
+        }))
+        .await;
+        match tokio::time::timeout(LIFESPAN_SHUTDOWN_TIMEOUT, call.next()).await {
+            Ok(Next::Message(reply)) => Python::with_gil(|py| {
+                let reply = reply.as_ref(py);
+                match message_type(reply)?.as_str() {
+                    "lifespan.shutdown.complete" => Ok(()),
+                    ty => {
+                        let reason = reply
+                            .get_item("message")
+                            .and_then(|m| m.extract::<String>())
+                            .unwrap_or_else(|_| format!("unexpected `{ty}` event"));
+                        Err(PyRuntimeError::new_err(format!(
+                            "the asgi application mounted on `{}` failed to shut down: {reason}",
+                            self.path()
+                        )))
+                    }
+                }
+            }),
+            Ok(Next::Returned(result)) => result,
+            Err(_) => Err(PyRuntimeError::new_err(format!(
+                "the asgi application mounted on `{}` did not shut down in time",
+                self.path()
+            ))),
+        }
+    }
+
+    /// Calls the application with `request` on the event loop of `locals`.
+    async fn call(&self, locals: &TaskLocals, head: RequestHead, body: Body) -> Response<BoxBody> {
+        // Waits for the startup to complete, if it is running.
+        let state = match &*self.inner.lifespan.lock().await {
+            Lifespan::Running { state, .. } => Some(state.clone()),
+            _ => None,
+        };
+        let scope = Python::with_gil(|py| head.scope(py, self.path(), state.as_ref()));
+        let mut call = match AsgiCall::spawn(&self.inner.app, locals, scope, OnClosed::Disconnect) {
+            Ok(call) => call,
+            Err(err) => return self.internal_error(err),
+        };
+        tokio::spawn(send_body(body, call.receive.clone()));
+
+        let start = match call.next().await {
+            Next::Message(start) => start,
+            Next::Returned(Ok(())) => {
+                return self.internal_error(PyRuntimeError::new_err(
+                    "the application returned without a response",
+                ))
+            }
+            Next::Returned(Err(err)) => return self.internal_error(err),
+        };
+        let head = Python::with_gil(|py| -> PyResult<_> {
+            let start = start.as_ref(py);
+            let ty = message_type(start)?;
+            if ty != "http.response.start" {
+                return Err(PyRuntimeError::new_err(format!(
+                    "expected `http.response.start`, got `{ty}`"
+                )));
+            }
+            let status = StatusCode::from_u16(start.get_item("status")?.extract()?)
+                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+            let headers = match start.downcast::<pyo3::types::PyDict>()?.get_item("headers") {
+                Some(headers) => headers_from_py(headers)?,
+                None => Default::default(),
+            };
+            Ok((status, headers))
+        });
+        let (status, headers) = match head {
+            Ok(head) => head,
+            Err(err) => return self.internal_error(err),
+        };
+
+        // The call, and so the `receive` channel, lives until the response body is complete.
+        let body = stream::unfold(Some(call), |call| async move {
+            let mut call = call?;
+            loop {
+                match call.next().await {
+                    Next::Message(message) => {
+                        let chunk = Python::with_gil(|py| -> PyResult<_> {
+                            let message = message.as_ref(py);
+                            if message_type(message)? != "http.response.body" {
+                                return Ok(None);
+                            }
+                            Ok(Some((
+                                message_body(message, "body")?,
+                                message_more_body(message)?,
+                            )))
+                        });
+                        match chunk {
+                            Ok(Some((chunk, true))) => return Some((Ok(chunk), Some(call))),
+                            Ok(Some((chunk, false))) => return Some((Ok(chunk), None)),
+                            Ok(None) => continue,
+                            Err(err) => return Some((Err(err), None)),
+                        }
+                    }
+                    Next::Returned(Ok(())) => return None,
+                    Next::Returned(Err(err)) => return Some((Err(err), None)),
+                }
+            }
+        });
+
+        let mut response = Response::new(boxed(Body::wrap_stream(body)));
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        response
+    }
+
+    fn internal_error(&self, err: PyErr) -> Response<BoxBody> {
+        tracing::error!(path = self.path(), error = ?rich_py_err(err), "mounted asgi application failed");
+        let mut response = Response::new(boxed(Body::empty()));
+        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        response
+    }
+}
+
+impl fmt::Debug for AsgiMount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsgiMount")
+            .field("path", &self.inner.path)
+            .field("app", &self.inner.app)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sends the body of a request to an application as `http.request` events.
+async fn send_body(mut body: Body, receive: mpsc::Sender<PyObject>) {
+    loop {
+        let (chunk, more_body) = match body.data().await {
+            Some(Ok(chunk)) => (chunk, true),
+            Some(Err(err)) => {
+                // The application receives `http.disconnect` once the response is complete.
+                tracing::debug!(error = %err, "unable to read request body for mounted asgi application");
+                return;
+            }
+            None => (Bytes::new(), false),
+        };
+        let message = Python::with_gil(|py| {
+            message(
+                py,
+                "http.request",
+                vec![
+                    ("body", PyBytes::new(py, &chunk).into()),
+                    ("more_body", more_body.into_py(py)),
+                ],
+            )
+        });
+        if receive.send(message).await.is_err() || !more_body {
+            return;
+        }
+    }
+}
+
+/// Returns whether the Smithy router found no operation for the request of `response`.
+fn is_unknown_operation(response: &Response<BoxBody>) -> bool {
+    response.status() == StatusCode::NOT_FOUND
+        && response
+            .extensions()
+            .get::<RuntimeErrorExtension>()
+            .is_some_and(|extension| extension.as_str() == "UnknownOperationException")
+}
+
+/// Python callable that shuts down mounted applications, awaited by the worker signal handlers.
+#[pyclass]
+pub(crate) struct ShutdownMounts {
+    mounts: Vec<AsgiMount>,
+}
+
+impl ShutdownMounts {
+    pub(crate) fn new(mounts: Vec<AsgiMount>) -> Self {
+        Self { mounts }
+    }
+}
+
+#[pymethods]
+impl ShutdownMounts {
+    fn __call__<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let mounts = self.mounts.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            for mount in mounts {
+                if let Err(err) = mount.shutdown().await {
+                    tracing::error!(path = mount.path(), error = ?rich_py_err(err), "unable to shut down mounted asgi application");
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// A [Layer] routing the requests that don't match any operation to mounted ASGI applications.
+#[derive(Debug, Clone)]
+pub struct AsgiFallbackLayer {
+    mounts: Arc<[AsgiMount]>,
+    locals: TaskLocals,
+}
+
+impl AsgiFallbackLayer {
+    /// Routes requests to `mounts`, which are run on the event loop of `locals`.
+    ///
+    /// Requests are routed to the mount with the longest matching path.
+    pub fn new(mut mounts: Vec<AsgiMount>, locals: TaskLocals) -> Self {
+        mounts.sort_by_key(|mount| std::cmp::Reverse(mount.path().len()));
+        Self {
+            mounts: mounts.into(),
+            locals,
+        }
+    }
+}
+
+impl<S> Layer<S> for AsgiFallbackLayer {
+    type Service = AsgiFallback<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AsgiFallback {
+            inner,
+            mounts: self.mounts.clone(),
+            locals: self.locals.clone(),
+        }
+    }
+}
+
+/// A middleware [Service] routing the requests that don't match any operation to mounted ASGI
+/// applications. See [AsgiFallbackLayer].
+///
+/// The request body is only given to a mounted application if the inner service didn't read it,
+/// which is the case when routing fails.
+#[derive(Debug, Clone)]
+pub struct AsgiFallback<S> {
+    inner: S,
+    mounts: Arc<[AsgiMount]>,
+    locals: TaskLocals,
+}
+
+impl<S> Service<Request<Body>> for AsgiFallback<S>
+where
+    S: Service<Request<Body>, Response = Response<BoxBody>, Error = Infallible>,
+    S::Future: Send + 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let Some(mount) = self
+            .mounts
+            .iter()
+            .find(|mount| mount.matches(request.uri().path()))
+            .cloned()
+        else {
+            return Box::pin(self.inner.call(request));
+        };
+
+        let (parts, body) = request.into_parts();
+        let head = RequestHead::new(&parts);
+        // The body is handed to the inner service lazily, so that it can be taken back if the
+        // inner service doesn't read it.
+        let body = Arc::new(parking_lot::Mutex::new(Some(body)));
+        let lazy_body = {
+            let body = body.clone();
+            stream::once(async move { body.lock().take().unwrap_or_else(Body::empty) }).flatten()
+        };
+        let future = self
+            .inner
+            .call(Request::from_parts(parts, Body::wrap_stream(lazy_body)));
+        let locals = self.locals.clone();
+
+        Box::pin(async move {
+            let response = future.await?;
+            if !is_unknown_operation(&response) {
+                return Ok(response);
+            }
+            let Some(body) = body.lock().take() else {
+                return Ok(response);
+            };
+            Ok(mount.call(&locals, head, body).await)
+        })
+    }
+}
@@ -17,6 +17,7 @@
 //!
 //! [PyO3]: https://pyo3.rs/
 
+pub mod asgi;
 pub mod context;
 mod error;
 pub mod lambda;
@@ -28,12 +29,16 @@ pub mod tls;
 pub mod types;
 mod util;
 
+#[doc(inline)]
+pub use asgi::{AsgiMount, PyAsgiApp};
 #[doc(inline)]
 pub use error::{PyError, PyMiddlewareException};
 #[doc(inline)]
 pub use logging::{py_tracing_event, PyTracingHandler};
 #[doc(inline)]
-pub use middleware::{PyMiddlewareHandler, PyMiddlewareLayer, PyRequest, PyResponse};
+pub use middleware::{
+    PyMiddlewareHandler, PyMiddlewareLayer, PyMiddlewares, PyNativeLayer, PyRequest, PyResponse,
+};
 #[doc(inline)]
 pub use server::{PyApp, PyHandler};
 #[doc(inline)]
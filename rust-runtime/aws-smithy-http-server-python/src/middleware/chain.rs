@@ -0,0 +1,166 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! The middlewares registered on a Python application, applied to every operation or scoped to
+//! some operations.
+
+use std::{convert::Infallible, sync::Arc};
+
+use aws_smithy_http_server::{
+    body::{Body, BoxBody},
+    operation::OperationShape,
+    plugin::{HttpMarker, Plugin},
+    response::IntoResponse,
+    service::ServiceShape,
+    shape_id::ShapeId,
+};
+use http::{Request, Response};
+use pyo3_asyncio::TaskLocals;
+use tower::{util::BoxCloneService, Layer, Service};
+
+use super::{PyMiddlewareHandler, PyMiddlewareLayer, PyNativeLayer};
+use crate::PyMiddlewareException;
+
+// A `BoxCloneService` with default `Request`, `Response` and `Error`.
+type BoxedService = BoxCloneService<Request<Body>, Response<BoxBody>, Infallible>;
+
+/// A middleware registered on a Python application.
+#[derive(Debug, Clone)]
+pub enum PyMiddleware {
+    /// A Python function, which runs holding the GIL.
+    Python(PyMiddlewareHandler),
+    /// A native Rust layer, which runs without holding the GIL.
+    Native(PyNativeLayer),
+}
+
+impl PyMiddleware {
+    fn layer<P>(&self, service: BoxedService, locals: &TaskLocals) -> BoxedService
+    where
+        PyMiddlewareException: IntoResponse<P>,
+    {
+        match self {
+            PyMiddleware::Python(handler) => {
+                tracing::trace!(name = &handler.name, "adding python middleware");
+                let layer = PyMiddlewareLayer::<P>::new(handler.clone(), locals.clone());
+                BoxCloneService::new(layer.layer(service))
+            }
+            PyMiddleware::Native(layer) => {
+                tracing::trace!(name = layer.name(), "adding native middleware");
+                layer.layer(service)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ScopedMiddleware {
+    middleware: PyMiddleware,
+    // `None` for middlewares applied to every operation.
+    operations: Option<Vec<ShapeId>>,
+}
+
+/// The chain of middlewares registered on a Python application.
+///
+/// Middlewares run in the order they are registered. Middlewares registered for every operation
+/// run before routing, so they also run for requests that don't match any operation, while
+/// middlewares scoped to some operations run after routing, in the same way as
+/// [`Scoped`](aws_smithy_http_server::plugin::Scoped) plugins. As a consequence, middlewares
+/// registered for every operation run before the scoped ones.
+#[derive(Debug, Clone, Default)]
+pub struct PyMiddlewares {
+    middlewares: Vec<ScopedMiddleware>,
+}
+
+impl PyMiddlewares {
+    /// Creates an empty chain of middlewares.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `middleware` for `operations`, or for every operation if `operations` is `None`.
+    pub fn push(&mut self, middleware: PyMiddleware, operations: Option<Vec<ShapeId>>) {
+        self.middlewares.push(ScopedMiddleware {
+            middleware,
+            operations,
+        });
+    }
+
+    /// Returns `true` if no middleware is registered.
+    pub fn is_empty(&self) -> bool {
+        self.middlewares.is_empty()
+    }
+
+    /// Applies the middlewares registered for every operation to `service`, the service of the
+    /// whole application.
+    pub fn apply<P>(&self, service: BoxedService, locals: &TaskLocals) -> BoxedService
+    where
+        PyMiddlewareException: IntoResponse<P>,
+    {
+        tracing::trace!("adding middlewares to rust python router");
+        // Wrap in reverse order, so that middlewares run in the order they are registered.
+        self.middlewares
+            .iter()
+            .rev()
+            .filter(|scoped| scoped.operations.is_none())
+            .fold(service, |service, scoped| {
+                scoped.middleware.layer::<P>(service, locals)
+            })
+    }
+
+    /// Returns the HTTP [Plugin] applying the middlewares scoped to some operations to those
+    /// operations.
+    pub fn plugin(&self, locals: TaskLocals) -> PyMiddlewarePlugin {
+        PyMiddlewarePlugin {
+            middlewares: self
+                .middlewares
+                .iter()
+                .filter(|scoped| scoped.operations.is_some())
+                .cloned()
+                .collect(),
+            locals,
+        }
+    }
+}
+
+/// An HTTP [Plugin] applying the middlewares of a [PyMiddlewares] chain that are scoped to some
+/// operations. See [PyMiddlewares::plugin].
+#[derive(Debug, Clone)]
+pub struct PyMiddlewarePlugin {
+    middlewares: Arc<[ScopedMiddleware]>,
+    locals: TaskLocals,
+}
+
+impl<Ser, Op, T> Plugin<Ser, Op, T> for PyMiddlewarePlugin
+where
+    Ser: ServiceShape,
+    Op: OperationShape,
+    T: Service<Request<Body>, Response = Response<BoxBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    T::Future: Send + 'static,
+    PyMiddlewareException: IntoResponse<Ser::Protocol>,
+{
+    type Output = BoxedService;
+
+    fn apply(&self, inner: T) -> Self::Output {
+        self.middlewares
+            .iter()
+            .rev()
+            .filter(|scoped| {
+                scoped
+                    .operations
+                    .as_ref()
+                    .is_some_and(|operations| operations.contains(&Op::ID))
+            })
+            .fold(BoxCloneService::new(inner), |service, scoped| {
+                scoped
+                    .middleware
+                    .layer::<Ser::Protocol>(service, &self.locals)
+            })
+    }
+}
+
+impl HttpMarker for PyMiddlewarePlugin {}
@@ -3,7 +3,11 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-//! Schedule pure-Python middlewares as [tower::Layer]s.
+//! Schedule pure-Python middlewares, and native Rust layers, as [tower::Layer]s.
+//!
+//! Middlewares are registered on a [PyMiddlewares] chain, either for every operation or for some
+//! operations only. Native Rust layers, which don't hold the GIL, can be exported to Python by
+//! extension modules with [PyNativeLayer].
 //!
 //! # Moving data from Rust to Python and back
 //!
@@ -72,16 +76,20 @@
 //! You can see this pattern in [PyRequest], [PyResponse] and the others.
 //!
 
+mod chain;
 mod error;
 mod handler;
 mod header_map;
 mod layer;
+mod native;
 mod request;
 mod response;
 
+pub use self::chain::{PyMiddleware, PyMiddlewarePlugin, PyMiddlewares};
 pub use self::error::PyMiddlewareError;
 pub use self::handler::PyMiddlewareHandler;
 pub use self::header_map::PyHeaderMap;
 pub use self::layer::PyMiddlewareLayer;
+pub use self::native::PyNativeLayer;
 pub use self::request::PyRequest;
 pub use self::response::PyResponse;
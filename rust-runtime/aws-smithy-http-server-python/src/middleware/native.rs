@@ -0,0 +1,160 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Native Rust [tower::Layer]s exported to Python by extension modules.
+
+use std::{
+    any::TypeId,
+    collections::hash_map::DefaultHasher,
+    convert::Infallible,
+    ffi::{CStr, CString},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, OnceLock},
+};
+
+use aws_smithy_http_server::body::{Body, BoxBody};
+use http::{Request, Response};
+use pyo3::{exceptions::PyTypeError, prelude::*, types::PyCapsule};
+use tower::{util::BoxCloneService, Layer, Service};
+
+include!(concat!(env!("OUT_DIR"), "/build_env.rs"));
+
+// A `BoxCloneService` with default `Request`, `Response` and `Error`.
+type BoxedService = BoxCloneService<Request<Body>, Response<BoxBody>, Infallible>;
+
+/// Returns the name of the capsules holding a [PyNativeLayer].
+///
+/// Layers can only be shared between an extension module and an application built with the same
+/// Rust compiler, against the same versions of this crate and of its dependencies, with the same
+/// features. The name contains the version of this crate, the version of the compiler and a
+/// fingerprint of the [TypeId]s of the types crossing the capsule, which depend on the versions and
+/// features of the crates defining them, so that capsules exported by incompatible builds are
+/// rejected. This is a best-effort check: it can't tell every incompatible build apart.
+fn capsule_name() -> &'static CStr {
+    static CAPSULE_NAME: OnceLock<CString> = OnceLock::new();
+    CAPSULE_NAME.get_or_init(|| {
+        let mut hasher = DefaultHasher::new();
+        TypeId::of::<PyNativeLayer>().hash(&mut hasher);
+        TypeId::of::<BoxedService>().hash(&mut hasher);
+        TypeId::of::<<BoxedService as Service<Request<Body>>>::Future>().hash(&mut hasher);
+        let name = format!(
+            "aws_smithy_http_server_python.NativeLayer@{}/{}/{:016x}",
+            env!("CARGO_PKG_VERSION"),
+            RUSTC_VERSION,
+            hasher.finish()
+        );
+        CString::new(name).expect("capsule name has no nul bytes")
+    })
+}
+
+/// A native Rust [tower::Layer] that can be registered on a Python application with
+/// `App.layer()`, so that it runs without holding the GIL.
+///
+/// Native layers are exported to Python from an extension module as capsules:
+///
+/// ```no_run
+/// use aws_smithy_http_server_python::middleware::PyNativeLayer;
+/// use pyo3::prelude::*;
+/// use tower::layer::util::Identity;
+///
+/// #[pyfunction]
+/// fn auth_layer(py: Python) -> PyResult<PyObject> {
+///     // Any `tower::Layer` producing an infallible service.
+///     PyNativeLayer::new("auth", Identity::new()).into_capsule(py)
+/// }
+/// ```
+///
+/// ```python
+/// app.layer(my_extension.auth_layer(), operations=["get_pokemon_species"])
+/// ```
+///
+/// The layer is passed to the application as a Rust value, so the extension module must be built
+/// with the same Rust compiler as the application, against the same versions of this crate and of
+/// its dependencies, with the same features: see [PyNativeLayer::from_capsule]. The extension
+/// module also has its own copy of the statics of its dependencies, including those of Tokio, so
+/// the services of the layer can't use APIs that need to run inside a Tokio runtime, like
+/// `tokio::spawn` or `tokio::time::sleep`.
+#[derive(Clone)]
+pub struct PyNativeLayer {
+    name: String,
+    layer: Arc<dyn Fn(BoxedService) -> BoxedService + Send + Sync>,
+}
+
+impl PyNativeLayer {
+    /// Creates a native layer named `name`, which is used in logs.
+    pub fn new<L>(name: impl Into<String>, layer: L) -> Self
+    where
+        L: Layer<BoxedService> + Send + Sync + 'static,
+        L::Service: Service<Request<Body>, Response = Response<BoxBody>, Error = Infallible>
+            + Clone
+            + Send
+            + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + 'static,
+    {
+        Self {
+            name: name.into(),
+            layer: Arc::new(move |service| BoxCloneService::new(layer.layer(service))),
+        }
+    }
+
+    /// Returns the name of the layer.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Wraps the layer in a Python capsule, to be returned to Python by an extension module.
+    pub fn into_capsule(self, py: Python) -> PyResult<PyObject> {
+        Ok(PyCapsule::new(py, self, Some(capsule_name().to_owned()))?.into())
+    }
+
+    /// Takes a copy of the layer held by a Python capsule created with
+    /// [PyNativeLayer::into_capsule].
+    ///
+    /// Returns an error if `capsule` isn't a capsule, or if its name shows that it was created by
+    /// an incompatible build of this crate.
+    ///
+    /// # Safety
+    ///
+    /// The capsule must have been created by [PyNativeLayer::into_capsule], in an extension
+    /// module built with the same Rust compiler as the caller, against the same versions of this
+    /// crate and of its dependencies, with the same features. Rust doesn't have a stable ABI, so
+    /// using a layer created by any other build is undefined behavior. The name of the capsule
+    /// is checked, but the check can't detect every incompatible build.
+    pub unsafe fn from_capsule(capsule: &PyAny) -> PyResult<Self> {
+        let capsule: &PyCapsule = capsule.downcast().map_err(|_| {
+            PyTypeError::new_err(
+                "native layers must be capsules created by `PyNativeLayer::into_capsule`",
+            )
+        })?;
+        let expected = capsule_name();
+        match capsule.name()? {
+            Some(name) if name == expected => {
+                // SAFETY: the caller guarantees that capsules with this name were created by
+                // `into_capsule`, by a compatible build of this crate, so they hold a
+                // `PyNativeLayer` with the same layout.
+                let layer: &PyNativeLayer = unsafe { capsule.reference() };
+                Ok(layer.clone())
+            }
+            name => Err(PyTypeError::new_err(format!(
+                "expected a `{}` capsule, got `{}`; the extension module must be built with the same Rust compiler, against the same versions of `aws-smithy-http-server-python` and of its dependencies",
+                expected.to_string_lossy(),
+                name.map(|name| name.to_string_lossy()).unwrap_or_default()
+            ))),
+        }
+    }
+
+    pub(crate) fn layer(&self, service: BoxedService) -> BoxedService {
+        (self.layer)(service)
+    }
+}
+
+impl fmt::Debug for PyNativeLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PyNativeLayer")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
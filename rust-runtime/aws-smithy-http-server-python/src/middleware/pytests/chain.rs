@@ -0,0 +1,172 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::convert::Infallible;
+
+use aws_smithy_http_server::{
+    body::{to_boxed, Body, BoxBody},
+    operation::OperationShape,
+    plugin::Plugin,
+    protocol::rest_json_1::RestJson1,
+    service::ServiceShape,
+    shape_id::ShapeId,
+};
+use aws_smithy_http_server_python::{
+    middleware::{PyMiddleware, PyMiddlewareHandler, PyNativeLayer},
+    PyMiddlewares, PyResponse,
+};
+use http::{HeaderValue, Request, Response};
+use pretty_assertions::assert_eq;
+use pyo3::{
+    exceptions::PyTypeError,
+    prelude::*,
+    types::{PyDict, PyString},
+};
+use pyo3_asyncio::TaskLocals;
+use tower::{service_fn, util::MapResponseLayer, ServiceExt};
+
+struct Pokedex;
+
+impl ServiceShape for Pokedex {
+    const ID: ShapeId = ShapeId::new("com.example#Pokedex", "com.example", "Pokedex");
+    const VERSION: Option<&'static str> = None;
+    type Protocol = RestJson1;
+    type Operations = ();
+}
+
+struct GetPokemon;
+
+impl OperationShape for GetPokemon {
+    const ID: ShapeId = ShapeId::new("com.example#GetPokemon", "com.example", "GetPokemon");
+    type Input = ();
+    type Output = ();
+    type Error = ();
+}
+
+struct GetStorage;
+
+impl OperationShape for GetStorage {
+    const ID: ShapeId = ShapeId::new("com.example#GetStorage", "com.example", "GetStorage");
+    type Input = ();
+    type Output = ();
+    type Error = ();
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn scoping_middlewares_to_operations() -> PyResult<()> {
+    let mut middlewares = PyMiddlewares::new();
+    middlewares.push(
+        PyMiddleware::Python(py_handler(
+            r#"
+async def middleware(request, next):
+    response = await next(request)
+    response.headers["X-Global"] = "yes"
+    return response
+"#,
+        )),
+        None,
+    );
+    middlewares.push(
+        PyMiddleware::Python(py_handler(
+            r#"
+def middleware(request, next):
+    return Response(200, {}, b"hello client from Python")
+"#,
+        )),
+        Some(vec![GetPokemon::ID]),
+    );
+    middlewares.push(
+        PyMiddleware::Native(native_layer("X-Native")),
+        Some(vec![GetStorage::ID]),
+    );
+
+    let locals = task_locals();
+    let plugin = middlewares.plugin(locals.clone());
+    let get_pokemon = Plugin::<Pokedex, GetPokemon, _>::apply(&plugin, service_fn(inner));
+    let get_storage = Plugin::<Pokedex, GetStorage, _>::apply(&plugin, service_fn(inner));
+
+    let response = get_pokemon.oneshot(simple_request()).await.unwrap();
+    assert_eq!(response.headers().get("X-Native"), None);
+    assert_body(response, "hello client from Python").await;
+
+    let response = get_storage.clone().oneshot(simple_request()).await.unwrap();
+    assert_eq!(response.headers()["X-Native"], "yes");
+    assert_eq!(response.headers().get("X-Global"), None);
+    assert_body(response, "hello client from operation").await;
+
+    // Middlewares registered for every operation wrap the whole application.
+    let app = middlewares.apply::<RestJson1>(get_storage.boxed_clone(), &locals);
+    let response = app.oneshot(simple_request()).await.unwrap();
+    assert_eq!(response.headers()["X-Global"], "yes");
+    assert_eq!(response.headers()["X-Native"], "yes");
+    assert_body(response, "hello client from operation").await;
+
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn native_layers_are_passed_as_capsules() -> PyResult<()> {
+    Python::with_gil(|py| {
+        let capsule = native_layer("X-Native").into_capsule(py)?;
+        // SAFETY: the capsule was created by this build of the crate.
+        let layer = unsafe { PyNativeLayer::from_capsule(capsule.as_ref(py)) }?;
+        assert_eq!(layer.name(), "X-Native");
+
+        let not_a_capsule = PyString::new(py, "not a layer");
+        // SAFETY: values that aren't capsules are rejected before being read.
+        let err = unsafe { PyNativeLayer::from_capsule(not_a_capsule) }.unwrap_err();
+        assert!(err.is_instance_of::<PyTypeError>(py));
+        Ok(())
+    })
+}
+
+async fn inner(_request: Request<Body>) -> Result<Response<BoxBody>, Infallible> {
+    Ok(Response::new(to_boxed("hello client from operation")))
+}
+
+fn native_layer(header: &'static str) -> PyNativeLayer {
+    PyNativeLayer::new(
+        header,
+        MapResponseLayer::new(move |mut response: Response<BoxBody>| {
+            response
+                .headers_mut()
+                .insert(header, HeaderValue::from_static("yes"));
+            response
+        }),
+    )
+}
+
+async fn assert_body(response: Response<BoxBody>, eq: &str) {
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, eq);
+}
+
+fn simple_request() -> Request<Body> {
+    Request::builder()
+        .body(Body::from("hello server"))
+        .expect("could not create request")
+}
+
+fn task_locals() -> TaskLocals {
+    Python::with_gil(|py| {
+        Ok::<_, PyErr>(TaskLocals::new(pyo3_asyncio::tokio::get_current_loop(py)?))
+    })
+    .unwrap()
+}
+
+fn py_handler(code: &str) -> PyMiddlewareHandler {
+    Python::with_gil(|py| {
+        let globals = PyModule::import(py, "__main__")?.dict();
+        globals.set_item("Response", py.get_type::<PyResponse>())?;
+        let locals = PyDict::new(py);
+        py.run(code, Some(globals), Some(locals))?;
+        let handler = locals
+            .get_item("middleware")
+            .expect("your handler must be named `middleware`")
+            .into();
+        PyMiddlewareHandler::new(py, handler)
+    })
+    .unwrap()
+}
@@ -8,6 +8,7 @@ async fn main() -> pyo3::PyResult<()> {
     pyo3_asyncio::testing::main().await
 }
 
+mod chain;
 mod layer;
 mod request;
 mod response;
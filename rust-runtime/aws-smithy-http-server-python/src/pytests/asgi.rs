@@ -0,0 +1,194 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use aws_smithy_http_server::{
+    body::{to_boxed, Body, BoxBody},
+    extension::RuntimeErrorExtension,
+};
+use aws_smithy_http_server_python::{AsgiMount, PyApp, PyAsgiApp, PyHandler};
+use http::{Request, Response, StatusCode};
+use parking_lot::Mutex;
+use pyo3::{prelude::*, types::PyDict};
+use tower::{service_fn, util::BoxCloneService};
+
+/// An application with a single operation, `POST /echo`, that echoes the request body.
+#[pyclass]
+#[derive(Default)]
+struct EchoApp {
+    workers: Mutex<Vec<PyObject>>,
+    context: Option<PyObject>,
+    handlers: HashMap<String, PyHandler>,
+    asgi_mounts: Vec<AsgiMount>,
+}
+
+impl Clone for EchoApp {
+    fn clone(&self) -> Self {
+        Self {
+            workers: Mutex::new(vec![]),
+            context: self.context.clone(),
+            handlers: self.handlers.clone(),
+            asgi_mounts: self.asgi_mounts.clone(),
+        }
+    }
+}
+
+impl PyApp for EchoApp {
+    fn workers(&self) -> &Mutex<Vec<PyObject>> {
+        &self.workers
+    }
+
+    fn context(&self) -> &Option<PyObject> {
+        &self.context
+    }
+
+    fn handlers(&mut self) -> &mut HashMap<String, PyHandler> {
+        &mut self.handlers
+    }
+
+    fn build_service(
+        &mut self,
+        _event_loop: &PyAny,
+    ) -> PyResult<BoxCloneService<Request<Body>, Response<BoxBody>, Infallible>> {
+        Ok(BoxCloneService::new(service_fn(echo)))
+    }
+
+    fn asgi_mounts(&self) -> &[AsgiMount] {
+        &self.asgi_mounts
+    }
+}
+
+async fn echo(request: Request<Body>) -> Result<Response<BoxBody>, Infallible> {
+    if request.uri().path() != "/echo" {
+        let mut response = Response::new(to_boxed(""));
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        response.extensions_mut().insert(RuntimeErrorExtension::new(
+            "UnknownOperationException".to_string(),
+        ));
+        return Ok(response);
+    }
+    let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+    Ok(Response::new(to_boxed(body)))
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn serving_application_with_mounted_asgi_application() -> PyResult<()> {
+    let future = Python::with_gil(|py| {
+        let globals = PyDict::new(py);
+        py.run(
+            r#"
+import asyncio
+
+admin_events = []
+
+async def admin(scope, receive, send):
+    if scope["type"] == "lifespan":
+        while True:
+            event = await receive()
+            admin_events.append(event["type"])
+            if event["type"] == "lifespan.startup":
+                scope["state"]["greeting"] = "hello " + scope["state"]["context"]
+                await send({"type": "lifespan.startup.complete"})
+            elif event["type"] == "lifespan.shutdown":
+                await send({"type": "lifespan.shutdown.complete"})
+                return
+
+    body = b""
+    while True:
+        event = await receive()
+        body += event.get("body", b"")
+        if not event.get("more_body"):
+            break
+    await send({
+        "type": "http.response.start",
+        "status": 201,
+        "headers": [(b"x-root-path", scope["root_path"].encode())],
+    })
+    await send({"type": "http.response.body", "body": scope["path"].encode(), "more_body": True})
+    await send({"type": "http.response.body", "body": b" " + body + b" " + scope["state"]["greeting"].encode()})
+
+async def request(app, path, chunks):
+    events = [
+        {"type": "http.request", "body": chunk, "more_body": idx < len(chunks) - 1}
+        for idx, chunk in enumerate(chunks)
+    ]
+    sent = []
+
+    async def receive():
+        return events.pop(0) if events else {"type": "http.disconnect"}
+
+    async def send(message):
+        sent.append(message)
+
+    scope = {
+        "type": "http",
+        "asgi": {"version": "3.0"},
+        "http_version": "1.1",
+        "method": "POST",
+        "path": path,
+        "raw_path": path.encode(),
+        "query_string": b"",
+        "root_path": "",
+        "headers": [(b"content-type", b"application/octet-stream")],
+    }
+    await app(scope, receive, send)
+    assert sent[0]["type"] == "http.response.start"
+    assert all(message["type"] == "http.response.body" for message in sent[1:])
+    assert not sent[-1].get("more_body")
+    headers = {name: value for name, value in sent[0]["headers"]}
+    return sent[0]["status"], headers, b"".join(message["body"] for message in sent[1:])
+
+async def main(app):
+    lifespan_events = asyncio.Queue()
+    lifespan_sent = []
+
+    async def receive():
+        return await lifespan_events.get()
+
+    async def send(message):
+        lifespan_sent.append(message["type"])
+
+    lifespan = asyncio.ensure_future(app({"type": "lifespan", "asgi": {"version": "3.0"}, "state": {}}, receive, send))
+    await lifespan_events.put({"type": "lifespan.startup"})
+    while not lifespan_sent:
+        await asyncio.sleep(0.01)
+    assert lifespan_sent == ["lifespan.startup.complete"]
+    assert admin_events == ["lifespan.startup"]
+
+    status, _, body = await request(app, "/echo", [b"hello ", b"world"])
+    assert (status, body) == (200, b"hello world")
+
+    status, headers, body = await request(app, "/admin/users", [b"a", b"b"])
+    assert (status, headers[b"x-root-path"], body) == (201, b"/admin", b"/admin/users ab hello ctx")
+
+    status, _, _ = await request(app, "/missing", [b""])
+    assert status == 404
+
+    await lifespan_events.put({"type": "lifespan.shutdown"})
+    await lifespan
+    assert lifespan_sent == ["lifespan.startup.complete", "lifespan.shutdown.complete"]
+    assert admin_events == ["lifespan.startup", "lifespan.shutdown"]
+"#,
+            Some(globals),
+            None,
+        )?;
+
+        let app = EchoApp {
+            context: Some("ctx".into_py(py)),
+            asgi_mounts: vec![AsgiMount::new(
+                "/admin/",
+                globals.get_item("admin").unwrap().into(),
+            )],
+            ..Default::default()
+        };
+        let asgi_app = Py::new(py, PyAsgiApp::new(app))?;
+        let main = globals.get_item("main").unwrap();
+        pyo3_asyncio::tokio::into_future(main.call1((asgi_app,))?)
+    })?;
+    future.await?;
+    Ok(())
+}
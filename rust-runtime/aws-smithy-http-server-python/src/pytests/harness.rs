@@ -8,4 +8,5 @@ async fn main() -> pyo3::PyResult<()> {
     pyo3_asyncio::testing::main().await
 }
 
+mod asgi;
 mod bytestream;
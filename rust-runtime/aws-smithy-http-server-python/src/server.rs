@@ -26,6 +26,7 @@ use tokio_rustls::TlsAcceptor;
 use tower::{util::BoxCloneService, ServiceBuilder};
 
 use crate::{
+    asgi::{mount::ShutdownMounts, AsgiFallbackLayer, AsgiMount},
     context::{layer::AddPyContextLayer, PyContext},
     tls::{listener::Listener as TlsListener, PyTlsConfig},
     util::{error::rich_py_err, func_metadata},
@@ -55,7 +56,7 @@ impl Deref for PyHandler {
 }
 
 // A `BoxCloneService` with default `Request`, `Response` and `Error`.
-type Service = BoxCloneService<Request<Body>, Response<BoxBody>, Infallible>;
+pub(crate) type Service = BoxCloneService<Request<Body>, Response<BoxBody>, Infallible>;
 
 /// Trait defining a Python application.
 ///
@@ -85,6 +86,11 @@ pub trait PyApp: Clone + pyo3::IntoPy<PyObject> {
     /// Build the app's `Service` using given `event_loop`.
     fn build_service(&mut self, event_loop: &pyo3::PyAny) -> pyo3::PyResult<Service>;
 
+    /// ASGI applications mounted on the paths that don't match any operation.
+    fn asgi_mounts(&self) -> &[AsgiMount] {
+        &[]
+    }
+
     /// Handle the graceful termination of Python workers by looping through all the
     /// active workers and calling `terminate()` on them. If termination fails, this
     /// method will try to `kill()` any failed worker.
@@ -176,8 +182,15 @@ pub trait PyApp: Clone + pyo3::IntoPy<PyObject> {
 
     /// Register and handle termination of all the tasks on the Python asynchronous event loop.
     /// We only register SIGQUIT and SIGINT since the main signal handling is done by Rust.
+    ///
+    /// Mounted ASGI applications are sent `lifespan.shutdown` before the tasks are cancelled.
     fn register_python_signals(&self, py: Python, event_loop: PyObject) -> PyResult<()> {
-        let locals = [("event_loop", event_loop)].into_py_dict(py);
+        let shutdown_asgi_mounts = ShutdownMounts::new(self.asgi_mounts().to_vec()).into_py(py);
+        let locals = [
+            ("event_loop", event_loop),
+            ("shutdown_asgi_mounts", shutdown_asgi_mounts),
+        ]
+        .into_py_dict(py);
         py.run(
             r#"
 import asyncio
@@ -185,11 +198,12 @@ import logging
 import functools
 import signal
 
-async def shutdown(sig, event_loop):
+async def shutdown(sig, event_loop, shutdown_asgi_mounts):
     # reimport asyncio and logging to be sure they are available when
     # this handler runs on signal catching.
     import asyncio
     import logging
+    await shutdown_asgi_mounts()
     logging.info(f"Caught signal {sig.name}, cancelling tasks registered on this loop")
     tasks = [task for task in asyncio.all_tasks() if task is not
              asyncio.current_task()]
@@ -199,9 +213,9 @@ async def shutdown(sig, event_loop):
     event_loop.stop()
 
 event_loop.add_signal_handler(signal.SIGTERM,
-    functools.partial(asyncio.ensure_future, shutdown(signal.SIGTERM, event_loop)))
+    functools.partial(asyncio.ensure_future, shutdown(signal.SIGTERM, event_loop, shutdown_asgi_mounts)))
 event_loop.add_signal_handler(signal.SIGINT,
-    functools.partial(asyncio.ensure_future, shutdown(signal.SIGINT, event_loop)))
+    functools.partial(asyncio.ensure_future, shutdown(signal.SIGINT, event_loop, shutdown_asgi_mounts)))
 "#,
             None,
             Some(locals),
@@ -483,17 +497,33 @@ event_loop.add_signal_handler(signal.SIGINT,
     }
 
     // Builds the `Service` and adds necessary layers to it.
+    //
+    // Mounted ASGI applications are started along with the context setup, and receive the context
+    // in their lifespan state.
     fn build_and_configure_service(
         &mut self,
         py: Python,
         event_loop: &pyo3::PyAny,
     ) -> pyo3::PyResult<Service> {
         let service = self.build_service(event_loop)?;
-        let context = PyContext::new(self.context().clone().unwrap_or_else(|| py.None()))?;
+        let context_object = self.context().clone().unwrap_or_else(|| py.None());
+        let context = PyContext::new(context_object.clone_ref(py))?;
         let service = ServiceBuilder::new()
             .boxed_clone()
             .layer(AddPyContextLayer::new(context))
             .service(service);
+        let mounts = self.asgi_mounts().to_vec();
+        if mounts.is_empty() {
+            return Ok(service);
+        }
+        let locals = pyo3_asyncio::TaskLocals::new(event_loop);
+        for mount in &mounts {
+            mount.spawn_startup(locals.clone(), context_object.clone_ref(py));
+        }
+        let service = ServiceBuilder::new()
+            .boxed_clone()
+            .layer(AsgiFallbackLayer::new(mounts, locals))
+            .service(service);
         Ok(service)
     }
 }
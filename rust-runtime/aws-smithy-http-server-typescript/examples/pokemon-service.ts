@@ -3,9 +3,6 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
-import cluster from "cluster";
-import { cpus } from "os";
-
 import {
     App,
     TsHandlers,
@@ -14,13 +11,17 @@ import {
     Language,
     DoNothingInput,
     DoNothingOutput,
-    TsSocket,
     CheckHealthOutput,
     CheckHealthInput,
     GetServerStatisticsInput,
     GetServerStatisticsOutput,
 } from ".";
 
+// The context passed to every handler, shared by the requests served by a worker.
+interface Context {
+    stats: { callsCount: number };
+}
+
 class HandlerImpl implements TsHandlers {
     // TODO: implement
     async doNothing(input: DoNothingInput): Promise<DoNothingOutput> {
@@ -30,15 +31,17 @@ class HandlerImpl implements TsHandlers {
     async checkHealth(input: CheckHealthInput): Promise<CheckHealthOutput> {
         return {};
     }
-    // TODO: implement
     async getServerStatistics(
-        input: GetServerStatisticsInput
+        input: GetServerStatisticsInput,
+        context: Context,
     ): Promise<GetServerStatisticsOutput> {
-        return { callsCount: 0 };
+        return { callsCount: context.stats.callsCount };
     }
     async getPokemonSpecies(
         input: GetPokemonSpeciesInput,
+        context: Context,
     ): Promise<GetPokemonSpeciesOutput> {
+        context.stats.callsCount += 1;
         return {
             name: input.name,
             flavorTextEntries: [
@@ -67,25 +70,15 @@ class HandlerImpl implements TsHandlers {
     }
 }
 
-// Pass the handlers to the App.
-const app = new App(new HandlerImpl());
+// Pass the handlers and their context to the App.
+const context: Context = { stats: { callsCount: 0 } };
+const app = new App(new HandlerImpl(), context);
 // Start the app 🤘
-const numCPUs = cpus().length / 2;
-const address ="127.0.0.1";
+// The first call starts the workers and blocks to manage them, the workers
+// run this script again and return from this call to serve the requests.
+const address = "127.0.0.1";
 const port = 9090;
-const socket = new TsSocket(address, port);
-app.start(socket);
-
-// TODO: This part should be abstracted out and done directly in Rust.
-// We could take an optional number of workers and the socket as input
-// of the App.start() method.
-if (cluster.isPrimary) {
-  console.log(`Listening on ${address}:${port}`);
-    // Fork workers.
-    for (let i = 0; i < numCPUs; i++) {
-        cluster.fork();
-    }
-}
+app.run({ address, port, workers: 2 });
 
 process.on("unhandledRejection", err => {
     console.error("Unhandled")
@@ -0,0 +1,134 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Context passed to the Node.js handlers.
+//!
+//! The application can be given a context object, which is passed to every handler as its
+//! second argument:
+//!
+//! ```typescript
+//! const app = new App(handlers, { db: new Database() });
+//!
+//! class HandlerImpl implements TsHandlers {
+//!     async getPokemonSpecies(input: GetPokemonSpeciesInput, context: { db: Database }) {
+//!         return context.db.species(input.name);
+//!     }
+//! }
+//! ```
+//!
+//! Values that are scoped to a request are injected in a new object for each request, which
+//! inherits from the context object, so that handlers running concurrently never observe each
+//! other's values. Currently only the `lambdaContext` of the requests served on Lambda is
+//! injected.
+
+use std::convert::Infallible;
+
+use aws_smithy_http_server::request::FromParts;
+use http::request::Parts;
+use napi::{Env, JsFunction, JsUnknown, NapiRaw, NapiValue, ValueType};
+
+use crate::lambda::TsLambdaContext;
+
+/// Wraps a handler so that it receives the context, and always returns a promise, even if the
+/// handler throws synchronously.
+const WITH_CONTEXT: &str = r#"
+(handler, context) => async (input, lambdaContext) => {
+    if (lambdaContext !== null && lambdaContext !== undefined) {
+        context = Object.assign(Object.create(context ?? null), { lambdaContext });
+    }
+    return handler(input, context);
+}
+"#;
+
+/// Values scoped to a request, which are injected in the context passed to the Node.js handler.
+///
+/// The code generated operation handlers extract it from the request, and pass it to
+/// [TsHandler::call](crate::TsHandler::call).
+#[derive(Debug, Clone, Default)]
+pub struct TsRequestContext {
+    lambda: Option<TsLambdaContext>,
+}
+
+impl TsRequestContext {
+    /// The context of the Lambda invocation, if the request is served on Lambda.
+    pub fn lambda(&self) -> Option<&TsLambdaContext> {
+        self.lambda.as_ref()
+    }
+
+    pub(crate) fn into_lambda(self) -> Option<TsLambdaContext> {
+        self.lambda
+    }
+}
+
+impl<P> FromParts<P> for TsRequestContext {
+    type Rejection = Infallible;
+
+    fn from_parts(parts: &mut Parts) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            lambda: parts
+                .extensions
+                .get::<lambda_http::Context>()
+                .cloned()
+                .map(TsLambdaContext::new),
+        })
+    }
+}
+
+/// Wraps `handler` into a function taking the input and the optional [TsLambdaContext], which
+/// calls `handler` with the input and `context`, extended with the request-scoped values.
+pub(crate) fn with_context(
+    env: &Env,
+    handler: JsFunction,
+    context: Option<&JsUnknown>,
+) -> napi::Result<JsFunction> {
+    let wrap: JsFunction = env.run_script(WITH_CONTEXT)?;
+    let context = match context {
+        // SAFETY: the context is a valid value of this environment, borrowed for this call.
+        Some(context) => unsafe { JsUnknown::from_raw_unchecked(env.raw(), context.raw()) },
+        None => env.get_undefined()?.into_unknown(),
+    };
+    let context_type = context.get_type()?;
+    if !matches!(
+        context_type,
+        ValueType::Object | ValueType::Undefined | ValueType::Null
+    ) {
+        return Err(napi::Error::new(
+            napi::Status::InvalidArg,
+            format!("the context must be an object, got `{context_type}`"),
+        ));
+    }
+    let wrapped = wrap.call(None, &[handler.into_unknown(), context])?;
+    JsFunction::try_from(wrapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Request;
+    use lambda_http::lambda_runtime::Config;
+
+    use super::*;
+
+    #[test]
+    fn request_context_has_lambda_context_of_lambda_requests() {
+        let (mut parts, _) = Request::new(()).into_parts();
+        let request_context = <TsRequestContext as FromParts<()>>::from_parts(&mut parts).unwrap();
+        assert!(request_context.lambda().is_none());
+
+        let mut lambda_context = lambda_http::Context::default().with_config(&Config {
+            function_name: "my-fn".to_string(),
+            memory: 128,
+            version: "my-version".to_string(),
+            log_stream: "my-log-stream".to_string(),
+            log_group: "my-log-group".to_string(),
+        });
+        lambda_context.request_id = "my-id".to_string();
+        parts.extensions.insert(lambda_context);
+        let request_context = <TsRequestContext as FromParts<()>>::from_parts(&mut parts).unwrap();
+        let lambda = request_context.lambda().unwrap();
+        assert_eq!(lambda.request_id, "my-id");
+        assert_eq!(lambda.env_config.function_name, "my-fn");
+        assert_eq!(lambda.env_config.memory, 128);
+    }
+}
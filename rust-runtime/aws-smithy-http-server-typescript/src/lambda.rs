@@ -0,0 +1,126 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Typescript wrappers for Lambda related types.
+
+use std::collections::HashMap;
+
+use lambda_http::Context;
+use napi_derive::napi;
+
+/// AWS Mobile SDK client fields.
+#[derive(Debug, Clone)]
+#[napi(object, js_name = "ClientApplication")]
+pub struct TsClientApplication {
+    /// The mobile app installation id
+    pub installation_id: String,
+    /// The app title for the mobile app as registered with AWS' mobile services.
+    pub app_title: String,
+    /// The version name of the application as registered with AWS' mobile services.
+    pub app_version_name: String,
+    /// The app version code.
+    pub app_version_code: String,
+    /// The package name for the mobile application invoking the function
+    pub app_package_name: String,
+}
+
+/// Client context sent by the AWS Mobile SDK.
+#[derive(Debug, Clone)]
+#[napi(object, js_name = "ClientContext")]
+pub struct TsClientContext {
+    /// Information about the mobile application invoking the function.
+    pub client: TsClientApplication,
+    /// Custom properties attached to the mobile event context.
+    pub custom: HashMap<String, String>,
+    /// Environment settings from the mobile client.
+    pub environment: HashMap<String, String>,
+}
+
+/// Cognito identity information sent with the event
+#[derive(Debug, Clone)]
+#[napi(object, js_name = "CognitoIdentity")]
+pub struct TsCognitoIdentity {
+    /// The unique identity id for the Cognito credentials invoking the function.
+    pub identity_id: String,
+    /// The identity pool id the caller is "registered" with.
+    pub identity_pool_id: String,
+}
+
+/// Configuration derived from environment variables.
+#[derive(Debug, Clone)]
+#[napi(object, js_name = "LambdaConfig")]
+pub struct TsLambdaConfig {
+    /// The name of the function.
+    pub function_name: String,
+    /// The amount of memory available to the function in MB.
+    pub memory: i32,
+    /// The version of the function being executed.
+    pub version: String,
+    /// The name of the Amazon CloudWatch Logs stream for the function.
+    pub log_stream: String,
+    /// The name of the Amazon CloudWatch Logs group for the function.
+    pub log_group: String,
+}
+
+/// The Lambda function execution context. The values in this struct
+/// are populated using the [Lambda environment variables](https://docs.aws.amazon.com/lambda/latest/dg/current-supported-versions.html)
+/// and the headers returned by the poll request to the Runtime APIs.
+#[derive(Debug, Clone)]
+#[napi(object, js_name = "LambdaContext")]
+pub struct TsLambdaContext {
+    /// The AWS request ID generated by the Lambda service.
+    pub request_id: String,
+    /// The execution deadline for the current invocation in milliseconds.
+    pub deadline: i64,
+    /// The ARN of the Lambda function being invoked.
+    pub invoked_function_arn: String,
+    /// The X-Ray trace ID for the current invocation.
+    pub xray_trace_id: Option<String>,
+    /// The client context object sent by the AWS mobile SDK. This field is
+    /// empty unless the function is invoked using an AWS mobile SDK.
+    pub client_context: Option<TsClientContext>,
+    /// The Cognito identity that invoked the function. This field is empty
+    /// unless the invocation request to the Lambda APIs was made using AWS
+    /// credentials issues by Amazon Cognito Identity Pools.
+    pub identity: Option<TsCognitoIdentity>,
+    /// Lambda function configuration from the local environment variables.
+    /// Includes information such as the function name, memory allocation,
+    /// version, and log streams.
+    pub env_config: TsLambdaConfig,
+}
+
+impl TsLambdaContext {
+    /// Create Typescript-compatible version of [Context].
+    pub fn new(ctx: Context) -> Self {
+        Self {
+            request_id: ctx.request_id,
+            deadline: ctx.deadline as i64,
+            invoked_function_arn: ctx.invoked_function_arn,
+            xray_trace_id: ctx.xray_trace_id,
+            client_context: ctx.client_context.map(|client_ctx| TsClientContext {
+                client: TsClientApplication {
+                    installation_id: client_ctx.client.installation_id,
+                    app_title: client_ctx.client.app_title,
+                    app_version_name: client_ctx.client.app_version_name,
+                    app_version_code: client_ctx.client.app_version_code,
+                    app_package_name: client_ctx.client.app_package_name,
+                },
+                custom: client_ctx.custom,
+                environment: client_ctx.environment,
+            }),
+            identity: ctx.identity.map(|identity| TsCognitoIdentity {
+                identity_id: identity.identity_id,
+                identity_pool_id: identity.identity_pool_id,
+            }),
+            env_config: TsLambdaConfig {
+                function_name: ctx.env_config.function_name,
+                memory: ctx.env_config.memory,
+                version: ctx.env_config.version,
+                log_stream: ctx.env_config.log_stream,
+                log_group: ctx.env_config.log_group,
+            },
+        }
+    }
+}
@@ -6,3 +6,30 @@
 /* Automatically managed default lints */
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 /* End of automatically managed default lints */
+#![allow(clippy::derive_partial_eq_without_eq)]
+
+//! Rust/Node.js bindings, runtime and utilities.
+//!
+//! This crates implements all the generic code needed to start and manage
+//! a Smithy Rust HTTP server where the business logic is implemented in Typescript,
+//! leveraging [napi-rs].
+//!
+//! The code generated application implements [TsApp] and registers a [TsHandler] per operation,
+//! which calls the Node.js function implementing the operation on the Node.js main thread once
+//! the request has been routed, deserialized and validated by the Rust server.
+//!
+//! [napi-rs]: https://napi.rs/
+
+pub mod context;
+pub mod lambda;
+mod server;
+mod socket;
+pub mod tls;
+pub mod types;
+
+#[doc(inline)]
+pub use context::TsRequestContext;
+#[doc(inline)]
+pub use server::{Service, TsApp, TsHandler, TsRunOptions};
+#[doc(inline)]
+pub use socket::TsSocket;
@@ -0,0 +1,489 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::convert::Infallible;
+use std::fmt;
+use std::future::{self, Future};
+use std::marker::PhantomData;
+use std::net::TcpListener as StdTcpListener;
+use std::sync::{mpsc, Arc};
+
+use aws_smithy_http_server::{
+    body::{Body, BoxBody},
+    routing::IntoMakeService,
+};
+use http::{Request, Response};
+use hyper::server::conn::AddrIncoming;
+use napi::{
+    bindgen_prelude::{
+        block_on, spawn, spawn_blocking, Either, FromNapiValue, Promise, ToNapiValue,
+    },
+    threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction},
+    Env, JsFunction, JsUnknown,
+};
+use napi_derive::napi;
+use socket2::Socket;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tower::util::BoxCloneService;
+
+use crate::{
+    context::{self, TsRequestContext},
+    lambda::TsLambdaContext,
+    tls::{listener::Listener as TlsListener, TsTlsConfig},
+    TsSocket,
+};
+
+/// A `BoxCloneService` with default `Request`, `Response` and `Error`.
+pub type Service = BoxCloneService<Request<Body>, Response<BoxBody>, Infallible>;
+
+// The arguments the Node.js handlers are called with.
+type HandlerArgs<I> = (I, Option<TsLambdaContext>);
+
+/// A Node.js handler function representation.
+///
+/// The handler implements the business logic of an operation: it is called on the Node.js main
+/// thread with the input of the operation and the context of the application, and returns a
+/// promise of the output of the operation.
+pub struct TsHandler<I: 'static, O> {
+    func: ThreadsafeFunction<HandlerArgs<I>, ErrorStrategy::Fatal>,
+    _output: PhantomData<fn() -> O>,
+}
+
+impl<I, O> TsHandler<I, O>
+where
+    I: ToNapiValue + Send + 'static,
+    O: FromNapiValue + Send + 'static,
+{
+    /// Register the Node.js function `func`, which will be passed `context` along with the input
+    /// of the operation. The same context is usually shared by all the handlers of an application.
+    ///
+    /// This must be called on the Node.js main thread.
+    pub fn new(env: &Env, func: JsFunction, context: Option<&JsUnknown>) -> napi::Result<Self> {
+        let func = context::with_context(env, func, context)?;
+        let func =
+            func.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<HandlerArgs<I>>| {
+                let (input, lambda_context) = ctx.value;
+                Ok(vec![Either::A(input), Either::B(lambda_context)])
+            })?;
+        Ok(Self {
+            func,
+            _output: PhantomData,
+        })
+    }
+
+    /// Call the Node.js function with `input` on the Node.js main thread, and wait for the
+    /// promise it returns.
+    ///
+    /// Errors thrown by the function, and rejections of the promise, are returned as
+    /// [napi::Error]s.
+    pub async fn call(&self, input: I, request_context: TsRequestContext) -> napi::Result<O> {
+        let output = self
+            .func
+            .call_async::<Promise<O>>((input, request_context.into_lambda()))
+            .await?;
+        output.await
+    }
+}
+
+impl<I: 'static, O> Clone for TsHandler<I, O> {
+    fn clone(&self) -> Self {
+        Self {
+            func: self.func.clone(),
+            _output: PhantomData,
+        }
+    }
+}
+
+impl<I: 'static, O> fmt::Debug for TsHandler<I, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TsHandler").finish_non_exhaustive()
+    }
+}
+
+/// Options of [TsApp::run_server].
+#[napi(object, js_name = "RunOptions")]
+#[derive(Debug, Clone, Default)]
+pub struct TsRunOptions {
+    /// The address to listen on, defaults to `127.0.0.1`.
+    pub address: Option<String>,
+    /// The port to listen on, defaults to `13734`.
+    pub port: Option<i32>,
+    /// The maximum number of pending connections, defaults to 1024.
+    pub backlog: Option<i32>,
+    /// The number of worker processes, defaults to the number of CPUs.
+    pub workers: Option<u32>,
+    /// The TLS configuration, if the server must use TLS.
+    pub tls: Option<TsTlsConfig>,
+}
+
+/// Trait defining a Typescript application.
+///
+/// The Node.js business logic runs on the Node.js main thread, while the Rust server runs on the
+/// Tokio runtime of napi-rs, and calls the [TsHandler]s registered for the operations once the
+/// requests have been routed, deserialized and validated.
+///
+/// Since Node.js runs the business logic on a single thread, [TsApp::run_server] spawns multiple
+/// Node.js worker processes sharing the same socket. The main process only manages the workers
+/// and their termination.
+///
+/// This trait will be implemented by the code generated by the `TsApplicationGenerator` Kotlin class.
+pub trait TsApp {
+    /// Build the app's `Service`, routing requests to the registered [TsHandler]s.
+    fn build_service(&mut self) -> napi::Result<Service>;
+
+    /// Start a server on a clone of `socket`, in the background, and return.
+    ///
+    /// This doesn't manage multiple workers, and can be used with the Node.js `cluster` module
+    /// for example.
+    fn start_hyper_worker(
+        &mut self,
+        socket: &TsSocket,
+        tls: Option<TsTlsConfig>,
+    ) -> napi::Result<()> {
+        let service = self.build_service()?;
+        let raw_socket = socket.get_socket()?;
+        tracing::trace!("start the hyper server in a background task");
+        spawn(serve(raw_socket, service, tls, future::pending()));
+        Ok(())
+    }
+
+    /// Main entrypoint: start the server on multiple workers.
+    ///
+    /// The first time it is called, in the main process, this creates the shared socket and
+    /// starts the worker processes, by running the same Node.js script again with the socket
+    /// passed down to them. It then blocks the main process to handle signals:
+    ///   * SIGTERM|SIGQUIT - graceful termination of all workers.
+    ///   * SIGINT - immediate termination of all workers.
+    ///
+    /// In the worker processes, this starts the server on the shared socket in the background
+    /// and returns, leaving the Node.js main thread to the handlers. Workers stop accepting
+    /// connections and exit once the in-flight requests are served when they receive SIGTERM or
+    /// SIGINT.
+    fn run_server(&mut self, options: Option<TsRunOptions>) -> napi::Result<()> {
+        let options = options.unwrap_or_default();
+        if let Some((idx, socket)) = workers::current()? {
+            tracing::trace!(idx, "starting worker");
+            let service = self.build_service()?;
+            spawn(async move {
+                serve(socket, service, options.tls, workers::shutdown_signal()).await;
+                tracing::debug!(idx, "worker terminated");
+                std::process::exit(0);
+            });
+            return Ok(());
+        }
+
+        let address = options.address.unwrap_or_else(|| String::from("127.0.0.1"));
+        let port = options.port.unwrap_or(13734);
+        let socket = TsSocket::new(address, port, options.backlog)?;
+        let count = options
+            .workers
+            .map(|workers| workers as usize)
+            .unwrap_or_else(num_cpus::get);
+        let workers = workers::Workers::spawn(&socket, count)?;
+        tracing::trace!("rust typescript server started successfully");
+        workers.block_on_rust_signals()
+    }
+
+    /// Lambda main entrypoint: start the handler on Lambda, in the background, and return.
+    fn run_lambda_handler(&mut self) -> napi::Result<()> {
+        use aws_smithy_http_server::routing::LambdaHandler;
+
+        let service = self.build_service()?;
+        tracing::trace!("start the lambda handler in a background task");
+        // The Lambda runtime future isn't `Send`, it is driven on a blocking thread instead.
+        spawn_blocking(move || {
+            let handler = LambdaHandler::new(service);
+            let lambda = lambda_http::run(handler);
+            tracing::debug!("starting lambda handler");
+            if let Err(err) = block_on(lambda) {
+                tracing::error!(error = %err, "unable to start lambda handler");
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Serve `service` on `socket` until `shutdown` completes, then wait for the in-flight requests.
+async fn serve(
+    socket: Socket,
+    service: Service,
+    tls: Option<TsTlsConfig>,
+    shutdown: impl Future<Output = ()>,
+) {
+    let addr = addr_incoming_from_socket(socket);
+    let result = if let Some(config) = tls {
+        let (acceptor, acceptor_rx) = tls_config_reloader(config);
+        let listener = TlsListener::new(acceptor, addr, acceptor_rx);
+        tracing::trace!("started tls hyper server from shared socket");
+        hyper::Server::builder(listener)
+            .serve(IntoMakeService::new(service))
+            .with_graceful_shutdown(shutdown)
+            .await
+    } else {
+        tracing::trace!("started hyper server from shared socket");
+        hyper::Server::builder(addr)
+            .serve(IntoMakeService::new(service))
+            .with_graceful_shutdown(shutdown)
+            .await
+    };
+    if let Err(err) = result {
+        tracing::error!(error = ?err, "server error");
+    }
+}
+
+fn addr_incoming_from_socket(socket: Socket) -> AddrIncoming {
+    let std_listener: StdTcpListener = socket.into();
+    // StdTcpListener::from_std doesn't set O_NONBLOCK
+    std_listener
+        .set_nonblocking(true)
+        .expect("unable to set `O_NONBLOCK=true` on `std::net::TcpListener`");
+    let listener = TcpListener::from_std(std_listener)
+        .expect("unable to create `tokio::net::TcpListener` from `std::net::TcpListener`");
+    AddrIncoming::from_listener(listener)
+        .expect("unable to create `AddrIncoming` from `TcpListener`")
+}
+
+// Builds `TlsAcceptor` from given `config` and also creates a background task
+// to reload certificates and returns a channel to receive new `TlsAcceptor`s.
+fn tls_config_reloader(config: TsTlsConfig) -> (TlsAcceptor, mpsc::Receiver<TlsAcceptor>) {
+    let reload_dur = config.reload_duration();
+    let (tx, rx) = mpsc::channel();
+    let acceptor = TlsAcceptor::from(Arc::new(config.build().expect("invalid tls config")));
+
+    tokio::spawn(async move {
+        tracing::trace!(dur = ?reload_dur, "starting timer to reload tls config");
+        loop {
+            tokio::time::sleep(reload_dur).await;
+            tracing::trace!("reloading tls config");
+            match config.build() {
+                Ok(config) => {
+                    let new_config = TlsAcceptor::from(Arc::new(config));
+                    // The server is gone if the receiver is dropped.
+                    if tx.send(new_config).is_err() {
+                        return;
+                    }
+                }
+                Err(err) => {
+                    tracing::error!(error = ?err, "could not reload tls config because it is invalid");
+                }
+            }
+        }
+    });
+
+    (acceptor, rx)
+}
+
+#[cfg(unix)]
+mod workers {
+    use std::env;
+    use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+    use std::process::{self, Child, Command};
+
+    use signal_hook::{consts::*, iterator::Signals};
+    use socket2::Socket;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    use crate::TsSocket;
+
+    /// The environment variable telling a process it is a worker, formatted as
+    /// `<worker number>:<shared socket file descriptor>`.
+    const WORKER_ENV: &str = "SMITHY_RS_TS_WORKER";
+
+    /// Returns the worker number and the shared socket if this process is a worker.
+    pub(super) fn current() -> napi::Result<Option<(usize, Socket)>> {
+        let Some(worker) = env::var_os(WORKER_ENV) else {
+            return Ok(None);
+        };
+        let (idx, fd) = worker
+            .to_str()
+            .and_then(parse_worker)
+            .ok_or_else(|| napi::Error::from_reason(format!("invalid `{WORKER_ENV}`")))?;
+        // Workers started by this worker must create their own socket.
+        env::remove_var(WORKER_ENV);
+        reset_node_signal_handlers();
+        // SAFETY: the main process passes down the file descriptor of the shared socket, which
+        // is owned by this process from now on.
+        let socket = unsafe { Socket::from_raw_fd(fd) };
+        Ok(Some((idx, socket)))
+    }
+
+    fn parse_worker(worker: &str) -> Option<(usize, RawFd)> {
+        let (idx, fd) = worker.split_once(':')?;
+        Some((idx.parse().ok()?, fd.parse().ok()?))
+    }
+
+    /// Node.js handles SIGINT and SIGTERM by resetting the terminal and raising the signal again.
+    /// Signal handlers registered in Rust call the previously installed handlers, so the signal
+    /// would be raised forever: the default disposition of these signals is restored first.
+    fn reset_node_signal_handlers() {
+        for sig in [libc::SIGINT, libc::SIGTERM] {
+            // SAFETY: restoring the default disposition has no memory safety requirements.
+            unsafe { libc::signal(sig, libc::SIG_DFL) };
+        }
+    }
+
+    /// Completes when the worker receives SIGTERM or SIGINT.
+    pub(super) async fn shutdown_signal() {
+        let (Ok(mut terminate), Ok(mut interrupt)) = (
+            signal(SignalKind::terminate()),
+            signal(SignalKind::interrupt()),
+        ) else {
+            tracing::error!("unable to register worker signals");
+            return std::future::pending().await;
+        };
+        tokio::select! {
+            _ = terminate.recv() => {},
+            _ = interrupt.recv() => {},
+        }
+        tracing::info!("termination signal received, the worker will be gracefully terminated");
+    }
+
+    /// The worker processes started by the main process.
+    pub(super) struct Workers(Vec<Child>);
+
+    impl Workers {
+        /// Start `count` workers, running the same Node.js script as this process.
+        pub(super) fn spawn(socket: &TsSocket, count: usize) -> napi::Result<Self> {
+            let program = env::current_exe()?;
+            let mut workers = Vec::with_capacity(count);
+            for idx in 1..count + 1 {
+                // The worker inherits a clone of the socket, closed here once it is started.
+                let sock = socket.get_socket()?;
+                sock.set_cloexec(false)?;
+                let child = Command::new(&program)
+                    .args(env::args_os().skip(1))
+                    .env(WORKER_ENV, format!("{idx}:{}", sock.as_raw_fd()))
+                    .spawn()?;
+                tracing::debug!(idx, pid = child.id(), "started worker");
+                workers.push(child);
+            }
+            Ok(Self(workers))
+        }
+
+        /// Register and handle signals of the main process. Signals not registered in this
+        /// method are ignored.
+        pub(super) fn block_on_rust_signals(mut self) -> ! {
+            reset_node_signal_handlers();
+            let mut signals = Signals::new([
+                SIGINT, SIGHUP, SIGQUIT, SIGTERM, SIGCHLD, SIGUSR1, SIGUSR2, SIGWINCH,
+            ])
+            .expect("Unable to register signals");
+            for sig in signals.forever() {
+                match sig {
+                    SIGINT => {
+                        tracing::info!(
+                            sig = %sig, "termination signal received, all workers will be immediately terminated"
+                        );
+                        self.immediate_termination();
+                    }
+                    SIGTERM | SIGQUIT => {
+                        tracing::info!(
+                            sig = %sig, "termination signal received, all workers will be gracefully terminated"
+                        );
+                        self.graceful_termination();
+                    }
+                    SIGCHLD => self.reap(),
+                    _ => {
+                        tracing::debug!(sig = %sig, "signal is ignored by this application");
+                    }
+                }
+            }
+            unreachable!("signals iterator never ends")
+        }
+
+        /// Send SIGTERM to all the workers, and wait for them to terminate.
+        fn graceful_termination(&mut self) -> ! {
+            for (idx, worker) in self.0.iter().enumerate() {
+                let idx = idx + 1;
+                let pid = worker.id();
+                tracing::debug!(idx, pid, "terminating worker");
+                // SAFETY: `kill` has no memory safety requirements.
+                if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+                    tracing::error!(
+                        error = %std::io::Error::last_os_error(), idx, pid, "error terminating worker"
+                    );
+                }
+            }
+            for worker in &mut self.0 {
+                let _ = worker.wait();
+            }
+            process::exit(0);
+        }
+
+        /// Kill all the workers.
+        fn immediate_termination(&mut self) -> ! {
+            for (idx, worker) in self.0.iter_mut().enumerate() {
+                let idx = idx + 1;
+                let pid = worker.id();
+                tracing::debug!(idx, pid, "killing worker");
+                if let Err(err) = worker.kill() {
+                    tracing::error!(error = %err, idx, pid, "unable to kill worker");
+                }
+            }
+            process::exit(0);
+        }
+
+        /// Log the workers that terminated, and exit once all of them did.
+        fn reap(&mut self) {
+            for (idx, worker) in self.0.iter_mut().enumerate() {
+                if let Ok(Some(status)) = worker.try_wait() {
+                    tracing::warn!(idx = idx + 1, pid = worker.id(), %status, "worker terminated");
+                }
+            }
+            let mut running = 0;
+            for worker in &mut self.0 {
+                if matches!(worker.try_wait(), Ok(None)) {
+                    running += 1;
+                }
+            }
+            if running == 0 {
+                tracing::error!("all workers terminated");
+                process::exit(1);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::parse_worker;
+
+        #[test]
+        fn parsing_worker_env() {
+            assert_eq!(parse_worker("3:42"), Some((3, 42)));
+            assert_eq!(parse_worker("3"), None);
+            assert_eq!(parse_worker("a:42"), None);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod workers {
+    use socket2::Socket;
+
+    use crate::TsSocket;
+
+    pub(super) fn current() -> napi::Result<Option<(usize, Socket)>> {
+        Ok(None)
+    }
+
+    pub(super) async fn shutdown_signal() {
+        std::future::pending().await
+    }
+
+    pub(super) struct Workers;
+
+    impl Workers {
+        pub(super) fn spawn(_socket: &TsSocket, _count: usize) -> napi::Result<Self> {
+            Err(napi::Error::from_reason(
+                "multiple workers are only supported on unix, use `start` with a `TsSocket`",
+            ))
+        }
+
+        pub(super) fn block_on_rust_signals(self) -> ! {
+            unreachable!("no workers can be started")
+        }
+    }
+}
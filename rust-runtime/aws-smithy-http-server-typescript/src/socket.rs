@@ -0,0 +1,116 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Socket implementation that can be shared between multiple Node.js processes.
+
+use std::net::SocketAddr;
+
+use napi_derive::napi;
+use socket2::{Domain, Protocol, Socket, Type};
+
+/// Socket implementation that can be shared between multiple Node.js processes.
+///
+/// Node.js runs the business logic on a single thread, so Node.js web applications usually
+/// create a socket with SO_REUSEADDR and SO_REUSEPORT enabled that is shared between multiple
+/// Node.js processes, allowing them to use all available computing capacity of the host.
+#[napi(js_name = "TsSocket")]
+#[derive(Debug)]
+pub struct TsSocket {
+    pub(crate) inner: Socket,
+}
+
+#[napi]
+impl TsSocket {
+    /// Create a new UNIX `Socket` from an address, port and backlog.
+    /// If not specified, the backlog defaults to 1024 connections.
+    #[napi(constructor)]
+    pub fn new(address: String, port: i32, backlog: Option<i32>) -> napi::Result<Self> {
+        let address: SocketAddr = format!("{}:{}", address, port)
+            .parse()
+            .map_err(|err| napi::Error::from_reason(format!("invalid address: {err}")))?;
+        let (domain, ip_version) = TsSocket::socket_domain(address);
+        tracing::trace!(address = %address, ip_version, "shared socket listening");
+        let socket = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))?;
+        // Set value for the `SO_REUSEPORT` and `SO_REUSEADDR` options on this socket.
+        // This indicates that further calls to `bind` may allow reuse of local
+        // addresses. For IPv4 sockets this means that a socket may bind even when
+        // there's a socket already listening on this port.
+        socket.set_reuse_port(true)?;
+        socket.set_reuse_address(true)?;
+        socket.bind(&address.into())?;
+        socket.listen(backlog.unwrap_or(1024))?;
+        Ok(TsSocket { inner: socket })
+    }
+
+    /// Clone the inner socket allowing it to be shared between multiple
+    /// Node.js processes.
+    #[napi]
+    pub fn try_clone(&self) -> napi::Result<TsSocket> {
+        let copied = self.inner.try_clone()?;
+        Ok(TsSocket { inner: copied })
+    }
+
+    /// The port the socket is listening on.
+    #[napi(getter)]
+    pub fn port(&self) -> napi::Result<u32> {
+        let port = self
+            .inner
+            .local_addr()?
+            .as_socket()
+            .map(|address| address.port())
+            .unwrap_or_default();
+        Ok(port.into())
+    }
+}
+
+impl TsSocket {
+    /// Get a cloned inner socket.
+    pub fn get_socket(&self) -> Result<Socket, std::io::Error> {
+        self.inner.try_clone()
+    }
+
+    /// Find the socket domain
+    fn socket_domain(address: SocketAddr) -> (Domain, &'static str) {
+        if address.is_ipv6() {
+            (Domain::IPV6, "6")
+        } else {
+            (Domain::IPV4, "4")
+        }
+    }
+}
+
+impl From<Socket> for TsSocket {
+    fn from(inner: Socket) -> Self {
+        Self { inner }
+    }
+}
+
+#[cfg(test)]
+// `is_listener` on `Socket` is only available on certain platforms.
+// In particular, this fails to compile on MacOS.
+#[cfg(any(
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "fuchsia",
+    target_os = "linux",
+))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn socket_can_bind_on_random_port() {
+        let socket = TsSocket::new("127.0.0.1".to_owned(), 0, None).unwrap();
+        assert!(socket.inner.is_listener().unwrap());
+        assert_ne!(socket.port().unwrap(), 0);
+    }
+
+    #[test]
+    fn socket_can_be_cloned() {
+        let socket = TsSocket::new("127.0.0.1".to_owned(), 0, None).unwrap();
+        let cloned_socket = socket.try_clone().unwrap();
+        assert!(cloned_socket.inner.is_listener().unwrap());
+        assert_eq!(cloned_socket.port().unwrap(), socket.port().unwrap());
+    }
+}
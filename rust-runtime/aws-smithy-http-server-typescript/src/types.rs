@@ -0,0 +1,456 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Typescript wrapped types from aws-smithy-types.
+//!
+//! ## `Deref` hacks for Json serializer
+//! [aws_smithy_json::serialize::JsonValueWriter] expects references to the types
+//! from [aws_smithy_types] (for example [aws_smithy_json::serialize::JsonValueWriter::document()]
+//! expects `&aws_smithy_types::Document`). As in the Python runtime, we implement `Deref` traits
+//! for the Typescript types to their Rust counterparts, so that `&Document` gets coerced to
+//! `&aws_smithy_types::Document`.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    ops::Deref,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use aws_smithy_types::Number;
+use bytes::Bytes;
+use napi::{
+    bindgen_prelude::{Buffer, FromNapiValue, ToNapiValue, TypeName, ValidateNapiValue},
+    sys, Env, JsBoolean, JsNumber, JsObject, JsString, JsUnknown, NapiRaw, NapiValue, ValueType,
+};
+use napi_derive::napi;
+use tokio::sync::Mutex;
+
+/// Typescript Wrapper for [aws_smithy_types::Blob], which is a `Buffer` in Node.js.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Blob(aws_smithy_types::Blob);
+
+impl Blob {
+    /// Creates a new blob from the given `input`.
+    pub fn new<T: Into<Vec<u8>>>(input: T) -> Self {
+        Self(aws_smithy_types::Blob::new(input))
+    }
+
+    /// Consumes the `Blob` and returns a `Vec<u8>` with its contents.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0.into_inner()
+    }
+}
+
+impl AsRef<[u8]> for Blob {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl From<aws_smithy_types::Blob> for Blob {
+    fn from(other: aws_smithy_types::Blob) -> Blob {
+        Blob(other)
+    }
+}
+
+impl From<Blob> for aws_smithy_types::Blob {
+    fn from(other: Blob) -> aws_smithy_types::Blob {
+        other.0
+    }
+}
+
+impl<'blob> From<&'blob Blob> for &'blob aws_smithy_types::Blob {
+    fn from(other: &'blob Blob) -> &'blob aws_smithy_types::Blob {
+        &other.0
+    }
+}
+
+impl Deref for Blob {
+    type Target = aws_smithy_types::Blob;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl TypeName for Blob {
+    fn type_name() -> &'static str {
+        "Buffer"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::Object
+    }
+}
+
+impl ValidateNapiValue for Blob {
+    unsafe fn validate(
+        env: sys::napi_env,
+        napi_val: sys::napi_value,
+    ) -> napi::Result<sys::napi_value> {
+        Buffer::validate(env, napi_val)
+    }
+}
+
+impl ToNapiValue for Blob {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+        Buffer::to_napi_value(env, val.into_inner().into())
+    }
+}
+
+impl FromNapiValue for Blob {
+    unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> napi::Result<Self> {
+        let buffer = Buffer::from_napi_value(env, napi_val)?;
+        Ok(Self::new(Vec::from(buffer)))
+    }
+}
+
+/// Typescript Wrapper for [aws_smithy_types::date_time::DateTime], which is a `Date` in Node.js.
+///
+/// Node.js dates have a millisecond precision, so sub-millisecond precision is lost when a
+/// `DateTime` is passed to Node.js.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DateTime(aws_smithy_types::date_time::DateTime);
+
+impl From<aws_smithy_types::DateTime> for DateTime {
+    fn from(other: aws_smithy_types::DateTime) -> DateTime {
+        DateTime(other)
+    }
+}
+
+impl From<DateTime> for aws_smithy_types::DateTime {
+    fn from(other: DateTime) -> aws_smithy_types::DateTime {
+        other.0
+    }
+}
+
+impl Deref for DateTime {
+    type Target = aws_smithy_types::DateTime;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl TypeName for DateTime {
+    fn type_name() -> &'static str {
+        "Date"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::Object
+    }
+}
+
+impl ValidateNapiValue for DateTime {
+    unsafe fn validate(
+        env: sys::napi_env,
+        napi_val: sys::napi_value,
+    ) -> napi::Result<sys::napi_value> {
+        let mut is_date = false;
+        napi::check_status!(sys::napi_is_date(env, napi_val, &mut is_date))?;
+        if !is_date {
+            return Err(napi::Error::new(
+                napi::Status::DateExpected,
+                "expected a `Date`".to_string(),
+            ));
+        }
+        Ok(std::ptr::null_mut())
+    }
+}
+
+impl ToNapiValue for DateTime {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+        let mut result = std::ptr::null_mut();
+        napi::check_status!(sys::napi_create_date(
+            env,
+            val.0.as_secs_f64() * 1000.0,
+            &mut result
+        ))?;
+        Ok(result)
+    }
+}
+
+impl FromNapiValue for DateTime {
+    unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> napi::Result<Self> {
+        Self::validate(env, napi_val)?;
+        let mut millis = 0.0;
+        napi::check_status!(sys::napi_get_date_value(env, napi_val, &mut millis))?;
+        if !millis.is_finite() {
+            return Err(napi::Error::new(
+                napi::Status::InvalidArg,
+                "invalid `Date`".to_string(),
+            ));
+        }
+        Ok(Self(aws_smithy_types::DateTime::from_secs_f64(
+            millis / 1000.0,
+        )))
+    }
+}
+
+/// Typescript Wrapper for [aws_smithy_types::Document], which is any JSON-like value in Node.js.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document(aws_smithy_types::Document);
+
+impl From<aws_smithy_types::Document> for Document {
+    fn from(other: aws_smithy_types::Document) -> Document {
+        Document(other)
+    }
+}
+
+impl From<Document> for aws_smithy_types::Document {
+    fn from(other: Document) -> aws_smithy_types::Document {
+        other.0
+    }
+}
+
+impl Deref for Document {
+    type Target = aws_smithy_types::Document;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl TypeName for Document {
+    fn type_name() -> &'static str {
+        "Document"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::Unknown
+    }
+}
+
+impl ValidateNapiValue for Document {}
+
+impl ToNapiValue for Document {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+        let env = Env::from_raw(env);
+        Ok(document_to_js(&env, val.0)?.raw())
+    }
+}
+
+impl FromNapiValue for Document {
+    unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> napi::Result<Self> {
+        let value = JsUnknown::from_raw(env, napi_val)?;
+        Ok(Self(document_from_js(value)?))
+    }
+}
+
+fn document_to_js(env: &Env, document: aws_smithy_types::Document) -> napi::Result<JsUnknown> {
+    use aws_smithy_types::Document as RustDocument;
+    Ok(match document {
+        RustDocument::Object(values) => {
+            let mut object = env.create_object()?;
+            for (key, value) in values {
+                object.set_named_property(&key, document_to_js(env, value)?)?;
+            }
+            object.into_unknown()
+        }
+        RustDocument::Array(values) => {
+            let mut array = env.create_array_with_length(values.len())?;
+            for (index, value) in values.into_iter().enumerate() {
+                array.set_element(index as u32, document_to_js(env, value)?)?;
+            }
+            array.into_unknown()
+        }
+        RustDocument::Number(Number::PosInt(value)) => {
+            env.create_double(value as f64)?.into_unknown()
+        }
+        RustDocument::Number(Number::NegInt(value)) => {
+            env.create_double(value as f64)?.into_unknown()
+        }
+        RustDocument::Number(Number::Float(value)) => env.create_double(value)?.into_unknown(),
+        RustDocument::String(value) => env.create_string(&value)?.into_unknown(),
+        RustDocument::Bool(value) => env.get_boolean(value)?.into_unknown(),
+        RustDocument::Null => env.get_null()?.into_unknown(),
+    })
+}
+
+// Integers beyond this bound can't be represented exactly by a JS number.
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+fn document_from_js(value: JsUnknown) -> napi::Result<aws_smithy_types::Document> {
+    use aws_smithy_types::Document as RustDocument;
+    Ok(match value.get_type()? {
+        ValueType::Null | ValueType::Undefined => RustDocument::Null,
+        ValueType::Boolean => {
+            // SAFETY: the type of the value has been checked.
+            let value: JsBoolean = unsafe { value.cast() };
+            RustDocument::Bool(value.get_value()?)
+        }
+        ValueType::Number => {
+            // SAFETY: the type of the value has been checked.
+            let value: JsNumber = unsafe { value.cast() };
+            let value = value.get_double()?;
+            let number = if value.fract() == 0.0 && value.abs() <= MAX_SAFE_INTEGER {
+                if value >= 0.0 {
+                    Number::PosInt(value as u64)
+                } else {
+                    Number::NegInt(value as i64)
+                }
+            } else {
+                Number::Float(value)
+            };
+            RustDocument::Number(number)
+        }
+        ValueType::String => {
+            // SAFETY: the type of the value has been checked.
+            let value: JsString = unsafe { value.cast() };
+            RustDocument::String(value.into_utf8()?.into_owned()?)
+        }
+        ValueType::Object if value.is_array()? => {
+            // SAFETY: arrays are objects.
+            let array: JsObject = unsafe { value.cast() };
+            let len = array.get_array_length()?;
+            let mut values = Vec::with_capacity(len as usize);
+            for index in 0..len {
+                values.push(document_from_js(array.get_element::<JsUnknown>(index)?)?);
+            }
+            RustDocument::Array(values)
+        }
+        ValueType::Object => {
+            // SAFETY: the type of the value has been checked.
+            let object: JsObject = unsafe { value.cast() };
+            let keys = object.get_property_names()?;
+            let len = keys.get_array_length()?;
+            let mut values = HashMap::with_capacity(len as usize);
+            for index in 0..len {
+                let key = keys
+                    .get_element::<JsString>(index)?
+                    .into_utf8()?
+                    .into_owned()?;
+                let value = object.get_named_property::<JsUnknown>(&key)?;
+                values.insert(key, document_from_js(value)?);
+            }
+            RustDocument::Object(values)
+        }
+        value_type => {
+            return Err(napi::Error::new(
+                napi::Status::InvalidArg,
+                format!("`{value_type}` can't be converted to a `Document`"),
+            ))
+        }
+    })
+}
+
+/// Typescript Wrapper for [aws_smithy_types::byte_stream::ByteStream].
+///
+/// ByteStream provides misuse-resistant primitives to make it easier to handle common patterns with streaming data.
+///
+/// On the Rust side, the Typescript implementation wraps the original
+/// [ByteStream](aws_smithy_types::byte_stream::ByteStream) in a clonable structure and implements
+/// the [Stream](futures::stream::Stream) trait for it to allow Rust to handle the type transparently.
+///
+/// On the Node.js side, the chunks of the stream can be awaited one at a time, or all at once:
+///
+/// ```typescript
+/// const stream = await ByteStream.fromPath("/tmp/music.mp3");
+/// for (let chunk = await stream.next(); chunk !== null; chunk = await stream.next()) {
+///     console.log(chunk);
+/// }
+/// ```
+#[napi]
+#[derive(Debug, Clone)]
+pub struct ByteStream {
+    inner: Arc<Mutex<aws_smithy_types::byte_stream::ByteStream>>,
+}
+
+impl futures::stream::Stream for ByteStream {
+    type Item = Result<Bytes, aws_smithy_types::byte_stream::error::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let stream = self.inner.lock();
+        tokio::pin!(stream);
+        match stream.poll(cx) {
+            Poll::Ready(mut stream) => Pin::new(&mut *stream).poll_next(cx),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl ByteStream {
+    /// Construct a new [`ByteStream`](aws_smithy_types::byte_stream::ByteStream) from a
+    /// [`SdkBody`](aws_smithy_types::body::SdkBody).
+    ///
+    /// This method is available only to Rust and it is required to comply with the
+    /// interface required by the code generator.
+    pub fn new(body: aws_smithy_types::body::SdkBody) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(aws_smithy_types::byte_stream::ByteStream::new(
+                body,
+            ))),
+        }
+    }
+}
+
+impl Default for ByteStream {
+    fn default() -> Self {
+        Self::new(aws_smithy_types::body::SdkBody::from(""))
+    }
+}
+
+#[napi]
+impl ByteStream {
+    /// Create a new [ByteStream](aws_smithy_types::byte_stream::ByteStream) from a `Buffer`.
+    #[napi(constructor)]
+    pub fn from_buffer(data: Buffer) -> Self {
+        Self::new(aws_smithy_types::body::SdkBody::from(Vec::from(data)))
+    }
+
+    /// Create a new [ByteStream](aws_smithy_types::byte_stream::ByteStream) from a path.
+    #[napi(factory)]
+    pub async fn from_path(path: String) -> napi::Result<ByteStream> {
+        let stream = aws_smithy_types::byte_stream::ByteStream::from_path(path)
+            .await
+            .map_err(|err| napi::Error::from_reason(err.to_string()))?;
+        Ok(Self {
+            inner: Arc::new(Mutex::new(stream)),
+        })
+    }
+
+    /// Return the next chunk of the stream, or `null` at the end of the stream.
+    #[napi]
+    pub async fn next(&self) -> napi::Result<Option<Buffer>> {
+        let mut stream = self.inner.lock().await;
+        let chunk = stream
+            .next()
+            .await
+            .transpose()
+            .map_err(|err| napi::Error::from_reason(err.to_string()))?;
+        Ok(chunk.map(|chunk| chunk.to_vec().into()))
+    }
+
+    /// Collect the remaining chunks of the stream into a single `Buffer`.
+    #[napi]
+    pub async fn collect(&self) -> napi::Result<Buffer> {
+        let stream = std::mem::take(&mut *self.inner.lock().await);
+        let data = stream
+            .collect()
+            .await
+            .map_err(|err| napi::Error::from_reason(err.to_string()))?;
+        Ok(data.into_bytes().to_vec().into())
+    }
+}
+
+impl ValidateNapiValue for ByteStream {
+    unsafe fn validate(
+        env: sys::napi_env,
+        napi_val: sys::napi_value,
+    ) -> napi::Result<sys::napi_value> {
+        <&ByteStream>::validate(env, napi_val)
+    }
+}
+
+// `#[napi]` classes can only be borrowed from Node.js, the handle to the stream is cloned instead.
+impl FromNapiValue for ByteStream {
+    unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> napi::Result<Self> {
+        <&ByteStream>::from_napi_value(env, napi_val).cloned()
+    }
+}
@@ -0,0 +1,162 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+#![deny(missing_docs, missing_debug_implementations)]
+
+//! Provides [`BodyLimitPlugin`], which limits the size of request bodies, and the rate streaming
+//! request bodies must be read at.
+//!
+//! Operations buffer request bodies before deserializing them, unless their input is streamed. The
+//! size of the bodies an operation accepts is, in order of precedence:
+//!
+//! 1. the size configured for the operation with [`BodyLimitPlugin::operation_max_body_size`],
+//! 2. the size given by the model, which is the maximum of the [`@length`] trait on the blob or
+//!    string member bound to the request payload with [`@httpPayload`]. Since the length of
+//!    strings is counted in characters, string payloads may be up to four times as many bytes
+//!    long, and
+//! 3. the default size the plugin was created with, or for operations with streaming input,
+//!    [`BodyLimitPlugin::max_streaming_body_size`].
+//!
+//! Requests whose `Content-Length` header is larger than the limit are rejected right away. Other
+//! request bodies are read until they exceed the limit, at which point operations with buffered
+//! input reject the request. Both are rejected with the protocol-specific `PayloadTooLargeException`
+//! error and a `413 Payload Too Large` status code. Operations with streaming input see an error
+//! when reading the stream, which can be downcast to [`BodyTooLarge`].
+//!
+//! The services the plugin is applied to receive request bodies as a [`LimitedBody`], so plugins
+//! whose services need a [`hyper::Body`](crate::body::Body), such as the SigV4 verification plugin,
+//! must be pushed to the [`HttpPlugins`](crate::plugin::HttpPlugins) before it.
+//!
+//! With [`BodyLimitPlugin::min_read_rate`], streaming blob request bodies must also be received at
+//! a minimum rate, so that slow clients can't hold on to connections. Streams that fall below the
+//! rate fail with [`BodyReadTooSlow`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::HttpPlugins;
+//! use aws_smithy_http_server::body_limit::{BodyLimitExt, BodyLimitPlugin};
+//! use std::time::Duration;
+//!
+//! let limits = BodyLimitPlugin::new(1024 * 1024)
+//!     // Uploads must be received at 1 KiB/s or faster after the first 10 seconds
+//!     .min_read_rate(1024, Duration::from_secs(10));
+//! let http_plugins = HttpPlugins::new().limit_bodies(limits);
+//! ```
+//!
+//! [`@length`]: https://smithy.io/2.0/spec/constraint-traits.html#length-trait
+//! [`@httpPayload`]: https://smithy.io/2.0/spec/http-bindings.html#httppayload-trait
+
+mod plugin;
+mod service;
+
+use std::error::Error as StdError;
+use std::time::Duration;
+
+use thiserror::Error;
+
+pub use plugin::*;
+pub use service::*;
+
+/// The request body properties of an operation, given by the model.
+///
+/// This is implemented for every operation by the generated code.
+pub trait BodyLimits {
+    /// The largest request body the operation accepts, if the model constrains it.
+    const MAX_REQUEST_BODY_SIZE: Option<u64> = None;
+
+    /// How the operation reads its request body.
+    const REQUEST_BODY: RequestBody = RequestBody::Buffered;
+}
+
+/// How an operation reads its request body.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestBody {
+    /// The body is buffered before the operation input is deserialized.
+    Buffered,
+    /// The body is a streaming blob, which is passed to the operation handler as it is received.
+    Streaming,
+    /// The body is an event stream. Event streams may be idle for long periods of time, so they
+    /// don't need to be received at the [minimum read rate](BodyLimitPlugin::min_read_rate).
+    EventStream,
+}
+
+/// The minimum rate streaming request bodies must be received at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinReadRate {
+    bytes_per_second: u64,
+    grace_period: Duration,
+}
+
+impl MinReadRate {
+    /// Creates a minimum rate of `bytes_per_second`, which is enforced once `grace_period` has
+    /// passed since the body started being read.
+    pub fn new(bytes_per_second: u64, grace_period: Duration) -> Self {
+        Self {
+            bytes_per_second,
+            grace_period,
+        }
+    }
+
+    /// Returns the minimum number of bytes that must be received per second.
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second
+    }
+
+    /// Returns how long bodies are read before the rate is enforced.
+    pub fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    /// Returns how long it may take to receive `read` bytes.
+    fn time_allowed(&self, read: u64) -> Duration {
+        if self.bytes_per_second == 0 {
+            return Duration::MAX;
+        }
+        let at_rate = Duration::from_secs_f64(read as f64 / self.bytes_per_second as f64);
+        self.grace_period.max(at_rate)
+    }
+}
+
+/// The error reading a request body that is larger than allowed.
+#[derive(Debug, Error)]
+#[error("request body is larger than the limit of {limit} bytes")]
+pub struct BodyTooLarge {
+    limit: u64,
+}
+
+impl BodyTooLarge {
+    /// Returns the largest body size allowed, in bytes.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+/// The error reading a streaming request body that is received slower than allowed.
+#[derive(Debug, Error)]
+#[error("request body was received slower than {} bytes per second", .rate.bytes_per_second)]
+pub struct BodyReadTooSlow {
+    rate: MinReadRate,
+}
+
+impl BodyReadTooSlow {
+    /// Returns the minimum rate the body had to be received at.
+    pub fn min_read_rate(&self) -> MinReadRate {
+        self.rate
+    }
+}
+
+/// Returns `true` if `err` was caused by a [`BodyTooLarge`] error.
+pub(crate) fn is_body_too_large(err: &(dyn StdError + 'static)) -> bool {
+    let mut next = Some(err);
+    while let Some(err) = next {
+        if err.is::<BodyTooLarge>() {
+            return true;
+        }
+        next = err.source();
+    }
+    false
+}
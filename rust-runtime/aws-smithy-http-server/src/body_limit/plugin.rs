@@ -0,0 +1,114 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::operation::OperationShape;
+use crate::plugin::{HttpMarker, HttpPlugins, Plugin, PluginStack};
+use crate::service::ServiceShape;
+use crate::shape_id::ShapeId;
+
+use super::{BodyLimitService, BodyLimits, MinReadRate, RequestBody};
+
+/// A [`Plugin`] which applies [`BodyLimitService`] to every operation.
+///
+/// See the [module documentation](crate::body_limit) for how the limit of each operation is chosen.
+#[derive(Debug, Clone)]
+pub struct BodyLimitPlugin {
+    max_body_size: u64,
+    max_streaming_body_size: Option<u64>,
+    operations: Arc<HashMap<ShapeId, u64>>,
+    min_read_rate: Option<MinReadRate>,
+}
+
+impl BodyLimitPlugin {
+    /// Creates a plugin limiting request bodies to `max_body_size` bytes, unless the model or
+    /// [`operation_max_body_size`](Self::operation_max_body_size) gives another limit.
+    ///
+    /// The limit doesn't apply to operations with streaming input, which are limited by
+    /// [`max_streaming_body_size`](Self::max_streaming_body_size).
+    pub fn new(max_body_size: u64) -> Self {
+        Self {
+            max_body_size,
+            max_streaming_body_size: None,
+            operations: Default::default(),
+            min_read_rate: None,
+        }
+    }
+
+    /// Limits the request bodies of `operation` to `max_body_size` bytes, taking precedence over the
+    /// model and the default limits.
+    pub fn operation_max_body_size(mut self, operation: ShapeId, max_body_size: u64) -> Self {
+        Arc::make_mut(&mut self.operations).insert(operation, max_body_size);
+        self
+    }
+
+    /// Limits the request bodies of operations with streaming blob or event stream input to
+    /// `max_body_size` bytes, unless the model or
+    /// [`operation_max_body_size`](Self::operation_max_body_size) gives another limit.
+    ///
+    /// By default, streaming request bodies aren't limited.
+    pub fn max_streaming_body_size(mut self, max_body_size: u64) -> Self {
+        self.max_streaming_body_size = Some(max_body_size);
+        self
+    }
+
+    /// Requires streaming blob request bodies to be received at `bytes_per_second` or faster, once
+    /// `grace_period` has passed since they started being read. Event streams aren't held to a
+    /// minimum rate.
+    ///
+    /// By default, streaming request bodies may be received at any rate.
+    pub fn min_read_rate(mut self, bytes_per_second: u64, grace_period: Duration) -> Self {
+        self.min_read_rate = Some(MinReadRate::new(bytes_per_second, grace_period));
+        self
+    }
+
+    fn max_body_size_of<Op>(&self) -> Option<u64>
+    where
+        Op: OperationShape + BodyLimits,
+    {
+        self.operations
+            .get(&Op::ID)
+            .copied()
+            .or(Op::MAX_REQUEST_BODY_SIZE)
+            .or(match Op::REQUEST_BODY {
+                RequestBody::Buffered => Some(self.max_body_size),
+                RequestBody::Streaming | RequestBody::EventStream => self.max_streaming_body_size,
+            })
+    }
+}
+
+impl<Ser, Op, T> Plugin<Ser, Op, T> for BodyLimitPlugin
+where
+    Ser: ServiceShape,
+    Op: OperationShape + BodyLimits,
+{
+    type Output = BodyLimitService<T, Ser::Protocol>;
+
+    fn apply(&self, inner: T) -> Self::Output {
+        let min_read_rate = match Op::REQUEST_BODY {
+            RequestBody::Streaming => self.min_read_rate,
+            RequestBody::Buffered | RequestBody::EventStream => None,
+        };
+        BodyLimitService::new(inner, self.max_body_size_of::<Op>(), min_read_rate)
+    }
+}
+
+impl HttpMarker for BodyLimitPlugin {}
+
+/// An extension trait for applying [`BodyLimitPlugin`].
+pub trait BodyLimitExt<CurrentPlugin> {
+    /// Limits the size of request bodies, and the rate streaming request bodies must be received
+    /// at, as configured by `limits`. See [`BodyLimitPlugin`] for more information.
+    fn limit_bodies(self, limits: BodyLimitPlugin) -> HttpPlugins<PluginStack<BodyLimitPlugin, CurrentPlugin>>;
+}
+
+impl<CurrentPlugin> BodyLimitExt<CurrentPlugin> for HttpPlugins<CurrentPlugin> {
+    fn limit_bodies(self, limits: BodyLimitPlugin) -> HttpPlugins<PluginStack<BodyLimitPlugin, CurrentPlugin>> {
+        self.push(limits)
+    }
+}
@@ -0,0 +1,359 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::fmt;
+use std::future::{ready, Future, Ready};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::byte_stream::ByteStream;
+use bytes::{Buf, Bytes};
+use futures_util::future::Either;
+use http::header::CONTENT_LENGTH;
+use http::{HeaderMap, Request, Response};
+use http_body::SizeHint;
+use pin_project_lite::pin_project;
+use tokio::time::{Instant, Sleep};
+use tower::Service;
+
+use crate::body::{BoxBody, HttpBody};
+use crate::error::BoxError;
+use crate::response::IntoResponse;
+use crate::runtime_error::PayloadTooLargeException;
+
+use super::{BodyReadTooSlow, BodyTooLarge, MinReadRate};
+
+/// A middleware [`Service`] that limits the size of request bodies, and the rate they must be
+/// received at.
+///
+/// Requests with a `Content-Length` larger than the limit are rejected with the protocol-specific
+/// `PayloadTooLargeException` error. Other request bodies are passed on as a [`LimitedBody`], which
+/// fails with [`BodyTooLarge`] once it exceeds the limit, or with [`BodyReadTooSlow`] if it is
+/// received slower than the minimum read rate.
+pub struct BodyLimitService<S, P> {
+    inner: S,
+    max_body_size: Option<u64>,
+    min_read_rate: Option<MinReadRate>,
+    _protocol: PhantomData<fn(P)>,
+}
+
+impl<S, P> BodyLimitService<S, P> {
+    /// Creates a service limiting request bodies to `max_body_size` bytes, received at
+    /// `min_read_rate` or faster. `None` doesn't limit the size or the rate respectively.
+    pub fn new(inner: S, max_body_size: Option<u64>, min_read_rate: Option<MinReadRate>) -> Self {
+        Self {
+            inner,
+            max_body_size,
+            min_read_rate,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: Clone, P> Clone for BodyLimitService<S, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            max_body_size: self.max_body_size,
+            min_read_rate: self.min_read_rate,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, P> fmt::Debug for BodyLimitService<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyLimitService")
+            .field("inner", &self.inner)
+            .field("max_body_size", &self.max_body_size)
+            .field("min_read_rate", &self.min_read_rate)
+            .finish()
+    }
+}
+
+impl<S, P, B> Service<Request<B>> for BodyLimitService<S, P>
+where
+    S: Service<Request<LimitedBody<B>>, Response = Response<BoxBody>>,
+    PayloadTooLargeException: IntoResponse<P>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<S::Response, S::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        if let (Some(limit), Some(content_length)) = (self.max_body_size, content_length(&req)) {
+            if content_length > limit {
+                tracing::debug!(content_length, limit, "rejecting request with a body that is too large");
+                return Either::Left(ready(Ok(IntoResponse::<P>::into_response(PayloadTooLargeException))));
+            }
+        }
+        let req = req.map(|body| LimitedBody::new(body, self.max_body_size, self.min_read_rate));
+        Either::Right(self.inner.call(req))
+    }
+}
+
+fn content_length<B>(req: &Request<B>) -> Option<u64> {
+    req.headers().get(CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+pin_project! {
+    /// A request body that fails with [`BodyTooLarge`] once it exceeds the size limit, or with
+    /// [`BodyReadTooSlow`] if it is received too slowly.
+    ///
+    /// The size hint and the trailers of the wrapped body are passed through.
+    pub struct LimitedBody<B> {
+        #[pin]
+        inner: B,
+        max_body_size: Option<u64>,
+        read: u64,
+        rate: Option<RateCheck>,
+        failed: bool,
+    }
+}
+
+struct RateCheck {
+    min: MinReadRate,
+    started: Instant,
+    // Fires when the body has been read too slowly, unless more of it is received first
+    deadline: Pin<Box<Sleep>>,
+}
+
+impl RateCheck {
+    fn new(min: MinReadRate) -> Self {
+        let started = Instant::now();
+        Self {
+            min,
+            started,
+            deadline: Box::pin(tokio::time::sleep_until(deadline(started, min.time_allowed(0)))),
+        }
+    }
+
+    fn received(&mut self, read: u64) {
+        let deadline = deadline(self.started, self.min.time_allowed(read));
+        self.deadline.as_mut().reset(deadline);
+    }
+}
+
+fn deadline(started: Instant, allowed: std::time::Duration) -> Instant {
+    started
+        .checked_add(allowed)
+        // Far enough in the future to never fire
+        .unwrap_or_else(|| started + std::time::Duration::from_secs(86400 * 365 * 30))
+}
+
+impl<B> LimitedBody<B> {
+    fn new(inner: B, max_body_size: Option<u64>, min_read_rate: Option<MinReadRate>) -> Self {
+        Self {
+            inner,
+            max_body_size,
+            read: 0,
+            rate: min_read_rate.map(RateCheck::new),
+            failed: false,
+        }
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for LimitedBody<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LimitedBody")
+            .field("inner", &self.inner)
+            .field("max_body_size", &self.max_body_size)
+            .field("min_read_rate", &self.rate.as_ref().map(|rate| rate.min))
+            .field("read", &self.read)
+            .finish()
+    }
+}
+
+impl<B> HttpBody for LimitedBody<B>
+where
+    B: HttpBody,
+    B::Error: Into<BoxError>,
+{
+    type Data = B::Data;
+    type Error = BoxError;
+
+    fn poll_data(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        if *this.failed {
+            return Poll::Ready(None);
+        }
+        match this.inner.poll_data(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                *this.read += chunk.remaining() as u64;
+                if let Some(limit) = *this.max_body_size {
+                    if *this.read > limit {
+                        *this.failed = true;
+                        return Poll::Ready(Some(Err(BodyTooLarge { limit }.into())));
+                    }
+                }
+                if let Some(rate) = this.rate {
+                    rate.received(*this.read);
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err.into()))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => {
+                if let Some(rate) = this.rate {
+                    if rate.deadline.as_mut().poll(cx).is_ready() {
+                        *this.failed = true;
+                        return Poll::Ready(Some(Err(BodyReadTooSlow { rate: rate.min }.into())));
+                    }
+                }
+                Poll::Pending
+            }
+        }
+    }
+
+    fn poll_trailers(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        let this = self.project();
+        if *this.failed {
+            return Poll::Ready(Ok(None));
+        }
+        this.inner.poll_trailers(cx).map_err(Into::into)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.failed || self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Lets operations with streaming input read a [`LimitedBody`].
+impl<B> From<LimitedBody<B>> for ByteStream
+where
+    B: HttpBody<Data = Bytes> + Send + Sync + 'static,
+    B::Error: Into<BoxError>,
+{
+    fn from(body: LimitedBody<B>) -> Self {
+        ByteStream::new(SdkBody::from_body_0_4(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::time::Duration;
+
+    use http::StatusCode;
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+    use crate::body::Body;
+    use crate::protocol::rest_json_1::rejection::RequestRejection;
+    use crate::protocol::rest_json_1::runtime_error::RuntimeError;
+    use crate::protocol::rest_json_1::RestJson1;
+
+    /// Buffers the request body like operations with buffered input do
+    async fn buffered(req: Request<LimitedBody<Body>>) -> Result<Response<BoxBody>, Infallible> {
+        Ok(match hyper::body::to_bytes(req.into_body()).await {
+            Ok(_) => Response::new(crate::body::empty()),
+            Err(err) => IntoResponse::<RestJson1>::into_response(RuntimeError::from(RequestRejection::from(err))),
+        })
+    }
+
+    async fn call(max_body_size: u64, req: Request<Body>) -> Response<BoxBody> {
+        BodyLimitService::<_, RestJson1>::new(service_fn(buffered), Some(max_body_size), None)
+            .oneshot(req)
+            .await
+            .unwrap()
+    }
+
+    fn chunked(chunks: &'static [&'static str]) -> Request<Body> {
+        let stream = futures_util::stream::iter(chunks.iter().map(|chunk| Ok::<_, Infallible>(*chunk)));
+        Request::new(Body::wrap_stream(stream))
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_with_a_large_content_length() {
+        let req = Request::builder()
+            .header(CONTENT_LENGTH, "11")
+            .body(Body::from("hello world"))
+            .unwrap();
+        let response = call(10, req).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        assert_eq!(response.headers()["x-amzn-errortype"], "PayloadTooLargeException");
+
+        let req = Request::builder()
+            .header(CONTENT_LENGTH, "11")
+            .body(Body::from("hello world"))
+            .unwrap();
+        assert_eq!(StatusCode::OK, call(11, req).await.status());
+    }
+
+    #[tokio::test]
+    async fn rejects_large_bodies_without_content_length() {
+        let response = call(10, chunked(&["hello", " ", "world"])).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        assert_eq!(response.headers()["x-amzn-errortype"], "PayloadTooLargeException");
+
+        assert_eq!(
+            StatusCode::OK,
+            call(11, chunked(&["hello", " ", "world"])).await.status()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn streams_must_be_received_at_the_minimum_rate() {
+        let (mut sender, body) = Body::channel();
+        let rate = MinReadRate::new(10, Duration::from_secs(5));
+        let mut body = LimitedBody::new(body, None, Some(rate));
+
+        let read = tokio::spawn(async move {
+            let mut chunks = 0;
+            while let Some(chunk) = body.data().await {
+                match chunk {
+                    Ok(_) => chunks += 1,
+                    Err(err) => return (chunks, err),
+                }
+            }
+            panic!("the body must fail");
+        });
+        // 100 bytes may take 10 seconds to be received
+        sender.send_data(Bytes::from(vec![0; 100])).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(9)).await;
+        sender.send_data(Bytes::from(vec![0; 10])).await.unwrap();
+        // 110 bytes may take 11 seconds, and nothing else is received
+        let (chunks, err) = read.await.unwrap();
+        assert_eq!(2, chunks);
+        assert!(err.is::<BodyReadTooSlow>(), "{err}");
+        drop(sender);
+    }
+
+    #[tokio::test]
+    async fn size_hints_and_trailers_are_passed_through() {
+        let (mut sender, body) = Body::channel();
+        let mut body = LimitedBody::new(body, Some(10), None);
+        assert_eq!(None, body.size_hint().exact());
+
+        let mut trailers = HeaderMap::new();
+        trailers.insert("checksum", "abc".parse().unwrap());
+        sender.send_data(Bytes::from("hello")).await.unwrap();
+        sender.send_trailers(trailers.clone()).await.unwrap();
+        drop(sender);
+        assert_eq!(Bytes::from("hello"), body.data().await.unwrap().unwrap());
+        assert!(body.data().await.is_none());
+        assert_eq!(Some(trailers), body.trailers().await.unwrap());
+
+        let body = LimitedBody::new(Body::from("hello world"), Some(10), None);
+        assert_eq!(Some(11), body.size_hint().exact());
+    }
+
+    #[tokio::test]
+    async fn byte_streams_fail_once_the_limit_is_exceeded() {
+        let body = LimitedBody::new(Body::from("hello world"), Some(10), None);
+        let err = ByteStream::from(body).collect().await.unwrap_err();
+        assert!(super::super::is_body_too_large(&err), "{err}");
+    }
+}
@@ -36,6 +36,7 @@ use crate::body::BoxBody;
 
 use crate::plugin::either::Either;
 use crate::plugin::either::EitherProj;
+use crate::shutdown::GracefulShutdown;
 
 /// A [`tower::Layer`] used to apply [`AlbHealthCheckService`].
 #[derive(Clone, Debug)]
@@ -62,6 +63,30 @@ impl AlbHealthCheckLayer<()> {
         AlbHealthCheckLayer::new(health_check_uri, service)
     }
 
+    /// Handle health check requests at `health_check_uri` by reporting whether the server is ready,
+    /// as tracked by `shutdown`: `200 OK` until the shutdown starts, and `503 Service Unavailable`
+    /// afterwards, so that load balancers stop routing requests to the server while it drains.
+    pub fn from_readiness(
+        health_check_uri: impl Into<Cow<'static, str>>,
+        shutdown: GracefulShutdown,
+    ) -> AlbHealthCheckLayer<
+        impl Service<
+                Request<Body>,
+                Response = StatusCode,
+                Error = Infallible,
+                Future = impl Future<Output = Result<StatusCode, Infallible>>,
+            > + Clone,
+    > {
+        Self::from_handler(health_check_uri, move |_req| {
+            let status = if shutdown.is_ready() {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            std::future::ready(status)
+        })
+    }
+
     /// Handle health check requests at `health_check_uri` with the specified service.
     pub fn new<H: Service<Request<Body>, Response = StatusCode>>(
         health_check_uri: impl Into<Cow<'static, str>>,
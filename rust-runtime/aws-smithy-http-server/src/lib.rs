@@ -16,14 +16,17 @@
 pub(crate) mod macros;
 
 pub mod body;
+pub mod body_limit;
 pub(crate) mod error;
 pub mod extension;
 pub mod instrumentation;
 pub mod layer;
+pub mod metrics;
 pub mod operation;
 pub mod plugin;
 #[doc(hidden)]
 pub mod protocol;
+pub mod rate_limit;
 #[doc(hidden)]
 pub mod rejection;
 pub mod request;
@@ -34,6 +37,11 @@ pub mod routing;
 pub mod runtime_error;
 pub mod service;
 pub mod shape_id;
+pub mod shutdown;
+#[cfg(feature = "aws-sigv4")]
+#[cfg_attr(docsrs, doc(cfg(feature = "aws-sigv4")))]
+pub mod sigv4;
+pub mod timeout;
 
 #[doc(inline)]
 pub(crate) use self::error::Error;
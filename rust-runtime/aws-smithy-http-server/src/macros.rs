@@ -83,11 +83,19 @@ macro_rules! opaque_future {
     };
 }
 
-macro_rules! convert_to_request_rejection {
-    ($from:ty, $to:ident) => {
+/// Converts errors reading request bodies into a `RequestRejection`. Bodies that were cut short by
+/// [`crate::body_limit`] because they are too large are converted into the `PayloadTooLarge`
+/// variant, and other errors into the `BufferHttpBodyBytes` variant.
+macro_rules! convert_body_error_to_request_rejection {
+    ($from:ty) => {
         impl From<$from> for RequestRejection {
             fn from(err: $from) -> Self {
-                Self::$to(crate::Error::new(err))
+                let err = crate::Error::new(err);
+                if crate::body_limit::is_body_too_large(&err) {
+                    Self::PayloadTooLarge(err)
+                } else {
+                    Self::BufferHttpBodyBytes(err)
+                }
             }
         }
     };
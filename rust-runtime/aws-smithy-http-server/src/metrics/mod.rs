@@ -0,0 +1,96 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+#![deny(missing_docs, missing_debug_implementations)]
+
+//! Provides [`MetricsPlugin`], which records the rate, errors and duration (RED) of every operation
+//! with a [`MetricsRecorder`].
+//!
+//! Each request is recorded with the [`ShapeId`] of the operation that handled it and its
+//! [`Outcome`]. Errors are split into modeled errors, returned by an operation handler, and
+//! framework errors, such as rejections of requests that couldn't be deserialized, which are
+//! raised before the handler is called.
+//!
+//! [`PrometheusRecorder`](prometheus::PrometheusRecorder) keeps the metrics in memory, and can be
+//! served in the Prometheus text exposition format with
+//! [`PrometheusExpositionLayer`](prometheus::PrometheusExpositionLayer).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::HttpPlugins;
+//! use aws_smithy_http_server::metrics::{prometheus::PrometheusRecorder, MetricsExt};
+//!
+//! let recorder = PrometheusRecorder::new();
+//! let http_plugins = HttpPlugins::new().record_metrics(recorder.clone());
+//! ```
+//!
+//! The router of the service can then be wrapped in a layer that serves the metrics:
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::metrics::prometheus::{PrometheusExpositionLayer, PrometheusRecorder};
+//! # let recorder = PrometheusRecorder::new();
+//! # let app = tower::service_fn(|_: http::Request<hyper::Body>| async { Ok::<_, std::convert::Infallible>(http::Response::new(aws_smithy_http_server::body::to_boxed(""))) });
+//! use tower::Layer;
+//!
+//! let app = PrometheusExpositionLayer::new("/metrics", recorder).layer(app);
+//! ```
+
+mod plugin;
+pub mod prometheus;
+mod service;
+
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::shape_id::ShapeId;
+
+pub use plugin::*;
+pub use service::*;
+
+/// The outcome of a request handled by an operation.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome<'a> {
+    /// The operation handler returned a successful response.
+    Success,
+    /// The operation handler returned an error that is defined in the model.
+    ModeledError {
+        /// The name of the error shape.
+        name: &'a str,
+    },
+    /// The request failed before or after the operation handler was called, for example because it
+    /// couldn't be deserialized.
+    FrameworkError {
+        /// The name of the runtime error, if it's known.
+        name: Option<&'a str>,
+    },
+}
+
+impl Outcome<'_> {
+    /// Returns `true` if the request failed.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Outcome::Success)
+    }
+}
+
+/// Records the metrics of the requests handled by a service.
+///
+/// Recorders are called on the request path, so recording should be cheap and must not block.
+pub trait MetricsRecorder: Debug + Send + Sync {
+    /// Records a request handled by `operation`, which took `duration` until the response was
+    /// ready to be sent.
+    fn record(&self, operation: &ShapeId, outcome: Outcome<'_>, duration: Duration);
+}
+
+impl<R> MetricsRecorder for Arc<R>
+where
+    R: MetricsRecorder + ?Sized,
+{
+    fn record(&self, operation: &ShapeId, outcome: Outcome<'_>, duration: Duration) {
+        (**self).record(operation, outcome, duration)
+    }
+}
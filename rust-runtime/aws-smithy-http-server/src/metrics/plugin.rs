@@ -0,0 +1,49 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::plugin::{HttpMarker, HttpPlugins, PluginStack};
+use crate::{operation::OperationShape, plugin::Plugin};
+
+use super::{MetricsRecorder, RecordMetrics};
+
+/// A [`Plugin`] which applies [`RecordMetrics`] to every operation.
+#[derive(Debug, Clone)]
+pub struct MetricsPlugin<R> {
+    recorder: R,
+}
+
+impl<R> MetricsPlugin<R> {
+    /// Creates a new `MetricsPlugin` which records metrics with `recorder`.
+    pub fn new(recorder: R) -> Self {
+        Self { recorder }
+    }
+}
+
+impl<Ser, Op, T, R> Plugin<Ser, Op, T> for MetricsPlugin<R>
+where
+    Op: OperationShape,
+    R: MetricsRecorder + Clone,
+{
+    type Output = RecordMetrics<T, R>;
+
+    fn apply(&self, input: T) -> Self::Output {
+        RecordMetrics::new(input, Op::ID, self.recorder.clone())
+    }
+}
+
+impl<R> HttpMarker for MetricsPlugin<R> {}
+
+/// An extension trait for applying [`MetricsPlugin`].
+pub trait MetricsExt<CurrentPlugin> {
+    /// Applies a [`RecordMetrics`] to every operation, recording the rate, errors and duration of
+    /// its requests with `recorder`. See [`RecordMetrics`] for more information.
+    fn record_metrics<R>(self, recorder: R) -> HttpPlugins<PluginStack<MetricsPlugin<R>, CurrentPlugin>>;
+}
+
+impl<CurrentPlugin> MetricsExt<CurrentPlugin> for HttpPlugins<CurrentPlugin> {
+    fn record_metrics<R>(self, recorder: R) -> HttpPlugins<PluginStack<MetricsPlugin<R>, CurrentPlugin>> {
+        self.push(MetricsPlugin::new(recorder))
+    }
+}
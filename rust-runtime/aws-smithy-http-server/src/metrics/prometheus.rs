@@ -0,0 +1,321 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A [`MetricsRecorder`] that keeps metrics in memory and renders them in the [Prometheus text
+//! exposition format], and a layer that serves them.
+//!
+//! The following metrics are recorded, labelled with the absolute shape ID of the `operation`:
+//!
+//! - `smithy_server_requests_total`: a counter of handled requests.
+//! - `smithy_server_errors_total`: a counter of failed requests, also labelled with the `kind` of
+//!   error (`modeled` or `framework`) and the name of the `error`.
+//! - `smithy_server_request_duration_seconds`: a histogram of request durations.
+//!
+//! [Prometheus text exposition format]: https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::future::{ready, Either, Ready};
+use http::{header, Method, Request, Response, StatusCode};
+use tower::{Layer, Service};
+
+use crate::body::{to_boxed, BoxBody};
+use crate::shape_id::ShapeId;
+
+use super::{MetricsRecorder, Outcome};
+
+/// The default upper bounds of the request duration histogram buckets, in seconds.
+pub const DEFAULT_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// The content type of the Prometheus text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Debug, Default)]
+struct OperationMetrics {
+    requests: u64,
+    errors: BTreeMap<(&'static str, String), u64>,
+    bucket_counts: Vec<u64>,
+    duration_sum: f64,
+}
+
+/// A [`MetricsRecorder`] that keeps metrics in memory, so that they can be scraped by Prometheus.
+///
+/// Clones share the same metrics, so a clone can be given to [`MetricsPlugin`](super::MetricsPlugin)
+/// and another to [`PrometheusExpositionLayer`].
+#[derive(Debug, Clone)]
+pub struct PrometheusRecorder {
+    buckets: Arc<[f64]>,
+    operations: Arc<Mutex<BTreeMap<&'static str, OperationMetrics>>>,
+}
+
+impl Default for PrometheusRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrometheusRecorder {
+    /// Creates a new recorder that uses [`DEFAULT_BUCKETS`] for the request duration histogram.
+    pub fn new() -> Self {
+        Self::with_buckets(DEFAULT_BUCKETS.to_vec())
+    }
+
+    /// Creates a new recorder with the given upper bounds for the request duration histogram
+    /// buckets, in seconds. A `+Inf` bucket is always added.
+    pub fn with_buckets(mut buckets: Vec<f64>) -> Self {
+        buckets.retain(|bound| bound.is_finite());
+        buckets.sort_by(|a, b| a.total_cmp(b));
+        buckets.dedup();
+        Self {
+            buckets: buckets.into(),
+            operations: Default::default(),
+        }
+    }
+
+    /// Renders the recorded metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let operations = self.operations.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str("# HELP smithy_server_requests_total Total number of requests handled by an operation.\n");
+        out.push_str("# TYPE smithy_server_requests_total counter\n");
+        for (operation, metrics) in operations.iter() {
+            let _ = writeln!(
+                out,
+                "smithy_server_requests_total{{operation=\"{}\"}} {}",
+                escape(operation),
+                metrics.requests
+            );
+        }
+
+        out.push_str("# HELP smithy_server_errors_total Total number of requests to an operation that failed.\n");
+        out.push_str("# TYPE smithy_server_errors_total counter\n");
+        for (operation, metrics) in operations.iter() {
+            for ((kind, error), count) in &metrics.errors {
+                let _ = writeln!(
+                    out,
+                    "smithy_server_errors_total{{operation=\"{}\",kind=\"{}\",error=\"{}\"}} {}",
+                    escape(operation),
+                    kind,
+                    escape(error),
+                    count
+                );
+            }
+        }
+
+        out.push_str(
+            "# HELP smithy_server_request_duration_seconds Time taken by an operation to produce a response.\n",
+        );
+        out.push_str("# TYPE smithy_server_request_duration_seconds histogram\n");
+        for (operation, metrics) in operations.iter() {
+            let operation = escape(operation);
+            let mut cumulative = 0;
+            for (bound, count) in self.buckets.iter().zip(&metrics.bucket_counts) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "smithy_server_request_duration_seconds_bucket{{operation=\"{operation}\",le=\"{bound}\"}} {cumulative}",
+                );
+            }
+            let _ = writeln!(
+                out,
+                "smithy_server_request_duration_seconds_bucket{{operation=\"{operation}\",le=\"+Inf\"}} {}",
+                metrics.requests
+            );
+            let _ = writeln!(
+                out,
+                "smithy_server_request_duration_seconds_sum{{operation=\"{operation}\"}} {}",
+                metrics.duration_sum
+            );
+            let _ = writeln!(
+                out,
+                "smithy_server_request_duration_seconds_count{{operation=\"{operation}\"}} {}",
+                metrics.requests
+            );
+        }
+
+        out
+    }
+}
+
+impl MetricsRecorder for PrometheusRecorder {
+    fn record(&self, operation: &ShapeId, outcome: Outcome<'_>, duration: Duration) {
+        let mut operations = self.operations.lock().unwrap();
+        let metrics = operations.entry(operation.absolute()).or_default();
+        metrics.requests += 1;
+
+        let error = match outcome {
+            Outcome::ModeledError { name } => Some(("modeled", name)),
+            Outcome::FrameworkError { name } => Some(("framework", name.unwrap_or("unknown"))),
+            _ => None,
+        };
+        if let Some((kind, name)) = error {
+            *metrics.errors.entry((kind, name.to_owned())).or_default() += 1;
+        }
+
+        let seconds = duration.as_secs_f64();
+        metrics.duration_sum += seconds;
+        metrics.bucket_counts.resize(self.buckets.len(), 0);
+        if let Some(bucket) = self.buckets.iter().position(|bound| seconds <= *bound) {
+            metrics.bucket_counts[bucket] += 1;
+        }
+    }
+}
+
+/// Escapes a label value, as required by the text exposition format.
+fn escape(value: &str) -> Cow<'_, str> {
+    if value.contains(['\\', '"', '\n']) {
+        Cow::Owned(value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// A [`tower::Layer`] used to apply [`PrometheusExpositionService`].
+#[derive(Debug, Clone)]
+pub struct PrometheusExpositionLayer {
+    path: Cow<'static, str>,
+    recorder: PrometheusRecorder,
+}
+
+impl PrometheusExpositionLayer {
+    /// Serves the metrics of `recorder` on `GET` requests to `path`.
+    pub fn new(path: impl Into<Cow<'static, str>>, recorder: PrometheusRecorder) -> Self {
+        Self {
+            path: path.into(),
+            recorder,
+        }
+    }
+}
+
+impl<S> Layer<S> for PrometheusExpositionLayer {
+    type Service = PrometheusExpositionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PrometheusExpositionService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// A middleware [`Service`] that responds to scrapes of the metrics endpoint, and passes all other
+/// requests to the inner service.
+#[derive(Debug, Clone)]
+pub struct PrometheusExpositionService<S> {
+    inner: S,
+    layer: PrometheusExpositionLayer,
+}
+
+impl<S, B> Service<Request<B>> for PrometheusExpositionService<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<Self::Response, Self::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        if req.method() == Method::GET && req.uri().path() == self.layer.path {
+            let response = Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, CONTENT_TYPE)
+                .body(to_boxed(self.layer.recorder.render()))
+                .expect("valid response");
+            Either::Left(ready(Ok(response)))
+        } else {
+            Either::Right(self.inner.call(req))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+
+    const GET_WIDGET: ShapeId = ShapeId::new("com.example#GetWidget", "com.example", "GetWidget");
+
+    #[test]
+    fn renders_recorded_metrics() {
+        let recorder = PrometheusRecorder::with_buckets(vec![0.1, 1.0]);
+        recorder.record(&GET_WIDGET, Outcome::Success, Duration::from_micros(62_500));
+        recorder.record(
+            &GET_WIDGET,
+            Outcome::ModeledError { name: "NotFound" },
+            Duration::from_millis(500),
+        );
+        recorder.record(
+            &GET_WIDGET,
+            Outcome::FrameworkError {
+                name: Some("SerializationException"),
+            },
+            Duration::from_secs(2),
+        );
+
+        let expected = "\
+# HELP smithy_server_requests_total Total number of requests handled by an operation.
+# TYPE smithy_server_requests_total counter
+smithy_server_requests_total{operation=\"com.example#GetWidget\"} 3
+# HELP smithy_server_errors_total Total number of requests to an operation that failed.
+# TYPE smithy_server_errors_total counter
+smithy_server_errors_total{operation=\"com.example#GetWidget\",kind=\"framework\",error=\"SerializationException\"} 1
+smithy_server_errors_total{operation=\"com.example#GetWidget\",kind=\"modeled\",error=\"NotFound\"} 1
+# HELP smithy_server_request_duration_seconds Time taken by an operation to produce a response.
+# TYPE smithy_server_request_duration_seconds histogram
+smithy_server_request_duration_seconds_bucket{operation=\"com.example#GetWidget\",le=\"0.1\"} 1
+smithy_server_request_duration_seconds_bucket{operation=\"com.example#GetWidget\",le=\"1\"} 2
+smithy_server_request_duration_seconds_bucket{operation=\"com.example#GetWidget\",le=\"+Inf\"} 3
+smithy_server_request_duration_seconds_sum{operation=\"com.example#GetWidget\"} 2.5625
+smithy_server_request_duration_seconds_count{operation=\"com.example#GetWidget\"} 3
+";
+        assert_eq!(expected, recorder.render());
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!("a\\\"b\\\\c\\nd", escape("a\"b\\c\nd"));
+    }
+
+    #[tokio::test]
+    async fn serves_metrics_and_passes_other_requests_through() {
+        let recorder = PrometheusRecorder::new();
+        recorder.record(&GET_WIDGET, Outcome::Success, Duration::from_millis(1));
+        let inner = service_fn(|_: Request<()>| async {
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .status(StatusCode::IM_A_TEAPOT)
+                    .body(to_boxed(""))
+                    .unwrap(),
+            )
+        });
+        let service = PrometheusExpositionLayer::new("/metrics", recorder).layer(inner);
+
+        let request = Request::get("/metrics").body(()).unwrap();
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(CONTENT_TYPE, response.headers()[header::CONTENT_TYPE]);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(std::str::from_utf8(&body)
+            .unwrap()
+            .contains("smithy_server_requests_total{operation=\"com.example#GetWidget\"} 1"));
+
+        let request = Request::get("/widgets").body(()).unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        assert_eq!(StatusCode::IM_A_TEAPOT, response.status());
+    }
+}
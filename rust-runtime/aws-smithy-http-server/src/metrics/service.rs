@@ -0,0 +1,177 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use futures_util::ready;
+use http::{Request, Response};
+use tower::Service;
+
+use crate::extension::{ModeledErrorExtension, RuntimeErrorExtension};
+use crate::shape_id::ShapeId;
+
+use super::{MetricsRecorder, Outcome};
+
+pin_project_lite::pin_project! {
+    /// The [`Future`] returned by [`RecordMetrics`].
+    pub struct RecordMetricsFuture<Fut, R> {
+        #[pin]
+        inner: Fut,
+        operation_id: ShapeId,
+        recorder: R,
+        start: Instant,
+    }
+}
+
+impl<Fut, R, B, E> Future for RecordMetricsFuture<Fut, R>
+where
+    Fut: Future<Output = Result<Response<B>, E>>,
+    R: MetricsRecorder,
+{
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = ready!(this.inner.poll(cx));
+        // Latency is measured with a monotonic clock; there is no time source to inject on servers.
+        #[allow(clippy::disallowed_methods)]
+        let duration = this.start.elapsed();
+
+        let outcome = match &result {
+            Ok(response) => outcome(response),
+            Err(_) => Outcome::FrameworkError { name: None },
+        };
+        this.recorder.record(this.operation_id, outcome, duration);
+
+        Poll::Ready(result)
+    }
+}
+
+/// Determines the [`Outcome`] of a request from the extensions the framework adds to its response.
+fn outcome<B>(response: &Response<B>) -> Outcome<'_> {
+    let extensions = response.extensions();
+    if let Some(modeled_error) = extensions.get::<ModeledErrorExtension>() {
+        Outcome::ModeledError { name: modeled_error }
+    } else if let Some(runtime_error) = extensions.get::<RuntimeErrorExtension>() {
+        Outcome::FrameworkError {
+            name: Some(runtime_error.as_str()),
+        }
+    } else if response.status().is_server_error() {
+        Outcome::FrameworkError { name: None }
+    } else {
+        Outcome::Success
+    }
+}
+
+/// A middleware [`Service`] that records the rate, errors and duration of the requests handled by
+/// an operation with a [`MetricsRecorder`].
+///
+/// The duration of a request is measured from when it's received until its response is ready to be
+/// sent; the time it takes to send the response body isn't included.
+#[derive(Debug, Clone)]
+pub struct RecordMetrics<S, R> {
+    inner: S,
+    operation_id: ShapeId,
+    recorder: R,
+}
+
+impl<S, R> RecordMetrics<S, R> {
+    /// Creates a new `RecordMetrics` for the operation identified by `operation_id`.
+    pub fn new(inner: S, operation_id: ShapeId, recorder: R) -> Self {
+        Self {
+            inner,
+            operation_id,
+            recorder,
+        }
+    }
+}
+
+impl<S, R, U, V> Service<Request<U>> for RecordMetrics<S, R>
+where
+    S: Service<Request<U>, Response = Response<V>>,
+    R: MetricsRecorder + Clone,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = RecordMetricsFuture<S::Future, R>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<U>) -> Self::Future {
+        #[allow(clippy::disallowed_methods)]
+        let start = Instant::now();
+        RecordMetricsFuture {
+            start,
+            inner: self.inner.call(request),
+            operation_id: self.operation_id.clone(),
+            recorder: self.recorder.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+
+    const GET_WIDGET: ShapeId = ShapeId::new("com.example#GetWidget", "com.example", "GetWidget");
+
+    #[derive(Debug, Clone, Default)]
+    struct Recorded(Arc<Mutex<Vec<String>>>);
+
+    impl MetricsRecorder for Recorded {
+        fn record(&self, operation: &ShapeId, outcome: Outcome<'_>, _duration: Duration) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{} {:?}", operation.name(), outcome));
+        }
+    }
+
+    async fn record(response: Response<()>) -> String {
+        let recorded = Recorded::default();
+        let response = Arc::new(Mutex::new(Some(response)));
+        let inner = service_fn(move |_: Request<()>| {
+            let response = response.lock().unwrap().take().unwrap();
+            async move { Ok::<_, Infallible>(response) }
+        });
+        RecordMetrics::new(inner, GET_WIDGET, recorded.clone())
+            .oneshot(Request::new(()))
+            .await
+            .unwrap();
+        let recorded = recorded.0.lock().unwrap();
+        recorded[0].clone()
+    }
+
+    #[tokio::test]
+    async fn classifies_outcomes() {
+        assert_eq!("GetWidget Success", record(Response::new(())).await);
+
+        let mut response = Response::new(());
+        response.extensions_mut().insert(ModeledErrorExtension::new("NotFound"));
+        assert_eq!("GetWidget ModeledError { name: \"NotFound\" }", record(response).await);
+
+        let mut response = Response::new(());
+        response
+            .extensions_mut()
+            .insert(RuntimeErrorExtension::new("SerializationException".to_owned()));
+        assert_eq!(
+            "GetWidget FrameworkError { name: Some(\"SerializationException\") }",
+            record(response).await
+        );
+    }
+}
@@ -5,19 +5,27 @@
 
 use crate::protocol::aws_json_11::AwsJson1_1;
 use crate::response::IntoResponse;
-use crate::runtime_error::{InternalFailureException, INVALID_HTTP_RESPONSE_FOR_RUNTIME_ERROR_PANIC_MESSAGE};
+use crate::runtime_error::{
+    AccessDeniedException, InternalFailureException, OperationTimeoutException, PayloadTooLargeException,
+    ThrottlingException, INVALID_HTTP_RESPONSE_FOR_RUNTIME_ERROR_PANIC_MESSAGE,
+};
 use crate::{extension::RuntimeErrorExtension, protocol::aws_json_10::AwsJson1_0};
 use http::StatusCode;
 
 use super::rejection::{RequestRejection, ResponseRejection};
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum RuntimeError {
     Serialization(crate::Error),
     InternalFailure(crate::Error),
     NotAcceptable,
     UnsupportedMediaType,
     Validation(String),
+    AccessDenied,
+    PayloadTooLarge,
+    OperationTimeout,
+    Throttling,
 }
 
 impl RuntimeError {
@@ -28,6 +36,10 @@ impl RuntimeError {
             Self::NotAcceptable => "NotAcceptableException",
             Self::UnsupportedMediaType => "UnsupportedMediaTypeException",
             Self::Validation(_) => "ValidationException",
+            Self::AccessDenied => "AccessDeniedException",
+            Self::PayloadTooLarge => "PayloadTooLargeException",
+            Self::OperationTimeout => "OperationTimeoutException",
+            Self::Throttling => "ThrottlingException",
         }
     }
 
@@ -38,6 +50,10 @@ impl RuntimeError {
             Self::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
             Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
             Self::Validation(_) => StatusCode::BAD_REQUEST,
+            Self::AccessDenied => StatusCode::FORBIDDEN,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::OperationTimeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Throttling => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -48,12 +64,60 @@ impl IntoResponse<AwsJson1_0> for InternalFailureException {
     }
 }
 
+impl IntoResponse<AwsJson1_0> for AccessDeniedException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<AwsJson1_0>::into_response(RuntimeError::AccessDenied)
+    }
+}
+
+impl IntoResponse<AwsJson1_0> for PayloadTooLargeException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<AwsJson1_0>::into_response(RuntimeError::PayloadTooLarge)
+    }
+}
+
+impl IntoResponse<AwsJson1_0> for OperationTimeoutException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<AwsJson1_0>::into_response(RuntimeError::OperationTimeout)
+    }
+}
+
+impl IntoResponse<AwsJson1_0> for ThrottlingException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<AwsJson1_0>::into_response(RuntimeError::Throttling)
+    }
+}
+
 impl IntoResponse<AwsJson1_1> for InternalFailureException {
     fn into_response(self) -> http::Response<crate::body::BoxBody> {
         IntoResponse::<AwsJson1_1>::into_response(RuntimeError::InternalFailure(crate::Error::new(String::new())))
     }
 }
 
+impl IntoResponse<AwsJson1_1> for AccessDeniedException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<AwsJson1_1>::into_response(RuntimeError::AccessDenied)
+    }
+}
+
+impl IntoResponse<AwsJson1_1> for PayloadTooLargeException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<AwsJson1_1>::into_response(RuntimeError::PayloadTooLarge)
+    }
+}
+
+impl IntoResponse<AwsJson1_1> for OperationTimeoutException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<AwsJson1_1>::into_response(RuntimeError::OperationTimeout)
+    }
+}
+
+impl IntoResponse<AwsJson1_1> for ThrottlingException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<AwsJson1_1>::into_response(RuntimeError::Throttling)
+    }
+}
+
 impl IntoResponse<AwsJson1_0> for RuntimeError {
     fn into_response(self) -> http::Response<crate::body::BoxBody> {
         let res = http::Response::builder()
@@ -99,6 +163,7 @@ impl From<RequestRejection> for RuntimeError {
     fn from(err: RequestRejection) -> Self {
         match err {
             RequestRejection::ConstraintViolation(reason) => Self::Validation(reason),
+            RequestRejection::PayloadTooLarge(_) => Self::PayloadTooLarge,
             _ => Self::Serialization(crate::Error::new(err)),
         }
     }
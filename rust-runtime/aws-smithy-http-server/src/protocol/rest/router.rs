@@ -90,6 +90,16 @@ where
             Err(Error::MethodNotAllowed)
         }
     }
+
+    fn allowed_methods(&self, request: &http::Request<B>) -> Vec<http::Method> {
+        let mut methods = Vec::new();
+        for (request_spec, _route) in &self.routes {
+            if request_spec.matches(request) != Match::No && !methods.contains(request_spec.method()) {
+                methods.push(request_spec.method().clone());
+            }
+        }
+        methods
+    }
 }
 
 impl<S> FromIterator<(RequestSpec, S)> for RestRouter<S> {
@@ -104,12 +104,18 @@ pub enum ResponseRejection {
 ///
 /// The variants are _roughly_ sorted in the order in which the HTTP request is processed.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum RequestRejection {
     /// Used when failing to convert non-streaming requests into a byte slab with
     /// `hyper::body::to_bytes`.
     #[error("error converting non-streaming body to bytes: {0}")]
     BufferHttpBodyBytes(crate::Error),
 
+    /// Used when the request body is larger than allowed by [`crate::body_limit`], and reading it
+    /// was cut short.
+    #[error("request body is too large: {0}")]
+    PayloadTooLarge(crate::Error),
+
     /// Used when the request contained an `Accept` header with a MIME type, and the server cannot
     /// return a response body adhering to that MIME type.
     #[error("request contains invalid value for `Accept` header")]
@@ -205,8 +211,8 @@ impl From<nom::Err<nom::error::Error<&str>>> for RequestRejection {
 // need this converter for when we convert the body into bytes in the framework, since protocol
 // tests use `[crate::body::Body]` as their body type when constructing requests (and almost
 // everyone will run a Hyper-based server in their services).
-convert_to_request_rejection!(hyper::Error, BufferHttpBodyBytes);
+convert_body_error_to_request_rejection!(hyper::Error);
 
 // Useful in general, but it also required in order to accept Lambda HTTP requests using
 // `Router<lambda_http::Body>` since `lambda_http::Error` is a type alias for `Box<dyn Error + ..>`.
-convert_to_request_rejection!(Box<dyn std::error::Error + Send + Sync + 'static>, BufferHttpBodyBytes);
+convert_body_error_to_request_rejection!(Box<dyn std::error::Error + Send + Sync + 'static>);
@@ -34,11 +34,15 @@ use super::rejection::ResponseRejection;
 use super::RestJson1;
 use crate::extension::RuntimeErrorExtension;
 use crate::response::IntoResponse;
-use crate::runtime_error::InternalFailureException;
 use crate::runtime_error::INVALID_HTTP_RESPONSE_FOR_RUNTIME_ERROR_PANIC_MESSAGE;
+use crate::runtime_error::{
+    AccessDeniedException, InternalFailureException, OperationTimeoutException, PayloadTooLargeException,
+    ThrottlingException,
+};
 use http::StatusCode;
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum RuntimeError {
     /// Request failed to deserialize or response failed to serialize.
     Serialization(crate::Error),
@@ -56,6 +60,15 @@ pub enum RuntimeError {
     /// Operation input contains data that does not adhere to the modeled [constraint traits].
     /// [constraint traits]: <https://awslabs.github.io/smithy/2.0/spec/constraint-traits.html>
     Validation(String),
+    /// The request failed authentication, for example because its signature could not be
+    /// verified.
+    AccessDenied,
+    /// The request body is larger than allowed by [`crate::body_limit`].
+    PayloadTooLarge,
+    /// The operation did not complete before the deadline set by [`crate::timeout`].
+    OperationTimeout,
+    /// The request was throttled by [`crate::rate_limit`].
+    Throttling,
 }
 
 impl RuntimeError {
@@ -69,6 +82,10 @@ impl RuntimeError {
             Self::NotAcceptable => "NotAcceptableException",
             Self::UnsupportedMediaType => "UnsupportedMediaTypeException",
             Self::Validation(_) => "ValidationException",
+            Self::AccessDenied => "AccessDeniedException",
+            Self::PayloadTooLarge => "PayloadTooLargeException",
+            Self::OperationTimeout => "OperationTimeoutException",
+            Self::Throttling => "ThrottlingException",
         }
     }
 
@@ -79,6 +96,10 @@ impl RuntimeError {
             Self::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
             Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
             Self::Validation(_) => StatusCode::BAD_REQUEST,
+            Self::AccessDenied => StatusCode::FORBIDDEN,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::OperationTimeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Throttling => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -89,6 +110,30 @@ impl IntoResponse<RestJson1> for InternalFailureException {
     }
 }
 
+impl IntoResponse<RestJson1> for AccessDeniedException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RestJson1>::into_response(RuntimeError::AccessDenied)
+    }
+}
+
+impl IntoResponse<RestJson1> for PayloadTooLargeException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RestJson1>::into_response(RuntimeError::PayloadTooLarge)
+    }
+}
+
+impl IntoResponse<RestJson1> for OperationTimeoutException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RestJson1>::into_response(RuntimeError::OperationTimeout)
+    }
+}
+
+impl IntoResponse<RestJson1> for ThrottlingException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RestJson1>::into_response(RuntimeError::Throttling)
+    }
+}
+
 impl IntoResponse<RestJson1> for RuntimeError {
     fn into_response(self) -> http::Response<crate::body::BoxBody> {
         let res = http::Response::builder()
@@ -118,6 +163,7 @@ impl From<RequestRejection> for RuntimeError {
         match err {
             RequestRejection::MissingContentType(_reason) => Self::UnsupportedMediaType,
             RequestRejection::ConstraintViolation(reason) => Self::Validation(reason),
+            RequestRejection::PayloadTooLarge(_) => Self::PayloadTooLarge,
             RequestRejection::NotAcceptable => Self::NotAcceptable,
             _ => Self::Serialization(crate::Error::new(err)),
         }
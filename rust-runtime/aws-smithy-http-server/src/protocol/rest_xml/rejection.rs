@@ -25,10 +25,14 @@ pub enum ResponseRejection {
 }
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum RequestRejection {
     #[error("error converting non-streaming body to bytes: {0}")]
     BufferHttpBodyBytes(crate::Error),
 
+    #[error("request body is too large: {0}")]
+    PayloadTooLarge(crate::Error),
+
     #[error("request contains invalid value for `Accept` header")]
     NotAcceptable,
 
@@ -77,5 +81,5 @@ impl From<nom::Err<nom::error::Error<&str>>> for RequestRejection {
     }
 }
 
-convert_to_request_rejection!(hyper::Error, BufferHttpBodyBytes);
-convert_to_request_rejection!(Box<dyn std::error::Error + Send + Sync + 'static>, BufferHttpBodyBytes);
+convert_body_error_to_request_rejection!(hyper::Error);
+convert_body_error_to_request_rejection!(Box<dyn std::error::Error + Send + Sync + 'static>);
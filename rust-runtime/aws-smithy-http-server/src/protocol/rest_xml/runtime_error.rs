@@ -5,19 +5,27 @@
 
 use crate::protocol::rest_xml::RestXml;
 use crate::response::IntoResponse;
-use crate::runtime_error::InternalFailureException;
+use crate::runtime_error::{
+    AccessDeniedException, InternalFailureException, OperationTimeoutException, PayloadTooLargeException,
+    ThrottlingException,
+};
 use crate::{extension::RuntimeErrorExtension, runtime_error::INVALID_HTTP_RESPONSE_FOR_RUNTIME_ERROR_PANIC_MESSAGE};
 use http::StatusCode;
 
 use super::rejection::{RequestRejection, ResponseRejection};
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum RuntimeError {
     Serialization(crate::Error),
     InternalFailure(crate::Error),
     NotAcceptable,
     UnsupportedMediaType,
     Validation(String),
+    AccessDenied,
+    PayloadTooLarge,
+    OperationTimeout,
+    Throttling,
 }
 
 impl RuntimeError {
@@ -28,6 +36,10 @@ impl RuntimeError {
             Self::NotAcceptable => "NotAcceptableException",
             Self::UnsupportedMediaType => "UnsupportedMediaTypeException",
             Self::Validation(_) => "ValidationException",
+            Self::AccessDenied => "AccessDeniedException",
+            Self::PayloadTooLarge => "PayloadTooLargeException",
+            Self::OperationTimeout => "OperationTimeoutException",
+            Self::Throttling => "ThrottlingException",
         }
     }
 
@@ -38,6 +50,10 @@ impl RuntimeError {
             Self::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
             Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
             Self::Validation(_) => StatusCode::BAD_REQUEST,
+            Self::AccessDenied => StatusCode::FORBIDDEN,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::OperationTimeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Throttling => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -48,6 +64,30 @@ impl IntoResponse<RestXml> for InternalFailureException {
     }
 }
 
+impl IntoResponse<RestXml> for AccessDeniedException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RestXml>::into_response(RuntimeError::AccessDenied)
+    }
+}
+
+impl IntoResponse<RestXml> for PayloadTooLargeException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RestXml>::into_response(RuntimeError::PayloadTooLarge)
+    }
+}
+
+impl IntoResponse<RestXml> for OperationTimeoutException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RestXml>::into_response(RuntimeError::OperationTimeout)
+    }
+}
+
+impl IntoResponse<RestXml> for ThrottlingException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RestXml>::into_response(RuntimeError::Throttling)
+    }
+}
+
 impl IntoResponse<RestXml> for RuntimeError {
     fn into_response(self) -> http::Response<crate::body::BoxBody> {
         let res = http::Response::builder()
@@ -73,6 +113,7 @@ impl From<RequestRejection> for RuntimeError {
         match err {
             RequestRejection::MissingContentType(_reason) => Self::UnsupportedMediaType,
             RequestRejection::ConstraintViolation(reason) => Self::Validation(reason),
+            RequestRejection::PayloadTooLarge(_) => Self::PayloadTooLarge,
             _ => Self::Serialization(crate::Error::new(err)),
         }
     }
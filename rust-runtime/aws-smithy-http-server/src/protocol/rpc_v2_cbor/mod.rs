@@ -0,0 +1,19 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+pub mod rejection;
+pub mod router;
+pub mod runtime_error;
+
+/// [Smithy RPC v2 CBOR Protocol](https://smithy.io/2.0/additional-specs/protocols/smithy-rpc-v2.html).
+pub struct RpcV2Cbor;
+
+impl RpcV2Cbor {
+    /// The value of the `smithy-protocol` header requests and responses must carry.
+    pub const PROTOCOL_HEADER_VALUE: &'static str = "rpc-v2-cbor";
+
+    /// The media type of request and response bodies.
+    pub const CONTENT_TYPE: &'static str = "application/cbor";
+}
@@ -0,0 +1,233 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::convert::Infallible;
+
+use http::header::ToStrError;
+use thiserror::Error;
+use tower::Layer;
+use tower::Service;
+
+use crate::body::BoxBody;
+use crate::extension::RuntimeErrorExtension;
+use crate::response::IntoResponse;
+use crate::routing::tiny_map::TinyMap;
+use crate::routing::{method_disallowed, Route, Router, UNKNOWN_OPERATION_EXCEPTION};
+
+use super::runtime_error::error_type_body;
+use super::RpcV2Cbor;
+
+/// An RPC v2 CBOR routing error.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Method was not `POST`.
+    #[error("method not POST")]
+    MethodNotAllowed,
+    /// Missing the `smithy-protocol` header, or its value is not `rpc-v2-cbor`.
+    #[error("the \"smithy-protocol\" header is missing or is not \"rpc-v2-cbor\"")]
+    InvalidProtocolHeader,
+    /// Unable to parse header into UTF-8.
+    #[error("failed to parse header: {0}")]
+    InvalidHeader(ToStrError),
+    /// The request has an `x-amz-target` header, which is forbidden by the protocol.
+    #[error("requests must not have an \"x-amz-target\" header")]
+    ForbiddenHeader,
+    /// The URI path does not end with `/service/{ServiceName}/operation/{OperationName}`.
+    #[error("URI path does not match \"/service/{{ServiceName}}/operation/{{OperationName}}\"")]
+    InvalidPath,
+    /// Operation not found.
+    #[error("operation not found")]
+    NotFound,
+}
+
+// This constant determines when the `TinyMap` implementation switches from being a `Vec` to a
+// `HashMap`. This is chosen to be 15 as a result of the discussion around
+// https://github.com/smithy-lang/smithy-rs/pull/1429#issuecomment-1147516546
+const ROUTE_CUTOFF: usize = 15;
+
+/// A [`Router`] supporting the [Smithy RPC v2 CBOR] protocol.
+///
+/// Routes are keyed by `{ServiceName}.{OperationName}`, and requests are matched by the last
+/// `/service/{ServiceName}/operation/{OperationName}` segments of their URI path, so services can be
+/// mounted under a path prefix.
+///
+/// [Smithy RPC v2 CBOR]: https://smithy.io/2.0/additional-specs/protocols/smithy-rpc-v2.html
+#[derive(Debug, Clone)]
+pub struct RpcV2CborRouter<S> {
+    routes: TinyMap<String, S, ROUTE_CUTOFF>,
+}
+
+impl<S> RpcV2CborRouter<S> {
+    /// Applies a [`Layer`] uniformly to all routes.
+    pub fn layer<L>(self, layer: L) -> RpcV2CborRouter<L::Service>
+    where
+        L: Layer<S>,
+    {
+        RpcV2CborRouter {
+            routes: self
+                .routes
+                .into_iter()
+                .map(|(key, route)| (key, layer.layer(route)))
+                .collect(),
+        }
+    }
+
+    /// Applies type erasure to the inner route using [`Route::new`].
+    pub fn boxed<B>(self) -> RpcV2CborRouter<Route<B>>
+    where
+        S: Service<http::Request<B>, Response = http::Response<BoxBody>, Error = Infallible>,
+        S: Send + Clone + 'static,
+        S::Future: Send + 'static,
+    {
+        RpcV2CborRouter {
+            routes: self.routes.into_iter().map(|(key, s)| (key, Route::new(s))).collect(),
+        }
+    }
+}
+
+/// Extracts the service and operation names from a path ending in
+/// `/service/{ServiceName}/operation/{OperationName}`.
+fn parse_path(path: &str) -> Option<(&str, &str)> {
+    let (prefix, operation) = path.rsplit_once("/operation/")?;
+    let (_, service) = prefix.rsplit_once("/service/")?;
+    let is_segment = |s: &str| !s.is_empty() && !s.contains('/');
+    (is_segment(service) && is_segment(operation)).then_some((service, operation))
+}
+
+impl<B, S> Router<B> for RpcV2CborRouter<S>
+where
+    S: Clone,
+{
+    type Service = S;
+    type Error = Error;
+
+    fn match_route(&self, request: &http::Request<B>) -> Result<S, Self::Error> {
+        // Only `Method::POST` is allowed.
+        if request.method() != http::Method::POST {
+            return Err(Error::MethodNotAllowed);
+        }
+
+        let headers = request.headers();
+        let protocol = headers.get("smithy-protocol").ok_or(Error::InvalidProtocolHeader)?;
+        if protocol.to_str().map_err(Error::InvalidHeader)? != RpcV2Cbor::PROTOCOL_HEADER_VALUE {
+            return Err(Error::InvalidProtocolHeader);
+        }
+        if headers.contains_key("x-amz-target") {
+            return Err(Error::ForbiddenHeader);
+        }
+
+        let (service, operation) = parse_path(request.uri().path()).ok_or(Error::InvalidPath)?;
+
+        // Lookup in the `TinyMap` for a route for the operation.
+        let route = self
+            .routes
+            .get(format!("{service}.{operation}").as_str())
+            .ok_or(Error::NotFound)?;
+        Ok(route.clone())
+    }
+
+    fn allowed_methods(&self, request: &http::Request<B>) -> Vec<http::Method> {
+        match parse_path(request.uri().path()) {
+            Some((service, operation)) if self.routes.get(format!("{service}.{operation}").as_str()).is_some() => {
+                vec![http::Method::POST]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl<S> FromIterator<(String, S)> for RpcV2CborRouter<S> {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = (String, S)>>(iter: T) -> Self {
+        Self {
+            routes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoResponse<RpcV2Cbor> for Error {
+    fn into_response(self) -> http::Response<BoxBody> {
+        match self {
+            Error::MethodNotAllowed => method_disallowed(),
+            _ => http::Response::builder()
+                .status(http::StatusCode::NOT_FOUND)
+                .header(http::header::CONTENT_TYPE, RpcV2Cbor::CONTENT_TYPE)
+                .header("smithy-protocol", RpcV2Cbor::PROTOCOL_HEADER_VALUE)
+                .extension(RuntimeErrorExtension::new(
+                    UNKNOWN_OPERATION_EXCEPTION.to_string(),
+                ))
+                .body(crate::body::to_boxed(error_type_body(UNKNOWN_OPERATION_EXCEPTION)))
+                .expect("invalid HTTP response for RPC v2 CBOR routing error; please file a bug report under https://github.com/smithy-lang/smithy-rs/issues"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{protocol::test_helpers::req, routing::Router};
+
+    use http::{HeaderMap, HeaderValue, Method};
+    use pretty_assertions::assert_eq;
+
+    fn headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("smithy-protocol", HeaderValue::from_static("rpc-v2-cbor"));
+        headers
+    }
+
+    #[test]
+    fn parses_paths() {
+        assert_eq!(
+            Some(("Service", "Operation")),
+            parse_path("/service/Service/operation/Operation")
+        );
+        assert_eq!(
+            Some(("Service", "Operation")),
+            parse_path("/prefix/service/Service/operation/Operation")
+        );
+        assert_eq!(None, parse_path("/service/Service/operation/"));
+        assert_eq!(None, parse_path("/service/Service/operation/Operation/suffix"));
+        assert_eq!(None, parse_path("/operation/Operation"));
+    }
+
+    #[tokio::test]
+    async fn simple_routing() {
+        let router: RpcV2CborRouter<_> = vec![("Service.Operation".to_string(), ())].into_iter().collect();
+        let uri = "/service/Service/operation/Operation";
+
+        // Valid requests, should match.
+        router.match_route(&req(&Method::POST, uri, Some(headers()))).unwrap();
+        router
+            .match_route(&req(
+                &Method::POST,
+                "/prefix/service/Service/operation/Operation?query",
+                Some(headers()),
+            ))
+            .unwrap();
+
+        // No headers, should return `InvalidProtocolHeader`.
+        let res = router.match_route(&req(&Method::POST, uri, None));
+        assert_eq!(res.unwrap_err().to_string(), Error::InvalidProtocolHeader.to_string());
+
+        // `x-amz-target` is forbidden.
+        let mut forbidden = headers();
+        forbidden.insert("x-amz-target", HeaderValue::from_static("Service.Operation"));
+        let res = router.match_route(&req(&Method::POST, uri, Some(forbidden)));
+        assert_eq!(res.unwrap_err().to_string(), Error::ForbiddenHeader.to_string());
+
+        // Wrong HTTP method, should return `MethodNotAllowed`.
+        let res = router.match_route(&req(&Method::GET, uri, Some(headers())));
+        assert_eq!(res.unwrap_err().to_string(), Error::MethodNotAllowed.to_string());
+
+        // Unknown operation, should return `NotFound`.
+        let res = router.match_route(&req(
+            &Method::POST,
+            "/service/Service/operation/Unknown",
+            Some(headers()),
+        ));
+        assert_eq!(res.unwrap_err().to_string(), Error::NotFound.to_string());
+    }
+}
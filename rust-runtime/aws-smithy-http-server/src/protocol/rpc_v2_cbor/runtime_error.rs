@@ -0,0 +1,134 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::extension::RuntimeErrorExtension;
+use crate::response::IntoResponse;
+use crate::runtime_error::{
+    AccessDeniedException, InternalFailureException, OperationTimeoutException, PayloadTooLargeException,
+    ThrottlingException, INVALID_HTTP_RESPONSE_FOR_RUNTIME_ERROR_PANIC_MESSAGE,
+};
+use aws_smithy_cbor::Encoder;
+use http::StatusCode;
+
+use super::rejection::{RequestRejection, ResponseRejection};
+use super::RpcV2Cbor;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RuntimeError {
+    Serialization(crate::Error),
+    InternalFailure(crate::Error),
+    NotAcceptable,
+    UnsupportedMediaType,
+    /// The payload is the CBOR-encoded `ValidationException`.
+    Validation(Vec<u8>),
+    AccessDenied,
+    PayloadTooLarge,
+    OperationTimeout,
+    Throttling,
+}
+
+impl RuntimeError {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Serialization(_) => "SerializationException",
+            Self::InternalFailure(_) => "InternalFailureException",
+            Self::NotAcceptable => "NotAcceptableException",
+            Self::UnsupportedMediaType => "UnsupportedMediaTypeException",
+            Self::Validation(_) => "ValidationException",
+            Self::AccessDenied => "AccessDeniedException",
+            Self::PayloadTooLarge => "PayloadTooLargeException",
+            Self::OperationTimeout => "OperationTimeoutException",
+            Self::Throttling => "ThrottlingException",
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Serialization(_) => StatusCode::BAD_REQUEST,
+            Self::InternalFailure(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Validation(_) => StatusCode::BAD_REQUEST,
+            Self::AccessDenied => StatusCode::FORBIDDEN,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::OperationTimeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Throttling => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+}
+
+/// Encodes a body only identifying the error type, as `{"__type": name}`.
+pub(crate) fn error_type_body(name: &str) -> Vec<u8> {
+    let mut encoder = Encoder::new(Vec::new());
+    encoder.begin_map().str("__type").str(name).end();
+    encoder.into_writer()
+}
+
+impl IntoResponse<RpcV2Cbor> for InternalFailureException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RpcV2Cbor>::into_response(RuntimeError::InternalFailure(crate::Error::new(String::new())))
+    }
+}
+
+impl IntoResponse<RpcV2Cbor> for AccessDeniedException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RpcV2Cbor>::into_response(RuntimeError::AccessDenied)
+    }
+}
+
+impl IntoResponse<RpcV2Cbor> for PayloadTooLargeException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RpcV2Cbor>::into_response(RuntimeError::PayloadTooLarge)
+    }
+}
+
+impl IntoResponse<RpcV2Cbor> for OperationTimeoutException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RpcV2Cbor>::into_response(RuntimeError::OperationTimeout)
+    }
+}
+
+impl IntoResponse<RpcV2Cbor> for ThrottlingException {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        IntoResponse::<RpcV2Cbor>::into_response(RuntimeError::Throttling)
+    }
+}
+
+impl IntoResponse<RpcV2Cbor> for RuntimeError {
+    fn into_response(self) -> http::Response<crate::body::BoxBody> {
+        let res = http::Response::builder()
+            .status(self.status_code())
+            .header("Content-Type", RpcV2Cbor::CONTENT_TYPE)
+            .header("smithy-protocol", RpcV2Cbor::PROTOCOL_HEADER_VALUE)
+            .extension(RuntimeErrorExtension::new(self.name().to_string()));
+
+        let body = match self {
+            RuntimeError::Validation(reason) => crate::body::to_boxed(reason),
+            _ => crate::body::to_boxed(error_type_body(self.name())),
+        };
+
+        res.body(body)
+            .expect(INVALID_HTTP_RESPONSE_FOR_RUNTIME_ERROR_PANIC_MESSAGE)
+    }
+}
+
+impl From<ResponseRejection> for RuntimeError {
+    fn from(err: ResponseRejection) -> Self {
+        Self::Serialization(crate::Error::new(err))
+    }
+}
+
+impl From<RequestRejection> for RuntimeError {
+    fn from(err: RequestRejection) -> Self {
+        match err {
+            RequestRejection::MissingContentType(_reason) => Self::UnsupportedMediaType,
+            RequestRejection::ConstraintViolation(reason) => Self::Validation(reason),
+            RequestRejection::PayloadTooLarge(_) => Self::PayloadTooLarge,
+            RequestRejection::NotAcceptable => Self::NotAcceptable,
+            _ => Self::Serialization(crate::Error::new(err)),
+        }
+    }
+}
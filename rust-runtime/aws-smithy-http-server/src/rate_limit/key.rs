@@ -0,0 +1,126 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::fmt;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::net::{IpAddr, SocketAddr};
+
+use http::header::{HeaderName, HeaderValue};
+use http::Request;
+
+use crate::request::connect_info::ConnectInfo;
+
+/// Identifies the caller of a request, so that [`RateLimitPlugin`](super::RateLimitPlugin) can
+/// throttle callers separately.
+pub trait CallerKey: Clone + Send + Sync + 'static {
+    /// The key callers are told apart by.
+    type Key: Hash + Eq + Clone + Send + Sync + 'static;
+
+    /// Returns the key of the caller of `request`, if it can be identified.
+    fn caller_key<B>(&self, request: &Request<B>) -> Option<Self::Key>;
+}
+
+/// Identifies callers by their IP address.
+///
+/// This requires the [`ConnectInfo<SocketAddr>`](ConnectInfo) extension, which is inserted when the
+/// service is run with
+/// [`IntoMakeServiceWithConnectInfo`](crate::routing::IntoMakeServiceWithConnectInfo).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PeerIp;
+
+impl CallerKey for PeerIp {
+    type Key = IpAddr;
+
+    fn caller_key<B>(&self, request: &Request<B>) -> Option<Self::Key> {
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    }
+}
+
+/// Identifies callers by the value of a header, such as an API key or a tenant ID.
+#[derive(Debug, Clone)]
+pub struct FromHeader {
+    name: HeaderName,
+}
+
+impl FromHeader {
+    /// Identifies callers by the value of the header `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: HeaderName::from_static(name),
+        }
+    }
+}
+
+impl From<HeaderName> for FromHeader {
+    fn from(name: HeaderName) -> Self {
+        Self { name }
+    }
+}
+
+impl CallerKey for FromHeader {
+    type Key = HeaderValue;
+
+    fn caller_key<B>(&self, request: &Request<B>) -> Option<Self::Key> {
+        request.headers().get(&self.name).cloned()
+    }
+}
+
+/// Identifies callers by an extension of type `T`, such as the identity inserted by an
+/// authentication layer, which is mapped to a key by a function.
+pub struct FromExtension<T, K, F> {
+    key: F,
+    _extension: PhantomData<fn(&T) -> K>,
+}
+
+impl<T, K, F> FromExtension<T, K, F>
+where
+    F: Fn(&T) -> K,
+{
+    /// Identifies callers by the key `key` returns for the extension of type `T`.
+    pub fn new(key: F) -> Self {
+        Self {
+            key,
+            _extension: PhantomData,
+        }
+    }
+}
+
+impl<T, K, F: Clone> Clone for FromExtension<T, K, F> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            _extension: PhantomData,
+        }
+    }
+}
+
+impl<T, K, F> fmt::Debug for FromExtension<T, K, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromExtension")
+            .field("extension", &std::any::type_name::<T>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, K, F> CallerKey for FromExtension<T, K, F>
+where
+    T: Send + Sync + 'static,
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+    F: Fn(&T) -> K + Clone + Send + Sync + 'static,
+{
+    type Key = K;
+
+    fn caller_key<B>(&self, request: &Request<B>) -> Option<Self::Key> {
+        request.extensions().get::<T>().map(&self.key)
+    }
+}
@@ -0,0 +1,153 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+#![deny(missing_docs, missing_debug_implementations)]
+
+//! Provides [`RateLimitPlugin`], which throttles callers with per-operation token buckets, and
+//! [`LoadShedPlugin`], which sheds requests by priority when the server is overloaded.
+//!
+//! # Rate limiting
+//!
+//! [`RateLimitPlugin`] gives every caller of every operation a token bucket, refilled according to
+//! a [`Quota`]. Callers are told apart by a [`CallerKey`], such as their IP address
+//! ([`PeerIp`]), a header ([`FromHeader`]), or an identity that was inserted in the request
+//! extensions by an authentication layer ([`FromExtension`]). Requests without a caller key share
+//! the same bucket, so a single client without a caller key can throttle every other one. Their
+//! quota can be set separately with [`RateLimitPlugin::anonymous_quota`].
+//!
+//! # Load shedding
+//!
+//! [`LoadShedPlugin`] bounds the number of requests in flight across every operation, and rejects
+//! requests of lower [`Priority`] first. Requests of [`Priority::Critical`] operations are never
+//! shed, so that health checks keep passing when they are modeled as operations. Health checks
+//! answered before routing, for example by
+//! [`AlbHealthCheckLayer`](crate::layer::alb_health_check::AlbHealthCheckLayer), are not affected
+//! by either plugin.
+//!
+//! Throttled and shed requests are rejected with the protocol-specific `ThrottlingException` error,
+//! a `429 Too Many Requests` status code, and a `Retry-After` header when the caller may retry
+//! after a known delay.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::HttpPlugins;
+//! # use aws_smithy_http_server::shape_id::ShapeId;
+//! use aws_smithy_http_server::rate_limit::{
+//!     FromHeader, LoadShedExt, LoadShedPlugin, Priority, Quota, RateLimitExt, RateLimitPlugin,
+//! };
+//!
+//! # let put_object = ShapeId::new("com.example#PutObject", "com.example", "PutObject");
+//! # let ping = ShapeId::new("com.example#Ping", "com.example", "Ping");
+//! // Every tenant may call every operation 100 times a second, in bursts of up to 200 calls
+//! let rate_limits = RateLimitPlugin::new(FromHeader::new("x-tenant-id"), Quota::per_second(100).allow_burst(200))
+//!     .operation_quota(put_object, Quota::per_second(10))
+//!     .unlimited_operation(ping.clone());
+//! let load_shedding = LoadShedPlugin::new(512).operation_priority(ping, Priority::Critical);
+//! let http_plugins = HttpPlugins::new().shed_load(load_shedding).rate_limit(rate_limits);
+//! ```
+
+mod key;
+mod plugin;
+mod service;
+
+use std::time::Duration;
+
+pub use key::*;
+pub use plugin::*;
+pub use service::*;
+
+/// The rate at which a caller may call an operation.
+///
+/// A quota is a token bucket: every request takes a token, the bucket holds up to
+/// [`allow_burst`](Self::allow_burst) tokens, and is refilled with a token every
+/// [replenish interval](Self::with_period).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    burst: u32,
+    replenish_interval: Duration,
+}
+
+impl Quota {
+    /// Creates a quota allowing `requests` requests per second, in bursts of up to `requests`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is zero.
+    pub fn per_second(requests: u32) -> Self {
+        assert!(requests > 0, "a quota must allow at least one request");
+        Self::with_period(Duration::from_secs(1) / requests).allow_burst(requests)
+    }
+
+    /// Creates a quota allowing `requests` requests per minute, in bursts of up to `requests`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is zero.
+    pub fn per_minute(requests: u32) -> Self {
+        assert!(requests > 0, "a quota must allow at least one request");
+        Self::with_period(Duration::from_secs(60) / requests).allow_burst(requests)
+    }
+
+    /// Creates a quota allowing one request every `replenish_interval`, without bursts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `replenish_interval` is zero.
+    pub fn with_period(replenish_interval: Duration) -> Self {
+        assert!(!replenish_interval.is_zero(), "the replenish interval must not be zero");
+        Self {
+            burst: 1,
+            replenish_interval,
+        }
+    }
+
+    /// Sets how many requests a caller may make at once, after not making requests for a while.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `burst` is zero.
+    pub fn allow_burst(mut self, burst: u32) -> Self {
+        assert!(burst > 0, "a quota must allow bursts of at least one request");
+        self.burst = burst;
+        self
+    }
+
+    /// Returns the number of requests a caller may make at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Returns how often a caller is given another request.
+    pub fn replenish_interval(&self) -> Duration {
+        self.replenish_interval
+    }
+}
+
+/// The priority of an operation's requests when the server is overloaded. See [`LoadShedPlugin`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Requests are shed once half of the requests allowed in flight are.
+    Low,
+    /// Requests are shed once as many requests as allowed are in flight.
+    Normal,
+    /// Requests are shed once one and a half times as many requests as allowed are in flight.
+    High,
+    /// Requests are never shed, such as health checks.
+    Critical,
+}
+
+impl Priority {
+    /// The number of requests in flight from which requests of this priority are shed.
+    fn shed_threshold(self, max_in_flight: usize) -> usize {
+        match self {
+            Priority::Low => max_in_flight / 2,
+            Priority::Normal => max_in_flight,
+            Priority::High => max_in_flight.saturating_add(max_in_flight / 2),
+            Priority::Critical => usize::MAX,
+        }
+    }
+}
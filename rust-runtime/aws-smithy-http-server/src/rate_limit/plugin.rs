@@ -0,0 +1,189 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::operation::OperationShape;
+use crate::plugin::{HttpMarker, HttpPlugins, Plugin, PluginStack};
+use crate::service::ServiceShape;
+use crate::shape_id::ShapeId;
+
+use super::service::InFlightRequests;
+use super::{CallerKey, LoadShedService, Priority, Quota, RateLimitService};
+
+const DEFAULT_MAX_TRACKED_CALLERS: usize = 10_000;
+
+/// A [`Plugin`] which applies [`RateLimitService`] to every operation.
+///
+/// Every operation gets its own token buckets, so callers are throttled separately for each
+/// operation.
+#[derive(Debug, Clone)]
+pub struct RateLimitPlugin<C> {
+    caller_key: C,
+    default_quota: Quota,
+    operations: Arc<HashMap<ShapeId, Option<Quota>>>,
+    anonymous_quota: Option<Quota>,
+    max_tracked_callers: usize,
+}
+
+impl<C> RateLimitPlugin<C> {
+    /// Creates a plugin that throttles every caller of every operation, as identified by
+    /// `caller_key`, to `default_quota`, unless [`operation_quota`](Self::operation_quota) gives
+    /// another quota.
+    pub fn new(caller_key: C, default_quota: Quota) -> Self {
+        Self {
+            caller_key,
+            default_quota,
+            operations: Default::default(),
+            anonymous_quota: None,
+            max_tracked_callers: DEFAULT_MAX_TRACKED_CALLERS,
+        }
+    }
+
+    /// Throttles every caller of `operation` to `quota`.
+    pub fn operation_quota(mut self, operation: ShapeId, quota: Quota) -> Self {
+        Arc::make_mut(&mut self.operations).insert(operation, Some(quota));
+        self
+    }
+
+    /// Doesn't throttle the callers of `operation`.
+    pub fn unlimited_operation(mut self, operation: ShapeId) -> Self {
+        Arc::make_mut(&mut self.operations).insert(operation, None);
+        self
+    }
+
+    /// Throttles the requests to every operation that don't have a caller key to `quota`.
+    ///
+    /// These requests share a single bucket per operation, so one anonymous client can throttle
+    /// every other anonymous client. By default, the bucket is refilled according to the quota of
+    /// the operation. Operations that aren't throttled aren't affected.
+    pub fn anonymous_quota(mut self, quota: Quota) -> Self {
+        self.anonymous_quota = Some(quota);
+        self
+    }
+
+    /// Sets how many callers are tracked per operation. Past that, callers that aren't tracked share
+    /// a bucket until the buckets of callers that have not made requests for a while are dropped.
+    ///
+    /// Defaults to 10,000.
+    pub fn max_tracked_callers(mut self, max_tracked_callers: usize) -> Self {
+        self.max_tracked_callers = max_tracked_callers;
+        self
+    }
+}
+
+impl<Ser, Op, T, C> Plugin<Ser, Op, T> for RateLimitPlugin<C>
+where
+    Ser: ServiceShape,
+    Op: OperationShape,
+    C: CallerKey,
+{
+    type Output = RateLimitService<T, C, Ser::Protocol>;
+
+    fn apply(&self, inner: T) -> Self::Output {
+        let quota = self
+            .operations
+            .get(&Op::ID)
+            .copied()
+            .unwrap_or(Some(self.default_quota));
+        RateLimitService::new(
+            inner,
+            self.caller_key.clone(),
+            Op::ID,
+            quota,
+            self.anonymous_quota,
+            self.max_tracked_callers,
+        )
+    }
+}
+
+impl<C> HttpMarker for RateLimitPlugin<C> {}
+
+/// An extension trait for applying [`RateLimitPlugin`].
+pub trait RateLimitExt<CurrentPlugin> {
+    /// Throttles the callers of operations, as configured by `rate_limits`. See
+    /// [`RateLimitPlugin`] for more information.
+    fn rate_limit<C>(
+        self,
+        rate_limits: RateLimitPlugin<C>,
+    ) -> HttpPlugins<PluginStack<RateLimitPlugin<C>, CurrentPlugin>>;
+}
+
+impl<CurrentPlugin> RateLimitExt<CurrentPlugin> for HttpPlugins<CurrentPlugin> {
+    fn rate_limit<C>(
+        self,
+        rate_limits: RateLimitPlugin<C>,
+    ) -> HttpPlugins<PluginStack<RateLimitPlugin<C>, CurrentPlugin>> {
+        self.push(rate_limits)
+    }
+}
+
+/// A [`Plugin`] which applies [`LoadShedService`] to every operation.
+///
+/// The operations share the number of requests in flight. Requests are shed according to the
+/// [`Priority`] of their operation, from half of the maximum number of requests in flight for
+/// [`Priority::Low`], to never for [`Priority::Critical`].
+#[derive(Debug, Clone)]
+pub struct LoadShedPlugin {
+    requests: Arc<InFlightRequests>,
+    default_priority: Priority,
+    operations: Arc<HashMap<ShapeId, Priority>>,
+}
+
+impl LoadShedPlugin {
+    /// Creates a plugin that sheds requests of [`Priority::Normal`] once `max_in_flight` requests
+    /// are in flight.
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            requests: Arc::new(InFlightRequests::new(max_in_flight)),
+            default_priority: Priority::Normal,
+            operations: Default::default(),
+        }
+    }
+
+    /// Sets the priority of the operations that aren't given one by
+    /// [`operation_priority`](Self::operation_priority).
+    ///
+    /// Defaults to [`Priority::Normal`].
+    pub fn default_priority(mut self, priority: Priority) -> Self {
+        self.default_priority = priority;
+        self
+    }
+
+    /// Sets the priority of `operation`.
+    pub fn operation_priority(mut self, operation: ShapeId, priority: Priority) -> Self {
+        Arc::make_mut(&mut self.operations).insert(operation, priority);
+        self
+    }
+}
+
+impl<Ser, Op, T> Plugin<Ser, Op, T> for LoadShedPlugin
+where
+    Ser: ServiceShape,
+    Op: OperationShape,
+{
+    type Output = LoadShedService<T, Ser::Protocol>;
+
+    fn apply(&self, inner: T) -> Self::Output {
+        let priority = self.operations.get(&Op::ID).copied().unwrap_or(self.default_priority);
+        LoadShedService::new(inner, Op::ID, priority, self.requests.clone())
+    }
+}
+
+impl HttpMarker for LoadShedPlugin {}
+
+/// An extension trait for applying [`LoadShedPlugin`].
+pub trait LoadShedExt<CurrentPlugin> {
+    /// Sheds requests when the server is overloaded, as configured by `load_shedding`. See
+    /// [`LoadShedPlugin`] for more information.
+    fn shed_load(self, load_shedding: LoadShedPlugin) -> HttpPlugins<PluginStack<LoadShedPlugin, CurrentPlugin>>;
+}
+
+impl<CurrentPlugin> LoadShedExt<CurrentPlugin> for HttpPlugins<CurrentPlugin> {
+    fn shed_load(self, load_shedding: LoadShedPlugin) -> HttpPlugins<PluginStack<LoadShedPlugin, CurrentPlugin>> {
+        self.push(load_shedding)
+    }
+}
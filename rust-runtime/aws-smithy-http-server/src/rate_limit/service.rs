@@ -0,0 +1,592 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::{ready, Future, Ready};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::future::Either;
+use http::header::{HeaderValue, RETRY_AFTER};
+use http::{Request, Response};
+use pin_project_lite::pin_project;
+use tokio::time::Instant;
+use tower::Service;
+
+use crate::body::BoxBody;
+use crate::response::IntoResponse;
+use crate::runtime_error::ThrottlingException;
+use crate::shape_id::ShapeId;
+
+use super::{CallerKey, Priority, Quota};
+
+/// The token buckets of the callers of an operation.
+pub(crate) struct TokenBuckets<K> {
+    quota: Quota,
+    anonymous_quota: Quota,
+    max_callers: usize,
+    state: Mutex<State<K>>,
+}
+
+struct State<K> {
+    buckets: HashMap<K, Bucket>,
+    /// The bucket shared by callers that aren't tracked because `max_callers` callers already are.
+    overflow: Bucket,
+    /// The bucket shared by requests without a caller key.
+    anonymous: Bucket,
+    /// When full buckets may next be dropped to make room for more callers.
+    next_sweep: Instant,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn full(quota: &Quota, now: Instant) -> Self {
+        Self {
+            tokens: quota.burst().into(),
+            refilled_at: now,
+        }
+    }
+
+    fn refill(&mut self, quota: &Quota, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        let refill = elapsed.as_secs_f64() / quota.replenish_interval().as_secs_f64();
+        self.tokens = (self.tokens + refill).min(quota.burst().into());
+        self.refilled_at = now;
+    }
+
+    /// Takes a token, or returns how long until one is available.
+    fn take(&mut self, quota: &Quota, now: Instant) -> Result<(), Duration> {
+        self.refill(quota, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(quota.replenish_interval().mul_f64(1.0 - self.tokens))
+        }
+    }
+}
+
+impl<K: std::hash::Hash + Eq> TokenBuckets<K> {
+    pub(crate) fn new(quota: Quota, anonymous_quota: Quota, max_callers: usize) -> Self {
+        let now = Instant::now();
+        Self {
+            quota,
+            anonymous_quota,
+            max_callers,
+            state: Mutex::new(State {
+                buckets: HashMap::new(),
+                overflow: Bucket::full(&quota, now),
+                anonymous: Bucket::full(&anonymous_quota, now),
+                next_sweep: now,
+            }),
+        }
+    }
+
+    /// Takes a token from the bucket of `caller`, or returns how long until one is available.
+    fn acquire(&self, caller: Option<K>) -> Result<(), Duration> {
+        let now = Instant::now();
+        let burst = f64::from(self.quota.burst());
+        let mut state = self.state.lock().expect("lock poisoned");
+        let State {
+            buckets,
+            overflow,
+            anonymous,
+            next_sweep,
+        } = &mut *state;
+        let caller = match caller {
+            Some(caller) => caller,
+            None => return anonymous.take(&self.anonymous_quota, now),
+        };
+        if buckets.len() >= self.max_callers && now >= *next_sweep && !buckets.contains_key(&caller) {
+            // Full buckets are the same as buckets that don't exist yet. Looking for them visits
+            // every bucket, so only do so once per the time it takes an empty bucket to refill.
+            buckets.retain(|_, bucket| {
+                bucket.refill(&self.quota, now);
+                bucket.tokens < burst
+            });
+            *next_sweep = now + self.quota.replenish_interval().mul_f64(burst);
+        }
+        let tracked = buckets.len();
+        let bucket = match buckets.entry(caller) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) if tracked < self.max_callers => entry.insert(Bucket::full(&self.quota, now)),
+            // Callers that can't be tracked are throttled together
+            Entry::Vacant(_) => overflow,
+        };
+        bucket.take(&self.quota, now)
+    }
+}
+
+impl<K> fmt::Debug for TokenBuckets<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenBuckets")
+            .field("quota", &self.quota)
+            .field("anonymous_quota", &self.anonymous_quota)
+            .field("max_callers", &self.max_callers)
+            .finish_non_exhaustive()
+    }
+}
+
+fn throttled<P>(retry_after: Option<Duration>) -> Response<BoxBody>
+where
+    ThrottlingException: IntoResponse<P>,
+{
+    let mut response = IntoResponse::<P>::into_response(ThrottlingException);
+    if let Some(retry_after) = retry_after {
+        // `Retry-After` is given in whole seconds, so round up
+        let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(seconds.max(1)));
+    }
+    response
+}
+
+/// A middleware [`Service`] that throttles the callers of an operation, each with their own token
+/// bucket.
+///
+/// Throttled requests are rejected with the protocol-specific `ThrottlingException` error.
+pub struct RateLimitService<S, C: CallerKey, P> {
+    inner: S,
+    caller_key: C,
+    operation_id: ShapeId,
+    buckets: Option<Arc<TokenBuckets<C::Key>>>,
+    _protocol: PhantomData<fn(P)>,
+}
+
+impl<S, C: CallerKey, P> RateLimitService<S, C, P> {
+    /// Creates a service throttling each caller of the operation `operation_id`, as identified by
+    /// `caller_key`, to `quota`. `None` doesn't throttle callers.
+    ///
+    /// Requests without a caller key share a single bucket, refilled according to
+    /// `anonymous_quota`, or `quota` if it's `None`.
+    ///
+    /// Up to `max_callers` callers are tracked, after which the buckets of callers that have not
+    /// made requests for a while are dropped. Until then, callers that aren't tracked share a bucket.
+    pub fn new(
+        inner: S,
+        caller_key: C,
+        operation_id: ShapeId,
+        quota: Option<Quota>,
+        anonymous_quota: Option<Quota>,
+        max_callers: usize,
+    ) -> Self {
+        let buckets =
+            quota.map(|quota| Arc::new(TokenBuckets::new(quota, anonymous_quota.unwrap_or(quota), max_callers)));
+        Self {
+            inner,
+            caller_key,
+            operation_id,
+            buckets,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: Clone, C: CallerKey, P> Clone for RateLimitService<S, C, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            caller_key: self.caller_key.clone(),
+            operation_id: self.operation_id.clone(),
+            buckets: self.buckets.clone(),
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, C: CallerKey + fmt::Debug, P> fmt::Debug for RateLimitService<S, C, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitService")
+            .field("inner", &self.inner)
+            .field("caller_key", &self.caller_key)
+            .field("operation_id", &self.operation_id)
+            .field("buckets", &self.buckets)
+            .finish()
+    }
+}
+
+impl<S, C, P, B> Service<Request<B>> for RateLimitService<S, C, P>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+    C: CallerKey,
+    ThrottlingException: IntoResponse<P>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<S::Response, S::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        if let Some(buckets) = &self.buckets {
+            if let Err(retry_after) = buckets.acquire(self.caller_key.caller_key(&req)) {
+                tracing::debug!(
+                    operation = %self.operation_id.absolute(),
+                    ?retry_after,
+                    "throttling request"
+                );
+                return Either::Left(ready(Ok(throttled::<P>(Some(retry_after)))));
+            }
+        }
+        Either::Right(self.inner.call(req))
+    }
+}
+
+/// The number of requests in flight, shared by the operations of a [`LoadShedPlugin`](super::LoadShedPlugin).
+#[derive(Debug)]
+pub(crate) struct InFlightRequests {
+    max_in_flight: usize,
+    in_flight: AtomicUsize,
+}
+
+impl InFlightRequests {
+    pub(crate) fn new(max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight,
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Counts a request of `priority` as in flight, unless it must be shed.
+    fn admit(self: &Arc<Self>, priority: Priority) -> Option<AdmittedGuard> {
+        let threshold = priority.shed_threshold(self.max_in_flight);
+        self.in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |in_flight| {
+                (in_flight < threshold).then_some(in_flight + 1)
+            })
+            .ok()
+            .map(|_| AdmittedGuard { requests: self.clone() })
+    }
+}
+
+/// Counts a request as in flight until it is dropped.
+struct AdmittedGuard {
+    requests: Arc<InFlightRequests>,
+}
+
+impl Drop for AdmittedGuard {
+    fn drop(&mut self) {
+        self.requests.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl fmt::Debug for AdmittedGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdmittedGuard").finish_non_exhaustive()
+    }
+}
+
+/// A middleware [`Service`] that sheds the requests of an operation when too many requests are in
+/// flight, according to the operation's [`Priority`].
+///
+/// Shed requests are rejected with the protocol-specific `ThrottlingException` error.
+pub struct LoadShedService<S, P> {
+    inner: S,
+    operation_id: ShapeId,
+    priority: Priority,
+    requests: Arc<InFlightRequests>,
+    _protocol: PhantomData<fn(P)>,
+}
+
+impl<S, P> LoadShedService<S, P> {
+    pub(crate) fn new(inner: S, operation_id: ShapeId, priority: Priority, requests: Arc<InFlightRequests>) -> Self {
+        Self {
+            inner,
+            operation_id,
+            priority,
+            requests,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: Clone, P> Clone for LoadShedService<S, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            operation_id: self.operation_id.clone(),
+            priority: self.priority,
+            requests: self.requests.clone(),
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, P> fmt::Debug for LoadShedService<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadShedService")
+            .field("inner", &self.inner)
+            .field("operation_id", &self.operation_id)
+            .field("priority", &self.priority)
+            .field("requests", &self.requests)
+            .finish()
+    }
+}
+
+impl<S, P, B> Service<Request<B>> for LoadShedService<S, P>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+    ThrottlingException: IntoResponse<P>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = LoadShedFuture<S::Future, S::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let inner = match self.requests.admit(self.priority) {
+            Some(guard) => Either::Right(Admitted {
+                inner: self.inner.call(req),
+                _guard: guard,
+            }),
+            None => {
+                tracing::debug!(
+                    operation = %self.operation_id.absolute(),
+                    priority = ?self.priority,
+                    "shedding request"
+                );
+                Either::Left(ready(Ok(throttled::<P>(None))))
+            }
+        };
+        LoadShedFuture { inner }
+    }
+}
+
+pin_project! {
+    struct Admitted<F> {
+        #[pin]
+        inner: F,
+        _guard: AdmittedGuard,
+    }
+}
+
+impl<F: Future> Future for Admitted<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().inner.poll(cx)
+    }
+}
+
+pin_project! {
+    /// Future for [`LoadShedService`].
+    pub struct LoadShedFuture<F, E> {
+        #[pin]
+        inner: Either<Ready<Result<Response<BoxBody>, E>>, Admitted<F>>,
+    }
+}
+
+impl<F, E> fmt::Debug for LoadShedFuture<F, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadShedFuture").finish_non_exhaustive()
+    }
+}
+
+impl<F, E> Future for LoadShedFuture<F, E>
+where
+    F: Future<Output = Result<Response<BoxBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().inner.poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    use http::StatusCode;
+    use tokio::sync::Semaphore;
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+    use crate::protocol::rest_json_1::RestJson1;
+    use crate::rate_limit::{FromHeader, PeerIp};
+    use crate::request::connect_info::ConnectInfo;
+
+    fn operation_id() -> ShapeId {
+        ShapeId::new("com.example#GetStorage", "com.example", "GetStorage")
+    }
+
+    fn ok() -> Response<BoxBody> {
+        Response::new(crate::body::empty())
+    }
+
+    fn request(tenant: &'static str) -> Request<()> {
+        Request::builder().header("x-tenant-id", tenant).body(()).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn callers_are_throttled_separately() {
+        let inner = service_fn(|_: Request<()>| async { Ok::<_, Infallible>(ok()) });
+        let quota = Quota::per_second(1).allow_burst(2);
+        let svc = RateLimitService::<_, _, RestJson1>::new(
+            inner,
+            FromHeader::new("x-tenant-id"),
+            operation_id(),
+            Some(quota),
+            None,
+            100,
+        );
+
+        for _ in 0..2 {
+            let response = svc.clone().oneshot(request("a")).await.unwrap();
+            assert_eq!(StatusCode::OK, response.status());
+        }
+        let response = svc.clone().oneshot(request("a")).await.unwrap();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        assert_eq!(response.headers()["x-amzn-errortype"], "ThrottlingException");
+        assert_eq!(response.headers()[RETRY_AFTER], "1");
+
+        let response = svc.clone().oneshot(request("b")).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let response = svc.clone().oneshot(request("a")).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        let response = svc.oneshot(request("a")).await.unwrap();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn callers_are_identified_by_peer_ip() {
+        let inner = service_fn(|_: Request<()>| async { Ok::<_, Infallible>(ok()) });
+        let svc = RateLimitService::<_, _, RestJson1>::new(
+            inner,
+            PeerIp,
+            operation_id(),
+            Some(Quota::per_minute(1)),
+            None,
+            100,
+        );
+        let request = |ip: [u8; 4]| {
+            let mut request = Request::new(());
+            let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::from(ip)), 1234);
+            request.extensions_mut().insert(ConnectInfo(addr));
+            request
+        };
+
+        let response = svc.clone().oneshot(request([10, 0, 0, 1])).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        let response = svc.clone().oneshot(request([10, 0, 0, 1])).await.unwrap();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        assert_eq!(response.headers()[RETRY_AFTER], "60");
+        let response = svc.oneshot(request([10, 0, 0, 2])).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn full_buckets_are_dropped_when_tracking_too_many_callers() {
+        let buckets = TokenBuckets::new(Quota::per_second(1), Quota::per_second(1), 2);
+        buckets.acquire(Some(1)).unwrap();
+        buckets.acquire(Some(2)).unwrap();
+        // Every bucket is still in use, so the third caller shares the overflow bucket
+        buckets.acquire(Some(3)).unwrap();
+        assert_eq!(2, buckets.state.lock().unwrap().buckets.len());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        buckets.acquire(Some(4)).unwrap();
+        assert_eq!(1, buckets.state.lock().unwrap().buckets.len());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn untracked_callers_share_a_bucket() {
+        let buckets = TokenBuckets::new(Quota::per_second(1), Quota::per_second(1), 2);
+        for caller in 0..1000 {
+            let _ = buckets.acquire(Some(caller));
+            assert!(buckets.state.lock().unwrap().buckets.len() <= 2);
+            tokio::time::advance(Duration::from_millis(10)).await;
+        }
+
+        let buckets = TokenBuckets::new(Quota::per_second(1), Quota::per_second(1), 2);
+        buckets.acquire(Some(1)).unwrap();
+        buckets.acquire(Some(2)).unwrap();
+        buckets.acquire(Some(3)).unwrap();
+        assert_eq!(Err(Duration::from_secs(1)), buckets.acquire(Some(4)));
+        // Tracked callers keep their own buckets
+        assert_eq!(Err(Duration::from_secs(1)), buckets.acquire(Some(1)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_without_a_caller_key_share_the_anonymous_bucket() {
+        let buckets = TokenBuckets::new(Quota::per_second(10), Quota::per_second(1), 2);
+        buckets.acquire(None).unwrap();
+        assert_eq!(Err(Duration::from_secs(1)), buckets.acquire(None));
+        // Identified callers aren't affected, and anonymous requests don't take one of their buckets
+        for _ in 0..10 {
+            buckets.acquire(Some(1)).unwrap();
+        }
+        buckets.acquire(Some(2)).unwrap();
+        assert_eq!(2, buckets.state.lock().unwrap().buckets.len());
+    }
+
+    #[tokio::test]
+    async fn low_priority_requests_are_shed_first() {
+        let requests = Arc::new(InFlightRequests::new(2));
+        // Closing the semaphore releases every request waiting on it
+        let release = Arc::new(Semaphore::new(0));
+        let inner = service_fn({
+            let release = release.clone();
+            move |_: Request<()>| {
+                let release = release.clone();
+                async move {
+                    let _ = release.acquire().await;
+                    Ok::<_, Infallible>(ok())
+                }
+            }
+        });
+        let svc =
+            |priority| LoadShedService::<_, RestJson1>::new(inner.clone(), operation_id(), priority, requests.clone());
+
+        // The first request fills half of the capacity
+        let pending = tokio::spawn(svc(Priority::Normal).oneshot(Request::new(())));
+        tokio::task::yield_now().await;
+        assert_eq!(1, requests.in_flight.load(Ordering::Acquire));
+
+        let response = svc(Priority::Low).oneshot(Request::new(())).await.unwrap();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        assert_eq!(response.headers()["x-amzn-errortype"], "ThrottlingException");
+
+        // Requests that are admitted wait for the first one, so hold on to them
+        let admitted = svc(Priority::Normal).oneshot(Request::new(()));
+        let admitted = tokio::spawn(admitted);
+        tokio::task::yield_now().await;
+        assert_eq!(2, requests.in_flight.load(Ordering::Acquire));
+
+        let response = svc(Priority::Normal).oneshot(Request::new(())).await.unwrap();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        let high = tokio::spawn(svc(Priority::High).oneshot(Request::new(())));
+        tokio::task::yield_now().await;
+        assert_eq!(3, requests.in_flight.load(Ordering::Acquire));
+        let critical = tokio::spawn(svc(Priority::Critical).oneshot(Request::new(())));
+        tokio::task::yield_now().await;
+        assert_eq!(4, requests.in_flight.load(Ordering::Acquire));
+
+        release.close();
+        for handle in [pending, admitted, high, critical] {
+            assert_eq!(StatusCode::OK, handle.await.unwrap().unwrap().status());
+        }
+        assert_eq!(0, requests.in_flight.load(Ordering::Acquire));
+    }
+}
@@ -71,7 +71,7 @@ pub mod lambda;
 #[cfg_attr(docsrs, doc(cfg(feature = "request-id")))]
 pub mod request_id;
 
-fn internal_server_error() -> http::Response<BoxBody> {
+pub(crate) fn internal_server_error() -> http::Response<BoxBody> {
     let mut response = http::Response::new(empty());
     *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
     response
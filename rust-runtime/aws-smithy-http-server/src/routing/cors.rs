@@ -0,0 +1,414 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+#![deny(missing_docs, missing_debug_implementations)]
+
+//! [Cross-origin resource sharing] (CORS) support for [`RoutingService`].
+//!
+//! A [`RoutingService`] configured with [`Cors`] answers CORS preflight requests for the URIs its
+//! [`Router`](super::Router) knows, allowing the methods of the operations bound to the URI, instead of rejecting
+//! them with the protocol's routing error. The responses of other requests from allowed origins get
+//! the CORS response headers, so that browsers let clients read them.
+//!
+//! Services whose model has the [`@cors`] trait are configured from the trait by the generated
+//! code. Other services can be configured with the generated `with_cors` method, or with
+//! [`CorsLayer`].
+//!
+//! # Example
+//!
+//! ```
+//! use aws_smithy_http_server::routing::cors::Cors;
+//! use std::time::Duration;
+//!
+//! let cors = Cors::new()
+//!     .allow_origin("https://example.com")
+//!     .allow_header(http::header::HeaderName::from_static("x-tenant-id"))
+//!     .max_age(Duration::from_secs(3600));
+//! ```
+//!
+//! [Cross-origin resource sharing]: https://developer.mozilla.org/en-US/docs/Web/HTTP/CORS
+//! [`@cors`]: https://smithy.io/2.0/spec/http-bindings.html#cors-trait
+
+use std::time::Duration;
+
+use http::header::{
+    HeaderName, HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN, VARY,
+};
+use http::{HeaderMap, Method, StatusCode};
+use tower::Layer;
+
+use crate::body::BoxBody;
+
+use super::RoutingService;
+
+/// The maximum age of preflight responses when the `@cors` trait doesn't give one.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(600);
+
+/// Headers that clients send regardless of the model, such as protocol and authentication headers.
+const DEFAULT_ALLOWED_HEADERS: &[&str] = &[
+    "amz-sdk-invocation-id",
+    "amz-sdk-request",
+    "authorization",
+    "content-type",
+    "smithy-protocol",
+    "x-amz-content-sha256",
+    "x-amz-date",
+    "x-amz-security-token",
+    "x-amz-target",
+    "x-amz-user-agent",
+];
+
+/// Headers that clients read regardless of the model, such as the error type of error responses.
+const DEFAULT_EXPOSED_HEADERS: &[&str] = &["x-amzn-errortype", "x-amzn-requestid"];
+
+#[derive(Debug, Clone)]
+enum AllowedOrigins {
+    Any,
+    List(Vec<HeaderValue>),
+}
+
+/// The CORS configuration of a [`RoutingService`].
+///
+/// By default, requests from any origin are allowed, with the headers clients send regardless of
+/// the model.
+#[derive(Debug, Clone)]
+pub struct Cors {
+    allowed_origins: AllowedOrigins,
+    allowed_headers: Vec<HeaderName>,
+    exposed_headers: Vec<HeaderName>,
+    max_age: Duration,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cors {
+    /// Creates a configuration allowing requests from any origin.
+    pub fn new() -> Self {
+        Self {
+            allowed_origins: AllowedOrigins::Any,
+            allowed_headers: DEFAULT_ALLOWED_HEADERS
+                .iter()
+                .map(|name| HeaderName::from_static(name))
+                .collect(),
+            exposed_headers: DEFAULT_EXPOSED_HEADERS
+                .iter()
+                .map(|name| HeaderName::from_static(name))
+                .collect(),
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Creates a configuration from the `@cors` trait of the model, and the headers bound to the
+    /// operations of the service.
+    ///
+    /// This is used by the generated code.
+    #[doc(hidden)]
+    pub fn from_model(
+        origin: &'static str,
+        max_age_seconds: u64,
+        allowed_headers: &[&'static str],
+        exposed_headers: &[&'static str],
+    ) -> Self {
+        let mut cors = Self::new().max_age(Duration::from_secs(max_age_seconds));
+        if origin != "*" {
+            cors = cors.allow_origin(origin);
+        }
+        for name in allowed_headers {
+            cors = cors.allow_header(HeaderName::from_static(name));
+        }
+        for name in exposed_headers {
+            cors = cors.expose_header(HeaderName::from_static(name));
+        }
+        cors
+    }
+
+    /// Allows requests from `origin`, such as `https://example.com`. Once an origin is allowed,
+    /// requests from other origins aren't anymore.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `origin` is not a valid header value.
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        let origin = HeaderValue::from_str(origin).expect("origins must be valid header values");
+        match &mut self.allowed_origins {
+            AllowedOrigins::List(origins) => origins.push(origin),
+            allowed_origins @ AllowedOrigins::Any => *allowed_origins = AllowedOrigins::List(vec![origin]),
+        }
+        self
+    }
+
+    /// Allows clients to send the header `name`.
+    pub fn allow_header(mut self, name: HeaderName) -> Self {
+        if !self.allowed_headers.contains(&name) {
+            self.allowed_headers.push(name);
+        }
+        self
+    }
+
+    /// Allows clients to read the response header `name`.
+    pub fn expose_header(mut self, name: HeaderName) -> Self {
+        if !self.exposed_headers.contains(&name) {
+            self.exposed_headers.push(name);
+        }
+        self
+    }
+
+    /// Sets how long browsers may cache preflight responses.
+    ///
+    /// Defaults to 10 minutes.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Returns the `Access-Control-Allow-Origin` header value for requests from `origin`, if it
+    /// is allowed.
+    fn allow_origin_value(&self, origin: &HeaderValue) -> Option<HeaderValue> {
+        match &self.allowed_origins {
+            AllowedOrigins::Any => Some(HeaderValue::from_static("*")),
+            AllowedOrigins::List(origins) => origins.contains(origin).then(|| origin.clone()),
+        }
+    }
+
+    /// Returns the headers added to the responses of requests from `origin`, if it is allowed.
+    pub(crate) fn response_headers(&self, origin: &HeaderValue) -> Option<HeaderMap> {
+        let allow_origin = self.allow_origin_value(origin)?;
+        let mut headers = HeaderMap::new();
+        if matches!(self.allowed_origins, AllowedOrigins::List(_)) {
+            headers.insert(VARY, HeaderValue::from_static("origin"));
+        }
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        if !self.exposed_headers.is_empty() {
+            headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, join(&self.exposed_headers));
+        }
+        Some(headers)
+    }
+
+    /// Answers the preflight request from `origin` for a URI whose operations have the methods
+    /// `allowed_methods`.
+    pub(crate) fn preflight_response(
+        &self,
+        origin: &HeaderValue,
+        requested_method: &Method,
+        allowed_methods: &[Method],
+    ) -> http::Response<BoxBody> {
+        let allow_origin = self.allow_origin_value(origin);
+        let mut response = http::Response::new(crate::body::empty());
+        match allow_origin {
+            Some(allow_origin) if allowed_methods.contains(requested_method) => {
+                *response.status_mut() = StatusCode::NO_CONTENT;
+                let headers = response.headers_mut();
+                if matches!(self.allowed_origins, AllowedOrigins::List(_)) {
+                    headers.insert(VARY, HeaderValue::from_static("origin"));
+                }
+                headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+                headers.insert(ACCESS_CONTROL_ALLOW_METHODS, join(allowed_methods));
+                headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, join(&self.allowed_headers));
+                headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from(self.max_age.as_secs()));
+            }
+            _ => {
+                tracing::debug!(?origin, %requested_method, "rejecting CORS preflight request");
+                *response.status_mut() = StatusCode::FORBIDDEN;
+            }
+        }
+        response
+    }
+}
+
+fn join<T: AsRef<str>>(values: &[T]) -> HeaderValue {
+    let joined = values.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(", ");
+    HeaderValue::try_from(joined).expect("header names and methods are valid header values")
+}
+
+/// The parts of a CORS preflight request.
+pub(crate) struct Preflight<'a> {
+    pub(crate) origin: &'a HeaderValue,
+    pub(crate) requested_method: Method,
+}
+
+impl<'a> Preflight<'a> {
+    /// Returns the parts of `request` if it is a CORS preflight request.
+    pub(crate) fn from_request<B>(request: &'a http::Request<B>) -> Option<Self> {
+        if request.method() != Method::OPTIONS {
+            return None;
+        }
+        let origin = request.headers().get(ORIGIN)?;
+        let requested_method = request.headers().get(ACCESS_CONTROL_REQUEST_METHOD)?;
+        let requested_method = Method::from_bytes(requested_method.as_bytes()).ok()?;
+        Some(Self {
+            origin,
+            requested_method,
+        })
+    }
+}
+
+/// A [`Layer`] configuring a [`RoutingService`] with [`Cors`].
+///
+/// See [`RoutingService::with_cors`].
+#[derive(Debug, Clone)]
+pub struct CorsLayer {
+    cors: Cors,
+}
+
+impl CorsLayer {
+    /// Creates a layer configuring [`RoutingService`]s with `cors`.
+    pub fn new(cors: Cors) -> Self {
+        Self { cors }
+    }
+}
+
+impl<R, P> Layer<RoutingService<R, P>> for CorsLayer {
+    type Service = RoutingService<R, P>;
+
+    fn layer(&self, inner: RoutingService<R, P>) -> Self::Service {
+        inner.with_cors(self.cors.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::header::ACCESS_CONTROL_REQUEST_HEADERS;
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+    use crate::protocol::rest::router::RestRouter;
+    use crate::protocol::rest_json_1::RestJson1;
+    use crate::routing::request_spec::{PathSegment, RequestSpec};
+
+    fn routing_service(
+        cors: Cors,
+    ) -> RoutingService<
+        RestRouter<
+            impl tower::Service<http::Request<()>, Response = http::Response<BoxBody>, Error = Infallible> + Clone,
+        >,
+        RestJson1,
+    > {
+        // Responses that vary on other headers than the origin
+        let route = service_fn(|_: http::Request<()>| async {
+            let response = http::Response::builder()
+                .header(VARY, "accept-encoding")
+                .body(crate::body::empty())
+                .unwrap();
+            Ok::<_, Infallible>(response)
+        });
+        let path = || vec![PathSegment::Literal(String::from("storage")), PathSegment::Label];
+        let router: RestRouter<_> = [
+            (RequestSpec::from_parts(Method::GET, path(), Vec::new()), route),
+            (RequestSpec::from_parts(Method::PUT, path(), Vec::new()), route),
+        ]
+        .into_iter()
+        .collect();
+        RoutingService::new(router).with_cors(cors)
+    }
+
+    fn preflight(uri: &str, origin: &'static str, method: &'static str) -> http::Request<()> {
+        http::Request::builder()
+            .method(Method::OPTIONS)
+            .uri(uri)
+            .header(ORIGIN, origin)
+            .header(ACCESS_CONTROL_REQUEST_METHOD, method)
+            .header(ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .body(())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn preflights_are_answered_with_the_methods_of_the_uri() {
+        let svc = routing_service(Cors::new());
+        let response = svc
+            .oneshot(preflight("/storage/alice", "https://example.com", "PUT"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::NO_CONTENT, response.status());
+        let headers = response.headers();
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_METHODS], "GET, PUT");
+        assert!(headers[ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap()
+            .contains("content-type"));
+        assert_eq!(headers[ACCESS_CONTROL_MAX_AGE], "600");
+    }
+
+    #[tokio::test]
+    async fn preflights_for_unknown_uris_are_rejected_by_the_router() {
+        let svc = routing_service(Cors::new());
+        let response = svc
+            .oneshot(preflight("/unknown", "https://example.com", "GET"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+    }
+
+    #[tokio::test]
+    async fn preflights_from_other_origins_or_for_other_methods_are_forbidden() {
+        let svc = routing_service(Cors::new().allow_origin("https://example.com"));
+        let response = svc
+            .clone()
+            .oneshot(preflight("/storage/alice", "https://example.org", "GET"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        assert!(!response.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let response = svc
+            .oneshot(preflight("/storage/alice", "https://example.com", "DELETE"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+    }
+
+    #[tokio::test]
+    async fn responses_to_allowed_origins_have_cors_headers() {
+        let svc = routing_service(Cors::new().allow_origin("https://example.com"));
+        let request = |origin| {
+            http::Request::builder()
+                .uri("/storage/alice")
+                .header(ORIGIN, origin)
+                .body(())
+                .unwrap()
+        };
+
+        let response = svc.clone().oneshot(request("https://example.com")).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        let headers = response.headers();
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "https://example.com");
+        assert_eq!(
+            headers[ACCESS_CONTROL_EXPOSE_HEADERS],
+            "x-amzn-errortype, x-amzn-requestid"
+        );
+
+        let response = svc.oneshot(request("https://example.org")).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(!response.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn responses_keep_their_vary_headers() {
+        let svc = routing_service(Cors::new().allow_origin("https://example.com"));
+        let request = |origin| {
+            http::Request::builder()
+                .uri("/storage/alice")
+                .header(ORIGIN, origin)
+                .body(())
+                .unwrap()
+        };
+
+        let response = svc.clone().oneshot(request("https://example.com")).await.unwrap();
+        let vary: Vec<_> = response.headers().get_all(VARY).iter().collect();
+        assert_eq!(vary, ["accept-encoding", "origin"]);
+
+        let response = svc.oneshot(request("https://example.org")).await.unwrap();
+        let vary: Vec<_> = response.headers().get_all(VARY).iter().collect();
+        assert_eq!(vary, ["accept-encoding"]);
+    }
+}
@@ -7,6 +7,7 @@
 //!
 //! [Smithy specification]: https://smithy.io/2.0/spec/http-bindings.html
 
+pub mod cors;
 mod into_make_service;
 mod into_make_service_with_connect_info;
 #[cfg(feature = "aws-lambda")]
@@ -26,6 +27,7 @@ use std::{
     future::{ready, Future, Ready},
     marker::PhantomData,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
@@ -34,7 +36,10 @@ use futures_util::{
     future::{Either, MapOk},
     TryFutureExt,
 };
-use http::Response;
+use http::{
+    header::{ORIGIN, VARY},
+    HeaderMap, Response,
+};
 use http_body::Body as HttpBody;
 use tower::{util::Oneshot, Service, ServiceExt};
 use tracing::debug;
@@ -45,6 +50,8 @@ use crate::{
     response::IntoResponse,
 };
 
+use self::cors::{Cors, Preflight};
+
 #[cfg(feature = "aws-lambda")]
 #[cfg_attr(docsrs, doc(cfg(feature = "aws-lambda")))]
 pub use self::lambda_handler::LambdaHandler;
@@ -72,6 +79,12 @@ pub trait Router<B> {
 
     /// Matches a [`http::Request`] to a target [`Service`].
     fn match_route(&self, request: &http::Request<B>) -> Result<Self::Service, Self::Error>;
+
+    /// Returns the methods of the routes matching the URI of a [`http::Request`], regardless of
+    /// its method. This is used to answer [CORS](cors) preflight requests.
+    fn allowed_methods(&self, _request: &http::Request<B>) -> Vec<http::Method> {
+        Vec::new()
+    }
 }
 
 /// A [`Service`] using the [`Router`] `R` to redirect messages to specific routes.
@@ -79,6 +92,7 @@ pub trait Router<B> {
 /// The `Protocol` parameter is used to determine the serialization of errors.
 pub struct RoutingService<R, Protocol> {
     router: R,
+    cors: Option<Arc<Cors>>,
     _protocol: PhantomData<Protocol>,
 }
 
@@ -89,6 +103,7 @@ where
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RoutingService")
             .field("router", &self.router)
+            .field("cors", &self.cors)
             .field("_protocol", &self._protocol)
             .finish()
     }
@@ -101,6 +116,7 @@ where
     fn clone(&self) -> Self {
         Self {
             router: self.router.clone(),
+            cors: self.cors.clone(),
             _protocol: PhantomData,
         }
     }
@@ -111,10 +127,18 @@ impl<R, P> RoutingService<R, P> {
     pub fn new(router: R) -> Self {
         Self {
             router,
+            cors: None,
             _protocol: PhantomData,
         }
     }
 
+    /// Answers [CORS](cors) preflight requests for the routes of the [`Router`], and adds the
+    /// CORS headers to the responses of requests from allowed origins, as configured by `cors`.
+    pub fn with_cors(mut self, cors: Cors) -> Self {
+        self.cors = Some(Arc::new(cors));
+        self
+    }
+
     /// Maps a [`Router`] using a closure.
     pub fn map<RNew, F>(self, f: F) -> RoutingService<RNew, P>
     where
@@ -122,6 +146,7 @@ impl<R, P> RoutingService<R, P> {
     {
         RoutingService {
             router: f(self.router),
+            cors: self.cors,
             _protocol: PhantomData,
         }
     }
@@ -135,7 +160,9 @@ type EitherOneshotReady<S, B> = Either<
 pin_project_lite::pin_project! {
     pub struct RoutingFuture<S, B> where S: Service<http::Request<B>> {
         #[pin]
-        inner: EitherOneshotReady<S, B>
+        inner: EitherOneshotReady<S, B>,
+        // The CORS headers added to the response
+        cors_headers: Option<HeaderMap>,
     }
 }
 
@@ -152,6 +179,7 @@ where
     {
         Self {
             inner: Either::Left(future.map_ok(|x| x.map(boxed))),
+            cors_headers: None,
         }
     }
 
@@ -159,8 +187,15 @@ where
     pub(super) fn from_response(response: http::Response<BoxBody>) -> Self {
         Self {
             inner: Either::Right(ready(Ok(response))),
+            cors_headers: None,
         }
     }
+
+    /// Adds `cors_headers` to the response.
+    fn with_cors_headers(mut self, cors_headers: Option<HeaderMap>) -> Self {
+        self.cors_headers = cors_headers;
+        self
+    }
 }
 
 impl<S, B> Future for RoutingFuture<S, B>
@@ -170,7 +205,21 @@ where
     type Output = Result<http::Response<BoxBody>, S::Error>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        self.project().inner.poll(cx)
+        let this = self.project();
+        this.inner.poll(cx).map_ok(|mut response| {
+            if let Some(cors_headers) = this.cors_headers.take() {
+                let headers = response.headers_mut();
+                for (name, value) in &cors_headers {
+                    // The response may already vary on other headers
+                    if name == VARY {
+                        headers.append(name, value.clone());
+                    } else {
+                        headers.insert(name, value.clone());
+                    }
+                }
+            }
+            response
+        })
     }
 }
 
@@ -191,7 +240,24 @@ where
     }
 
     fn call(&mut self, req: http::Request<B>) -> Self::Future {
-        match self.router.match_route(&req) {
+        let mut cors_headers = None;
+        if let Some(cors) = &self.cors {
+            if let Some(preflight) = Preflight::from_request(&req) {
+                let allowed_methods = self.router.allowed_methods(&req);
+                // Preflight requests for unknown URIs are rejected with the routing error below
+                if !allowed_methods.is_empty() {
+                    let response =
+                        cors.preflight_response(preflight.origin, &preflight.requested_method, &allowed_methods);
+                    return RoutingFuture::from_response(response);
+                }
+            }
+            cors_headers = req
+                .headers()
+                .get(ORIGIN)
+                .and_then(|origin| cors.response_headers(origin));
+        }
+
+        let future = match self.router.match_route(&req) {
             // Successfully routed, use the routes `Service::call`.
             Ok(ok) => RoutingFuture::from_oneshot(ok.oneshot(req)),
             // Failed to route, use the `R::Error`s `IntoResponse<P>`.
@@ -199,6 +265,7 @@ where
                 debug!(%error, "failed to route");
                 RoutingFuture::from_response(error.into_response())
             }
-        }
+        };
+        future.with_cors_headers(cors_headers)
     }
 }
@@ -129,6 +129,10 @@ impl RequestSpec {
         }
     }
 
+    pub(crate) fn method(&self) -> &http::Method {
+        &self.method
+    }
+
     /// A measure of how "important" a `RequestSpec` is. The more specific a `RequestSpec` is, the
     /// higher it ranks in importance. Specificity is measured by the number of segments plus the
     /// number of query string literals in its URI pattern, so `/{Bucket}/{Key}?query` is more
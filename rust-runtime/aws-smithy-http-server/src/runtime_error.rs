@@ -10,4 +10,28 @@
 /// [`crate::protocol::rest_json_1::runtime_error::RuntimeError::InternalFailure`] variant.
 pub struct InternalFailureException;
 
+/// A _protocol-agnostic_ type representing a request that failed authentication, for example
+/// because its signature could not be verified. This type is converted into protocol-specific
+/// error variants. For example, in the [`crate::protocol::rest_json_1`] protocol, it is converted
+/// to the [`crate::protocol::rest_json_1::runtime_error::RuntimeError::AccessDenied`] variant.
+pub struct AccessDeniedException;
+
+/// A _protocol-agnostic_ type representing a request whose body is larger than allowed by
+/// [`crate::body_limit`]. This type is converted into protocol-specific error variants. For
+/// example, in the [`crate::protocol::rest_json_1`] protocol, it is converted to the
+/// [`crate::protocol::rest_json_1::runtime_error::RuntimeError::PayloadTooLarge`] variant.
+pub struct PayloadTooLargeException;
+
+/// A _protocol-agnostic_ type representing an operation that did not complete before the deadline
+/// set by [`crate::timeout`]. This type is converted into protocol-specific error variants. For
+/// example, in the [`crate::protocol::rest_json_1`] protocol, it is converted to the
+/// [`crate::protocol::rest_json_1::runtime_error::RuntimeError::OperationTimeout`] variant.
+pub struct OperationTimeoutException;
+
+/// A _protocol-agnostic_ type representing a request that was throttled or shed by
+/// [`crate::rate_limit`]. This type is converted into protocol-specific error variants. For
+/// example, in the [`crate::protocol::rest_json_1`] protocol, it is converted to the
+/// [`crate::protocol::rest_json_1::runtime_error::RuntimeError::Throttling`] variant.
+pub struct ThrottlingException;
+
 pub const INVALID_HTTP_RESPONSE_FOR_RUNTIME_ERROR_PANIC_MESSAGE: &str = "invalid HTTP response for `RuntimeError`; please file a bug report under https://github.com/smithy-lang/smithy-rs/issues";
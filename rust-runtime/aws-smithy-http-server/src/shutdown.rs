@@ -0,0 +1,433 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+#![deny(missing_docs, missing_debug_implementations)]
+
+//! Graceful shutdown of servers, draining in-flight requests.
+//!
+//! [`GracefulShutdown`] shuts a server down in three steps:
+//!
+//! 1. The server is reported as not ready, for example by health checks answered with
+//!    [`AlbHealthCheckLayer::from_readiness`](crate::layer::alb_health_check::AlbHealthCheckLayer::from_readiness),
+//!    so that load balancers stop routing requests to it. The server keeps accepting connections
+//!    for the [drain delay](GracefulShutdown::drain_delay), while load balancers notice.
+//! 2. The server stops accepting connections, when the future returned by
+//!    [`GracefulShutdown::signal`] completes.
+//! 3. Requests that are in flight are given the [drain timeout](GracefulShutdown::drain_timeout) to
+//!    complete.
+//!
+//! Requests are tracked from when they are received until their response is ready to be sent, by
+//! the services made by [`GracefulShutdown::track_in_flight`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! # let app = tower::service_fn(|_: http::Request<hyper::Body>| async { Ok::<_, std::convert::Infallible>(http::Response::new(aws_smithy_http_server::body::to_boxed(""))) });
+//! use aws_smithy_http_server::layer::alb_health_check::AlbHealthCheckLayer;
+//! use aws_smithy_http_server::routing::IntoMakeService;
+//! use aws_smithy_http_server::shutdown::GracefulShutdown;
+//! use std::time::Duration;
+//! use tower::Layer;
+//!
+//! let shutdown = GracefulShutdown::new()
+//!     .drain_delay(Duration::from_secs(15))
+//!     .drain_timeout(Duration::from_secs(30));
+//! let app = AlbHealthCheckLayer::from_readiness("/ping", shutdown.clone()).layer(app);
+//! let server = hyper::Server::bind(&"0.0.0.0:13734".parse()?)
+//!     .serve(shutdown.track_in_flight(IntoMakeService::new(app)))
+//!     .with_graceful_shutdown(shutdown.signal());
+//! let server = tokio::spawn(server);
+//!
+//! tokio::signal::ctrl_c().await?;
+//! let drained = shutdown.shutdown().await;
+//! if !drained.is_complete() {
+//!     eprintln!("abandoning {} requests", drained.abandoned());
+//! }
+//! # drop(server);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use pin_project_lite::pin_project;
+use tokio::sync::watch;
+use tower::Service;
+
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Serving,
+    /// The server is reported as not ready, but still accepts connections.
+    Draining,
+    /// The server doesn't accept connections anymore.
+    Stopping,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: watch::Sender<State>,
+    in_flight: watch::Sender<usize>,
+}
+
+/// Coordinates the graceful shutdown of a server.
+///
+/// Clones share the same state. See the [module documentation](crate::shutdown) for more
+/// information.
+#[derive(Debug, Clone)]
+pub struct GracefulShutdown {
+    inner: Arc<Inner>,
+    drain_delay: Duration,
+    drain_timeout: Duration,
+}
+
+impl Default for GracefulShutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GracefulShutdown {
+    /// Creates a new `GracefulShutdown`, for a server that is ready.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                state: watch::channel(State::Serving).0,
+                in_flight: watch::channel(0).0,
+            }),
+            drain_delay: Duration::ZERO,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+    }
+
+    /// Sets how long the server keeps accepting connections after it is reported as not ready, so
+    /// that load balancers can stop routing requests to it first. This should be longer than the
+    /// time load balancers take to consider the server unhealthy.
+    ///
+    /// Defaults to zero.
+    pub fn drain_delay(mut self, drain_delay: Duration) -> Self {
+        self.drain_delay = drain_delay;
+        self
+    }
+
+    /// Sets how long requests that are in flight when the server stops accepting connections are
+    /// given to complete.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    /// Returns `true` until the shutdown starts.
+    pub fn is_ready(&self) -> bool {
+        *self.inner.state.borrow() == State::Serving
+    }
+
+    /// Returns the number of requests in flight.
+    pub fn in_flight(&self) -> usize {
+        *self.inner.in_flight.borrow()
+    }
+
+    /// Wraps a [`MakeService`](tower::make::MakeService), such as an
+    /// [`IntoMakeService`](crate::routing::IntoMakeService), so that the requests handled by the
+    /// services it makes are tracked as in flight.
+    pub fn track_in_flight<M>(&self, make_service: M) -> TrackInFlight<M> {
+        TrackInFlight {
+            inner: make_service,
+            shutdown: self.clone(),
+        }
+    }
+
+    /// Returns a future that completes when the server should stop accepting connections, such as
+    /// the signal given to `hyper::Server::with_graceful_shutdown`.
+    pub fn signal(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut state = self.inner.state.subscribe();
+        async move {
+            while *state.borrow_and_update() != State::Stopping {
+                if state.changed().await.is_err() {
+                    // Every `GracefulShutdown` was dropped, so the shutdown can't start anymore
+                    std::future::pending::<()>().await;
+                }
+            }
+        }
+    }
+
+    /// Shuts the server down, and waits for the requests in flight to complete.
+    ///
+    /// The server is reported as not ready right away. After the drain delay, the
+    /// [signal](Self::signal) completes, and requests in flight are waited for until the drain
+    /// timeout elapses.
+    pub async fn shutdown(&self) -> Drained {
+        self.inner.state.send_if_modified(|state| {
+            let serving = *state == State::Serving;
+            if serving {
+                *state = State::Draining;
+            }
+            serving
+        });
+        tracing::info!(drain_delay = ?self.drain_delay, "shutting down; reporting the server as not ready");
+        if !self.drain_delay.is_zero() {
+            tokio::time::sleep(self.drain_delay).await;
+        }
+
+        self.inner.state.send_replace(State::Stopping);
+        tracing::info!(
+            in_flight = self.in_flight(),
+            drain_timeout = ?self.drain_timeout,
+            "stopped accepting connections; waiting for requests in flight"
+        );
+        let mut in_flight = self.inner.in_flight.subscribe();
+        let _ = tokio::time::timeout(self.drain_timeout, async move {
+            while *in_flight.borrow_and_update() > 0 {
+                if in_flight.changed().await.is_err() {
+                    break;
+                }
+            }
+        })
+        .await;
+
+        let drained = Drained {
+            abandoned: self.in_flight(),
+        };
+        if !drained.is_complete() {
+            tracing::warn!(
+                abandoned = drained.abandoned,
+                "requests in flight did not complete before the drain timeout"
+            );
+        }
+        drained
+    }
+
+    fn start_request(&self) -> InFlightGuard {
+        self.inner.in_flight.send_modify(|in_flight| *in_flight += 1);
+        InFlightGuard { shutdown: self.clone() }
+    }
+}
+
+/// The outcome of [`GracefulShutdown::shutdown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Drained {
+    abandoned: usize,
+}
+
+impl Drained {
+    /// Returns `true` if every request in flight completed before the drain timeout.
+    pub fn is_complete(&self) -> bool {
+        self.abandoned == 0
+    }
+
+    /// Returns the number of requests that were still in flight when the drain timeout elapsed.
+    pub fn abandoned(&self) -> usize {
+        self.abandoned
+    }
+}
+
+/// Marks a request as in flight until it is dropped.
+struct InFlightGuard {
+    shutdown: GracefulShutdown,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.shutdown.inner.in_flight.send_modify(|in_flight| *in_flight -= 1);
+    }
+}
+
+impl fmt::Debug for InFlightGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InFlightGuard").finish_non_exhaustive()
+    }
+}
+
+/// A [`MakeService`](tower::make::MakeService) whose services track their requests as in flight.
+///
+/// See [`GracefulShutdown::track_in_flight`].
+#[derive(Debug, Clone)]
+pub struct TrackInFlight<M> {
+    inner: M,
+    shutdown: GracefulShutdown,
+}
+
+impl<M, T> Service<T> for TrackInFlight<M>
+where
+    M: Service<T>,
+{
+    type Response = InFlight<M::Response>;
+    type Error = M::Error;
+    type Future = TrackInFlightFuture<M::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        TrackInFlightFuture {
+            inner: self.inner.call(target),
+            shutdown: Some(self.shutdown.clone()),
+        }
+    }
+}
+
+pin_project! {
+    /// Future for [`TrackInFlight`].
+    #[derive(Debug)]
+    pub struct TrackInFlightFuture<F> {
+        #[pin]
+        inner: F,
+        shutdown: Option<GracefulShutdown>,
+    }
+}
+
+impl<F, S, E> Future for TrackInFlightFuture<F>
+where
+    F: Future<Output = Result<S, E>>,
+{
+    type Output = Result<InFlight<S>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        this.inner.poll(cx).map_ok(|inner| InFlight {
+            inner,
+            shutdown: this.shutdown.take().expect("polled after completion"),
+        })
+    }
+}
+
+/// A middleware [`Service`] that tracks its requests as in flight until their response is ready.
+#[derive(Debug, Clone)]
+pub struct InFlight<S> {
+    inner: S,
+    shutdown: GracefulShutdown,
+}
+
+impl<S, R> Service<R> for InFlight<S>
+where
+    S: Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = InFlightFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        InFlightFuture {
+            guard: self.shutdown.start_request(),
+            inner: self.inner.call(req),
+        }
+    }
+}
+
+pin_project! {
+    /// Future for [`InFlight`].
+    #[derive(Debug)]
+    pub struct InFlightFuture<F> {
+        #[pin]
+        inner: F,
+        guard: InFlightGuard,
+    }
+}
+
+impl<F: Future> Future for InFlightFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().inner.poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::{Request, Response, StatusCode};
+    use hyper::Body;
+    use tower::{service_fn, Layer, ServiceExt};
+
+    use super::*;
+    use crate::body::BoxBody;
+    use crate::layer::alb_health_check::AlbHealthCheckLayer;
+    use crate::routing::IntoMakeService;
+
+    fn slow_app(
+        handler_duration: Duration,
+    ) -> impl Service<Request<Body>, Response = Response<BoxBody>, Error = Infallible, Future = impl Send> + Clone {
+        service_fn(move |_: Request<Body>| async move {
+            tokio::time::sleep(handler_duration).await;
+            Ok::<_, Infallible>(Response::new(crate::body::empty()))
+        })
+    }
+
+    /// Starts a request, which is in flight until the returned handle is awaited
+    async fn start_request<M>(make_service: &mut TrackInFlight<M>) -> tokio::task::JoinHandle<StatusCode>
+    where
+        M: Service<(), Error = Infallible>,
+        M::Response: Service<Request<Body>, Response = Response<BoxBody>, Error = Infallible> + Send + 'static,
+        <M::Response as Service<Request<Body>>>::Future: Send,
+    {
+        let service = make_service.ready().await.unwrap().call(()).await.unwrap();
+        let handle = tokio::spawn(async move { service.oneshot(Request::new(Body::empty())).await.unwrap().status() });
+        tokio::task::yield_now().await;
+        handle
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_for_requests_in_flight() {
+        let shutdown = GracefulShutdown::new().drain_delay(Duration::from_secs(5));
+        let mut make_service = shutdown.track_in_flight(IntoMakeService::new(slow_app(Duration::from_secs(20))));
+        let request = start_request(&mut make_service).await;
+        assert_eq!(1, shutdown.in_flight());
+
+        let signal = tokio::spawn(shutdown.signal());
+        let drained = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.shutdown().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!shutdown.is_ready());
+        assert!(!signal.is_finished());
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        signal.await.unwrap();
+        assert!(drained.await.unwrap().is_complete());
+        assert_eq!(StatusCode::OK, request.await.unwrap());
+        assert_eq!(0, shutdown.in_flight());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn abandons_requests_after_the_drain_timeout() {
+        let shutdown = GracefulShutdown::new().drain_timeout(Duration::from_secs(10));
+        let mut make_service = shutdown.track_in_flight(IntoMakeService::new(slow_app(Duration::from_secs(60))));
+        let _request = start_request(&mut make_service).await;
+
+        let drained = shutdown.shutdown().await;
+        assert!(!drained.is_complete());
+        assert_eq!(1, drained.abandoned());
+    }
+
+    #[tokio::test]
+    async fn health_checks_fail_once_the_shutdown_starts() {
+        let shutdown = GracefulShutdown::new();
+        let app = AlbHealthCheckLayer::from_readiness("/ping", shutdown.clone()).layer(slow_app(Duration::ZERO));
+        let ping = || Request::get("/ping").body(Body::empty()).unwrap();
+
+        assert_eq!(StatusCode::OK, app.clone().oneshot(ping()).await.unwrap().status());
+        shutdown.shutdown().await;
+        assert_eq!(
+            StatusCode::SERVICE_UNAVAILABLE,
+            app.clone().oneshot(ping()).await.unwrap().status()
+        );
+    }
+}
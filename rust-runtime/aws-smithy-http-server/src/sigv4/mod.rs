@@ -0,0 +1,216 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Verification of [SigV4] signed requests.
+//!
+//! [`SigV4VerificationPlugin`] rejects requests that don't carry a valid SigV4 signature, either in
+//! the `Authorization` header or in the query string of a presigned request. The canonical request is
+//! rebuilt with the same code [`aws_sigv4`] uses to sign requests, so any client signing with
+//! `aws-sigv4` can call a service protected by this plugin.
+//!
+//! The secret key of the signer is resolved from the access key ID in the request through a
+//! [`LookupCredentials`] implementation. Requests that fail verification are rejected with the
+//! protocol-specific `AccessDeniedException` error.
+//!
+//! Once a request has been verified, a [`VerifiedIdentity`] is inserted into the request extensions,
+//! and it can be extracted in a handler.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aws_credential_types::Credentials;
+//! use aws_smithy_http_server::plugin::HttpPlugins;
+//! use aws_smithy_http_server::sigv4::{SigV4VerificationExt, StaticCredentials, VerifiedIdentity};
+//!
+//! pub async fn handler(input: Input, identity: VerifiedIdentity) -> Output {
+//!     tracing::info!(access_key_id = identity.access_key_id(), "authenticated request");
+//!     todo!()
+//! }
+//!
+//! let credentials = StaticCredentials::new().with(Credentials::new("AKID", "secret", None, None, "static"));
+//! let http_plugins = HttpPlugins::new().sigv4_verification("us-east-1", "pokemon", credentials);
+//! let config = PokemonServiceConfig::builder().http_plugin(http_plugins).build();
+//! let app = PokemonService::builder(config).operation(handler).build().unwrap();
+//! ```
+//!
+//! Requests signed with `x-amz-content-sha256: UNSIGNED-PAYLOAD` are verified without reading the
+//! body. Otherwise, the body has to be buffered to compute its hash. It is only read once the rest
+//! of the request has been checked, and bodies larger than
+//! [`max_body_size`](SigV4VerificationPlugin::max_body_size) are rejected.
+//!
+//! [SigV4]: https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_aws-signing.html
+
+mod plugin;
+mod service;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use aws_credential_types::Credentials;
+use aws_smithy_async::future::now_or_later::{BoxFuture, NowOrLater};
+use http::request::Parts;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+use thiserror::Error;
+
+use crate::{body::BoxBody, request::FromParts, response::IntoResponse};
+
+pub use aws_sigv4::http_request::{PercentEncodingMode, UriPathNormalizationMode, VerificationSettings};
+pub use plugin::*;
+pub use service::*;
+
+/// Future returned by [`LookupCredentials::lookup`].
+pub struct LookupFuture<'a>(NowOrLater<Option<Credentials>, BoxFuture<'a, Option<Credentials>>>);
+
+impl<'a> LookupFuture<'a> {
+    /// Creates a `LookupFuture` from a future.
+    pub fn new(future: impl Future<Output = Option<Credentials>> + Send + 'a) -> Self {
+        LookupFuture(NowOrLater::new(Box::pin(future)))
+    }
+
+    /// Creates a `LookupFuture` from a resolved value.
+    pub fn ready(credentials: Option<Credentials>) -> Self {
+        LookupFuture(NowOrLater::ready(credentials))
+    }
+}
+
+impl Future for LookupFuture<'_> {
+    type Output = Option<Credentials>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+impl Debug for LookupFuture<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LookupFuture").finish_non_exhaustive()
+    }
+}
+
+/// Resolves the credentials of a signer from the access key ID found in its request.
+pub trait LookupCredentials: Debug + Send + Sync {
+    /// Returns the credentials identified by `access_key_id`, or `None` if the access key ID is
+    /// unknown.
+    ///
+    /// If the returned credentials include a session token, requests must be signed with that same
+    /// session token.
+    fn lookup<'a>(&'a self, access_key_id: &'a str) -> LookupFuture<'a>;
+}
+
+impl<T: LookupCredentials + ?Sized> LookupCredentials for Arc<T> {
+    fn lookup<'a>(&'a self, access_key_id: &'a str) -> LookupFuture<'a> {
+        T::lookup(self, access_key_id)
+    }
+}
+
+/// A [`LookupCredentials`] implementation backed by a fixed set of credentials.
+#[derive(Clone, Debug, Default)]
+pub struct StaticCredentials {
+    credentials: HashMap<String, Credentials>,
+}
+
+impl StaticCredentials {
+    /// Creates an empty set of credentials.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `credentials` to the set, replacing any credentials with the same access key ID.
+    pub fn with(mut self, credentials: Credentials) -> Self {
+        self.credentials
+            .insert(credentials.access_key_id().to_owned(), credentials);
+        self
+    }
+}
+
+impl LookupCredentials for StaticCredentials {
+    fn lookup<'a>(&'a self, access_key_id: &'a str) -> LookupFuture<'a> {
+        LookupFuture::ready(self.credentials.get(access_key_id).cloned())
+    }
+}
+
+/// The identity of the signer of a request, inserted into the request extensions by
+/// [`SigV4VerificationService`] after its signature has been verified.
+///
+/// If it is missing, the request will be rejected with a `500 Internal Server Error` response.
+#[derive(Clone)]
+pub struct VerifiedIdentity {
+    credentials: Credentials,
+    region: String,
+    service: String,
+    signing_time: SystemTime,
+    presigned: bool,
+}
+
+impl VerifiedIdentity {
+    /// The access key ID the request was signed with.
+    pub fn access_key_id(&self) -> &str {
+        self.credentials.access_key_id()
+    }
+
+    /// The credentials the request was signed with, as returned by [`LookupCredentials`].
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    /// The region from the credential scope of the signature.
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    /// The service signing name from the credential scope of the signature.
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// The time the request was signed at.
+    pub fn signing_time(&self) -> SystemTime {
+        self.signing_time
+    }
+
+    /// Whether the signature was found in the query string of a presigned request, rather than in
+    /// the `Authorization` header.
+    pub fn is_presigned(&self) -> bool {
+        self.presigned
+    }
+}
+
+impl Debug for VerifiedIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VerifiedIdentity")
+            .field("access_key_id", &self.access_key_id())
+            .field("region", &self.region)
+            .field("service", &self.service)
+            .field("signing_time", &self.signing_time)
+            .field("presigned", &self.presigned)
+            .finish()
+    }
+}
+
+/// The [`VerifiedIdentity`] was not found in the [`http::Request`] extensions.
+///
+/// Use [`SigV4VerificationPlugin`] to ensure it's present.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+#[error("`VerifiedIdentity` is not present in the `http::Request` extensions - consider using `aws_smithy_http_server::sigv4::SigV4VerificationPlugin`")]
+pub struct MissingVerifiedIdentity;
+
+impl<Protocol> IntoResponse<Protocol> for MissingVerifiedIdentity {
+    fn into_response(self) -> http::Response<BoxBody> {
+        crate::request::internal_server_error()
+    }
+}
+
+impl<P> FromParts<P> for VerifiedIdentity {
+    type Rejection = MissingVerifiedIdentity;
+
+    fn from_parts(parts: &mut Parts) -> Result<Self, Self::Rejection> {
+        parts.extensions.remove().ok_or(MissingVerifiedIdentity)
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aws_smithy_async::time::SharedTimeSource;
+
+use crate::plugin::{HttpMarker, HttpPlugins, Plugin, PluginStack};
+use crate::service::ServiceShape;
+
+use super::{LookupCredentials, SigV4VerificationService, VerificationSettings};
+
+/// The largest body buffered to compute its hash when no maximum size is configured: 10 MiB.
+const DEFAULT_MAX_BODY_SIZE: u64 = 10 * 1024 * 1024;
+
+#[derive(Clone, Debug)]
+pub(crate) struct VerificationConfig {
+    pub(crate) region: Cow<'static, str>,
+    pub(crate) service: Cow<'static, str>,
+    pub(crate) credentials: Arc<dyn LookupCredentials>,
+    pub(crate) settings: VerificationSettings,
+    pub(crate) time_source: SharedTimeSource,
+    pub(crate) max_body_size: u64,
+}
+
+/// A [`Plugin`] which applies [`SigV4VerificationService`] to every operation.
+///
+/// Requests must be signed for the configured region and service signing name.
+#[derive(Clone, Debug)]
+pub struct SigV4VerificationPlugin {
+    config: VerificationConfig,
+}
+
+impl SigV4VerificationPlugin {
+    /// Creates a plugin verifying requests signed for `region` and `service`, with credentials
+    /// resolved by `credentials`.
+    pub fn new(
+        region: impl Into<Cow<'static, str>>,
+        service: impl Into<Cow<'static, str>>,
+        credentials: impl LookupCredentials + 'static,
+    ) -> Self {
+        Self {
+            config: VerificationConfig {
+                region: region.into(),
+                service: service.into(),
+                credentials: Arc::new(credentials),
+                settings: VerificationSettings::default(),
+                time_source: SharedTimeSource::default(),
+                max_body_size: DEFAULT_MAX_BODY_SIZE,
+            },
+        }
+    }
+
+    /// Sets the settings used to verify signatures. These must match the settings clients sign
+    /// requests with.
+    pub fn settings(mut self, settings: VerificationSettings) -> Self {
+        self.config.settings = settings;
+        self
+    }
+
+    /// Sets the maximum difference allowed between the signing time of a request and the time the
+    /// server receives it. Defaults to 5 minutes.
+    pub fn max_clock_skew(mut self, max_clock_skew: Duration) -> Self {
+        self.config.settings.max_clock_skew = max_clock_skew;
+        self
+    }
+
+    /// Sets the time source the signing time of requests is checked against.
+    pub fn time_source(mut self, time_source: SharedTimeSource) -> Self {
+        self.config.time_source = time_source;
+        self
+    }
+
+    /// Sets the largest body, in bytes, that is buffered to compute its hash. Larger bodies are
+    /// rejected with the protocol-specific `PayloadTooLargeException` error. Defaults to 10 MiB.
+    ///
+    /// Bodies of requests signed with `x-amz-content-sha256: UNSIGNED-PAYLOAD` aren't read, so they
+    /// aren't limited.
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.config.max_body_size = max_body_size;
+        self
+    }
+}
+
+impl<Ser, Op, T> Plugin<Ser, Op, T> for SigV4VerificationPlugin
+where
+    Ser: ServiceShape,
+{
+    type Output = SigV4VerificationService<T, Ser::Protocol>;
+
+    fn apply(&self, inner: T) -> Self::Output {
+        SigV4VerificationService::new(inner, Arc::new(self.config.clone()))
+    }
+}
+
+impl HttpMarker for SigV4VerificationPlugin {}
+
+/// An extension trait for applying [`SigV4VerificationPlugin`].
+pub trait SigV4VerificationExt<CurrentPlugin> {
+    /// Verifies the SigV4 signature of every request, rejecting requests that aren't signed for
+    /// `region` and `service` with credentials known to `credentials`.
+    ///
+    /// See [`SigV4VerificationPlugin`] for more information.
+    fn sigv4_verification(
+        self,
+        region: impl Into<Cow<'static, str>>,
+        service: impl Into<Cow<'static, str>>,
+        credentials: impl LookupCredentials + 'static,
+    ) -> HttpPlugins<PluginStack<SigV4VerificationPlugin, CurrentPlugin>>;
+}
+
+impl<CurrentPlugin> SigV4VerificationExt<CurrentPlugin> for HttpPlugins<CurrentPlugin> {
+    fn sigv4_verification(
+        self,
+        region: impl Into<Cow<'static, str>>,
+        service: impl Into<Cow<'static, str>>,
+        credentials: impl LookupCredentials + 'static,
+    ) -> HttpPlugins<PluginStack<SigV4VerificationPlugin, CurrentPlugin>> {
+        self.push(SigV4VerificationPlugin::new(region, service, credentials))
+    }
+}
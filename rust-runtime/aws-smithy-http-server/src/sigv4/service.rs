@@ -0,0 +1,427 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use aws_credential_types::Credentials;
+use aws_sigv4::http_request::{
+    parse_signature, verify, verify_signing_time, ParsedSignature, SignableBody, SignableRequest, SignatureLocation,
+};
+use aws_smithy_async::future::now_or_later::BoxFuture;
+use bytes::Bytes;
+use http::request::Parts;
+use http::{Request, Response};
+use http_body::{LengthLimitError, Limited};
+use tower::{Service, ServiceExt};
+
+use crate::body::{Body, BoxBody};
+use crate::error::BoxError;
+use crate::response::IntoResponse;
+use crate::runtime_error::{AccessDeniedException, PayloadTooLargeException};
+
+use super::plugin::VerificationConfig;
+use super::VerifiedIdentity;
+
+const X_AMZ_CONTENT_SHA_256: &str = "x-amz-content-sha256";
+
+/// A middleware [`Service`] that verifies the SigV4 signature of requests before passing them to the
+/// inner service.
+///
+/// Requests that aren't signed, or whose signature can't be verified, are rejected with the
+/// protocol-specific `AccessDeniedException` error. Verified requests carry a [`VerifiedIdentity`]
+/// in their extensions.
+///
+/// The signature, its credential scope and its signing time are checked before the body is read.
+/// Bodies that have to be buffered to compute their hash are rejected with the protocol-specific
+/// `PayloadTooLargeException` error once they exceed the configured maximum size.
+pub struct SigV4VerificationService<S, P> {
+    inner: S,
+    config: Arc<VerificationConfig>,
+    _protocol: PhantomData<fn(P)>,
+}
+
+impl<S, P> SigV4VerificationService<S, P> {
+    pub(crate) fn new(inner: S, config: Arc<VerificationConfig>) -> Self {
+        Self {
+            inner,
+            config,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: Clone, P> Clone for SigV4VerificationService<S, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            config: self.config.clone(),
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, P> fmt::Debug for SigV4VerificationService<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigV4VerificationService")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S, P> Service<Request<Body>> for SigV4VerificationService<S, P>
+where
+    S: Service<Request<Body>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    AccessDeniedException: IntoResponse<P>,
+    PayloadTooLargeException: IntoResponse<P>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The check that the service is ready is done by `Oneshot` below.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let clone = self.inner.clone();
+        let service = std::mem::replace(&mut self.inner, clone);
+        let config = self.config.clone();
+
+        Box::pin(async move {
+            let (mut parts, body) = req.into_parts();
+            // Everything but the payload is checked first, so that callers that can't be
+            // authenticated can't make the server buffer their payload
+            let (signature, credentials) = match authenticate(&parts, &config).await {
+                Ok(authenticated) => authenticated,
+                Err(reason) => {
+                    tracing::debug!(%reason, "rejecting request that failed SigV4 verification");
+                    return Ok(IntoResponse::<P>::into_response(AccessDeniedException));
+                }
+            };
+
+            let body = match read_signed_body(&parts, body, config.max_body_size).await {
+                Ok(body) => body,
+                Err(ReadError::TooLarge) => {
+                    tracing::debug!(
+                        max_body_size = config.max_body_size,
+                        "rejecting signed request whose body is too large to be verified"
+                    );
+                    return Ok(IntoResponse::<P>::into_response(PayloadTooLargeException));
+                }
+                Err(ReadError::Body(err)) => {
+                    tracing::debug!(error = %err, "failed to read the body of a signed request");
+                    return Ok(IntoResponse::<P>::into_response(AccessDeniedException));
+                }
+            };
+
+            // Bodies that weren't read were verified by `authenticate` already
+            if let SignedBody::Buffered(bytes) = &body {
+                let request = signable_request(&parts, SignableBody::Bytes(bytes));
+                let verified = request.and_then(|request| {
+                    verify(
+                        &request,
+                        &signature,
+                        &credentials,
+                        &config.settings,
+                        config.time_source.now(),
+                    )
+                    .map_err(|err| err.to_string())
+                });
+                if let Err(reason) = verified {
+                    tracing::debug!(%reason, "rejecting request that failed SigV4 verification");
+                    return Ok(IntoResponse::<P>::into_response(AccessDeniedException));
+                }
+            }
+            parts.extensions.insert(VerifiedIdentity {
+                credentials,
+                region: signature.region().to_owned(),
+                service: signature.service().to_owned(),
+                signing_time: signature.time(),
+                presigned: signature.location() == SignatureLocation::QueryParams,
+            });
+
+            let body = match body {
+                SignedBody::Buffered(bytes) => Body::from(bytes),
+                SignedBody::Unsigned(body) => body,
+            };
+            service.oneshot(Request::from_parts(parts, body)).await
+        })
+    }
+}
+
+enum SignedBody {
+    /// The body was read to compute its hash.
+    Buffered(Bytes),
+    /// The client declared the body as not signed through `x-amz-content-sha256`, so it doesn't need to be read.
+    Unsigned(Body),
+}
+
+enum ReadError {
+    /// The body is larger than the maximum size of buffered bodies.
+    TooLarge,
+    /// The body couldn't be read.
+    Body(BoxError),
+}
+
+async fn read_signed_body(parts: &Parts, body: Body, max_body_size: u64) -> Result<SignedBody, ReadError> {
+    match parts.headers.get(X_AMZ_CONTENT_SHA_256) {
+        // The payload hash is taken from the header as is, e.g. `UNSIGNED-PAYLOAD`
+        Some(content_sha256) if !is_digest(content_sha256.as_bytes()) => Ok(SignedBody::Unsigned(body)),
+        _ => {
+            let limit = usize::try_from(max_body_size).unwrap_or(usize::MAX);
+            match hyper::body::to_bytes(Limited::new(body, limit)).await {
+                Ok(bytes) => Ok(SignedBody::Buffered(bytes)),
+                Err(err) if err.is::<LengthLimitError>() => Err(ReadError::TooLarge),
+                Err(err) => Err(ReadError::Body(err)),
+            }
+        }
+    }
+}
+
+fn is_digest(value: &[u8]) -> bool {
+    value.len() == 64 && value.iter().all(u8::is_ascii_hexdigit)
+}
+
+fn signable_request<'a>(parts: &'a Parts, body: SignableBody<'a>) -> Result<SignableRequest<'a>, String> {
+    // Header values that aren't valid UTF-8 can't have been signed by `aws-sigv4`
+    let headers = parts
+        .headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?)));
+    SignableRequest::new(parts.method.as_str(), parts.uri.to_string(), headers, body).map_err(|err| err.to_string())
+}
+
+/// Checks the signature of a request without reading its body, and returns the credentials it was
+/// signed with.
+///
+/// When the request carries its payload hash in the `x-amz-content-sha256` header, the signature is
+/// verified in full against that hash. Otherwise, only its credential scope and signing time are.
+async fn authenticate(parts: &Parts, config: &VerificationConfig) -> Result<(ParsedSignature, Credentials), String> {
+    let request = signable_request(parts, SignableBody::UnsignedPayload)?;
+    let signature = parse_signature(&request).map_err(|err| err.to_string())?;
+    if signature.region() != config.region || signature.service() != config.service {
+        return Err(format!(
+            "the request is signed for `{}` in `{}`",
+            signature.service(),
+            signature.region()
+        ));
+    }
+    let now = config.time_source.now();
+    verify_signing_time(&signature, &config.settings, now).map_err(|err| err.to_string())?;
+    let credentials = config
+        .credentials
+        .lookup(signature.access_key_id())
+        .await
+        .ok_or_else(|| format!("unknown access key ID `{}`", signature.access_key_id()))?;
+    if parts.headers.contains_key(X_AMZ_CONTENT_SHA_256) {
+        verify(&request, &signature, &credentials, &config.settings, now).map_err(|err| err.to_string())?;
+    }
+    Ok((signature, credentials))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use aws_credential_types::Credentials;
+    use aws_sigv4::http_request::{sign, PayloadChecksumKind, SigningSettings};
+    use aws_sigv4::sign::v4;
+    use aws_smithy_async::time::{SharedTimeSource, StaticTimeSource};
+    use http::StatusCode;
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+    use crate::protocol::rest_json_1::RestJson1;
+    use crate::sigv4::StaticCredentials;
+
+    fn signing_time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    fn credentials() -> Credentials {
+        Credentials::new("AKIDEXAMPLE", "secret", None, None, "test")
+    }
+
+    fn signed_request(body: &'static str, settings: SigningSettings, credentials: &Credentials) -> Request<Body> {
+        let mut request = http::Request::builder()
+            .method("POST")
+            .uri("/pokemon?name=pikachu")
+            .header("host", "localhost:13734")
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap();
+        let identity = credentials.clone().into();
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region("us-east-1")
+            .name("pokemon")
+            .time(signing_time())
+            .settings(settings)
+            .build()
+            .unwrap()
+            .into();
+        let signable = SignableRequest::new(
+            request.method().as_str(),
+            request.uri().to_string(),
+            request.headers().iter().map(|(k, v)| (k.as_str(), v.to_str().unwrap())),
+            SignableBody::Bytes(body.as_bytes()),
+        )
+        .unwrap();
+        let (instructions, _) = sign(signable, &params).unwrap().into_parts();
+        instructions.apply_to_request_http0x(&mut request);
+        request.map(Body::from)
+    }
+
+    fn config(now: SystemTime) -> VerificationConfig {
+        VerificationConfig {
+            region: "us-east-1".into(),
+            service: "pokemon".into(),
+            credentials: Arc::new(StaticCredentials::new().with(credentials())),
+            settings: Default::default(),
+            time_source: SharedTimeSource::new(StaticTimeSource::new(now)),
+            max_body_size: 1024,
+        }
+    }
+
+    async fn call(request: Request<Body>, now: SystemTime) -> (StatusCode, Option<VerifiedIdentity>) {
+        call_with(request, config(now)).await
+    }
+
+    async fn call_with(request: Request<Body>, config: VerificationConfig) -> (StatusCode, Option<VerifiedIdentity>) {
+        let config = Arc::new(config);
+        let inner = service_fn(|request: Request<Body>| async move {
+            let identity = request.extensions().get::<VerifiedIdentity>().cloned();
+            let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+            assert_eq!(&body[..], b"{\"name\":\"pikachu\"}");
+            let mut response = Response::new(crate::body::empty());
+            response.extensions_mut().insert(identity);
+            Ok::<_, Infallible>(response)
+        });
+        let service = SigV4VerificationService::<_, RestJson1>::new(inner, config);
+        let mut response = service.oneshot(request).await.unwrap();
+        let identity = response.extensions_mut().remove::<Option<VerifiedIdentity>>().flatten();
+        (response.status(), identity)
+    }
+
+    #[tokio::test]
+    async fn accepts_signed_requests() {
+        let request = signed_request("{\"name\":\"pikachu\"}", SigningSettings::default(), &credentials());
+        let (status, identity) = call(request, signing_time()).await;
+
+        assert_eq!(StatusCode::OK, status);
+        let identity = identity.expect("the identity is added to the request extensions");
+        assert_eq!("AKIDEXAMPLE", identity.access_key_id());
+        assert_eq!("us-east-1", identity.region());
+        assert_eq!("pokemon", identity.service());
+        assert_eq!(signing_time(), identity.signing_time());
+        assert!(!identity.is_presigned());
+    }
+
+    #[tokio::test]
+    async fn accepts_presigned_requests() {
+        let mut settings = SigningSettings::default();
+        settings.signature_location = SignatureLocation::QueryParams;
+        settings.expires_in = Some(Duration::from_secs(60));
+        let request = signed_request("{\"name\":\"pikachu\"}", settings, &credentials());
+        let (status, identity) = call(request, signing_time() + Duration::from_secs(30)).await;
+
+        assert_eq!(StatusCode::OK, status);
+        assert!(identity.unwrap().is_presigned());
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_requests() {
+        let unsigned = Request::builder()
+            .uri("/pokemon")
+            .header("host", "localhost:13734")
+            .body(Body::from("{\"name\":\"pikachu\"}"))
+            .unwrap();
+        let (status, _) = call(unsigned, signing_time()).await;
+        assert_eq!(StatusCode::FORBIDDEN, status);
+
+        let unknown = Credentials::new("AKIDUNKNOWN", "secret", None, None, "test");
+        let request = signed_request("{\"name\":\"pikachu\"}", SigningSettings::default(), &unknown);
+        let (status, _) = call(request, signing_time()).await;
+        assert_eq!(StatusCode::FORBIDDEN, status);
+
+        let request = signed_request("{\"name\":\"pikachu\"}", SigningSettings::default(), &credentials());
+        let (status, _) = call(request, signing_time() + Duration::from_secs(600)).await;
+        assert_eq!(StatusCode::FORBIDDEN, status);
+    }
+
+    /// A body that fails the test if it is read.
+    fn unreadable_body() -> Body {
+        Body::wrap_stream(futures_util::stream::poll_fn(
+            |_| -> Poll<Option<Result<Bytes, Infallible>>> { panic!("the body must not be read") },
+        ))
+    }
+
+    #[tokio::test]
+    async fn bodies_are_only_read_once_the_rest_of_the_request_is_verified() {
+        let unsigned = Request::builder()
+            .uri("/pokemon")
+            .header("host", "localhost:13734")
+            .body(unreadable_body())
+            .unwrap();
+        let (status, _) = call(unsigned, signing_time()).await;
+        assert_eq!(StatusCode::FORBIDDEN, status);
+
+        let unknown = Credentials::new("AKIDUNKNOWN", "secret", None, None, "test");
+        let request = signed_request("{\"name\":\"pikachu\"}", SigningSettings::default(), &unknown);
+        let (status, _) = call(request.map(|_| unreadable_body()), signing_time()).await;
+        assert_eq!(StatusCode::FORBIDDEN, status);
+
+        let request = signed_request("{\"name\":\"pikachu\"}", SigningSettings::default(), &credentials());
+        let (status, _) = call(
+            request.map(|_| unreadable_body()),
+            signing_time() + Duration::from_secs(600),
+        )
+        .await;
+        assert_eq!(StatusCode::FORBIDDEN, status);
+
+        // The payload hash is signed, so the signature is checked against it before reading the body
+        let mut settings = SigningSettings::default();
+        settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+        let mut request = signed_request("{\"name\":\"pikachu\"}", settings, &credentials());
+        request.headers_mut().insert("host", "localhost:1".parse().unwrap());
+        let (status, _) = call(request.map(|_| unreadable_body()), signing_time()).await;
+        assert_eq!(StatusCode::FORBIDDEN, status);
+    }
+
+    #[tokio::test]
+    async fn rejects_bodies_larger_than_the_maximum_size() {
+        let request = signed_request("{\"name\":\"pikachu\"}", SigningSettings::default(), &credentials());
+        let config = VerificationConfig {
+            max_body_size: 4,
+            ..config(signing_time())
+        };
+        let (status, _) = call_with(request, config).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, status);
+    }
+
+    #[tokio::test]
+    async fn accepts_requests_with_signed_payload_hashes() {
+        let mut settings = SigningSettings::default();
+        settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+        let request = signed_request("{\"name\":\"pikachu\"}", settings, &credentials());
+        let (status, identity) = call(request, signing_time()).await;
+        assert_eq!(StatusCode::OK, status);
+        assert!(identity.is_some());
+    }
+
+    #[test]
+    fn rejection_is_protocol_specific() {
+        let response = IntoResponse::<RestJson1>::into_response(AccessDeniedException);
+        assert_eq!(StatusCode::FORBIDDEN, response.status());
+        assert_eq!(response.headers()["x-amzn-errortype"], "AccessDeniedException");
+    }
+}
@@ -0,0 +1,34 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+#![deny(missing_docs, missing_debug_implementations)]
+
+//! Provides [`OperationTimeoutPlugin`], which bounds the time operations take to handle requests.
+//!
+//! Requests that aren't handled before their deadline are cancelled, by dropping the operation
+//! handler, and rejected with the protocol-specific `OperationTimeoutException` error and a
+//! `504 Gateway Timeout` status code. The deadline covers reading and deserializing the request,
+//! the operation handler, and serializing the response, but not sending the response body.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use aws_smithy_http_server::plugin::HttpPlugins;
+//! # use aws_smithy_http_server::shape_id::ShapeId;
+//! use aws_smithy_http_server::timeout::{OperationTimeoutExt, OperationTimeoutPlugin};
+//! use std::time::Duration;
+//!
+//! # let get_storage = ShapeId::new("com.example#GetStorage", "com.example", "GetStorage");
+//! let timeouts = OperationTimeoutPlugin::new(Duration::from_secs(10))
+//!     // `GetStorage` may take up to a minute
+//!     .operation_timeout(get_storage, Duration::from_secs(60));
+//! let http_plugins = HttpPlugins::new().operation_timeouts(timeouts);
+//! ```
+
+mod plugin;
+mod service;
+
+pub use plugin::*;
+pub use service::*;
@@ -0,0 +1,74 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::operation::OperationShape;
+use crate::plugin::{HttpMarker, HttpPlugins, Plugin, PluginStack};
+use crate::service::ServiceShape;
+use crate::shape_id::ShapeId;
+
+use super::OperationTimeoutService;
+
+/// A [`Plugin`] which applies [`OperationTimeoutService`] to every operation.
+#[derive(Debug, Clone)]
+pub struct OperationTimeoutPlugin {
+    default_timeout: Duration,
+    operations: Arc<HashMap<ShapeId, Duration>>,
+}
+
+impl OperationTimeoutPlugin {
+    /// Creates a plugin that bounds the time every operation takes to handle a request to
+    /// `default_timeout`, unless [`operation_timeout`](Self::operation_timeout) gives another
+    /// timeout.
+    pub fn new(default_timeout: Duration) -> Self {
+        Self {
+            default_timeout,
+            operations: Default::default(),
+        }
+    }
+
+    /// Bounds the time `operation` takes to handle a request to `timeout`.
+    pub fn operation_timeout(mut self, operation: ShapeId, timeout: Duration) -> Self {
+        Arc::make_mut(&mut self.operations).insert(operation, timeout);
+        self
+    }
+}
+
+impl<Ser, Op, T> Plugin<Ser, Op, T> for OperationTimeoutPlugin
+where
+    Ser: ServiceShape,
+    Op: OperationShape,
+{
+    type Output = OperationTimeoutService<T, Ser::Protocol>;
+
+    fn apply(&self, inner: T) -> Self::Output {
+        let timeout = self.operations.get(&Op::ID).copied().unwrap_or(self.default_timeout);
+        OperationTimeoutService::new(inner, Op::ID, timeout)
+    }
+}
+
+impl HttpMarker for OperationTimeoutPlugin {}
+
+/// An extension trait for applying [`OperationTimeoutPlugin`].
+pub trait OperationTimeoutExt<CurrentPlugin> {
+    /// Bounds the time operations take to handle requests, as configured by `timeouts`. See
+    /// [`OperationTimeoutPlugin`] for more information.
+    fn operation_timeouts(
+        self,
+        timeouts: OperationTimeoutPlugin,
+    ) -> HttpPlugins<PluginStack<OperationTimeoutPlugin, CurrentPlugin>>;
+}
+
+impl<CurrentPlugin> OperationTimeoutExt<CurrentPlugin> for HttpPlugins<CurrentPlugin> {
+    fn operation_timeouts(
+        self,
+        timeouts: OperationTimeoutPlugin,
+    ) -> HttpPlugins<PluginStack<OperationTimeoutPlugin, CurrentPlugin>> {
+        self.push(timeouts)
+    }
+}
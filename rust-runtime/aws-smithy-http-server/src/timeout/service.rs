@@ -0,0 +1,176 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::fmt;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use http::{Request, Response};
+use pin_project_lite::pin_project;
+use tokio::time::Timeout;
+use tower::Service;
+
+use crate::body::BoxBody;
+use crate::response::IntoResponse;
+use crate::runtime_error::OperationTimeoutException;
+use crate::shape_id::ShapeId;
+
+pin_project! {
+    /// Future for [`OperationTimeoutService`].
+    pub struct OperationTimeoutFuture<F, P> {
+        #[pin]
+        inner: Timeout<F>,
+        operation_id: ShapeId,
+        timeout: Duration,
+        _protocol: PhantomData<fn(P)>,
+    }
+}
+
+impl<F, P> fmt::Debug for OperationTimeoutFuture<F, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OperationTimeoutFuture")
+            .field("operation_id", &self.operation_id)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, E, P> Future for OperationTimeoutFuture<F, P>
+where
+    F: Future<Output = Result<Response<BoxBody>, E>>,
+    OperationTimeoutException: IntoResponse<P>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match this.inner.poll(cx) {
+            Poll::Ready(Ok(result)) => Poll::Ready(result),
+            Poll::Ready(Err(_elapsed)) => {
+                tracing::debug!(
+                    operation = %this.operation_id.absolute(),
+                    timeout = ?this.timeout,
+                    "operation did not complete before its deadline"
+                );
+                Poll::Ready(Ok(IntoResponse::<P>::into_response(OperationTimeoutException)))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A middleware [`Service`] that bounds the time the inner service takes to respond.
+///
+/// Requests that aren't handled within the timeout are cancelled, and rejected with the
+/// protocol-specific `OperationTimeoutException` error.
+pub struct OperationTimeoutService<S, P> {
+    inner: S,
+    operation_id: ShapeId,
+    timeout: Duration,
+    _protocol: PhantomData<fn(P)>,
+}
+
+impl<S, P> OperationTimeoutService<S, P> {
+    /// Creates a service that responds to requests for the operation `operation_id` within
+    /// `timeout`.
+    pub fn new(inner: S, operation_id: ShapeId, timeout: Duration) -> Self {
+        Self {
+            inner,
+            operation_id,
+            timeout,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: Clone, P> Clone for OperationTimeoutService<S, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            operation_id: self.operation_id.clone(),
+            timeout: self.timeout,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+impl<S: fmt::Debug, P> fmt::Debug for OperationTimeoutService<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OperationTimeoutService")
+            .field("inner", &self.inner)
+            .field("operation_id", &self.operation_id)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl<S, P, B> Service<Request<B>> for OperationTimeoutService<S, P>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>>,
+    OperationTimeoutException: IntoResponse<P>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = OperationTimeoutFuture<S::Future, P>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        OperationTimeoutFuture {
+            inner: tokio::time::timeout(self.timeout, self.inner.call(req)),
+            operation_id: self.operation_id.clone(),
+            timeout: self.timeout,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::StatusCode;
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+    use crate::protocol::aws_json_11::AwsJson1_1;
+    use crate::protocol::rest_json_1::RestJson1;
+
+    async fn call<P>(handler_duration: Duration) -> Response<BoxBody>
+    where
+        OperationTimeoutException: IntoResponse<P>,
+    {
+        let inner = service_fn(move |_: Request<()>| async move {
+            tokio::time::sleep(handler_duration).await;
+            Ok::<_, Infallible>(Response::new(crate::body::empty()))
+        });
+        let operation_id = ShapeId::new("com.example#GetStorage", "com.example", "GetStorage");
+        OperationTimeoutService::<_, P>::new(inner, operation_id, Duration::from_secs(10))
+            .oneshot(Request::new(()))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn operations_completing_in_time_are_not_affected() {
+        let response = call::<RestJson1>(Duration::from_secs(9)).await;
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn operations_exceeding_their_deadline_are_rejected() {
+        let response = call::<RestJson1>(Duration::from_secs(11)).await;
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, response.status());
+        assert_eq!(response.headers()["x-amzn-errortype"], "OperationTimeoutException");
+
+        let response = call::<AwsJson1_1>(Duration::from_secs(11)).await;
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, response.status());
+    }
+}
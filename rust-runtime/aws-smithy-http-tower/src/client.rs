@@ -0,0 +1,255 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpClient,
+    SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::body::SdkBody;
+use bytes::Bytes;
+use std::fmt;
+use std::future::poll_fn;
+use std::sync::Arc;
+use tower_service::Service;
+
+type MapError = Arc<dyn Fn(BoxError) -> ConnectorError + Send + Sync>;
+
+/// Builder for [`TowerHttpClient`].
+#[derive(Default)]
+#[non_exhaustive]
+pub struct TowerHttpClientBuilder {
+    map_error: Option<MapError>,
+}
+
+impl fmt::Debug for TowerHttpClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TowerHttpClientBuilder")
+            .field(
+                "map_error",
+                &self.map_error.as_ref().map(|_| "** user provided **"),
+            )
+            .finish()
+    }
+}
+
+impl TowerHttpClientBuilder {
+    /// Creates a new builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the function used to classify errors returned by the service into [`ConnectorError`]s.
+    ///
+    /// The classification decides how the orchestrator treats the failure, for example only
+    /// [`ConnectorError::timeout`] and [`ConnectorError::io`] errors are retried as transient errors.
+    ///
+    /// By default, errors that already are a [`ConnectorError`] are passed through unchanged,
+    /// and all other errors become [`ConnectorError::other`] errors.
+    pub fn map_error(
+        mut self,
+        map_error: impl Fn(BoxError) -> ConnectorError + Send + Sync + 'static,
+    ) -> Self {
+        self.map_error = Some(Arc::new(map_error));
+        self
+    }
+
+    /// Builds a [`TowerHttpClient`] that sends requests through the given `service`.
+    ///
+    /// The service is cloned for every request, so services that share state between clones
+    /// (such as `tower::buffer::Buffer`) should be used to share a limit or a connection pool
+    /// across requests.
+    pub fn build<S, B>(self, service: S) -> SharedHttpClient
+    where
+        S: Service<http::Request<SdkBody>, Response = http::Response<B>>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Error: Into<BoxError>,
+        S::Future: Send + 'static,
+        B: http_body::Body<Data = Bytes> + Send + Sync + 'static,
+        B::Error: Into<BoxError>,
+    {
+        let connector = TowerHttpConnector {
+            service,
+            map_error: self
+                .map_error
+                .unwrap_or_else(|| Arc::new(default_map_error)),
+        };
+        TowerHttpClient {
+            connector: connector.into_shared(),
+        }
+        .into_shared()
+    }
+}
+
+/// An HTTP client that sends requests through a [`tower_service::Service`].
+///
+/// The timeouts in [`HttpConnectorSettings`] are not applied by this client, they should be
+/// configured with tower middleware instead. Operation timeouts are still enforced by the
+/// orchestrator.
+#[derive(Debug, Clone)]
+pub struct TowerHttpClient {
+    connector: SharedHttpConnector,
+}
+
+impl TowerHttpClient {
+    /// Returns a builder for a [`TowerHttpClient`].
+    pub fn builder() -> TowerHttpClientBuilder {
+        TowerHttpClientBuilder::new()
+    }
+}
+
+impl HttpClient for TowerHttpClient {
+    fn http_connector(
+        &self,
+        _settings: &HttpConnectorSettings,
+        _components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        self.connector.clone()
+    }
+}
+
+struct TowerHttpConnector<S> {
+    service: S,
+    map_error: MapError,
+}
+
+impl<S> fmt::Debug for TowerHttpConnector<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TowerHttpConnector")
+            .field("service", &std::any::type_name::<S>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, B> HttpConnector for TowerHttpConnector<S>
+where
+    S: Service<http::Request<SdkBody>, Response = http::Response<B>>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    B: http_body::Body<Data = Bytes> + Send + Sync + 'static,
+    B::Error: Into<BoxError>,
+{
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let mut service = self.service.clone();
+        let map_error = self.map_error.clone();
+        HttpConnectorFuture::new(async move {
+            let request = request
+                .try_into_http1x()
+                .map_err(|err| ConnectorError::user(err.into()))?;
+            poll_fn(|cx| service.poll_ready(cx))
+                .await
+                .map_err(|err| map_error(err.into()))?;
+            let response = service
+                .call(request)
+                .await
+                .map_err(|err| map_error(err.into()))?;
+            HttpResponse::try_from(response.map(SdkBody::from_body_1_x))
+                .map_err(|err| ConnectorError::other(err.into(), None))
+        })
+    }
+}
+
+fn default_map_error(err: BoxError) -> ConnectorError {
+    match err.downcast::<ConnectorError>() {
+        Ok(connector_error) => *connector_error,
+        Err(err) => ConnectorError::other(err, None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TowerHttpClientBuilder;
+    use aws_smithy_runtime_api::box_error::BoxError;
+    use aws_smithy_runtime_api::client::http::{
+        HttpClient, HttpConnector, HttpConnectorSettings, SharedHttpClient,
+    };
+    use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+    use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+    use aws_smithy_runtime_api::client::result::ConnectorError;
+    use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::byte_stream::ByteStream;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tower::{service_fn, ServiceBuilder};
+
+    fn request() -> HttpRequest {
+        HttpRequest::get("https://example.com/hello").unwrap()
+    }
+
+    async fn send(client: &SharedHttpClient) -> Result<HttpResponse, ConnectorError> {
+        let components = RuntimeComponentsBuilder::for_tests().build().unwrap();
+        let connector =
+            client.http_connector(&HttpConnectorSettings::builder().build(), &components);
+        connector.call(request()).await
+    }
+
+    #[tokio::test]
+    async fn sends_requests_through_tower_middleware() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = {
+            let calls = calls.clone();
+            service_fn(move |req: http::Request<SdkBody>| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    assert_eq!("/hello", req.uri().path());
+                    Ok::<_, Infallible>(http::Response::new(SdkBody::from("hello!")))
+                }
+            })
+        };
+        let service = ServiceBuilder::new()
+            .buffer(4)
+            .concurrency_limit(1)
+            .service(service);
+        let client = TowerHttpClientBuilder::new().build(service);
+
+        for _ in 0..2 {
+            let response = send(&client).await.expect("success");
+            assert_eq!(200, response.status().as_u16());
+            let body = ByteStream::new(response.into_body()).collect().await;
+            assert_eq!(b"hello!", &body.unwrap().into_bytes()[..]);
+        }
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn errors_are_classified_by_the_mapper() {
+        let service = service_fn(|_req: http::Request<SdkBody>| async {
+            Err::<http::Response<SdkBody>, BoxError>("took too long".into())
+        });
+
+        let client = TowerHttpClientBuilder::new().build(service);
+        let err = send(&client).await.expect_err("service failed");
+        assert!(err.is_other(), "{err:?}");
+
+        let client = TowerHttpClientBuilder::new()
+            .map_error(ConnectorError::timeout)
+            .build(service);
+        let err = send(&client).await.expect_err("service failed");
+        assert!(err.is_timeout(), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn connector_errors_are_passed_through_by_default() {
+        let service = service_fn(|_req: http::Request<SdkBody>| async {
+            Err::<http::Response<SdkBody>, _>(ConnectorError::io("connection reset".into()))
+        });
+        let client = TowerHttpClientBuilder::new().build(service);
+        let err = send(&client).await.expect_err("service failed");
+        assert!(err.is_io(), "{err:?}");
+    }
+}
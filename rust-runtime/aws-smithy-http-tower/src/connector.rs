@@ -0,0 +1,140 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_async::future::BoxFuture;
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorSettings, SharedHttpClient, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::body::SdkBody;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+/// A [`tower_service::Service`] that sends requests through an [`HttpConnector`].
+///
+/// This allows HTTP clients that were written for smithy-rs to be used with tower middleware,
+/// or wrapped back into an HTTP client with [`TowerHttpClientBuilder`](crate::client::TowerHttpClientBuilder).
+#[derive(Debug, Clone)]
+pub struct HttpConnectorService {
+    connector: SharedHttpConnector,
+}
+
+impl HttpConnectorService {
+    /// Creates a new service that sends requests through the given `connector`.
+    pub fn new(connector: impl HttpConnector + 'static) -> Self {
+        Self {
+            connector: connector.into_shared(),
+        }
+    }
+
+    /// Creates a new service that sends requests through the connector that `http_client`
+    /// returns for the given `settings` and `components`.
+    pub fn from_http_client(
+        http_client: &SharedHttpClient,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> Self {
+        Self {
+            connector: http_client.http_connector(settings, components),
+        }
+    }
+}
+
+impl Service<http::Request<SdkBody>> for HttpConnectorService {
+    type Response = http::Response<SdkBody>;
+    type Error = ConnectorError;
+    type Future = BoxFuture<'static, Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<SdkBody>) -> Self::Future {
+        let connector = self.connector.clone();
+        Box::pin(async move {
+            let request =
+                HttpRequest::try_from(request).map_err(|err| ConnectorError::user(err.into()))?;
+            let response = connector.call(request).await?;
+            response
+                .try_into_http1x()
+                .map_err(|err| ConnectorError::other(err.into(), None))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HttpConnectorService;
+    use crate::client::TowerHttpClientBuilder;
+    use aws_smithy_runtime_api::client::http::{
+        HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings,
+    };
+    use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+    use aws_smithy_runtime_api::client::result::ConnectorError;
+    use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use aws_smithy_types::body::SdkBody;
+    use tower::ServiceExt;
+
+    #[derive(Debug)]
+    struct PathConnector;
+
+    impl HttpConnector for PathConnector {
+        fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+            let path = http::Uri::try_from(request.uri())
+                .unwrap()
+                .path()
+                .to_string();
+            let response = if path == "/missing" {
+                Err(ConnectorError::io("connection refused".into()))
+            } else {
+                Ok(HttpResponse::new(
+                    200.try_into().unwrap(),
+                    SdkBody::from(path),
+                ))
+            };
+            HttpConnectorFuture::ready(response)
+        }
+    }
+
+    fn request(path: &str) -> http::Request<SdkBody> {
+        http::Request::builder()
+            .uri(format!("https://example.com{path}"))
+            .body(SdkBody::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn calls_the_connector() {
+        let service = HttpConnectorService::new(PathConnector);
+
+        let response = service.clone().oneshot(request("/hello")).await.unwrap();
+        assert_eq!(200, response.status().as_u16());
+        assert_eq!(Some(&b"/hello"[..]), response.body().bytes());
+
+        let err = service.oneshot(request("/missing")).await.unwrap_err();
+        assert!(err.is_io(), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn round_trips_through_an_http_client() {
+        let client = TowerHttpClientBuilder::new().build(HttpConnectorService::new(PathConnector));
+        let components = RuntimeComponentsBuilder::for_tests().build().unwrap();
+        let settings = HttpConnectorSettings::builder().build();
+        let service = HttpConnectorService::from_http_client(&client, &settings, &components);
+
+        let err = service.oneshot(request("/missing")).await.unwrap_err();
+        assert!(err.is_io(), "{err:?}");
+        // the client is still usable as an `HttpClient`
+        let connector = client.http_connector(&settings, &components);
+        let response = connector
+            .call(HttpRequest::get("https://example.com/hello").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(200, response.status().as_u16());
+    }
+}
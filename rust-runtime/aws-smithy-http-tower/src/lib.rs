@@ -6,7 +6,12 @@
 /* Automatically managed default lints */
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 /* End of automatically managed default lints */
-//! This crate is no longer used by smithy-rs and is deprecated.
+//! Adapters between [`tower_service::Service`] and smithy-rs HTTP clients.
+//!
+//! [`client::TowerHttpClientBuilder`] turns any `Service<http::Request<SdkBody>>` into a
+//! [`SharedHttpClient`](aws_smithy_runtime_api::client::http::SharedHttpClient), so that tower
+//! middleware can be used underneath the orchestrator. [`connector::HttpConnectorService`] goes
+//! the other way and exposes an HTTP connector as a `Service`.
 
 #![warn(
     missing_docs,
@@ -14,3 +19,9 @@
     unreachable_pub,
     rust_2018_idioms
 )]
+
+/// Use a `tower::Service` as an HTTP client.
+pub mod client;
+
+/// Use an HTTP connector as a `tower::Service`.
+pub mod connector;
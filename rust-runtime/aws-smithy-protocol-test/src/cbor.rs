@@ -0,0 +1,199 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::{FloatEquals, ProtocolTestFailure};
+use minicbor::data::{Tag, Type};
+use minicbor::decode::Error;
+use minicbor::Decoder;
+
+/// A CBOR data item, decoded without any knowledge of the shape it represents.
+///
+/// Comparing values rather than bytes makes the comparison independent of how each data item
+/// was encoded: definite or indefinite lengths, the width of integers and floats, and the order of
+/// map entries.
+#[derive(Debug)]
+enum Value {
+    Bool(bool),
+    Null,
+    Undefined,
+    Integer(i128),
+    Float(f64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(Tag, Box<Value>),
+    Simple(u8),
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        use Value::*;
+        match (self, other) {
+            (Bool(a), Bool(b)) => a == b,
+            (Null, Null) | (Undefined, Undefined) => true,
+            (Integer(a), Integer(b)) => a == b,
+            (Float(a), Float(b)) => a.float_equals(b),
+            (Bytes(a), Bytes(b)) => a == b,
+            (Text(a), Text(b)) => a == b,
+            (Array(a), Array(b)) => a == b,
+            // Map entries can be in any order.
+            (Map(a), Map(b)) => a.len() == b.len() && a.iter().all(|entry| b.contains(entry)),
+            (Tag(tag_a, a), Tag(tag_b, b)) => tag_a == tag_b && a == b,
+            (Simple(a), Simple(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+fn decode_value(decoder: &mut Decoder<'_>) -> Result<Value, Error> {
+    Ok(match decoder.datatype()? {
+        Type::Bool => Value::Bool(decoder.bool()?),
+        Type::Null => {
+            decoder.null()?;
+            Value::Null
+        }
+        Type::Undefined => {
+            decoder.undefined()?;
+            Value::Undefined
+        }
+        Type::U8 | Type::U16 | Type::U32 | Type::U64 => Value::Integer(decoder.u64()?.into()),
+        Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::Int => {
+            Value::Integer(decoder.int()?.into())
+        }
+        Type::F16 | Type::F32 | Type::F64 => Value::Float(decoder.f64()?),
+        Type::Simple => Value::Simple(decoder.simple()?),
+        Type::Bytes | Type::BytesIndef => {
+            let mut bytes = Vec::new();
+            for chunk in decoder.bytes_iter()? {
+                bytes.extend_from_slice(chunk?);
+            }
+            Value::Bytes(bytes)
+        }
+        Type::String | Type::StringIndef => {
+            let mut text = String::new();
+            for chunk in decoder.str_iter()? {
+                text.push_str(chunk?);
+            }
+            Value::Text(text)
+        }
+        Type::Array | Type::ArrayIndef => {
+            let mut items = Vec::new();
+            match decoder.array()? {
+                Some(len) => {
+                    for _ in 0..len {
+                        items.push(decode_value(decoder)?);
+                    }
+                }
+                None => {
+                    while decoder.datatype()? != Type::Break {
+                        items.push(decode_value(decoder)?);
+                    }
+                    decoder.skip()?;
+                }
+            }
+            Value::Array(items)
+        }
+        Type::Map | Type::MapIndef => {
+            let mut entries = Vec::new();
+            match decoder.map()? {
+                Some(len) => {
+                    for _ in 0..len {
+                        entries.push((decode_value(decoder)?, decode_value(decoder)?));
+                    }
+                }
+                None => {
+                    while decoder.datatype()? != Type::Break {
+                        entries.push((decode_value(decoder)?, decode_value(decoder)?));
+                    }
+                    decoder.skip()?;
+                }
+            }
+            Value::Map(entries)
+        }
+        Type::Tag => {
+            let tag = decoder.tag()?;
+            Value::Tag(tag, Box::new(decode_value(decoder)?))
+        }
+        ty @ (Type::Break | Type::Unknown(_)) => {
+            return Err(Error::type_mismatch(ty).with_message("unexpected data item"))
+        }
+    })
+}
+
+fn decode(bytes: &[u8]) -> Result<Value, Error> {
+    let mut decoder = Decoder::new(bytes);
+    let value = decode_value(&mut decoder)?;
+    if decoder.position() != bytes.len() {
+        return Err(Error::message("trailing data after the first data item"));
+    }
+    Ok(value)
+}
+
+/// Compares a CBOR body against the base64 encoded CBOR body of a protocol test.
+pub(crate) fn try_cbor_eq<T: AsRef<[u8]>>(
+    actual_body: T,
+    expected_body: &str,
+) -> Result<(), ProtocolTestFailure> {
+    let expected_bytes = aws_smithy_types::base64::decode(expected_body)
+        .expect("smithy protocol test `body` property is not properly base64 encoded");
+    // Protocol tests without a body expect no body at all.
+    if expected_bytes.is_empty() {
+        return match actual_body.as_ref() {
+            [] => Ok(()),
+            actual => Err(ProtocolTestFailure::BodyDidNotMatch {
+                comparison: crate::pretty_comparison("", &aws_smithy_types::base64::encode(actual)),
+                hint: "expected an empty body".to_owned(),
+            }),
+        };
+    }
+    let expected = decode(&expected_bytes).expect("expected value must be valid CBOR");
+    let actual =
+        decode(actual_body.as_ref()).map_err(|err| ProtocolTestFailure::InvalidBodyFormat {
+            expected: "cbor".to_owned(),
+            found: format!(
+                "{err}: {}",
+                aws_smithy_types::base64::encode(actual_body.as_ref())
+            ),
+        })?;
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(ProtocolTestFailure::BodyDidNotMatch {
+            comparison: crate::pretty_comparison(
+                &format!("{expected:#?}"),
+                &format!("{actual:#?}"),
+            ),
+            hint: "CBOR data items did not match".to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::try_cbor_eq;
+
+    #[test]
+    fn ignores_encoding_differences() {
+        // {"a": 1.5, "b": [1]} with a double and a definite length map.
+        let expected = aws_smithy_types::base64::encode([
+            0xa2, 0x61, b'a', 0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0, 0x61, b'b', 0x81, 0x01,
+        ]);
+        // {_ "b": [_ 1], "a": 1.5} with a half float and indefinite lengths.
+        let actual = [
+            0xbf, 0x61, b'b', 0x9f, 0x01, 0xff, 0x61, b'a', 0xf9, 0x3e, 0x00, 0xff,
+        ];
+        try_cbor_eq(actual, &expected).expect("bodies are equivalent");
+
+        // {"a": 2}
+        let different = [0xa1, 0x61, b'a', 0x02];
+        try_cbor_eq(
+            different,
+            &aws_smithy_types::base64::encode([0xa1, 0x61, b'a', 0x01]),
+        )
+        .expect_err("bodies are different");
+        try_cbor_eq([0xff], &expected).expect_err("body is not valid CBOR");
+    }
+}
@@ -13,9 +13,11 @@
     rust_2018_idioms
 )]
 
+mod cbor;
 mod urlencoded;
 mod xml;
 
+use crate::cbor::try_cbor_eq;
 use crate::sealed::GetNormalizedHeader;
 use crate::xml::try_xml_equivalent;
 use assert_json_diff::assert_json_eq_no_panic;
@@ -310,6 +312,8 @@ pub enum MediaType {
     Json,
     /// XML media types are normalized and compared
     Xml,
+    /// CBOR media types are decoded from base64 to binary and compared
+    Cbor,
     /// For x-www-form-urlencoded, do some map order comparison shenanigans
     UrlEncodedForm,
     /// Other media types are compared literally
@@ -322,6 +326,7 @@ impl<T: AsRef<str>> From<T> for MediaType {
             "application/json" => MediaType::Json,
             "application/x-amz-json-1.1" => MediaType::Json,
             "application/xml" => MediaType::Xml,
+            "application/cbor" => MediaType::Cbor,
             "application/x-www-form-urlencoded" => MediaType::UrlEncodedForm,
             other => MediaType::Other(other.to_string()),
         }
@@ -335,6 +340,7 @@ pub fn validate_body<T: AsRef<[u8]>>(
 ) -> Result<(), ProtocolTestFailure> {
     let body_str = std::str::from_utf8(actual_body.as_ref());
     match (media_type, body_str) {
+        (MediaType::Cbor, _) => try_cbor_eq(actual_body, expected_body),
         (MediaType::Json, Ok(actual_body)) => try_json_eq(expected_body, actual_body),
         (MediaType::Xml, Ok(actual_body)) => try_xml_equivalent(expected_body, actual_body),
         (MediaType::Json, Err(_)) => Err(ProtocolTestFailure::InvalidBodyFormat {
@@ -99,6 +99,8 @@ pub mod dns;
 
 pub mod endpoint;
 
+pub mod hedging;
+
 pub mod http;
 
 /// Smithy identity used by auth and signing.
@@ -122,5 +124,7 @@ pub mod ser_de;
 
 pub mod stalled_stream_protection;
 
+pub mod telemetry;
+
 /// Smithy support-code for code generated waiters.
 pub mod waiters;
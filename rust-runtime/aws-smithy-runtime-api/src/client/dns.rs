@@ -11,6 +11,7 @@ use std::error::Error as StdError;
 use std::fmt;
 use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Error that occurs when failing to perform a DNS lookup.
 #[derive(Debug)]
@@ -44,10 +45,71 @@ new_type_future! {
     pub struct DnsFuture<'a, Vec<IpAddr>, ResolveDnsError>;
 }
 
+/// The addresses a domain name resolved to, along with how long they may be cached for.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DnsRecords {
+    addresses: Vec<IpAddr>,
+    ttl: Option<Duration>,
+}
+
+impl DnsRecords {
+    /// Creates records for the given addresses, without a TTL.
+    pub fn new(addresses: Vec<IpAddr>) -> Self {
+        Self {
+            addresses,
+            ttl: None,
+        }
+    }
+
+    /// Sets how long these records may be cached for.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Returns the resolved addresses.
+    pub fn addresses(&self) -> &[IpAddr] {
+        &self.addresses
+    }
+
+    /// Returns how long these records may be cached for, if the resolver knows.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Consumes these records, returning the resolved addresses.
+    pub fn into_addresses(self) -> Vec<IpAddr> {
+        self.addresses
+    }
+}
+
+new_type_future! {
+    #[doc = "New-type for the future returned by [`ResolveDns::resolve_dns_records`]."]
+    pub struct DnsRecordsFuture<'a, DnsRecords, ResolveDnsError>;
+}
+
 /// Trait for resolving domain names
 pub trait ResolveDns: fmt::Debug + Send + Sync {
     /// Asynchronously resolve the given domain name
     fn resolve_dns<'a>(&'a self, name: &'a str) -> DnsFuture<'a>;
+
+    /// Asynchronously resolve the given domain name, along with how long the result may be cached.
+    ///
+    /// Resolvers that know the TTL of their records should override this. The default
+    /// implementation calls [`resolve_dns`](ResolveDns::resolve_dns) and reports no TTL.
+    fn resolve_dns_records<'a>(&'a self, name: &'a str) -> DnsRecordsFuture<'a> {
+        let addresses = self.resolve_dns(name);
+        DnsRecordsFuture::new(async move { addresses.await.map(DnsRecords::new) })
+    }
+
+    /// Reports that a connection to `address`, which this resolver returned, failed.
+    ///
+    /// Connectors call this when a connection is poisoned, for example by the
+    /// `ConnectionPoisoningInterceptor` after a transient error. Resolvers that cache results can
+    /// use it to stop returning unhealthy addresses. The default implementation does nothing.
+    fn report_connection_failure(&self, address: IpAddr) {
+        let _ = address;
+    }
 }
 
 /// Shared instance of [`ResolveDns`].
@@ -65,6 +127,14 @@ impl ResolveDns for SharedDnsResolver {
     fn resolve_dns<'a>(&'a self, name: &'a str) -> DnsFuture<'a> {
         self.0.resolve_dns(name)
     }
+
+    fn resolve_dns_records<'a>(&'a self, name: &'a str) -> DnsRecordsFuture<'a> {
+        self.0.resolve_dns_records(name)
+    }
+
+    fn report_connection_failure(&self, address: IpAddr) {
+        self.0.report_connection_failure(address)
+    }
 }
 
 impl_shared_conversions!(convert SharedDnsResolver from ResolveDns using SharedDnsResolver::new);
@@ -77,5 +147,6 @@ mod tests {
     fn check_send() {
         fn is_send<T: Send>() {}
         is_send::<DnsFuture<'_>>();
+        is_send::<DnsRecordsFuture<'_>>();
     }
 }
@@ -0,0 +1,190 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Hedged requests.
+//!
+//! When hedging is enabled, the orchestrator sends a second, speculative attempt for idempotent and
+//! read-only operations if the first attempt hasn't completed within a delay derived from recently
+//! observed latency. The first successful response is used, and the other attempt is cancelled.
+//!
+//! Hedging is unstable: this config is ignored unless `aws-smithy-runtime` is built with its
+//! `unstable-hedging` feature and with `--cfg aws_sdk_unstable`.
+
+use aws_smithy_types::config_bag::{Storable, StoreReplace};
+use std::time::Duration;
+
+/// The default latency percentile after which a hedged attempt is sent.
+pub const DEFAULT_PERCENTILE: f64 = 0.95;
+
+/// The default delay used before enough latency has been observed to calculate a percentile.
+pub const DEFAULT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// The default minimum delay before a hedged attempt is sent.
+pub const DEFAULT_MIN_DELAY: Duration = Duration::from_millis(10);
+
+/// Configuration for hedged requests.
+///
+/// Hedging only applies to operations that are marked with an [`OperationIdempotency`], and only
+/// when the request body can be cloned. Hedged attempts draw from the same token bucket as retries,
+/// so an outage can't be amplified by hedging.
+#[derive(Clone, Debug)]
+pub struct HedgingConfig {
+    enabled: bool,
+    percentile: f64,
+    initial_delay: Duration,
+    min_delay: Duration,
+}
+
+impl HedgingConfig {
+    /// Create a new config that enables hedging.
+    pub fn enabled() -> Builder {
+        Builder {
+            percentile: None,
+            initial_delay: None,
+            min_delay: None,
+        }
+    }
+
+    /// Create a new config that disables hedging.
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            percentile: DEFAULT_PERCENTILE,
+            initial_delay: DEFAULT_INITIAL_DELAY,
+            min_delay: DEFAULT_MIN_DELAY,
+        }
+    }
+
+    /// Return whether hedging is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Return the latency percentile after which a hedged attempt is sent.
+    pub fn percentile(&self) -> f64 {
+        self.percentile
+    }
+
+    /// Return the delay used before enough latency has been observed to calculate a percentile.
+    pub fn initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    /// Return the minimum delay before a hedged attempt is sent.
+    pub fn min_delay(&self) -> Duration {
+        self.min_delay
+    }
+}
+
+impl Storable for HedgingConfig {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Builder for [`HedgingConfig`].
+#[derive(Clone, Debug)]
+pub struct Builder {
+    percentile: Option<f64>,
+    initial_delay: Option<Duration>,
+    min_delay: Option<Duration>,
+}
+
+impl Builder {
+    /// Set the latency percentile after which a hedged attempt is sent.
+    ///
+    /// This must be between 0 and 1. For example, a value of `0.95` sends a hedged attempt when
+    /// the first attempt is slower than 95% of recent attempts.
+    pub fn percentile(mut self, percentile: f64) -> Self {
+        self.set_percentile(Some(percentile));
+        self
+    }
+
+    /// Set the latency percentile after which a hedged attempt is sent.
+    ///
+    /// This must be between 0 and 1. For example, a value of `0.95` sends a hedged attempt when
+    /// the first attempt is slower than 95% of recent attempts.
+    pub fn set_percentile(&mut self, percentile: Option<f64>) -> &mut Self {
+        self.percentile = percentile;
+        self
+    }
+
+    /// Set the delay used before enough latency has been observed to calculate a percentile.
+    pub fn initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.set_initial_delay(Some(initial_delay));
+        self
+    }
+
+    /// Set the delay used before enough latency has been observed to calculate a percentile.
+    pub fn set_initial_delay(&mut self, initial_delay: Option<Duration>) -> &mut Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Set the minimum delay before a hedged attempt is sent.
+    pub fn min_delay(mut self, min_delay: Duration) -> Self {
+        self.set_min_delay(Some(min_delay));
+        self
+    }
+
+    /// Set the minimum delay before a hedged attempt is sent.
+    pub fn set_min_delay(&mut self, min_delay: Option<Duration>) -> &mut Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    /// Build the config.
+    pub fn build(self) -> HedgingConfig {
+        HedgingConfig {
+            enabled: true,
+            percentile: self
+                .percentile
+                .unwrap_or(DEFAULT_PERCENTILE)
+                .clamp(0.0, 1.0),
+            initial_delay: self.initial_delay.unwrap_or(DEFAULT_INITIAL_DELAY),
+            min_delay: self.min_delay.unwrap_or(DEFAULT_MIN_DELAY),
+        }
+    }
+}
+
+/// Marks an operation that can safely be sent to the service more than once.
+///
+/// Code generated clients store this in the config bag of operations with the Smithy `@readonly`
+/// or `@idempotent` traits.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperationIdempotency {
+    /// The operation doesn't modify any state.
+    ReadOnly,
+    /// Sending the operation more than once has the same effect as sending it once.
+    Idempotent,
+}
+
+impl Storable for OperationIdempotency {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Marker stored in the config bag of a hedged attempt.
+///
+/// Interceptors can load this from the config bag to tell hedged attempts apart from the attempts
+/// made by the retry loop.
+#[derive(Clone, Debug)]
+pub struct HedgedAttempt {
+    attempt: u32,
+}
+
+impl HedgedAttempt {
+    /// Creates a new `HedgedAttempt` for the given attempt.
+    pub fn new(attempt: u32) -> Self {
+        Self { attempt }
+    }
+
+    /// Returns the number of the attempt that this hedged attempt is racing against.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+}
+
+impl Storable for HedgedAttempt {
+    type Storer = StoreReplace<Self>;
+}
@@ -42,6 +42,7 @@
 //! - HTTP protocol versions
 //! - TLS settings
 //! - Timeouts
+//! - Connection pool settings
 //!
 //! Some of these aren't implemented yet, but they will appear in the [`HttpConnectorSettings`] struct
 //! once they are.
@@ -56,7 +57,8 @@ use crate::client::result::ConnectorError;
 use crate::client::runtime_components::sealed::ValidateConfig;
 use crate::client::runtime_components::{RuntimeComponents, RuntimeComponentsBuilder};
 use crate::impl_shared_conversions;
-use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::sync::Arc;
 use std::time::Duration;
@@ -165,6 +167,13 @@ pub trait HttpClient: Send + Sync + fmt::Debug {
         let _ = (runtime_components, cfg);
         Ok(())
     }
+
+    /// Returns a snapshot of the connections in this client's connection pool.
+    ///
+    /// Returns `None` if the client doesn't support pool introspection.
+    fn pool_snapshot(&self) -> Option<PoolSnapshot> {
+        None
+    }
 }
 
 /// Shared HTTP client for use across multiple clients and requests.
@@ -190,6 +199,10 @@ impl HttpClient for SharedHttpClient {
     ) -> SharedHttpConnector {
         self.selector.http_connector(settings, components)
     }
+
+    fn pool_snapshot(&self) -> Option<PoolSnapshot> {
+        self.selector.pool_snapshot()
+    }
 }
 
 impl ValidateConfig for SharedHttpClient {
@@ -219,6 +232,10 @@ impl_shared_conversions!(convert SharedHttpClient from HttpClient using SharedHt
 pub struct HttpConnectorSettingsBuilder {
     connect_timeout: Option<Duration>,
     read_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+    http2_max_concurrent_streams: Option<u32>,
 }
 
 impl HttpConnectorSettingsBuilder {
@@ -261,21 +278,88 @@ impl HttpConnectorSettingsBuilder {
         self
     }
 
+    /// Sets the maximum number of idle connections kept in the pool for each host.
+    pub fn pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max_idle);
+        self
+    }
+
+    /// Sets the maximum number of idle connections kept in the pool for each host.
+    pub fn set_pool_max_idle_per_host(&mut self, max_idle: Option<usize>) -> &mut Self {
+        self.pool_max_idle_per_host = max_idle;
+        self
+    }
+
+    /// Sets how long an idle connection is kept in the pool before it's closed.
+    pub fn pool_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Sets how long an idle connection is kept in the pool before it's closed.
+    pub fn set_pool_idle_timeout(&mut self, idle_timeout: Option<Duration>) -> &mut Self {
+        self.pool_idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Sets the interval at which HTTP/2 PING frames are sent to keep connections alive.
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Sets the interval at which HTTP/2 PING frames are sent to keep connections alive.
+    pub fn set_http2_keep_alive_interval(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.http2_keep_alive_interval = interval;
+        self
+    }
+
+    /// Sets the maximum number of concurrent streams opened on a HTTP/2 connection.
+    ///
+    /// The server may lower this limit with its `SETTINGS_MAX_CONCURRENT_STREAMS` setting.
+    pub fn http2_max_concurrent_streams(mut self, max_streams: u32) -> Self {
+        self.http2_max_concurrent_streams = Some(max_streams);
+        self
+    }
+
+    /// Sets the maximum number of concurrent streams opened on a HTTP/2 connection.
+    ///
+    /// The server may lower this limit with its `SETTINGS_MAX_CONCURRENT_STREAMS` setting.
+    pub fn set_http2_max_concurrent_streams(&mut self, max_streams: Option<u32>) -> &mut Self {
+        self.http2_max_concurrent_streams = max_streams;
+        self
+    }
+
     /// Builds the [`HttpConnectorSettings`].
     pub fn build(self) -> HttpConnectorSettings {
         HttpConnectorSettings {
             connect_timeout: self.connect_timeout,
             read_timeout: self.read_timeout,
+            pool_max_idle_per_host: self.pool_max_idle_per_host,
+            pool_idle_timeout: self.pool_idle_timeout,
+            http2_keep_alive_interval: self.http2_keep_alive_interval,
+            http2_max_concurrent_streams: self.http2_max_concurrent_streams,
         }
     }
 }
 
 /// Settings for HTTP Connectors
+///
+/// The connect and read timeouts are taken from the `TimeoutConfig` of a request. The connection
+/// pool settings are taken from `HttpConnectorSettings` stored in the config bag, if any.
 #[non_exhaustive]
 #[derive(Clone, Default, Debug)]
 pub struct HttpConnectorSettings {
     connect_timeout: Option<Duration>,
     read_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+    http2_max_concurrent_streams: Option<u32>,
+}
+
+impl Storable for HttpConnectorSettings {
+    type Storer = StoreReplace<Self>;
 }
 
 impl HttpConnectorSettings {
@@ -284,6 +368,18 @@ impl HttpConnectorSettings {
         Default::default()
     }
 
+    /// Converts these settings back into a builder.
+    pub fn to_builder(&self) -> HttpConnectorSettingsBuilder {
+        HttpConnectorSettingsBuilder {
+            connect_timeout: self.connect_timeout,
+            read_timeout: self.read_timeout,
+            pool_max_idle_per_host: self.pool_max_idle_per_host,
+            pool_idle_timeout: self.pool_idle_timeout,
+            http2_keep_alive_interval: self.http2_keep_alive_interval,
+            http2_max_concurrent_streams: self.http2_max_concurrent_streams,
+        }
+    }
+
     /// Returns the connect timeout that should be used.
     ///
     /// The connect timeout is a limit on the amount of time it takes to initiate a socket connection.
@@ -298,4 +394,100 @@ impl HttpConnectorSettings {
     pub fn read_timeout(&self) -> Option<Duration> {
         self.read_timeout
     }
+
+    /// Returns the maximum number of idle connections kept in the pool for each host.
+    pub fn pool_max_idle_per_host(&self) -> Option<usize> {
+        self.pool_max_idle_per_host
+    }
+
+    /// Returns how long an idle connection is kept in the pool before it's closed.
+    pub fn pool_idle_timeout(&self) -> Option<Duration> {
+        self.pool_idle_timeout
+    }
+
+    /// Returns the interval at which HTTP/2 PING frames are sent to keep connections alive.
+    pub fn http2_keep_alive_interval(&self) -> Option<Duration> {
+        self.http2_keep_alive_interval
+    }
+
+    /// Returns the maximum number of concurrent streams opened on a HTTP/2 connection.
+    pub fn http2_max_concurrent_streams(&self) -> Option<u32> {
+        self.http2_max_concurrent_streams
+    }
+}
+
+/// Connection counts for a single authority (`host:port`) in a [`PoolSnapshot`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AuthorityPoolStats {
+    idle: usize,
+    active: usize,
+}
+
+impl AuthorityPoolStats {
+    /// Creates new stats with the given number of idle and active connections.
+    pub fn new(idle: usize, active: usize) -> Self {
+        Self { idle, active }
+    }
+
+    /// Returns the number of open connections that aren't serving a request.
+    pub fn idle(&self) -> usize {
+        self.idle
+    }
+
+    /// Returns the number of open connections that are serving at least one request.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+}
+
+/// A point-in-time view of the connections in a [`HttpClient`]'s connection pool.
+///
+/// See [`HttpClient::pool_snapshot`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolSnapshot {
+    authorities: BTreeMap<String, AuthorityPoolStats>,
+}
+
+impl PoolSnapshot {
+    /// Creates an empty snapshot.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `stats` to the connection counts of `authority`.
+    pub fn record(&mut self, authority: impl Into<String>, stats: AuthorityPoolStats) -> &mut Self {
+        let entry = self.authorities.entry(authority.into()).or_default();
+        entry.idle += stats.idle;
+        entry.active += stats.active;
+        self
+    }
+
+    /// Returns the connection counts of `authority`, if it has any connections.
+    pub fn get(&self, authority: &str) -> Option<AuthorityPoolStats> {
+        self.authorities.get(authority).copied()
+    }
+
+    /// Returns the connection counts of every authority, ordered by authority.
+    pub fn authorities(&self) -> impl Iterator<Item = (&str, AuthorityPoolStats)> {
+        self.authorities
+            .iter()
+            .map(|(authority, stats)| (authority.as_str(), *stats))
+    }
+
+    /// Returns the total number of idle connections.
+    pub fn idle(&self) -> usize {
+        self.authorities
+            .values()
+            .map(AuthorityPoolStats::idle)
+            .sum()
+    }
+
+    /// Returns the total number of active connections.
+    pub fn active(&self) -> usize {
+        self.authorities
+            .values()
+            .map(AuthorityPoolStats::active)
+            .sum()
+    }
 }
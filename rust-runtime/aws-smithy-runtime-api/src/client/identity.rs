@@ -18,6 +18,8 @@ use std::time::SystemTime;
 #[cfg(feature = "http-auth")]
 pub mod http;
 
+pub mod store;
+
 new_type_future! {
     #[doc = "Future for [`IdentityResolver::resolve_identity`]."]
     pub struct IdentityFuture<'a, Identity, BoxError>;
@@ -175,6 +177,18 @@ pub trait ResolveIdentity: Send + Sync + Debug {
     fn cache_partition(&self) -> Option<IdentityCachePartition> {
         None
     }
+
+    /// Returns a key that identifies this identity resolver across processes.
+    ///
+    /// Identity caches that persist identities with a [`StoreIdentity`](store::StoreIdentity)
+    /// store them under this key. Resolvers that return the same key must resolve the same
+    /// identity, so the key should include everything that the identity depends on, such as a
+    /// role ARN or an SSO start URL.
+    ///
+    /// By default this returns `None`, and identities of this resolver aren't persisted.
+    fn persistent_cache_key(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Cache location for identity caching.
@@ -240,6 +254,10 @@ impl ResolveIdentity for SharedIdentityResolver {
     fn cache_partition(&self) -> Option<IdentityCachePartition> {
         Some(self.cache_partition())
     }
+
+    fn persistent_cache_key(&self) -> Option<String> {
+        self.inner.persistent_cache_key()
+    }
 }
 
 impl_shared_conversions!(convert SharedIdentityResolver from ResolveIdentity using SharedIdentityResolver::new);
@@ -5,6 +5,8 @@
 
 //! Identity types for HTTP auth
 
+use crate::box_error::BoxError;
+use crate::client::identity::store::IdentityCodec;
 use crate::client::identity::{Identity, IdentityFuture, ResolveIdentity};
 use crate::client::runtime_components::RuntimeComponents;
 use aws_smithy_types::config_bag::ConfigBag;
@@ -97,6 +99,31 @@ impl From<Token> for Identity {
     }
 }
 
+/// [`IdentityCodec`] for [`Token`] identities, for persisting them in an identity store.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct TokenCodec;
+
+impl TokenCodec {
+    /// Creates a new `TokenCodec`.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl IdentityCodec for TokenCodec {
+    fn encode(&self, identity: &Identity) -> Option<Vec<u8>> {
+        identity
+            .data::<Token>()
+            .map(|token| token.token().as_bytes().to_vec())
+    }
+
+    fn decode(&self, data: &[u8], expiration: SystemTime) -> Result<Identity, BoxError> {
+        let token = std::str::from_utf8(data)?;
+        Ok(Token::new(token, Some(expiration)).into())
+    }
+}
+
 /// Identity type required to sign requests using Smithy's login-based HTTP auth schemes
 ///
 /// This `Login` type is used with Smithy's `@httpBasicAuth` and `@httpDigestAuth`
@@ -0,0 +1,219 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Persistent storage for cached identities.
+//!
+//! Identity caches keep identities in memory, so every new process has to resolve them again.
+//! An identity store lets a cache persist identities across processes, which saves short-lived
+//! programs such as CLI tools from repeating expensive identity resolution on every run.
+//!
+//! Stores deal in bytes, so an [`IdentityCodec`] converts identities to and from their stored form.
+//! Only identities of resolvers that return a
+//! [`persistent_cache_key`](crate::client::identity::ResolveIdentity::persistent_cache_key) are
+//! persisted.
+
+use crate::box_error::BoxError;
+use crate::client::identity::Identity;
+use crate::impl_shared_conversions;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+new_type_future! {
+    #[doc = "Future for [`StoreIdentity::load`]."]
+    pub struct LoadIdentityFuture<'a, Option<StoredIdentity>, BoxError>;
+}
+
+new_type_future! {
+    #[doc = "Future for [`StoreIdentity::save`]."]
+    pub struct SaveIdentityFuture<'a, (), BoxError>;
+}
+
+new_type_future! {
+    #[doc = "Future for [`StoreIdentity::lock`]."]
+    pub struct LockIdentityFuture<'a, IdentityStoreLock, BoxError>;
+}
+
+/// Key of an identity in an identity store.
+///
+/// The key is made of the stable key of the identity resolver, and a partition that separates the
+/// identities of otherwise identical resolvers, such as resolvers for different profiles.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct IdentityStoreKey {
+    resolver: String,
+    partition: String,
+}
+
+impl IdentityStoreKey {
+    /// Creates a new key.
+    pub fn new(resolver: impl Into<String>, partition: impl Into<String>) -> Self {
+        Self {
+            resolver: resolver.into(),
+            partition: partition.into(),
+        }
+    }
+
+    /// Returns the key of the identity resolver.
+    pub fn resolver(&self) -> &str {
+        &self.resolver
+    }
+
+    /// Returns the partition.
+    pub fn partition(&self) -> &str {
+        &self.partition
+    }
+}
+
+/// An identity in its stored form.
+#[derive(Clone, Eq, PartialEq)]
+pub struct StoredIdentity {
+    data: Vec<u8>,
+    expiration: SystemTime,
+}
+
+impl StoredIdentity {
+    /// Creates a stored identity from encoded identity data and the time the identity expires.
+    pub fn new(data: Vec<u8>, expiration: SystemTime) -> Self {
+        Self { data, expiration }
+    }
+
+    /// Returns the encoded identity data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the time the identity expires.
+    pub fn expiration(&self) -> SystemTime {
+        self.expiration
+    }
+}
+
+impl fmt::Debug for StoredIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoredIdentity")
+            .field("data", &"** redacted **")
+            .field("expiration", &self.expiration)
+            .finish()
+    }
+}
+
+/// Exclusive access to an identity in an identity store, released when dropped.
+pub struct IdentityStoreLock {
+    _guard: Option<Box<dyn Send + Sync>>,
+}
+
+impl IdentityStoreLock {
+    /// Creates a lock that is released when `guard` is dropped.
+    pub fn new(guard: impl Send + Sync + 'static) -> Self {
+        Self {
+            _guard: Some(Box::new(guard)),
+        }
+    }
+
+    /// Creates a lock for stores that don't support locking.
+    pub fn none() -> Self {
+        Self { _guard: None }
+    }
+}
+
+impl fmt::Debug for IdentityStoreLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdentityStoreLock").finish_non_exhaustive()
+    }
+}
+
+/// Persistent storage for identities.
+///
+/// Identity caches [`lock`](StoreIdentity::lock) an identity before loading it, and hold the lock
+/// while resolving and saving a new identity when the stored one has expired. Stores that are
+/// shared between processes should lock across processes, so that only one process at a time
+/// resolves an identity.
+pub trait StoreIdentity: fmt::Debug + Send + Sync {
+    /// Loads the identity stored under `key`, if there is one.
+    ///
+    /// Expired identities may be returned; the identity cache decides whether they are usable.
+    fn load<'a>(&'a self, key: &'a IdentityStoreKey) -> LoadIdentityFuture<'a>;
+
+    /// Saves `identity` under `key`, replacing any identity stored there.
+    fn save<'a>(
+        &'a self,
+        key: &'a IdentityStoreKey,
+        identity: StoredIdentity,
+    ) -> SaveIdentityFuture<'a>;
+
+    /// Waits for exclusive access to the identity stored under `key`.
+    ///
+    /// By default, stores don't lock.
+    fn lock<'a>(&'a self, key: &'a IdentityStoreKey) -> LockIdentityFuture<'a> {
+        let _ = key;
+        LockIdentityFuture::ready(Ok(IdentityStoreLock::none()))
+    }
+}
+
+/// Shared identity store.
+#[derive(Clone, Debug)]
+pub struct SharedIdentityStore(Arc<dyn StoreIdentity>);
+
+impl SharedIdentityStore {
+    /// Create a new `SharedIdentityStore`.
+    pub fn new(store: impl StoreIdentity + 'static) -> Self {
+        Self(Arc::new(store))
+    }
+}
+
+impl StoreIdentity for SharedIdentityStore {
+    fn load<'a>(&'a self, key: &'a IdentityStoreKey) -> LoadIdentityFuture<'a> {
+        self.0.load(key)
+    }
+
+    fn save<'a>(
+        &'a self,
+        key: &'a IdentityStoreKey,
+        identity: StoredIdentity,
+    ) -> SaveIdentityFuture<'a> {
+        self.0.save(key, identity)
+    }
+
+    fn lock<'a>(&'a self, key: &'a IdentityStoreKey) -> LockIdentityFuture<'a> {
+        self.0.lock(key)
+    }
+}
+
+impl_shared_conversions!(convert SharedIdentityStore from StoreIdentity using SharedIdentityStore::new);
+
+/// Converts identities to and from the bytes kept in an identity store.
+pub trait IdentityCodec: fmt::Debug + Send + Sync {
+    /// Encodes the data of `identity`.
+    ///
+    /// Returns `None` for identities that this codec doesn't support, which aren't persisted.
+    fn encode(&self, identity: &Identity) -> Option<Vec<u8>>;
+
+    /// Decodes an identity that expires at `expiration` from data returned by
+    /// [`encode`](IdentityCodec::encode).
+    fn decode(&self, data: &[u8], expiration: SystemTime) -> Result<Identity, BoxError>;
+}
+
+/// Shared identity codec.
+#[derive(Clone, Debug)]
+pub struct SharedIdentityCodec(Arc<dyn IdentityCodec>);
+
+impl SharedIdentityCodec {
+    /// Create a new `SharedIdentityCodec`.
+    pub fn new(codec: impl IdentityCodec + 'static) -> Self {
+        Self(Arc::new(codec))
+    }
+}
+
+impl IdentityCodec for SharedIdentityCodec {
+    fn encode(&self, identity: &Identity) -> Option<Vec<u8>> {
+        self.0.encode(identity)
+    }
+
+    fn decode(&self, data: &[u8], expiration: SystemTime) -> Result<Identity, BoxError> {
+        self.0.decode(data, expiration)
+    }
+}
+
+impl_shared_conversions!(convert SharedIdentityCodec from IdentityCodec using SharedIdentityCodec::new);
@@ -84,6 +84,20 @@ new_type_box!(
     std::error::Error,
 );
 
+impl Output {
+    /// Creates a new `Output` that can be cloned with [`Output::try_clone`].
+    pub fn erase_with_clone<T: Clone + Send + Sync + fmt::Debug + 'static>(output: T) -> Self {
+        Self(TypeErasedBox::new_with_clone(output))
+    }
+
+    /// Attempts to clone this output.
+    ///
+    /// This only succeeds if the output was created with [`Output::erase_with_clone`].
+    pub fn try_clone(&self) -> Option<Self> {
+        self.0.try_clone().map(Self)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(&self.0, f)
@@ -361,6 +375,25 @@ impl<I, O, E> InterceptorContext<I, O, E> {
         }
     }
 
+    /// Creates a new context from the saved request checkpoint, for making an attempt concurrently
+    /// with the one using this context. Returns `None` if no checkpoint was saved.
+    ///
+    /// Note: This method is intended for internal use only.
+    #[cfg(all(aws_sdk_unstable, feature = "unstable-hedging"))]
+    #[doc(hidden)]
+    pub fn fork_from_checkpoint(&self) -> Option<Self> {
+        let request = self.request_checkpoint.as_ref()?.try_clone()?;
+        Some(Self {
+            input: None,
+            output_or_error: None,
+            request: Some(request),
+            response: None,
+            phase: Phase::BeforeTransmit,
+            tainted: true,
+            request_checkpoint: self.request_checkpoint.as_ref().and_then(|r| r.try_clone()),
+        })
+    }
+
     /// Returns false if rewinding isn't possible
     ///
     /// Note: This method is intended for internal use only.
@@ -610,6 +643,56 @@ mod tests {
         assert_eq!("output", output.downcast_ref::<String>().unwrap());
     }
 
+    #[cfg(all(aws_sdk_unstable, feature = "unstable-hedging"))]
+    #[test]
+    fn fork_from_checkpoint() {
+        let mut cfg = ConfigBag::base();
+        let mut context = InterceptorContext::new(Input::doesnt_matter());
+        context.enter_serialization_phase();
+        let _ = context.take_input();
+        context.set_request(
+            http::Request::builder()
+                .header("test", "the-original-un-mutated-request")
+                .body(SdkBody::empty())
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        );
+        context.enter_before_transmit_phase();
+        context.save_checkpoint();
+        assert_eq!(context.rewind(&mut cfg), RewindResult::Unnecessary);
+        context.request_mut().unwrap().headers_mut().insert(
+            "test",
+            HeaderValue::from_static("request-modified-after-signing"),
+        );
+
+        let mut fork = context
+            .fork_from_checkpoint()
+            .expect("checkpoint was saved");
+        assert_eq!(
+            "the-original-un-mutated-request",
+            fork.request().unwrap().headers().get("test").unwrap()
+        );
+        // The fork can make an attempt, and be rewound for retries, independently of the original
+        fork.enter_transmit_phase();
+        let _ = fork.take_request();
+        fork.set_response(
+            http::Response::builder()
+                .body(SdkBody::empty())
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        );
+        fork.enter_before_deserialization_phase();
+        fork.enter_deserialization_phase();
+        fork.set_output_or_error(Err(OrchestratorError::operation(Error::doesnt_matter())));
+        assert_eq!(fork.rewind(&mut cfg), RewindResult::Occurred);
+        assert_eq!(
+            "request-modified-after-signing",
+            context.request().unwrap().headers().get("test").unwrap()
+        );
+    }
+
     #[test]
     fn try_clone_clones_all_data() {
         let request: HttpRequest = http::Request::builder()
@@ -225,6 +225,11 @@ impl DispatchFailure {
         self.source.is_other()
     }
 
+    /// Returns true if the request wasn't sent because a circuit breaker is open for its endpoint
+    pub fn is_circuit_open(&self) -> bool {
+        self.source.is_circuit_open()
+    }
+
     /// Returns the optional error kind associated with an unclassified error
     pub fn as_other(&self) -> Option<ErrorKind> {
         self.source.as_other()
@@ -544,6 +549,9 @@ enum ConnectorErrorKind {
     /// Socket/IO error
     Io,
 
+    /// The request wasn't sent because a circuit breaker is open for its endpoint
+    CircuitOpen,
+
     /// An unclassified Error with an explicit error kind
     Other(Option<ErrorKind>),
 }
@@ -582,6 +590,7 @@ impl Display for ConnectorError {
             ConnectorErrorKind::Timeout => write!(f, "timeout"),
             ConnectorErrorKind::User => write!(f, "user error"),
             ConnectorErrorKind::Io => write!(f, "io error"),
+            ConnectorErrorKind::CircuitOpen => write!(f, "circuit open"),
             ConnectorErrorKind::Other(_) => write!(f, "other"),
         }
     }
@@ -635,6 +644,18 @@ impl ConnectorError {
         }
     }
 
+    /// Construct a [`ConnectorError`] for a request that wasn't sent because a circuit breaker is
+    /// open for its endpoint
+    ///
+    /// Circuit open errors are not retried, since the request would fail again immediately.
+    pub fn circuit_open(source: BoxError) -> Self {
+        Self {
+            kind: ConnectorErrorKind::CircuitOpen,
+            source,
+            connection: ConnectionStatus::NeverConnected,
+        }
+    }
+
     /// Construct a [`ConnectorError`] from an different unclassified error.
     ///
     /// Optionally, an explicit `Kind` may be passed.
@@ -666,6 +687,11 @@ impl ConnectorError {
         matches!(self.kind, ConnectorErrorKind::Other(..))
     }
 
+    /// Returns true if the request wasn't sent because a circuit breaker is open for its endpoint
+    pub fn is_circuit_open(&self) -> bool {
+        matches!(self.kind, ConnectorErrorKind::CircuitOpen)
+    }
+
     /// Returns the optional error kind associated with an unclassified error
     pub fn as_other(&self) -> Option<ErrorKind> {
         match &self.kind {
@@ -1108,6 +1108,15 @@ impl GetIdentityResolver for RuntimeComponents {
     }
 }
 
+impl GetIdentityResolver for RuntimeComponentsBuilder {
+    fn identity_resolver(&self, scheme_id: AuthSchemeId) -> Option<SharedIdentityResolver> {
+        self.identity_resolvers
+            .as_ref()
+            .and_then(|resolvers| resolvers.get(&scheme_id))
+            .map(|s| s.value.clone())
+    }
+}
+
 #[cfg(all(test, feature = "test-util"))]
 mod tests {
     use super::{BuildError, RuntimeComponentsBuilder, Tracked};
@@ -0,0 +1,311 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Client telemetry.
+//!
+//! The orchestrator records metrics about each operation it runs with the [`TelemetryProvider`]
+//! in the config bag, if there is one. Metrics are identified by a [`Metric`], and the ones the
+//! orchestrator records are defined in the [`metrics`] module. A [`TelemetryProvider`] is free to
+//! send them wherever it likes; `aws-smithy-runtime` includes one backed by OpenTelemetry.
+
+use crate::impl_shared_conversions;
+use aws_smithy_types::config_bag::{Storable, StoreReplace};
+use std::fmt;
+use std::sync::Arc;
+
+/// The kind of instrument a metric is recorded with.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MetricKind {
+    /// A monotonically increasing count. Each recorded value is added to the total.
+    Counter,
+    /// A distribution of values, such as latencies.
+    Histogram,
+    /// The current value of something that goes up and down, such as the available capacity of a
+    /// token bucket.
+    Gauge,
+}
+
+/// Describes a metric recorded by the orchestrator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Metric {
+    name: &'static str,
+    description: &'static str,
+    unit: &'static str,
+    kind: MetricKind,
+}
+
+impl Metric {
+    /// Creates a new metric.
+    pub const fn new(
+        name: &'static str,
+        description: &'static str,
+        unit: &'static str,
+        kind: MetricKind,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            unit,
+            kind,
+        }
+    }
+
+    /// Returns the name of this metric.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the description of this metric.
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// Returns the unit of this metric, in [UCUM](https://ucum.org/) notation.
+    pub fn unit(&self) -> &'static str {
+        self.unit
+    }
+
+    /// Returns the kind of instrument this metric is recorded with.
+    pub fn kind(&self) -> MetricKind {
+        self.kind
+    }
+}
+
+/// The metrics recorded by the orchestrator.
+pub mod metrics {
+    use super::{Metric, MetricKind};
+
+    /// The duration of an operation, including all of its attempts.
+    pub const CALL_DURATION: Metric = Metric::new(
+        "smithy.client.call.duration",
+        "Overall call duration (including retries and time to send or receive request and response body)",
+        "s",
+        MetricKind::Histogram,
+    );
+
+    /// The number of attempts made for an operation.
+    pub const CALL_ATTEMPTS: Metric = Metric::new(
+        "smithy.client.call.attempts",
+        "The number of attempts for an individual operation",
+        "{attempt}",
+        MetricKind::Counter,
+    );
+
+    /// The number of operations that failed.
+    pub const CALL_ERRORS: Metric = Metric::new(
+        "smithy.client.call.errors",
+        "The number of errors for an operation",
+        "{error}",
+        MetricKind::Counter,
+    );
+
+    /// The duration of a single attempt.
+    pub const ATTEMPT_DURATION: Metric = Metric::new(
+        "smithy.client.call.attempt_duration",
+        "The time it takes to connect to the service, send the request, and get back HTTP status code and headers (including time queued waiting to be sent)",
+        "s",
+        MetricKind::Histogram,
+    );
+
+    /// The time it takes to serialize a request.
+    pub const SERIALIZATION_DURATION: Metric = Metric::new(
+        "smithy.client.call.serialization_duration",
+        "The time it takes to serialize a message body",
+        "s",
+        MetricKind::Histogram,
+    );
+
+    /// The time it takes to resolve an endpoint.
+    pub const RESOLVE_ENDPOINT_DURATION: Metric = Metric::new(
+        "smithy.client.call.resolve_endpoint_duration",
+        "The time it takes to resolve an endpoint (endpoint resolver, not DNS) for the request",
+        "s",
+        MetricKind::Histogram,
+    );
+
+    /// The time it takes to resolve an identity and sign a request.
+    pub const SIGNING_DURATION: Metric = Metric::new(
+        "smithy.client.call.auth.sign_duration",
+        "The time it takes to resolve an identity and sign a request",
+        "s",
+        MetricKind::Histogram,
+    );
+
+    /// The time it takes to send a request and receive the status code and headers of its response.
+    pub const TRANSMIT_DURATION: Metric = Metric::new(
+        "smithy.client.call.transmit_duration",
+        "The time it takes to send a request and receive the status code and headers of its response",
+        "s",
+        MetricKind::Histogram,
+    );
+
+    /// The time it takes to deserialize a response.
+    pub const DESERIALIZATION_DURATION: Metric = Metric::new(
+        "smithy.client.call.deserialization_duration",
+        "The time it takes to deserialize a message body",
+        "s",
+        MetricKind::Histogram,
+    );
+
+    /// The time it takes to run the interceptors of a hook.
+    pub const INTERCEPTOR_DURATION: Metric = Metric::new(
+        "smithy.client.call.interceptor_duration",
+        "The time it takes to run the interceptors registered for a hook",
+        "s",
+        MetricKind::Histogram,
+    );
+
+    /// The number of retries, by reason.
+    pub const RETRIES: Metric = Metric::new(
+        "smithy.client.call.retries",
+        "The number of retries for an operation, by the reason they were made",
+        "{retry}",
+        MetricKind::Counter,
+    );
+
+    /// The capacity available in the retry token bucket.
+    pub const TOKEN_BUCKET_AVAILABLE: Metric = Metric::new(
+        "smithy.client.retries.token_bucket.available",
+        "The number of permits available in the retry token bucket",
+        "{permit}",
+        MetricKind::Gauge,
+    );
+
+    /// The rate at which the client rate limiter allows requests.
+    pub const RATE_LIMITER_FILL_RATE: Metric = Metric::new(
+        "smithy.client.retries.rate_limiter.fill_rate",
+        "The rate at which the client rate limiter allows requests to be sent",
+        "{request}/s",
+        MetricKind::Gauge,
+    );
+}
+
+/// The names of the attributes recorded with metrics.
+pub mod attributes {
+    /// The name of the service an operation belongs to.
+    pub const RPC_SERVICE: &str = "rpc.service";
+    /// The name of an operation.
+    pub const RPC_METHOD: &str = "rpc.method";
+    /// The kind of error an operation failed with, or the reason a retry was made.
+    pub const ERROR_TYPE: &str = "error.type";
+    /// The name of an interceptor hook.
+    pub const HOOK: &str = "smithy.client.hook";
+}
+
+/// Key-value pairs that are recorded with a metric.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Attributes {
+    attributes: Vec<(&'static str, String)>,
+}
+
+impl Attributes {
+    /// Creates an empty set of attributes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an attribute, replacing any existing value.
+    pub fn set(&mut self, key: &'static str, value: impl Into<String>) -> &mut Self {
+        let value = value.into();
+        match self.attributes.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.attributes.push((key, value)),
+        }
+        self
+    }
+
+    /// Sets an attribute, replacing any existing value.
+    pub fn with(mut self, key: &'static str, value: impl Into<String>) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    /// Returns the value of an attribute, if it's set.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns an iterator over the attributes, in the order they were first set.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        self.attributes.iter().map(|(k, v)| (*k, v.as_str()))
+    }
+}
+
+/// Records the metrics of a client.
+///
+/// Implementations must be cheap to call, since metrics are recorded while operations run.
+pub trait TelemetryProvider: fmt::Debug + Send + Sync {
+    /// Records a value of a metric.
+    ///
+    /// For [`MetricKind::Counter`] metrics the value is an increment, for
+    /// [`MetricKind::Histogram`] metrics it is a sample, and for [`MetricKind::Gauge`] metrics it
+    /// is the current value.
+    fn record(&self, metric: &Metric, value: f64, attributes: &Attributes);
+}
+
+/// Shared instance of [`TelemetryProvider`].
+#[derive(Clone, Debug)]
+pub struct SharedTelemetryProvider(Arc<dyn TelemetryProvider>);
+
+impl SharedTelemetryProvider {
+    /// Creates a new `SharedTelemetryProvider`.
+    pub fn new(provider: impl TelemetryProvider + 'static) -> Self {
+        Self(Arc::new(provider))
+    }
+}
+
+impl TelemetryProvider for SharedTelemetryProvider {
+    fn record(&self, metric: &Metric, value: f64, attributes: &Attributes) {
+        self.0.record(metric, value, attributes)
+    }
+}
+
+impl Storable for SharedTelemetryProvider {
+    type Storer = StoreReplace<Self>;
+}
+
+impl_shared_conversions!(convert SharedTelemetryProvider from TelemetryProvider using SharedTelemetryProvider::new);
+
+/// A [`TelemetryProvider`] that discards everything it's given.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct NoopTelemetryProvider;
+
+impl NoopTelemetryProvider {
+    /// Creates a new `NoopTelemetryProvider`.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TelemetryProvider for NoopTelemetryProvider {
+    fn record(&self, _metric: &Metric, _value: f64, _attributes: &Attributes) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setting_an_attribute_replaces_it() {
+        let mut attrs = Attributes::new()
+            .with(attributes::RPC_SERVICE, "s3")
+            .with(attributes::RPC_METHOD, "GetObject");
+        attrs.set(attributes::RPC_SERVICE, "dynamodb");
+
+        assert_eq!(Some("dynamodb"), attrs.get(attributes::RPC_SERVICE));
+        assert_eq!(
+            vec![
+                (attributes::RPC_SERVICE, "dynamodb"),
+                (attributes::RPC_METHOD, "GetObject")
+            ],
+            attrs.iter().collect::<Vec<_>>()
+        );
+    }
+}
@@ -6,6 +6,8 @@
 /// Smithy auth scheme implementations.
 pub mod auth;
 
+pub mod circuit_breaker;
+
 pub mod defaults;
 
 pub mod dns;
@@ -24,12 +26,16 @@ pub mod config_override;
 /// The client orchestrator implementation
 pub mod orchestrator;
 
+pub mod response_cache;
+
 /// Smithy code related to retry handling and token buckets.
 ///
 /// This code defines when and how failed requests should be retried. It also defines the behavior
 /// used to limit the rate at which requests are sent.
 pub mod retries;
 
+pub mod single_flight;
+
 /// Utilities for testing orchestrators. An orchestrator missing required components will panic when
 /// run. This module contains stub components that can be used when you only care about testing some
 /// specific aspect of the orchestrator.
@@ -47,5 +53,7 @@ pub mod interceptors;
 /// Stalled stream protection for clients
 pub mod stalled_stream_protection;
 
+pub mod telemetry;
+
 /// Smithy support-code for code generated waiters.
 pub mod waiters;
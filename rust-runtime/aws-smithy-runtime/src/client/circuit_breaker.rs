@@ -0,0 +1,465 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A circuit breaker that stops sending requests to an endpoint after it has failed repeatedly.
+//!
+//! Failures are tracked separately for each endpoint URL resolved by the orchestrator. Once an
+//! endpoint has failed [`failure_threshold`](CircuitBreakerConfig::failure_threshold) times in a
+//! row, its circuit opens, and requests to it fail immediately with a
+//! [`ConnectorError`] for which [`is_circuit_open`](ConnectorError::is_circuit_open) returns
+//! true. After [`open_duration`](CircuitBreakerConfig::open_duration) has passed, the circuit
+//! half-opens and a single trial request is let through. If the trial succeeds the circuit closes;
+//! otherwise it opens again.
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::context::InterceptorContext;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugin;
+use aws_smithy_types::config_bag::{ConfigBag, FrozenLayer, Layer, Storable, StoreReplace};
+use aws_smithy_types::endpoint::Endpoint;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+const DEFAULT_OPEN_DURATION: Duration = Duration::from_secs(10);
+
+/// Configuration for a [`CircuitBreaker`].
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerConfig {
+    failure_threshold: u32,
+    open_duration: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            open_duration: DEFAULT_OPEN_DURATION,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Creates a new `CircuitBreakerConfig` with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of consecutive failures after which the circuit for an endpoint opens.
+    ///
+    /// A threshold of zero is treated as one.
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    /// Set how long the circuit for an endpoint stays open before a trial request is let through.
+    pub fn with_open_duration(mut self, open_duration: Duration) -> Self {
+        self.open_duration = open_duration;
+        self
+    }
+
+    /// Returns the number of consecutive failures after which the circuit for an endpoint opens.
+    pub fn failure_threshold(&self) -> u32 {
+        self.failure_threshold
+    }
+
+    /// Returns how long the circuit for an endpoint stays open before a trial request is let through.
+    pub fn open_duration(&self) -> Duration {
+        self.open_duration
+    }
+}
+
+/// The state of the circuit for an endpoint.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent to the endpoint.
+    Closed,
+    /// Requests to the endpoint fail immediately.
+    Open,
+    /// A single trial request is let through to check whether the endpoint has recovered.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+enum Circuit {
+    Closed { consecutive_failures: u32 },
+    Open { until: SystemTime },
+    HalfOpen { trial_started: SystemTime },
+}
+
+/// Circuit breaker that tracks failures for each endpoint URL.
+///
+/// Cloning a `CircuitBreaker` shares its state, so the same breaker can be given to several
+/// clients with [`CircuitBreakerRuntimePlugin`].
+#[derive(Clone, Debug, Default)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    circuits: Arc<Mutex<HashMap<String, Circuit>>>,
+}
+
+impl Storable for CircuitBreaker {
+    type Storer = StoreReplace<Self>;
+}
+
+impl CircuitBreaker {
+    /// Creates a new `CircuitBreaker` with the given config.
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            circuits: Default::default(),
+        }
+    }
+
+    /// Returns the state of the circuit for the given endpoint URL at the given time.
+    pub fn state(&self, endpoint: &str, now: SystemTime) -> CircuitState {
+        match self.circuits.lock().unwrap().get(endpoint) {
+            None | Some(Circuit::Closed { .. }) => CircuitState::Closed,
+            Some(Circuit::Open { until }) if now < *until => CircuitState::Open,
+            Some(Circuit::Open { .. }) | Some(Circuit::HalfOpen { .. }) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Checks whether a request may be sent to the given endpoint.
+    pub(crate) fn admit(&self, endpoint: &str, now: SystemTime) -> Result<(), CircuitOpenError> {
+        let mut circuits = self.circuits.lock().unwrap();
+        let Some(circuit) = circuits.get_mut(endpoint) else {
+            return Ok(());
+        };
+        let open_duration = self.config.open_duration;
+        match circuit {
+            Circuit::Closed { .. } => Ok(()),
+            Circuit::Open { until } if now < *until => Err(CircuitOpenError::new(endpoint)),
+            Circuit::Open { .. } => {
+                debug!(endpoint = %endpoint, "circuit is half-open; sending a trial request");
+                *circuit = Circuit::HalfOpen { trial_started: now };
+                Ok(())
+            }
+            // If the outcome of a trial is never recorded, e.g. because it was cancelled, then
+            // another trial is let through once the open duration has passed.
+            Circuit::HalfOpen { trial_started } if now < *trial_started + open_duration => {
+                Err(CircuitOpenError::new(endpoint))
+            }
+            Circuit::HalfOpen { trial_started } => {
+                *trial_started = now;
+                Ok(())
+            }
+        }
+    }
+
+    /// Records the outcome of a request sent to the given endpoint.
+    pub(crate) fn record(&self, endpoint: &str, now: SystemTime, failed: bool) {
+        let mut circuits = self.circuits.lock().unwrap();
+        if !failed {
+            circuits.remove(endpoint);
+            return;
+        }
+        let circuit = circuits
+            .entry(endpoint.to_string())
+            .or_insert(Circuit::Closed {
+                consecutive_failures: 0,
+            });
+        let open = match circuit {
+            Circuit::Closed {
+                consecutive_failures,
+            } => {
+                *consecutive_failures += 1;
+                *consecutive_failures >= self.config.failure_threshold
+            }
+            Circuit::Open { .. } | Circuit::HalfOpen { .. } => true,
+        };
+        if open {
+            debug!(endpoint = %endpoint, "opening circuit for {:?}", self.config.open_duration);
+            *circuit = Circuit::Open {
+                until: now + self.config.open_duration,
+            };
+        }
+    }
+}
+
+/// The error returned when a request isn't sent because the circuit for its endpoint is open.
+///
+/// This error is the source of a [`ConnectorError`] for which
+/// [`is_circuit_open`](ConnectorError::is_circuit_open) returns true.
+#[derive(Debug)]
+pub struct CircuitOpenError {
+    endpoint: String,
+}
+
+impl CircuitOpenError {
+    fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+        }
+    }
+
+    /// Returns the URL of the endpoint whose circuit is open.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+impl fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the circuit breaker is open for the endpoint `{}` because it failed repeatedly",
+            self.endpoint
+        )
+    }
+}
+
+impl StdError for CircuitOpenError {}
+
+/// Runtime plugin that enables a [`CircuitBreaker`].
+#[derive(Debug)]
+pub struct CircuitBreakerRuntimePlugin {
+    config: FrozenLayer,
+}
+
+impl CircuitBreakerRuntimePlugin {
+    /// Creates a runtime plugin that uses the given circuit breaker.
+    pub fn new(circuit_breaker: CircuitBreaker) -> Self {
+        let mut layer = Layer::new("CircuitBreaker");
+        layer.store_put(circuit_breaker);
+        Self {
+            config: layer.freeze(),
+        }
+    }
+}
+
+impl RuntimePlugin for CircuitBreakerRuntimePlugin {
+    fn config(&self) -> Option<FrozenLayer> {
+        Some(self.config.clone())
+    }
+}
+
+/// The endpoint an attempt was admitted to, so that its outcome can be recorded.
+#[derive(Clone, Debug)]
+struct AdmittedEndpoint(String);
+
+impl Storable for AdmittedEndpoint {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Checks the circuit for the endpoint resolved for the current attempt.
+///
+/// This is called by the orchestrator after endpoint resolution.
+pub(crate) fn check(
+    runtime_components: &RuntimeComponents,
+    cfg: &mut ConfigBag,
+) -> Result<(), ConnectorError> {
+    let (Some(circuit_breaker), Some(time_source), Some(endpoint)) = (
+        cfg.load::<CircuitBreaker>(),
+        runtime_components.time_source(),
+        cfg.load::<Endpoint>(),
+    ) else {
+        return Ok(());
+    };
+    let endpoint = endpoint.url().to_string();
+    circuit_breaker
+        .admit(&endpoint, time_source.now())
+        .map_err(|err| ConnectorError::circuit_open(BoxError::from(err)))?;
+    cfg.interceptor_state()
+        .store_put(AdmittedEndpoint(endpoint));
+    Ok(())
+}
+
+/// Records the outcome of the current attempt for the endpoint it was sent to.
+///
+/// This is called by the orchestrator after each attempt.
+pub(crate) fn record(
+    ctx: &InterceptorContext,
+    runtime_components: &RuntimeComponents,
+    cfg: &mut ConfigBag,
+) {
+    let (Some(circuit_breaker), Some(time_source), Some(AdmittedEndpoint(endpoint))) = (
+        cfg.load::<CircuitBreaker>(),
+        runtime_components.time_source(),
+        cfg.load::<AdmittedEndpoint>(),
+    ) else {
+        return;
+    };
+    circuit_breaker.record(endpoint, time_source.now(), is_failure(ctx));
+    cfg.interceptor_state().unset::<AdmittedEndpoint>();
+}
+
+/// Transport failures, timeouts, and server errors count against an endpoint.
+fn is_failure(ctx: &InterceptorContext) -> bool {
+    if let Some(Err(err)) = ctx.output_or_error() {
+        if err.is_timeout_error() || err.is_response_error() {
+            return true;
+        }
+        if let Some(err) = err.as_connector_error() {
+            return !err.is_user() && !err.is_circuit_open();
+        }
+    }
+    ctx.response()
+        .map(|response| response.status().is_server_error())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDPOINT: &str = "https://example.com";
+
+    fn circuit_breaker() -> CircuitBreaker {
+        CircuitBreaker::new(
+            CircuitBreakerConfig::new()
+                .with_failure_threshold(3)
+                .with_open_duration(Duration::from_secs(10)),
+        )
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let circuit_breaker = circuit_breaker();
+        let now = SystemTime::UNIX_EPOCH;
+
+        circuit_breaker.record(ENDPOINT, now, true);
+        circuit_breaker.record(ENDPOINT, now, true);
+        circuit_breaker.record(ENDPOINT, now, false);
+        circuit_breaker.record(ENDPOINT, now, true);
+        circuit_breaker.record(ENDPOINT, now, true);
+        assert_eq!(CircuitState::Closed, circuit_breaker.state(ENDPOINT, now));
+        assert!(circuit_breaker.admit(ENDPOINT, now).is_ok());
+
+        circuit_breaker.record(ENDPOINT, now, true);
+        assert_eq!(CircuitState::Open, circuit_breaker.state(ENDPOINT, now));
+        let err = circuit_breaker.admit(ENDPOINT, now).unwrap_err();
+        assert_eq!(ENDPOINT, err.endpoint());
+
+        // Other endpoints aren't affected
+        assert!(circuit_breaker.admit("https://other.com", now).is_ok());
+    }
+
+    #[test]
+    fn half_opens_after_open_duration() {
+        let circuit_breaker = circuit_breaker();
+        let now = SystemTime::UNIX_EPOCH;
+        for _ in 0..3 {
+            circuit_breaker.record(ENDPOINT, now, true);
+        }
+
+        let later = now + Duration::from_secs(10);
+        assert_eq!(
+            CircuitState::HalfOpen,
+            circuit_breaker.state(ENDPOINT, later)
+        );
+        assert!(circuit_breaker.admit(ENDPOINT, later).is_ok());
+        assert!(
+            circuit_breaker.admit(ENDPOINT, later).is_err(),
+            "only one trial request is allowed at a time"
+        );
+
+        // A failed trial opens the circuit again
+        circuit_breaker.record(ENDPOINT, later, true);
+        assert_eq!(CircuitState::Open, circuit_breaker.state(ENDPOINT, later));
+
+        // A successful trial closes it
+        let even_later = later + Duration::from_secs(10);
+        assert!(circuit_breaker.admit(ENDPOINT, even_later).is_ok());
+        circuit_breaker.record(ENDPOINT, even_later, false);
+        assert_eq!(
+            CircuitState::Closed,
+            circuit_breaker.state(ENDPOINT, even_later)
+        );
+        assert!(circuit_breaker.admit(ENDPOINT, even_later).is_ok());
+    }
+
+    #[test]
+    fn trial_is_retried_if_its_outcome_is_never_recorded() {
+        let circuit_breaker = circuit_breaker();
+        let now = SystemTime::UNIX_EPOCH;
+        for _ in 0..3 {
+            circuit_breaker.record(ENDPOINT, now, true);
+        }
+        let later = now + Duration::from_secs(10);
+        assert!(circuit_breaker.admit(ENDPOINT, later).is_ok());
+        assert!(circuit_breaker
+            .admit(ENDPOINT, later + Duration::from_secs(5))
+            .is_err());
+        assert!(circuit_breaker
+            .admit(ENDPOINT, later + Duration::from_secs(10))
+            .is_ok());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn fails_fast_while_circuit_is_open() {
+        use crate::client::orchestrator::operation::Operation;
+        use aws_smithy_async::test_util::ManualTimeSource;
+        use aws_smithy_runtime_api::client::http::{
+            http_client_fn, HttpConnector, HttpConnectorFuture,
+        };
+        use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+        use aws_smithy_runtime_api::client::result::SdkError;
+        use aws_smithy_runtime_api::shared::IntoShared;
+        use aws_smithy_types::body::SdkBody;
+        use aws_smithy_types::timeout::TimeoutConfig;
+        use std::convert::Infallible;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Clone, Debug, Default)]
+        struct FailingConnector {
+            calls: Arc<AtomicUsize>,
+        }
+
+        impl HttpConnector for FailingConnector {
+            fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                HttpConnectorFuture::ready(Err(ConnectorError::io("connection refused".into())))
+            }
+        }
+
+        let connector = FailingConnector::default();
+        let time_source = ManualTimeSource::new(SystemTime::UNIX_EPOCH);
+        let operation = Operation::builder()
+            .service_name("test")
+            .operation_name("test")
+            .http_client(http_client_fn({
+                let connector = connector.clone();
+                move |_, _| connector.clone().into_shared()
+            }))
+            .endpoint_url(ENDPOINT)
+            .no_auth()
+            .no_retry()
+            .timeout_config(TimeoutConfig::disabled())
+            .time_source(time_source.clone())
+            .runtime_plugin(CircuitBreakerRuntimePlugin::new(circuit_breaker()))
+            .serializer(|_: ()| Ok(HttpRequest::new(SdkBody::empty())))
+            .deserializer::<(), Infallible>(|_| Ok(()))
+            .build();
+
+        let dispatch_failure = |err: SdkError<Infallible, _>| match err {
+            SdkError::DispatchFailure(failure) => failure,
+            other => panic!("expected a dispatch failure, got {other:?}"),
+        };
+        for _ in 0..3 {
+            let err = dispatch_failure(operation.invoke(()).await.unwrap_err());
+            assert!(err.is_io());
+        }
+        assert_eq!(3, connector.calls.load(Ordering::SeqCst));
+
+        let err = dispatch_failure(operation.invoke(()).await.unwrap_err());
+        assert!(err.is_circuit_open());
+        assert_eq!(3, connector.calls.load(Ordering::SeqCst));
+
+        time_source.advance(Duration::from_secs(10));
+        let err = dispatch_failure(operation.invoke(()).await.unwrap_err());
+        assert!(err.is_io(), "the trial request is sent");
+        assert_eq!(4, connector.calls.load(Ordering::SeqCst));
+        let err = dispatch_failure(operation.invoke(()).await.unwrap_err());
+        assert!(err.is_circuit_open());
+    }
+}
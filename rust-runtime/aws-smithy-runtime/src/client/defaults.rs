@@ -12,7 +12,7 @@
 use crate::client::http::body::content_length_enforcement::EnforceContentLengthRuntimePlugin;
 use crate::client::identity::IdentityCache;
 use crate::client::retries::strategy::StandardRetryStrategy;
-use crate::client::retries::RetryPartition;
+use crate::client::retries::{ConcurrencyLimiterInterceptor, RetryPartition};
 use aws_smithy_async::rt::sleep::default_async_sleep;
 use aws_smithy_async::time::SystemTimeSource;
 use aws_smithy_runtime_api::box_error::BoxError;
@@ -55,6 +55,8 @@ pub fn default_http_client_plugin() -> Option<SharedRuntimePlugin> {
     let _default: Option<SharedHttpClient> = None;
     #[cfg(feature = "connector-hyper-0-14-x")]
     let _default = crate::client::http::hyper_014::default_client();
+    #[cfg(any(feature = "crypto-ring", feature = "crypto-aws-lc"))]
+    let _default = _default.or_else(crate::client::http::hyper_1::default_client);
 
     _default.map(|default| {
         default_plugin("default_http_client_plugin", |components| {
@@ -85,6 +87,9 @@ pub fn default_time_source_plugin() -> Option<SharedRuntimePlugin> {
 }
 
 /// Runtime plugin that sets the default retry strategy, config (disabled), and partition.
+///
+/// This also registers the [`ConcurrencyLimiterInterceptor`] that the retry strategy relies on
+/// when a concurrency limit is configured.
 pub fn default_retry_config_plugin(
     default_partition_name: impl Into<Cow<'static, str>>,
 ) -> Option<SharedRuntimePlugin> {
@@ -92,6 +97,7 @@ pub fn default_retry_config_plugin(
         default_plugin("default_retry_config_plugin", |components| {
             components
                 .with_retry_strategy(Some(StandardRetryStrategy::new()))
+                .with_interceptor(ConcurrencyLimiterInterceptor::new())
                 .with_config_validator(SharedConfigValidator::base_client_config_fn(
                     validate_retry_config,
                 ))
@@ -5,6 +5,9 @@
 
 //! Built-in DNS resolver implementations.
 
+#[cfg(all(feature = "rt-tokio", not(target_family = "wasm")))]
+mod caching;
+
 #[cfg(all(feature = "rt-tokio", not(target_family = "wasm")))]
 mod tokio {
     use aws_smithy_runtime_api::client::dns::{DnsFuture, ResolveDns, ResolveDnsError};
@@ -47,3 +50,6 @@ mod tokio {
 
 #[cfg(all(feature = "rt-tokio", not(target_family = "wasm")))]
 pub use self::tokio::TokioDnsResolver;
+
+#[cfg(all(feature = "rt-tokio", not(target_family = "wasm")))]
+pub use self::caching::{CachingDnsResolver, CachingDnsResolverBuilder};
@@ -0,0 +1,619 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use crate::client::dns::TokioDnsResolver;
+use aws_smithy_async::time::{SharedTimeSource, TimeSource};
+use aws_smithy_runtime_api::client::dns::{
+    DnsFuture, DnsRecords, ResolveDns, ResolveDnsError, SharedDnsResolver,
+};
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::error::display::DisplayErrorContext;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_TTL: Duration = Duration::from_secs(300);
+const DEFAULT_STALE_WINDOW: Duration = Duration::from_secs(60);
+const DEFAULT_FAILURE_PENALTY: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+
+/// DNS resolver that caches the results of another resolver.
+///
+/// - Results are cached for their TTL, as reported by
+///   [`resolve_dns_records`](ResolveDns::resolve_dns_records). When the underlying resolver doesn't
+///   report a TTL, a default TTL is used.
+/// - After an entry expires, it is still returned for a short stale window while it is refreshed in
+///   the background, so callers don't wait on DNS. Stale entries are also returned if refreshing
+///   them fails.
+/// - Each lookup rotates the order of the returned addresses, so that new connections are spread
+///   across all of the addresses of a host.
+/// - Addresses that connections failed on, as reported through
+///   [`report_connection_failure`](ResolveDns::report_connection_failure), aren't returned for a
+///   while, unless every address of the host has failed.
+/// - At most a fixed number of names are cached. Expired entries are kept for as long as the
+///   longest TTL so that they can be used if looking them up again fails, and are evicted after
+///   that, or sooner when room is needed for another name.
+///
+/// Connectors created with a resolver report poisoned connections to it, so pairing this resolver
+/// with the `ConnectionPoisoningInterceptor` removes addresses that stop responding.
+///
+/// Background refreshes are spawned onto the current Tokio runtime.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aws_smithy_runtime::client::dns::CachingDnsResolver;
+/// use std::time::Duration;
+///
+/// let resolver = CachingDnsResolver::builder()
+///     .default_ttl(Duration::from_secs(60))
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct CachingDnsResolver {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    resolver: SharedDnsResolver,
+    time_source: SharedTimeSource,
+    default_ttl: Duration,
+    max_ttl: Duration,
+    stale_window: Duration,
+    failure_penalty: Duration,
+    max_entries: usize,
+    entries: Mutex<HashMap<String, Entry>>,
+    lookups: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    failed: Mutex<HashMap<IpAddr, SystemTime>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    addresses: Vec<IpAddr>,
+    expires_at: SystemTime,
+    next: usize,
+    refreshing: bool,
+}
+
+enum Cached {
+    Fresh(Vec<IpAddr>),
+    Stale {
+        addresses: Vec<IpAddr>,
+        refresh: bool,
+    },
+    Missing,
+}
+
+impl CachingDnsResolver {
+    /// Creates a caching resolver with default settings around `resolver`.
+    pub fn new(resolver: impl ResolveDns + 'static) -> Self {
+        Self::builder().resolver(resolver).build()
+    }
+
+    /// Returns a builder for a caching resolver.
+    pub fn builder() -> CachingDnsResolverBuilder {
+        CachingDnsResolverBuilder::default()
+    }
+
+    async fn resolve(&self, name: &str) -> Result<Vec<IpAddr>, ResolveDnsError> {
+        match self.inner.cached(name) {
+            Cached::Fresh(addresses) => return Ok(addresses),
+            Cached::Stale { addresses, refresh } => {
+                if refresh {
+                    self.refresh_in_background(name);
+                }
+                return Ok(addresses);
+            }
+            Cached::Missing => {}
+        }
+
+        // Only look each name up once, even when many connections are being made to it at once
+        let lookup = LookupGuard::new(&self.inner, name);
+        let _guard = lookup.lookup.lock().await;
+        if let Cached::Fresh(addresses) = self.inner.cached(name) {
+            return Ok(addresses);
+        }
+        match self.inner.resolver.resolve_dns_records(name).await {
+            Ok(records) => {
+                self.inner.store(name, records);
+                Ok(self.inner.select(name).unwrap_or_default())
+            }
+            Err(err) => match self.inner.select(name) {
+                Some(addresses) => {
+                    tracing::warn!(name = %name, err = %DisplayErrorContext(&err), "DNS lookup failed; using the expired cache entry");
+                    Ok(addresses)
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    fn refresh_in_background(&self, name: &str) {
+        let handle = match tokio::runtime::Handle::try_current() {
+            Ok(handle) => handle,
+            Err(_) => {
+                tracing::debug!(name = %name, "no Tokio runtime to refresh the DNS cache in the background");
+                self.inner.refresh_finished(name);
+                return;
+            }
+        };
+        let inner = self.inner.clone();
+        let name = name.to_string();
+        handle.spawn(async move {
+            match inner.resolver.resolve_dns_records(&name).await {
+                Ok(records) => inner.store(&name, records),
+                Err(err) => {
+                    tracing::warn!(name = %name, err = %DisplayErrorContext(&err), "failed to refresh the DNS cache");
+                    inner.refresh_finished(&name);
+                }
+            }
+        });
+    }
+}
+
+/// Shares a lookup of a name with the other callers looking it up at the same time, and forgets
+/// the lookup once the last of them is done with it, whether it finished or was cancelled.
+struct LookupGuard<'a> {
+    inner: &'a Inner,
+    name: &'a str,
+    lookup: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl<'a> LookupGuard<'a> {
+    fn new(inner: &'a Inner, name: &'a str) -> Self {
+        let lookup = inner
+            .lookups
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone();
+        Self {
+            inner,
+            name,
+            lookup,
+        }
+    }
+}
+
+impl Drop for LookupGuard<'_> {
+    fn drop(&mut self) {
+        let mut lookups = self.inner.lookups.lock().unwrap();
+        // Callers only clone the lookup while holding the lock, so if the map's reference and this
+        // one are the only ones left, nobody else is waiting on it.
+        if lookups
+            .get(self.name)
+            .is_some_and(|lookup| Arc::ptr_eq(lookup, &self.lookup))
+            && Arc::strong_count(&self.lookup) == 2
+        {
+            lookups.remove(self.name);
+        }
+    }
+}
+
+impl Inner {
+    fn cached(&self, name: &str) -> Cached {
+        let now = self.time_source.now();
+        let mut entries = self.entries.lock().unwrap();
+        let entry = match entries.get_mut(name) {
+            Some(entry) => entry,
+            None => return Cached::Missing,
+        };
+        if now < entry.expires_at {
+            return Cached::Fresh(self.rotate(entry, now));
+        }
+        if now < entry.expires_at + self.stale_window {
+            let refresh = !entry.refreshing;
+            entry.refreshing = true;
+            return Cached::Stale {
+                addresses: self.rotate(entry, now),
+                refresh,
+            };
+        }
+        Cached::Missing
+    }
+
+    /// Returns the addresses of an entry, even if it has expired.
+    fn select(&self, name: &str) -> Option<Vec<IpAddr>> {
+        let now = self.time_source.now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.get_mut(name).map(|entry| self.rotate(entry, now))
+    }
+
+    fn store(&self, name: &str, records: DnsRecords) {
+        let ttl = records.ttl().unwrap_or(self.default_ttl).min(self.max_ttl);
+        let addresses = records.into_addresses();
+        let mut entries = self.entries.lock().unwrap();
+        if addresses.is_empty() {
+            // Don't cache empty results, but keep serving the previous addresses if there were any
+            if let Some(entry) = entries.get_mut(name) {
+                entry.refreshing = false;
+            }
+            return;
+        }
+        tracing::trace!(name = %name, addresses = ?addresses, ttl = ?ttl, "caching DNS records");
+        let now = self.time_source.now();
+        if !entries.contains_key(name) && entries.len() >= self.max_entries {
+            self.make_room(&mut entries, now);
+        }
+        let next = entries.get(name).map(|entry| entry.next).unwrap_or(0);
+        entries.insert(
+            name.to_string(),
+            Entry {
+                addresses,
+                expires_at: now + ttl,
+                next,
+                refreshing: false,
+            },
+        );
+    }
+
+    /// Evicts the entries that have been expired for longer than the longest TTL, and if that
+    /// doesn't free up room for another entry, the entry that expires first.
+    fn make_room(&self, entries: &mut HashMap<String, Entry>, now: SystemTime) {
+        entries.retain(|_, entry| now < entry.expires_at + self.max_ttl);
+        if entries.len() < self.max_entries {
+            return;
+        }
+        let oldest = entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.expires_at)
+            .map(|(name, _)| name.clone());
+        if let Some(oldest) = oldest {
+            tracing::trace!(name = %oldest, "evicting DNS records to make room in the cache");
+            entries.remove(&oldest);
+        }
+    }
+
+    fn refresh_finished(&self, name: &str) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(name) {
+            entry.refreshing = false;
+        }
+    }
+
+    /// Returns the healthy addresses of `entry`, rotated so that each lookup starts with a
+    /// different address.
+    fn rotate(&self, entry: &mut Entry, now: SystemTime) -> Vec<IpAddr> {
+        let mut failed = self.failed.lock().unwrap();
+        failed.retain(|_, until| now < *until);
+        let mut addresses: Vec<IpAddr> = entry
+            .addresses
+            .iter()
+            .filter(|address| !failed.contains_key(address))
+            .copied()
+            .collect();
+        if addresses.is_empty() {
+            // Every address has failed recently; trying them again beats failing without trying
+            addresses = entry.addresses.clone();
+        }
+        let len = addresses.len();
+        addresses.rotate_left(entry.next % len);
+        entry.next = entry.next.wrapping_add(1);
+        addresses
+    }
+}
+
+impl ResolveDns for CachingDnsResolver {
+    fn resolve_dns<'a>(&'a self, name: &'a str) -> DnsFuture<'a> {
+        DnsFuture::new(self.resolve(name))
+    }
+
+    fn report_connection_failure(&self, address: IpAddr) {
+        tracing::debug!(address = %address, penalty = ?self.inner.failure_penalty, "removing a failed address from DNS results");
+        let until = self.inner.time_source.now() + self.inner.failure_penalty;
+        self.inner.failed.lock().unwrap().insert(address, until);
+        self.inner.resolver.report_connection_failure(address);
+    }
+}
+
+/// Builder for [`CachingDnsResolver`].
+#[derive(Debug, Default)]
+pub struct CachingDnsResolverBuilder {
+    resolver: Option<SharedDnsResolver>,
+    time_source: Option<SharedTimeSource>,
+    default_ttl: Option<Duration>,
+    max_ttl: Option<Duration>,
+    stale_window: Option<Duration>,
+    failure_penalty: Option<Duration>,
+    max_entries: Option<usize>,
+}
+
+impl CachingDnsResolverBuilder {
+    /// Sets the resolver whose results are cached.
+    ///
+    /// Defaults to [`TokioDnsResolver`].
+    pub fn resolver(mut self, resolver: impl ResolveDns + 'static) -> Self {
+        self.resolver = Some(resolver.into_shared());
+        self
+    }
+
+    /// Sets the time source used to expire cache entries.
+    pub fn time_source(mut self, time_source: impl TimeSource + 'static) -> Self {
+        self.time_source = Some(time_source.into_shared());
+        self
+    }
+
+    /// Sets how long results are cached when the resolver doesn't report a TTL.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the longest time that results are cached for, regardless of their TTL.
+    ///
+    /// Defaults to 5 minutes.
+    pub fn max_ttl(mut self, ttl: Duration) -> Self {
+        self.max_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets how long an expired entry is still returned while it is refreshed in the background.
+    ///
+    /// Defaults to 1 minute. Setting this to zero makes every lookup of an expired entry wait for
+    /// the resolver.
+    pub fn stale_window(mut self, window: Duration) -> Self {
+        self.stale_window = Some(window);
+        self
+    }
+
+    /// Sets how long an address isn't returned for after a connection to it failed.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn failure_penalty(mut self, penalty: Duration) -> Self {
+        self.failure_penalty = Some(penalty);
+        self
+    }
+
+    /// Sets the most names that are cached at once.
+    ///
+    /// Defaults to 1000. When the cache is full, the entry that expires first is evicted to make
+    /// room for another name.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Builds the resolver.
+    pub fn build(self) -> CachingDnsResolver {
+        CachingDnsResolver {
+            inner: Arc::new(Inner {
+                resolver: self
+                    .resolver
+                    .unwrap_or_else(|| TokioDnsResolver::new().into_shared()),
+                time_source: self.time_source.unwrap_or_default(),
+                default_ttl: self.default_ttl.unwrap_or(DEFAULT_TTL),
+                max_ttl: self.max_ttl.unwrap_or(DEFAULT_MAX_TTL),
+                stale_window: self.stale_window.unwrap_or(DEFAULT_STALE_WINDOW),
+                failure_penalty: self.failure_penalty.unwrap_or(DEFAULT_FAILURE_PENALTY),
+                max_entries: self.max_entries.unwrap_or(DEFAULT_MAX_ENTRIES).max(1),
+                entries: Default::default(),
+                lookups: Default::default(),
+                failed: Default::default(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CachingDnsResolver;
+    use aws_smithy_async::test_util::ManualTimeSource;
+    use aws_smithy_runtime_api::client::dns::{
+        DnsFuture, DnsRecords, DnsRecordsFuture, ResolveDns, ResolveDnsError,
+    };
+    use std::net::IpAddr;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[derive(Clone, Debug)]
+    struct FakeResolver {
+        records: Arc<Mutex<Result<DnsRecords, String>>>,
+        lookups: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Default for FakeResolver {
+        fn default() -> Self {
+            Self {
+                records: Arc::new(Mutex::new(Ok(DnsRecords::new(Vec::new())))),
+                lookups: Default::default(),
+            }
+        }
+    }
+
+    impl FakeResolver {
+        fn set(&self, addresses: &[&str], ttl: Option<u64>) {
+            let records = DnsRecords::new(addresses.iter().map(|a| a.parse().unwrap()).collect());
+            *self.records.lock().unwrap() = Ok(match ttl {
+                Some(ttl) => records.with_ttl(Duration::from_secs(ttl)),
+                None => records,
+            });
+        }
+
+        fn fail(&self) {
+            *self.records.lock().unwrap() = Err("no DNS for you".into());
+        }
+
+        fn lookups(&self) -> usize {
+            self.lookups.lock().unwrap().len()
+        }
+    }
+
+    impl ResolveDns for FakeResolver {
+        fn resolve_dns<'a>(&'a self, _name: &'a str) -> DnsFuture<'a> {
+            unreachable!("the caching resolver uses resolve_dns_records")
+        }
+
+        fn resolve_dns_records<'a>(&'a self, name: &'a str) -> DnsRecordsFuture<'a> {
+            self.lookups.lock().unwrap().push(name.to_string());
+            DnsRecordsFuture::ready(
+                self.records
+                    .lock()
+                    .unwrap()
+                    .clone()
+                    .map_err(ResolveDnsError::new),
+            )
+        }
+    }
+
+    fn ips(addresses: &[&str]) -> Vec<IpAddr> {
+        addresses.iter().map(|a| a.parse().unwrap()).collect()
+    }
+
+    fn resolver(fake: &FakeResolver, time: &ManualTimeSource) -> CachingDnsResolver {
+        CachingDnsResolver::builder()
+            .resolver(fake.clone())
+            .time_source(time.clone())
+            .stale_window(Duration::from_secs(10))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn results_are_cached_for_their_ttl() {
+        let (fake, time) = (FakeResolver::default(), ManualTimeSource::new(UNIX_EPOCH));
+        let resolver = resolver(&fake, &time);
+        fake.set(&["10.0.0.1"], Some(5));
+
+        assert_eq!(ips(&["10.0.0.1"]), resolver.resolve_dns("a").await.unwrap());
+        time.advance(Duration::from_secs(4));
+        assert_eq!(ips(&["10.0.0.1"]), resolver.resolve_dns("a").await.unwrap());
+        assert_eq!(1, fake.lookups());
+
+        // Without a TTL, the default TTL of 30 seconds is used
+        fake.set(&["10.0.0.2"], None);
+        assert_eq!(ips(&["10.0.0.2"]), resolver.resolve_dns("b").await.unwrap());
+        time.advance(Duration::from_secs(29));
+        assert_eq!(ips(&["10.0.0.2"]), resolver.resolve_dns("b").await.unwrap());
+        assert_eq!(2, fake.lookups());
+    }
+
+    #[tokio::test]
+    async fn stale_entries_are_refreshed_in_the_background() {
+        let (fake, time) = (FakeResolver::default(), ManualTimeSource::new(UNIX_EPOCH));
+        let resolver = resolver(&fake, &time);
+        fake.set(&["10.0.0.1"], Some(5));
+        resolver.resolve_dns("a").await.unwrap();
+
+        fake.set(&["10.0.0.2"], Some(5));
+        time.advance(Duration::from_secs(6));
+        // The stale entry is returned right away, and only one refresh is started
+        assert_eq!(ips(&["10.0.0.1"]), resolver.resolve_dns("a").await.unwrap());
+        assert_eq!(ips(&["10.0.0.1"]), resolver.resolve_dns("a").await.unwrap());
+        tokio::task::yield_now().await;
+        assert_eq!(2, fake.lookups());
+        assert_eq!(ips(&["10.0.0.2"]), resolver.resolve_dns("a").await.unwrap());
+
+        // Entries that are too stale are looked up again before returning
+        time.advance(Duration::from_secs(20));
+        fake.set(&["10.0.0.3"], Some(5));
+        assert_eq!(ips(&["10.0.0.3"]), resolver.resolve_dns("a").await.unwrap());
+        assert_eq!(3, fake.lookups());
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_used_when_lookups_fail() {
+        let (fake, time) = (FakeResolver::default(), ManualTimeSource::new(UNIX_EPOCH));
+        let resolver = resolver(&fake, &time);
+        fake.fail();
+        assert!(resolver.resolve_dns("a").await.is_err());
+
+        fake.set(&["10.0.0.1"], Some(5));
+        resolver.resolve_dns("a").await.unwrap();
+        fake.fail();
+        time.advance(Duration::from_secs(60));
+        assert_eq!(ips(&["10.0.0.1"]), resolver.resolve_dns("a").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn addresses_are_rotated() {
+        let (fake, time) = (FakeResolver::default(), ManualTimeSource::new(UNIX_EPOCH));
+        let resolver = resolver(&fake, &time);
+        fake.set(&["10.0.0.1", "10.0.0.2", "10.0.0.3"], Some(60));
+
+        let mut first_addresses = Vec::new();
+        for _ in 0..3 {
+            let addresses = resolver.resolve_dns("a").await.unwrap();
+            assert_eq!(3, addresses.len());
+            first_addresses.push(addresses[0]);
+        }
+        assert_eq!(ips(&["10.0.0.1", "10.0.0.2", "10.0.0.3"]), first_addresses);
+    }
+
+    #[tokio::test]
+    async fn failed_addresses_are_removed_for_a_while() {
+        let (fake, time) = (FakeResolver::default(), ManualTimeSource::new(UNIX_EPOCH));
+        let resolver = resolver(&fake, &time);
+        fake.set(&["10.0.0.1", "10.0.0.2"], Some(600));
+        resolver.resolve_dns("a").await.unwrap();
+
+        resolver.report_connection_failure("10.0.0.1".parse().unwrap());
+        for _ in 0..3 {
+            assert_eq!(ips(&["10.0.0.2"]), resolver.resolve_dns("a").await.unwrap());
+        }
+
+        // When every address has failed, all of them are returned
+        resolver.report_connection_failure("10.0.0.2".parse().unwrap());
+        assert_eq!(2, resolver.resolve_dns("a").await.unwrap().len());
+
+        time.advance(Duration::from_secs(31));
+        let mut addresses = resolver.resolve_dns("a").await.unwrap();
+        addresses.sort();
+        assert_eq!(ips(&["10.0.0.1", "10.0.0.2"]), addresses);
+    }
+
+    #[tokio::test]
+    async fn finished_lookups_are_forgotten() {
+        let (fake, time) = (FakeResolver::default(), ManualTimeSource::new(UNIX_EPOCH));
+        let resolver = resolver(&fake, &time);
+        fake.set(&["10.0.0.1"], Some(5));
+        resolver.resolve_dns("a").await.unwrap();
+        fake.fail();
+        assert!(resolver.resolve_dns("b").await.is_err());
+
+        assert!(resolver.inner.lookups.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_cache_is_bounded() {
+        let (fake, time) = (FakeResolver::default(), ManualTimeSource::new(UNIX_EPOCH));
+        let resolver = CachingDnsResolver::builder()
+            .resolver(fake.clone())
+            .time_source(time.clone())
+            .max_ttl(Duration::from_secs(60))
+            .max_entries(2)
+            .build();
+        let cached = || {
+            let mut names: Vec<_> = resolver
+                .inner
+                .entries
+                .lock()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect();
+            names.sort();
+            names
+        };
+
+        fake.set(&["10.0.0.1"], Some(10));
+        resolver.resolve_dns("a").await.unwrap();
+        fake.set(&["10.0.0.2"], Some(20));
+        resolver.resolve_dns("b").await.unwrap();
+        // When the cache is full, the entry that expires first is evicted
+        fake.set(&["10.0.0.3"], Some(30));
+        resolver.resolve_dns("c").await.unwrap();
+        assert_eq!(vec!["b", "c"], cached());
+
+        // Entries that have been expired for longer than the max TTL are evicted first
+        time.advance(Duration::from_secs(85));
+        fake.set(&["10.0.0.4"], Some(30));
+        resolver.resolve_dns("d").await.unwrap();
+        assert_eq!(vec!["c", "d"], cached());
+    }
+}
@@ -11,10 +11,27 @@ pub mod test_util;
 
 /// Default HTTP and TLS connectors that use hyper 0.14.x and rustls.
 ///
-/// This module is named after the hyper version number since we anticipate
-/// needing to provide equivalent functionality for hyper 1.x in the future.
+/// This module is named after the hyper version number. Equivalent functionality
+/// for hyper 1.x lives in the `hyper_1` module.
 #[cfg(feature = "connector-hyper-0-14-x")]
 pub mod hyper_014;
 
+/// HTTP and TLS connectors that use hyper 1.x and rustls.
+///
+/// The cryptography provider is selected with the `crypto-ring`, `crypto-aws-lc`,
+/// and `crypto-aws-lc-fips` features.
+#[cfg(feature = "connector-hyper-1-x")]
+pub mod hyper_1;
+
+#[cfg(any(feature = "connector-hyper-0-14-x", feature = "connector-hyper-1-x"))]
+pub mod proxy;
+
+/// How long the default connectors wait for a connection attempt before racing it against an
+/// attempt to the next address (the "Connection Attempt Delay" of [RFC 8305]).
+///
+/// [RFC 8305]: https://www.rfc-editor.org/rfc/rfc8305#section-5
+#[cfg(any(feature = "connector-hyper-0-14-x", feature = "connector-hyper-1-x"))]
+const HAPPY_EYEBALLS_DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+
 /// HTTP body and body-wrapper types
 pub mod body;
@@ -4,14 +4,16 @@
  */
 
 use crate::client::http::connection_poisoning::CaptureSmithyConnection;
+use crate::client::http::hyper_014::pool_tracking::{PoolTracker, TrackConnections};
 use crate::client::http::hyper_014::timeout_middleware::HttpTimeoutError;
+use crate::client::http::proxy::ProxyConfig;
 use aws_smithy_async::future::timeout::TimedOutError;
 use aws_smithy_async::rt::sleep::{default_async_sleep, AsyncSleep, SharedAsyncSleep};
 use aws_smithy_runtime_api::box_error::BoxError;
 use aws_smithy_runtime_api::client::connection::ConnectionMetadata;
 use aws_smithy_runtime_api::client::http::{
-    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpClient,
-    SharedHttpConnector,
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, PoolSnapshot,
+    SharedHttpClient, SharedHttpConnector,
 };
 use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
 use aws_smithy_runtime_api::client::result::ConnectorError;
@@ -37,6 +39,9 @@ use tokio::io::{AsyncRead, AsyncWrite};
 
 #[cfg(feature = "tls-rustls")]
 mod default_connector {
+    use crate::client::http::hyper_014::proxy_connector::ProxyConnector;
+    use crate::client::http::proxy::ProxyConfig;
+    use crate::client::http::HAPPY_EYEBALLS_DELAY;
     use aws_smithy_async::rt::sleep::SharedAsyncSleep;
     use aws_smithy_runtime_api::client::http::HttpConnectorSettings;
     use hyper_0_14::client::HttpConnector;
@@ -48,11 +53,29 @@ mod default_connector {
         hyper_rustls::HttpsConnector<hyper_0_14::client::HttpConnector>,
     > = once_cell::sync::Lazy::new(default_tls);
 
+    static TLS_CONFIG: once_cell::sync::Lazy<rustls::ClientConfig> =
+        once_cell::sync::Lazy::new(tls_config);
+
     fn default_tls() -> HttpsConnector<HttpConnector> {
-        use hyper_rustls::ConfigBuilderExt;
         hyper_rustls::HttpsConnectorBuilder::new()
-               .with_tls_config(
-                rustls::ClientConfig::builder()
+            .with_tls_config(TLS_CONFIG.clone())
+            .https_or_http()
+            .enable_http1()
+            .enable_http2()
+            .wrap_connector(http_connector())
+    }
+
+    /// Returns a TCP connector that races connections to dual-stack hosts as described by RFC 8305.
+    fn http_connector() -> HttpConnector {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_happy_eyeballs_timeout(Some(HAPPY_EYEBALLS_DELAY));
+        http
+    }
+
+    fn tls_config() -> rustls::ClientConfig {
+        use hyper_rustls::ConfigBuilderExt;
+        rustls::ClientConfig::builder()
                     .with_cipher_suites(&[
                         // TLS1.3 suites
                         rustls::cipher_suite::TLS13_AES_256_GCM_SHA384,
@@ -69,11 +92,6 @@ mod default_connector {
                     .expect("Error with the TLS configuration. Please file a bug report under https://github.com/smithy-lang/smithy-rs/issues.")
                     .with_native_roots()
                     .with_no_client_auth()
-            )
-            .https_or_http()
-            .enable_http1()
-            .enable_http2()
-            .build()
     }
 
     pub(super) fn base(
@@ -94,6 +112,18 @@ mod default_connector {
     pub(super) fn https() -> hyper_rustls::HttpsConnector<hyper_0_14::client::HttpConnector> {
         HTTPS_NATIVE_ROOTS.clone()
     }
+
+    /// Return a default HTTPS connector that routes connections through the proxies in `proxy`.
+    pub(super) fn proxied_https(
+        proxy: &ProxyConfig,
+    ) -> HttpsConnector<ProxyConnector<HttpConnector>> {
+        hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config(TLS_CONFIG.clone())
+            .https_or_http()
+            .enable_http1()
+            .enable_http2()
+            .wrap_connector(ProxyConnector::new(http_connector(), proxy.clone()))
+    }
 }
 
 /// Given `HttpConnectorSettings` and an `SharedAsyncSleep`, create a `SharedHttpConnector` from defaults depending on what cargo features are activated.
@@ -130,7 +160,10 @@ pub fn default_client() -> Option<SharedHttpClient> {
 
 /// [`HttpConnector`] that uses [`hyper_0_14`] to make HTTP requests.
 ///
-/// This connector also implements socket connect and read timeouts.
+/// This connector also implements socket connect and read timeouts, and applies the connection
+/// pool settings of [`HttpConnectorSettings`]. Note that hyper 0.14 doesn't support limiting the
+/// number of concurrent HTTP/2 streams, so
+/// [`http2_max_concurrent_streams`](HttpConnectorSettings::http2_max_concurrent_streams) is ignored.
 ///
 /// This shouldn't be used directly in most cases.
 /// See the docs on [`HyperClientBuilder`] for examples of how
@@ -138,6 +171,7 @@ pub fn default_client() -> Option<SharedHttpClient> {
 #[derive(Debug)]
 pub struct HyperConnector {
     adapter: Box<dyn HttpConnector>,
+    pool: PoolTracker,
 }
 
 impl HyperConnector {
@@ -145,6 +179,11 @@ impl HyperConnector {
     pub fn builder() -> HyperConnectorBuilder {
         Default::default()
     }
+
+    /// Returns a snapshot of the idle and active connections in this connector's pool.
+    pub fn pool_snapshot(&self) -> PoolSnapshot {
+        self.pool.snapshot()
+    }
 }
 
 impl HttpConnector for HyperConnector {
@@ -159,6 +198,8 @@ pub struct HyperConnectorBuilder {
     connector_settings: Option<HttpConnectorSettings>,
     sleep_impl: Option<SharedAsyncSleep>,
     client_builder: Option<hyper_0_14::client::Builder>,
+    pool: Option<PoolTracker>,
+    proxy: ProxyConfig,
 }
 
 impl HyperConnectorBuilder {
@@ -171,13 +212,25 @@ impl HyperConnectorBuilder {
         C::Future: Unpin + Send + 'static,
         C::Error: Into<BoxError>,
     {
-        let client_builder = self.client_builder.unwrap_or_default();
+        let mut client_builder = self.client_builder.unwrap_or_default();
         let sleep_impl = self.sleep_impl.or_else(default_async_sleep);
-        let (connect_timeout, read_timeout) = self
-            .connector_settings
-            .map(|c| (c.connect_timeout(), c.read_timeout()))
-            .unwrap_or((None, None));
+        let settings = self.connector_settings.unwrap_or_default();
+        let (connect_timeout, read_timeout) = (settings.connect_timeout(), settings.read_timeout());
+        if let Some(max_idle) = settings.pool_max_idle_per_host() {
+            client_builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(idle_timeout) = settings.pool_idle_timeout() {
+            client_builder.pool_idle_timeout(idle_timeout);
+        }
+        if let Some(interval) = settings.http2_keep_alive_interval() {
+            client_builder.http2_keep_alive_interval(interval);
+        }
+        if settings.http2_max_concurrent_streams().is_some() {
+            tracing::debug!("hyper 0.14 doesn't support limiting concurrent HTTP/2 streams; ignoring http2_max_concurrent_streams");
+        }
 
+        let pool = self.pool.unwrap_or_default();
+        let tcp_connector = TrackConnections::new(tcp_connector, pool.clone());
         let connector = match connect_timeout {
             Some(duration) => timeout_middleware::ConnectTimeout::new(
                 tcp_connector,
@@ -200,7 +253,10 @@ impl HyperConnectorBuilder {
         HyperConnector {
             adapter: Box::new(Adapter {
                 client: read_timeout,
+                pool: pool.clone(),
+                proxy: self.proxy,
             }),
+            pool,
         }
     }
 
@@ -261,6 +317,18 @@ impl HyperConnectorBuilder {
         self.client_builder = hyper_builder;
         self
     }
+
+    /// Share a pool tracker with other connectors, so that their connections are reported together.
+    fn pool_tracker(mut self, pool: PoolTracker) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Authenticate requests that the TCP connector forwards to the `http` proxy of `proxy`.
+    fn forwarding_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = proxy;
+        self
+    }
 }
 
 /// Adapter from a [`hyper_0_14::Client`] to [`HttpConnector`].
@@ -268,8 +336,10 @@ impl HyperConnectorBuilder {
 /// This adapter also enables TCP `CONNECT` and HTTP `READ` timeouts via [`HyperConnector::builder`].
 struct Adapter<C> {
     client: timeout_middleware::HttpReadTimeout<
-        hyper_0_14::Client<timeout_middleware::ConnectTimeout<C>, SdkBody>,
+        hyper_0_14::Client<timeout_middleware::ConnectTimeout<TrackConnections<C>>, SdkBody>,
     >,
+    pool: PoolTracker,
+    proxy: ProxyConfig,
 }
 
 impl<C> fmt::Debug for Adapter<C> {
@@ -321,6 +391,15 @@ where
                 return HttpConnectorFuture::ready(Err(ConnectorError::other(err.into(), None)));
             }
         };
+        if let Some(auth) = self.proxy.forwarding_authorization(
+            request.uri().scheme_str(),
+            request.uri().host().unwrap_or_default(),
+        ) {
+            request.headers_mut().insert(
+                http::header::PROXY_AUTHORIZATION,
+                http::HeaderValue::from_str(auth).expect("base64 is a valid header value"),
+            );
+        }
         let capture_connection = capture_connection(&mut request);
         if let Some(capture_smithy_connection) =
             request.extensions().get::<CaptureSmithyConnection>()
@@ -328,13 +407,14 @@ where
             capture_smithy_connection
                 .set_connection_retriever(move || extract_smithy_connection(&capture_connection));
         }
+        let request_guard = self.pool.request_started(request.uri());
         let mut client = self.client.clone();
         let fut = client.call(request);
         HttpConnectorFuture::new(async move {
             let response = fut
                 .await
                 .map_err(downcast_error)?
-                .map(SdkBody::from_body_0_4);
+                .map(|body| SdkBody::from_body_0_4(request_guard.track_body(body)));
             match HttpResponse::try_from(response) {
                 Ok(response) => Ok(response),
                 Err(err) => Err(ConnectorError::other(err.into(), None)),
@@ -407,6 +487,9 @@ fn find_source<'a, E: Error + 'static>(err: &'a (dyn Error + 'static)) -> Option
 struct CacheKey {
     connect_timeout: Option<Duration>,
     read_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
 }
 
 impl From<&HttpConnectorSettings> for CacheKey {
@@ -414,6 +497,9 @@ impl From<&HttpConnectorSettings> for CacheKey {
         Self {
             connect_timeout: value.connect_timeout(),
             read_timeout: value.read_timeout(),
+            pool_max_idle_per_host: value.pool_max_idle_per_host(),
+            pool_idle_timeout: value.pool_idle_timeout(),
+            http2_keep_alive_interval: value.http2_keep_alive_interval(),
         }
     }
 }
@@ -422,6 +508,8 @@ struct HyperClient<F> {
     connector_cache: RwLock<HashMap<CacheKey, SharedHttpConnector>>,
     client_builder: hyper_0_14::client::Builder,
     tcp_connector_fn: F,
+    pool: PoolTracker,
+    proxy: ProxyConfig,
 }
 
 impl<F> fmt::Debug for HyperClient<F> {
@@ -469,7 +557,9 @@ where
             if !cache.contains_key(&key) {
                 let mut builder = HyperConnector::builder()
                     .hyper_builder(self.client_builder.clone())
-                    .connector_settings(settings.clone());
+                    .connector_settings(settings.clone())
+                    .pool_tracker(self.pool.clone())
+                    .forwarding_proxy(self.proxy.clone());
                 builder.set_sleep_impl(components.sleep_impl());
 
                 let start = components.time_source().map(|ts| ts.now());
@@ -488,6 +578,10 @@ where
 
         connector.expect("cache populated above")
     }
+
+    fn pool_snapshot(&self) -> Option<PoolSnapshot> {
+        Some(self.pool.snapshot())
+    }
 }
 
 /// Builder for a hyper-backed [`HttpClient`] implementation.
@@ -546,6 +640,7 @@ where
 #[derive(Clone, Default, Debug)]
 pub struct HyperClientBuilder {
     client_builder: Option<hyper_0_14::client::Builder>,
+    proxy: ProxyConfig,
 }
 
 impl HyperClientBuilder {
@@ -573,22 +668,45 @@ impl HyperClientBuilder {
         self
     }
 
+    /// Send requests through the proxies in the given [`ProxyConfig`].
+    ///
+    /// This applies to the clients created by `build_https`.
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.set_proxy(Some(proxy));
+        self
+    }
+
+    /// Send requests through the proxies in the given [`ProxyConfig`].
+    ///
+    /// This applies to the clients created by `build_https`.
+    pub fn set_proxy(&mut self, proxy: Option<ProxyConfig>) -> &mut Self {
+        self.proxy = proxy.unwrap_or_default();
+        self
+    }
+
     /// Create a hyper client with the default rustls HTTPS implementation.
     ///
     /// The trusted certificates will be loaded later when this becomes the selected
     /// HTTP client for a Smithy client.
     #[cfg(feature = "tls-rustls")]
     pub fn build_https(self) -> SharedHttpClient {
-        self.build_with_fn(default_connector::https)
+        if self.proxy.is_disabled() {
+            return self.build_with_fn(default_connector::https);
+        }
+        let proxy = self.proxy.clone();
+        self.build_with_fn(move || default_connector::proxied_https(&proxy))
     }
 
     /// Create a [`SharedHttpClient`] from this builder and a given connector.
     ///
+    /// The connector is responsible for reaching the destination, so the proxy settings of this
+    /// builder aren't applied.
+    ///
     #[cfg_attr(
         feature = "tls-rustls",
         doc = "Use [`build_https`](HyperClientBuilder::build_https) if you don't want to provide a custom TCP connector."
     )]
-    pub fn build<C>(self, tcp_connector: C) -> SharedHttpClient
+    pub fn build<C>(mut self, tcp_connector: C) -> SharedHttpClient
     where
         C: Clone + Send + Sync + 'static,
         C: Service<Uri>,
@@ -596,6 +714,7 @@ impl HyperClientBuilder {
         C::Future: Unpin + Send + 'static,
         C::Error: Into<BoxError>,
     {
+        self.proxy = ProxyConfig::disabled();
         self.build_with_fn(move || tcp_connector.clone())
     }
 
@@ -612,10 +731,452 @@ impl HyperClientBuilder {
             connector_cache: RwLock::new(HashMap::new()),
             client_builder: self.client_builder.unwrap_or_default(),
             tcp_connector_fn,
+            pool: PoolTracker::default(),
+            proxy: self.proxy,
         })
     }
 }
 
+#[cfg(feature = "tls-rustls")]
+mod proxy_connector {
+    use crate::client::http::proxy::ProxyConfig;
+    use aws_smithy_runtime_api::box_error::BoxError;
+    use http::Uri;
+    use hyper_0_14::client::connect::{Connected, Connection};
+    use hyper_0_14::service::Service;
+    use std::future::{poll_fn, Future};
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    /// The largest `CONNECT` response head that will be read from a proxy.
+    const MAX_RESPONSE_HEAD: usize = 8192;
+
+    /// Routes connections through the proxies in a [`ProxyConfig`].
+    ///
+    /// `https` destinations are reached through a `CONNECT` tunnel so that TLS is still negotiated with
+    /// the destination. `http` destinations connect to the proxy directly, and the connection is marked
+    /// as proxied so that hyper sends requests in absolute form. Destinations matching the `NO_PROXY`
+    /// rules are connected to directly.
+    #[derive(Clone, Debug)]
+    pub(crate) struct ProxyConnector<C> {
+        inner: C,
+        config: ProxyConfig,
+    }
+
+    impl<C> ProxyConnector<C> {
+        pub(crate) fn new(inner: C, config: ProxyConfig) -> Self {
+            Self { inner, config }
+        }
+    }
+
+    impl<C> Service<Uri> for ProxyConnector<C>
+    where
+        C: Service<Uri> + Clone + Send + 'static,
+        C::Response: AsyncRead + AsyncWrite + Connection + Unpin + Send + 'static,
+        C::Future: Send + 'static,
+        C::Error: Into<BoxError>,
+    {
+        type Response = ProxyStream<C::Response>;
+        type Error = BoxError;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx).map_err(Into::into)
+        }
+
+        fn call(&mut self, dst: Uri) -> Self::Future {
+            let scheme = dst.scheme_str();
+            let proxy = self
+                .config
+                .proxy_for(scheme, dst.host().unwrap_or_default())
+                .cloned();
+            let tunnel_auth = match (scheme, &proxy) {
+                (Some("https"), Some(_)) => {
+                    Some(self.config.authorization(scheme).map(str::to_owned))
+                }
+                _ => None,
+            };
+            let forwarding = proxy.is_some() && tunnel_auth.is_none();
+            let connecting = self.inner.call(proxy.unwrap_or_else(|| dst.clone()));
+            Box::pin(async move {
+                let mut inner = connecting.await.map_err(Into::into)?;
+                if let Some(auth) = tunnel_auth {
+                    tunnel(&mut inner, &dst, auth.as_deref()).await?;
+                }
+                Ok(ProxyStream { inner, forwarding })
+            })
+        }
+    }
+
+    /// Asks the proxy on the other end of `conn` to open a tunnel to `dst`.
+    async fn tunnel<T>(conn: &mut T, dst: &Uri, auth: Option<&str>) -> io::Result<()>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+    {
+        let host = dst
+            .host()
+            .ok_or_else(|| io::Error::other("the destination URI has no host"))?;
+        let port = dst.port_u16().unwrap_or(443);
+        let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+        if let Some(auth) = auth {
+            request.push_str(&format!("Proxy-Authorization: {auth}\r\n"));
+        }
+        request.push_str("\r\n");
+
+        let mut request = request.as_bytes();
+        while !request.is_empty() {
+            match poll_fn(|cx| Pin::new(&mut *conn).poll_write(cx, request)).await? {
+                0 => return Err(io::ErrorKind::WriteZero.into()),
+                n => request = &request[n..],
+            }
+        }
+        poll_fn(|cx| Pin::new(&mut *conn).poll_flush(cx)).await?;
+
+        let mut response = [0; MAX_RESPONSE_HEAD];
+        let mut len = 0;
+        loop {
+            if len == response.len() {
+                return Err(io::Error::other(
+                    "the proxy's CONNECT response is too large",
+                ));
+            }
+            let mut buf = ReadBuf::new(&mut response[len..]);
+            poll_fn(|cx| Pin::new(&mut *conn).poll_read(cx, &mut buf)).await?;
+            match buf.filled().len() {
+                0 => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "the proxy closed the connection before the tunnel was established",
+                    ))
+                }
+                n => len += n,
+            }
+            if response[..len].windows(4).any(|w| w == b"\r\n\r\n") {
+                break;
+            }
+        }
+
+        let head = String::from_utf8_lossy(&response[..len]);
+        let status_line = head.lines().next().unwrap_or_default();
+        match status_line.split(' ').nth(1) {
+            Some(status) if status.len() == 3 && status.starts_with('2') => Ok(()),
+            Some("407") => Err(io::Error::other(
+                "the proxy requires authentication (407 Proxy Authentication Required)",
+            )),
+            _ => Err(io::Error::other(format!(
+                "the proxy refused to open a tunnel: {status_line}"
+            ))),
+        }
+    }
+
+    /// A connection that may have been established through a proxy.
+    #[derive(Debug)]
+    pub(crate) struct ProxyStream<T> {
+        inner: T,
+        forwarding: bool,
+    }
+
+    impl<T: Connection> Connection for ProxyStream<T> {
+        fn connected(&self) -> Connected {
+            let connected = self.inner.connected();
+            if self.forwarding {
+                connected.proxy(true)
+            } else {
+                connected
+            }
+        }
+    }
+
+    impl<T: AsyncRead + Unpin> AsyncRead for ProxyStream<T> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<T: AsyncWrite + Unpin> AsyncWrite for ProxyStream<T> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[io::IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+    }
+}
+
+mod pool_tracking {
+    use aws_smithy_runtime_api::client::http::{AuthorityPoolStats, PoolSnapshot};
+    use bytes::Bytes;
+    use http::Uri;
+    use hyper_0_14::client::connect::{Connected, Connection};
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    #[derive(Debug, Default)]
+    struct Counts {
+        open: usize,
+        in_flight: usize,
+    }
+
+    /// Tracks the open connections and in-flight requests of each authority.
+    ///
+    /// Hyper doesn't expose the state of its pool, so a connection is considered active if there
+    /// is a request in flight to its authority, and idle otherwise.
+    #[derive(Clone, Debug, Default)]
+    pub(super) struct PoolTracker {
+        authorities: Arc<Mutex<HashMap<String, Counts>>>,
+    }
+
+    impl PoolTracker {
+        pub(super) fn snapshot(&self) -> PoolSnapshot {
+            let authorities = self.authorities.lock().unwrap();
+            let mut snapshot = PoolSnapshot::new();
+            for (authority, counts) in authorities.iter().filter(|(_, c)| c.open > 0) {
+                let active = counts.in_flight.min(counts.open);
+                snapshot.record(
+                    authority.clone(),
+                    AuthorityPoolStats::new(counts.open - active, active),
+                );
+            }
+            snapshot
+        }
+
+        pub(super) fn request_started(&self, uri: &Uri) -> Guard {
+            self.guard(uri, |counts| &mut counts.in_flight)
+        }
+
+        fn connection_opened(&self, uri: &Uri) -> Guard {
+            self.guard(uri, |counts| &mut counts.open)
+        }
+
+        fn guard(&self, uri: &Uri, count: fn(&mut Counts) -> &mut usize) -> Guard {
+            let authority = uri
+                .authority()
+                .map(|authority| authority.as_str().to_owned())
+                .unwrap_or_default();
+            let mut authorities = self.authorities.lock().unwrap();
+            *count(authorities.entry(authority.clone()).or_default()) += 1;
+            Guard {
+                tracker: self.clone(),
+                authority,
+                count,
+            }
+        }
+    }
+
+    /// Decrements a count of a [`PoolTracker`] when dropped.
+    #[derive(Debug)]
+    pub(super) struct Guard {
+        tracker: PoolTracker,
+        authority: String,
+        count: fn(&mut Counts) -> &mut usize,
+    }
+
+    impl Guard {
+        /// Keeps this guard alive until `body` has been read to the end or dropped.
+        pub(super) fn track_body<B>(self, body: B) -> TrackedBody<B> {
+            TrackedBody {
+                inner: body,
+                guard: Some(self),
+            }
+        }
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            let mut authorities = self.tracker.authorities.lock().unwrap();
+            if let Some(counts) = authorities.get_mut(&self.authority) {
+                *(self.count)(counts) -= 1;
+                if counts.open == 0 && counts.in_flight == 0 {
+                    authorities.remove(&self.authority);
+                }
+            }
+        }
+    }
+
+    /// A response body that holds a request [`Guard`] until it's finished.
+    pub(super) struct TrackedBody<B> {
+        inner: B,
+        guard: Option<Guard>,
+    }
+
+    impl<B> http_body_0_4::Body for TrackedBody<B>
+    where
+        B: http_body_0_4::Body<Data = Bytes> + Unpin,
+    {
+        type Data = Bytes;
+        type Error = B::Error;
+
+        fn poll_data(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+            let result = Pin::new(&mut self.inner).poll_data(cx);
+            if matches!(result, Poll::Ready(None)) || self.inner.is_end_stream() {
+                self.guard = None;
+            }
+            result
+        }
+
+        fn poll_trailers(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+            Pin::new(&mut self.inner).poll_trailers(cx)
+        }
+
+        fn is_end_stream(&self) -> bool {
+            self.inner.is_end_stream()
+        }
+
+        fn size_hint(&self) -> http_body_0_4::SizeHint {
+            self.inner.size_hint()
+        }
+    }
+
+    /// A connector that counts the connections it opens with a [`PoolTracker`].
+    #[derive(Clone, Debug)]
+    pub(super) struct TrackConnections<C> {
+        inner: C,
+        pool: PoolTracker,
+    }
+
+    impl<C> TrackConnections<C> {
+        pub(super) fn new(inner: C, pool: PoolTracker) -> Self {
+            Self { inner, pool }
+        }
+    }
+
+    impl<C> hyper_0_14::service::Service<Uri> for TrackConnections<C>
+    where
+        C: hyper_0_14::service::Service<Uri>,
+        C::Future: Unpin,
+    {
+        type Response = TrackedConnection<C::Response>;
+        type Error = C::Error;
+        type Future = TrackConnectionsFuture<C::Future>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, uri: Uri) -> Self::Future {
+            TrackConnectionsFuture {
+                pool: self.pool.clone(),
+                inner: self.inner.call(uri.clone()),
+                uri,
+            }
+        }
+    }
+
+    pub(super) struct TrackConnectionsFuture<F> {
+        inner: F,
+        pool: PoolTracker,
+        uri: Uri,
+    }
+
+    impl<F, T, E> Future for TrackConnectionsFuture<F>
+    where
+        F: Future<Output = Result<T, E>> + Unpin,
+    {
+        type Output = Result<TrackedConnection<T>, E>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let this = &mut *self;
+            Pin::new(&mut this.inner)
+                .poll(cx)
+                .map_ok(|inner| TrackedConnection {
+                    inner,
+                    _guard: this.pool.connection_opened(&this.uri),
+                })
+        }
+    }
+
+    /// A connection that is counted by a [`PoolTracker`] until it's closed.
+    #[derive(Debug)]
+    pub(super) struct TrackedConnection<T> {
+        inner: T,
+        _guard: Guard,
+    }
+
+    impl<T: Connection> Connection for TrackedConnection<T> {
+        fn connected(&self) -> Connected {
+            self.inner.connected()
+        }
+    }
+
+    impl<T: AsyncRead + Unpin> AsyncRead for TrackedConnection<T> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<T: AsyncWrite + Unpin> AsyncWrite for TrackedConnection<T> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[io::IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+    }
+}
+
 mod timeout_middleware {
     use aws_smithy_async::future::timeout::{TimedOutError, Timeout};
     use aws_smithy_async::rt::sleep::Sleep;
@@ -988,8 +1549,11 @@ mod timeout_middleware {
 mod test {
     use super::*;
     use crate::client::http::test_util::NeverTcpConnector;
+    use aws_smithy_async::rt::sleep::TokioSleep;
     use aws_smithy_async::time::SystemTimeSource;
+    use aws_smithy_runtime_api::client::http::AuthorityPoolStats;
     use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use aws_smithy_types::byte_stream::ByteStream;
     use http::Uri;
     use hyper_0_14::client::connect::{Connected, Connection};
     use std::io::{Error, ErrorKind};
@@ -1055,6 +1619,58 @@ mod test {
         assert_eq!(4, creation_count.load(Ordering::Relaxed));
     }
 
+    #[tokio::test]
+    async fn pool_snapshot_reports_idle_and_active_connections() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let authority = listener.local_addr().unwrap().to_string();
+        let server = hyper_0_14::Server::from_tcp(listener).unwrap().serve(
+            hyper_0_14::service::make_service_fn(|_| async {
+                Ok::<_, std::convert::Infallible>(hyper_0_14::service::service_fn(|_| async {
+                    Ok::<_, std::convert::Infallible>(http::Response::new(hyper_0_14::Body::from(
+                        "hello",
+                    )))
+                }))
+            }),
+        );
+        tokio::spawn(server);
+
+        let http_client = HyperClientBuilder::new().build(hyper_0_14::client::HttpConnector::new());
+        let settings = HttpConnectorSettings::builder()
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(Duration::from_secs(30))
+            .http2_keep_alive_interval(Duration::from_secs(10))
+            .build();
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_sleep_impl(Some(TokioSleep::new()))
+            .with_time_source(Some(SystemTimeSource::new()))
+            .build()
+            .unwrap();
+        let connector = http_client.http_connector(&settings, &components);
+        assert_eq!(Some(PoolSnapshot::new()), http_client.pool_snapshot());
+
+        let response = connector
+            .call(HttpRequest::get(format!("http://{authority}/")).unwrap())
+            .await
+            .unwrap();
+        let snapshot = http_client.pool_snapshot().unwrap();
+        assert_eq!(
+            Some(AuthorityPoolStats::new(0, 1)),
+            snapshot.get(&authority)
+        );
+
+        let body = ByteStream::new(response.into_body())
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(b"hello", &body.into_bytes()[..]);
+        let snapshot = http_client.pool_snapshot().unwrap();
+        assert_eq!(
+            Some(AuthorityPoolStats::new(1, 0)),
+            snapshot.get(&authority)
+        );
+        assert_eq!((1, 0), (snapshot.idle(), snapshot.active()));
+    }
+
     #[tokio::test]
     async fn hyper_io_error() {
         let connector = TestConnection {
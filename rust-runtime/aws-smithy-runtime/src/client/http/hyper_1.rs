@@ -0,0 +1,2357 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Debug;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{fmt, vec};
+
+use client::connect::{capture_connection, CaptureConnection, Connection, HttpInfo};
+use h2_0_4::Reason;
+use http1::{Extensions, Uri};
+use hyper_1_x::rt::{Read, Write};
+use hyper_util::client::legacy as client;
+use hyper_util::client::legacy::connect::dns::Name;
+use hyper_util::client::legacy::connect::Connect;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use rustls_0_23::crypto::CryptoProvider;
+
+use aws_smithy_async::future::timeout::TimedOutError;
+use aws_smithy_async::rt::sleep::{default_async_sleep, AsyncSleep, SharedAsyncSleep};
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::connection::ConnectionMetadata;
+use aws_smithy_runtime_api::client::dns::{ResolveDns, SharedDnsResolver};
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, PoolSnapshot,
+    SharedHttpClient, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::{
+    RuntimeComponents, RuntimeComponentsBuilder,
+};
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::error::display::DisplayErrorContext;
+use aws_smithy_types::retry::ErrorKind;
+
+use crate::client::http::connection_poisoning::CaptureSmithyConnection;
+use crate::client::http::hyper_1::pool_tracking::{PoolTracker, TrackConnections};
+use crate::client::http::hyper_1::timeout_middleware::{ConnectTimeout, HttpTimeoutError};
+use crate::client::http::proxy::ProxyConfig;
+
+/// The cryptography provider used by rustls.
+///
+/// Each variant is enabled by the cargo feature of the same name.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[non_exhaustive]
+pub enum CryptoMode {
+    /// Use the [`ring`](https://docs.rs/ring) provider (`crypto-ring` feature).
+    #[cfg(feature = "crypto-ring")]
+    Ring,
+    /// Use the [`aws-lc-rs`](https://docs.rs/aws-lc-rs) provider (`crypto-aws-lc` feature).
+    #[cfg(feature = "crypto-aws-lc")]
+    AwsLc,
+    /// Use the FIPS-validated `aws-lc-rs` provider (`crypto-aws-lc-fips` feature).
+    #[cfg(feature = "crypto-aws-lc-fips")]
+    AwsLcFips,
+}
+
+impl CryptoMode {
+    fn provider(self) -> CryptoProvider {
+        match self {
+            #[cfg(feature = "crypto-aws-lc")]
+            CryptoMode::AwsLc => rustls_0_23::crypto::aws_lc_rs::default_provider(),
+
+            #[cfg(feature = "crypto-ring")]
+            CryptoMode::Ring => rustls_0_23::crypto::ring::default_provider(),
+
+            #[cfg(feature = "crypto-aws-lc-fips")]
+            CryptoMode::AwsLcFips => {
+                let provider = rustls_0_23::crypto::default_fips_provider();
+                assert!(
+                    provider.fips(),
+                    "FIPS was requested but the provider did not support FIPS"
+                );
+                provider
+            }
+        }
+    }
+}
+
+/// Creates a hyper-backed HTTPS client from defaults depending on what cargo features are activated.
+///
+/// `aws-lc-rs` is preferred over `ring` when both the `crypto-aws-lc` and `crypto-ring` features are enabled.
+#[cfg(any(feature = "crypto-ring", feature = "crypto-aws-lc"))]
+pub fn default_client() -> Option<SharedHttpClient> {
+    #[cfg(feature = "crypto-aws-lc")]
+    let mode = CryptoMode::AwsLc;
+    #[cfg(all(feature = "crypto-ring", not(feature = "crypto-aws-lc")))]
+    let mode = CryptoMode::Ring;
+    tracing::trace!(mode = ?mode, "creating a new default hyper 1.x client");
+    Some(HyperClientBuilder::new().crypto_mode(mode).build_https())
+}
+
+/// The root certificates used to verify the TLS certificates presented by servers.
+///
+/// By default, the platform's native root certificates are trusted.
+///
+/// # Examples
+///
+/// Trust only a private certificate authority:
+/// ```no_run
+/// use aws_smithy_runtime::client::http::hyper_1::TrustStore;
+///
+/// let ca_pem = std::fs::read("private-ca.pem").expect("CA exists");
+/// let trust_store = TrustStore::empty().with_pem_certificate(ca_pem);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TrustStore {
+    enable_native_roots: bool,
+    custom_certs: Vec<CustomCertificate>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum CustomCertificate {
+    Pem(Vec<u8>),
+    Der(Vec<u8>),
+}
+
+impl Default for TrustStore {
+    fn default() -> Self {
+        Self {
+            enable_native_roots: true,
+            custom_certs: Vec::new(),
+        }
+    }
+}
+
+impl TrustStore {
+    /// Creates a trust store that doesn't trust any certificates.
+    ///
+    /// Use [`with_pem_certificate`](Self::with_pem_certificate) to add certificates to it.
+    pub fn empty() -> Self {
+        Self {
+            enable_native_roots: false,
+            custom_certs: Vec::new(),
+        }
+    }
+
+    /// Enables or disables trusting the platform's native root certificates.
+    pub fn with_native_roots(mut self, enable_native_roots: bool) -> Self {
+        self.enable_native_roots = enable_native_roots;
+        self
+    }
+
+    /// Trusts every certificate in the given PEM-encoded data.
+    ///
+    /// The certificates are parsed when the HTTP client is initialized, and parsing
+    /// failures are reported at that time.
+    pub fn with_pem_certificate(mut self, pem_bytes: impl Into<Vec<u8>>) -> Self {
+        self.custom_certs
+            .push(CustomCertificate::Pem(pem_bytes.into()));
+        self
+    }
+
+    /// Trusts the given DER-encoded certificate.
+    pub fn with_der_certificate(mut self, der_bytes: impl Into<Vec<u8>>) -> Self {
+        self.custom_certs
+            .push(CustomCertificate::Der(der_bytes.into()));
+        self
+    }
+
+    fn root_cert_store(&self) -> Result<rustls_0_23::RootCertStore, BoxError> {
+        use rustls_0_23::pki_types::pem::PemObject;
+        use rustls_0_23::pki_types::CertificateDer;
+
+        let mut roots = rustls_0_23::RootCertStore::empty();
+        if self.enable_native_roots {
+            let native = rustls_native_certs::load_native_certs();
+            for err in &native.errors {
+                tracing::debug!(err = %DisplayErrorContext(err), "failed to load a native root certificate");
+            }
+            let (added, ignored) = roots.add_parsable_certificates(native.certs);
+            tracing::debug!(added, ignored, "loaded native root certificates");
+            if added == 0 && self.custom_certs.is_empty() {
+                return Err("no valid native root CA certificates found".into());
+            }
+        }
+        for cert in &self.custom_certs {
+            match cert {
+                CustomCertificate::Pem(pem) => {
+                    let mut found = false;
+                    for cert in CertificateDer::pem_slice_iter(pem) {
+                        roots.add(cert?)?;
+                        found = true;
+                    }
+                    if !found {
+                        return Err(
+                            "no certificates found in the PEM data for the trust store".into()
+                        );
+                    }
+                }
+                CustomCertificate::Der(der) => {
+                    roots.add(CertificateDer::from(der.clone()))?;
+                }
+            }
+        }
+        Ok(roots)
+    }
+}
+
+/// A client certificate and private key presented to servers that request TLS client authentication (mTLS).
+#[derive(Clone, Eq, PartialEq)]
+pub struct ClientCertificate {
+    cert_chain_pem: Vec<u8>,
+    private_key_pem: Vec<u8>,
+}
+
+impl ClientCertificate {
+    /// Creates a client certificate from a PEM-encoded certificate chain and private key.
+    ///
+    /// The chain must begin with the client's certificate. The key may be PKCS#1, PKCS#8, or SEC1
+    /// encoded. Both are parsed when the HTTP client is initialized, and parsing failures are reported
+    /// at that time.
+    pub fn from_pem(
+        cert_chain_pem: impl Into<Vec<u8>>,
+        private_key_pem: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            cert_chain_pem: cert_chain_pem.into(),
+            private_key_pem: private_key_pem.into(),
+        }
+    }
+
+    fn parse(
+        &self,
+    ) -> Result<
+        (
+            Vec<rustls_0_23::pki_types::CertificateDer<'static>>,
+            rustls_0_23::pki_types::PrivateKeyDer<'static>,
+        ),
+        BoxError,
+    > {
+        use rustls_0_23::pki_types::pem::PemObject;
+        use rustls_0_23::pki_types::{CertificateDer, PrivateKeyDer};
+
+        let chain =
+            CertificateDer::pem_slice_iter(&self.cert_chain_pem).collect::<Result<Vec<_>, _>>()?;
+        if chain.is_empty() {
+            return Err("no certificates found in the client certificate chain".into());
+        }
+        let key = PrivateKeyDer::from_pem_slice(&self.private_key_pem)?;
+        Ok((chain, key))
+    }
+}
+
+impl fmt::Debug for ClientCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientCertificate")
+            .field(
+                "cert_chain_pem",
+                &String::from_utf8_lossy(&self.cert_chain_pem),
+            )
+            .field("private_key_pem", &"** redacted **")
+            .finish()
+    }
+}
+
+/// TLS and proxy settings applied to the connectors created by [`HyperClientBuilder`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct ConnectionSettings {
+    trust_store: TrustStore,
+    client_certificate: Option<ClientCertificate>,
+    proxy: ProxyConfig,
+}
+
+impl ConnectionSettings {
+    fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// A bridge that allows our `ResolveDns` trait to work with Hyper's `Resolver` interface (based on tower)
+#[derive(Clone)]
+struct HyperUtilResolver<R> {
+    resolver: R,
+}
+
+impl<R: ResolveDns + Clone + 'static> tower_service::Service<Name> for HyperUtilResolver<R> {
+    type Response = vec::IntoIter<SocketAddr>;
+    type Error = Box<dyn Error + Send + Sync>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Name) -> Self::Future {
+        let resolver = self.resolver.clone();
+        Box::pin(async move {
+            let dns_entries = resolver.resolve_dns(req.as_str()).await?;
+            Ok(interleave_families(dns_entries)
+                .into_iter()
+                .map(|ip_addr| SocketAddr::new(ip_addr, 0))
+                .collect::<Vec<_>>()
+                .into_iter())
+        })
+    }
+}
+
+/// Orders addresses for connection racing as described by [RFC 8305 section 4]: IPv6 first,
+/// alternating between address families, and otherwise keeping the order of the resolver.
+///
+/// [RFC 8305 section 4]: https://www.rfc-editor.org/rfc/rfc8305#section-4
+fn interleave_families(addresses: Vec<IpAddr>) -> Vec<IpAddr> {
+    let (v6, v4): (Vec<_>, Vec<_>) = addresses.into_iter().partition(IpAddr::is_ipv6);
+    let mut v6 = v6.into_iter();
+    let mut v4 = v4.into_iter();
+    let mut interleaved = Vec::with_capacity(v6.len() + v4.len());
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (a, b) => interleaved.extend(a.into_iter().chain(b)),
+        }
+    }
+    interleaved
+}
+
+type HttpsConnector<R> = hyper_rustls_0_27::HttpsConnector<
+    proxy_connector::ProxyConnector<client::connect::HttpConnector<R>>,
+>;
+
+#[allow(unused_imports)]
+mod cached_connectors {
+    use hyper_util::client::legacy::connect::dns::GaiResolver;
+
+    use crate::client::http::hyper_1::build_connector::make_tls;
+    use crate::client::http::hyper_1::{ConnectionSettings, CryptoMode, HttpsConnector, Inner};
+    use aws_smithy_runtime_api::box_error::BoxError;
+
+    // Creating a connector that trusts the native roots is expensive. Cache the connectors that
+    // use the default connection settings so that we don't need to repeatedly incur that cost.
+    #[cfg(feature = "crypto-ring")]
+    pub(crate) static HTTPS_NATIVE_ROOTS_RING: once_cell::sync::Lazy<HttpsConnector<GaiResolver>> =
+        once_cell::sync::Lazy::new(|| default_tls(CryptoMode::Ring));
+
+    #[cfg(feature = "crypto-aws-lc")]
+    pub(crate) static HTTPS_NATIVE_ROOTS_AWS_LC: once_cell::sync::Lazy<
+        HttpsConnector<GaiResolver>,
+    > = once_cell::sync::Lazy::new(|| default_tls(CryptoMode::AwsLc));
+
+    #[cfg(feature = "crypto-aws-lc-fips")]
+    pub(crate) static HTTPS_NATIVE_ROOTS_AWS_LC_FIPS: once_cell::sync::Lazy<
+        HttpsConnector<GaiResolver>,
+    > = once_cell::sync::Lazy::new(|| default_tls(CryptoMode::AwsLcFips));
+
+    #[cfg(any(
+        feature = "crypto-ring",
+        feature = "crypto-aws-lc",
+        feature = "crypto-aws-lc-fips"
+    ))]
+    fn default_tls(mode: CryptoMode) -> HttpsConnector<GaiResolver> {
+        make_tls(
+            GaiResolver::new(),
+            mode.provider(),
+            &ConnectionSettings::default(),
+        )
+        .expect("error with TLS configuration.")
+    }
+
+    pub(super) fn cached_https(
+        mode: Inner,
+        settings: &ConnectionSettings,
+    ) -> Result<HttpsConnector<GaiResolver>, BoxError> {
+        if !settings.is_default() {
+            return make_tls(GaiResolver::new(), mode.provider(), settings);
+        }
+        Ok(match mode {
+            #[cfg(feature = "crypto-ring")]
+            Inner::Standard(CryptoMode::Ring) => HTTPS_NATIVE_ROOTS_RING.clone(),
+            #[cfg(feature = "crypto-aws-lc")]
+            Inner::Standard(CryptoMode::AwsLc) => HTTPS_NATIVE_ROOTS_AWS_LC.clone(),
+            #[cfg(feature = "crypto-aws-lc-fips")]
+            Inner::Standard(CryptoMode::AwsLcFips) => HTTPS_NATIVE_ROOTS_AWS_LC_FIPS.clone(),
+            #[allow(unreachable_patterns)]
+            Inner::Standard(_) => unreachable!("unexpected mode"),
+            Inner::Custom(provider) => make_tls(GaiResolver::new(), provider, settings)?,
+        })
+    }
+}
+
+mod build_connector {
+    use std::sync::Arc;
+
+    use client::connect::HttpConnector;
+    use hyper_util::client::legacy as client;
+    use rustls_0_23::crypto::CryptoProvider;
+
+    use aws_smithy_runtime_api::box_error::BoxError;
+    use aws_smithy_runtime_api::client::dns::ResolveDns;
+
+    use crate::client::http::hyper_1::proxy_connector::ProxyConnector;
+    use crate::client::http::hyper_1::{
+        ConnectionSettings, HttpsConnector, HyperUtilResolver, Inner,
+    };
+    use crate::client::http::HAPPY_EYEBALLS_DELAY;
+
+    fn restrict_ciphers(base: CryptoProvider) -> CryptoProvider {
+        let suites = &[
+            rustls_0_23::CipherSuite::TLS13_AES_256_GCM_SHA384,
+            rustls_0_23::CipherSuite::TLS13_AES_128_GCM_SHA256,
+            // TLS1.2 suites
+            rustls_0_23::CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+            rustls_0_23::CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            rustls_0_23::CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+            rustls_0_23::CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            rustls_0_23::CipherSuite::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+        ];
+        let supported_suites = suites
+            .iter()
+            .flat_map(|suite| {
+                base.cipher_suites
+                    .iter()
+                    .find(|s| &s.suite() == suite)
+                    .cloned()
+            })
+            .collect::<Vec<_>>();
+        CryptoProvider {
+            cipher_suites: supported_suites,
+            ..base
+        }
+    }
+
+    pub(crate) fn make_tls<R: Clone>(
+        resolver: R,
+        crypto_provider: CryptoProvider,
+        settings: &ConnectionSettings,
+    ) -> Result<HttpsConnector<R>, BoxError> {
+        let mut base_connector = HttpConnector::new_with_resolver(resolver);
+        base_connector.enforce_http(false);
+        base_connector.set_happy_eyeballs_timeout(Some(HAPPY_EYEBALLS_DELAY));
+        let tls_config = rustls_0_23::ClientConfig::builder_with_provider(Arc::new(restrict_ciphers(crypto_provider)))
+            .with_safe_default_protocol_versions()
+            .expect("Error with the TLS configuration. Please file a bug report under https://github.com/smithy-lang/smithy-rs/issues.")
+            .with_root_certificates(settings.trust_store.root_cert_store()?);
+        let tls_config = match &settings.client_certificate {
+            Some(client_certificate) => {
+                let (cert_chain, key) = client_certificate.parse()?;
+                tls_config.with_client_auth_cert(cert_chain, key)?
+            }
+            None => tls_config.with_no_client_auth(),
+        };
+        Ok(hyper_rustls_0_27::HttpsConnectorBuilder::new()
+            .with_tls_config(tls_config)
+            .https_or_http()
+            .enable_http1()
+            .enable_http2()
+            .wrap_connector(ProxyConnector::new(base_connector, &settings.proxy)))
+    }
+
+    pub(super) fn https_with_resolver<R: ResolveDns + Clone>(
+        crypto_provider: Inner,
+        resolver: R,
+        settings: &ConnectionSettings,
+    ) -> Result<HttpsConnector<HyperUtilResolver<R>>, BoxError> {
+        make_tls(
+            HyperUtilResolver { resolver },
+            crypto_provider.provider(),
+            settings,
+        )
+    }
+}
+
+mod proxy_connector {
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use http1::{HeaderValue, Uri};
+    use hyper_1_x::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::proxy::Tunnel;
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+
+    use aws_smithy_runtime_api::box_error::BoxError;
+
+    use crate::client::http::proxy::ProxyConfig;
+
+    fn to_http_1x(uri: &http::Uri) -> Uri {
+        uri.to_string()
+            .parse()
+            .expect("proxy URIs are validated by `ProxyConfig`")
+    }
+
+    /// Routes connections through the proxies in a [`ProxyConfig`].
+    ///
+    /// `https` destinations are reached through a `CONNECT` tunnel so that TLS is still negotiated with
+    /// the destination. `http` destinations connect to the proxy directly, and the connection is marked
+    /// as proxied so that hyper sends requests in absolute form. Destinations matching the `NO_PROXY`
+    /// rules are connected to directly.
+    #[derive(Clone, Debug)]
+    pub(crate) struct ProxyConnector<C> {
+        inner: C,
+        config: ProxyConfig,
+        http_proxy: Option<Uri>,
+        https_tunnel: Option<Tunnel<C>>,
+    }
+
+    impl<C: Clone> ProxyConnector<C> {
+        pub(crate) fn new(inner: C, config: &ProxyConfig) -> Self {
+            Self {
+                http_proxy: config.http_proxy().map(to_http_1x),
+                https_tunnel: config.https_proxy().map(|proxy| {
+                    let tunnel = Tunnel::new(to_http_1x(proxy), inner.clone());
+                    match config.authorization(Some("https")) {
+                        Some(auth) => tunnel.with_auth(
+                            HeaderValue::from_str(auth).expect("base64 is a valid header value"),
+                        ),
+                        None => tunnel,
+                    }
+                }),
+                config: config.clone(),
+                inner,
+            }
+        }
+    }
+
+    impl<C> tower_service::Service<Uri> for ProxyConnector<C>
+    where
+        C: tower_service::Service<Uri> + Clone + Send + 'static,
+        C::Response: Read + Write + Connection + Unpin + Send + 'static,
+        C::Future: Send + 'static,
+        C::Error: Into<BoxError>,
+    {
+        type Response = ProxyStream<C::Response>;
+        type Error = BoxError;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx).map_err(Into::into)
+        }
+
+        fn call(&mut self, dst: Uri) -> Self::Future {
+            let bypassed = self.config.is_bypassed(dst.host().unwrap_or_default());
+            match (dst.scheme_str(), &mut self.https_tunnel, &self.http_proxy) {
+                (Some("https"), Some(tunnel), _) if !bypassed => {
+                    let tunnelling = tunnel.call(dst);
+                    Box::pin(async move {
+                        Ok(ProxyStream {
+                            inner: tunnelling.await?,
+                            forwarding: false,
+                        })
+                    })
+                }
+                (Some("http"), _, Some(proxy)) if !bypassed => {
+                    let connecting = self.inner.call(proxy.clone());
+                    Box::pin(async move {
+                        Ok(ProxyStream {
+                            inner: connecting.await.map_err(Into::into)?,
+                            forwarding: true,
+                        })
+                    })
+                }
+                _ => {
+                    let connecting = self.inner.call(dst);
+                    Box::pin(async move {
+                        Ok(ProxyStream {
+                            inner: connecting.await.map_err(Into::into)?,
+                            forwarding: false,
+                        })
+                    })
+                }
+            }
+        }
+    }
+
+    /// A connection that may have been established through a proxy.
+    #[derive(Debug)]
+    pub(crate) struct ProxyStream<T> {
+        inner: T,
+        forwarding: bool,
+    }
+
+    impl<T: Connection> Connection for ProxyStream<T> {
+        fn connected(&self) -> Connected {
+            let connected = self.inner.connected();
+            if self.forwarding {
+                connected.proxy(true)
+            } else {
+                connected
+            }
+        }
+    }
+
+    impl<T: Read + Unpin> Read for ProxyStream<T> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<T: Write + Unpin> Write for ProxyStream<T> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[io::IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        }
+    }
+}
+
+/// [`HttpConnector`] that uses [`hyper`](hyper_1_x) to make HTTP requests.
+///
+/// This connector also implements socket connect and read timeouts, and applies the connection
+/// pool settings of [`HttpConnectorSettings`].
+///
+/// This shouldn't be used directly in most cases.
+/// See the docs on [`HyperClientBuilder`] for examples of how
+/// to customize the Hyper client.
+#[derive(Debug)]
+pub struct HyperConnector {
+    adapter: Box<dyn HttpConnector>,
+    pool: PoolTracker,
+}
+
+impl HyperConnector {
+    /// Builder for a Hyper connector.
+    pub fn builder() -> HyperConnectorBuilder {
+        Default::default()
+    }
+
+    /// Returns a snapshot of the idle and active connections in this connector's pool.
+    pub fn pool_snapshot(&self) -> PoolSnapshot {
+        self.pool.snapshot()
+    }
+}
+
+impl HttpConnector for HyperConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        self.adapter.call(request)
+    }
+}
+
+/// Builder for [`HyperConnector`].
+#[derive(Default, Debug)]
+pub struct HyperConnectorBuilder<Crypto = CryptoUnset> {
+    connector_settings: Option<HttpConnectorSettings>,
+    sleep_impl: Option<SharedAsyncSleep>,
+    client_builder: Option<hyper_util::client::legacy::Builder>,
+    pool: Option<PoolTracker>,
+    proxy: ProxyConfig,
+    resolver: Option<SharedDnsResolver>,
+    #[allow(unused)]
+    crypto: Crypto,
+}
+
+/// Type state for builders that don't have a cryptography provider selected yet.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct CryptoUnset {}
+
+/// Type state for builders that have a cryptography provider selected.
+#[derive(Clone, Debug)]
+pub struct CryptoProviderSelected {
+    crypto_provider: Inner,
+}
+
+#[derive(Clone)]
+enum Inner {
+    Standard(CryptoMode),
+    #[allow(dead_code)]
+    Custom(CryptoProvider),
+}
+
+impl Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Inner::Standard(mode) => f.debug_tuple("Standard").field(mode).finish(),
+            Inner::Custom(_) => f.debug_tuple("Custom").field(&"** provider **").finish(),
+        }
+    }
+}
+
+impl Inner {
+    fn provider(&self) -> CryptoProvider {
+        match self {
+            Inner::Standard(mode) => mode.provider(),
+            Inner::Custom(provider) => provider.clone(),
+        }
+    }
+}
+
+impl HyperConnectorBuilder<CryptoUnset> {
+    /// Selects the cryptography provider used for TLS.
+    pub fn crypto_mode(self, mode: CryptoMode) -> HyperConnectorBuilder<CryptoProviderSelected> {
+        HyperConnectorBuilder {
+            connector_settings: self.connector_settings,
+            sleep_impl: self.sleep_impl,
+            client_builder: self.client_builder,
+            pool: self.pool,
+            proxy: self.proxy,
+            resolver: self.resolver,
+            crypto: CryptoProviderSelected {
+                crypto_provider: Inner::Standard(mode),
+            },
+        }
+    }
+}
+
+impl HyperConnectorBuilder<CryptoProviderSelected> {
+    /// Create a [`HyperConnector`] that uses rustls for TLS, the platform's native root certificates,
+    /// and the given DNS resolver.
+    ///
+    /// Addresses of poisoned connections are reported to the resolver with
+    /// [`report_connection_failure`](ResolveDns::report_connection_failure).
+    pub fn build_from_resolver<R: ResolveDns + Clone + 'static>(
+        self,
+        resolver: R,
+    ) -> HyperConnector {
+        let resolver = SharedDnsResolver::new(resolver);
+        let connector = build_connector::https_with_resolver(
+            self.crypto.crypto_provider.clone(),
+            resolver.clone(),
+            &ConnectionSettings::default(),
+        )
+        .expect("error with TLS configuration.");
+        self.reporting_resolver(Some(resolver)).build(connector)
+    }
+}
+
+impl<Any> HyperConnectorBuilder<Any> {
+    /// Create a [`HyperConnector`] from this builder and a given connector.
+    pub fn build<C>(self, tcp_connector: C) -> HyperConnector
+    where
+        C: Send + Sync + 'static,
+        C: Clone,
+        C: tower_service::Service<http1::Uri>,
+        C::Response: Read + Write + Connection + Send + Sync + Unpin,
+        C: Connect,
+        C::Future: Unpin + Send + 'static,
+        C::Error: Into<BoxError>,
+    {
+        let mut client_builder =
+            self.client_builder
+                .unwrap_or(hyper_util::client::legacy::Builder::new(
+                    TokioExecutor::new(),
+                ));
+        let sleep_impl = self.sleep_impl.or_else(default_async_sleep);
+        let settings = self.connector_settings.unwrap_or_default();
+        let (connect_timeout, read_timeout) = (settings.connect_timeout(), settings.read_timeout());
+        if let Some(max_idle) = settings.pool_max_idle_per_host() {
+            client_builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(idle_timeout) = settings.pool_idle_timeout() {
+            client_builder
+                .pool_timer(TokioTimer::new())
+                .pool_idle_timeout(idle_timeout);
+        }
+        if let Some(interval) = settings.http2_keep_alive_interval() {
+            client_builder
+                .timer(TokioTimer::new())
+                .http2_keep_alive_interval(interval);
+        }
+        if let Some(max_streams) = settings.http2_max_concurrent_streams() {
+            client_builder.http2_initial_max_send_streams(max_streams as usize);
+        }
+
+        let pool = self.pool.unwrap_or_default();
+        let tcp_connector = TrackConnections::new(tcp_connector, pool.clone());
+        let connector = match connect_timeout {
+            Some(duration) => timeout_middleware::ConnectTimeout::new(
+                tcp_connector,
+                sleep_impl
+                    .clone()
+                    .expect("a sleep impl must be provided in order to have a connect timeout"),
+                duration,
+            ),
+            None => timeout_middleware::ConnectTimeout::no_timeout(tcp_connector),
+        };
+        let base = client_builder.build(connector);
+        let read_timeout = match read_timeout {
+            Some(duration) => timeout_middleware::HttpReadTimeout::new(
+                base,
+                sleep_impl.expect("a sleep impl must be provided in order to have a read timeout"),
+                duration,
+            ),
+            None => timeout_middleware::HttpReadTimeout::no_timeout(base),
+        };
+        HyperConnector {
+            adapter: Box::new(Adapter {
+                client: read_timeout,
+                pool: pool.clone(),
+                proxy: self.proxy,
+                resolver: self.resolver,
+            }),
+            pool,
+        }
+    }
+
+    /// Set the async sleep implementation used for timeouts
+    ///
+    /// Calling this is only necessary for testing or to use something other than
+    /// [`default_async_sleep`].
+    pub fn sleep_impl(mut self, sleep_impl: impl AsyncSleep + 'static) -> Self {
+        self.sleep_impl = Some(sleep_impl.into_shared());
+        self
+    }
+
+    /// Set the async sleep implementation used for timeouts
+    ///
+    /// Calling this is only necessary for testing or to use something other than
+    /// [`default_async_sleep`].
+    pub fn set_sleep_impl(&mut self, sleep_impl: Option<SharedAsyncSleep>) -> &mut Self {
+        self.sleep_impl = sleep_impl;
+        self
+    }
+
+    /// Configure the HTTP settings for the `HyperAdapter`
+    pub fn connector_settings(mut self, connector_settings: HttpConnectorSettings) -> Self {
+        self.connector_settings = Some(connector_settings);
+        self
+    }
+
+    /// Configure the HTTP settings for the `HyperAdapter`
+    pub fn set_connector_settings(
+        &mut self,
+        connector_settings: Option<HttpConnectorSettings>,
+    ) -> &mut Self {
+        self.connector_settings = connector_settings;
+        self
+    }
+
+    /// Override the Hyper client [`Builder`](hyper_util::client::legacy::Builder) used to construct this client.
+    ///
+    /// This enables changing settings like forcing HTTP2 and modifying other default client behavior.
+    pub fn hyper_builder(mut self, hyper_builder: hyper_util::client::legacy::Builder) -> Self {
+        self.set_hyper_builder(Some(hyper_builder));
+        self
+    }
+
+    /// Override the Hyper client [`Builder`](hyper_util::client::legacy::Builder) used to construct this client.
+    ///
+    /// This enables changing settings like forcing HTTP2 and modifying other default client behavior.
+    pub fn set_hyper_builder(
+        &mut self,
+        hyper_builder: Option<hyper_util::client::legacy::Builder>,
+    ) -> &mut Self {
+        self.client_builder = hyper_builder;
+        self
+    }
+
+    /// Share a pool tracker with other connectors, so that their connections are reported together.
+    fn pool_tracker(mut self, pool: PoolTracker) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Authenticate requests that the TCP connector forwards to the `http` proxy of `proxy`.
+    fn forwarding_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Report the addresses of poisoned connections to `resolver`.
+    fn reporting_resolver(mut self, resolver: Option<SharedDnsResolver>) -> Self {
+        self.resolver = resolver;
+        self
+    }
+}
+
+/// Adapter to use a Hyper 1.0-based Client as an `HttpConnector`
+///
+/// This adapter also enables TCP `CONNECT` and HTTP `READ` timeouts via [`HyperConnector::builder`].
+struct Adapter<C> {
+    client: timeout_middleware::HttpReadTimeout<
+        hyper_util::client::legacy::Client<
+            timeout_middleware::ConnectTimeout<TrackConnections<C>>,
+            SdkBody,
+        >,
+    >,
+    pool: PoolTracker,
+    proxy: ProxyConfig,
+    resolver: Option<SharedDnsResolver>,
+}
+
+impl<C> fmt::Debug for Adapter<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Adapter")
+            .field("client", &"** hyper client **")
+            .finish()
+    }
+}
+
+/// Extract a smithy connection from a hyper CaptureConnection
+fn extract_smithy_connection(
+    capture_conn: &CaptureConnection,
+    resolver: Option<SharedDnsResolver>,
+) -> Option<ConnectionMetadata> {
+    let capture_conn = capture_conn.clone();
+    if let Some(conn) = capture_conn.clone().connection_metadata().as_ref() {
+        let mut extensions = Extensions::new();
+        conn.get_extras(&mut extensions);
+        let http_info = extensions.get::<HttpInfo>();
+        // The remote address of a proxied connection is the proxy, which the resolver didn't return
+        let failed_address = match (resolver, http_info) {
+            (Some(resolver), Some(info)) if !conn.is_proxied() => {
+                Some((resolver, info.remote_addr().ip()))
+            }
+            _ => None,
+        };
+        let mut builder = ConnectionMetadata::builder()
+            .proxied(conn.is_proxied())
+            .poison_fn(move || {
+                match capture_conn.connection_metadata().as_ref() {
+                    Some(conn) => conn.poison(),
+                    None => tracing::trace!("no connection existed to poison"),
+                }
+                if let Some((resolver, address)) = &failed_address {
+                    resolver.report_connection_failure(*address);
+                }
+            });
+
+        builder
+            .set_local_addr(http_info.map(|info| info.local_addr()))
+            .set_remote_addr(http_info.map(|info| info.remote_addr()));
+
+        let smithy_connection = builder.build();
+
+        Some(smithy_connection)
+    } else {
+        None
+    }
+}
+
+impl<C> HttpConnector for Adapter<C>
+where
+    C: Clone + Send + Sync + 'static,
+    C: tower_service::Service<Uri>,
+    C::Response: Connection + Read + Write + Unpin + 'static,
+    ConnectTimeout<TrackConnections<C>>: Connect,
+    C::Future: Unpin + Send + 'static,
+    C::Error: Into<BoxError>,
+{
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let mut request = match request.try_into_http1x() {
+            Ok(request) => request,
+            Err(err) => {
+                return HttpConnectorFuture::ready(Err(ConnectorError::user(err.into())));
+            }
+        };
+        if let Some(auth) = self.proxy.forwarding_authorization(
+            request.uri().scheme_str(),
+            request.uri().host().unwrap_or_default(),
+        ) {
+            request.headers_mut().insert(
+                http1::header::PROXY_AUTHORIZATION,
+                http1::HeaderValue::from_str(auth).expect("base64 is a valid header value"),
+            );
+        }
+        let capture_connection = capture_connection(&mut request);
+        if let Some(capture_smithy_connection) =
+            request.extensions().get::<CaptureSmithyConnection>()
+        {
+            let resolver = self.resolver.clone();
+            capture_smithy_connection.set_connection_retriever(move || {
+                extract_smithy_connection(&capture_connection, resolver.clone())
+            });
+        }
+        let request_guard = self.pool.request_started(request.uri());
+        let mut client = self.client.clone();
+        let fut = tower_service::Service::call(&mut client, request);
+        HttpConnectorFuture::new(async move {
+            let response = fut
+                .await
+                .map_err(downcast_error)?
+                .map(|body| SdkBody::from_body_1_x(request_guard.track_body(body)));
+            match HttpResponse::try_from(response) {
+                Ok(response) => Ok(response),
+                Err(err) => Err(ConnectorError::other(err.into(), None)),
+            }
+        })
+    }
+}
+
+/// Downcast errors coming out of hyper into an appropriate `ConnectorError`
+fn downcast_error(err: BoxError) -> ConnectorError {
+    // is a `TimedOutError` (from aws_smithy_async::timeout) in the chain? if it is, this is a timeout
+    if find_source::<TimedOutError>(err.as_ref()).is_some() {
+        return ConnectorError::timeout(err);
+    }
+    // is the top of chain error actually already a `ConnectorError`? return that directly
+    let err = match err.downcast::<ConnectorError>() {
+        Ok(connector_error) => return *connector_error,
+        Err(box_error) => box_error,
+    };
+    // generally, the top of chain will probably be a hyper error. Go through a set of hyper specific
+    // error classifications
+    let err = match find_source::<hyper_1_x::Error>(err.as_ref()) {
+        Some(hyper_error) => return to_connector_error(hyper_error)(err),
+        None => err,
+    };
+    // hyper-util reports connection failures without an underlying hyper error. hyper 0.14 reported
+    // them as IO errors, so classify them the same way.
+    if let Some(client_error) = find_source::<client::Error>(err.as_ref()) {
+        if client_error.is_connect() {
+            return ConnectorError::io(err);
+        }
+    }
+
+    // otherwise, we have no idea!
+    ConnectorError::other(err, None)
+}
+
+/// Convert a [`hyper_1_x::Error`] into a [`ConnectorError`]
+fn to_connector_error(err: &hyper_1_x::Error) -> fn(BoxError) -> ConnectorError {
+    if err.is_timeout() || find_source::<HttpTimeoutError>(err).is_some() {
+        return ConnectorError::timeout;
+    }
+    if err.is_user() {
+        return ConnectorError::user;
+    }
+    if err.is_closed() || err.is_canceled() || find_source::<std::io::Error>(err).is_some() {
+        return ConnectorError::io;
+    }
+    // We sometimes receive this from S3: hyper_1_x::Error(IncompleteMessage)
+    if err.is_incomplete_message() {
+        return |err: BoxError| ConnectorError::other(err, Some(ErrorKind::TransientError));
+    }
+
+    if let Some(h2_err) = find_source::<h2_0_4::Error>(err) {
+        if h2_err.is_go_away()
+            || (h2_err.is_reset() && h2_err.reason() == Some(Reason::REFUSED_STREAM))
+        {
+            return ConnectorError::io;
+        }
+    }
+
+    tracing::warn!(err = %DisplayErrorContext(&err), "unrecognized error from Hyper. If this error should be retried, please file an issue.");
+    |err: BoxError| ConnectorError::other(err, None)
+}
+
+fn find_source<'a, E: Error + 'static>(err: &'a (dyn Error + 'static)) -> Option<&'a E> {
+    let mut next = Some(err);
+    while let Some(err) = next {
+        if let Some(matching_err) = err.downcast_ref::<E>() {
+            return Some(matching_err);
+        }
+        next = err.source();
+    }
+    None
+}
+
+// TODO(https://github.com/awslabs/aws-sdk-rust/issues/1090): CacheKey must also include ptr equality to any
+// runtime components that are used—sleep_impl as a base (unless we prohibit overridding sleep impl)
+// If we decide to put a DnsResolver in RuntimeComponents, then we'll need to handle that as well.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct CacheKey {
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+    http2_max_concurrent_streams: Option<u32>,
+}
+
+impl From<&HttpConnectorSettings> for CacheKey {
+    fn from(value: &HttpConnectorSettings) -> Self {
+        Self {
+            connect_timeout: value.connect_timeout(),
+            read_timeout: value.read_timeout(),
+            pool_max_idle_per_host: value.pool_max_idle_per_host(),
+            pool_idle_timeout: value.pool_idle_timeout(),
+            http2_keep_alive_interval: value.http2_keep_alive_interval(),
+            http2_max_concurrent_streams: value.http2_max_concurrent_streams(),
+        }
+    }
+}
+
+/// An error returned for every request made through a connector that couldn't be created,
+/// for example because a custom root certificate failed to parse.
+#[derive(Clone, Debug)]
+struct ConnectorCreationError(Arc<BoxError>);
+
+impl fmt::Display for ConnectorCreationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to create the HTTP connector")
+    }
+}
+
+impl Error for ConnectorCreationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.0.as_ref().as_ref())
+    }
+}
+
+#[derive(Debug)]
+struct FailingConnector(ConnectorCreationError);
+
+impl HttpConnector for FailingConnector {
+    fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+        HttpConnectorFuture::ready(Err(ConnectorError::user(self.0.clone().into())))
+    }
+}
+
+struct HyperClient<F> {
+    connector_cache: RwLock<HashMap<CacheKey, SharedHttpConnector>>,
+    client_builder: hyper_util::client::legacy::Builder,
+    tcp_connector_fn: F,
+    pool: PoolTracker,
+    proxy: ProxyConfig,
+    resolver: Option<SharedDnsResolver>,
+}
+
+impl<F> fmt::Debug for HyperClient<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HyperClient")
+            .field("connector_cache", &self.connector_cache)
+            .field("client_builder", &self.client_builder)
+            .finish()
+    }
+}
+
+impl<C, F> HttpClient for HyperClient<F>
+where
+    F: Fn() -> Result<C, BoxError> + Send + Sync,
+    C: Clone + Send + Sync + 'static,
+    C: tower_service::Service<Uri>,
+    C::Response: Connection + Read + Write + Send + Sync + Unpin + 'static,
+    C::Future: Unpin + Send + 'static,
+    C::Error: Into<BoxError>,
+{
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        let key = CacheKey::from(settings);
+        let mut connector = self.connector_cache.read().unwrap().get(&key).cloned();
+        if connector.is_none() {
+            let mut cache = self.connector_cache.write().unwrap();
+            // Short-circuit if another thread already wrote a connector to the cache for this key
+            if !cache.contains_key(&key) {
+                let mut builder = HyperConnector::builder()
+                    .hyper_builder(self.client_builder.clone())
+                    .connector_settings(settings.clone())
+                    .pool_tracker(self.pool.clone())
+                    .forwarding_proxy(self.proxy.clone())
+                    .reporting_resolver(self.resolver.clone());
+                builder.set_sleep_impl(components.sleep_impl());
+
+                let start = components.time_source().map(|ts| ts.now());
+                let tcp_connector = (self.tcp_connector_fn)();
+                let end = components.time_source().map(|ts| ts.now());
+                if let (Some(start), Some(end)) = (start, end) {
+                    if let Ok(elapsed) = end.duration_since(start) {
+                        tracing::debug!("new TCP connector created in {:?}", elapsed);
+                    }
+                }
+                let connector = match tcp_connector {
+                    Ok(tcp_connector) => SharedHttpConnector::new(builder.build(tcp_connector)),
+                    Err(err) => {
+                        tracing::warn!(err = %DisplayErrorContext(err.as_ref()), "failed to create the TCP connector");
+                        SharedHttpConnector::new(FailingConnector(ConnectorCreationError(
+                            Arc::new(err),
+                        )))
+                    }
+                };
+                cache.insert(key.clone(), connector);
+            }
+            connector = cache.get(&key).cloned();
+        }
+
+        connector.expect("cache populated above")
+    }
+
+    fn validate_base_client_config(
+        &self,
+        _: &RuntimeComponentsBuilder,
+        _: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        // Initialize the TCP connector at this point so that native certs load
+        // at client initialization time instead of upon first request. We do it
+        // here rather than at construction so that it won't run if this is not
+        // the selected HTTP client for the base config (for example, if this was
+        // the default HTTP client, and it was overridden by a later plugin).
+        (self.tcp_connector_fn)().map(|_| ())
+    }
+
+    fn pool_snapshot(&self) -> Option<PoolSnapshot> {
+        Some(self.pool.snapshot())
+    }
+}
+
+/// Builder for a hyper-backed [`HttpClient`] implementation.
+///
+/// This builder can be used to customize the underlying TCP connector used, as well as
+/// hyper client configuration.
+///
+/// # Examples
+///
+/// Construct a Hyper client with the RusTLS TLS implementation and the `aws-lc-rs` crypto provider.
+/// This can be useful when you want to share a Hyper connector between multiple
+/// generated Smithy clients.
+///
+/// ```no_run,ignore
+/// use aws_smithy_runtime::client::http::hyper_1::{CryptoMode, HyperClientBuilder};
+///
+/// let http_client = HyperClientBuilder::new()
+///     .crypto_mode(CryptoMode::AwsLc)
+///     .build_https();
+///
+/// // This connector can then be given to a generated service Config
+/// let config = my_service_client::Config::builder()
+///     .http_client(http_client)
+///     .build();
+/// let client = my_service_client::Client::from_conf(config);
+/// ```
+///
+/// ## Use a proxy, a private certificate authority, and a client certificate
+///
+/// ```no_run,ignore
+/// use aws_smithy_runtime::client::http::hyper_1::{
+///     ClientCertificate, CryptoMode, HyperClientBuilder, TrustStore,
+/// };
+/// use aws_smithy_runtime::client::http::proxy::ProxyConfig;
+///
+/// let http_client = HyperClientBuilder::new()
+///     .crypto_mode(CryptoMode::AwsLc)
+///     .proxy(ProxyConfig::all("http://localhost:3128").unwrap())
+///     .trust_store(TrustStore::empty().with_pem_certificate(std::fs::read("ca.pem").unwrap()))
+///     .client_certificate(ClientCertificate::from_pem(
+///         std::fs::read("client.pem").unwrap(),
+///         std::fs::read("client.key").unwrap(),
+///     ))
+///     .build_https();
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct HyperClientBuilder<Crypto = CryptoUnset> {
+    client_builder: Option<hyper_util::client::legacy::Builder>,
+    connection_settings: ConnectionSettings,
+    crypto_provider: Crypto,
+}
+
+impl HyperClientBuilder<CryptoProviderSelected> {
+    /// Create a hyper client using RusTLS for TLS
+    ///
+    /// The trusted certificates will be loaded later when this becomes the selected
+    /// HTTP client for a Smithy client.
+    pub fn build_https(self) -> SharedHttpClient {
+        let crypto = self.crypto_provider.crypto_provider;
+        let settings = self.connection_settings;
+        build_with_fn(
+            self.client_builder,
+            settings.proxy.clone(),
+            None,
+            move || cached_connectors::cached_https(crypto.clone(), &settings),
+        )
+    }
+
+    /// Create a hyper client using a custom DNS resolver
+    ///
+    /// Addresses of poisoned connections are reported to the resolver with
+    /// [`report_connection_failure`](ResolveDns::report_connection_failure), which lets resolvers
+    /// such as [`CachingDnsResolver`](crate::client::dns::CachingDnsResolver) stop returning them.
+    pub fn build_with_resolver(
+        self,
+        resolver: impl ResolveDns + Clone + 'static,
+    ) -> SharedHttpClient {
+        let settings = self.connection_settings;
+        let resolver = SharedDnsResolver::new(resolver);
+        build_with_fn(
+            self.client_builder,
+            settings.proxy.clone(),
+            Some(resolver.clone()),
+            move || {
+                build_connector::https_with_resolver(
+                    self.crypto_provider.crypto_provider.clone(),
+                    resolver.clone(),
+                    &settings,
+                )
+            },
+        )
+    }
+}
+
+impl HyperClientBuilder<CryptoUnset> {
+    /// Creates a new builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects the cryptography provider used for TLS.
+    pub fn crypto_mode(self, provider: CryptoMode) -> HyperClientBuilder<CryptoProviderSelected> {
+        HyperClientBuilder {
+            client_builder: self.client_builder,
+            connection_settings: self.connection_settings,
+            crypto_provider: CryptoProviderSelected {
+                crypto_provider: Inner::Standard(provider),
+            },
+        }
+    }
+
+    /// This interface will be broken in the future
+    ///
+    /// This exposes `CryptoProvider` from `rustls` directly and this API has no stability guarantee.
+    #[cfg(crypto_unstable)]
+    pub fn crypto_provider_unstable(
+        self,
+        provider: CryptoProvider,
+    ) -> HyperClientBuilder<CryptoProviderSelected> {
+        HyperClientBuilder {
+            client_builder: self.client_builder,
+            connection_settings: self.connection_settings,
+            crypto_provider: CryptoProviderSelected {
+                crypto_provider: Inner::Custom(provider),
+            },
+        }
+    }
+}
+
+impl<Crypto> HyperClientBuilder<Crypto> {
+    /// Override the Hyper client [`Builder`](hyper_util::client::legacy::Builder) used to construct this client.
+    ///
+    /// This enables changing settings like forcing HTTP2 and modifying other default client behavior.
+    pub fn hyper_builder(mut self, hyper_builder: hyper_util::client::legacy::Builder) -> Self {
+        self.client_builder = Some(hyper_builder);
+        self
+    }
+
+    /// Override the Hyper client [`Builder`](hyper_util::client::legacy::Builder) used to construct this client.
+    ///
+    /// This enables changing settings like forcing HTTP2 and modifying other default client behavior.
+    pub fn set_hyper_builder(
+        &mut self,
+        hyper_builder: Option<hyper_util::client::legacy::Builder>,
+    ) -> &mut Self {
+        self.client_builder = hyper_builder;
+        self
+    }
+
+    /// Send requests through the proxies in the given [`ProxyConfig`].
+    ///
+    /// This applies to the clients created by `build_https` and `build_with_resolver`.
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.set_proxy(Some(proxy));
+        self
+    }
+
+    /// Send requests through the proxies in the given [`ProxyConfig`].
+    ///
+    /// This applies to the clients created by `build_https` and `build_with_resolver`.
+    pub fn set_proxy(&mut self, proxy: Option<ProxyConfig>) -> &mut Self {
+        self.connection_settings.proxy = proxy.unwrap_or_default();
+        self
+    }
+
+    /// Override the root certificates that are trusted when verifying servers.
+    ///
+    /// This applies to the clients created by `build_https` and `build_with_resolver`.
+    pub fn trust_store(mut self, trust_store: TrustStore) -> Self {
+        self.set_trust_store(Some(trust_store));
+        self
+    }
+
+    /// Override the root certificates that are trusted when verifying servers.
+    ///
+    /// This applies to the clients created by `build_https` and `build_with_resolver`.
+    pub fn set_trust_store(&mut self, trust_store: Option<TrustStore>) -> &mut Self {
+        self.connection_settings.trust_store = trust_store.unwrap_or_default();
+        self
+    }
+
+    /// Present the given certificate to servers that request TLS client authentication.
+    ///
+    /// This applies to the clients created by `build_https` and `build_with_resolver`.
+    pub fn client_certificate(mut self, client_certificate: ClientCertificate) -> Self {
+        self.set_client_certificate(Some(client_certificate));
+        self
+    }
+
+    /// Present the given certificate to servers that request TLS client authentication.
+    ///
+    /// This applies to the clients created by `build_https` and `build_with_resolver`.
+    pub fn set_client_certificate(
+        &mut self,
+        client_certificate: Option<ClientCertificate>,
+    ) -> &mut Self {
+        self.connection_settings.client_certificate = client_certificate;
+        self
+    }
+
+    /// Create a hyper client with a custom TCP connector.
+    ///
+    /// The connector is responsible for TLS, so the proxy, trust store, and client certificate
+    /// settings of this builder aren't applied.
+    pub fn build<C>(self, tcp_connector: C) -> SharedHttpClient
+    where
+        C: Clone + Send + Sync + 'static,
+        C: tower_service::Service<Uri>,
+        C::Response: Connection + Read + Write + Send + Sync + Unpin + 'static,
+        C::Future: Unpin + Send + 'static,
+        C::Error: Into<BoxError>,
+    {
+        build_with_fn(
+            self.client_builder,
+            ProxyConfig::disabled(),
+            None,
+            move || Ok(tcp_connector.clone()),
+        )
+    }
+}
+
+fn build_with_fn<C, F>(
+    client_builder: Option<hyper_util::client::legacy::Builder>,
+    proxy: ProxyConfig,
+    resolver: Option<SharedDnsResolver>,
+    tcp_connector_fn: F,
+) -> SharedHttpClient
+where
+    F: Fn() -> Result<C, BoxError> + Send + Sync + 'static,
+    C: Clone + Send + Sync + 'static,
+    C: tower_service::Service<Uri>,
+    C::Response: Connection + Read + Write + Send + Sync + Unpin + 'static,
+    C::Future: Unpin + Send + 'static,
+    C::Error: Into<BoxError>,
+{
+    SharedHttpClient::new(HyperClient {
+        connector_cache: RwLock::new(HashMap::new()),
+        client_builder: client_builder
+            .unwrap_or_else(|| hyper_util::client::legacy::Builder::new(TokioExecutor::new())),
+        tcp_connector_fn,
+        pool: PoolTracker::default(),
+        proxy,
+        resolver,
+    })
+}
+
+mod pool_tracking {
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+
+    use http1::Uri;
+    use hyper_1_x::body::{Body, Frame, SizeHint};
+    use hyper_1_x::rt::{Read, ReadBufCursor, Write};
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+
+    use aws_smithy_runtime_api::client::http::{AuthorityPoolStats, PoolSnapshot};
+
+    #[derive(Debug, Default)]
+    struct Counts {
+        open: usize,
+        in_flight: usize,
+    }
+
+    /// Tracks the open connections and in-flight requests of each authority.
+    ///
+    /// Hyper doesn't expose the state of its pool, so a connection is considered active if there
+    /// is a request in flight to its authority, and idle otherwise.
+    #[derive(Clone, Debug, Default)]
+    pub(super) struct PoolTracker {
+        authorities: Arc<Mutex<HashMap<String, Counts>>>,
+    }
+
+    impl PoolTracker {
+        pub(super) fn snapshot(&self) -> PoolSnapshot {
+            let authorities = self.authorities.lock().unwrap();
+            let mut snapshot = PoolSnapshot::new();
+            for (authority, counts) in authorities.iter().filter(|(_, c)| c.open > 0) {
+                let active = counts.in_flight.min(counts.open);
+                snapshot.record(
+                    authority.clone(),
+                    AuthorityPoolStats::new(counts.open - active, active),
+                );
+            }
+            snapshot
+        }
+
+        pub(super) fn request_started(&self, uri: &Uri) -> Guard {
+            self.guard(uri, |counts| &mut counts.in_flight)
+        }
+
+        fn connection_opened(&self, uri: &Uri) -> Guard {
+            self.guard(uri, |counts| &mut counts.open)
+        }
+
+        fn guard(&self, uri: &Uri, count: fn(&mut Counts) -> &mut usize) -> Guard {
+            let authority = uri
+                .authority()
+                .map(|authority| authority.as_str().to_owned())
+                .unwrap_or_default();
+            let mut authorities = self.authorities.lock().unwrap();
+            *count(authorities.entry(authority.clone()).or_default()) += 1;
+            Guard {
+                tracker: self.clone(),
+                authority,
+                count,
+            }
+        }
+    }
+
+    /// Decrements a count of a [`PoolTracker`] when dropped.
+    #[derive(Debug)]
+    pub(super) struct Guard {
+        tracker: PoolTracker,
+        authority: String,
+        count: fn(&mut Counts) -> &mut usize,
+    }
+
+    impl Guard {
+        /// Keeps this guard alive until `body` has been read to the end or dropped.
+        pub(super) fn track_body<B>(self, body: B) -> TrackedBody<B> {
+            TrackedBody {
+                inner: body,
+                guard: Some(self),
+            }
+        }
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            let mut authorities = self.tracker.authorities.lock().unwrap();
+            if let Some(counts) = authorities.get_mut(&self.authority) {
+                *(self.count)(counts) -= 1;
+                if counts.open == 0 && counts.in_flight == 0 {
+                    authorities.remove(&self.authority);
+                }
+            }
+        }
+    }
+
+    /// A response body that holds a request [`Guard`] until it's finished.
+    pub(super) struct TrackedBody<B> {
+        inner: B,
+        guard: Option<Guard>,
+    }
+
+    impl<B: Body + Unpin> Body for TrackedBody<B> {
+        type Data = B::Data;
+        type Error = B::Error;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+            let result = Pin::new(&mut self.inner).poll_frame(cx);
+            if matches!(result, Poll::Ready(None)) || self.inner.is_end_stream() {
+                self.guard = None;
+            }
+            result
+        }
+
+        fn is_end_stream(&self) -> bool {
+            self.inner.is_end_stream()
+        }
+
+        fn size_hint(&self) -> SizeHint {
+            self.inner.size_hint()
+        }
+    }
+
+    /// A connector that counts the connections it opens with a [`PoolTracker`].
+    #[derive(Clone, Debug)]
+    pub(super) struct TrackConnections<C> {
+        inner: C,
+        pool: PoolTracker,
+    }
+
+    impl<C> TrackConnections<C> {
+        pub(super) fn new(inner: C, pool: PoolTracker) -> Self {
+            Self { inner, pool }
+        }
+    }
+
+    impl<C> tower_service::Service<Uri> for TrackConnections<C>
+    where
+        C: tower_service::Service<Uri>,
+        C::Future: Unpin,
+    {
+        type Response = TrackedConnection<C::Response>;
+        type Error = C::Error;
+        type Future = TrackConnectionsFuture<C::Future>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx)
+        }
+
+        fn call(&mut self, uri: Uri) -> Self::Future {
+            TrackConnectionsFuture {
+                pool: self.pool.clone(),
+                inner: self.inner.call(uri.clone()),
+                uri,
+            }
+        }
+    }
+
+    pub(super) struct TrackConnectionsFuture<F> {
+        inner: F,
+        pool: PoolTracker,
+        uri: Uri,
+    }
+
+    impl<F, T, E> Future for TrackConnectionsFuture<F>
+    where
+        F: Future<Output = Result<T, E>> + Unpin,
+    {
+        type Output = Result<TrackedConnection<T>, E>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let this = &mut *self;
+            Pin::new(&mut this.inner)
+                .poll(cx)
+                .map_ok(|inner| TrackedConnection {
+                    inner,
+                    _guard: this.pool.connection_opened(&this.uri),
+                })
+        }
+    }
+
+    /// A connection that is counted by a [`PoolTracker`] until it's closed.
+    #[derive(Debug)]
+    pub(super) struct TrackedConnection<T> {
+        inner: T,
+        _guard: Guard,
+    }
+
+    impl<T: Connection> Connection for TrackedConnection<T> {
+        fn connected(&self) -> Connected {
+            self.inner.connected()
+        }
+    }
+
+    impl<T: Read + Unpin> Read for TrackedConnection<T> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: ReadBufCursor<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<T: Write + Unpin> Write for TrackedConnection<T> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.inner.is_write_vectored()
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[io::IoSlice<'_>],
+        ) -> Poll<Result<usize, io::Error>> {
+            Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+        }
+    }
+}
+
+mod timeout_middleware {
+    use std::error::Error;
+    use std::fmt::Formatter;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+
+    use http1::Uri;
+    use pin_project_lite::pin_project;
+
+    use aws_smithy_async::future::timeout::{TimedOutError, Timeout};
+    use aws_smithy_async::rt::sleep::Sleep;
+    use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep};
+    use aws_smithy_runtime_api::box_error::BoxError;
+
+    #[derive(Debug)]
+    pub(crate) struct HttpTimeoutError {
+        kind: &'static str,
+        duration: Duration,
+    }
+
+    impl std::fmt::Display for HttpTimeoutError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "{} timeout occurred after {:?}",
+                self.kind, self.duration
+            )
+        }
+    }
+
+    impl Error for HttpTimeoutError {
+        // We implement the `source` function as returning a `TimedOutError` because when `downcast_error`
+        // or `find_source` is called with an `HttpTimeoutError` (or another error wrapping an `HttpTimeoutError`)
+        // this method will be checked to determine if it's a timeout-related error.
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&TimedOutError)
+        }
+    }
+
+    /// Timeout wrapper that will timeout on the initial TCP connection
+    ///
+    /// # Stability
+    /// This interface is unstable.
+    #[derive(Clone, Debug)]
+    pub(super) struct ConnectTimeout<I> {
+        inner: I,
+        timeout: Option<(SharedAsyncSleep, Duration)>,
+    }
+
+    impl<I> ConnectTimeout<I> {
+        /// Create a new `ConnectTimeout` around `inner`.
+        ///
+        /// Typically, `I` will implement [`hyper_util::client::legacy::connect::Connect`].
+        pub(crate) fn new(inner: I, sleep: SharedAsyncSleep, timeout: Duration) -> Self {
+            Self {
+                inner,
+                timeout: Some((sleep, timeout)),
+            }
+        }
+
+        pub(crate) fn no_timeout(inner: I) -> Self {
+            Self {
+                inner,
+                timeout: None,
+            }
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub(crate) struct HttpReadTimeout<I> {
+        inner: I,
+        timeout: Option<(SharedAsyncSleep, Duration)>,
+    }
+
+    impl<I> HttpReadTimeout<I> {
+        /// Create a new `HttpReadTimeout` around `inner`.
+        ///
+        /// Typically, `I` will implement [`tower_service::Service<http1::Request<SdkBody>>`].
+        pub(crate) fn new(inner: I, sleep: SharedAsyncSleep, timeout: Duration) -> Self {
+            Self {
+                inner,
+                timeout: Some((sleep, timeout)),
+            }
+        }
+
+        pub(crate) fn no_timeout(inner: I) -> Self {
+            Self {
+                inner,
+                timeout: None,
+            }
+        }
+    }
+
+    pin_project! {
+        /// Timeout future for Tower services
+        ///
+        /// Timeout future to handle timing out, mapping errors, and the possibility of not timing out
+        /// without incurring an additional allocation for each timeout layer.
+        #[project = MaybeTimeoutFutureProj]
+        pub enum MaybeTimeoutFuture<F> {
+            Timeout {
+                #[pin]
+                timeout: Timeout<F, Sleep>,
+                error_type: &'static str,
+                duration: Duration,
+            },
+            NoTimeout {
+                #[pin]
+                future: F
+            }
+        }
+    }
+
+    impl<F, T, E> Future for MaybeTimeoutFuture<F>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Into<BoxError>,
+    {
+        type Output = Result<T, BoxError>;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            let (timeout_future, kind, &mut duration) = match self.project() {
+                MaybeTimeoutFutureProj::NoTimeout { future } => {
+                    return future.poll(cx).map_err(|err| err.into());
+                }
+                MaybeTimeoutFutureProj::Timeout {
+                    timeout,
+                    error_type,
+                    duration,
+                } => (timeout, error_type, duration),
+            };
+            match timeout_future.poll(cx) {
+                Poll::Ready(Ok(response)) => Poll::Ready(response.map_err(|err| err.into())),
+                Poll::Ready(Err(_timeout)) => {
+                    Poll::Ready(Err(HttpTimeoutError { kind, duration }.into()))
+                }
+                Poll::Pending => Poll::Pending,
+            }
+        }
+    }
+
+    impl<I> tower_service::Service<Uri> for ConnectTimeout<I>
+    where
+        I: tower_service::Service<Uri>,
+        I::Error: Into<BoxError>,
+    {
+        type Response = I::Response;
+        type Error = BoxError;
+        type Future = MaybeTimeoutFuture<I::Future>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx).map_err(|err| err.into())
+        }
+
+        fn call(&mut self, req: Uri) -> Self::Future {
+            match &self.timeout {
+                Some((sleep, duration)) => {
+                    let sleep = sleep.sleep(*duration);
+                    MaybeTimeoutFuture::Timeout {
+                        timeout: Timeout::new(self.inner.call(req), sleep),
+                        error_type: "HTTP connect",
+                        duration: *duration,
+                    }
+                }
+                None => MaybeTimeoutFuture::NoTimeout {
+                    future: self.inner.call(req),
+                },
+            }
+        }
+    }
+
+    impl<I, B> tower_service::Service<http1::Request<B>> for HttpReadTimeout<I>
+    where
+        I: tower_service::Service<http1::Request<B>>,
+        I::Error: Send + Sync + Error + 'static,
+    {
+        type Response = I::Response;
+        type Error = BoxError;
+        type Future = MaybeTimeoutFuture<I::Future>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.inner.poll_ready(cx).map_err(|err| err.into())
+        }
+
+        fn call(&mut self, req: http1::Request<B>) -> Self::Future {
+            match &self.timeout {
+                Some((sleep, duration)) => {
+                    let sleep = sleep.sleep(*duration);
+                    MaybeTimeoutFuture::Timeout {
+                        timeout: Timeout::new(self.inner.call(req), sleep),
+                        error_type: "HTTP read",
+                        duration: *duration,
+                    }
+                }
+                None => MaybeTimeoutFuture::NoTimeout {
+                    future: self.inner.call(req),
+                },
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) mod test {
+        use std::time::Duration;
+
+        use hyper_1_x::rt::ReadBufCursor;
+        use hyper_util::client::legacy::connect::Connected;
+        use hyper_util::rt::TokioIo;
+        use tokio::net::TcpStream;
+
+        use aws_smithy_async::assert_elapsed;
+        use aws_smithy_async::future::never::Never;
+        use aws_smithy_async::rt::sleep::{SharedAsyncSleep, TokioSleep};
+        use aws_smithy_types::error::display::DisplayErrorContext;
+
+        use super::super::*;
+
+        #[allow(unused)]
+        fn connect_timeout_is_correct<T: Send + Sync + Clone + 'static>() {
+            is_send_sync::<super::ConnectTimeout<T>>();
+        }
+
+        #[allow(unused)]
+        fn is_send_sync<T: Send + Sync>() {}
+
+        /// A service that will never return whatever it is you want
+        ///
+        /// Returned futures will return Pending forever
+        #[non_exhaustive]
+        #[derive(Clone, Default, Debug)]
+        pub(crate) struct NeverConnects;
+        impl tower_service::Service<Uri> for NeverConnects {
+            type Response = TokioIo<TcpStream>;
+            type Error = ConnectorError;
+            type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, _uri: Uri) -> Self::Future {
+                Box::pin(async move {
+                    Never::new().await;
+                    unreachable!()
+                })
+            }
+        }
+
+        /// A service that will connect but never send any data
+        #[derive(Clone, Debug, Default)]
+        struct NeverReplies;
+        impl tower_service::Service<Uri> for NeverReplies {
+            type Response = EmptyStream;
+            type Error = BoxError;
+            type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, _req: Uri) -> Self::Future {
+                std::future::ready(Ok(EmptyStream))
+            }
+        }
+
+        /// A stream that will never return or accept any data
+        #[non_exhaustive]
+        #[derive(Debug, Default)]
+        struct EmptyStream;
+        impl Read for EmptyStream {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+                _buf: ReadBufCursor<'_>,
+            ) -> Poll<Result<(), std::io::Error>> {
+                Poll::Pending
+            }
+        }
+        impl Write for EmptyStream {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+                _buf: &[u8],
+            ) -> Poll<Result<usize, std::io::Error>> {
+                Poll::Pending
+            }
+
+            fn poll_flush(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+            ) -> Poll<Result<(), std::io::Error>> {
+                Poll::Pending
+            }
+
+            fn poll_shutdown(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+            ) -> Poll<Result<(), std::io::Error>> {
+                Poll::Pending
+            }
+        }
+        impl Connection for EmptyStream {
+            fn connected(&self) -> Connected {
+                Connected::new()
+            }
+        }
+
+        #[tokio::test]
+        async fn http_connect_timeout_works() {
+            let tcp_connector = NeverConnects::default();
+            let connector_settings = HttpConnectorSettings::builder()
+                .connect_timeout(Duration::from_secs(1))
+                .build();
+            let hyper = HyperConnector::builder()
+                .connector_settings(connector_settings)
+                .sleep_impl(SharedAsyncSleep::new(TokioSleep::new()))
+                .build(tcp_connector)
+                .adapter;
+            let now = tokio::time::Instant::now();
+            tokio::time::pause();
+            let resp = hyper
+                .call(HttpRequest::get("https://static-uri.com").unwrap())
+                .await
+                .unwrap_err();
+            assert!(
+                resp.is_timeout(),
+                "expected resp.is_timeout() to be true but it was false, resp == {:?}",
+                resp
+            );
+            let message = DisplayErrorContext(&resp).to_string();
+            let expected =
+                "timeout: client error (Connect): HTTP connect timeout occurred after 1s";
+            assert!(
+                message.contains(expected),
+                "expected '{message}' to contain '{expected}'"
+            );
+            assert_elapsed!(now, Duration::from_secs(1));
+        }
+
+        #[tokio::test]
+        async fn http_read_timeout_works() {
+            let tcp_connector = NeverReplies;
+            let connector_settings = HttpConnectorSettings::builder()
+                .connect_timeout(Duration::from_secs(1))
+                .read_timeout(Duration::from_secs(2))
+                .build();
+            let hyper = HyperConnector::builder()
+                .connector_settings(connector_settings)
+                .sleep_impl(SharedAsyncSleep::new(TokioSleep::new()))
+                .build(tcp_connector)
+                .adapter;
+            let now = tokio::time::Instant::now();
+            tokio::time::pause();
+            let err = hyper
+                .call(HttpRequest::get("https://fake-uri.com").unwrap())
+                .await
+                .unwrap_err();
+            assert!(
+                err.is_timeout(),
+                "expected err.is_timeout() to be true but it was false, err == {err:?}",
+            );
+            let message = format!("{}", DisplayErrorContext(&err));
+            let expected = "timeout: HTTP read timeout occurred after 2s";
+            assert!(
+                message.contains(expected),
+                "expected '{message}' to contain '{expected}'"
+            );
+            assert_elapsed!(now, Duration::from_secs(2));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Error, ErrorKind};
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    use http1::Uri;
+    use hyper_1_x::rt::ReadBufCursor;
+    use hyper_util::client::legacy::connect::Connected;
+
+    use aws_smithy_async::rt::sleep::TokioSleep;
+    use aws_smithy_async::time::SystemTimeSource;
+    use aws_smithy_runtime_api::client::http::AuthorityPoolStats;
+    use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+    use aws_smithy_types::byte_stream::ByteStream;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use crate::client::http::hyper_1::timeout_middleware::test::NeverConnects;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn connector_selection() {
+        // Create a client that increments a count every time it creates a new HyperConnector
+        let creation_count = Arc::new(AtomicU32::new(0));
+        let http_client = build_with_fn(None, ProxyConfig::disabled(), None, {
+            let count = creation_count.clone();
+            move || {
+                count.fetch_add(1, Ordering::Relaxed);
+                Ok(NeverConnects)
+            }
+        });
+
+        // This configuration should result in 4 separate connectors with different timeout settings
+        let settings = [
+            HttpConnectorSettings::builder()
+                .connect_timeout(Duration::from_secs(3))
+                .build(),
+            HttpConnectorSettings::builder()
+                .read_timeout(Duration::from_secs(3))
+                .build(),
+            HttpConnectorSettings::builder()
+                .connect_timeout(Duration::from_secs(3))
+                .read_timeout(Duration::from_secs(3))
+                .build(),
+            HttpConnectorSettings::builder()
+                .connect_timeout(Duration::from_secs(5))
+                .read_timeout(Duration::from_secs(3))
+                .build(),
+        ];
+
+        // Kick off thousands of parallel tasks that will try to create a connector
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(SystemTimeSource::new()))
+            .build()
+            .unwrap();
+        let mut handles = Vec::new();
+        for setting in &settings {
+            for _ in 0..1000 {
+                let client = http_client.clone();
+                handles.push(tokio::spawn({
+                    let setting = setting.clone();
+                    let components = components.clone();
+                    async move {
+                        let _ = client.http_connector(&setting, &components);
+                    }
+                }));
+            }
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        // Verify only 4 connectors were created amidst the chaos
+        assert_eq!(4, creation_count.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn pool_snapshot_reports_idle_and_active_connections() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let authority = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nhello")
+                .await
+                .unwrap();
+            // Keep the connection open so that it stays in the pool
+            let _ = stream.read(&mut request).await;
+        });
+
+        let mut http_connector = client::connect::HttpConnector::new();
+        http_connector.enforce_http(false);
+        let http_client = build_with_fn(None, ProxyConfig::disabled(), None, move || {
+            Ok(http_connector.clone())
+        });
+        let settings = HttpConnectorSettings::builder()
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(Duration::from_secs(30))
+            .http2_keep_alive_interval(Duration::from_secs(10))
+            .http2_max_concurrent_streams(16)
+            .build();
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_sleep_impl(Some(TokioSleep::new()))
+            .with_time_source(Some(SystemTimeSource::new()))
+            .build()
+            .unwrap();
+        let connector = http_client.http_connector(&settings, &components);
+        assert_eq!(Some(PoolSnapshot::new()), http_client.pool_snapshot());
+
+        let response = connector
+            .call(HttpRequest::get(format!("http://{authority}/")).unwrap())
+            .await
+            .unwrap();
+        let snapshot = http_client.pool_snapshot().unwrap();
+        assert_eq!(
+            Some(AuthorityPoolStats::new(0, 1)),
+            snapshot.get(&authority)
+        );
+
+        let body = ByteStream::new(response.into_body())
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(b"hello", &body.into_bytes()[..]);
+        let snapshot = http_client.pool_snapshot().unwrap();
+        assert_eq!(
+            Some(AuthorityPoolStats::new(1, 0)),
+            snapshot.get(&authority)
+        );
+        assert_eq!((1, 0), (snapshot.idle(), snapshot.active()));
+    }
+
+    #[tokio::test]
+    async fn hyper_io_error() {
+        let connector = TestConnection {
+            inner: HangupStream,
+        };
+        let adapter = HyperConnector::builder().build(connector).adapter;
+        let err = adapter
+            .call(HttpRequest::get("https://socket-hangup.com").unwrap())
+            .await
+            .expect_err("socket hangup");
+        assert!(err.is_io(), "unexpected error type: {:?}", err);
+    }
+
+    #[test]
+    fn address_families_are_interleaved() {
+        let ips = |addresses: &[&str]| -> Vec<IpAddr> {
+            addresses.iter().map(|a| a.parse().unwrap()).collect()
+        };
+        assert_eq!(
+            ips(&["::1", "10.0.0.1", "::2", "10.0.0.2", "10.0.0.3"]),
+            interleave_families(ips(&["10.0.0.1", "10.0.0.2", "::1", "10.0.0.3", "::2"]))
+        );
+        assert_eq!(
+            ips(&["10.0.0.1", "10.0.0.2"]),
+            interleave_families(ips(&["10.0.0.1", "10.0.0.2"]))
+        );
+        assert!(interleave_families(Vec::new()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn poisoned_connections_are_reported_to_the_resolver() {
+        #[derive(Clone, Debug, Default)]
+        struct ReportingResolver(Arc<std::sync::Mutex<Vec<IpAddr>>>);
+
+        impl ResolveDns for ReportingResolver {
+            fn resolve_dns<'a>(
+                &'a self,
+                _name: &'a str,
+            ) -> aws_smithy_runtime_api::client::dns::DnsFuture<'a> {
+                unreachable!("the TCP connector resolves names itself")
+            }
+
+            fn report_connection_failure(&self, address: IpAddr) {
+                self.0.lock().unwrap().push(address);
+            }
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let authority = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let resolver = ReportingResolver::default();
+        let http_client = build_with_fn(
+            None,
+            ProxyConfig::disabled(),
+            Some(SharedDnsResolver::new(resolver.clone())),
+            move || Ok(client::connect::HttpConnector::new()),
+        );
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(SystemTimeSource::new()))
+            .build()
+            .unwrap();
+        let connector = http_client.http_connector(&HttpConnectorSettings::default(), &components);
+        let capture = CaptureSmithyConnection::new();
+        let mut request = HttpRequest::get(format!("http://{authority}/")).unwrap();
+        request.add_extension(capture.clone());
+        connector.call(request).await.unwrap();
+        assert!(resolver.0.lock().unwrap().is_empty());
+
+        capture.get().expect("a connection was made").poison();
+        assert_eq!(
+            vec!["127.0.0.1".parse::<IpAddr>().unwrap()],
+            *resolver.0.lock().unwrap()
+        );
+    }
+
+    // ---- machinery to make a Hyper connector that responds with an IO Error
+    #[derive(Clone)]
+    struct HangupStream;
+
+    impl Connection for HangupStream {
+        fn connected(&self) -> Connected {
+            Connected::new()
+        }
+    }
+
+    impl Read for HangupStream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: ReadBufCursor<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Err(Error::new(
+                ErrorKind::ConnectionReset,
+                "connection reset",
+            )))
+        }
+    }
+
+    impl Write for HangupStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &[u8],
+        ) -> Poll<Result<usize, Error>> {
+            Poll::Pending
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+            Poll::Pending
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+            Poll::Pending
+        }
+    }
+
+    #[derive(Clone)]
+    struct TestConnection<T> {
+        inner: T,
+    }
+
+    impl<T> tower_service::Service<Uri> for TestConnection<T>
+    where
+        T: Clone + Connection,
+    {
+        type Response = T;
+        type Error = BoxError;
+        type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _req: Uri) -> Self::Future {
+            std::future::ready(Ok(self.inner.clone()))
+        }
+    }
+}
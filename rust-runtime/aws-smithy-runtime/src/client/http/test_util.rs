@@ -27,6 +27,10 @@
 //! - [`infallible_client_fn`]: Allows you to create a client from an infallible function
 //! that takes a request and returns a response.
 //! - [`NeverClient`]: Useful for testing timeouts, where you want the client to never respond.
+//! - [`MockServer`](mock::MockServer): If you want responses to be picked by matching requests
+//! against routes, for example to simulate stateful services, latency, or faults such as connection
+//! resets, then the mock server will be useful. At the end of the test, it panics if a request
+//! didn't match a route or if a route wasn't called as expected.
 //!
 #![cfg_attr(
     feature = "connector-hyper-0-14-x",
@@ -42,6 +46,8 @@ pub use capture_request::{capture_request, CaptureRequestHandler, CaptureRequest
 #[cfg(feature = "connector-hyper-0-14-x")]
 pub mod dvr;
 
+pub mod mock;
+
 mod replay;
 pub use replay::{ReplayEvent, StaticReplayClient};
 
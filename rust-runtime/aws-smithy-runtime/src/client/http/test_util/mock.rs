@@ -0,0 +1,860 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! A programmable, in-process fake HTTP server.
+//!
+//! Unlike [`StaticReplayClient`](super::StaticReplayClient), which responds to requests in a fixed
+//! order, [`MockServer`] picks the response for each request by matching it against a set of
+//! [`MockRoute`]s. Routes can match on the method, path, query parameters, headers and body of a
+//! request, can be limited to a state of a named scenario, and can respond with a delay or a fault
+//! such as a connection reset, a truncated body, or a body that is dripped out slowly.
+//!
+//! When the last clone of a `MockServer` is dropped, it panics if any request didn't match a route,
+//! or if any route wasn't called as many times as it expected to be. [`MockServer::verify`] can be
+//! used to check this earlier.
+//!
+//! Delays are implemented with the [`AsyncSleep`](aws_smithy_async::rt::sleep::AsyncSleep)
+//! implementation in the client's runtime components, so they can be tested without waiting, e.g.
+//! with a paused Tokio runtime or [`instant_time_and_sleep`](aws_smithy_async::test_util::instant_time_and_sleep).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use aws_smithy_runtime::client::http::test_util::mock::{MockResponse, MockRoute, MockServer};
+//! use std::time::Duration;
+//!
+//! let server = MockServer::new();
+//! server.route(
+//!     MockRoute::get("/widgets/1")
+//!         .header("accept", "application/json")
+//!         .times(1)
+//!         .respond_with(MockResponse::new(503).delay(Duration::from_millis(200))),
+//! );
+//! server.route(
+//!     MockRoute::get("/widgets/1")
+//!         .respond_with(MockResponse::new(200).body(r#"{"id":1}"#)),
+//! );
+//!
+//! # /*
+//! let config = my_generated_client::Config::builder()
+//!     .http_client(server.clone())
+//!     .build();
+//! let client = my_generated_client::Client::from_conf(config);
+//! # */
+//!
+//! // Do stuff with the client...
+//!
+//! // Panic if anything unexpected happened. This is also done when the server is dropped.
+//! server.verify();
+//! ```
+
+use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep, Sleep};
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::http::Headers;
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::body::SdkBody;
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The state every scenario starts in.
+pub const STARTED: &str = "Started";
+
+type BodyPredicate = Arc<dyn Fn(&[u8]) -> bool + Send + Sync>;
+type RequestPredicate = Arc<dyn Fn(&HttpRequest) -> bool + Send + Sync>;
+
+/// How many times a [`MockRoute`] is expected to be called.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Times {
+    AtLeastOnce,
+    Exactly(usize),
+    Any,
+}
+
+/// A route of a [`MockServer`]: a set of conditions a request must meet, and the response to give
+/// to requests that meet them.
+///
+/// All the conditions of a route must be met for a request to match it.
+#[derive(Clone)]
+pub struct MockRoute {
+    method: Option<String>,
+    path: Option<String>,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: Vec<BodyPredicate>,
+    predicates: Vec<RequestPredicate>,
+    scenario: Option<(String, String)>,
+    next_state: Option<String>,
+    times: Times,
+    response: MockResponse,
+}
+
+impl fmt::Debug for MockRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockRoute")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .field("query", &self.query)
+            .field("headers", &self.headers)
+            .field("body_predicates", &self.body.len())
+            .field("predicates", &self.predicates.len())
+            .field("scenario", &self.scenario)
+            .field("next_state", &self.next_state)
+            .field("times", &self.times)
+            .field("response", &self.response)
+            .finish()
+    }
+}
+
+impl Default for MockRoute {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockRoute {
+    /// Creates a route that matches every request, and responds with an empty `200 OK`.
+    ///
+    /// Unless [`times`](MockRoute::times) or [`optional`](MockRoute::optional) is used, the route
+    /// is expected to be called at least once.
+    pub fn new() -> Self {
+        Self {
+            method: None,
+            path: None,
+            query: Vec::new(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            predicates: Vec::new(),
+            scenario: None,
+            next_state: None,
+            times: Times::AtLeastOnce,
+            response: MockResponse::new(200),
+        }
+    }
+
+    /// Creates a route that matches `GET` requests to `path`.
+    pub fn get(path: impl Into<String>) -> Self {
+        Self::new().method("GET").path(path)
+    }
+
+    /// Creates a route that matches `PUT` requests to `path`.
+    pub fn put(path: impl Into<String>) -> Self {
+        Self::new().method("PUT").path(path)
+    }
+
+    /// Creates a route that matches `POST` requests to `path`.
+    pub fn post(path: impl Into<String>) -> Self {
+        Self::new().method("POST").path(path)
+    }
+
+    /// Creates a route that matches `DELETE` requests to `path`.
+    pub fn delete(path: impl Into<String>) -> Self {
+        Self::new().method("DELETE").path(path)
+    }
+
+    /// Only match requests with the given method.
+    pub fn method(mut self, method: impl Into<String>) -> Self {
+        self.method = Some(method.into().to_ascii_uppercase());
+        self
+    }
+
+    /// Only match requests to the given path, not including the query string.
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Only match requests with a query parameter `name` with the value `value`.
+    pub fn query_param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((name.into(), value.into()));
+        self
+    }
+
+    /// Only match requests with a header `name` with the value `value`.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Only match requests whose body is exactly `body`.
+    pub fn body(self, body: impl Into<Bytes>) -> Self {
+        let expected = body.into();
+        self.body_matches(move |actual| actual == expected)
+    }
+
+    /// Only match requests whose body satisfies `predicate`.
+    ///
+    /// Streaming bodies are given to the predicate as an empty slice.
+    pub fn body_matches(
+        mut self,
+        predicate: impl Fn(&[u8]) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.body.push(Arc::new(predicate));
+        self
+    }
+
+    /// Only match requests that satisfy `predicate`.
+    pub fn matches(
+        mut self,
+        predicate: impl Fn(&HttpRequest) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.predicates.push(Arc::new(predicate));
+        self
+    }
+
+    /// Only match requests while the scenario named `scenario` is in the state `state`.
+    ///
+    /// Every scenario starts in the [`STARTED`] state.
+    pub fn in_scenario(mut self, scenario: impl Into<String>, state: impl Into<String>) -> Self {
+        self.scenario = Some((scenario.into(), state.into()));
+        self
+    }
+
+    /// Moves the scenario of this route to the state `state` when the route is called.
+    ///
+    /// This has no effect unless the route is part of a scenario; see [`in_scenario`](MockRoute::in_scenario).
+    pub fn set_scenario_state(mut self, state: impl Into<String>) -> Self {
+        self.next_state = Some(state.into());
+        self
+    }
+
+    /// Expect this route to be called exactly `times` times.
+    ///
+    /// Once it has been called `times` times, the route no longer matches requests, which makes
+    /// it possible to give different responses to the same request by adding several routes.
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Times::Exactly(times);
+        self
+    }
+
+    /// Don't expect this route to be called.
+    pub fn optional(mut self) -> Self {
+        self.times = Times::Any;
+        self
+    }
+
+    /// Responds to matching requests with `response`.
+    pub fn respond_with(mut self, response: MockResponse) -> Self {
+        self.response = response;
+        self
+    }
+
+    fn matches_request(&self, request: &HttpRequest) -> bool {
+        let uri = request.uri();
+        let (path, query) = uri.split_once('?').unwrap_or((uri, ""));
+        let path = path
+            .find("://")
+            .and_then(|scheme_end| {
+                let rest = &path[scheme_end + 3..];
+                rest.find('/').map(|path_start| &rest[path_start..])
+            })
+            .unwrap_or(if path.contains("://") { "/" } else { path });
+        let query_params: Vec<(&str, &str)> = query
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .map(|param| param.split_once('=').unwrap_or((param, "")))
+            .collect();
+
+        self.method.iter().all(|method| method == request.method())
+            && self.path.iter().all(|expected| expected == path)
+            && self
+                .query
+                .iter()
+                .all(|(name, value)| query_params.iter().any(|(n, v)| n == name && v == value))
+            && self.headers.iter().all(|(name, value)| {
+                request
+                    .headers()
+                    .get_all(name.as_str())
+                    .any(|actual| actual == value)
+            })
+            && self
+                .body
+                .iter()
+                .all(|predicate| predicate(request.body().bytes().unwrap_or(&[])))
+            && self.predicates.iter().all(|predicate| predicate(request))
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} {}",
+            self.method.as_deref().unwrap_or("*"),
+            self.path.as_deref().unwrap_or("*")
+        )
+    }
+}
+
+/// A fault to inject into a response.
+#[derive(Clone, Debug)]
+enum Fault {
+    /// Fail the request as if the connection was reset before a response was received.
+    ConnectionReset,
+    /// Send the first `n` bytes of the body, then fail as if the connection was reset.
+    TruncateBody(usize),
+    /// Send the body in chunks of `chunk_size` bytes, waiting `interval` before each chunk.
+    Drip {
+        chunk_size: usize,
+        interval: Duration,
+    },
+}
+
+/// The response a [`MockRoute`] responds with.
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Bytes,
+    delay: Option<Duration>,
+    fault: Option<Fault>,
+}
+
+impl MockResponse {
+    /// Creates a response with the given status code and an empty body.
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Bytes::new(),
+            delay: None,
+            fault: None,
+        }
+    }
+
+    /// Creates a response that fails as if the connection was reset before a response was received.
+    pub fn connection_reset() -> Self {
+        Self {
+            fault: Some(Fault::ConnectionReset),
+            ..Self::new(200)
+        }
+    }
+
+    /// Adds a header to the response.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the body of the response.
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Waits for `delay` before responding.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Sends only the first `len` bytes of the body, and then fails as if the connection was reset.
+    pub fn truncate_body(mut self, len: usize) -> Self {
+        self.fault = Some(Fault::TruncateBody(len));
+        self
+    }
+
+    /// Sends the body in chunks of `chunk_size` bytes, waiting `interval` before each chunk.
+    pub fn drip(mut self, chunk_size: usize, interval: Duration) -> Self {
+        self.fault = Some(Fault::Drip {
+            chunk_size: chunk_size.max(1),
+            interval,
+        });
+        self
+    }
+
+    fn into_response(
+        self,
+        sleep_impl: Option<SharedAsyncSleep>,
+    ) -> Result<HttpResponse, ConnectorError> {
+        let body = match self.fault {
+            Some(Fault::ConnectionReset) => {
+                return Err(ConnectorError::io(
+                    "MockServer: connection reset by peer".into(),
+                ))
+            }
+            Some(Fault::TruncateBody(len)) => {
+                let len = len.min(self.body.len());
+                SdkBody::from_body_0_4(FaultyBody {
+                    chunks: VecDeque::from([self.body.slice(..len)]),
+                    interval: None,
+                    sleep_impl: None,
+                    sleep: None,
+                    fail_at_end: true,
+                })
+            }
+            Some(Fault::Drip {
+                chunk_size,
+                interval,
+            }) => {
+                let sleep_impl = sleep_impl.ok_or_else(|| {
+                    ConnectorError::other(
+                        "MockServer: dripping a body requires an async sleep implementation".into(),
+                        None,
+                    )
+                })?;
+                SdkBody::from_body_0_4(FaultyBody {
+                    chunks: self
+                        .body
+                        .chunks(chunk_size)
+                        .map(|chunk| self.body.slice_ref(chunk))
+                        .collect(),
+                    interval: Some(interval),
+                    sleep_impl: Some(sleep_impl),
+                    sleep: None,
+                    fail_at_end: false,
+                })
+            }
+            None => SdkBody::from(self.body),
+        };
+        let status = self.status.try_into().map_err(|err| {
+            ConnectorError::other(
+                format!("MockServer: invalid status code {}: {err}", self.status).into(),
+                None,
+            )
+        })?;
+        let mut response = HttpResponse::new(status, body);
+        let headers: &mut Headers = response.headers_mut();
+        for (name, value) in self.headers {
+            headers.append(name, value);
+        }
+        Ok(response)
+    }
+}
+
+/// A response body that is sent in chunks, and can fail after the last chunk.
+struct FaultyBody {
+    chunks: VecDeque<Bytes>,
+    interval: Option<Duration>,
+    sleep_impl: Option<SharedAsyncSleep>,
+    sleep: Option<Sleep>,
+    fail_at_end: bool,
+}
+
+impl http_body_0_4::Body for FaultyBody {
+    type Data = Bytes;
+    type Error = ConnectorError;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.get_mut();
+        if let (Some(interval), Some(sleep_impl)) = (this.interval, &this.sleep_impl) {
+            if !this.chunks.is_empty() {
+                let sleep = this.sleep.get_or_insert_with(|| sleep_impl.sleep(interval));
+                if Pin::new(sleep).poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                this.sleep = None;
+            }
+        }
+        match this.chunks.pop_front() {
+            Some(chunk) => Poll::Ready(Some(Ok(chunk))),
+            None if this.fail_at_end => {
+                this.fail_at_end = false;
+                Poll::Ready(Some(Err(ConnectorError::io(
+                    "MockServer: connection reset by peer while sending the body".into(),
+                ))))
+            }
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<http::HeaderMap>, Self::Error>> {
+        Poll::Ready(Ok(None))
+    }
+}
+
+#[derive(Debug)]
+struct RouteState {
+    route: MockRoute,
+    calls: usize,
+}
+
+impl RouteState {
+    fn is_exhausted(&self) -> bool {
+        matches!(self.route.times, Times::Exactly(times) if self.calls >= times)
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    routes: Vec<RouteState>,
+    scenarios: HashMap<String, String>,
+    requests: Vec<HttpRequest>,
+    unmatched: Vec<String>,
+}
+
+impl State {
+    fn respond(&mut self, request: &HttpRequest) -> Option<MockResponse> {
+        let scenarios = &self.scenarios;
+        let route = self.routes.iter_mut().find(|state| {
+            !state.is_exhausted()
+                && state.route.scenario.iter().all(|(name, state)| {
+                    scenarios.get(name).map_or(STARTED, String::as_str) == state
+                })
+                && state.route.matches_request(request)
+        })?;
+        route.calls += 1;
+        if let (Some((scenario, _)), Some(next_state)) =
+            (&route.route.scenario, &route.route.next_state)
+        {
+            self.scenarios.insert(scenario.clone(), next_state.clone());
+        }
+        Some(route.route.response.clone())
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .unmatched
+            .iter()
+            .map(|request| format!("no route matched the request {request}"))
+            .collect();
+        for state in &self.routes {
+            let expected = match state.route.times {
+                Times::AtLeastOnce if state.calls == 0 => "at least once",
+                Times::Exactly(times) if state.calls != times => {
+                    problems.push(format!(
+                        "the route {} was expected to be called {times} time(s), but was called {} time(s)",
+                        state.route.describe(),
+                        state.calls
+                    ));
+                    continue;
+                }
+                _ => continue,
+            };
+            problems.push(format!(
+                "the route {} was expected to be called {expected}, but was never called",
+                state.route.describe()
+            ));
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    state: Mutex<State>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            return;
+        }
+        let problems = self.state.get_mut().unwrap().problems();
+        assert!(
+            problems.is_empty(),
+            "MockServer expectations weren't met:\n- {}",
+            problems.join("\n- ")
+        );
+    }
+}
+
+/// A programmable, in-process fake HTTP server. See the [module docs](self) for more information.
+///
+/// `MockServer` implements [`HttpClient`], so it can be given to a client in place of a real HTTP
+/// client. Clones share the same routes and recorded requests.
+#[derive(Clone, Debug, Default)]
+pub struct MockServer {
+    inner: Arc<Inner>,
+}
+
+impl MockServer {
+    /// Creates a server without any routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a route to the server.
+    ///
+    /// Requests are matched against routes in the order they were added, and the first route that
+    /// matches gives the response.
+    pub fn route(&self, route: MockRoute) -> &Self {
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .routes
+            .push(RouteState { route, calls: 0 });
+        self
+    }
+
+    /// Returns the requests the server received, in the order they were received.
+    ///
+    /// Request bodies are only included if they could be read without streaming.
+    pub fn received_requests(&self) -> Vec<HttpRequest> {
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .map(|request| {
+                request
+                    .try_clone()
+                    .expect("bodies are replaced when recorded")
+            })
+            .collect()
+    }
+
+    /// Returns the current state of the scenario named `scenario`.
+    pub fn scenario_state(&self, scenario: &str) -> String {
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .scenarios
+            .get(scenario)
+            .cloned()
+            .unwrap_or_else(|| STARTED.to_owned())
+    }
+
+    /// Panics if a request didn't match any route, or if a route wasn't called as many times as it
+    /// expected to be.
+    #[track_caller]
+    pub fn verify(&self) {
+        let problems = self.inner.state.lock().unwrap().problems();
+        assert!(
+            problems.is_empty(),
+            "MockServer expectations weren't met:\n- {}",
+            problems.join("\n- ")
+        );
+    }
+
+    fn respond(&self, request: HttpRequest) -> (Option<MockResponse>, String) {
+        let description = format!("{} {}", request.method(), request.uri());
+        let mut state = self.inner.state.lock().unwrap();
+        let response = state.respond(&request);
+        if response.is_none() {
+            state.unmatched.push(description.clone());
+        }
+        let mut recorded = request;
+        let body = recorded.body().bytes().map(Bytes::copy_from_slice);
+        *recorded.body_mut() = body.map(SdkBody::from).unwrap_or_else(SdkBody::empty);
+        state.requests.push(recorded);
+        (response, description)
+    }
+}
+
+#[derive(Debug)]
+struct MockConnector {
+    server: MockServer,
+    sleep_impl: Option<SharedAsyncSleep>,
+}
+
+impl HttpConnector for MockConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let (response, description) = self.server.respond(request);
+        let Some(response) = response else {
+            return HttpConnectorFuture::ready(Err(ConnectorError::other(
+                format!("MockServer: no route matched the request {description}").into(),
+                None,
+            )));
+        };
+        let sleep_impl = self.sleep_impl.clone();
+        HttpConnectorFuture::new(async move {
+            if let Some(delay) = response.delay {
+                let sleep_impl = sleep_impl.as_ref().ok_or_else(|| {
+                    ConnectorError::other(
+                        "MockServer: delaying a response requires an async sleep implementation"
+                            .into(),
+                        None,
+                    )
+                })?;
+                sleep_impl.sleep(delay).await;
+            }
+            response.into_response(sleep_impl)
+        })
+    }
+}
+
+impl HttpClient for MockServer {
+    fn http_connector(
+        &self,
+        _: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        MockConnector {
+            server: self.clone(),
+            sleep_impl: components.sleep_impl(),
+        }
+        .into_shared()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_smithy_async::rt::sleep::TokioSleep;
+    use aws_smithy_types::byte_stream::ByteStream;
+
+    fn connector(server: &MockServer) -> MockConnector {
+        MockConnector {
+            server: server.clone(),
+            sleep_impl: Some(SharedAsyncSleep::new(TokioSleep::new())),
+        }
+    }
+
+    fn request(method: &str, uri: &str) -> HttpRequest {
+        http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(SdkBody::empty())
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    async fn body(response: HttpResponse) -> Result<Bytes, String> {
+        ByteStream::new(response.into_body())
+            .collect()
+            .await
+            .map(|body| body.into_bytes())
+            .map_err(|err| format!("{err:?}"))
+    }
+
+    #[tokio::test]
+    async fn routes_on_method_path_query_headers_and_body() {
+        let server = MockServer::new();
+        server.route(
+            MockRoute::post("/widgets")
+                .query_param("dryRun", "true")
+                .header("content-type", "application/json")
+                .body(r#"{"name":"a"}"#)
+                .respond_with(MockResponse::new(201).header("x-id", "1").body("created")),
+        );
+        server.route(MockRoute::get("/widgets").respond_with(MockResponse::new(200).body("list")));
+        let connector = connector(&server);
+
+        let response = connector
+            .call(request("GET", "https://example.com/widgets"))
+            .await
+            .unwrap();
+        assert_eq!(200, response.status().as_u16());
+        assert_eq!("list", body(response).await.unwrap());
+
+        let mut post = request("POST", "https://example.com/widgets?dryRun=true&x=1");
+        post.headers_mut()
+            .insert("content-type", "application/json");
+        *post.body_mut() = SdkBody::from(r#"{"name":"a"}"#);
+        let response = connector.call(post).await.unwrap();
+        assert_eq!(201, response.status().as_u16());
+        assert_eq!(Some("1"), response.headers().get("x-id"));
+        assert_eq!("created", body(response).await.unwrap());
+
+        assert_eq!(2, server.received_requests().len());
+        server.verify();
+    }
+
+    #[tokio::test]
+    async fn scenarios_and_limited_routes_change_responses() {
+        let server = MockServer::new();
+        server.route(
+            MockRoute::get("/job")
+                .in_scenario("job", STARTED)
+                .set_scenario_state("done")
+                .respond_with(MockResponse::new(202)),
+        );
+        server.route(
+            MockRoute::get("/job")
+                .in_scenario("job", "done")
+                .times(1)
+                .respond_with(MockResponse::new(200)),
+        );
+        server.route(
+            MockRoute::get("/job")
+                .optional()
+                .respond_with(MockResponse::new(404)),
+        );
+        let connector = connector(&server);
+
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            let response = connector
+                .call(request("GET", "http://localhost/job"))
+                .await
+                .unwrap();
+            statuses.push(response.status().as_u16());
+        }
+        assert_eq!(vec![202, 200, 404], statuses);
+        assert_eq!("done", server.scenario_state("job"));
+        server.verify();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn faults_and_latency() {
+        let server = MockServer::new();
+        server.route(MockRoute::get("/reset").respond_with(MockResponse::connection_reset()));
+        server.route(
+            MockRoute::get("/truncated")
+                .respond_with(MockResponse::new(200).body("hello world").truncate_body(5)),
+        );
+        server.route(
+            MockRoute::get("/slow").respond_with(
+                MockResponse::new(200)
+                    .body("hello")
+                    .delay(Duration::from_secs(5))
+                    .drip(2, Duration::from_secs(1)),
+            ),
+        );
+        let connector = connector(&server);
+
+        let err = connector
+            .call(request("GET", "http://localhost/reset"))
+            .await
+            .unwrap_err();
+        assert!(err.is_io());
+
+        let response = connector
+            .call(request("GET", "http://localhost/truncated"))
+            .await
+            .unwrap();
+        assert!(body(response)
+            .await
+            .unwrap_err()
+            .contains("connection reset"));
+
+        let start = tokio::time::Instant::now();
+        let response = connector
+            .call(request("GET", "http://localhost/slow"))
+            .await
+            .unwrap();
+        assert_eq!(Duration::from_secs(5), start.elapsed());
+        assert_eq!("hello", body(response).await.unwrap());
+        assert_eq!(Duration::from_secs(8), start.elapsed());
+        server.verify();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "no route matched the request GET http://localhost/missing")]
+    async fn unmatched_requests_fail_verification_on_drop() {
+        let server = MockServer::new();
+        let err = connector(&server)
+            .call(request("GET", "http://localhost/missing"))
+            .await
+            .unwrap_err();
+        assert!(err.is_other());
+    }
+
+    #[test]
+    #[should_panic(expected = "the route GET /unused was expected to be called at least once")]
+    fn unused_routes_fail_verification() {
+        let server = MockServer::new();
+        server.route(MockRoute::get("/unused"));
+        server.verify();
+    }
+}
@@ -195,6 +195,7 @@ impl ReplayedEvent {
 #[derive(Debug)]
 pub struct WireMockServer {
     event_log: Arc<Mutex<Vec<RecordedEvent>>>,
+    request_log: Arc<Mutex<Vec<Request<()>>>>,
     bind_addr: SocketAddr,
     // when the sender is dropped, that stops the server
     shutdown_hook: oneshot::Sender<()>,
@@ -211,16 +212,20 @@ impl WireMockServer {
         let handler_events = response_events;
         let wire_events = Arc::new(Mutex::new(vec![]));
         let wire_log_for_service = wire_events.clone();
+        let request_log: Arc<Mutex<Vec<Request<()>>>> = Default::default();
+        let request_log_for_service = request_log.clone();
         let poisoned_conns: Arc<Mutex<HashSet<SocketAddr>>> = Default::default();
         let make_service = make_service_fn(move |connection: &AddrStream| {
             let poisoned_conns = poisoned_conns.clone();
             let events = handler_events.clone();
             let wire_log = wire_log_for_service.clone();
+            let request_log = request_log_for_service.clone();
             let remote_addr = connection.remote_addr();
             tracing::info!("established connection: {:?}", connection);
             wire_log.lock().unwrap().push(RecordedEvent::NewConnection);
             async move {
-                Ok::<_, Infallible>(service_fn(move |_: Request<hyper_0_14::Body>| {
+                Ok::<_, Infallible>(service_fn(move |request: Request<hyper_0_14::Body>| {
+                    request_log.lock().unwrap().push(request_head(&request));
                     if poisoned_conns.lock().unwrap().contains(&remote_addr) {
                         tracing::error!("poisoned connection {:?} was reused!", &remote_addr);
                         panic!("poisoned connection was reused!");
@@ -256,6 +261,7 @@ impl WireMockServer {
         spawn(server);
         Self {
             event_log: wire_events,
+            request_log,
             bind_addr: listener_addr,
             shutdown_hook: tx,
         }
@@ -266,7 +272,22 @@ impl WireMockServer {
         self.event_log.lock().unwrap().clone()
     }
 
-    fn bind_addr(&self) -> SocketAddr {
+    /// Retrieve the method, URI, and headers of the requests received by this server
+    ///
+    /// `CONNECT` requests are answered with the replayed events like any other request, which makes
+    /// this server usable as a stand-in for an HTTP proxy. Tunnels aren't established, so the
+    /// connection is closed after a successful `CONNECT`.
+    pub fn requests(&self) -> Vec<Request<()>> {
+        self.request_log
+            .lock()
+            .unwrap()
+            .iter()
+            .map(request_head)
+            .collect()
+    }
+
+    /// The address this server is listening on
+    pub fn bind_addr(&self) -> SocketAddr {
         self.bind_addr
     }
 
@@ -306,6 +327,14 @@ impl WireMockServer {
     }
 }
 
+fn request_head<B>(request: &Request<B>) -> Request<()> {
+    let mut head = Request::new(());
+    *head.method_mut() = request.method().clone();
+    *head.uri_mut() = request.uri().clone();
+    *head.headers_mut() = request.headers().clone();
+    head
+}
+
 async fn generate_response_event(event: ReplayedEvent) -> Result<Response<Body>, Infallible> {
     let resp = match event {
         ReplayedEvent::HttpResponse { status, body } => http::Response::builder()
@@ -339,13 +368,33 @@ impl Service<Name> for LoggingDnsResolver {
     }
 
     fn call(&mut self, req: Name) -> Self::Future {
+        self.lookup(req.to_string())
+    }
+}
+
+/// Allows the resolver to be used with the hyper 1.x `HttpConnector` from `hyper-util`
+#[cfg(feature = "connector-hyper-1-x")]
+impl Service<hyper_util::client::legacy::connect::dns::Name> for LoggingDnsResolver {
+    type Response = Once<SocketAddr>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: hyper_util::client::legacy::connect::dns::Name) -> Self::Future {
+        self.lookup(req.to_string())
+    }
+}
+
+impl LoggingDnsResolver {
+    fn lookup(&self, name: String) -> BoxFuture<'static, Once<SocketAddr>, Infallible> {
         let socket_addr = self.socket_addr;
         let log = self.log.clone();
         Box::pin(async move {
-            println!("looking up {:?}, replying with {:?}", req, socket_addr);
-            log.lock()
-                .unwrap()
-                .push(RecordedEvent::DnsLookup(req.to_string()));
+            println!("looking up {:?}, replying with {:?}", name, socket_addr);
+            log.lock().unwrap().push(RecordedEvent::DnsLookup(name));
             Ok(std::iter::once(socket_addr))
         })
     }
@@ -4,6 +4,10 @@
  */
 
 mod cache;
+#[cfg(all(aws_sdk_unstable, feature = "unstable-identity-refresh"))]
+pub use cache::EagerCacheBuilder;
+#[cfg(feature = "identity-store-file")]
+pub use cache::{EncryptionKey, FileIdentityStore, FileIdentityStoreBuilder};
 pub use cache::{IdentityCache, LazyCacheBuilder};
 
 /// Identity resolver implementation for "no auth".
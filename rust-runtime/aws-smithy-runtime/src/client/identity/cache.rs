@@ -14,6 +14,16 @@ mod lazy;
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
 pub use lazy::LazyCacheBuilder;
 
+#[cfg(all(aws_sdk_unstable, feature = "unstable-identity-refresh"))]
+mod eager;
+#[cfg(all(aws_sdk_unstable, feature = "unstable-identity-refresh"))]
+pub use eager::EagerCacheBuilder;
+
+#[cfg(feature = "identity-store-file")]
+mod file_store;
+#[cfg(feature = "identity-store-file")]
+pub use file_store::{EncryptionKey, FileIdentityStore, FileIdentityStoreBuilder};
+
 /// Identity cache configuration.
 ///
 /// # Examples
@@ -74,6 +84,18 @@ impl IdentityCache {
     pub fn lazy() -> LazyCacheBuilder {
         LazyCacheBuilder::new()
     }
+
+    /// Configure an eager identity cache.
+    ///
+    /// Identities are loaded and cached when a request is made, and then refreshed in the
+    /// background before they expire.
+    ///
+    /// This requires the `unstable-identity-refresh` feature and building with
+    /// `--cfg aws_sdk_unstable`, and isn't covered by semver.
+    #[cfg(all(aws_sdk_unstable, feature = "unstable-identity-refresh"))]
+    pub fn eager() -> EagerCacheBuilder {
+        EagerCacheBuilder::new()
+    }
 }
 
 #[derive(Clone, Debug)]
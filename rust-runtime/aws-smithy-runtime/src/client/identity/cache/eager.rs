@@ -0,0 +1,822 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use super::lazy::{CachePartitions, TimedOutError};
+use super::IdentityCache;
+use crate::expiring_cache::ExpiringCache;
+use aws_smithy_async::future::timeout::Timeout;
+use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep, Sleep};
+use aws_smithy_async::time::SharedTimeSource;
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::auth::AuthScheme;
+use aws_smithy_runtime_api::client::identity::{
+    Identity, IdentityCachePartition, IdentityFuture, ResolveCachedIdentity, ResolveIdentity,
+    SharedIdentityCache, SharedIdentityResolver,
+};
+use aws_smithy_runtime_api::client::runtime_components::{
+    RuntimeComponents, RuntimeComponentsBuilder,
+};
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::error::display::DisplayErrorContext;
+use aws_smithy_types::DateTime;
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::Poll;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tracing::Instrument;
+
+const DEFAULT_LOAD_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_EXPIRATION: Duration = Duration::from_secs(15 * 60);
+const DEFAULT_BUFFER_TIME: Duration = Duration::from_secs(10);
+const DEFAULT_REFRESH_BEFORE: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_REFRESH_JITTER_FRACTION: fn() -> f64 = || fastrand::f64() * 0.5;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Builder for eager identity caching.
+///
+/// Eager caches load identities the same way that [lazy caches](super::LazyCacheBuilder) do, but
+/// then refresh them in the background before they expire, so that requests don't wait for
+/// identity resolution.
+///
+/// - Refreshes are scheduled [`refresh_before`](Self::refresh_before) the identity expires, plus a
+///   random jitter so that many processes don't refresh at once.
+/// - When a refresh fails, it is retried with exponential backoff while the cache continues to
+///   serve the identity it has, for as long as that identity is valid.
+/// - Identities that weren't used since their last refresh aren't refreshed again until the next
+///   request loads them.
+/// - Background refreshes stop when the cache, and with it the client, is dropped.
+///
+/// Background refreshes are spawned onto the current Tokio runtime, and resolve identities with the
+/// runtime components and config of the client that the identity resolver is configured on, rather
+/// than those of the request that loaded the identity. Without a Tokio runtime, or for identity
+/// resolvers that are only configured on an operation, identities are only loaded when requests
+/// need them, like with a lazy cache.
+#[derive(Debug, Default)]
+pub struct EagerCacheBuilder {
+    load_timeout: Option<Duration>,
+    buffer_time: Option<Duration>,
+    refresh_before: Option<Duration>,
+    refresh_jitter_fraction: Option<fn() -> f64>,
+    default_expiration: Option<Duration>,
+    initial_backoff: Option<Duration>,
+    max_backoff: Option<Duration>,
+}
+
+impl EagerCacheBuilder {
+    /// Create a new builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Timeout for identity resolution.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn load_timeout(mut self, timeout: Duration) -> Self {
+        self.set_load_timeout(Some(timeout));
+        self
+    }
+
+    /// Timeout for identity resolution.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn set_load_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.load_timeout = timeout;
+        self
+    }
+
+    /// Amount of time before the actual identity expiration time where the identity is considered expired.
+    ///
+    /// Requests that find an identity within this time of its expiration wait for a new identity.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn buffer_time(mut self, buffer_time: Duration) -> Self {
+        self.set_buffer_time(Some(buffer_time));
+        self
+    }
+
+    /// Amount of time before the actual identity expiration time where the identity is considered expired.
+    ///
+    /// Requests that find an identity within this time of its expiration wait for a new identity.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn set_buffer_time(&mut self, buffer_time: Option<Duration>) -> &mut Self {
+        self.buffer_time = buffer_time;
+        self
+    }
+
+    /// Amount of time before the identity expiration time when the identity is refreshed.
+    ///
+    /// Identities that are valid for less than twice this time are refreshed halfway through their
+    /// remaining lifetime. A random jitter of up to half of this time is added.
+    ///
+    /// Defaults to 5 minutes.
+    pub fn refresh_before(mut self, refresh_before: Duration) -> Self {
+        self.set_refresh_before(Some(refresh_before));
+        self
+    }
+
+    /// Amount of time before the identity expiration time when the identity is refreshed.
+    ///
+    /// Identities that are valid for less than twice this time are refreshed halfway through their
+    /// remaining lifetime. A random jitter of up to half of this time is added.
+    ///
+    /// Defaults to 5 minutes.
+    pub fn set_refresh_before(&mut self, refresh_before: Option<Duration>) -> &mut Self {
+        self.refresh_before = refresh_before;
+        self
+    }
+
+    /// A random fraction of the refresh time that is added to it.
+    ///
+    /// Defaults to a randomly generated value between 0.0 and 0.5. This setter is for testing only.
+    #[allow(unused)]
+    #[cfg(test)]
+    fn refresh_jitter_fraction(mut self, refresh_jitter_fraction: fn() -> f64) -> Self {
+        self.refresh_jitter_fraction = Some(refresh_jitter_fraction);
+        self
+    }
+
+    /// Default expiration time to set on an identity if it doesn't have an expiration time.
+    ///
+    /// This is only used if the resolved identity doesn't have an expiration time set.
+    /// This must be at least 15 minutes.
+    ///
+    /// Defaults to 15 minutes.
+    pub fn default_expiration(mut self, duration: Duration) -> Self {
+        self.set_default_expiration(Some(duration));
+        self
+    }
+
+    /// Default expiration time to set on an identity if it doesn't have an expiration time.
+    ///
+    /// This is only used if the resolved identity doesn't have an expiration time set.
+    /// This must be at least 15 minutes.
+    ///
+    /// Defaults to 15 minutes.
+    pub fn set_default_expiration(&mut self, duration: Option<Duration>) -> &mut Self {
+        self.default_expiration = duration;
+        self
+    }
+
+    /// How long to wait before retrying a failed refresh.
+    ///
+    /// The wait doubles after each consecutive failure, up to the
+    /// [`max_backoff`](Self::max_backoff). Defaults to 1 second.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.set_initial_backoff(Some(backoff));
+        self
+    }
+
+    /// How long to wait before retrying a failed refresh.
+    ///
+    /// The wait doubles after each consecutive failure, up to the
+    /// [`max_backoff`](Self::max_backoff). Defaults to 1 second.
+    pub fn set_initial_backoff(&mut self, backoff: Option<Duration>) -> &mut Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// The longest time to wait before retrying a failed refresh.
+    ///
+    /// Defaults to 1 minute.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.set_max_backoff(Some(backoff));
+        self
+    }
+
+    /// The longest time to wait before retrying a failed refresh.
+    ///
+    /// Defaults to 1 minute.
+    pub fn set_max_backoff(&mut self, backoff: Option<Duration>) -> &mut Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Builds a [`SharedIdentityCache`] from this builder.
+    ///
+    /// # Panics
+    ///
+    /// This builder will panic if required fields are not given, or if given values are not valid.
+    pub fn build(self) -> SharedIdentityCache {
+        let default_expiration = self.default_expiration.unwrap_or(DEFAULT_EXPIRATION);
+        assert!(
+            default_expiration >= DEFAULT_EXPIRATION,
+            "default_expiration must be at least 15 minutes"
+        );
+        let buffer_time = self.buffer_time.unwrap_or(DEFAULT_BUFFER_TIME);
+        let (shutdown, _) = watch::channel(());
+        EagerCache {
+            inner: Arc::new(Inner {
+                partitions: CachePartitions::new(buffer_time),
+                refreshers: Mutex::new(HashMap::new()),
+                clients: Mutex::new(HashMap::new()),
+                load_timeout: self.load_timeout.unwrap_or(DEFAULT_LOAD_TIMEOUT),
+                buffer_time,
+                refresh_before: self.refresh_before.unwrap_or(DEFAULT_REFRESH_BEFORE),
+                refresh_jitter_fraction: self
+                    .refresh_jitter_fraction
+                    .unwrap_or(DEFAULT_REFRESH_JITTER_FRACTION),
+                default_expiration,
+                initial_backoff: self.initial_backoff.unwrap_or(DEFAULT_INITIAL_BACKOFF),
+                max_backoff: self.max_backoff.unwrap_or(DEFAULT_MAX_BACKOFF),
+                shutdown,
+            }),
+        }
+        .into_shared()
+    }
+}
+
+#[derive(Debug)]
+struct EagerCache {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    partitions: CachePartitions,
+    /// Partitions that are refreshed in the background, and whether they were used since their
+    /// last refresh
+    refreshers: Mutex<HashMap<IdentityCachePartition, Arc<AtomicBool>>>,
+    /// Clients that the identity resolver of each partition is configured on
+    clients: Mutex<HashMap<IdentityCachePartition, Arc<Client>>>,
+    load_timeout: Duration,
+    buffer_time: Duration,
+    refresh_before: Duration,
+    refresh_jitter_fraction: fn() -> f64,
+    default_expiration: Duration,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    /// Background refreshes stop when this is dropped
+    shutdown: watch::Sender<()>,
+}
+
+/// The base runtime components and config of a client
+#[derive(Debug)]
+struct Client {
+    runtime_components: RuntimeComponentsBuilder,
+    config_bag: ConfigBag,
+}
+
+/// What a background refresh needs to resolve an identity
+struct RefreshContext {
+    resolver: SharedIdentityResolver,
+    runtime_components: RuntimeComponents,
+    client: Arc<Client>,
+    time_source: SharedTimeSource,
+    sleep_impl: SharedAsyncSleep,
+}
+
+impl Inner {
+    async fn load(
+        &self,
+        resolver: &SharedIdentityResolver,
+        runtime_components: &RuntimeComponents,
+        config_bag: &ConfigBag,
+        time_source: &SharedTimeSource,
+        sleep_impl: &SharedAsyncSleep,
+    ) -> Result<(Identity, SystemTime), BoxError> {
+        let now = time_source.now();
+        let fut = Timeout::new(
+            resolver.resolve_identity(runtime_components, config_bag),
+            sleep_impl.sleep(self.load_timeout),
+        );
+        let identity = match fut.await {
+            Ok(result) => result?,
+            Err(_err) => match resolver.fallback_on_interrupt() {
+                Some(identity) => identity,
+                None => return Err(BoxError::from(TimedOutError(self.load_timeout))),
+            },
+        };
+        let expiration = identity
+            .expiration()
+            .unwrap_or(now + self.default_expiration);
+        Ok((identity, expiration))
+    }
+
+    /// Returns how long to wait before refreshing an identity that expires at `expiration`.
+    fn refresh_delay(&self, expiration: SystemTime, now: SystemTime) -> Duration {
+        let remaining = expiration.duration_since(now).unwrap_or_default();
+        let lead = self.refresh_before.min(remaining / 2);
+        let lead = lead + lead.mul_f64((self.refresh_jitter_fraction)());
+        remaining.saturating_sub(lead)
+    }
+
+    fn next_backoff(&self, backoff: Option<Duration>) -> Duration {
+        match backoff {
+            Some(backoff) => (backoff * 2).min(self.max_backoff),
+            None => self.initial_backoff.min(self.max_backoff),
+        }
+    }
+
+    /// Remembers the client that each configured identity resolver belongs to.
+    fn remember_client(&self, runtime_components: &RuntimeComponentsBuilder, cfg: &ConfigBag) {
+        let partitions: Vec<_> = runtime_components
+            .auth_schemes()
+            .filter_map(|auth_scheme| auth_scheme.identity_resolver(runtime_components))
+            .map(|resolver| resolver.cache_partition())
+            .collect();
+        if partitions.is_empty() {
+            return;
+        }
+        let client = Arc::new(Client {
+            // Background refreshes don't use this cache, since the client holding on to it would
+            // keep it from being dropped
+            runtime_components: runtime_components
+                .clone()
+                .with_identity_cache(Some(IdentityCache::no_cache())),
+            config_bag: cfg.snapshot(),
+        });
+        let mut clients = self.clients.lock().unwrap();
+        for partition in partitions {
+            clients.insert(partition, client.clone());
+        }
+    }
+
+    /// Marks the partition as used, and returns false if it isn't refreshed in the background.
+    fn mark_used(&self, partition: IdentityCachePartition) -> bool {
+        match self.refreshers.lock().unwrap().get(&partition) {
+            Some(used) => {
+                used.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn start_refreshing(
+        self: &Arc<Self>,
+        partition: IdentityCachePartition,
+        cache: ExpiringCache<Identity, BoxError>,
+        context: RefreshContext,
+    ) {
+        let handle = match tokio::runtime::Handle::try_current() {
+            Ok(handle) => handle,
+            Err(_) => {
+                tracing::debug!("no Tokio runtime to refresh identities in the background");
+                return;
+            }
+        };
+        let used = Arc::new(AtomicBool::new(false));
+        {
+            let mut refreshers = self.refreshers.lock().unwrap();
+            if refreshers.contains_key(&partition) {
+                return;
+            }
+            refreshers.insert(partition, used.clone());
+        }
+        let span = tracing::debug_span!("eager_identity_refresh", partition = ?partition);
+        handle.spawn(
+            refresh(
+                Arc::downgrade(self),
+                self.shutdown.subscribe(),
+                partition,
+                cache,
+                used,
+                context,
+            )
+            .instrument(span),
+        );
+    }
+}
+
+/// Refreshes the identity of a partition until the cache is dropped, or the identity isn't used.
+async fn refresh(
+    inner: Weak<Inner>,
+    mut shutdown: watch::Receiver<()>,
+    partition: IdentityCachePartition,
+    cache: ExpiringCache<Identity, BoxError>,
+    used: Arc<AtomicBool>,
+    context: RefreshContext,
+) {
+    let mut backoff = None;
+    loop {
+        // Only hold on to the cache while refreshing, so that dropping it stops the refreshes
+        let delay = {
+            let inner = match inner.upgrade() {
+                Some(inner) => inner,
+                None => return,
+            };
+            match (backoff, cache.expiration().await) {
+                (Some(backoff), _) => backoff,
+                (None, Some(expiration)) => {
+                    inner.refresh_delay(expiration, context.time_source.now())
+                }
+                // The identity expired and a request is loading it
+                (None, None) => inner.buffer_time,
+            }
+        };
+        tracing::trace!(delay = ?delay, "waiting to refresh the identity");
+        if !sleep_unless_shut_down(context.sleep_impl.sleep(delay), &mut shutdown).await {
+            tracing::debug!("identity cache dropped; stopping background refreshes");
+            return;
+        }
+
+        let inner = match inner.upgrade() {
+            Some(inner) => inner,
+            None => return,
+        };
+        if backoff.is_none() && !used.swap(false, Ordering::Relaxed) {
+            tracing::debug!(
+                "identity wasn't used since it was loaded; stopping background refreshes"
+            );
+            inner.refreshers.lock().unwrap().remove(&partition);
+            return;
+        }
+        let result = inner
+            .load(
+                &context.resolver,
+                &context.runtime_components,
+                &context.client.config_bag,
+                &context.time_source,
+                &context.sleep_impl,
+            )
+            .await;
+        match result {
+            Ok((identity, expiration)) => {
+                tracing::debug!(
+                    new_expiration = %DateTime::from(expiration),
+                    "refreshed identity in the background"
+                );
+                cache.set(identity, expiration).await;
+                backoff = None;
+            }
+            Err(err) => {
+                let next = inner.next_backoff(backoff);
+                tracing::warn!(
+                    err = %DisplayErrorContext(&*err),
+                    retry_in = ?next,
+                    "failed to refresh identity in the background; continuing to use the cached identity"
+                );
+                backoff = Some(next);
+            }
+        }
+    }
+}
+
+/// Waits for `sleep`, and returns false if the cache was dropped first.
+async fn sleep_unless_shut_down(sleep: Sleep, shutdown: &mut watch::Receiver<()>) -> bool {
+    let mut sleep = pin!(sleep);
+    // Nothing is ever sent, so this only completes when the sender is dropped
+    let mut shut_down = pin!(shutdown.changed());
+    poll_fn(|cx| {
+        if sleep.as_mut().poll(cx).is_ready() {
+            Poll::Ready(true)
+        } else if shut_down.as_mut().poll(cx).is_ready() {
+            Poll::Ready(false)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+macro_rules! required_err {
+    ($thing:literal, $how:literal) => {
+        BoxError::from(concat!(
+            "Eager identity caching requires ",
+            $thing,
+            " to be configured. ",
+            $how,
+            " If this isn't possible, then use lazy identity caching by calling ",
+            "the `identity_cache` method on config with `IdentityCache::lazy().build()`",
+        ))
+    };
+}
+macro_rules! validate_components {
+    ($components:ident) => {
+        let _ = $components.time_source().ok_or_else(|| {
+            required_err!(
+                "a time source",
+                "Set a time source using the `time_source` method on config."
+            )
+        })?;
+        let _ = $components.sleep_impl().ok_or_else(|| {
+            required_err!(
+                "an async sleep implementation",
+                "Set a sleep impl using the `sleep_impl` method on config."
+            )
+        })?;
+    };
+}
+
+impl ResolveCachedIdentity for EagerCache {
+    fn validate_base_client_config(
+        &self,
+        runtime_components: &RuntimeComponentsBuilder,
+        cfg: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        validate_components!(runtime_components);
+        self.inner.remember_client(runtime_components, cfg);
+        Ok(())
+    }
+
+    fn validate_final_config(
+        &self,
+        runtime_components: &RuntimeComponents,
+        _cfg: &ConfigBag,
+    ) -> Result<(), BoxError> {
+        validate_components!(runtime_components);
+        Ok(())
+    }
+
+    fn resolve_cached_identity<'a>(
+        &'a self,
+        resolver: SharedIdentityResolver,
+        runtime_components: &'a RuntimeComponents,
+        config_bag: &'a ConfigBag,
+    ) -> IdentityFuture<'a> {
+        let (time_source, sleep_impl) = (
+            runtime_components.time_source().expect("validated"),
+            runtime_components.sleep_impl().expect("validated"),
+        );
+        let partition = resolver.cache_partition();
+        let cache = self.inner.partitions.partition(partition);
+
+        IdentityFuture::new(async move {
+            let refreshing = self.inner.mark_used(partition);
+            if let Some(identity) = cache.yield_or_clear_if_expired(time_source.now()).await {
+                tracing::debug!(
+                    cached_expiration = ?identity.expiration(),
+                    "loaded identity from cache"
+                );
+                return Ok(identity);
+            }
+            let identity = cache
+                .get_or_load(|| {
+                    let span = tracing::info_span!("eager_load_identity");
+                    async {
+                        let (identity, expiration) = self
+                            .inner
+                            .load(
+                                &resolver,
+                                runtime_components,
+                                config_bag,
+                                &time_source,
+                                &sleep_impl,
+                            )
+                            .await?;
+                        tracing::debug!(
+                            new_expiration = %DateTime::from(expiration),
+                            partition = ?partition,
+                            "identity cache miss occurred; added new identity"
+                        );
+                        Ok((identity, expiration))
+                    }
+                    .instrument(span)
+                })
+                .await?;
+            if refreshing {
+                return Ok(identity);
+            }
+            let client = self.inner.clients.lock().unwrap().get(&partition).cloned();
+            match client {
+                Some(client) => {
+                    // Components that only operations set, such as the auth scheme option
+                    // resolver, are taken from this request
+                    let runtime_components = runtime_components
+                        .to_builder()
+                        .merge_from(&client.runtime_components)
+                        .build()?;
+                    self.inner.start_refreshing(
+                        partition,
+                        cache,
+                        RefreshContext {
+                            time_source: runtime_components.time_source().expect("validated"),
+                            sleep_impl: runtime_components.sleep_impl().expect("validated"),
+                            resolver,
+                            runtime_components,
+                            client,
+                        },
+                    );
+                }
+                None => tracing::debug!(
+                    "the identity resolver isn't configured on a client; not refreshing it in the background"
+                ),
+            }
+            Ok(identity)
+        })
+    }
+}
+
+#[cfg(all(test, feature = "client", feature = "http-auth"))]
+mod tests {
+    use super::*;
+    use crate::client::auth::no_auth::{NoAuthScheme, NO_AUTH_SCHEME_ID};
+    use aws_smithy_async::rt::sleep::TokioSleep;
+    use aws_smithy_async::time::TimeSource;
+    use aws_smithy_runtime_api::client::identity::http::Token;
+    use aws_smithy_types::config_bag::{Layer, Storable, StoreReplace};
+    use std::sync::atomic::AtomicUsize;
+    use std::time::UNIX_EPOCH;
+
+    const NO_JITTER: fn() -> f64 = || 0.0;
+
+    /// Time source that follows Tokio's (paused) clock
+    #[derive(Debug)]
+    struct TokioTime(tokio::time::Instant);
+
+    impl TimeSource for TokioTime {
+        fn now(&self) -> SystemTime {
+            UNIX_EPOCH + self.0.elapsed()
+        }
+    }
+
+    /// Returns the components of a client with `resolver`, and validates them with `cache`
+    fn client(
+        cache: &SharedIdentityCache,
+        resolver: &SharedIdentityResolver,
+        cfg: &ConfigBag,
+    ) -> RuntimeComponents {
+        let builder = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(TokioTime(tokio::time::Instant::now())))
+            .with_sleep_impl(Some(TokioSleep::new()))
+            .with_auth_scheme(NoAuthScheme::new())
+            .with_identity_resolver(NO_AUTH_SCHEME_ID, resolver.clone());
+        builder
+            .clone()
+            .with_identity_cache(Some(cache.clone()))
+            .validate_base_client_config(cfg)
+            .unwrap();
+        builder.build().unwrap()
+    }
+
+    /// Resolves tokens that are valid for 15 minutes, unless told to fail
+    #[derive(Clone, Debug, Default)]
+    struct CountingResolver(Arc<Counts>);
+
+    #[derive(Debug, Default)]
+    struct Counts {
+        calls: AtomicUsize,
+        fail: AtomicBool,
+        sources: Mutex<Vec<Option<&'static str>>>,
+    }
+
+    /// Config that identifies where a config bag came from
+    #[derive(Debug)]
+    struct Source(&'static str);
+
+    impl Storable for Source {
+        type Storer = StoreReplace<Self>;
+    }
+
+    impl ResolveIdentity for CountingResolver {
+        fn resolve_identity<'a>(
+            &'a self,
+            runtime_components: &'a RuntimeComponents,
+            config_bag: &'a ConfigBag,
+        ) -> IdentityFuture<'a> {
+            let call = self.0.calls.fetch_add(1, Ordering::Relaxed);
+            self.0
+                .sources
+                .lock()
+                .unwrap()
+                .push(config_bag.load::<Source>().map(|source| source.0));
+            if self.0.fail.load(Ordering::Relaxed) {
+                return IdentityFuture::ready(Err("resolution failed".into()));
+            }
+            let expiration = runtime_components.time_source().unwrap().now() + DEFAULT_EXPIRATION;
+            IdentityFuture::ready(Ok(
+                Token::new(format!("token-{call}"), Some(expiration)).into()
+            ))
+        }
+    }
+
+    async fn token(
+        cache: &SharedIdentityCache,
+        resolver: &SharedIdentityResolver,
+        components: &RuntimeComponents,
+    ) -> String {
+        let identity = cache
+            .resolve_cached_identity(resolver.clone(), components, &ConfigBag::base())
+            .await
+            .unwrap();
+        identity.data::<Token>().unwrap().token().to_string()
+    }
+
+    fn cache() -> SharedIdentityCache {
+        EagerCacheBuilder::new()
+            .refresh_jitter_fraction(NO_JITTER)
+            .build()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn identities_are_refreshed_before_they_expire() {
+        let counting = CountingResolver::default();
+        let resolver = SharedIdentityResolver::new(counting.clone());
+        let cache = cache();
+        let components = client(&cache, &resolver, &ConfigBag::base());
+
+        assert_eq!("token-0", token(&cache, &resolver, &components).await);
+        // The refresh is scheduled 5 minutes before the identity expires
+        tokio::time::sleep(Duration::from_secs(9 * 60)).await;
+        assert_eq!("token-0", token(&cache, &resolver, &components).await);
+        tokio::time::sleep(Duration::from_secs(61)).await;
+        assert_eq!(2, counting.0.calls.load(Ordering::Relaxed));
+        assert_eq!("token-1", token(&cache, &resolver, &components).await);
+        assert_eq!(2, counting.0.calls.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_refreshes_back_off_and_serve_the_cached_identity() {
+        let counting = CountingResolver::default();
+        let resolver = SharedIdentityResolver::new(counting.clone());
+        let cache = cache();
+        let components = client(&cache, &resolver, &ConfigBag::base());
+
+        assert_eq!("token-0", token(&cache, &resolver, &components).await);
+        assert_eq!("token-0", token(&cache, &resolver, &components).await);
+        counting.0.fail.store(true, Ordering::Relaxed);
+        // The refresh fails at 10 minutes, and is retried after 1, 2, and 4 seconds
+        tokio::time::sleep(Duration::from_secs(10 * 60 + 8)).await;
+        assert_eq!(5, counting.0.calls.load(Ordering::Relaxed));
+        assert_eq!("token-0", token(&cache, &resolver, &components).await);
+
+        counting.0.fail.store(false, Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_secs(8)).await;
+        assert_eq!(6, counting.0.calls.load(Ordering::Relaxed));
+        assert_eq!("token-5", token(&cache, &resolver, &components).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unused_identities_are_not_refreshed() {
+        let counting = CountingResolver::default();
+        let resolver = SharedIdentityResolver::new(counting.clone());
+        let cache = cache();
+        let components = client(&cache, &resolver, &ConfigBag::base());
+
+        assert_eq!("token-0", token(&cache, &resolver, &components).await);
+        tokio::time::sleep(Duration::from_secs(30 * 60)).await;
+        assert_eq!(1, counting.0.calls.load(Ordering::Relaxed));
+
+        // The next request loads an identity, and refreshes start again
+        assert_eq!("token-1", token(&cache, &resolver, &components).await);
+        assert_eq!("token-1", token(&cache, &resolver, &components).await);
+        tokio::time::sleep(Duration::from_secs(10 * 60 + 1)).await;
+        assert_eq!(3, counting.0.calls.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refreshes_stop_when_the_cache_is_dropped() {
+        let counting = CountingResolver::default();
+        let resolver = SharedIdentityResolver::new(counting.clone());
+        let cache = cache();
+        let components = client(&cache, &resolver, &ConfigBag::base());
+
+        assert_eq!("token-0", token(&cache, &resolver, &components).await);
+        drop((resolver, components));
+        // The cache and the background refresh hold on to the resolver
+        assert!(Arc::strong_count(&counting.0) > 1);
+        drop(cache);
+        tokio::task::yield_now().await;
+        assert_eq!(1, Arc::strong_count(&counting.0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refreshes_use_the_client_config() {
+        let counting = CountingResolver::default();
+        let resolver = SharedIdentityResolver::new(counting.clone());
+        let cache = cache();
+        let mut client_layer = Layer::new("client");
+        client_layer.store_put(Source("client"));
+        let components = client(&cache, &resolver, &ConfigBag::of_layers(vec![client_layer]));
+
+        // The request that loads the identity overrides the config
+        let mut operation_layer = Layer::new("operation");
+        operation_layer.store_put(Source("operation"));
+        let operation_cfg = ConfigBag::of_layers(vec![operation_layer]);
+        for _ in 0..2 {
+            cache
+                .resolve_cached_identity(resolver.clone(), &components, &operation_cfg)
+                .await
+                .unwrap();
+        }
+        tokio::time::sleep(Duration::from_secs(10 * 60 + 1)).await;
+        assert_eq!(
+            vec![Some("operation"), Some("client")],
+            *counting.0.sources.lock().unwrap()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resolvers_that_arent_on_a_client_arent_refreshed() {
+        let cache = cache();
+        let client_resolver = SharedIdentityResolver::new(CountingResolver::default());
+        let components = client(&cache, &client_resolver, &ConfigBag::base());
+
+        // This resolver was only set on an operation
+        let counting = CountingResolver::default();
+        let resolver = SharedIdentityResolver::new(counting.clone());
+        assert_eq!("token-0", token(&cache, &resolver, &components).await);
+        assert_eq!("token-0", token(&cache, &resolver, &components).await);
+        tokio::time::sleep(Duration::from_secs(11 * 60)).await;
+        assert_eq!(1, counting.0.calls.load(Ordering::Relaxed));
+    }
+}
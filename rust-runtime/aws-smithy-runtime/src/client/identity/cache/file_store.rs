@@ -0,0 +1,522 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_async::rt::sleep::{default_async_sleep, AsyncSleep, SharedAsyncSleep};
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::identity::store::{
+    IdentityStoreKey, IdentityStoreLock, LoadIdentityFuture, LockIdentityFuture,
+    SaveIdentityFuture, StoreIdentity, StoredIdentity,
+};
+use aws_smithy_runtime_api::shared::IntoShared;
+use fs2::FileExt;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fmt;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, ErrorKind, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+const FORMAT_VERSION: u8 = 1;
+const KEY_LEN: usize = 32;
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Key used to encrypt the identities of a [`FileIdentityStore`].
+#[derive(Clone)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl EncryptionKey {
+    /// Creates an encryption key from 32 bytes of key material.
+    pub fn new(key: [u8; KEY_LEN]) -> Self {
+        Self(key)
+    }
+
+    /// Loads the key stored in the file at `path`, generating it first if the file doesn't exist.
+    ///
+    /// Generated key files are only readable by the current user on Unix platforms. If the key
+    /// file is lost, identities that were encrypted with it can't be loaded and are resolved again.
+    ///
+    /// The key file shouldn't be kept in the directory of the [`FileIdentityStore`], or anywhere
+    /// else that is exposed along with the identity files, since anyone who can read both can
+    /// decrypt the identities.
+    pub fn load_or_create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        match fs::read(path) {
+            Ok(key) => return Self::from_slice(&key, path),
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+            Err(_) => {}
+        }
+        let rng = SystemRandom::new();
+        let mut key = [0; KEY_LEN];
+        rng.fill(&mut key)
+            .map_err(|_| io::Error::other("failed to generate an encryption key"))?;
+        if let Some(parent) = path.parent() {
+            create_private_dir(parent)?;
+        }
+        // Write the key to a temporary file and link it into place, so that readers never see a
+        // partial key. Unlike a rename, linking fails if another process created the key first.
+        let mut suffix = [0; 8];
+        rng.fill(&mut suffix)
+            .map_err(|_| io::Error::other("failed to generate a file name"))?;
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(format!(".{}.tmp", hex(&suffix)));
+        let temp_path = PathBuf::from(temp_path);
+        let result = create_private_file(&temp_path)
+            .and_then(|mut file| {
+                file.write_all(&key)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::hard_link(&temp_path, path));
+        let _ = fs::remove_file(&temp_path);
+        match result {
+            Ok(_) => Ok(Self(key)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                Self::from_slice(&fs::read(path)?, path)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn from_slice(key: &[u8], path: &Path) -> io::Result<Self> {
+        let key = key.try_into().map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "the key file `{}` must contain exactly {KEY_LEN} bytes",
+                    path.display()
+                ),
+            )
+        })?;
+        Ok(Self(key))
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(** redacted **)")
+    }
+}
+
+/// Identity store that keeps identities in encrypted files.
+///
+/// Each identity is stored in its own file in the store's directory, encrypted with AES-256-GCM.
+/// File names are derived from a hash of the [`IdentityStoreKey`], so they don't reveal which
+/// identity they hold.
+///
+/// Files are read and written on Tokio's blocking thread pool, so the store must be used within a
+/// Tokio runtime.
+///
+/// Locks are held with advisory file locks on lock files next to the identity files, so processes
+/// that share a directory take turns resolving an identity instead of all resolving it at once.
+/// The operating system releases these locks when the process holding them exits, so a crashed
+/// process never leaves an identity locked. Lock files are never removed, since removing a lock
+/// file while another process waits on it would let two processes hold the lock at once.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aws_smithy_runtime::client::identity::{EncryptionKey, FileIdentityStore, IdentityCache};
+/// use aws_smithy_runtime_api::client::identity::http::TokenCodec;
+///
+/// # fn key_from_os_keychain() -> [u8; 32] { unimplemented!() }
+/// // Keep the key apart from the identities, for example in the keychain of the operating system
+/// let key = EncryptionKey::new(key_from_os_keychain());
+/// let directory = std::env::temp_dir().join("my-tool").join("identities");
+/// let store = FileIdentityStore::builder(directory, key).build();
+/// let identity_cache = IdentityCache::lazy()
+///     .persistent_store(store, TokenCodec::new())
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct FileIdentityStore {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    directory: PathBuf,
+    key: LessSafeKey,
+    rng: SystemRandom,
+    lock_timeout: Duration,
+    sleep_impl: Option<SharedAsyncSleep>,
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileIdentityStore")
+            .field("directory", &self.directory)
+            .field("key", &"** redacted **")
+            .field("lock_timeout", &self.lock_timeout)
+            .finish()
+    }
+}
+
+impl FileIdentityStore {
+    /// Returns a builder for a store that keeps identities in `directory`, encrypted with
+    /// `encryption_key`.
+    ///
+    /// The directory is created when the first identity is stored.
+    pub fn builder(
+        directory: impl Into<PathBuf>,
+        encryption_key: EncryptionKey,
+    ) -> FileIdentityStoreBuilder {
+        FileIdentityStoreBuilder {
+            directory: directory.into(),
+            encryption_key,
+            lock_timeout: None,
+            sleep_impl: None,
+        }
+    }
+
+    async fn lock_file(&self, key: &IdentityStoreKey) -> Result<IdentityStoreLock, BoxError> {
+        let path = self
+            .inner
+            .directory
+            .join(format!("{}.lock", file_name(key)));
+        let file = {
+            let (inner, path) = (self.inner.clone(), path.clone());
+            run_blocking(move || {
+                create_private_dir(&inner.directory)?;
+                Ok(open_lock_file(&path)?)
+            })
+            .await?
+        };
+        let mut waited = Duration::ZERO;
+        loop {
+            // Trying to take the lock doesn't block
+            match file.try_lock_exclusive() {
+                // The lock is released when the file is closed
+                Ok(_) => return Ok(IdentityStoreLock::new(file)),
+                Err(err) if err.kind() != fs2::lock_contended_error().kind() => {
+                    return Err(err.into())
+                }
+                Err(_) => {}
+            }
+            let sleep_impl = match &self.inner.sleep_impl {
+                Some(sleep_impl) if waited < self.inner.lock_timeout => sleep_impl,
+                _ => {
+                    tracing::warn!(
+                        path = %path.display(),
+                        "timed out waiting for the identity lock; continuing without it"
+                    );
+                    return Ok(IdentityStoreLock::none());
+                }
+            };
+            sleep_impl.sleep(LOCK_POLL_INTERVAL).await;
+            waited += LOCK_POLL_INTERVAL;
+        }
+    }
+}
+
+impl Inner {
+    fn load(&self, key: &IdentityStoreKey) -> Result<Option<StoredIdentity>, BoxError> {
+        let name = file_name(key);
+        let contents = match fs::read(self.directory.join(format!("{name}.identity"))) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let (version, rest) = contents.split_first().ok_or("the identity file is empty")?;
+        if *version != FORMAT_VERSION || rest.len() < NONCE_LEN {
+            return Err("the identity file has an unsupported format".into());
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).expect("length checked above");
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(name.as_bytes()), &mut in_out)
+            .map_err(|_| {
+                "failed to decrypt the identity file; it was written with a different key or is corrupt"
+            })?;
+        if plaintext.len() < 12 {
+            return Err("the identity file is truncated".into());
+        }
+        let (expiration, data) = plaintext.split_at(12);
+        let secs = u64::from_be_bytes(expiration[..8].try_into().expect("8 bytes"));
+        let nanos = u32::from_be_bytes(expiration[8..].try_into().expect("4 bytes"));
+        Ok(Some(StoredIdentity::new(
+            data.to_vec(),
+            UNIX_EPOCH + Duration::new(secs, nanos),
+        )))
+    }
+
+    fn save(&self, key: &IdentityStoreKey, identity: StoredIdentity) -> Result<(), BoxError> {
+        let name = file_name(key);
+        let since_epoch = identity
+            .expiration()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| "identities that expire before 1970 can't be stored")?;
+        let mut in_out = Vec::with_capacity(12 + identity.data().len() + AES_256_GCM.tag_len());
+        in_out.extend_from_slice(&since_epoch.as_secs().to_be_bytes());
+        in_out.extend_from_slice(&since_epoch.subsec_nanos().to_be_bytes());
+        in_out.extend_from_slice(identity.data());
+
+        let mut nonce = [0; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| "failed to generate a nonce")?;
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(name.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| "failed to encrypt the identity")?;
+
+        create_private_dir(&self.directory)?;
+        // Write to a temporary file and rename it, so that readers never see a partial file
+        let mut suffix = [0; 8];
+        self.rng
+            .fill(&mut suffix)
+            .map_err(|_| "failed to generate a file name")?;
+        let temp_path = self.directory.join(format!("{name}.{}.tmp", hex(&suffix)));
+        let result = create_private_file(&temp_path)
+            .and_then(|mut file| {
+                file.write_all(&[FORMAT_VERSION])?;
+                file.write_all(&nonce)?;
+                file.write_all(&in_out)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&temp_path, self.directory.join(format!("{name}.identity"))));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        Ok(result?)
+    }
+}
+
+impl StoreIdentity for FileIdentityStore {
+    fn load<'a>(&'a self, key: &'a IdentityStoreKey) -> LoadIdentityFuture<'a> {
+        let (inner, key) = (self.inner.clone(), key.clone());
+        LoadIdentityFuture::new(run_blocking(move || inner.load(&key)))
+    }
+
+    fn save<'a>(
+        &'a self,
+        key: &'a IdentityStoreKey,
+        identity: StoredIdentity,
+    ) -> SaveIdentityFuture<'a> {
+        let (inner, key) = (self.inner.clone(), key.clone());
+        SaveIdentityFuture::new(run_blocking(move || inner.save(&key, identity)))
+    }
+
+    fn lock<'a>(&'a self, key: &'a IdentityStoreKey) -> LockIdentityFuture<'a> {
+        LockIdentityFuture::new(self.lock_file(key))
+    }
+}
+
+/// Builder for [`FileIdentityStore`].
+#[derive(Debug)]
+pub struct FileIdentityStoreBuilder {
+    directory: PathBuf,
+    encryption_key: EncryptionKey,
+    lock_timeout: Option<Duration>,
+    sleep_impl: Option<SharedAsyncSleep>,
+}
+
+impl FileIdentityStoreBuilder {
+    /// Sets how long to wait for another process to release an identity lock.
+    ///
+    /// After this time, the identity is resolved without holding the lock. Defaults to 30 seconds.
+    /// Identity caches also stop waiting once their load timeout has passed.
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the async sleep implementation used while waiting for identity locks.
+    ///
+    /// Defaults to [`default_async_sleep`].
+    pub fn sleep_impl(mut self, sleep_impl: impl AsyncSleep + 'static) -> Self {
+        self.sleep_impl = Some(sleep_impl.into_shared());
+        self
+    }
+
+    /// Builds the store.
+    pub fn build(self) -> FileIdentityStore {
+        FileIdentityStore {
+            inner: Arc::new(Inner {
+                directory: self.directory,
+                key: LessSafeKey::new(
+                    UnboundKey::new(&AES_256_GCM, &self.encryption_key.0)
+                        .expect("the key has the correct length"),
+                ),
+                rng: SystemRandom::new(),
+                lock_timeout: self.lock_timeout.unwrap_or(DEFAULT_LOCK_TIMEOUT),
+                sleep_impl: self.sleep_impl.or_else(default_async_sleep),
+            }),
+        }
+    }
+}
+
+/// Runs blocking file system work on Tokio's blocking thread pool.
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, BoxError> + Send + 'static,
+) -> Result<T, BoxError> {
+    match tokio::task::spawn_blocking(work).await {
+        Ok(result) => result,
+        Err(join_failure) => Err(io::Error::other(join_failure).into()),
+    }
+}
+
+fn file_name(key: &IdentityStoreKey) -> String {
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    context.update(key.resolver().as_bytes());
+    context.update(&[0]);
+    context.update(key.partition().as_bytes());
+    hex(context.finish().as_ref())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(path)
+}
+
+fn open_lock_file(path: &Path) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+fn create_private_file(path: &Path) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EncryptionKey, FileIdentityStore};
+    use aws_smithy_async::rt::sleep::TokioSleep;
+    use aws_smithy_runtime_api::client::identity::store::{
+        IdentityStoreKey, StoreIdentity, StoredIdentity,
+    };
+    use std::path::Path;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn store(directory: &Path, key: [u8; 32]) -> FileIdentityStore {
+        FileIdentityStore::builder(directory, EncryptionKey::new(key))
+            .lock_timeout(Duration::from_millis(200))
+            .sleep_impl(TokioSleep::new())
+            .build()
+    }
+
+    #[tokio::test]
+    async fn identities_round_trip_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path(), [1; 32]);
+        let key = IdentityStoreKey::new("sso:https://start.example.com", "default");
+        assert_eq!(None, store.load(&key).await.unwrap());
+
+        let expiration = UNIX_EPOCH + Duration::new(1_700_000_000, 5);
+        let identity = StoredIdentity::new(b"secret-token".to_vec(), expiration);
+        store.save(&key, identity.clone()).await.unwrap();
+        assert_eq!(Some(identity), store.load(&key).await.unwrap());
+
+        // Other partitions are separate
+        let other = IdentityStoreKey::new("sso:https://start.example.com", "other");
+        assert_eq!(None, store.load(&other).await.unwrap());
+
+        // Nothing is stored in plain text
+        let files: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(1, files.len());
+        let contents = std::fs::read(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(!contents
+            .windows(b"secret-token".len())
+            .any(|window| window == b"secret-token"));
+    }
+
+    #[tokio::test]
+    async fn identities_written_with_another_key_fail_to_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = IdentityStoreKey::new("resolver", "default");
+        store(dir.path(), [1; 32])
+            .save(&key, StoredIdentity::new(b"token".to_vec(), UNIX_EPOCH))
+            .await
+            .unwrap();
+        let err = store(dir.path(), [2; 32]).load(&key).await.unwrap_err();
+        assert!(err.to_string().contains("failed to decrypt"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn locks_are_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path(), [1; 32]);
+        let key = IdentityStoreKey::new("resolver", "default");
+
+        let lock = store.lock(&key).await.unwrap();
+        let waiter = tokio::spawn({
+            let (store, key) = (store.clone(), key.clone());
+            async move { store.lock(&key).await.unwrap() }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!waiter.is_finished());
+        drop(lock);
+        let lock = waiter.await.unwrap();
+
+        // The lock file stays in place so that every process locks the same file
+        assert_eq!(1, std::fs::read_dir(dir.path()).unwrap().count());
+        drop(lock);
+        assert_eq!(1, std::fs::read_dir(dir.path()).unwrap().count());
+    }
+
+    #[tokio::test]
+    async fn locks_of_crashed_holders_are_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path(), [1; 32]);
+        let key = IdentityStoreKey::new("resolver", "default");
+
+        // A lock file left behind by a process that exited doesn't hold the lock
+        std::fs::write(
+            dir.path().join(format!("{}.lock", super::file_name(&key))),
+            b"",
+        )
+        .unwrap();
+        let _lock = tokio::time::timeout(Duration::from_millis(100), store.lock(&key))
+            .await
+            .expect("the lock should be acquired right away")
+            .unwrap();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), store.lock(&key))
+                .await
+                .is_err(),
+            "the lock should be held"
+        );
+    }
+
+    #[test]
+    fn key_files_are_created_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join("identity.key");
+        let key = EncryptionKey::load_or_create(&path).unwrap();
+        assert_eq!(key.0, EncryptionKey::load_or_create(&path).unwrap().0);
+
+        // No temporary files are left behind
+        assert_eq!(
+            1,
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count()
+        );
+
+        std::fs::write(&path, b"short").unwrap();
+        assert!(EncryptionKey::load_or_create(&path).is_err());
+    }
+}
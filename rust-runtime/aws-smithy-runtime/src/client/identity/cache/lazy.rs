@@ -5,9 +5,13 @@
 
 use crate::expiring_cache::ExpiringCache;
 use aws_smithy_async::future::timeout::Timeout;
-use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep};
+use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep, Sleep};
 use aws_smithy_async::time::{SharedTimeSource, TimeSource};
 use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::identity::store::{
+    IdentityCodec, IdentityStoreKey, IdentityStoreLock, SharedIdentityCodec, SharedIdentityStore,
+    StoreIdentity, StoredIdentity,
+};
 use aws_smithy_runtime_api::client::identity::{
     Identity, IdentityCachePartition, IdentityFuture, ResolveCachedIdentity, ResolveIdentity,
     SharedIdentityCache, SharedIdentityResolver,
@@ -15,17 +19,19 @@ use aws_smithy_runtime_api::client::identity::{
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
 use aws_smithy_runtime_api::shared::IntoShared;
 use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::error::display::DisplayErrorContext;
 use aws_smithy_types::DateTime;
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::RwLock;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use tracing::Instrument;
 
 const DEFAULT_LOAD_TIMEOUT: Duration = Duration::from_secs(5);
 const DEFAULT_EXPIRATION: Duration = Duration::from_secs(15 * 60);
 const DEFAULT_BUFFER_TIME: Duration = Duration::from_secs(10);
 const DEFAULT_BUFFER_TIME_JITTER_FRACTION: fn() -> f64 = || fastrand::f64() * 0.5;
+const DEFAULT_PERSISTENT_PARTITION: &str = "default";
 
 /// Builder for lazy identity caching.
 #[derive(Default, Debug)]
@@ -36,6 +42,8 @@ pub struct LazyCacheBuilder {
     buffer_time: Option<Duration>,
     buffer_time_jitter_fraction: Option<fn() -> f64>,
     default_expiration: Option<Duration>,
+    persistent_store: Option<(SharedIdentityStore, SharedIdentityCodec)>,
+    persistent_partition: Option<String>,
 }
 
 impl LazyCacheBuilder {
@@ -161,6 +169,60 @@ impl LazyCacheBuilder {
         self
     }
 
+    /// Persistent store that identities are saved to and loaded from.
+    ///
+    /// When an identity isn't cached in memory, the cache loads it from the store before resolving
+    /// it, and saves newly resolved identities to the store. This lets identities outlive the
+    /// process, which saves short-lived programs from resolving them on every run. Stored
+    /// identities expire the same way as identities cached in memory.
+    ///
+    /// Identities are converted to and from their persistent form with `codec`, and identities
+    /// that the codec doesn't support aren't persisted. Only the identities of resolvers that
+    /// return a [`persistent_cache_key`](ResolveIdentity::persistent_cache_key) are persisted.
+    ///
+    /// Waiting for the store's lock on an identity counts towards the
+    /// [`load_timeout`](Self::load_timeout).
+    pub fn persistent_store(
+        mut self,
+        store: impl StoreIdentity + 'static,
+        codec: impl IdentityCodec + 'static,
+    ) -> Self {
+        self.set_persistent_store(Some((store.into_shared(), codec.into_shared())));
+        self
+    }
+
+    /// Persistent store that identities are saved to and loaded from, and the codec that converts
+    /// identities to and from their persistent form.
+    ///
+    /// See [`persistent_store`](Self::persistent_store) for details.
+    pub fn set_persistent_store(
+        &mut self,
+        store_and_codec: Option<(SharedIdentityStore, SharedIdentityCodec)>,
+    ) -> &mut Self {
+        self.persistent_store = store_and_codec;
+        self
+    }
+
+    /// Partition of the persistent store that this cache uses.
+    ///
+    /// Caches with different partitions don't share persisted identities, even for resolvers with
+    /// the same persistent cache key. For example, a CLI tool could use the name of the selected
+    /// profile.
+    ///
+    /// Defaults to `default`.
+    pub fn persistent_partition(mut self, partition: impl Into<String>) -> Self {
+        self.set_persistent_partition(Some(partition.into()));
+        self
+    }
+
+    /// Partition of the persistent store that this cache uses.
+    ///
+    /// See [`persistent_partition`](Self::persistent_partition) for details.
+    pub fn set_persistent_partition(&mut self, partition: Option<String>) -> &mut Self {
+        self.persistent_partition = partition;
+        self
+    }
+
     /// Builds a [`SharedIdentityCache`] from this builder.
     ///
     /// # Panics
@@ -172,32 +234,43 @@ impl LazyCacheBuilder {
             default_expiration >= DEFAULT_EXPIRATION,
             "default_expiration must be at least 15 minutes"
         );
-        LazyCache::new(
+        let persistence = self.persistent_store.map(|(store, codec)| Persistence {
+            store,
+            codec,
+            partition: self
+                .persistent_partition
+                .unwrap_or_else(|| DEFAULT_PERSISTENT_PARTITION.into()),
+        });
+        let mut cache = LazyCache::new(
             self.load_timeout.unwrap_or(DEFAULT_LOAD_TIMEOUT),
             self.buffer_time.unwrap_or(DEFAULT_BUFFER_TIME),
             self.buffer_time_jitter_fraction
                 .unwrap_or(DEFAULT_BUFFER_TIME_JITTER_FRACTION),
             default_expiration,
-        )
-        .into_shared()
+        );
+        cache.persistence = persistence;
+        cache.into_shared()
     }
 }
 
 #[derive(Debug)]
-struct CachePartitions {
+pub(super) struct CachePartitions {
     partitions: RwLock<HashMap<IdentityCachePartition, ExpiringCache<Identity, BoxError>>>,
     buffer_time: Duration,
 }
 
 impl CachePartitions {
-    fn new(buffer_time: Duration) -> Self {
+    pub(super) fn new(buffer_time: Duration) -> Self {
         Self {
             partitions: RwLock::new(HashMap::new()),
             buffer_time,
         }
     }
 
-    fn partition(&self, key: IdentityCachePartition) -> ExpiringCache<Identity, BoxError> {
+    pub(super) fn partition(
+        &self,
+        key: IdentityCachePartition,
+    ) -> ExpiringCache<Identity, BoxError> {
         let mut partition = self.partitions.read().unwrap().get(&key).cloned();
         // Add the partition to the cache if it doesn't already exist.
         // Partitions will never be removed.
@@ -216,6 +289,69 @@ impl CachePartitions {
     }
 }
 
+/// Persistent storage for a [`LazyCache`].
+#[derive(Debug)]
+struct Persistence {
+    store: SharedIdentityStore,
+    codec: SharedIdentityCodec,
+    partition: String,
+}
+
+impl Persistence {
+    fn key(&self, resolver: &SharedIdentityResolver) -> Option<IdentityStoreKey> {
+        resolver
+            .persistent_cache_key()
+            .map(|key| IdentityStoreKey::new(key, self.partition.clone()))
+    }
+
+    /// Locks the persisted identity, giving up on the lock once `timeout` completes.
+    async fn lock(&self, key: &IdentityStoreKey, timeout: Sleep) -> IdentityStoreLock {
+        match Timeout::new(self.store.lock(key), timeout).await {
+            Ok(Ok(lock)) => lock,
+            Ok(Err(err)) => {
+                tracing::warn!(err = %DisplayErrorContext(&*err), "failed to lock the persisted identity");
+                IdentityStoreLock::none()
+            }
+            Err(_) => {
+                tracing::warn!("timed out waiting for the lock on the persisted identity");
+                IdentityStoreLock::none()
+            }
+        }
+    }
+
+    /// Loads the persisted identity and its expiration time, if there is one.
+    async fn load(&self, key: &IdentityStoreKey) -> Option<(Identity, SystemTime)> {
+        let stored = match self.store.load(key).await {
+            Ok(stored) => stored?,
+            Err(err) => {
+                tracing::warn!(err = %DisplayErrorContext(&*err), "failed to load the persisted identity");
+                return None;
+            }
+        };
+        match self.codec.decode(stored.data(), stored.expiration()) {
+            Ok(identity) => Some((identity, stored.expiration())),
+            Err(err) => {
+                tracing::warn!(err = %DisplayErrorContext(&*err), "failed to decode the persisted identity");
+                None
+            }
+        }
+    }
+
+    async fn save(&self, key: &IdentityStoreKey, identity: &Identity, expiration: SystemTime) {
+        let data = match self.codec.encode(identity) {
+            Some(data) => data,
+            None => return,
+        };
+        if let Err(err) = self
+            .store
+            .save(key, StoredIdentity::new(data, expiration))
+            .await
+        {
+            tracing::warn!(err = %DisplayErrorContext(&*err), "failed to persist the identity");
+        }
+    }
+}
+
 #[derive(Debug)]
 struct LazyCache {
     partitions: CachePartitions,
@@ -223,6 +359,7 @@ struct LazyCache {
     buffer_time: Duration,
     buffer_time_jitter_fraction: fn() -> f64,
     default_expiration: Duration,
+    persistence: Option<Persistence>,
 }
 
 impl LazyCache {
@@ -238,6 +375,7 @@ impl LazyCache {
             buffer_time,
             buffer_time_jitter_fraction,
             default_expiration,
+            persistence: None,
         }
     }
 }
@@ -328,6 +466,40 @@ impl ResolveCachedIdentity for LazyCache {
                     .get_or_load(|| {
                         let span = tracing::info_span!("lazy_load_identity");
                         async move {
+                            let jitter = self
+                                .buffer_time
+                                .mul_f64((self.buffer_time_jitter_fraction)());
+
+                            // Only one process at a time resolves a persisted identity, and the
+                            // others wait for it and then load the identity it persisted
+                            let persisted = match &self.persistence {
+                                Some(persistence) => persistence
+                                    .key(&resolver)
+                                    .map(|key| (persistence, key)),
+                                None => None,
+                            };
+                            let _lock = match &persisted {
+                                Some((persistence, key)) => Some(
+                                    persistence
+                                        .lock(key, sleep_impl.sleep(load_timeout))
+                                        .await,
+                                ),
+                                None => None,
+                            };
+                            if let Some((persistence, key)) = &persisted {
+                                if let Some((identity, expiration)) = persistence.load(key).await {
+                                    // Persisted identities expire the same way as cached ones
+                                    if time_source.now() < expiration + jitter - self.buffer_time {
+                                        tracing::debug!(
+                                            expiration=%DateTime::from(expiration),
+                                            partition=?partition,
+                                            "loaded identity from the persistent store"
+                                        );
+                                        return Ok((identity, expiration + jitter));
+                                    }
+                                }
+                            }
+
                             let fut = Timeout::new(
                                 resolver.resolve_identity(runtime_components, config_bag),
                                 timeout_future,
@@ -344,10 +516,9 @@ impl ResolveCachedIdentity for LazyCache {
                             // If the identity don't have an expiration time, then create a default one
                             let expiration =
                                 identity.expiration().unwrap_or(now + default_expiration);
-
-                            let jitter = self
-                                .buffer_time
-                                .mul_f64((self.buffer_time_jitter_fraction)());
+                            if let Some((persistence, key)) = &persisted {
+                                persistence.save(key, &identity, expiration).await;
+                            }
 
                             // Logging for cache miss should be emitted here as opposed to after the call to
                             // `cache.get_or_load` above. In the case of multiple threads concurrently executing
@@ -377,7 +548,7 @@ impl ResolveCachedIdentity for LazyCache {
 }
 
 #[derive(Debug)]
-struct TimedOutError(Duration);
+pub(super) struct TimedOutError(pub(super) Duration);
 
 impl std::error::Error for TimedOutError {}
 
@@ -393,7 +564,7 @@ mod tests {
     use aws_smithy_async::rt::sleep::TokioSleep;
     use aws_smithy_async::test_util::{instant_time_and_sleep, ManualTimeSource};
     use aws_smithy_async::time::TimeSource;
-    use aws_smithy_runtime_api::client::identity::http::Token;
+    use aws_smithy_runtime_api::client::identity::http::{Token, TokenCodec};
     use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex};
@@ -768,4 +939,170 @@ mod tests {
         assert_eq!(1, resolver_a_calls.load(Ordering::Relaxed));
         assert_eq!(1, resolver_b_calls.load(Ordering::Relaxed));
     }
+
+    #[derive(Debug, Default)]
+    struct MemoryStore(Mutex<HashMap<IdentityStoreKey, StoredIdentity>>);
+
+    impl StoreIdentity for MemoryStore {
+        fn load<'a>(
+            &'a self,
+            key: &'a IdentityStoreKey,
+        ) -> aws_smithy_runtime_api::client::identity::store::LoadIdentityFuture<'a> {
+            let stored = self.0.lock().unwrap().get(key).cloned();
+            aws_smithy_runtime_api::client::identity::store::LoadIdentityFuture::ready(Ok(stored))
+        }
+
+        fn save<'a>(
+            &'a self,
+            key: &'a IdentityStoreKey,
+            identity: StoredIdentity,
+        ) -> aws_smithy_runtime_api::client::identity::store::SaveIdentityFuture<'a> {
+            self.0.lock().unwrap().insert(key.clone(), identity);
+            aws_smithy_runtime_api::client::identity::store::SaveIdentityFuture::ready(Ok(()))
+        }
+    }
+
+    /// Store whose identities are locked by another process that never releases them.
+    #[derive(Debug, Default)]
+    struct LockedStore(MemoryStore);
+
+    impl StoreIdentity for LockedStore {
+        fn load<'a>(
+            &'a self,
+            key: &'a IdentityStoreKey,
+        ) -> aws_smithy_runtime_api::client::identity::store::LoadIdentityFuture<'a> {
+            self.0.load(key)
+        }
+
+        fn save<'a>(
+            &'a self,
+            key: &'a IdentityStoreKey,
+            identity: StoredIdentity,
+        ) -> aws_smithy_runtime_api::client::identity::store::SaveIdentityFuture<'a> {
+            self.0.save(key, identity)
+        }
+
+        fn lock<'a>(
+            &'a self,
+            _key: &'a IdentityStoreKey,
+        ) -> aws_smithy_runtime_api::client::identity::store::LockIdentityFuture<'a> {
+            aws_smithy_runtime_api::client::identity::store::LockIdentityFuture::new(
+                std::future::pending(),
+            )
+        }
+    }
+
+    #[derive(Debug)]
+    struct PersistedResolver {
+        key: Option<&'static str>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl ResolveIdentity for PersistedResolver {
+        fn resolve_identity<'a>(
+            &'a self,
+            _: &'a RuntimeComponents,
+            _config_bag: &'a ConfigBag,
+        ) -> IdentityFuture<'a> {
+            let call = self.calls.fetch_add(1, Ordering::Relaxed);
+            let expiration = Some(epoch_secs(1000 * (call as u64 + 1)));
+            IdentityFuture::ready(Ok(Token::new(format!("token-{call}"), expiration).into()))
+        }
+
+        fn persistent_cache_key(&self) -> Option<String> {
+            self.key.map(String::from)
+        }
+    }
+
+    #[tokio::test]
+    async fn persisted_identities_are_shared_between_caches() {
+        let time = ManualTimeSource::new(epoch_secs(100));
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(time.clone()))
+            .with_sleep_impl(Some(TokioSleep::new()))
+            .build()
+            .unwrap();
+        let config_bag = ConfigBag::base();
+        let store = SharedIdentityStore::new(MemoryStore::default());
+        let new_cache = || {
+            LazyCacheBuilder::new()
+                .buffer_time_jitter_fraction(BUFFER_TIME_NO_JITTER)
+                .persistent_store(store.clone(), TokenCodec::new())
+                .build()
+        };
+        let calls = Arc::new(AtomicUsize::new(0));
+        let new_resolver = |key| {
+            SharedIdentityResolver::new(PersistedResolver {
+                key,
+                calls: calls.clone(),
+            })
+        };
+        let token = |identity: Identity| identity.data::<Token>().unwrap().token().to_string();
+
+        let identity = new_cache()
+            .resolve_cached_identity(new_resolver(Some("resolver")), &components, &config_bag)
+            .await
+            .unwrap();
+        assert_eq!("token-0", token(identity));
+
+        // A new cache, as in a new process, loads the persisted identity
+        let identity = new_cache()
+            .resolve_cached_identity(new_resolver(Some("resolver")), &components, &config_bag)
+            .await
+            .unwrap();
+        assert_eq!("token-0", token(identity.clone()));
+        assert_eq!(Some(epoch_secs(1000)), identity.expiration());
+        assert_eq!(1, calls.load(Ordering::Relaxed));
+
+        // Persisted identities within the buffer time are resolved and persisted again
+        time.set_time(epoch_secs(995));
+        let identity = new_cache()
+            .resolve_cached_identity(new_resolver(Some("resolver")), &components, &config_bag)
+            .await
+            .unwrap();
+        assert_eq!("token-1", token(identity));
+        let identity = new_cache()
+            .resolve_cached_identity(new_resolver(Some("resolver")), &components, &config_bag)
+            .await
+            .unwrap();
+        assert_eq!("token-1", token(identity));
+        assert_eq!(2, calls.load(Ordering::Relaxed));
+
+        // Resolvers without a persistent cache key aren't persisted
+        for expected in ["token-2", "token-3"] {
+            let identity = new_cache()
+                .resolve_cached_identity(new_resolver(None), &components, &config_bag)
+                .await
+                .unwrap();
+            assert_eq!(expected, token(identity));
+        }
+    }
+
+    #[tokio::test]
+    async fn waiting_for_persisted_identity_locks_is_bounded_by_the_load_timeout() {
+        let components = RuntimeComponentsBuilder::for_tests()
+            .with_time_source(Some(ManualTimeSource::new(epoch_secs(100))))
+            .with_sleep_impl(Some(TokioSleep::new()))
+            .build()
+            .unwrap();
+        let cache = LazyCacheBuilder::new()
+            .load_timeout(Duration::from_millis(100))
+            .persistent_store(LockedStore::default(), TokenCodec::new())
+            .build();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let resolver = SharedIdentityResolver::new(PersistedResolver {
+            key: Some("resolver"),
+            calls: calls.clone(),
+        });
+
+        let identity = tokio::time::timeout(
+            Duration::from_secs(5),
+            cache.resolve_cached_identity(resolver, &components, &ConfigBag::base()),
+        )
+        .await
+        .expect("the lock wait should time out")
+        .unwrap();
+        assert_eq!("token-0", identity.data::<Token>().unwrap().token());
+        assert_eq!(1, calls.load(Ordering::Relaxed));
+    }
 }
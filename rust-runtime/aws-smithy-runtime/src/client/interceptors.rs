@@ -3,6 +3,7 @@
  * SPDX-License-Identifier: Apache-2.0
  */
 
+use crate::client::telemetry::Stopwatch;
 use aws_smithy_runtime_api::box_error::BoxError;
 use aws_smithy_runtime_api::client::interceptors::context::{
     BeforeSerializationInterceptorContextRef, BeforeTransmitInterceptorContextMut,
@@ -16,6 +17,7 @@ use aws_smithy_runtime_api::client::interceptors::{
 };
 use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::client::telemetry::{attributes, metrics};
 use aws_smithy_types::body::SdkBody;
 use aws_smithy_types::config_bag::ConfigBag;
 use aws_smithy_types::error::display::DisplayErrorContext;
@@ -36,6 +38,7 @@ macro_rules! interceptor_impl_fn {
                 stringify!($interceptor),
                 "` interceptors"
             ));
+            let stopwatch = Stopwatch::start(runtime_components, cfg);
             let mut result: Result<(), (&str, BoxError)> = Ok(());
             let mut ctx = ctx.into();
             for interceptor in self.into_iter() {
@@ -55,6 +58,14 @@ macro_rules! interceptor_impl_fn {
                     }
                 }
             }
+            stopwatch.stop_with(
+                runtime_components,
+                cfg,
+                &metrics::INTERCEPTOR_DURATION,
+                |attributes| {
+                    attributes.set(attributes::HOOK, stringify!($interceptor));
+                },
+            );
             result.map_err(|(name, err)| InterceptorError::$interceptor(name, err))
         }
     };
@@ -70,6 +81,7 @@ macro_rules! interceptor_impl_fn {
                 stringify!($interceptor),
                 "` interceptors"
             ));
+            let stopwatch = Stopwatch::start(runtime_components, cfg);
             let mut result: Result<(), (&str, BoxError)> = Ok(());
             let ctx = ctx.into();
             for interceptor in self.into_iter() {
@@ -88,6 +100,14 @@ macro_rules! interceptor_impl_fn {
                     }
                 }
             }
+            stopwatch.stop_with(
+                runtime_components,
+                cfg,
+                &metrics::INTERCEPTOR_DURATION,
+                |attributes| {
+                    attributes.set(attributes::HOOK, stringify!($interceptor));
+                },
+            );
             result.map_err(|(name, err)| InterceptorError::$interceptor(name, err))
         }
     };
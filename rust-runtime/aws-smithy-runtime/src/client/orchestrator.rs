@@ -4,8 +4,14 @@
  */
 
 use self::auth::orchestrate_auth;
+pub(crate) use self::auth::IdentityDiscriminator;
+use crate::client::circuit_breaker;
 use crate::client::interceptors::Interceptors;
 use crate::client::orchestrator::http::{log_response_body, read_body};
+use crate::client::response_cache;
+use crate::client::retries::concurrency_limiter;
+use crate::client::single_flight;
+use crate::client::telemetry::{self, Stopwatch};
 use crate::client::timeout::{MaybeTimeout, MaybeTimeoutConfig, TimeoutKind};
 use crate::client::{
     http::body::minimum_throughput::MaybeUploadThroughputCheckFuture,
@@ -27,6 +33,7 @@ use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugins;
 use aws_smithy_runtime_api::client::ser_de::{
     DeserializeResponse, SerializeRequest, SharedRequestSerializer, SharedResponseDeserializer,
 };
+use aws_smithy_runtime_api::client::telemetry::metrics;
 use aws_smithy_types::body::SdkBody;
 use aws_smithy_types::byte_stream::ByteStream;
 use aws_smithy_types::config_bag::ConfigBag;
@@ -36,6 +43,10 @@ use tracing::{debug, debug_span, instrument, trace, Instrument};
 
 mod auth;
 
+/// Hedged attempts for idempotent and read-only operations
+#[cfg(all(aws_sdk_unstable, feature = "unstable-hedging"))]
+mod hedging;
+
 /// Defines types that implement a trait for endpoint resolution
 pub mod endpoints;
 
@@ -152,13 +163,17 @@ pub async fn invoke_with_stop_point(
         let operation_timeout_config =
             MaybeTimeoutConfig::new(&runtime_components, cfg, TimeoutKind::Operation);
         trace!(operation_timeout_config = ?operation_timeout_config);
+        let call_stopwatch = Stopwatch::start(&runtime_components, cfg);
         async {
             // If running the pre-execution interceptors failed, then we skip running the op and run the
             // final interceptors instead.
             if !ctx.is_failed() {
                 try_op(&mut ctx, cfg, &runtime_components, stop_point).await;
+                response_cache::store(&ctx, &runtime_components, cfg);
             }
             finally_op(&mut ctx, cfg, &runtime_components).await;
+            call_stopwatch.stop(&runtime_components, cfg, &metrics::CALL_DURATION);
+            telemetry::record_call_error(&ctx, cfg);
             if ctx.is_failed() {
                 Err(ctx.finalize().expect_err("it is failed"))
             } else {
@@ -224,6 +239,7 @@ async fn try_op(
     ctx.enter_serialization_phase();
     {
         let _span = debug_span!("serialization").entered();
+        let stopwatch = Stopwatch::start(runtime_components, cfg);
         let request_serializer = cfg
             .load::<SharedRequestSerializer>()
             .expect("request serializer must be in the config bag")
@@ -231,6 +247,7 @@ async fn try_op(
         let input = ctx.take_input().expect("input set at this point");
         let request = halt_on_err!([ctx] => request_serializer.serialize_input(input, cfg).map_err(OrchestratorError::other));
         ctx.set_request(request);
+        stopwatch.stop(runtime_components, cfg, &metrics::SERIALIZATION_DURATION);
     }
 
     // Load the request body into memory if configured to do so
@@ -248,6 +265,8 @@ async fn try_op(
             .store_put(LoadedRequestBody::Loaded(loaded_body));
     }
 
+    response_cache::remember_serialized_request(ctx, cfg);
+
     // Before transmit
     ctx.enter_before_transmit_phase();
     run_interceptors!(halt_on_err: {
@@ -282,8 +301,24 @@ async fn try_op(
     // Save a request checkpoint before we make the request. This will allow us to "rewind"
     // the request in the case of retry attempts.
     ctx.save_checkpoint();
+    #[cfg(all(aws_sdk_unstable, feature = "unstable-hedging"))]
+    let mut hedge_base = hedging::HedgeBase::new(cfg);
     let mut retry_delay = None;
     for i in 1u32.. {
+        // Backoff time should not be included in the attempt timeout
+        if let Some((delay, sleep)) = retry_delay.take() {
+            debug!("delaying for {delay:?}");
+            sleep.await;
+        }
+        // Wait for a slot if a concurrency limit is configured. This happens before rewinding so
+        // that the result of the previous attempt is kept if a retry can't get a slot.
+        if let Err(err) = concurrency_limiter::reserve_slot(runtime_components, cfg).await {
+            if i == 1 {
+                halt!([ctx] => OrchestratorError::other(err));
+            }
+            debug!("not retrying because {err}");
+            break;
+        }
         // Break from the loop if we can't rewind the request's state. This will always succeed the
         // first time, but will fail on subsequent iterations if the request body wasn't retryable.
         trace!("checking if context can be rewound for attempt #{i}");
@@ -294,26 +329,38 @@ async fn try_op(
         // Track which attempt we're currently on.
         cfg.interceptor_state()
             .store_put::<RequestAttempts>(i.into());
-        // Backoff time should not be included in the attempt timeout
-        if let Some((delay, sleep)) = retry_delay.take() {
-            debug!("delaying for {delay:?}");
-            sleep.await;
-        }
         let attempt_timeout_config =
             MaybeTimeoutConfig::new(runtime_components, cfg, TimeoutKind::OperationAttempt);
         trace!(attempt_timeout_config = ?attempt_timeout_config);
+        telemetry::record(cfg, &metrics::CALL_ATTEMPTS, 1.0);
+        let attempt_stopwatch = Stopwatch::start(runtime_components, cfg);
         let maybe_timeout = async {
             debug!("beginning attempt #{i}");
-            try_attempt(ctx, cfg, runtime_components, stop_point).await;
-            finally_attempt(ctx, cfg, runtime_components).await;
+            #[cfg(all(aws_sdk_unstable, feature = "unstable-hedging"))]
+            hedging::try_hedged_attempt(
+                ctx,
+                cfg,
+                hedge_base.as_mut(),
+                runtime_components,
+                stop_point,
+                i,
+            )
+            .await;
+            #[cfg(not(all(aws_sdk_unstable, feature = "unstable-hedging")))]
+            {
+                try_attempt(ctx, cfg, runtime_components, stop_point).await;
+                finally_attempt(ctx, cfg, runtime_components).await;
+            }
             Result::<_, SdkError<Error, HttpResponse>>::Ok(())
         }
         .maybe_timeout(attempt_timeout_config)
         .await
         .map_err(|err| OrchestratorError::timeout(err.into_source().unwrap()));
+        attempt_stopwatch.stop(runtime_components, cfg, &metrics::ATTEMPT_DURATION);
 
         // We continue when encountering a timeout error. The retry classifier will decide what to do with it.
         continue_on_err!([ctx] => maybe_timeout);
+        circuit_breaker::record(ctx, runtime_components, cfg);
 
         // If we got a retry strategy from the bag, ask it what to do.
         // If no strategy was set, we won't retry.
@@ -349,14 +396,19 @@ async fn try_attempt(
 ) {
     run_interceptors!(halt_on_err: read_before_attempt(ctx, runtime_components, cfg));
 
+    let stopwatch = Stopwatch::start(runtime_components, cfg);
     halt_on_err!([ctx] => orchestrate_endpoint(ctx, runtime_components, cfg).await.map_err(OrchestratorError::other));
+    stopwatch.stop(runtime_components, cfg, &metrics::RESOLVE_ENDPOINT_DURATION);
+    halt_on_err!([ctx] => circuit_breaker::check(runtime_components, cfg).map_err(OrchestratorError::connector));
 
     run_interceptors!(halt_on_err: {
         modify_before_signing(ctx, runtime_components, cfg);
         read_before_signing(ctx, runtime_components, cfg);
     });
 
+    let stopwatch = Stopwatch::start(runtime_components, cfg);
     halt_on_err!([ctx] => orchestrate_auth(ctx, runtime_components, cfg).await.map_err(OrchestratorError::other));
+    stopwatch.stop(runtime_components, cfg, &metrics::SIGNING_DURATION);
 
     run_interceptors!(halt_on_err: {
         read_after_signing(ctx, runtime_components, cfg);
@@ -370,9 +422,16 @@ async fn try_attempt(
         return;
     }
 
+    // Answer from the response cache if it has an unexpired output for this request
+    if let Some(output) = response_cache::lookup(runtime_components, cfg) {
+        ctx.set_output_or_error(Ok(output));
+        return;
+    }
+
     // The connection consumes the request but we need to keep a copy of it
     // within the interceptor context, so we clone it here.
     ctx.enter_transmit_phase();
+    let stopwatch = Stopwatch::start(runtime_components, cfg);
     let response = halt_on_err!([ctx] => {
         let request = ctx.take_request().expect("set during serialization");
         trace!(request = ?request, "transmitting request");
@@ -382,19 +441,25 @@ async fn try_attempt(
         ));
         let timeout_config = cfg.load::<TimeoutConfig>().expect("timeout config must be set");
         let settings = {
-            let mut builder = HttpConnectorSettings::builder();
+            let mut builder = cfg
+                .load::<HttpConnectorSettings>()
+                .map(HttpConnectorSettings::to_builder)
+                .unwrap_or_default();
             builder.set_connect_timeout(timeout_config.connect_timeout());
             builder.set_read_timeout(timeout_config.read_timeout());
             builder.build()
         };
         let connector = http_client.http_connector(&settings, runtime_components);
-        let response_future = MaybeUploadThroughputCheckFuture::new(
-            cfg,
-            runtime_components,
-            connector.call(request),
-        );
+        let response_future = single_flight::transmit(cfg, |cfg| {
+            MaybeUploadThroughputCheckFuture::new(
+                cfg,
+                runtime_components,
+                connector.call(request),
+            )
+        });
         response_future.await.map_err(OrchestratorError::connector)
     });
+    stopwatch.stop(runtime_components, cfg, &metrics::TRANSMIT_DURATION);
     trace!(response = ?response, "received response from service");
     ctx.set_response(response);
     ctx.enter_before_deserialization_phase();
@@ -406,6 +471,7 @@ async fn try_attempt(
     });
 
     ctx.enter_deserialization_phase();
+    let stopwatch = Stopwatch::start(runtime_components, cfg);
     let output_or_error = async {
         let response = ctx.response_mut().expect("set during transmit");
         let response_deserializer = cfg
@@ -430,6 +496,7 @@ async fn try_attempt(
     }
     .instrument(debug_span!("deserialization"))
     .await;
+    stopwatch.stop(runtime_components, cfg, &metrics::DESERIALIZATION_DURATION);
     trace!(output_or_error = ?output_or_error);
     ctx.set_output_or_error(output_or_error);
 
@@ -11,10 +11,12 @@ use aws_smithy_runtime_api::client::auth::{
     ResolveAuthSchemeOptions,
 };
 use aws_smithy_runtime_api::client::identity::ResolveIdentity;
-use aws_smithy_runtime_api::client::identity::{IdentityCacheLocation, ResolveCachedIdentity};
+use aws_smithy_runtime_api::client::identity::{
+    IdentityCacheLocation, IdentityCachePartition, ResolveCachedIdentity,
+};
 use aws_smithy_runtime_api::client::interceptors::context::InterceptorContext;
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
-use aws_smithy_types::config_bag::ConfigBag;
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
 use aws_smithy_types::endpoint::Endpoint;
 use aws_smithy_types::Document;
 use std::borrow::Cow;
@@ -22,6 +24,33 @@ use std::error::Error as StdError;
 use std::fmt;
 use tracing::trace;
 
+/// Identifies the identity that a request was signed with, without holding on to the identity.
+///
+/// Every configured identity resolver has its own cache partition, so requests signed with
+/// identities from different resolvers have different discriminators. This is stored in the
+/// config bag once the request has been signed, so that responses received with one identity are
+/// never shared with callers using another.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub(crate) struct IdentityDiscriminator {
+    auth_scheme: AuthSchemeId,
+    identity_resolver: IdentityCachePartition,
+}
+
+impl Storable for IdentityDiscriminator {
+    type Storer = StoreReplace<Self>;
+}
+
+#[cfg(test)]
+impl IdentityDiscriminator {
+    /// Creates a discriminator for a new, unique identity resolver.
+    pub(crate) fn for_tests() -> Self {
+        Self {
+            auth_scheme: NO_AUTH_SCHEME_ID,
+            identity_resolver: IdentityCachePartition::new(),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct NoMatchingAuthSchemeError(ExploredList);
 
@@ -112,7 +141,7 @@ impl StdError for AuthOrchestrationError {}
 pub(super) async fn orchestrate_auth(
     ctx: &mut InterceptorContext,
     runtime_components: &RuntimeComponents,
-    cfg: &ConfigBag,
+    cfg: &mut ConfigBag,
 ) -> Result<(), BoxError> {
     let params = cfg
         .load::<AuthSchemeOptionResolverParams>()
@@ -157,6 +186,10 @@ pub(super) async fn orchestrate_auth(
                     Ok(auth_scheme_endpoint_config) => {
                         trace!(auth_scheme_endpoint_config = ?auth_scheme_endpoint_config, "extracted auth scheme endpoint config");
 
+                        let discriminator = IdentityDiscriminator {
+                            auth_scheme: scheme_id,
+                            identity_resolver: identity_resolver.cache_partition(),
+                        };
                         let identity = identity_cache
                             .resolve_cached_identity(identity_resolver, runtime_components, cfg)
                             .await?;
@@ -171,6 +204,7 @@ pub(super) async fn orchestrate_auth(
                             runtime_components,
                             cfg,
                         )?;
+                        cfg.interceptor_state().store_put(discriminator);
                         return Ok(());
                     }
                     Err(AuthOrchestrationError::MissingEndpointConfig) => {
@@ -374,9 +408,9 @@ mod tests {
         let mut layer: Layer = Layer::new("test");
         layer.store_put(AuthSchemeOptionResolverParams::new("doesntmatter"));
         layer.store_put(Endpoint::builder().url("dontcare").build());
-        let cfg = ConfigBag::of_layers(vec![layer]);
+        let mut cfg = ConfigBag::of_layers(vec![layer]);
 
-        orchestrate_auth(&mut ctx, &runtime_components, &cfg)
+        orchestrate_auth(&mut ctx, &runtime_components, &mut cfg)
             .await
             .expect("success");
 
@@ -430,9 +464,9 @@ mod tests {
         }
 
         // First, test the presence of a basic auth login and absence of a bearer token
-        let (runtime_components, cfg) =
+        let (runtime_components, mut cfg) =
             config_with_identity(HTTP_BASIC_AUTH_SCHEME_ID, Login::new("a", "b", None));
-        orchestrate_auth(&mut ctx, &runtime_components, &cfg)
+        orchestrate_auth(&mut ctx, &runtime_components, &mut cfg)
             .await
             .expect("success");
         assert_eq!(
@@ -446,14 +480,14 @@ mod tests {
         );
 
         // Next, test the presence of a bearer token and absence of basic auth
-        let (runtime_components, cfg) =
+        let (runtime_components, mut cfg) =
             config_with_identity(HTTP_BEARER_AUTH_SCHEME_ID, Token::new("t", None));
         let mut ctx = InterceptorContext::new(Input::erase("doesnt-matter"));
         ctx.enter_serialization_phase();
         ctx.set_request(HttpRequest::empty());
         let _ = ctx.take_input();
         ctx.enter_before_transmit_phase();
-        orchestrate_auth(&mut ctx, &runtime_components, &cfg)
+        orchestrate_auth(&mut ctx, &runtime_components, &mut cfg)
             .await
             .expect("success");
         assert_eq!(
@@ -606,9 +640,9 @@ mod tests {
         let mut layer = Layer::new("test");
         layer.store_put(Endpoint::builder().url("dontcare").build());
         layer.store_put(AuthSchemeOptionResolverParams::new("doesntmatter"));
-        let config_bag = ConfigBag::of_layers(vec![layer]);
+        let mut config_bag = ConfigBag::of_layers(vec![layer]);
 
-        orchestrate_auth(&mut ctx, &runtime_components, &config_bag)
+        orchestrate_auth(&mut ctx, &runtime_components, &mut config_bag)
             .await
             .expect("success");
         assert_eq!(
@@ -0,0 +1,608 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Hedged attempts for idempotent and read-only operations.
+//!
+//! If an attempt takes longer than a percentile of the latency recently observed for the same
+//! operation, a second attempt is sent from the same request checkpoint. Whichever attempt succeeds
+//! first is used, and the other one is cancelled.
+
+use super::{finally_attempt, try_attempt, StopPoint};
+use crate::client::retries::concurrency_limiter::{self, InFlightAttempt};
+use crate::client::retries::{RetryPartition, TokenBucket};
+use crate::static_partition_map::StaticPartitionMap;
+use aws_smithy_async::rt::sleep::{AsyncSleep, SharedAsyncSleep};
+use aws_smithy_async::time::SharedTimeSource;
+use aws_smithy_runtime_api::client::hedging::{HedgedAttempt, HedgingConfig, OperationIdempotency};
+use aws_smithy_runtime_api::client::interceptors::context::InterceptorContext;
+use aws_smithy_runtime_api::client::orchestrator::{Metadata, OrchestratorError};
+use aws_smithy_runtime_api::client::retries::RequestAttempts;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::config_bag::{ConfigBag, Layer};
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::mem;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+/// The number of recent latencies kept for each operation.
+const MAX_SAMPLES: usize = 128;
+/// The number of latencies that must be observed before the configured percentile is used.
+const MIN_SAMPLES: usize = 16;
+
+static LATENCY_TRACKERS: StaticPartitionMap<HedgingPartition, LatencyTracker> =
+    StaticPartitionMap::new();
+
+/// Latencies are tracked separately for each operation of each retry partition.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct HedgingPartition {
+    retry_partition: RetryPartition,
+    operation: String,
+}
+
+/// Keeps track of the latency of the most recent successful attempts.
+#[derive(Clone, Debug, Default)]
+struct LatencyTracker {
+    samples: Arc<Mutex<VecDeque<Duration>>>,
+}
+
+impl LatencyTracker {
+    fn record(&self, latency: Duration) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(latency);
+    }
+
+    fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut samples: Vec<_> = self.samples.lock().unwrap().iter().copied().collect();
+        if samples.len() < MIN_SAMPLES {
+            return None;
+        }
+        samples.sort_unstable();
+        let index = ((samples.len() - 1) as f64 * percentile).round() as usize;
+        samples.get(index).copied()
+    }
+}
+
+/// The config bag that hedged attempts are forked from.
+///
+/// This is forked from the operation's config bag once, before the first attempt, so that making
+/// a hedged attempt doesn't add another layer to the bag each time. Its interceptor state is never
+/// written to, so forking it again only shares its layers.
+pub(super) struct HedgeBase {
+    cfg: ConfigBag,
+}
+
+impl HedgeBase {
+    /// Returns `None` if hedging is disabled or doesn't apply to this operation.
+    pub(super) fn new(cfg: &mut ConfigBag) -> Option<Self> {
+        let enabled = cfg
+            .load::<HedgingConfig>()
+            .is_some_and(HedgingConfig::is_enabled);
+        if !enabled || cfg.load::<OperationIdempotency>().is_none() {
+            return None;
+        }
+        Some(Self { cfg: cfg.fork() })
+    }
+
+    fn fork(&mut self) -> ConfigBag {
+        self.cfg.fork()
+    }
+}
+
+/// Everything needed to send a hedged attempt.
+struct Hedge {
+    attempt: u32,
+    delay: Duration,
+    tracker: Option<LatencyTracker>,
+    sleep_impl: SharedAsyncSleep,
+    time_source: SharedTimeSource,
+    token_bucket: Option<TokenBucket>,
+}
+
+impl Hedge {
+    fn new(cfg: &ConfigBag, runtime_components: &RuntimeComponents, attempt: u32) -> Option<Self> {
+        let config = cfg.load::<HedgingConfig>()?;
+        let (Some(sleep_impl), Some(time_source)) = (
+            runtime_components.sleep_impl(),
+            runtime_components.time_source(),
+        ) else {
+            debug!("hedging requires an async sleep implementation and a time source");
+            return None;
+        };
+        let tracker = cfg.load::<RetryPartition>().map(|retry_partition| {
+            LATENCY_TRACKERS.get_or_init_default(HedgingPartition {
+                retry_partition: retry_partition.clone(),
+                operation: cfg
+                    .load::<Metadata>()
+                    .map(|metadata| metadata.name().to_string())
+                    .unwrap_or_default(),
+            })
+        });
+        let delay = tracker
+            .as_ref()
+            .and_then(|tracker| tracker.percentile(config.percentile()))
+            .unwrap_or_else(|| config.initial_delay())
+            .max(config.min_delay());
+        Some(Self {
+            attempt,
+            delay,
+            tracker,
+            sleep_impl,
+            time_source,
+            token_bucket: cfg.load::<TokenBucket>().cloned(),
+        })
+    }
+
+    fn record_latency(&self, start: SystemTime) {
+        if let (Some(tracker), Ok(latency)) = (
+            self.tracker.as_ref(),
+            self.time_source.now().duration_since(start),
+        ) {
+            tracker.record(latency);
+        }
+    }
+}
+
+enum Winner {
+    /// The original attempt finished first, or both attempts failed.
+    Attempt {
+        succeeded: bool,
+        /// Whether a hedged attempt was sent and cancelled before it finished.
+        hedge_cancelled: bool,
+    },
+    /// The hedged attempt succeeded first.
+    Hedge {
+        start: SystemTime,
+        /// Whether the original attempt was cancelled before it finished.
+        attempt_cancelled: bool,
+    },
+}
+
+/// Makes an attempt, and races it against a hedged attempt if hedging applies to the operation.
+///
+/// If the hedged attempt wins, its context replaces the one passed in, and what it stored in its
+/// config bag is merged into the one passed in, so the retry strategy and the rest of the
+/// orchestrator see the result of the winning attempt. An attempt that is cancelled because the
+/// other one won still runs its attempt completion interceptors.
+pub(super) async fn try_hedged_attempt(
+    ctx: &mut InterceptorContext,
+    cfg: &mut ConfigBag,
+    hedge_base: Option<&mut HedgeBase>,
+    runtime_components: &RuntimeComponents,
+    stop_point: StopPoint,
+    attempt: u32,
+) {
+    let hedge = hedge_base.and_then(|base| {
+        let hedge = Hedge::new(cfg, runtime_components, attempt)?;
+        Some((hedge, base))
+    });
+    let (hedge, hedge_base, mut hedge_ctx) = match (hedge, ctx.fork_from_checkpoint()) {
+        (Some((hedge, hedge_base)), Some(hedge_ctx)) => (hedge, hedge_base, hedge_ctx),
+        _ => {
+            run_attempt(ctx, cfg, runtime_components, stop_point).await;
+            return;
+        }
+    };
+    let mut hedge_cfg = hedge_base.fork();
+
+    let start = hedge.time_source.now();
+    let winner = race(
+        (ctx, cfg),
+        (&mut hedge_ctx, &mut hedge_cfg),
+        runtime_components,
+        stop_point,
+        &hedge,
+    )
+    .await;
+    match winner {
+        Winner::Attempt {
+            succeeded,
+            hedge_cancelled,
+        } => {
+            if succeeded {
+                hedge.record_latency(start);
+            }
+            if hedge_cancelled {
+                finish_cancelled_attempt(&mut hedge_ctx, &mut hedge_cfg, runtime_components).await;
+            }
+        }
+        Winner::Hedge {
+            start,
+            attempt_cancelled,
+        } => {
+            hedge.record_latency(start);
+            if attempt_cancelled {
+                finish_cancelled_attempt(ctx, cfg, runtime_components).await;
+            }
+            mem::swap(ctx, &mut hedge_ctx);
+            // What the original attempt stored is kept, but what the hedged attempt stored takes
+            // precedence over it
+            let hedge_state = mem::replace(
+                hedge_cfg.interceptor_state(),
+                Layer::new("interceptor_state"),
+            );
+            let attempt_state = mem::replace(cfg.interceptor_state(), hedge_state);
+            // The slot of the original attempt would otherwise be held until the operation ends,
+            // since the retry strategy only releases the slot of the winning attempt
+            if let Some(in_flight) = attempt_state.load::<InFlightAttempt>() {
+                in_flight.finish(None, false);
+            }
+            cfg.push_layer(attempt_state.with_name("hedged_out_attempt"));
+            cfg.interceptor_state().unset::<HedgedAttempt>();
+        }
+    }
+}
+
+/// Fails an attempt that was cancelled because the other attempt of the race won, and runs its
+/// attempt completion interceptors.
+async fn finish_cancelled_attempt(
+    ctx: &mut InterceptorContext,
+    cfg: &mut ConfigBag,
+    runtime_components: &RuntimeComponents,
+) {
+    ctx.fail(OrchestratorError::other(
+        "the attempt was cancelled because a concurrent attempt succeeded first",
+    ));
+    finally_attempt(ctx, cfg, runtime_components).await;
+}
+
+async fn race(
+    (ctx, cfg): (&mut InterceptorContext, &mut ConfigBag),
+    (hedge_ctx, hedge_cfg): (&mut InterceptorContext, &mut ConfigBag),
+    runtime_components: &RuntimeComponents,
+    stop_point: StopPoint,
+    hedge: &Hedge,
+) -> Winner {
+    let mut attempt = pin!(run_attempt(ctx, cfg, runtime_components, stop_point));
+    let mut delay = pin!(hedge.sleep_impl.sleep(hedge.delay));
+    let finished = poll_fn(|cx| {
+        if let Poll::Ready(succeeded) = attempt.as_mut().poll(cx) {
+            return Poll::Ready(Some(succeeded));
+        }
+        delay.as_mut().poll(cx).map(|_| None)
+    })
+    .await;
+    if let Some(succeeded) = finished {
+        return Winner::Attempt {
+            succeeded,
+            hedge_cancelled: false,
+        };
+    }
+
+    let permit = match hedge.token_bucket.as_ref() {
+        Some(token_bucket) => match token_bucket.acquire_hedge_permit() {
+            Some(permit) => Some(permit),
+            None => {
+                debug!("no retry permits are available, so no hedged attempt will be made");
+                let succeeded = attempt.await;
+                return Winner::Attempt {
+                    succeeded,
+                    hedge_cancelled: false,
+                };
+            }
+        },
+        None => None,
+    };
+
+    if !concurrency_limiter::try_reserve_slot(hedge_cfg) {
+        debug!("the concurrency limit has been reached, so no hedged attempt will be made");
+        let succeeded = attempt.await;
+        return Winner::Attempt {
+            succeeded,
+            hedge_cancelled: false,
+        };
+    }
+
+    debug!(
+        "attempt #{} is taking longer than {:?}; sending a hedged attempt",
+        hedge.attempt, hedge.delay
+    );
+    hedge_cfg
+        .interceptor_state()
+        .store_put(HedgedAttempt::new(hedge.attempt))
+        .store_put::<RequestAttempts>(hedge.attempt.into());
+    let hedge_start = hedge.time_source.now();
+    let mut hedged = pin!(run_attempt(
+        hedge_ctx,
+        hedge_cfg,
+        runtime_components,
+        stop_point
+    ));
+    let (mut attempt_result, mut hedge_result) = (None, None);
+    poll_fn(|cx| {
+        if attempt_result.is_none() {
+            if let Poll::Ready(succeeded) = attempt.as_mut().poll(cx) {
+                attempt_result = Some(succeeded);
+            }
+        }
+        if hedge_result.is_none() {
+            if let Poll::Ready(succeeded) = hedged.as_mut().poll(cx) {
+                hedge_result = Some(succeeded);
+            }
+        }
+        match (attempt_result, hedge_result) {
+            (Some(true), _) | (_, Some(true)) | (Some(false), Some(false)) => Poll::Ready(()),
+            _ => Poll::Pending,
+        }
+    })
+    .await;
+
+    // A hedged attempt that failed consumes its permit, just like a failed retry would. Otherwise,
+    // the permit is released back to the bucket when it's dropped.
+    if let (Some(false), Some(permit)) = (hedge_result, permit) {
+        permit.forget();
+    }
+    match (attempt_result, hedge_result) {
+        (Some(true), _) => {
+            debug!(
+                "attempt #{} won the race against its hedged attempt",
+                hedge.attempt
+            );
+            Winner::Attempt {
+                succeeded: true,
+                hedge_cancelled: hedge_result.is_none(),
+            }
+        }
+        (_, Some(true)) => {
+            debug!(
+                "the hedged attempt for attempt #{} won the race",
+                hedge.attempt
+            );
+            Winner::Hedge {
+                start: hedge_start,
+                attempt_cancelled: attempt_result.is_none(),
+            }
+        }
+        _ => Winner::Attempt {
+            succeeded: false,
+            hedge_cancelled: false,
+        },
+    }
+}
+
+async fn run_attempt(
+    ctx: &mut InterceptorContext,
+    cfg: &mut ConfigBag,
+    runtime_components: &RuntimeComponents,
+    stop_point: StopPoint,
+) -> bool {
+    try_attempt(ctx, cfg, runtime_components, stop_point).await;
+    finally_attempt(ctx, cfg, runtime_components).await;
+    !ctx.is_failed()
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::client::orchestrator::operation::Operation;
+    use crate::client::retries::TokenBucket;
+    use aws_smithy_async::rt::sleep::TokioSleep;
+    use aws_smithy_async::time::SystemTimeSource;
+    use aws_smithy_runtime_api::box_error::BoxError;
+    use aws_smithy_runtime_api::client::http::{
+        http_client_fn, HttpConnector, HttpConnectorFuture,
+    };
+    use aws_smithy_runtime_api::client::interceptors::context::{
+        BeforeTransmitInterceptorContextRef, FinalizerInterceptorContextRef,
+    };
+    use aws_smithy_runtime_api::client::interceptors::Intercept;
+    use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+    use aws_smithy_runtime_api::client::runtime_plugin::StaticRuntimePlugin;
+    use aws_smithy_runtime_api::shared::IntoShared;
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::config_bag::{Storable, StoreReplace};
+    use aws_smithy_types::timeout::TimeoutConfig;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Takes ten seconds to respond to the first request, and responds to the others immediately.
+    #[derive(Clone, Debug, Default)]
+    struct SlowFirstResponse {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl HttpConnector for SlowFirstResponse {
+        fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            HttpConnectorFuture::new(async move {
+                if call == 0 {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                }
+                Ok(HttpResponse::try_from(
+                    ::http::Response::builder()
+                        .status(200)
+                        .body(SdkBody::from(format!("response #{call}")))
+                        .unwrap(),
+                )
+                .unwrap())
+            })
+        }
+    }
+
+    /// Stored by the original attempt, after the config bag of hedged attempts was forked.
+    #[derive(Debug)]
+    struct StoredByAttempt;
+
+    impl Storable for StoredByAttempt {
+        type Storer = StoreReplace<Self>;
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct RecordHedgedAttempts {
+        hedged: Arc<Mutex<Vec<Option<u32>>>>,
+        /// Whether each attempt that completed was hedged, and whether it failed.
+        completed: Arc<Mutex<Vec<(Option<u32>, bool)>>>,
+        /// Whether the original attempt's state was still there once the operation completed.
+        kept_attempt_state: Arc<Mutex<Option<bool>>>,
+    }
+
+    impl Intercept for RecordHedgedAttempts {
+        fn name(&self) -> &'static str {
+            "RecordHedgedAttempts"
+        }
+
+        fn read_before_transmit(
+            &self,
+            _context: &BeforeTransmitInterceptorContextRef<'_>,
+            _runtime_components: &RuntimeComponents,
+            cfg: &mut ConfigBag,
+        ) -> Result<(), BoxError> {
+            let hedged = cfg.load::<HedgedAttempt>().map(HedgedAttempt::attempt);
+            if hedged.is_none() {
+                cfg.interceptor_state().store_put(StoredByAttempt);
+            }
+            self.hedged.lock().unwrap().push(hedged);
+            Ok(())
+        }
+
+        fn read_after_attempt(
+            &self,
+            context: &FinalizerInterceptorContextRef<'_>,
+            _runtime_components: &RuntimeComponents,
+            cfg: &mut ConfigBag,
+        ) -> Result<(), BoxError> {
+            let hedged = cfg.load::<HedgedAttempt>().map(HedgedAttempt::attempt);
+            let failed = matches!(context.output_or_error(), Some(Err(_)));
+            self.completed.lock().unwrap().push((hedged, failed));
+            Ok(())
+        }
+
+        fn read_after_execution(
+            &self,
+            _context: &FinalizerInterceptorContextRef<'_>,
+            _runtime_components: &RuntimeComponents,
+            cfg: &mut ConfigBag,
+        ) -> Result<(), BoxError> {
+            *self.kept_attempt_state.lock().unwrap() =
+                Some(cfg.load::<StoredByAttempt>().is_some());
+            Ok(())
+        }
+    }
+
+    fn operation(
+        connector: SlowFirstResponse,
+        interceptor: RecordHedgedAttempts,
+        idempotency: Option<OperationIdempotency>,
+        token_bucket: TokenBucket,
+    ) -> Operation<(), String, Infallible> {
+        let mut layer = Layer::new("test");
+        layer.store_put(token_bucket);
+        if let Some(idempotency) = idempotency {
+            layer.store_put(idempotency);
+        }
+        Operation::builder()
+            .service_name("test")
+            .operation_name("test")
+            .http_client(http_client_fn(move |_, _| connector.clone().into_shared()))
+            .endpoint_url("http://localhost:1234")
+            .no_auth()
+            .no_retry()
+            .timeout_config(TimeoutConfig::disabled())
+            .sleep_impl(TokioSleep::new())
+            .time_source(SystemTimeSource::new())
+            .interceptor(interceptor)
+            .hedging(
+                HedgingConfig::enabled()
+                    .initial_delay(Duration::from_millis(100))
+                    .build(),
+            )
+            .runtime_plugin(StaticRuntimePlugin::new().with_config(layer.freeze()))
+            .serializer(|_: ()| Ok(HttpRequest::new(SdkBody::empty())))
+            .deserializer::<_, Infallible>(|response| {
+                Ok(std::str::from_utf8(response.body().bytes().unwrap())
+                    .unwrap()
+                    .to_string())
+            })
+            .build()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hedged_attempt_wins() {
+        let connector = SlowFirstResponse::default();
+        let interceptor = RecordHedgedAttempts::default();
+        let operation = operation(
+            connector.clone(),
+            interceptor.clone(),
+            Some(OperationIdempotency::ReadOnly),
+            TokenBucket::default(),
+        );
+
+        let output = operation.invoke(()).await.expect("success");
+        assert_eq!("response #1", output);
+        assert_eq!(2, connector.calls.load(Ordering::SeqCst));
+        assert_eq!(vec![None, Some(1)], *interceptor.hedged.lock().unwrap());
+        // The original attempt is cancelled, and completes after the hedged attempt
+        assert_eq!(
+            vec![(Some(1), false), (None, true)],
+            *interceptor.completed.lock().unwrap()
+        );
+        assert_eq!(Some(true), *interceptor.kept_attempt_state.lock().unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_hedged_attempt_for_operations_that_arent_idempotent() {
+        let connector = SlowFirstResponse::default();
+        let interceptor = RecordHedgedAttempts::default();
+        let operation = operation(
+            connector.clone(),
+            interceptor.clone(),
+            None,
+            TokenBucket::default(),
+        );
+
+        let output = operation.invoke(()).await.expect("success");
+        assert_eq!("response #0", output);
+        assert_eq!(1, connector.calls.load(Ordering::SeqCst));
+        assert_eq!(vec![None], *interceptor.hedged.lock().unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_hedged_attempt_without_retry_permits() {
+        let connector = SlowFirstResponse::default();
+        let interceptor = RecordHedgedAttempts::default();
+        let operation = operation(
+            connector.clone(),
+            interceptor.clone(),
+            Some(OperationIdempotency::Idempotent),
+            TokenBucket::new(0),
+        );
+
+        let output = operation.invoke(()).await.expect("success");
+        assert_eq!("response #0", output);
+        assert_eq!(1, connector.calls.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn latency_percentile() {
+        let tracker = LatencyTracker::default();
+        for millis in 1..MIN_SAMPLES as u64 {
+            tracker.record(Duration::from_millis(millis));
+        }
+        assert_eq!(None, tracker.percentile(0.5), "not enough samples yet");
+
+        tracker.record(Duration::from_millis(MIN_SAMPLES as u64));
+        assert_eq!(Some(Duration::from_millis(16)), tracker.percentile(1.0));
+        assert_eq!(Some(Duration::from_millis(9)), tracker.percentile(0.5));
+        assert_eq!(Some(Duration::from_millis(1)), tracker.percentile(0.0));
+    }
+
+    #[test]
+    fn latency_tracker_keeps_recent_samples() {
+        let tracker = LatencyTracker::default();
+        for _ in 0..MAX_SAMPLES {
+            tracker.record(Duration::from_secs(10));
+        }
+        for _ in 0..MAX_SAMPLES {
+            tracker.record(Duration::from_millis(10));
+        }
+        assert_eq!(Some(Duration::from_millis(10)), tracker.percentile(1.0));
+    }
+}
@@ -17,12 +17,13 @@ use aws_smithy_runtime_api::client::auth::{
     AuthSchemeOptionResolverParams, SharedAuthScheme, SharedAuthSchemeOptionResolver,
 };
 use aws_smithy_runtime_api::client::endpoint::{EndpointResolverParams, SharedEndpointResolver};
+use aws_smithy_runtime_api::client::hedging::{HedgingConfig, OperationIdempotency};
 use aws_smithy_runtime_api::client::http::HttpClient;
 use aws_smithy_runtime_api::client::identity::SharedIdentityResolver;
 use aws_smithy_runtime_api::client::interceptors::context::{Error, Input, Output};
 use aws_smithy_runtime_api::client::interceptors::Intercept;
 use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
-use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, OrchestratorError};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, Metadata, OrchestratorError};
 use aws_smithy_runtime_api::client::result::SdkError;
 use aws_smithy_runtime_api::client::retries::classifiers::ClassifyRetry;
 use aws_smithy_runtime_api::client::retries::SharedRetryStrategy;
@@ -304,6 +305,20 @@ impl<I, O, E> OperationBuilder<I, O, E> {
         self
     }
 
+    /// Configures hedged requests with the given config.
+    ///
+    /// Hedging only applies if the operation's [`OperationIdempotency`] is also configured.
+    pub fn hedging(mut self, hedging: HedgingConfig) -> Self {
+        self.config.store_put(hedging);
+        self
+    }
+
+    /// Marks the operation as safe to send more than once.
+    pub fn idempotency(mut self, idempotency: OperationIdempotency) -> Self {
+        self.config.store_put(idempotency);
+        self
+    }
+
     /// Configures the serializer for the builder.
     pub fn serializer<I2>(
         mut self,
@@ -373,9 +388,11 @@ impl<I, O, E> OperationBuilder<I, O, E> {
     }
 
     /// Creates an `Operation` from the builder.
-    pub fn build(self) -> Operation<I, O, E> {
+    pub fn build(mut self) -> Operation<I, O, E> {
         let service_name = self.service_name.expect("service_name required");
         let operation_name = self.operation_name.expect("operation_name required");
+        self.config
+            .store_put(Metadata::new(operation_name.clone(), service_name.clone()));
 
         let mut runtime_plugins = RuntimePlugins::new()
             .with_client_plugins(default_plugins(
@@ -0,0 +1,732 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Caching of deserialized outputs for read-only operations.
+//!
+//! When a [`ResponseCache`] is added to a client with [`ResponseCacheRuntimePlugin`], successful
+//! outputs of operations marked [`OperationIdempotency::ReadOnly`] are cached, keyed by the
+//! serialized request, the resolved endpoint, and the identity the request is signed with. Later
+//! calls that serialize to the same request, for the same endpoint and identity, are answered from
+//! the cache without being sent, until the cached output expires.
+//!
+//! The cache is checked right before the request would be transmitted, so every interceptor hook
+//! up to and including `read_before_transmit` runs for calls answered from the cache, as do the
+//! hooks that run once an attempt or the operation has completed. The hooks that read or modify the
+//! response (`read_after_transmit` through `read_after_deserialization`) are skipped, since no
+//! response is received.
+//!
+//! Only outputs that can be cloned are cached. Code generated clients make outputs of read-only
+//! operations cloneable unless they have a streaming body.
+
+use crate::client::orchestrator::IdentityDiscriminator;
+use crate::client::single_flight::SingleFlight;
+use aws_smithy_runtime_api::client::hedging::OperationIdempotency;
+use aws_smithy_runtime_api::client::interceptors::context::{InterceptorContext, Output};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, Metadata};
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugin;
+use aws_smithy_types::config_bag::{ConfigBag, FrozenLayer, Layer, Storable, StoreReplace};
+use aws_smithy_types::endpoint::Endpoint;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+
+/// Identifies a cached output.
+///
+/// The key includes the service and operation names, the method, URI, headers, and body of the
+/// request as it was serialized, before any interceptors modified it, the URL of the resolved
+/// endpoint, and the identity that the request is signed with.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ResponseCacheKey {
+    service: String,
+    operation: String,
+    endpoint: String,
+    identity: IdentityDiscriminator,
+    request: Vec<u8>,
+}
+
+impl ResponseCacheKey {
+    /// Returns the name of the service that the cached output came from.
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Returns the name of the operation that the cached output came from.
+    pub fn operation(&self) -> &str {
+        &self.operation
+    }
+
+    /// Returns the URL of the endpoint that the cached output came from.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Creates a key for the current attempt. Returns `None` if the request wasn't remembered after
+    /// serialization, or if the endpoint or identity haven't been resolved.
+    fn from_config(cfg: &ConfigBag) -> Option<Self> {
+        let serialized = cfg.load::<SerializedRequest>()?;
+        Some(Self {
+            service: serialized.service.clone(),
+            operation: serialized.operation.clone(),
+            endpoint: cfg.load::<Endpoint>()?.url().into(),
+            identity: *cfg.load::<IdentityDiscriminator>()?,
+            request: serialized.request.clone(),
+        })
+    }
+}
+
+/// The serialized request of a read-only operation, stored in the config bag so that it can be
+/// combined with the endpoint and identity once they have been resolved.
+#[derive(Clone, Debug)]
+pub(crate) struct SerializedRequest {
+    pub(crate) service: String,
+    pub(crate) operation: String,
+    pub(crate) request: Vec<u8>,
+}
+
+impl Storable for SerializedRequest {
+    type Storer = StoreReplace<Self>;
+}
+
+/// Remembers the serialized request of a read-only operation, so that identical requests can be
+/// answered from the response cache or coalesced with single-flight.
+///
+/// This is called by the orchestrator right after serialization, before interceptors have a chance
+/// to add headers that are unique to each request.
+pub(crate) fn remember_serialized_request(ctx: &InterceptorContext, cfg: &mut ConfigBag) {
+    if (cfg.load::<ResponseCache>().is_none() && cfg.load::<SingleFlight>().is_none())
+        || cfg.load::<OperationIdempotency>() != Some(&OperationIdempotency::ReadOnly)
+    {
+        return;
+    }
+    let (Some(metadata), Some(request)) = (
+        cfg.load::<Metadata>(),
+        ctx.request().and_then(canonical_request),
+    ) else {
+        return;
+    };
+    let serialized = SerializedRequest {
+        service: metadata.service().into(),
+        operation: metadata.name().into(),
+        request,
+    };
+    cfg.interceptor_state().store_put(serialized);
+}
+
+/// Returns the method, URI, sorted headers, and body of a request as a single byte string, so that
+/// identical requests can be detected. Returns `None` if the request body isn't loaded into memory.
+pub(crate) fn canonical_request(request: &HttpRequest) -> Option<Vec<u8>> {
+    let body = request.body().bytes()?;
+    let mut headers: Vec<_> = request.headers().iter().collect();
+    headers.sort_unstable();
+
+    let mut canonical = Vec::with_capacity(request.uri().len() + body.len() + 64);
+    canonical.extend_from_slice(request.method().as_bytes());
+    canonical.push(b'\n');
+    canonical.extend_from_slice(request.uri().as_bytes());
+    canonical.push(b'\n');
+    for (name, value) in headers {
+        canonical.extend_from_slice(name.as_bytes());
+        canonical.push(b':');
+        canonical.extend_from_slice(value.as_bytes());
+        canonical.push(b'\n');
+    }
+    canonical.push(b'\n');
+    canonical.extend_from_slice(body);
+    Some(canonical)
+}
+
+impl Storable for ResponseCacheKey {
+    type Storer = StoreReplace<Self>;
+}
+
+/// A cached output and the time at which it expires.
+#[derive(Debug)]
+pub struct CachedOutput {
+    output: Output,
+    expires_at: SystemTime,
+}
+
+impl CachedOutput {
+    /// Creates a new `CachedOutput`.
+    ///
+    /// The output must have been created with [`Output::erase_with_clone`] so that it can be
+    /// returned more than once.
+    pub fn new(output: Output, expires_at: SystemTime) -> Self {
+        Self { output, expires_at }
+    }
+
+    /// Returns a clone of the cached output.
+    pub fn output(&self) -> Option<Output> {
+        self.output.try_clone()
+    }
+
+    /// Returns the time at which the cached output expires.
+    pub fn expires_at(&self) -> SystemTime {
+        self.expires_at
+    }
+}
+
+/// Storage for cached outputs.
+///
+/// Implementations decide how many outputs to keep and which to evict. Expiration is checked
+/// by the [`ResponseCache`], so a store may return expired outputs.
+pub trait ResponseCacheStore: fmt::Debug + Send + Sync {
+    /// Returns a clone of the cached output for the given key, if there is one.
+    fn get(&self, key: &ResponseCacheKey) -> Option<CachedOutput>;
+
+    /// Stores an output, replacing any output already stored for the key.
+    fn put(&self, key: ResponseCacheKey, output: CachedOutput);
+
+    /// Removes every output whose key matches the given predicate.
+    fn invalidate(&self, predicate: &dyn Fn(&ResponseCacheKey) -> bool);
+}
+
+/// A [`ResponseCacheStore`] that keeps outputs in memory.
+///
+/// When the store is full, the oldest output is evicted.
+#[derive(Debug)]
+pub struct InMemoryResponseCacheStore {
+    max_entries: usize,
+    inner: Mutex<InMemoryInner>,
+}
+
+#[derive(Debug, Default)]
+struct InMemoryInner {
+    entries: HashMap<ResponseCacheKey, CachedOutput>,
+    insertion_order: VecDeque<ResponseCacheKey>,
+}
+
+impl Default for InMemoryResponseCacheStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES)
+    }
+}
+
+impl InMemoryResponseCacheStore {
+    /// Creates a new store that keeps at most `max_entries` outputs.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            inner: Default::default(),
+        }
+    }
+
+    /// Returns the number of outputs in the store.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Returns true if there are no outputs in the store.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ResponseCacheStore for InMemoryResponseCacheStore {
+    fn get(&self, key: &ResponseCacheKey) -> Option<CachedOutput> {
+        let inner = self.inner.lock().unwrap();
+        let cached = inner.entries.get(key)?;
+        Some(CachedOutput::new(cached.output()?, cached.expires_at))
+    }
+
+    fn put(&self, key: ResponseCacheKey, output: CachedOutput) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.insert(key.clone(), output).is_some() {
+            inner.insertion_order.retain(|k| k != &key);
+        }
+        inner.insertion_order.push_back(key);
+        while inner.entries.len() > self.max_entries {
+            let oldest = inner
+                .insertion_order
+                .pop_front()
+                .expect("entries isn't empty");
+            inner.entries.remove(&oldest);
+        }
+    }
+
+    fn invalidate(&self, predicate: &dyn Fn(&ResponseCacheKey) -> bool) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.retain(|key, _| !predicate(key));
+        inner.insertion_order.retain(|key| !predicate(key));
+    }
+}
+
+/// Caches the outputs of read-only operations.
+///
+/// Cloning a `ResponseCache` shares its store, so the same cache can be given to several clients
+/// with [`ResponseCacheRuntimePlugin`], and entries can be invalidated from outside the client.
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    store: Arc<dyn ResponseCacheStore>,
+    ttl: Duration,
+    invalidation_rules: Arc<HashMap<String, Vec<String>>>,
+}
+
+impl Storable for ResponseCache {
+    type Storer = StoreReplace<Self>;
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResponseCache {
+    /// Creates a new `ResponseCache` with an [`InMemoryResponseCacheStore`] and a one minute TTL.
+    pub fn new() -> Self {
+        Self {
+            store: Arc::new(InMemoryResponseCacheStore::default()),
+            ttl: DEFAULT_TTL,
+            invalidation_rules: Default::default(),
+        }
+    }
+
+    /// Set how long outputs are cached for.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set the store that cached outputs are kept in.
+    pub fn with_store(mut self, store: impl ResponseCacheStore + 'static) -> Self {
+        self.store = Arc::new(store);
+        self
+    }
+
+    /// Invalidate the cached outputs of `cached_operations` whenever `operation` succeeds.
+    ///
+    /// For example, a successful `PutParameter` can invalidate the cached outputs of
+    /// `GetParameter` and `GetParametersByPath` for the same service.
+    pub fn invalidate_after(
+        mut self,
+        operation: impl Into<String>,
+        cached_operations: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Arc::make_mut(&mut self.invalidation_rules)
+            .entry(operation.into())
+            .or_default()
+            .extend(cached_operations.into_iter().map(Into::into));
+        self
+    }
+
+    /// Returns how long outputs are cached for.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Removes the cached outputs of an operation.
+    pub fn invalidate_operation(&self, service: &str, operation: &str) {
+        self.store
+            .invalidate(&|key| key.service == service && key.operation == operation);
+    }
+
+    /// Removes every cached output whose key matches the given predicate.
+    pub fn invalidate(&self, predicate: impl Fn(&ResponseCacheKey) -> bool) {
+        self.store.invalidate(&predicate);
+    }
+
+    /// Removes every cached output.
+    pub fn clear(&self) {
+        self.store.invalidate(&|_| true);
+    }
+}
+
+/// Runtime plugin that caches the outputs of read-only operations in a [`ResponseCache`].
+#[derive(Debug)]
+pub struct ResponseCacheRuntimePlugin {
+    config: FrozenLayer,
+}
+
+impl ResponseCacheRuntimePlugin {
+    /// Creates a runtime plugin that uses the given response cache.
+    pub fn new(response_cache: ResponseCache) -> Self {
+        let mut layer = Layer::new("ResponseCache");
+        layer.store_put(response_cache);
+        Self {
+            config: layer.freeze(),
+        }
+    }
+}
+
+impl RuntimePlugin for ResponseCacheRuntimePlugin {
+    fn config(&self) -> Option<FrozenLayer> {
+        Some(self.config.clone())
+    }
+}
+
+/// Returns the cached output for the current attempt, if there is an unexpired one.
+///
+/// This is called by the orchestrator once the request has been signed, right before it would be
+/// transmitted. If the operation's output can be cached, the key is stored in the config bag so
+/// that [`store`] can cache the output later.
+pub(crate) fn lookup(
+    runtime_components: &RuntimeComponents,
+    cfg: &mut ConfigBag,
+) -> Option<Output> {
+    let response_cache = cfg.load::<ResponseCache>()?;
+    let time_source = runtime_components.time_source()?;
+    let key = ResponseCacheKey::from_config(cfg)?;
+    let cached = response_cache
+        .store
+        .get(&key)
+        .filter(|cached| time_source.now() < cached.expires_at())
+        .and_then(|cached| cached.output());
+    if cached.is_some() {
+        debug!(operation = %key.operation, "serving the output from the response cache");
+    } else {
+        cfg.interceptor_state().store_put(key);
+    }
+    cached
+}
+
+/// Caches the output of a successful operation, and applies the cache's invalidation rules.
+///
+/// This is called by the orchestrator once the operation has completed.
+pub(crate) fn store(
+    ctx: &InterceptorContext,
+    runtime_components: &RuntimeComponents,
+    cfg: &ConfigBag,
+) {
+    let (Some(response_cache), Some(Ok(output))) =
+        (cfg.load::<ResponseCache>(), ctx.output_or_error())
+    else {
+        return;
+    };
+    if let Some(metadata) = cfg.load::<Metadata>() {
+        if let Some(invalidated) = response_cache.invalidation_rules.get(metadata.name()) {
+            response_cache.invalidate(|key| {
+                key.service == metadata.service() && invalidated.contains(&key.operation)
+            });
+        }
+    }
+    if let (Some(key), Some(time_source), Some(output)) = (
+        cfg.load::<ResponseCacheKey>(),
+        runtime_components.time_source(),
+        output.try_clone(),
+    ) {
+        let expires_at = time_source.now() + response_cache.ttl;
+        response_cache
+            .store
+            .put(key.clone(), CachedOutput::new(output, expires_at));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(identity: IdentityDiscriminator, operation: &str, request: &str) -> ResponseCacheKey {
+        ResponseCacheKey {
+            service: "test".into(),
+            operation: operation.into(),
+            endpoint: "http://localhost:1234".into(),
+            identity,
+            request: request.as_bytes().to_vec(),
+        }
+    }
+
+    fn cached(value: &str) -> CachedOutput {
+        CachedOutput::new(
+            Output::erase_with_clone(value.to_string()),
+            SystemTime::UNIX_EPOCH,
+        )
+    }
+
+    fn get(store: &InMemoryResponseCacheStore, key: &ResponseCacheKey) -> Option<String> {
+        store
+            .get(key)
+            .map(|cached| cached.output().unwrap().downcast::<String>().unwrap())
+    }
+
+    #[test]
+    fn in_memory_store_evicts_oldest_entries() {
+        let identity = IdentityDiscriminator::for_tests();
+        let key = |operation, request| key(identity, operation, request);
+        let store = InMemoryResponseCacheStore::new(2);
+        store.put(key("Get", "a"), cached("a"));
+        store.put(key("Get", "b"), cached("b"));
+        store.put(key("Get", "a"), cached("a2"));
+        store.put(key("Get", "c"), cached("c"));
+
+        assert_eq!(2, store.len());
+        assert_eq!(None, get(&store, &key("Get", "b")));
+        assert_eq!(Some("a2".to_string()), get(&store, &key("Get", "a")));
+        assert_eq!(Some("c".to_string()), get(&store, &key("Get", "c")));
+    }
+
+    #[test]
+    fn invalidation() {
+        let identity = IdentityDiscriminator::for_tests();
+        let key = |operation, request| key(identity, operation, request);
+        let store = Arc::new(InMemoryResponseCacheStore::new(10));
+        let cache = ResponseCache {
+            store: store.clone(),
+            ttl: DEFAULT_TTL,
+            invalidation_rules: Default::default(),
+        };
+        store.put(key("Get", "a"), cached("a"));
+        store.put(key("Describe", "a"), cached("a"));
+
+        cache.invalidate_operation("test", "Get");
+        assert_eq!(None, get(&store, &key("Get", "a")));
+        assert!(get(&store, &key("Describe", "a")).is_some());
+
+        cache.clear();
+        assert!(store.is_empty());
+    }
+
+    mod operations {
+        use super::*;
+        use crate::client::orchestrator::operation::Operation;
+        use aws_smithy_async::test_util::ManualTimeSource;
+        use aws_smithy_runtime_api::box_error::BoxError;
+        use aws_smithy_runtime_api::client::http::{
+            http_client_fn, HttpConnector, HttpConnectorFuture,
+        };
+        use aws_smithy_runtime_api::client::interceptors::context::{
+            BeforeDeserializationInterceptorContextRef, BeforeTransmitInterceptorContextMut,
+            BeforeTransmitInterceptorContextRef, Error,
+        };
+        use aws_smithy_runtime_api::client::interceptors::Intercept;
+        use aws_smithy_runtime_api::client::orchestrator::{
+            HttpRequest, HttpResponse, OrchestratorError,
+        };
+        use aws_smithy_runtime_api::client::ser_de::DeserializeResponse;
+        use aws_smithy_runtime_api::shared::IntoShared;
+        use aws_smithy_types::body::SdkBody;
+        use aws_smithy_types::timeout::TimeoutConfig;
+        use std::convert::Infallible;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Clone, Debug, Default)]
+        struct CountingConnector {
+            calls: Arc<AtomicUsize>,
+        }
+
+        impl HttpConnector for CountingConnector {
+            fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+                let call = self.calls.fetch_add(1, Ordering::SeqCst);
+                HttpConnectorFuture::ready(Ok(HttpResponse::try_from(
+                    ::http::Response::builder()
+                        .body(SdkBody::from(format!("response #{call}")))
+                        .unwrap(),
+                )
+                .unwrap()))
+            }
+        }
+
+        #[derive(Debug)]
+        struct CloneableDeserializer;
+
+        impl DeserializeResponse for CloneableDeserializer {
+            fn deserialize_nonstreaming(
+                &self,
+                response: &HttpResponse,
+            ) -> Result<Output, OrchestratorError<Error>> {
+                let body = std::str::from_utf8(response.body().bytes().unwrap()).unwrap();
+                Ok(Output::erase_with_clone(body.to_string()))
+            }
+        }
+
+        /// Records the interceptor hooks that were called.
+        #[derive(Clone, Debug, Default)]
+        struct RecordHooks {
+            hooks: Arc<Mutex<Vec<&'static str>>>,
+        }
+
+        impl RecordHooks {
+            fn take(&self) -> Vec<&'static str> {
+                std::mem::take(&mut *self.hooks.lock().unwrap())
+            }
+
+            fn record(&self, hook: &'static str) -> Result<(), BoxError> {
+                self.hooks.lock().unwrap().push(hook);
+                Ok(())
+            }
+        }
+
+        impl Intercept for RecordHooks {
+            fn name(&self) -> &'static str {
+                "RecordHooks"
+            }
+
+            fn read_after_serialization(
+                &self,
+                _context: &BeforeTransmitInterceptorContextRef<'_>,
+                _runtime_components: &RuntimeComponents,
+                _cfg: &mut ConfigBag,
+            ) -> Result<(), BoxError> {
+                self.record("read_after_serialization")
+            }
+
+            fn modify_before_retry_loop(
+                &self,
+                _context: &mut BeforeTransmitInterceptorContextMut<'_>,
+                _runtime_components: &RuntimeComponents,
+                _cfg: &mut ConfigBag,
+            ) -> Result<(), BoxError> {
+                self.record("modify_before_retry_loop")
+            }
+
+            fn read_before_transmit(
+                &self,
+                _context: &BeforeTransmitInterceptorContextRef<'_>,
+                _runtime_components: &RuntimeComponents,
+                _cfg: &mut ConfigBag,
+            ) -> Result<(), BoxError> {
+                self.record("read_before_transmit")
+            }
+
+            fn read_after_transmit(
+                &self,
+                _context: &BeforeDeserializationInterceptorContextRef<'_>,
+                _runtime_components: &RuntimeComponents,
+                _cfg: &mut ConfigBag,
+            ) -> Result<(), BoxError> {
+                self.record("read_after_transmit")
+            }
+        }
+
+        struct TestOperation {
+            name: &'static str,
+            idempotency: Option<OperationIdempotency>,
+            endpoint_url: &'static str,
+            hooks: RecordHooks,
+        }
+
+        impl TestOperation {
+            fn new(name: &'static str, idempotency: Option<OperationIdempotency>) -> Self {
+                Self {
+                    name,
+                    idempotency,
+                    endpoint_url: "http://localhost:1234",
+                    hooks: RecordHooks::default(),
+                }
+            }
+
+            fn build(
+                self,
+                connector: &CountingConnector,
+                cache: &ResponseCache,
+                time_source: &ManualTimeSource,
+            ) -> Operation<&'static str, String, Infallible> {
+                let connector = connector.clone();
+                let builder = Operation::builder()
+                    .service_name("test")
+                    .operation_name(self.name)
+                    .http_client(http_client_fn(move |_, _| connector.clone().into_shared()))
+                    .endpoint_url(self.endpoint_url)
+                    .no_auth()
+                    .no_retry()
+                    .timeout_config(TimeoutConfig::disabled())
+                    .time_source(time_source.clone())
+                    .interceptor(self.hooks)
+                    .runtime_plugin(ResponseCacheRuntimePlugin::new(cache.clone()))
+                    .serializer(|input: &'static str| Ok(HttpRequest::new(SdkBody::from(input))))
+                    .deserializer_impl::<String, Infallible>(CloneableDeserializer);
+                match self.idempotency {
+                    Some(idempotency) => builder.idempotency(idempotency),
+                    None => builder,
+                }
+                .build()
+            }
+        }
+
+        #[tokio::test]
+        async fn caches_read_only_operations() {
+            let connector = CountingConnector::default();
+            let time_source = ManualTimeSource::new(SystemTime::UNIX_EPOCH);
+            let cache = ResponseCache::new()
+                .with_ttl(Duration::from_secs(30))
+                .invalidate_after("Put", ["Get"]);
+            let get = TestOperation::new("Get", Some(OperationIdempotency::ReadOnly)).build(
+                &connector,
+                &cache,
+                &time_source,
+            );
+            let put = TestOperation::new("Put", None).build(&connector, &cache, &time_source);
+
+            assert_eq!("response #0", get.invoke("a").await.unwrap());
+            assert_eq!("response #0", get.invoke("a").await.unwrap());
+            assert_eq!("response #1", get.invoke("b").await.unwrap());
+
+            // Cached outputs expire
+            time_source.advance(Duration::from_secs(30));
+            assert_eq!("response #2", get.invoke("a").await.unwrap());
+            assert_eq!("response #2", get.invoke("a").await.unwrap());
+
+            // Operations that aren't read-only are never cached, and can invalidate cached outputs
+            assert_eq!("response #3", put.invoke("a").await.unwrap());
+            assert_eq!("response #4", put.invoke("a").await.unwrap());
+            assert_eq!("response #5", get.invoke("a").await.unwrap());
+            assert_eq!("response #5", get.invoke("a").await.unwrap());
+            assert_eq!(6, connector.calls.load(Ordering::SeqCst));
+        }
+
+        #[tokio::test]
+        async fn outputs_arent_shared_across_identities_or_endpoints() {
+            let connector = CountingConnector::default();
+            let time_source = ManualTimeSource::new(SystemTime::UNIX_EPOCH);
+            let cache = ResponseCache::new();
+            let get = || TestOperation::new("Get", Some(OperationIdempotency::ReadOnly));
+            // Each client has its own identity resolver
+            let client_a = get().build(&connector, &cache, &time_source);
+            let client_b = get().build(&connector, &cache, &time_source);
+            let other_endpoint = TestOperation {
+                endpoint_url: "http://localhost:5678",
+                ..get()
+            }
+            .build(&connector, &cache, &time_source);
+
+            assert_eq!("response #0", client_a.invoke("a").await.unwrap());
+            assert_eq!("response #1", client_b.invoke("a").await.unwrap());
+            assert_eq!("response #2", other_endpoint.invoke("a").await.unwrap());
+            assert_eq!("response #0", client_a.invoke("a").await.unwrap());
+            assert_eq!("response #1", client_b.invoke("a").await.unwrap());
+            assert_eq!(3, connector.calls.load(Ordering::SeqCst));
+        }
+
+        #[tokio::test]
+        async fn cache_hits_skip_only_the_response_hooks() {
+            let connector = CountingConnector::default();
+            let time_source = ManualTimeSource::new(SystemTime::UNIX_EPOCH);
+            let cache = ResponseCache::new();
+            let operation = TestOperation::new("Get", Some(OperationIdempotency::ReadOnly));
+            let hooks = operation.hooks.clone();
+            let operation = operation.build(&connector, &cache, &time_source);
+
+            assert_eq!("response #0", operation.invoke("a").await.unwrap());
+            assert_eq!(
+                vec![
+                    "read_after_serialization",
+                    "modify_before_retry_loop",
+                    "read_before_transmit",
+                    "read_after_transmit"
+                ],
+                hooks.take()
+            );
+
+            assert_eq!("response #0", operation.invoke("a").await.unwrap());
+            assert_eq!(
+                vec![
+                    "read_after_serialization",
+                    "modify_before_retry_loop",
+                    "read_before_transmit"
+                ],
+                hooks.take()
+            );
+        }
+    }
+}
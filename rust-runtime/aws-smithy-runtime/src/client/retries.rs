@@ -10,12 +10,17 @@ pub mod classifiers;
 pub mod strategy;
 
 mod client_rate_limiter;
+pub(crate) mod concurrency_limiter;
 mod token_bucket;
 
 use aws_smithy_types::config_bag::{Storable, StoreReplace};
 use std::fmt;
 
 pub use client_rate_limiter::ClientRateLimiter;
+pub use concurrency_limiter::{
+    ConcurrencyLimitExceeded, ConcurrencyLimiter, ConcurrencyLimiterInterceptor,
+    ConcurrencyLimiterPartition,
+};
 pub use token_bucket::TokenBucket;
 
 pub use client_rate_limiter::ClientRateLimiterPartition;
@@ -159,6 +159,11 @@ impl ClientRateLimiter {
         let new_rate = f64::min(calculated_rate, 2.0 * it.measured_tx_rate);
         it.update_bucket_refill_rate(seconds_since_unix_epoch, new_rate);
     }
+
+    /// Returns the rate at which tokens are currently replenished, in tokens per second.
+    pub(crate) fn fill_rate(&self) -> f64 {
+        self.inner.lock().unwrap().fill_rate
+    }
 }
 
 impl Inner {
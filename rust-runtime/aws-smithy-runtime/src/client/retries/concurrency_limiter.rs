@@ -0,0 +1,720 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! An adaptive limiter for the number of attempts that can be in flight at once. The limit
+//! changes based on the latency of completed attempts and the number of overload errors
+//! encountered.
+
+use crate::client::retries::strategy::StandardRetryStrategy;
+use crate::client::retries::RetryPartition;
+use aws_smithy_async::future::timeout::Timeout;
+use aws_smithy_async::rt::sleep::AsyncSleep;
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::context::BeforeTransmitInterceptorContextRef;
+use aws_smithy_runtime_api::client::interceptors::Intercept;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
+use aws_smithy_types::retry::{ConcurrencyLimitAlgorithm, ConcurrencyLimitConfig};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+
+/// Represents a partition for the concurrency limiter, e.g. an endpoint, a region
+#[non_exhaustive]
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ConcurrencyLimiterPartition {
+    retry_partition: RetryPartition,
+}
+
+impl ConcurrencyLimiterPartition {
+    /// Creates a `ConcurrencyLimiterPartition` from the given [`RetryPartition`]
+    pub fn new(retry_partition: RetryPartition) -> Self {
+        Self { retry_partition }
+    }
+}
+
+/// How much weight the latest attempt has in the smoothed latency.
+const LATENCY_SMOOTHING: f64 = 0.2;
+/// How much weight the latest attempt has in the long-term latency used by the gradient algorithm.
+const LONG_TERM_LATENCY_SMOOTHING: f64 = 0.05;
+/// How much latency may grow above the long-term average before the gradient algorithm backs off.
+const GRADIENT_TOLERANCE: f64 = 1.5;
+/// The lowest gradient, used when an attempt fails with an overload error.
+const MIN_GRADIENT: f64 = 0.5;
+/// How quickly the gradient algorithm moves towards a newly calculated limit.
+const GRADIENT_SMOOTHING: f64 = 0.2;
+
+/// Adaptive concurrency limiter for the standard retry strategy.
+///
+/// The limiter tracks the number of attempts in flight for a [`RetryPartition`] and adjusts its
+/// limit with the [`ConcurrencyLimitAlgorithm`] set in the [`ConcurrencyLimitConfig`]. Each
+/// attempt holds a slot for as long as it is in flight. When no slot is available, attempts wait
+/// for one for up to the configured max queue time, and are rejected after that.
+///
+/// Like the [`TokenBucket`](crate::client::retries::TokenBucket), a limiter can be placed in the
+/// config bag to share it between clients. Otherwise, clients using the same retry partition share
+/// a limiter created from the first configuration that was used for that partition.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimiter {
+    inner: Arc<Mutex<Inner>>,
+    /// Holds one permit for each slot of the current limit that isn't taken by an attempt.
+    slots: Arc<Semaphore>,
+}
+
+impl Storable for ConcurrencyLimiter {
+    type Storer = StoreReplace<Self>;
+}
+
+#[derive(Debug)]
+struct Inner {
+    config: ConcurrencyLimitConfig,
+    /// The current limit. Kept fractional so that small adjustments accumulate.
+    limit: f64,
+    /// The number of slots currently backed by permits, whether they are available or taken.
+    slots: usize,
+    /// The number of taken slots that must be removed when they are released, because the limit
+    /// was lowered while they were in use.
+    excess_slots: usize,
+    /// The number of attempts currently in flight.
+    in_flight: usize,
+    /// The smoothed latency of recent attempts, in seconds.
+    smoothed_latency: Option<f64>,
+    /// The long-term average latency, in seconds, used as the baseline by the gradient algorithm.
+    long_term_latency: Option<f64>,
+}
+
+impl ConcurrencyLimiter {
+    /// Creates a new `ConcurrencyLimiter` with the given config.
+    pub fn new(config: ConcurrencyLimitConfig) -> Self {
+        let min_limit = config.min_limit().max(1);
+        let limit = config
+            .initial_limit()
+            .clamp(min_limit, config.max_limit().max(min_limit));
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                config,
+                limit: limit as f64,
+                slots: limit,
+                excess_slots: 0,
+                in_flight: 0,
+                smoothed_latency: None,
+                long_term_latency: None,
+            })),
+            slots: Arc::new(Semaphore::new(limit)),
+        }
+    }
+
+    /// Returns the number of attempts currently allowed to be in flight.
+    pub fn limit(&self) -> usize {
+        self.inner.lock().unwrap().limit()
+    }
+
+    /// Returns the number of attempts currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.inner.lock().unwrap().in_flight
+    }
+
+    /// Takes a slot for a new attempt if one is available right away.
+    pub(crate) fn try_acquire(&self) -> Option<InFlightAttempt> {
+        let permit = self.slots.clone().try_acquire_owned().ok()?;
+        Some(self.in_flight_attempt(permit))
+    }
+
+    /// Takes a slot for a new attempt, waiting for one to be released if the limit has been reached.
+    ///
+    /// The attempt is rejected if no slot becomes available within the configured max queue time.
+    pub(crate) async fn acquire(
+        &self,
+        sleep_impl: Option<&dyn AsyncSleep>,
+    ) -> Result<InFlightAttempt, ConcurrencyLimitExceeded> {
+        if let Some(attempt) = self.try_acquire() {
+            return Ok(attempt);
+        }
+        let max_queue_time = self.inner.lock().unwrap().config.max_queue_time();
+        let permit = match sleep_impl {
+            Some(sleep_impl) if !max_queue_time.is_zero() => {
+                debug!("concurrency limit reached; waiting up to {max_queue_time:?} for a slot");
+                Timeout::new(
+                    self.slots.clone().acquire_owned(),
+                    sleep_impl.sleep(max_queue_time),
+                )
+                .await
+                .ok()
+                .and_then(Result::ok)
+            }
+            _ => None,
+        };
+        match permit {
+            Some(permit) => Ok(self.in_flight_attempt(permit)),
+            None => {
+                let it = self.inner.lock().unwrap();
+                let err = ConcurrencyLimitExceeded {
+                    in_flight: it.in_flight,
+                    limit: it.limit(),
+                };
+                debug!("concurrency limiter rejected an attempt: {err}");
+                Err(err)
+            }
+        }
+    }
+
+    fn in_flight_attempt(&self, permit: OwnedSemaphorePermit) -> InFlightAttempt {
+        self.inner.lock().unwrap().in_flight += 1;
+        InFlightAttempt {
+            state: Mutex::new(Some(InFlight {
+                limiter: self.clone(),
+                permit,
+                started_at: None,
+            })),
+        }
+    }
+
+    fn finish_attempt(
+        &self,
+        permit: OwnedSemaphorePermit,
+        latency: Option<Duration>,
+        overloaded: bool,
+    ) {
+        let mut it = self.inner.lock().unwrap();
+        if let Some(latency) = latency {
+            it.update_limit(&self.slots, latency.as_secs_f64(), overloaded);
+        }
+        it.in_flight = it.in_flight.saturating_sub(1);
+        if it.excess_slots > 0 {
+            it.excess_slots -= 1;
+            it.slots -= 1;
+            permit.forget();
+        }
+    }
+}
+
+impl Inner {
+    fn limit(&self) -> usize {
+        (self.limit.floor() as usize).max(1)
+    }
+
+    fn update_limit(&mut self, slots: &Semaphore, latency: f64, overloaded: bool) {
+        self.smoothed_latency = Some(match self.smoothed_latency {
+            Some(smoothed) => smoothed * (1.0 - LATENCY_SMOOTHING) + latency * LATENCY_SMOOTHING,
+            None => latency,
+        });
+        // Only grow the limit while it is actually being used. Otherwise, a client sending a
+        // trickle of requests would slowly raise its limit to the maximum.
+        let limit_is_in_use = (self.in_flight * 2) as f64 >= self.limit;
+
+        let new_limit = match self.config.algorithm() {
+            ConcurrencyLimitAlgorithm::Gradient => {
+                let long_term = match self.long_term_latency {
+                    Some(long_term) => {
+                        long_term * (1.0 - LONG_TERM_LATENCY_SMOOTHING)
+                            + latency * LONG_TERM_LATENCY_SMOOTHING
+                    }
+                    None => latency,
+                };
+                self.long_term_latency = Some(long_term);
+
+                let gradient = if overloaded {
+                    MIN_GRADIENT
+                } else if latency > 0.0 {
+                    (GRADIENT_TOLERANCE * long_term / latency).clamp(MIN_GRADIENT, 1.0)
+                } else {
+                    1.0
+                };
+                if gradient >= 1.0 && !limit_is_in_use {
+                    return;
+                }
+                // The square root of the limit gives the limit room to grow when latency is stable.
+                let target = self.limit * gradient + self.limit.sqrt();
+                self.limit * (1.0 - GRADIENT_SMOOTHING) + target * GRADIENT_SMOOTHING
+            }
+            ConcurrencyLimitAlgorithm::Aimd => {
+                if overloaded || latency > self.config.latency_threshold().as_secs_f64() {
+                    self.limit * self.config.backoff_ratio()
+                } else if limit_is_in_use {
+                    self.limit + 1.0
+                } else {
+                    return;
+                }
+            }
+            // Algorithms this version doesn't know about keep the current limit.
+            _ => return,
+        };
+
+        let min_limit = self.config.min_limit().max(1) as f64;
+        let max_limit = (self.config.max_limit() as f64).max(min_limit);
+        self.limit = new_limit.clamp(min_limit, max_limit);
+        self.resize(slots);
+        debug!(
+            latency,
+            overloaded,
+            limit = self.limit,
+            "concurrency limiter updated its limit"
+        );
+    }
+
+    /// Adds or removes slots so that their number matches the current limit.
+    fn resize(&mut self, slots: &Semaphore) {
+        let target = self.limit();
+        let current = self.slots - self.excess_slots;
+        if target > current {
+            // Keep taken slots that were going to be removed before adding new ones.
+            let mut grow = target - current;
+            let kept = grow.min(self.excess_slots);
+            self.excess_slots -= kept;
+            grow -= kept;
+            slots.add_permits(grow);
+            self.slots += grow;
+        } else if target < current {
+            // Remove available slots right away, and taken slots once they are released.
+            for _ in 0..current - target {
+                match slots.try_acquire() {
+                    Ok(permit) => {
+                        permit.forget();
+                        self.slots -= 1;
+                    }
+                    Err(_) => self.excess_slots += 1,
+                }
+            }
+        }
+    }
+}
+
+/// An attempt that holds a slot of a [`ConcurrencyLimiter`].
+///
+/// If the attempt is dropped before it is finished, e.g. because the request future was
+/// cancelled, its slot is released without affecting the limit.
+#[derive(Debug)]
+pub(crate) struct InFlightAttempt {
+    state: Mutex<Option<InFlight>>,
+}
+
+#[derive(Debug)]
+struct InFlight {
+    limiter: ConcurrencyLimiter,
+    permit: OwnedSemaphorePermit,
+    started_at: Option<SystemTime>,
+}
+
+impl Storable for InFlightAttempt {
+    type Storer = StoreReplace<Self>;
+}
+
+impl InFlightAttempt {
+    /// Records when the attempt started, so that its latency can be fed into the limiter.
+    pub(crate) fn start(&self, started_at: SystemTime) {
+        if let Some(in_flight) = self.state.lock().unwrap().as_mut() {
+            in_flight.started_at = Some(started_at);
+        }
+    }
+
+    /// Finishes the attempt, releasing its slot and feeding its latency into the limiter.
+    ///
+    /// `overloaded` should be `true` if the attempt failed with an error indicating that the
+    /// service is overloaded, such as a throttling error or a timeout.
+    pub(crate) fn finish(&self, finished_at: Option<SystemTime>, overloaded: bool) {
+        if let Some(in_flight) = self.state.lock().unwrap().take() {
+            let latency = in_flight
+                .started_at
+                .zip(finished_at)
+                .and_then(|(started_at, finished_at)| finished_at.duration_since(started_at).ok());
+            in_flight
+                .limiter
+                .finish_attempt(in_flight.permit, latency, overloaded);
+        }
+    }
+}
+
+impl Drop for InFlightAttempt {
+    fn drop(&mut self) {
+        if let Some(in_flight) = self.state.get_mut().unwrap().take() {
+            in_flight
+                .limiter
+                .finish_attempt(in_flight.permit, None, false);
+        }
+    }
+}
+
+/// Waits for a slot of the concurrency limiter, if one is configured, and stores it in the config
+/// bag for the next attempt.
+pub(crate) async fn reserve_slot(
+    runtime_components: &RuntimeComponents,
+    cfg: &mut ConfigBag,
+) -> Result<(), ConcurrencyLimitExceeded> {
+    if let Some(limiter) = StandardRetryStrategy::concurrency_limiter(cfg) {
+        let sleep_impl = runtime_components.sleep_impl();
+        let attempt = limiter
+            .acquire(sleep_impl.as_ref().map(|sleep| sleep as &dyn AsyncSleep))
+            .await?;
+        cfg.interceptor_state().store_put(attempt);
+    }
+    Ok(())
+}
+
+/// Takes a slot of the concurrency limiter for a hedged attempt, if one is configured.
+///
+/// Hedged attempts don't wait for a slot. Returns `false` if no slot is available, in which case
+/// the hedged attempt shouldn't be sent.
+#[cfg(all(aws_sdk_unstable, feature = "unstable-hedging"))]
+pub(crate) fn try_reserve_slot(cfg: &mut ConfigBag) -> bool {
+    match StandardRetryStrategy::concurrency_limiter(cfg) {
+        Some(limiter) => match limiter.try_acquire() {
+            Some(attempt) => {
+                cfg.interceptor_state().store_put(attempt);
+                true
+            }
+            None => false,
+        },
+        None => true,
+    }
+}
+
+/// Error returned when an attempt is rejected because the concurrency limit has been reached.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConcurrencyLimitExceeded {
+    in_flight: usize,
+    limit: usize,
+}
+
+impl ConcurrencyLimitExceeded {
+    /// Returns the number of attempts that were in flight when the attempt was rejected.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Returns the concurrency limit at the time the attempt was rejected.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for ConcurrencyLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the attempt was rejected because {} attempts are already in flight and the concurrency limit is {}",
+            self.in_flight, self.limit
+        )
+    }
+}
+
+impl std::error::Error for ConcurrencyLimitExceeded {}
+
+/// Interceptor that records the start of each attempt for the [`ConcurrencyLimiter`].
+///
+/// The orchestrator takes a slot of the limiter before each attempt, and the
+/// [`StandardRetryStrategy`] releases it once the attempt is finished. This interceptor records
+/// when the attempt starts so that the limiter can adapt its limit to the latency of attempts. It
+/// is registered by the default retry config plugin, and does nothing unless a concurrency limit
+/// is configured in the [`RetryConfig`](aws_smithy_types::retry::RetryConfig).
+#[non_exhaustive]
+#[derive(Debug, Default)]
+pub struct ConcurrencyLimiterInterceptor {}
+
+impl ConcurrencyLimiterInterceptor {
+    /// Create a new `ConcurrencyLimiterInterceptor`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Intercept for ConcurrencyLimiterInterceptor {
+    fn name(&self) -> &'static str {
+        "ConcurrencyLimiterInterceptor"
+    }
+
+    fn read_before_attempt(
+        &self,
+        _context: &BeforeTransmitInterceptorContextRef<'_>,
+        runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        if let (Some(attempt), Some(time_source)) = (
+            cfg.load::<InFlightAttempt>(),
+            runtime_components.time_source(),
+        ) {
+            attempt.start(time_source.now());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConcurrencyLimiter;
+    use aws_smithy_async::rt::sleep::TokioSleep;
+    use aws_smithy_types::retry::ConcurrencyLimitConfig;
+    use std::time::{Duration, SystemTime};
+
+    fn run_attempts(
+        limiter: &ConcurrencyLimiter,
+        count: usize,
+        latency: Duration,
+        overloaded: bool,
+    ) {
+        let start = SystemTime::UNIX_EPOCH;
+        let attempts: Vec<_> = (0..count)
+            .map(|_| {
+                let attempt = limiter.try_acquire().expect("a slot is available");
+                attempt.start(start);
+                attempt
+            })
+            .collect();
+        for attempt in attempts {
+            attempt.finish(Some(start + latency), overloaded);
+        }
+    }
+
+    #[test]
+    fn attempts_are_admitted_until_the_limit_is_reached() {
+        let limiter = ConcurrencyLimiter::new(ConcurrencyLimitConfig::aimd().with_initial_limit(2));
+        let _first = limiter.try_acquire().expect("below the limit");
+        let _second = limiter.try_acquire().expect("below the limit");
+        assert!(limiter.try_acquire().is_none());
+        assert_eq!(2, limiter.in_flight());
+    }
+
+    #[tokio::test]
+    async fn attempts_are_rejected_when_no_slot_is_released_in_time() {
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyLimitConfig::aimd()
+                .with_initial_limit(1)
+                .with_max_queue_time(Duration::ZERO),
+        );
+        let _in_flight = limiter.try_acquire().expect("below the limit");
+        let err = limiter.acquire(Some(&TokioSleep::new())).await.unwrap_err();
+        assert_eq!(1, err.in_flight());
+        assert_eq!(1, err.limit());
+
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyLimitConfig::aimd()
+                .with_initial_limit(1)
+                .with_max_queue_time(Duration::from_millis(10)),
+        );
+        let _in_flight = limiter.try_acquire().expect("below the limit");
+        limiter
+            .acquire(Some(&TokioSleep::new()))
+            .await
+            .expect_err("the slot is never released");
+    }
+
+    #[tokio::test]
+    async fn queued_attempts_wait_for_a_slot() {
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyLimitConfig::aimd()
+                .with_initial_limit(1)
+                .with_max_queue_time(Duration::from_secs(10)),
+        );
+        let in_flight = limiter.try_acquire().expect("below the limit");
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(Some(&TokioSleep::new())).await.map(drop) }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+
+        in_flight.finish(None, false);
+        waiting.await.unwrap().expect("the slot was released");
+    }
+
+    #[tokio::test]
+    async fn concurrent_callers_never_exceed_the_limit() {
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyLimitConfig::aimd()
+                .with_initial_limit(3)
+                .with_max_limit(3)
+                .with_max_queue_time(Duration::from_secs(10)),
+        );
+        let max_in_flight = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let tasks: Vec<_> = (0..20)
+            .map(|_| {
+                let (limiter, max_in_flight) = (limiter.clone(), max_in_flight.clone());
+                tokio::spawn(async move {
+                    let attempt = limiter.acquire(Some(&TokioSleep::new())).await.unwrap();
+                    max_in_flight
+                        .fetch_max(limiter.in_flight(), std::sync::atomic::Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                    drop(attempt);
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(3, max_in_flight.load(std::sync::atomic::Ordering::SeqCst));
+        assert_eq!(0, limiter.in_flight());
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn concurrent_operations_are_held_to_the_limit() {
+        use crate::client::orchestrator::operation::Operation;
+        use aws_smithy_async::time::SystemTimeSource;
+        use aws_smithy_runtime_api::client::http::{
+            http_client_fn, HttpConnector, HttpConnectorFuture,
+        };
+        use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+        use aws_smithy_runtime_api::client::runtime_plugin::StaticRuntimePlugin;
+        use aws_smithy_runtime_api::shared::IntoShared;
+        use aws_smithy_types::body::SdkBody;
+        use aws_smithy_types::config_bag::Layer;
+        use aws_smithy_types::retry::RetryConfig;
+        use aws_smithy_types::timeout::TimeoutConfig;
+        use std::convert::Infallible;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Clone, Debug, Default)]
+        struct SlowConnector {
+            in_flight: Arc<AtomicUsize>,
+            max_in_flight: Arc<AtomicUsize>,
+        }
+
+        impl HttpConnector for SlowConnector {
+            fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+                let this = self.clone();
+                HttpConnectorFuture::new(async move {
+                    let in_flight = this.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    this.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    this.in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok(HttpResponse::new(200.try_into().unwrap(), SdkBody::empty()))
+                })
+            }
+        }
+
+        let config = ConcurrencyLimitConfig::aimd()
+            .with_initial_limit(2)
+            .with_max_limit(2)
+            .with_max_queue_time(Duration::from_secs(10));
+        let mut layer = Layer::new("test");
+        layer.store_put(ConcurrencyLimiter::new(config.clone()));
+        let connector = SlowConnector::default();
+        let operation = Arc::new(
+            Operation::builder()
+                .service_name("test")
+                .operation_name("test")
+                .http_client(http_client_fn({
+                    let connector = connector.clone();
+                    move |_, _| connector.clone().into_shared()
+                }))
+                .endpoint_url("http://localhost:1234")
+                .no_auth()
+                .standard_retry(&RetryConfig::standard().with_concurrency_limit(config))
+                .timeout_config(TimeoutConfig::disabled())
+                .sleep_impl(TokioSleep::new())
+                .time_source(SystemTimeSource::new())
+                .runtime_plugin(StaticRuntimePlugin::new().with_config(layer.freeze()))
+                .serializer(|_: ()| Ok(HttpRequest::new(SdkBody::empty())))
+                .deserializer::<_, Infallible>(|response| Ok(response.status().as_u16()))
+                .build(),
+        );
+
+        let tasks: Vec<_> = (0..10)
+            .map(|_| {
+                let operation = operation.clone();
+                tokio::spawn(async move { operation.invoke(()).await })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(200, task.await.unwrap().expect("success"));
+        }
+        assert_eq!(2, connector.max_in_flight.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn lowering_the_limit_removes_slots_once_they_are_released() {
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyLimitConfig::aimd()
+                .with_initial_limit(4)
+                .with_backoff_ratio(0.5),
+        );
+        let attempts: Vec<_> = (0..4).map(|_| limiter.try_acquire().unwrap()).collect();
+        attempts[0].start(SystemTime::UNIX_EPOCH);
+        attempts[0].finish(Some(SystemTime::UNIX_EPOCH), true);
+        assert_eq!(2, limiter.limit());
+        // Three attempts are still in flight, which is above the new limit
+        assert!(limiter.try_acquire().is_none());
+        drop(attempts);
+        let _first = limiter.try_acquire().expect("below the new limit");
+        let _second = limiter.try_acquire().expect("below the new limit");
+        assert!(limiter.try_acquire().is_none());
+    }
+
+    #[test]
+    fn dropped_attempts_stop_counting_towards_the_limit() {
+        let limiter = ConcurrencyLimiter::new(ConcurrencyLimitConfig::aimd().with_initial_limit(4));
+        let attempt = limiter.try_acquire().unwrap();
+        assert_eq!(1, limiter.in_flight());
+        drop(attempt);
+        assert_eq!(0, limiter.in_flight());
+        assert_eq!(4, limiter.limit());
+    }
+
+    #[test]
+    fn aimd_grows_additively_and_shrinks_multiplicatively() {
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyLimitConfig::aimd()
+                .with_initial_limit(10)
+                .with_latency_threshold(Duration::from_secs(1)),
+        );
+        // Attempts that don't use the limit don't grow it
+        run_attempts(&limiter, 1, Duration::from_millis(100), false);
+        assert_eq!(10, limiter.limit());
+
+        run_attempts(&limiter, 10, Duration::from_millis(100), false);
+        assert_eq!(14, limiter.limit());
+
+        run_attempts(&limiter, 1, Duration::from_millis(100), true);
+        assert_eq!(12, limiter.limit());
+
+        run_attempts(&limiter, 1, Duration::from_secs(2), false);
+        assert_eq!(11, limiter.limit());
+    }
+
+    #[test]
+    fn aimd_respects_the_min_and_max_limits() {
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyLimitConfig::aimd()
+                .with_initial_limit(3)
+                .with_min_limit(2)
+                .with_max_limit(4),
+        );
+        run_attempts(&limiter, 3, Duration::from_millis(1), false);
+        assert_eq!(4, limiter.limit());
+        for _ in 0..10 {
+            run_attempts(&limiter, 1, Duration::from_millis(1), true);
+        }
+        assert_eq!(2, limiter.limit());
+    }
+
+    #[test]
+    fn gradient_backs_off_when_latency_increases() {
+        let limiter =
+            ConcurrencyLimiter::new(ConcurrencyLimitConfig::gradient().with_initial_limit(20));
+        for _ in 0..5 {
+            run_attempts(&limiter, 20, Duration::from_millis(100), false);
+        }
+        let steady_limit = limiter.limit();
+        assert!(steady_limit > 20, "limit grows while latency is stable");
+
+        for _ in 0..5 {
+            run_attempts(&limiter, 20, Duration::from_secs(1), false);
+        }
+        assert!(
+            limiter.limit() < steady_limit,
+            "limit shrinks once latency rises"
+        );
+    }
+
+    #[test]
+    fn gradient_backs_off_on_overload() {
+        let limiter =
+            ConcurrencyLimiter::new(ConcurrencyLimitConfig::gradient().with_initial_limit(100));
+        run_attempts(&limiter, 1, Duration::from_millis(100), true);
+        assert!(limiter.limit() < 100);
+    }
+}
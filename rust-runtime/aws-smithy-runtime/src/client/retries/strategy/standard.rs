@@ -14,21 +14,29 @@ use aws_smithy_runtime_api::client::interceptors::context::InterceptorContext;
 use aws_smithy_runtime_api::client::retries::classifiers::{RetryAction, RetryReason};
 use aws_smithy_runtime_api::client::retries::{RequestAttempts, RetryStrategy, ShouldAttempt};
 use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::client::telemetry::{attributes, metrics};
 use aws_smithy_types::config_bag::{ConfigBag, Storable, StoreReplace};
 use aws_smithy_types::retry::{ErrorKind, RetryConfig, RetryMode};
 
 use crate::client::retries::classifiers::run_classifiers_on_ctx;
 use crate::client::retries::client_rate_limiter::{ClientRateLimiter, RequestReason};
+use crate::client::retries::concurrency_limiter::InFlightAttempt;
 use crate::client::retries::strategy::standard::ReleaseResult::{
     APermitWasReleased, NoPermitWasReleased,
 };
 use crate::client::retries::token_bucket::TokenBucket;
-use crate::client::retries::{ClientRateLimiterPartition, RetryPartition};
+use crate::client::retries::{
+    ClientRateLimiterPartition, ConcurrencyLimiter, ConcurrencyLimiterPartition, RetryPartition,
+};
+use crate::client::telemetry;
 use crate::static_partition_map::StaticPartitionMap;
 
 static CLIENT_RATE_LIMITER: StaticPartitionMap<ClientRateLimiterPartition, ClientRateLimiter> =
     StaticPartitionMap::new();
 
+static CONCURRENCY_LIMITER: StaticPartitionMap<ConcurrencyLimiterPartition, ConcurrencyLimiter> =
+    StaticPartitionMap::new();
+
 /// Retry strategy with exponential backoff, max attempts, and a token bucket.
 #[derive(Debug, Default)]
 pub struct StandardRetryStrategy {
@@ -91,6 +99,23 @@ impl StandardRetryStrategy {
         None
     }
 
+    /// Returns a [`ConcurrencyLimiter`] if a concurrency limit is configured.
+    ///
+    /// A limiter stored in the config bag takes precedence over the one shared by all clients
+    /// using the same [`RetryPartition`].
+    pub(crate) fn concurrency_limiter(cfg: &ConfigBag) -> Option<ConcurrencyLimiter> {
+        let retry_config = cfg.load::<RetryConfig>()?;
+        let concurrency_limit = retry_config.concurrency_limit()?;
+        if let Some(limiter) = cfg.load::<ConcurrencyLimiter>() {
+            return Some(limiter.clone());
+        }
+        let retry_partition = cfg.load::<RetryPartition>().expect("set in default config");
+        Some(CONCURRENCY_LIMITER.get_or_init(
+            ConcurrencyLimiterPartition::new(retry_partition.clone()),
+            || ConcurrencyLimiter::new(concurrency_limit.clone()),
+        ))
+    }
+
     fn calculate_backoff(
         &self,
         runtime_components: &RuntimeComponents,
@@ -124,7 +149,9 @@ impl StandardRetryStrategy {
                     Ok(delay)
                 } else {
                     if let Some(tb) = token_bucket {
-                        match tb.acquire(kind) {
+                        let permit = tb.acquire(kind);
+                        record_token_bucket(cfg, tb);
+                        match permit {
                             Some(permit) => self.set_retry_permit(permit),
                             None => {
                                 debug!("attempt #{request_attempts} failed with {kind:?}; However, no retry permits are available, so no retry will be attempted.");
@@ -199,6 +226,12 @@ impl RetryStrategy for StandardRetryStrategy {
     ) -> Result<ShouldAttempt, BoxError> {
         let retry_cfg = cfg.load::<RetryConfig>().expect("retry config is required");
 
+        // Run the classifier against the context to determine if we should retry
+        let retry_classifiers = runtime_components.retry_classifiers();
+        let classifier_result = run_classifiers_on_ctx(retry_classifiers, ctx);
+
+        finish_in_flight_attempt(runtime_components, cfg, &classifier_result);
+
         // Check if we're out of attempts
         let request_attempts = cfg
             .load::<RequestAttempts>()
@@ -215,10 +248,6 @@ impl RetryStrategy for StandardRetryStrategy {
             return Ok(ShouldAttempt::No);
         }
 
-        // Run the classifier against the context to determine if we should retry
-        let retry_classifiers = runtime_components.retry_classifiers();
-        let classifier_result = run_classifiers_on_ctx(retry_classifiers, ctx);
-
         if classifier_result.should_retry() {
             // Calculate the appropriate backoff time.
             let backoff = match self.calculate_backoff(
@@ -231,6 +260,13 @@ impl RetryStrategy for StandardRetryStrategy {
                 // In some cases, backoff calculation will decide that we shouldn't retry at all.
                 Err(value) => return Ok(value),
             };
+            if let RetryAction::RetryIndicated(RetryReason::RetryableError { kind, .. }) =
+                &classifier_result
+            {
+                telemetry::record_with(cfg, &metrics::RETRIES, 1.0, |attributes| {
+                    attributes.set(attributes::ERROR_TYPE, format!("{kind:?}"));
+                });
+            }
             debug!(
                 "attempt #{request_attempts} failed with {:?}; retrying after {:?}",
                 classifier_result, backoff,
@@ -248,6 +284,7 @@ impl RetryStrategy for StandardRetryStrategy {
                     // back up again.
                     tb.regenerate_a_token();
                 }
+                record_token_bucket(cfg, tb);
             }
             update_rate_limiter_if_exists(runtime_components, cfg, false);
 
@@ -256,6 +293,27 @@ impl RetryStrategy for StandardRetryStrategy {
     }
 }
 
+/// Feeds the outcome of the attempt that just finished into the concurrency limiter, if one is in use.
+fn finish_in_flight_attempt(
+    runtime_components: &RuntimeComponents,
+    cfg: &ConfigBag,
+    classifier_result: &RetryAction,
+) {
+    if let Some(attempt) = cfg.load::<InFlightAttempt>() {
+        let overloaded = matches!(
+            classifier_result,
+            RetryAction::RetryIndicated(RetryReason::RetryableError {
+                kind: ErrorKind::ThrottlingError | ErrorKind::TransientError,
+                ..
+            })
+        );
+        let finished_at = runtime_components
+            .time_source()
+            .map(|time_source| time_source.now());
+        attempt.finish(finished_at, overloaded);
+    }
+}
+
 fn update_rate_limiter_if_exists(
     runtime_components: &RuntimeComponents,
     cfg: &ConfigBag,
@@ -264,9 +322,18 @@ fn update_rate_limiter_if_exists(
     if let Some(crl) = StandardRetryStrategy::adaptive_retry_rate_limiter(runtime_components, cfg) {
         let seconds_since_unix_epoch = get_seconds_since_unix_epoch(runtime_components);
         crl.update_rate_limiter(seconds_since_unix_epoch, is_throttling_error);
+        telemetry::record(cfg, &metrics::RATE_LIMITER_FILL_RATE, crl.fill_rate());
     }
 }
 
+fn record_token_bucket(cfg: &ConfigBag, token_bucket: &TokenBucket) {
+    telemetry::record(
+        cfg,
+        &metrics::TOKEN_BUCKET_AVAILABLE,
+        token_bucket.available_permits() as f64,
+    );
+}
+
 fn check_rate_limiter_for_delay(
     runtime_components: &RuntimeComponents,
     cfg: &ConfigBag,
@@ -329,8 +396,9 @@ fn get_seconds_since_unix_epoch(runtime_components: &RuntimeComponents) -> f64 {
 mod tests {
     use std::fmt;
     use std::sync::Mutex;
-    use std::time::Duration;
+    use std::time::{Duration, UNIX_EPOCH};
 
+    use aws_smithy_async::time::StaticTimeSource;
     use aws_smithy_runtime_api::client::interceptors::context::{
         Input, InterceptorContext, Output,
     };
@@ -345,9 +413,12 @@ mod tests {
         RuntimeComponents, RuntimeComponentsBuilder,
     };
     use aws_smithy_types::config_bag::{ConfigBag, Layer};
-    use aws_smithy_types::retry::{ErrorKind, ProvideErrorKind, RetryConfig};
+    use aws_smithy_types::retry::{
+        ConcurrencyLimitConfig, ErrorKind, ProvideErrorKind, RetryConfig,
+    };
 
     use super::{calculate_exponential_backoff, StandardRetryStrategy};
+    use crate::client::retries::ConcurrencyLimiter;
     #[cfg(feature = "test-util")]
     use crate::client::retries::TokenBucket;
 
@@ -426,6 +497,41 @@ mod tests {
         test_should_retry_error_kind(ErrorKind::ThrottlingError);
     }
 
+    #[test]
+    fn concurrency_limiter_releases_finished_attempts() {
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyLimitConfig::aimd()
+                .with_initial_limit(1)
+                .with_max_limit(1),
+        );
+        let (ctx, _, _) =
+            set_up_cfg_and_context(ErrorKind::ThrottlingError, 1, RetryConfig::standard());
+        let rc = RuntimeComponentsBuilder::for_tests()
+            .with_retry_classifier(SharedRetryClassifier::new(AlwaysRetry(
+                ErrorKind::ThrottlingError,
+            )))
+            .with_time_source(Some(StaticTimeSource::new(UNIX_EPOCH)))
+            .build()
+            .unwrap();
+        let mut layer = Layer::new("test");
+        layer.store_put(RequestAttempts::new(1));
+        layer.store_put(
+            RetryConfig::standard().with_concurrency_limit(ConcurrencyLimitConfig::aimd()),
+        );
+        layer.store_put(limiter.clone());
+        layer.store_put(limiter.try_acquire().expect("a slot is available"));
+        let cfg = ConfigBag::of_layers(vec![layer]);
+        let strategy = StandardRetryStrategy::new();
+
+        assert_eq!(1, limiter.in_flight());
+        assert!(limiter.try_acquire().is_none());
+        strategy
+            .should_attempt_retry(&ctx, &rc, &cfg)
+            .expect("method is infallible for this use");
+        assert_eq!(0, limiter.in_flight());
+        assert!(limiter.try_acquire().is_some());
+    }
+
     #[test]
     fn dont_retry_when_out_of_attempts() {
         let current_attempts = 4;
@@ -62,6 +62,14 @@ impl TokenBucket {
             .ok()
     }
 
+    #[cfg(all(aws_sdk_unstable, feature = "unstable-hedging"))]
+    pub(crate) fn acquire_hedge_permit(&self) -> Option<OwnedSemaphorePermit> {
+        self.semaphore
+            .clone()
+            .try_acquire_many_owned(self.retry_cost)
+            .ok()
+    }
+
     pub(crate) fn regenerate_a_token(&self) {
         if self.semaphore.available_permits() < (self.max_permits) {
             trace!("adding {PERMIT_REGENERATION_AMOUNT} back into the bucket");
@@ -69,7 +77,6 @@ impl TokenBucket {
         }
     }
 
-    #[cfg(all(test, feature = "test-util"))]
     pub(crate) fn available_permits(&self) -> usize {
         self.semaphore.available_permits()
     }
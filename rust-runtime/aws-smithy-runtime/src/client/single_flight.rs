@@ -0,0 +1,427 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Coalescing of identical in-flight requests.
+//!
+//! When [`SingleFlight`] is added to a client with [`SingleFlightRuntimePlugin`], read-only
+//! operations that serialize to the same request, resolve to the same endpoint, and are signed with
+//! the same identity share a single HTTP exchange. The first caller sends the request, and everyone who asks for the same thing
+//! while it is in flight waits for its response instead of sending their own. Each caller then
+//! deserializes its own copy of the response, so every caller gets its own output or error.
+//!
+//! Shared responses are read into memory before they are handed out, so single-flight shouldn't be
+//! used for operations with large streaming responses.
+
+use crate::client::orchestrator::IdentityDiscriminator;
+use crate::client::response_cache::SerializedRequest;
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::hedging::HedgedAttempt;
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugin;
+use aws_smithy_runtime_api::http::{Headers, StatusCode};
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::byte_stream::ByteStream;
+use aws_smithy_types::config_bag::{ConfigBag, FrozenLayer, Layer, Storable, StoreReplace};
+use aws_smithy_types::endpoint::Endpoint;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tracing::debug;
+
+/// Identifies requests that can share an HTTP exchange.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct FlightKey {
+    service: String,
+    operation: String,
+    endpoint: String,
+    identity: IdentityDiscriminator,
+    request: Vec<u8>,
+}
+
+type Outcome = Result<SharedResponse, Arc<ConnectorError>>;
+
+#[derive(Debug)]
+struct SharedResponse {
+    status: StatusCode,
+    headers: Headers,
+    body: Bytes,
+}
+
+impl SharedResponse {
+    fn to_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::new(self.status, SdkBody::from(self.body.clone()));
+        *response.headers_mut() = self.headers.clone();
+        response
+    }
+}
+
+#[derive(Debug)]
+struct Flight {
+    id: u64,
+    outcome: watch::Receiver<Option<Arc<Outcome>>>,
+}
+
+/// Shares HTTP exchanges between identical requests that are in flight at the same time.
+///
+/// Cloning a `SingleFlight` shares its in-flight requests, so the same instance can be given to
+/// several clients with [`SingleFlightRuntimePlugin`].
+#[derive(Clone, Debug, Default)]
+pub struct SingleFlight {
+    flights: Arc<Mutex<HashMap<FlightKey, Flight>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Storable for SingleFlight {
+    type Storer = StoreReplace<Self>;
+}
+
+impl SingleFlight {
+    /// Creates a new `SingleFlight`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of distinct requests currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.flights.lock().unwrap().len()
+    }
+}
+
+/// Runtime plugin that coalesces identical in-flight requests with a [`SingleFlight`].
+#[derive(Debug)]
+pub struct SingleFlightRuntimePlugin {
+    config: FrozenLayer,
+}
+
+impl SingleFlightRuntimePlugin {
+    /// Creates a runtime plugin that uses the given `SingleFlight`.
+    pub fn new(single_flight: SingleFlight) -> Self {
+        let mut layer = Layer::new("SingleFlight");
+        layer.store_put(single_flight);
+        Self {
+            config: layer.freeze(),
+        }
+    }
+}
+
+impl RuntimePlugin for SingleFlightRuntimePlugin {
+    fn config(&self) -> Option<FrozenLayer> {
+        Some(self.config.clone())
+    }
+}
+
+/// Sends a request with `send`, unless an identical request is already in flight, in which case
+/// its response is shared.
+///
+/// This is called by the orchestrator to transmit requests.
+pub(crate) async fn transmit<F>(
+    cfg: &mut ConfigBag,
+    send: impl FnOnce(&mut ConfigBag) -> F,
+) -> Result<HttpResponse, ConnectorError>
+where
+    F: Future<Output = Result<HttpResponse, ConnectorError>>,
+{
+    let (Some(single_flight), Some(serialized), Some(endpoint), Some(identity)) = (
+        cfg.load::<SingleFlight>(),
+        cfg.load::<SerializedRequest>(),
+        cfg.load::<Endpoint>(),
+        cfg.load::<IdentityDiscriminator>(),
+    ) else {
+        return send(cfg).await;
+    };
+    // Hedged attempts race the attempt they hedge, so they must not wait for it.
+    if cfg.load::<HedgedAttempt>().is_some() {
+        return send(cfg).await;
+    }
+    let single_flight = single_flight.clone();
+    let key = FlightKey {
+        service: serialized.service.clone(),
+        operation: serialized.operation.clone(),
+        endpoint: endpoint.url().to_string(),
+        identity: *identity,
+        request: serialized.request.clone(),
+    };
+
+    let leader = {
+        let mut flights = single_flight.flights.lock().unwrap();
+        match flights.get(&key) {
+            Some(flight) => Err(flight.outcome.clone()),
+            None => {
+                let (sender, outcome) = watch::channel(None);
+                let id = single_flight.next_id.fetch_add(1, Ordering::Relaxed);
+                flights.insert(key.clone(), Flight { id, outcome });
+                Ok(Leader {
+                    single_flight: single_flight.clone(),
+                    key,
+                    id,
+                    sender,
+                })
+            }
+        }
+    };
+
+    match leader {
+        Ok(leader) => {
+            let outcome = match send(cfg).await {
+                Ok(mut response) => match ByteStream::new(response.take_body()).collect().await {
+                    Ok(body) => Ok(SharedResponse {
+                        status: response.status(),
+                        headers: response.headers().clone(),
+                        body: body.into_bytes(),
+                    }),
+                    Err(err) => Err(Arc::new(ConnectorError::io(err.into()))),
+                },
+                Err(err) => Err(Arc::new(err)),
+            };
+            let outcome = Arc::new(outcome);
+            leader.finish(outcome.clone());
+            to_result(&outcome)
+        }
+        Err(mut outcome) => {
+            debug!("an identical request is already in flight; waiting for its response");
+            loop {
+                if let Some(outcome) = outcome.borrow_and_update().as_ref() {
+                    return to_result(outcome);
+                }
+                if outcome.changed().await.is_err() {
+                    debug!("the identical request was cancelled; sending this request instead");
+                    return send(cfg).await;
+                }
+            }
+        }
+    }
+}
+
+/// The caller that sends the request on behalf of everyone waiting for it.
+///
+/// If the leader is dropped before it finishes, e.g. because it timed out, the flight is removed
+/// and the waiting callers send their own requests.
+struct Leader {
+    single_flight: SingleFlight,
+    key: FlightKey,
+    id: u64,
+    sender: watch::Sender<Option<Arc<Outcome>>>,
+}
+
+impl Leader {
+    fn finish(self, outcome: Arc<Outcome>) {
+        // Remove the flight before publishing its outcome so that requests made from now on
+        // aren't answered with a response that was received before they were made.
+        self.remove();
+        let _ = self.sender.send(Some(outcome));
+    }
+
+    fn remove(&self) {
+        let mut flights = self.single_flight.flights.lock().unwrap();
+        if flights.get(&self.key).map(|flight| flight.id) == Some(self.id) {
+            flights.remove(&self.key);
+        }
+    }
+}
+
+impl Drop for Leader {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+fn to_result(outcome: &Outcome) -> Result<HttpResponse, ConnectorError> {
+    match outcome {
+        Ok(response) => Ok(response.to_response()),
+        Err(err) => Err(share_connector_error(err.clone())),
+    }
+}
+
+/// Creates a connector error of the same kind as the shared one, with the shared error as its source.
+fn share_connector_error(err: Arc<ConnectorError>) -> ConnectorError {
+    let source: BoxError = Box::new(SharedConnectorError(err.clone()));
+    if err.is_timeout() {
+        ConnectorError::timeout(source)
+    } else if err.is_io() {
+        ConnectorError::io(source)
+    } else if err.is_user() {
+        ConnectorError::user(source)
+    } else if err.is_circuit_open() {
+        ConnectorError::circuit_open(source)
+    } else {
+        ConnectorError::other(source, err.as_other())
+    }
+}
+
+/// An error that occurred while sending a request that was shared with other callers.
+#[derive(Debug)]
+struct SharedConnectorError(Arc<ConnectorError>);
+
+impl fmt::Display for SharedConnectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "an identical request that this request was coalesced with failed"
+        )
+    }
+}
+
+impl StdError for SharedConnectorError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.0.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::orchestrator::operation::Operation;
+    use aws_smithy_runtime_api::client::hedging::OperationIdempotency;
+    use aws_smithy_runtime_api::client::http::{
+        http_client_fn, HttpConnector, HttpConnectorFuture,
+    };
+    use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+    use aws_smithy_runtime_api::client::result::SdkError;
+    use aws_smithy_runtime_api::shared::IntoShared;
+    use aws_smithy_types::timeout::TimeoutConfig;
+    use std::convert::Infallible;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    /// Responds after a delay, so that concurrent requests overlap.
+    #[derive(Clone, Debug, Default)]
+    struct SlowConnector {
+        calls: Arc<AtomicUsize>,
+        fail: bool,
+    }
+
+    impl HttpConnector for SlowConnector {
+        fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            let fail = self.fail;
+            HttpConnectorFuture::new(async move {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                if fail {
+                    return Err(ConnectorError::io("connection reset".into()));
+                }
+                Ok(HttpResponse::try_from(
+                    ::http::Response::builder()
+                        .header("x-call", call.to_string())
+                        .body(SdkBody::from(format!("response #{call}")))
+                        .unwrap(),
+                )
+                .unwrap())
+            })
+        }
+    }
+
+    fn operation(
+        connector: SlowConnector,
+        single_flight: SingleFlight,
+        idempotency: Option<OperationIdempotency>,
+    ) -> Operation<&'static str, String, Infallible> {
+        let builder = Operation::builder()
+            .service_name("test")
+            .operation_name("GetConfiguration")
+            .http_client(http_client_fn(move |_, _| connector.clone().into_shared()))
+            .endpoint_url("http://localhost:1234")
+            .no_auth()
+            .no_retry()
+            .timeout_config(TimeoutConfig::disabled())
+            .runtime_plugin(SingleFlightRuntimePlugin::new(single_flight))
+            .serializer(|input: &'static str| Ok(HttpRequest::new(SdkBody::from(input))))
+            .deserializer::<_, Infallible>(|response| {
+                Ok(std::str::from_utf8(response.body().bytes().unwrap())
+                    .unwrap()
+                    .to_string())
+            });
+        match idempotency {
+            Some(idempotency) => builder.idempotency(idempotency),
+            None => builder,
+        }
+        .build()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn identical_requests_share_a_response() {
+        let connector = SlowConnector::default();
+        let single_flight = SingleFlight::new();
+        let operation = operation(
+            connector.clone(),
+            single_flight.clone(),
+            Some(OperationIdempotency::ReadOnly),
+        );
+
+        let (a, b, c, other) = futures_util::join!(
+            operation.invoke("a"),
+            operation.invoke("a"),
+            operation.invoke("a"),
+            operation.invoke("b"),
+        );
+        assert_eq!("response #0", a.unwrap());
+        assert_eq!("response #0", b.unwrap());
+        assert_eq!("response #0", c.unwrap());
+        assert_eq!("response #1", other.unwrap());
+        assert_eq!(2, connector.calls.load(Ordering::SeqCst));
+        assert_eq!(0, single_flight.in_flight());
+
+        // Requests made after the response was received send a new request
+        assert_eq!("response #2", operation.invoke("a").await.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn errors_are_shared() {
+        let connector = SlowConnector {
+            fail: true,
+            ..Default::default()
+        };
+        let operation = operation(
+            connector.clone(),
+            SingleFlight::new(),
+            Some(OperationIdempotency::ReadOnly),
+        );
+
+        let (a, b) = futures_util::join!(operation.invoke("a"), operation.invoke("a"));
+        for result in [a, b] {
+            match result.unwrap_err() {
+                SdkError::DispatchFailure(err) => assert!(err.is_io()),
+                err => panic!("expected a dispatch failure, got {err:?}"),
+            }
+        }
+        assert_eq!(1, connector.calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_with_different_identities_arent_coalesced() {
+        let connector = SlowConnector::default();
+        let single_flight = SingleFlight::new();
+        // Each operation has its own identity resolver
+        let a = operation(
+            connector.clone(),
+            single_flight.clone(),
+            Some(OperationIdempotency::ReadOnly),
+        );
+        let b = operation(
+            connector.clone(),
+            single_flight,
+            Some(OperationIdempotency::ReadOnly),
+        );
+
+        let (a, b) = futures_util::join!(a.invoke("a"), b.invoke("a"));
+        assert_ne!(a.unwrap(), b.unwrap());
+        assert_eq!(2, connector.calls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn only_read_only_operations_are_coalesced() {
+        let connector = SlowConnector::default();
+        let operation = operation(connector.clone(), SingleFlight::new(), None);
+
+        let (a, b) = futures_util::join!(operation.invoke("a"), operation.invoke("a"));
+        assert_ne!(a.unwrap(), b.unwrap());
+        assert_eq!(2, connector.calls.load(Ordering::SeqCst));
+    }
+}
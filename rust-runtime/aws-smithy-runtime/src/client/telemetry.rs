@@ -0,0 +1,254 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Recording of client metrics.
+//!
+//! Metrics are recorded with the [`SharedTelemetryProvider`] in the config bag, which is added to
+//! a client with [`TelemetryRuntimePlugin`]. Without one, nothing is recorded.
+
+use aws_smithy_runtime_api::client::interceptors::context::InterceptorContext;
+use aws_smithy_runtime_api::client::orchestrator::Metadata;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::client::runtime_plugin::RuntimePlugin;
+use aws_smithy_runtime_api::client::telemetry::{
+    attributes, metrics, Attributes, Metric, SharedTelemetryProvider, TelemetryProvider,
+};
+use aws_smithy_runtime_api::shared::IntoShared;
+use aws_smithy_types::config_bag::{ConfigBag, FrozenLayer, Layer};
+use std::time::SystemTime;
+
+/// An OpenTelemetry-backed [`TelemetryProvider`].
+#[cfg(feature = "telemetry-otel")]
+pub mod otel;
+
+/// Runtime plugin that records client metrics with a [`TelemetryProvider`].
+#[derive(Debug)]
+pub struct TelemetryRuntimePlugin {
+    config: FrozenLayer,
+}
+
+impl TelemetryRuntimePlugin {
+    /// Creates a runtime plugin that records metrics with the given provider.
+    pub fn new(provider: impl TelemetryProvider + 'static) -> Self {
+        let mut layer = Layer::new("Telemetry");
+        layer.store_put::<SharedTelemetryProvider>(provider.into_shared());
+        Self {
+            config: layer.freeze(),
+        }
+    }
+}
+
+impl RuntimePlugin for TelemetryRuntimePlugin {
+    fn config(&self) -> Option<FrozenLayer> {
+        Some(self.config.clone())
+    }
+}
+
+/// Returns the attributes that identify the operation being run.
+fn operation_attributes(cfg: &ConfigBag) -> Attributes {
+    let mut attributes = Attributes::new();
+    if let Some(metadata) = cfg.load::<Metadata>() {
+        attributes
+            .set(attributes::RPC_SERVICE, metadata.service())
+            .set(attributes::RPC_METHOD, metadata.name());
+    }
+    attributes
+}
+
+/// Records a metric for the operation being run, if a telemetry provider is configured.
+pub(crate) fn record(cfg: &ConfigBag, metric: &Metric, value: f64) {
+    record_with(cfg, metric, value, |_| {})
+}
+
+/// Records a metric for the operation being run, with extra attributes set by `attributes`.
+pub(crate) fn record_with(
+    cfg: &ConfigBag,
+    metric: &Metric,
+    value: f64,
+    attributes: impl FnOnce(&mut Attributes),
+) {
+    if let Some(provider) = cfg.load::<SharedTelemetryProvider>() {
+        let mut operation_attributes = operation_attributes(cfg);
+        attributes(&mut operation_attributes);
+        provider.record(metric, value, &operation_attributes);
+    }
+}
+
+/// Records that the operation failed, if it did.
+pub(crate) fn record_call_error(ctx: &InterceptorContext, cfg: &ConfigBag) {
+    if let Some(Err(err)) = ctx.output_or_error() {
+        let error_type = if err.is_operation_error() {
+            "ServiceError"
+        } else if err.is_timeout_error() {
+            "TimeoutError"
+        } else if err.is_connector_error() {
+            "ConnectorError"
+        } else if err.is_response_error() {
+            "ResponseError"
+        } else if err.is_interceptor_error() {
+            "InterceptorError"
+        } else {
+            "OtherError"
+        };
+        record_with(cfg, &metrics::CALL_ERRORS, 1.0, |attributes| {
+            attributes.set(attributes::ERROR_TYPE, error_type);
+        });
+    }
+}
+
+/// Measures how long something takes, so that it can be recorded as a metric.
+///
+/// A stopwatch does nothing if no telemetry provider is configured.
+#[derive(Debug)]
+pub(crate) struct Stopwatch {
+    start: Option<SystemTime>,
+}
+
+impl Stopwatch {
+    pub(crate) fn start(runtime_components: &RuntimeComponents, cfg: &ConfigBag) -> Self {
+        let start = match (
+            cfg.load::<SharedTelemetryProvider>(),
+            runtime_components.time_source(),
+        ) {
+            (Some(_), Some(time_source)) => Some(time_source.now()),
+            _ => None,
+        };
+        Self { start }
+    }
+
+    /// Records the time elapsed since the stopwatch was started, in seconds.
+    pub(crate) fn stop(
+        self,
+        runtime_components: &RuntimeComponents,
+        cfg: &ConfigBag,
+        metric: &Metric,
+    ) {
+        self.stop_with(runtime_components, cfg, metric, |_| {})
+    }
+
+    /// Records the time elapsed since the stopwatch was started, in seconds, with extra attributes.
+    pub(crate) fn stop_with(
+        self,
+        runtime_components: &RuntimeComponents,
+        cfg: &ConfigBag,
+        metric: &Metric,
+        attributes: impl FnOnce(&mut Attributes),
+    ) {
+        if let (Some(start), Some(time_source)) = (self.start, runtime_components.time_source()) {
+            let elapsed = time_source
+                .now()
+                .duration_since(start)
+                .unwrap_or_default()
+                .as_secs_f64();
+            record_with(cfg, metric, elapsed, attributes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::orchestrator::operation::Operation;
+    use aws_smithy_runtime_api::client::http::{
+        http_client_fn, HttpConnector, HttpConnectorFuture,
+    };
+    use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+    use aws_smithy_types::body::SdkBody;
+    use aws_smithy_types::timeout::TimeoutConfig;
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    /// Remembers every value it's given.
+    #[derive(Clone, Debug, Default)]
+    struct RecordingTelemetryProvider {
+        records: Arc<Mutex<Vec<(Metric, f64, Attributes)>>>,
+    }
+
+    impl RecordingTelemetryProvider {
+        fn recorded(&self, metric: &Metric) -> Vec<(f64, Attributes)> {
+            self.records
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(m, _, _)| m == metric)
+                .map(|(_, value, attributes)| (*value, attributes.clone()))
+                .collect()
+        }
+    }
+
+    impl TelemetryProvider for RecordingTelemetryProvider {
+        fn record(&self, metric: &Metric, value: f64, attributes: &Attributes) {
+            self.records
+                .lock()
+                .unwrap()
+                .push((*metric, value, attributes.clone()));
+        }
+    }
+
+    #[derive(Debug)]
+    struct OkConnector;
+
+    impl HttpConnector for OkConnector {
+        fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+            HttpConnectorFuture::ready(Ok(HttpResponse::new(
+                200.try_into().unwrap(),
+                SdkBody::empty(),
+            )))
+        }
+    }
+
+    #[tokio::test]
+    async fn records_metrics_for_each_phase() {
+        let provider = RecordingTelemetryProvider::default();
+        let operation = Operation::builder()
+            .service_name("test-service")
+            .operation_name("GetThing")
+            .http_client(http_client_fn(|_, _| OkConnector.into_shared()))
+            .endpoint_url("http://localhost:1234")
+            .no_auth()
+            .no_retry()
+            .timeout_config(TimeoutConfig::disabled())
+            .runtime_plugin(TelemetryRuntimePlugin::new(provider.clone()))
+            .serializer(|_: ()| Ok(HttpRequest::new(SdkBody::empty())))
+            .deserializer::<_, Infallible>(|_| Ok(()))
+            .build();
+        operation.invoke(()).await.unwrap();
+
+        let expected_attributes = Attributes::new()
+            .with(attributes::RPC_SERVICE, "test-service")
+            .with(attributes::RPC_METHOD, "GetThing");
+        assert_eq!(
+            vec![(1.0, expected_attributes.clone())],
+            provider.recorded(&metrics::CALL_ATTEMPTS)
+        );
+        for metric in [
+            metrics::CALL_DURATION,
+            metrics::ATTEMPT_DURATION,
+            metrics::SERIALIZATION_DURATION,
+            metrics::RESOLVE_ENDPOINT_DURATION,
+            metrics::SIGNING_DURATION,
+            metrics::TRANSMIT_DURATION,
+            metrics::DESERIALIZATION_DURATION,
+        ] {
+            let recorded = provider.recorded(&metric);
+            assert_eq!(
+                1,
+                recorded.len(),
+                "{} should be recorded once",
+                metric.name()
+            );
+            assert_eq!(expected_attributes, recorded[0].1);
+        }
+        assert!(provider.recorded(&metrics::CALL_ERRORS).is_empty());
+
+        let hooks: Vec<_> = provider
+            .recorded(&metrics::INTERCEPTOR_DURATION)
+            .into_iter()
+            .map(|(_, attributes)| attributes.get(attributes::HOOK).unwrap().to_string())
+            .collect();
+        assert!(hooks.contains(&"modify_before_transmit".to_string()));
+        assert!(hooks.contains(&"read_after_deserialization".to_string()));
+    }
+}
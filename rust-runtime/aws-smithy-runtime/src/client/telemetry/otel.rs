@@ -0,0 +1,201 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+use aws_smithy_runtime_api::client::telemetry::{
+    Attributes, Metric, MetricKind, TelemetryProvider,
+};
+use opentelemetry::metrics::{Counter, Gauge, Histogram, Meter, MeterProvider};
+use opentelemetry::KeyValue;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+/// The name of the instrumentation scope metrics are recorded in.
+const SCOPE: &str = "aws-smithy-runtime";
+
+#[derive(Clone)]
+enum Instrument {
+    Counter(Counter<u64>),
+    Histogram(Histogram<f64>),
+    Gauge(Gauge<f64>),
+}
+
+/// A [`TelemetryProvider`] that records metrics with an OpenTelemetry [`Meter`].
+///
+/// Instruments are created the first time a metric is recorded, and reused after that.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aws_smithy_runtime::client::telemetry::otel::OtelTelemetryProvider;
+/// use aws_smithy_runtime::client::telemetry::TelemetryRuntimePlugin;
+///
+/// // Record metrics with the globally registered meter provider
+/// let plugin = TelemetryRuntimePlugin::new(OtelTelemetryProvider::global());
+/// ```
+pub struct OtelTelemetryProvider {
+    meter: Meter,
+    instruments: Mutex<HashMap<&'static str, Instrument>>,
+}
+
+impl OtelTelemetryProvider {
+    /// Creates a provider that records metrics with a meter from the given meter provider.
+    pub fn new(meter_provider: &impl MeterProvider) -> Self {
+        Self::from_meter(meter_provider.meter(SCOPE))
+    }
+
+    /// Creates a provider that records metrics with the given meter.
+    pub fn from_meter(meter: Meter) -> Self {
+        Self {
+            meter,
+            instruments: Default::default(),
+        }
+    }
+
+    /// Creates a provider that records metrics with the globally registered meter provider.
+    pub fn global() -> Self {
+        Self::from_meter(opentelemetry::global::meter(SCOPE))
+    }
+
+    fn instrument(&self, metric: &Metric) -> Instrument {
+        let mut instruments = self.instruments.lock().unwrap();
+        instruments
+            .entry(metric.name())
+            .or_insert_with(|| match metric.kind() {
+                MetricKind::Counter => Instrument::Counter(
+                    self.meter
+                        .u64_counter(metric.name())
+                        .with_description(metric.description())
+                        .with_unit(metric.unit())
+                        .build(),
+                ),
+                MetricKind::Gauge => Instrument::Gauge(
+                    self.meter
+                        .f64_gauge(metric.name())
+                        .with_description(metric.description())
+                        .with_unit(metric.unit())
+                        .build(),
+                ),
+                _ => Instrument::Histogram(
+                    self.meter
+                        .f64_histogram(metric.name())
+                        .with_description(metric.description())
+                        .with_unit(metric.unit())
+                        .build(),
+                ),
+            })
+            .clone()
+    }
+}
+
+impl fmt::Debug for OtelTelemetryProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtelTelemetryProvider")
+            .field("meter", &self.meter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TelemetryProvider for OtelTelemetryProvider {
+    fn record(&self, metric: &Metric, value: f64, attributes: &Attributes) {
+        let attributes: Vec<_> = attributes
+            .iter()
+            .map(|(key, value)| KeyValue::new(key, value.to_string()))
+            .collect();
+        match self.instrument(metric) {
+            Instrument::Counter(counter) => counter.add(value as u64, &attributes),
+            Instrument::Histogram(histogram) => histogram.record(value, &attributes),
+            Instrument::Gauge(gauge) => gauge.record(value, &attributes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_smithy_runtime_api::client::telemetry::{attributes, metrics};
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::metrics::data::{Histogram, ResourceMetrics, Sum};
+    use opentelemetry_sdk::metrics::reader::MetricReader;
+    use opentelemetry_sdk::metrics::{
+        InstrumentKind, ManualReader, MetricResult, Pipeline, SdkMeterProvider, Temporality,
+    };
+    use opentelemetry_sdk::Resource;
+    use std::sync::{Arc, Weak};
+
+    /// A [`ManualReader`] that can be given to a meter provider and still be read from.
+    #[derive(Clone, Debug)]
+    struct SharedReader(Arc<ManualReader>);
+
+    impl MetricReader for SharedReader {
+        fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+            self.0.register_pipeline(pipeline)
+        }
+
+        fn collect(&self, rm: &mut ResourceMetrics) -> MetricResult<()> {
+            self.0.collect(rm)
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            self.0.force_flush()
+        }
+
+        fn shutdown(&self) -> OTelSdkResult {
+            self.0.shutdown()
+        }
+
+        fn temporality(&self, kind: InstrumentKind) -> Temporality {
+            self.0.temporality(kind)
+        }
+    }
+
+    #[test]
+    fn records_with_otel_instruments() {
+        let reader = SharedReader(Arc::new(ManualReader::builder().build()));
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(reader.clone())
+            .build();
+        let provider = OtelTelemetryProvider::new(&meter_provider);
+
+        let attributes = Attributes::new().with(attributes::RPC_METHOD, "GetThing");
+        provider.record(&metrics::CALL_ATTEMPTS, 1.0, &attributes);
+        provider.record(&metrics::CALL_ATTEMPTS, 1.0, &attributes);
+        provider.record(&metrics::TRANSMIT_DURATION, 0.25, &attributes);
+
+        let mut collected = ResourceMetrics {
+            resource: Resource::builder_empty().build(),
+            scope_metrics: vec![],
+        };
+        reader.collect(&mut collected).unwrap();
+        let scope = &collected.scope_metrics[0];
+        assert_eq!(SCOPE, scope.scope.name());
+
+        let attempts = scope
+            .metrics
+            .iter()
+            .find(|m| m.name == metrics::CALL_ATTEMPTS.name())
+            .unwrap();
+        let attempts = attempts.data.as_any().downcast_ref::<Sum<u64>>().unwrap();
+        assert_eq!(2, attempts.data_points[0].value);
+        assert_eq!(
+            vec![KeyValue::new(attributes::RPC_METHOD, "GetThing")],
+            attempts.data_points[0].attributes
+        );
+
+        let transmit = scope
+            .metrics
+            .iter()
+            .find(|m| m.name == metrics::TRANSMIT_DURATION.name())
+            .unwrap();
+        assert_eq!("s", transmit.unit);
+        let transmit = transmit
+            .data
+            .as_any()
+            .downcast_ref::<Histogram<f64>>()
+            .unwrap();
+        assert_eq!(1, transmit.data_points[0].count);
+        assert_eq!(0.25, transmit.data_points[0].sum);
+    }
+}
@@ -74,6 +74,20 @@ where
         future.await.map(|(value, _expiry)| value.clone())
     }
 
+    /// Returns the expiration time of the cached value, if there is one.
+    pub async fn expiration(&self) -> Option<SystemTime> {
+        self.value
+            .read()
+            .await
+            .get()
+            .map(|(_value, expiration)| *expiration)
+    }
+
+    /// Replaces the cached value, such as with a value that was refreshed before it expired.
+    pub async fn set(&self, value: T, expiration: SystemTime) {
+        *self.value.write().await = OnceCell::new_with(Some((value, expiration)));
+    }
+
     /// If the value is expired, clears the cache. Otherwise, yields the current value.
     pub async fn yield_or_clear_if_expired(&self, now: SystemTime) -> Option<T> {
         // Short-circuit if the value is not expired
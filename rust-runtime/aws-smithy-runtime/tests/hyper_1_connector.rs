@@ -0,0 +1,318 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+#![cfg(all(
+    feature = "client",
+    feature = "test-util",
+    feature = "connector-hyper-1-x",
+    feature = "crypto-ring",
+))]
+
+use aws_smithy_async::rt::sleep::TokioSleep;
+use aws_smithy_async::time::SystemTimeSource;
+use aws_smithy_runtime::client::http::hyper_1::{
+    ClientCertificate, CryptoMode, HyperClientBuilder, TrustStore,
+};
+use aws_smithy_runtime::client::http::proxy::ProxyConfig;
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorSettings, SharedHttpClient,
+};
+use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+use aws_smithy_types::byte_stream::ByteStream;
+use aws_smithy_types::config_bag::ConfigBag;
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa};
+use rustls_0_23::pki_types::pem::PemObject;
+use rustls_0_23::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls_0_23::server::WebPkiClientVerifier;
+use rustls_0_23::{RootCertStore, ServerConfig};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls_0_26::TlsAcceptor;
+
+struct Pki {
+    ca: Certificate,
+    server: Certificate,
+    client: Certificate,
+}
+
+impl Pki {
+    fn new() -> Self {
+        let mut ca_params = CertificateParams::new(Vec::<String>::new());
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        Self {
+            ca: Certificate::from_params(ca_params).unwrap(),
+            server: Certificate::from_params(CertificateParams::new(vec!["localhost".into()]))
+                .unwrap(),
+            client: Certificate::from_params(CertificateParams::new(vec!["client".into()]))
+                .unwrap(),
+        }
+    }
+
+    fn ca_pem(&self) -> String {
+        self.ca.serialize_pem().unwrap()
+    }
+
+    fn client_certificate(&self) -> ClientCertificate {
+        ClientCertificate::from_pem(
+            self.client.serialize_pem_with_signer(&self.ca).unwrap(),
+            self.client.serialize_private_key_pem(),
+        )
+    }
+
+    fn server_config(&self, require_client_auth: bool) -> ServerConfig {
+        let provider = Arc::new(rustls_0_23::crypto::ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap();
+        let builder = if require_client_auth {
+            let mut roots = RootCertStore::empty();
+            roots
+                .add(CertificateDer::from(self.ca.serialize_der().unwrap()))
+                .unwrap();
+            builder.with_client_cert_verifier(
+                WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                    .build()
+                    .unwrap(),
+            )
+        } else {
+            builder.with_no_client_auth()
+        };
+        builder
+            .with_single_cert(
+                vec![CertificateDer::from(
+                    self.server.serialize_der_with_signer(&self.ca).unwrap(),
+                )],
+                PrivateKeyDer::from_pem_slice(self.server.serialize_private_key_pem().as_bytes())
+                    .unwrap(),
+            )
+            .unwrap()
+    }
+}
+
+/// Writes a fixed response to every request and records the request line
+async fn respond_hello(
+    mut stream: impl tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    request_lines: &Mutex<Vec<String>>,
+) {
+    let mut request = vec![0; 4096];
+    let mut len = 0;
+    while !request[..len].ends_with(b"\r\n\r\n") {
+        match stream.read(&mut request[len..]).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => len += n,
+        }
+    }
+    let request = String::from_utf8_lossy(&request[..len]);
+    request_lines
+        .lock()
+        .unwrap()
+        .push(request.lines().next().unwrap().to_string());
+    let _ = stream
+        .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello")
+        .await;
+    let _ = stream.shutdown().await;
+}
+
+/// Starts an HTTPS server that responds `hello` to every request
+async fn https_server(config: ServerConfig) -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let request_lines = Arc::new(Mutex::new(Vec::new()));
+    let lines = request_lines.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (acceptor, lines) = (acceptor.clone(), lines.clone());
+            tokio::spawn(async move {
+                if let Ok(stream) = acceptor.accept(stream).await {
+                    respond_hello(stream, &lines).await;
+                }
+            });
+        }
+    });
+    (addr, request_lines)
+}
+
+/// Starts a proxy stand-in that tunnels `CONNECT` requests and answers forwarded requests itself
+async fn proxy_server() -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let request_lines = Arc::new(Mutex::new(Vec::new()));
+    let lines = request_lines.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let lines = lines.clone();
+            tokio::spawn(async move {
+                let mut head = vec![0; 4096];
+                let n = stream.peek(&mut head).await.unwrap();
+                if !head[..n].starts_with(b"CONNECT ") {
+                    return respond_hello(stream, &lines).await;
+                }
+                let mut len = 0;
+                while !head[..len].ends_with(b"\r\n\r\n") {
+                    len += stream.read(&mut head[len..]).await.unwrap();
+                }
+                let request = String::from_utf8_lossy(&head[..len]).to_string();
+                let request_line = request.lines().next().unwrap().to_string();
+                let target = request_line.split(' ').nth(1).unwrap().to_string();
+                lines.lock().unwrap().push(request_line);
+                let mut upstream = TcpStream::connect(target).await.unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                    .await
+                    .unwrap();
+                let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+            });
+        }
+    });
+    (addr, request_lines)
+}
+
+async fn get(http_client: &SharedHttpClient, uri: &str) -> Result<String, ConnectorError> {
+    let components = RuntimeComponentsBuilder::for_tests()
+        .with_sleep_impl(Some(TokioSleep::new()))
+        .with_time_source(Some(SystemTimeSource::new()))
+        .build()
+        .unwrap();
+    let connector = http_client.http_connector(&HttpConnectorSettings::default(), &components);
+    let response = connector.call(HttpRequest::get(uri).unwrap()).await?;
+    assert_eq!(200, response.status().as_u16());
+    let body = ByteStream::new(response.into_body())
+        .collect()
+        .await
+        .unwrap()
+        .into_bytes();
+    Ok(String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn custom_trust_store() {
+    let pki = Pki::new();
+    let (addr, _) = https_server(pki.server_config(false)).await;
+    let uri = format!("https://localhost:{}/", addr.port());
+
+    let trusting_client = HyperClientBuilder::new()
+        .crypto_mode(CryptoMode::Ring)
+        .trust_store(TrustStore::empty().with_pem_certificate(pki.ca_pem()))
+        .build_https();
+    assert_eq!("hello", get(&trusting_client, &uri).await.unwrap());
+
+    let untrusting_client = HyperClientBuilder::new()
+        .crypto_mode(CryptoMode::Ring)
+        .trust_store(
+            TrustStore::empty()
+                .with_der_certificate(pki.client.serialize_der_with_signer(&pki.ca).unwrap()),
+        )
+        .build_https();
+    let err = get(&untrusting_client, &uri)
+        .await
+        .expect_err("the server certificate isn't trusted");
+    assert!(err.is_io(), "{err:?}");
+}
+
+#[tokio::test]
+async fn client_certificate_is_presented() {
+    let pki = Pki::new();
+    let (addr, request_lines) = https_server(pki.server_config(true)).await;
+    let uri = format!("https://localhost:{}/mtls", addr.port());
+    let trust_store = TrustStore::empty().with_pem_certificate(pki.ca_pem());
+
+    let without_certificate = HyperClientBuilder::new()
+        .crypto_mode(CryptoMode::Ring)
+        .trust_store(trust_store.clone())
+        .build_https();
+    assert!(get(&without_certificate, &uri).await.is_err());
+    assert!(request_lines.lock().unwrap().is_empty());
+
+    let with_certificate = HyperClientBuilder::new()
+        .crypto_mode(CryptoMode::Ring)
+        .trust_store(trust_store)
+        .client_certificate(pki.client_certificate())
+        .build_https();
+    assert_eq!("hello", get(&with_certificate, &uri).await.unwrap());
+    assert_eq!(
+        vec!["GET /mtls HTTP/1.1".to_string()],
+        *request_lines.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn invalid_tls_configuration_is_reported() {
+    let http_client = HyperClientBuilder::new()
+        .crypto_mode(CryptoMode::Ring)
+        .client_certificate(ClientCertificate::from_pem(
+            "not a certificate",
+            "not a key",
+        ))
+        .build_https();
+    let err = RuntimeComponentsBuilder::for_tests()
+        .with_http_client(Some(http_client.clone()))
+        .validate_base_client_config(&ConfigBag::base())
+        .expect_err("invalid client certificate");
+    assert!(
+        err.to_string().contains("client certificate"),
+        "unexpected error: {err}"
+    );
+
+    let err = get(&http_client, "https://localhost:1234/")
+        .await
+        .expect_err("the connector couldn't be created");
+    assert!(err.is_user(), "{err:?}");
+}
+
+#[tokio::test]
+async fn https_requests_are_tunnelled_through_proxy() {
+    let pki = Pki::new();
+    let (server_addr, server_requests) = https_server(pki.server_config(false)).await;
+    let (proxy_addr, proxy_requests) = proxy_server().await;
+
+    let http_client = HyperClientBuilder::new()
+        .crypto_mode(CryptoMode::Ring)
+        .trust_store(TrustStore::empty().with_pem_certificate(pki.ca_pem()))
+        .proxy(ProxyConfig::all(format!("http://{proxy_addr}")).unwrap())
+        .build_https();
+    let uri = format!("https://localhost:{}/tunnelled", server_addr.port());
+    assert_eq!("hello", get(&http_client, &uri).await.unwrap());
+
+    assert_eq!(
+        vec![format!("CONNECT localhost:{} HTTP/1.1", server_addr.port())],
+        *proxy_requests.lock().unwrap()
+    );
+    assert_eq!(
+        vec!["GET /tunnelled HTTP/1.1".to_string()],
+        *server_requests.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn http_requests_are_forwarded_to_proxy() {
+    let (proxy_addr, proxy_requests) = proxy_server().await;
+
+    let http_client = HyperClientBuilder::new()
+        .crypto_mode(CryptoMode::Ring)
+        .trust_store(TrustStore::empty())
+        .proxy(
+            ProxyConfig::disabled()
+                .with_http_proxy(proxy_addr.to_string())
+                .unwrap(),
+        )
+        .build_https();
+    assert_eq!(
+        "hello",
+        get(&http_client, "http://example.invalid/forwarded")
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        vec!["GET http://example.invalid/forwarded HTTP/1.1".to_string()],
+        *proxy_requests.lock().unwrap()
+    );
+}
@@ -0,0 +1,228 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Verifies that the hyper 0.14 and hyper 1.x connectors behave the same way
+
+#![cfg(all(
+    feature = "client",
+    feature = "test-util",
+    feature = "connector-hyper-0-14-x",
+    feature = "connector-hyper-1-x",
+))]
+
+use aws_smithy_async::rt::sleep::TokioSleep;
+use aws_smithy_async::time::SystemTimeSource;
+use aws_smithy_runtime::client::http::{hyper_014, hyper_1};
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorSettings, SharedHttpClient,
+};
+use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+use aws_smithy_types::byte_stream::ByteStream;
+use aws_smithy_types::retry::ErrorKind;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+fn clients() -> Vec<(&'static str, SharedHttpClient)> {
+    vec![
+        (
+            "hyper 0.14",
+            hyper_014::HyperClientBuilder::new().build(hyper_0_14::client::HttpConnector::new()),
+        ),
+        (
+            "hyper 1.x",
+            hyper_1::HyperClientBuilder::new()
+                .build(hyper_util::client::legacy::connect::HttpConnector::new()),
+        ),
+    ]
+}
+
+/// Starts a server that handles every connection with `handler`
+async fn server<F, Fut>(handler: F) -> SocketAddr
+where
+    F: Fn(TcpStream) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(handler(stream));
+        }
+    });
+    addr
+}
+
+async fn read_request(stream: &mut TcpStream) {
+    let mut request = vec![0; 4096];
+    let mut len = 0;
+    while !request[..len].ends_with(b"\r\n\r\n") {
+        match stream.read(&mut request[len..]).await.unwrap() {
+            0 => return,
+            n => len += n,
+        }
+    }
+}
+
+async fn call(
+    http_client: &SharedHttpClient,
+    settings: &HttpConnectorSettings,
+    uri: &str,
+) -> Result<(u16, Vec<(String, String)>, String), ConnectorError> {
+    let components = RuntimeComponentsBuilder::for_tests()
+        .with_sleep_impl(Some(TokioSleep::new()))
+        .with_time_source(Some(SystemTimeSource::new()))
+        .build()
+        .unwrap();
+    let connector = http_client.http_connector(settings, &components);
+    let response = connector.call(HttpRequest::get(uri).unwrap()).await?;
+    let status = response.status().as_u16();
+    let mut headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect::<Vec<_>>();
+    headers.sort();
+    let body = ByteStream::new(response.into_body())
+        .collect()
+        .await
+        .unwrap()
+        .into_bytes();
+    Ok((status, headers, String::from_utf8(body.to_vec()).unwrap()))
+}
+
+/// A description of how an error was classified that can be compared across connectors
+fn classification(err: &ConnectorError) -> (&'static str, Option<ErrorKind>) {
+    let kind = if err.is_timeout() {
+        "timeout"
+    } else if err.is_io() {
+        "io"
+    } else if err.is_user() {
+        "user"
+    } else {
+        "other"
+    };
+    (kind, err.as_other())
+}
+
+async fn assert_error_parity(
+    settings: HttpConnectorSettings,
+    uri: &str,
+    expected: (&'static str, Option<ErrorKind>),
+) {
+    for (name, http_client) in clients() {
+        let err = call(&http_client, &settings, uri)
+            .await
+            .expect_err("request should fail");
+        assert_eq!(expected, classification(&err), "{name}: {err:?}");
+    }
+}
+
+#[tokio::test]
+async fn responses_match() {
+    let addr = server(|mut stream| async move {
+        read_request(&mut stream).await;
+        stream
+            .write_all(
+                b"HTTP/1.1 201 Created\r\n\
+                  content-type: text/plain\r\n\
+                  x-custom: some value\r\n\
+                  content-length: 11\r\n\r\n\
+                  hello world",
+            )
+            .await
+            .unwrap();
+    })
+    .await;
+
+    let mut responses = Vec::new();
+    for (_, http_client) in clients() {
+        responses.push(
+            call(
+                &http_client,
+                &HttpConnectorSettings::default(),
+                &format!("http://{addr}/"),
+            )
+            .await
+            .unwrap(),
+        );
+    }
+    let (status, headers, body) = &responses[0];
+    assert_eq!(201, *status);
+    assert!(headers.contains(&("x-custom".to_string(), "some value".to_string())));
+    assert_eq!("hello world", body);
+    assert_eq!(responses[0], responses[1]);
+}
+
+#[tokio::test]
+async fn connection_refused_is_io() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    assert_error_parity(
+        HttpConnectorSettings::default(),
+        &format!("http://{addr}/"),
+        ("io", None),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn connection_reset_is_io() {
+    let addr = server(|mut stream| async move {
+        read_request(&mut stream).await;
+        // Setting a zero linger causes the connection to be reset on drop rather than closed.
+        // A zero linger never blocks on drop, which is what the deprecation warns about.
+        #[allow(deprecated)]
+        stream.set_linger(Some(Duration::ZERO)).unwrap();
+    })
+    .await;
+    assert_error_parity(
+        HttpConnectorSettings::default(),
+        &format!("http://{addr}/"),
+        ("io", None),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn incomplete_response_is_transient() {
+    let addr = server(|mut stream| async move {
+        read_request(&mut stream).await;
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-")
+            .await
+            .unwrap();
+    })
+    .await;
+    assert_error_parity(
+        HttpConnectorSettings::default(),
+        &format!("http://{addr}/"),
+        ("other", Some(ErrorKind::TransientError)),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn read_timeout_is_timeout() {
+    let addr = server(|mut stream| async move {
+        read_request(&mut stream).await;
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    })
+    .await;
+    assert_error_parity(
+        HttpConnectorSettings::builder()
+            .read_timeout(Duration::from_millis(100))
+            .build(),
+        &format!("http://{addr}/"),
+        ("timeout", None),
+    )
+    .await;
+}
@@ -0,0 +1,177 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Verifies that the default connectors route requests through proxies, using a
+//! [`WireMockServer`] as a stand-in for the proxy.
+
+#![cfg(all(feature = "client", feature = "wire-mock", feature = "tls-rustls"))]
+
+use aws_smithy_async::rt::sleep::TokioSleep;
+use aws_smithy_async::time::SystemTimeSource;
+use aws_smithy_runtime::client::http::hyper_014;
+use aws_smithy_runtime::client::http::proxy::ProxyConfig;
+use aws_smithy_runtime::client::http::test_util::wire::{ReplayedEvent, WireMockServer};
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorSettings, SharedHttpClient,
+};
+use aws_smithy_runtime_api::client::orchestrator::HttpRequest;
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponentsBuilder;
+use aws_smithy_types::byte_stream::ByteStream;
+
+/// The `Proxy-Authorization` header value for `user:pass`
+const USER_PASS: &str = "Basic dXNlcjpwYXNz";
+
+#[derive(Clone, Copy, Debug)]
+enum Hyper {
+    V0_14,
+    #[cfg(all(feature = "connector-hyper-1-x", feature = "crypto-ring"))]
+    V1,
+}
+
+impl Hyper {
+    fn all() -> Vec<Hyper> {
+        vec![
+            Hyper::V0_14,
+            #[cfg(all(feature = "connector-hyper-1-x", feature = "crypto-ring"))]
+            Hyper::V1,
+        ]
+    }
+
+    fn http_client(self, proxy: ProxyConfig) -> SharedHttpClient {
+        match self {
+            Hyper::V0_14 => hyper_014::HyperClientBuilder::new()
+                .proxy(proxy)
+                .build_https(),
+            #[cfg(all(feature = "connector-hyper-1-x", feature = "crypto-ring"))]
+            Hyper::V1 => aws_smithy_runtime::client::http::hyper_1::HyperClientBuilder::new()
+                .crypto_mode(aws_smithy_runtime::client::http::hyper_1::CryptoMode::Ring)
+                .proxy(proxy)
+                .build_https(),
+        }
+    }
+}
+
+async fn get(http_client: &SharedHttpClient, uri: &str) -> Result<String, ConnectorError> {
+    let components = RuntimeComponentsBuilder::for_tests()
+        .with_sleep_impl(Some(TokioSleep::new()))
+        .with_time_source(Some(SystemTimeSource::new()))
+        .build()
+        .unwrap();
+    let connector = http_client.http_connector(&HttpConnectorSettings::default(), &components);
+    let response = connector.call(HttpRequest::get(uri).unwrap()).await?;
+    let body = ByteStream::new(response.into_body())
+        .collect()
+        .await
+        .unwrap()
+        .into_bytes();
+    Ok(String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn http_requests_are_forwarded_with_credentials() {
+    for hyper in Hyper::all() {
+        let proxy = WireMockServer::start(vec![ReplayedEvent::with_body("hello")]).await;
+        let config = ProxyConfig::all(format!("http://user:pass@{}", proxy.bind_addr())).unwrap();
+        let http_client = hyper.http_client(config);
+
+        let body = get(&http_client, "http://example.invalid/forwarded")
+            .await
+            .unwrap();
+        assert_eq!("hello", body, "{hyper:?}");
+
+        let requests = proxy.requests();
+        assert_eq!(1, requests.len(), "{hyper:?}");
+        assert_eq!(
+            "http://example.invalid/forwarded",
+            requests[0].uri().to_string(),
+            "{hyper:?}"
+        );
+        assert_eq!(
+            USER_PASS,
+            requests[0].headers()["proxy-authorization"],
+            "{hyper:?}"
+        );
+        proxy.shutdown();
+    }
+}
+
+#[tokio::test]
+async fn https_requests_are_tunnelled_with_credentials() {
+    for hyper in Hyper::all() {
+        let proxy = WireMockServer::start(vec![ReplayedEvent::ok()]).await;
+        let config = ProxyConfig::disabled()
+            .with_https_proxy(proxy.bind_addr().to_string())
+            .unwrap()
+            .with_basic_auth("user", "pass");
+        let http_client = hyper.http_client(config);
+
+        // The stand-in doesn't establish the tunnel, so the TLS handshake fails after `CONNECT`
+        get(&http_client, "https://example.invalid:8443/tunnelled")
+            .await
+            .expect_err("the tunnel isn't established");
+
+        let requests = proxy.requests();
+        assert_eq!(1, requests.len(), "{hyper:?}");
+        assert_eq!("CONNECT", requests[0].method(), "{hyper:?}");
+        assert_eq!(
+            "example.invalid:8443",
+            requests[0].uri().to_string(),
+            "{hyper:?}"
+        );
+        assert_eq!(
+            USER_PASS,
+            requests[0].headers()["proxy-authorization"],
+            "{hyper:?}"
+        );
+        proxy.shutdown();
+    }
+}
+
+#[tokio::test]
+async fn rejected_tunnels_are_io_errors() {
+    for hyper in Hyper::all() {
+        let proxy = WireMockServer::start(vec![ReplayedEvent::status(407)]).await;
+        let http_client =
+            hyper.http_client(ProxyConfig::all(proxy.bind_addr().to_string()).unwrap());
+
+        let err = get(&http_client, "https://example.invalid/")
+            .await
+            .expect_err("the proxy requires authentication");
+        assert!(err.is_io(), "{hyper:?}: {err:?}");
+        assert!(
+            proxy.requests()[0]
+                .headers()
+                .get("proxy-authorization")
+                .is_none(),
+            "{hyper:?}"
+        );
+        proxy.shutdown();
+    }
+}
+
+#[tokio::test]
+async fn no_proxy_hosts_are_connected_to_directly() {
+    for hyper in Hyper::all() {
+        let proxy = WireMockServer::start(vec![]).await;
+        let server = WireMockServer::start(vec![ReplayedEvent::with_body("direct")]).await;
+        let config = ProxyConfig::all(proxy.bind_addr().to_string())
+            .unwrap()
+            .with_no_proxy("localhost, 127.0.0.0/8");
+        let http_client = hyper.http_client(config);
+
+        let uri = format!("http://{}/direct", server.bind_addr());
+        assert_eq!(
+            "direct",
+            get(&http_client, &uri).await.unwrap(),
+            "{hyper:?}"
+        );
+        assert!(proxy.requests().is_empty(), "{hyper:?}");
+        assert!(proxy.events().is_empty(), "{hyper:?}");
+        assert_eq!(1, server.requests().len(), "{hyper:?}");
+        proxy.shutdown();
+        server.shutdown();
+    }
+}
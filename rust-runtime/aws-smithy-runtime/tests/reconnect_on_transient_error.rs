@@ -13,20 +13,19 @@ use ::aws_smithy_runtime::client::retries::classifiers::{
     HttpStatusCodeClassifier, TransientErrorClassifier,
 };
 use aws_smithy_async::rt::sleep::TokioSleep;
-use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
 use aws_smithy_runtime::client::http::test_util::wire::{
     RecordedEvent, ReplayedEvent, WireMockServer,
 };
 use aws_smithy_runtime::client::orchestrator::operation::Operation;
 use aws_smithy_runtime::test_util::capture_test_logs::capture_test_logs;
 use aws_smithy_runtime::{ev, match_events};
+use aws_smithy_runtime_api::client::http::SharedHttpClient;
 use aws_smithy_runtime_api::client::interceptors::context::InterceptorContext;
 use aws_smithy_runtime_api::client::orchestrator::OrchestratorError;
 use aws_smithy_runtime_api::client::retries::classifiers::{ClassifyRetry, RetryAction};
 use aws_smithy_types::body::SdkBody;
 use aws_smithy_types::retry::{ErrorKind, ProvideErrorKind, ReconnectMode, RetryConfig};
 use aws_smithy_types::timeout::TimeoutConfig;
-use hyper_0_14::client::Builder as HyperBuilder;
 use std::fmt;
 use std::time::Duration;
 
@@ -90,24 +89,71 @@ impl ClassifyRetry for TestRetryClassifier {
     }
 }
 
+/// The hyper versions that connection poisoning is tested against
+#[derive(Clone, Copy, Debug)]
+enum Hyper {
+    V0_14,
+    #[cfg(feature = "connector-hyper-1-x")]
+    V1,
+}
+
+impl Hyper {
+    fn all() -> Vec<Hyper> {
+        vec![
+            Hyper::V0_14,
+            #[cfg(feature = "connector-hyper-1-x")]
+            Hyper::V1,
+        ]
+    }
+
+    fn http_client(self, mock: &WireMockServer, http2_only: bool) -> SharedHttpClient {
+        match self {
+            Hyper::V0_14 => {
+                let mut hyper_builder = hyper_0_14::Client::builder();
+                hyper_builder.http2_only(http2_only);
+                aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder::new()
+                    .hyper_builder(hyper_builder)
+                    .build(hyper_0_14::client::HttpConnector::new_with_resolver(
+                        mock.dns_resolver(),
+                    ))
+            }
+            #[cfg(feature = "connector-hyper-1-x")]
+            Hyper::V1 => {
+                let mut hyper_builder =
+                    hyper_util::client::legacy::Builder::new(hyper_util::rt::TokioExecutor::new());
+                hyper_builder.http2_only(http2_only);
+                aws_smithy_runtime::client::http::hyper_1::HyperClientBuilder::new()
+                    .hyper_builder(hyper_builder)
+                    .build(
+                        hyper_util::client::legacy::connect::HttpConnector::new_with_resolver(
+                            mock.dns_resolver(),
+                        ),
+                    )
+            }
+        }
+    }
+}
+
 async fn h1_and_h2(events: Vec<ReplayedEvent>, match_clause: impl Fn(&[RecordedEvent])) {
-    wire_level_test(
-        events.clone(),
-        |_b| {},
-        ReconnectMode::ReconnectOnTransientError,
-        &match_clause,
-    )
-    .await;
-    wire_level_test(
-        events,
-        |b| {
-            b.http2_only(true);
-        },
-        ReconnectMode::ReconnectOnTransientError,
-        match_clause,
-    )
-    .await;
-    tracing::info!("h2 ok!");
+    for hyper in Hyper::all() {
+        wire_level_test(
+            events.clone(),
+            hyper,
+            false,
+            ReconnectMode::ReconnectOnTransientError,
+            &match_clause,
+        )
+        .await;
+        wire_level_test(
+            events.clone(),
+            hyper,
+            true,
+            ReconnectMode::ReconnectOnTransientError,
+            &match_clause,
+        )
+        .await;
+        tracing::info!("{hyper:?} h2 ok!");
+    }
 }
 
 /// Repeatedly send test operation until `end_of_test` is received
@@ -115,19 +161,13 @@ async fn h1_and_h2(events: Vec<ReplayedEvent>, match_clause: impl Fn(&[RecordedE
 /// When the test is over, match_clause is evaluated
 async fn wire_level_test(
     events: Vec<ReplayedEvent>,
-    hyper_builder_settings: impl Fn(&mut HyperBuilder),
+    hyper: Hyper,
+    http2_only: bool,
     reconnect_mode: ReconnectMode,
     match_clause: impl Fn(&[RecordedEvent]),
 ) {
-    let mut hyper_builder = hyper_0_14::Client::builder();
-    hyper_builder_settings(&mut hyper_builder);
-
     let mock = WireMockServer::start(events).await;
-    let http_client = HyperClientBuilder::new()
-        .hyper_builder(hyper_builder)
-        .build(hyper_0_14::client::HttpConnector::new_with_resolver(
-            mock.dns_resolver(),
-        ));
+    let http_client = hyper.http_client(&mock, http2_only);
 
     let operation = Operation::builder()
         .service_name("test")
@@ -269,16 +309,19 @@ async fn connection_shared_on_success() {
 #[tokio::test]
 async fn no_reconnect_when_disabled() {
     let _logs = capture_test_logs();
-    wire_level_test(
-        vec![
-            ReplayedEvent::status(503),
-            ReplayedEvent::with_body(END_OF_TEST),
-        ],
-        |_b| {},
-        ReconnectMode::ReuseAllConnections,
-        match_events!(ev!(dns), ev!(connect), ev!(http(503)), ev!(http(200))),
-    )
-    .await;
+    for hyper in Hyper::all() {
+        wire_level_test(
+            vec![
+                ReplayedEvent::status(503),
+                ReplayedEvent::with_body(END_OF_TEST),
+            ],
+            hyper,
+            false,
+            ReconnectMode::ReuseAllConnections,
+            match_events!(ev!(dns), ev!(connect), ev!(http(503)), ev!(http(200))),
+        )
+        .await;
+    }
 }
 
 #[tokio::test]
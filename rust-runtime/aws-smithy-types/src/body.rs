@@ -152,14 +152,14 @@ impl SdkBody {
                 }
             }
             InnerProj::Dyn { inner: body } => match body.get_mut() {
-                #[cfg(feature = "http-body-0-4-x")]
+                #[cfg(any(feature = "http-body-0-4-x", feature = "http-body-1-x"))]
                 BoxBody::HttpBody04(box_body) => {
                     use http_body_0_4::Body;
                     Pin::new(box_body).poll_data(cx)
                 }
                 #[allow(unreachable_patterns)]
                 _ => unreachable!(
-                    "enabling `http-body-0-4-x` is the only way to create the `Dyn` variant"
+                    "enabling `http-body-0-4-x` or `http-body-1-x` is the only way to create the `Dyn` variant"
                 ),
             },
             InnerProj::Taken => {
@@ -248,14 +248,14 @@ impl SdkBody {
             Inner::Once { inner: None } => true,
             Inner::Once { inner: Some(bytes) } => bytes.is_empty(),
             Inner::Dyn { inner: box_body } => match box_body {
-                #[cfg(feature = "http-body-0-4-x")]
+                #[cfg(any(feature = "http-body-0-4-x", feature = "http-body-1-x"))]
                 BoxBody::HttpBody04(box_body) => {
                     use http_body_0_4::Body;
                     box_body.is_end_stream()
                 }
                 #[allow(unreachable_patterns)]
                 _ => unreachable!(
-                    "enabling `http-body-0-4-x` is the only way to create the `Dyn` variant"
+                    "enabling `http-body-0-4-x` or `http-body-1-x` is the only way to create the `Dyn` variant"
                 ),
             },
             Inner::Taken => true,
@@ -270,7 +270,7 @@ impl SdkBody {
                 (len, Some(len))
             }
             Inner::Dyn { inner: box_body } => match box_body {
-                #[cfg(feature = "http-body-0-4-x")]
+                #[cfg(any(feature = "http-body-0-4-x", feature = "http-body-1-x"))]
                 BoxBody::HttpBody04(box_body) => {
                     use http_body_0_4::Body;
                     let hint = box_body.size_hint();
@@ -278,7 +278,7 @@ impl SdkBody {
                 }
                 #[allow(unreachable_patterns)]
                 _ => unreachable!(
-                    "enabling `http-body-0-4-x` is the only way to create the `Dyn` variant"
+                    "enabling `http-body-0-4-x` or `http-body-1-x` is the only way to create the `Dyn` variant"
                 ),
             },
             Inner::Taken => (0, Some(0)),
@@ -572,6 +572,41 @@ impl ConfigBag {
         self
     }
 
+    /// Returns a new bag that shares everything stored in this bag so far.
+    ///
+    /// The interceptor state of this bag is frozen and shared by both bags, and each bag gets a new,
+    /// empty interceptor state. Changes made to either bag after the fork aren't visible in the other.
+    ///
+    /// Note: This method is intended for internal use only.
+    #[cfg(all(aws_sdk_unstable, feature = "unstable-hedging"))]
+    #[doc(hidden)]
+    pub fn fork(&mut self) -> ConfigBag {
+        let interceptor_state =
+            std::mem::replace(&mut self.interceptor_state, Layer::new("interceptor_state"));
+        if !interceptor_state.is_empty() {
+            self.tail.push(interceptor_state.freeze());
+        }
+        ConfigBag {
+            interceptor_state: Layer::new("interceptor_state"),
+            tail: self.tail.clone(),
+        }
+    }
+
+    /// Returns a new bag that shares the layers of this bag, but not its interceptor state.
+    ///
+    /// This is for work that outlives the operation that this bag belongs to, such as refreshing
+    /// an identity in the background.
+    ///
+    /// Note: This method is intended for internal use only.
+    #[cfg(all(aws_sdk_unstable, feature = "unstable-identity-refresh"))]
+    #[doc(hidden)]
+    pub fn snapshot(&self) -> ConfigBag {
+        ConfigBag {
+            interceptor_state: Layer::new("interceptor_state"),
+            tail: self.tail.clone(),
+        }
+    }
+
     /// Return a reference to the mutable interceptor state.
     pub fn interceptor_state(&mut self) -> &mut Layer {
         &mut self.interceptor_state
@@ -1030,4 +1065,58 @@ mod test {
                 .join(" ")
         );
     }
+
+    #[cfg(all(aws_sdk_unstable, feature = "unstable-hedging"))]
+    #[test]
+    fn fork_shares_existing_state() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Attempt(u32);
+        impl Storable for Attempt {
+            type Storer = StoreReplace<Self>;
+        }
+        #[derive(Debug)]
+        struct Base;
+        impl Storable for Base {
+            type Storer = StoreReplace<Self>;
+        }
+
+        let mut layer = Layer::new("base");
+        layer.store_put(Base);
+        let mut bag = ConfigBag::of_layers(vec![layer]);
+        bag.interceptor_state().store_put(Attempt(1));
+
+        let mut fork = bag.fork();
+        assert!(fork.load::<Base>().is_some());
+        assert_eq!(Some(&Attempt(1)), fork.load::<Attempt>());
+
+        fork.interceptor_state().store_put(Attempt(2));
+        assert_eq!(Some(&Attempt(1)), bag.load::<Attempt>());
+        bag.get_mut::<Attempt>().unwrap().0 = 3;
+        assert_eq!(Some(&Attempt(2)), fork.load::<Attempt>());
+        assert_eq!(Some(&Attempt(3)), bag.load::<Attempt>());
+    }
+
+    #[cfg(all(aws_sdk_unstable, feature = "unstable-identity-refresh"))]
+    #[test]
+    fn snapshot_shares_layers_but_not_interceptor_state() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Attempt(u32);
+        impl Storable for Attempt {
+            type Storer = StoreReplace<Self>;
+        }
+        #[derive(Debug)]
+        struct Base;
+        impl Storable for Base {
+            type Storer = StoreReplace<Self>;
+        }
+
+        let mut layer = Layer::new("base");
+        layer.store_put(Base);
+        let mut bag = ConfigBag::of_layers(vec![layer]);
+        bag.interceptor_state().store_put(Attempt(1));
+
+        let snapshot = bag.snapshot();
+        assert!(snapshot.load::<Base>().is_some());
+        assert_eq!(None, snapshot.load::<Attempt>());
+    }
 }
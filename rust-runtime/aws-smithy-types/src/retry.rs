@@ -157,6 +157,7 @@ pub struct RetryConfigBuilder {
     initial_backoff: Option<Duration>,
     max_backoff: Option<Duration>,
     reconnect_mode: Option<ReconnectMode>,
+    concurrency_limit: Option<ConcurrencyLimitConfig>,
 }
 
 impl RetryConfigBuilder {
@@ -237,6 +238,25 @@ impl RetryConfigBuilder {
         self
     }
 
+    /// Set the [`ConcurrencyLimitConfig`] used to adaptively limit the number of in-flight attempts.
+    ///
+    /// Concurrency limiting is disabled unless this is set.
+    pub fn set_concurrency_limit(
+        &mut self,
+        concurrency_limit: Option<ConcurrencyLimitConfig>,
+    ) -> &mut Self {
+        self.concurrency_limit = concurrency_limit;
+        self
+    }
+
+    /// Set the [`ConcurrencyLimitConfig`] used to adaptively limit the number of in-flight attempts.
+    ///
+    /// Concurrency limiting is disabled unless this is set.
+    pub fn concurrency_limit(mut self, concurrency_limit: ConcurrencyLimitConfig) -> Self {
+        self.set_concurrency_limit(Some(concurrency_limit));
+        self
+    }
+
     /// Merge two builders together. Values from `other` will only be used as a fallback for values
     /// from `self` Useful for merging configs from different sources together when you want to
     /// handle "precedence" per value instead of at the config level
@@ -260,6 +280,7 @@ impl RetryConfigBuilder {
             initial_backoff: self.initial_backoff.or(other.initial_backoff),
             max_backoff: self.max_backoff.or(other.max_backoff),
             reconnect_mode: self.reconnect_mode.or(other.reconnect_mode),
+            concurrency_limit: self.concurrency_limit.or(other.concurrency_limit),
         }
     }
 
@@ -276,6 +297,7 @@ impl RetryConfigBuilder {
                 .unwrap_or(ReconnectMode::ReconnectOnTransientError),
             max_backoff: self.max_backoff.unwrap_or_else(|| Duration::from_secs(20)),
             use_static_exponential_base: false,
+            concurrency_limit: self.concurrency_limit,
         }
     }
 }
@@ -290,6 +312,7 @@ pub struct RetryConfig {
     max_backoff: Duration,
     reconnect_mode: ReconnectMode,
     use_static_exponential_base: bool,
+    concurrency_limit: Option<ConcurrencyLimitConfig>,
 }
 
 impl Storable for RetryConfig {
@@ -326,6 +349,7 @@ impl RetryConfig {
             reconnect_mode: ReconnectMode::ReconnectOnTransientError,
             max_backoff: Duration::from_secs(20),
             use_static_exponential_base: false,
+            concurrency_limit: None,
         }
     }
 
@@ -338,6 +362,7 @@ impl RetryConfig {
             reconnect_mode: ReconnectMode::ReconnectOnTransientError,
             max_backoff: Duration::from_secs(20),
             use_static_exponential_base: false,
+            concurrency_limit: None,
         }
     }
 
@@ -399,6 +424,17 @@ impl RetryConfig {
         self
     }
 
+    /// Enable adaptive concurrency limiting with the given [`ConcurrencyLimitConfig`].
+    ///
+    /// When enabled, the retry strategy tracks the number of in-flight attempts for each retry
+    /// partition and adjusts the number of attempts it allows based on observed latency and
+    /// overload errors. Attempts made while the limit is reached are delayed, or rejected when
+    /// they would have to wait longer than [`ConcurrencyLimitConfig::max_queue_time`].
+    pub fn with_concurrency_limit(mut self, concurrency_limit: ConcurrencyLimitConfig) -> Self {
+        self.concurrency_limit = Some(concurrency_limit);
+        self
+    }
+
     /// Hint to the retry strategy whether to use a static exponential base.
     ///
     /// When a retry strategy uses exponential backoff, it calculates a random base. This causes the
@@ -438,6 +474,11 @@ impl RetryConfig {
         self.max_backoff
     }
 
+    /// Returns the [`ConcurrencyLimitConfig`], if concurrency limiting is enabled.
+    pub fn concurrency_limit(&self) -> Option<&ConcurrencyLimitConfig> {
+        self.concurrency_limit.as_ref()
+    }
+
     /// Returns true if retry is enabled with this config
     pub fn has_retry(&self) -> bool {
         self.max_attempts > 1
@@ -452,9 +493,163 @@ impl RetryConfig {
     }
 }
 
+/// Algorithm used by an adaptive concurrency limiter to adjust its limit.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConcurrencyLimitAlgorithm {
+    /// Additive increase, multiplicative decrease.
+    ///
+    /// The limit grows by one for every successful attempt made while the limit is being used,
+    /// and is multiplied by the [backoff ratio](ConcurrencyLimitConfig::backoff_ratio) whenever
+    /// an attempt fails with an overload error or takes longer than the
+    /// [latency threshold](ConcurrencyLimitConfig::latency_threshold).
+    Aimd,
+
+    /// Gradient-based limiting.
+    ///
+    /// The limit is adjusted by the ratio between the long-term average latency and the latency
+    /// of the most recent attempt, so that it shrinks as soon as latency starts to climb and grows
+    /// back while latency stays at its baseline.
+    Gradient,
+}
+
+/// Configuration for adaptive concurrency limiting.
+///
+/// # Example
+///
+/// ```rust
+/// use aws_smithy_types::retry::{ConcurrencyLimitAlgorithm, ConcurrencyLimitConfig, RetryConfig};
+/// use std::time::Duration;
+///
+/// let retry_config = RetryConfig::standard().with_concurrency_limit(
+///     ConcurrencyLimitConfig::new(ConcurrencyLimitAlgorithm::Gradient)
+///         .with_initial_limit(50)
+///         .with_max_queue_time(Duration::from_millis(500)),
+/// );
+/// assert_eq!(retry_config.concurrency_limit().unwrap().initial_limit(), 50);
+/// ```
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConcurrencyLimitConfig {
+    algorithm: ConcurrencyLimitAlgorithm,
+    initial_limit: usize,
+    min_limit: usize,
+    max_limit: usize,
+    latency_threshold: Duration,
+    backoff_ratio: f64,
+    max_queue_time: Duration,
+}
+
+impl ConcurrencyLimitConfig {
+    /// Creates a `ConcurrencyLimitConfig` for the given algorithm with default settings.
+    ///
+    /// The defaults are an initial limit of 20 in-flight attempts, bounded between 1 and 1000, a
+    /// latency threshold of 5 seconds, a backoff ratio of 0.9, and a maximum queue time of 1 second.
+    pub fn new(algorithm: ConcurrencyLimitAlgorithm) -> Self {
+        Self {
+            algorithm,
+            initial_limit: 20,
+            min_limit: 1,
+            max_limit: 1000,
+            latency_threshold: Duration::from_secs(5),
+            backoff_ratio: 0.9,
+            max_queue_time: Duration::from_secs(1),
+        }
+    }
+
+    /// Creates a `ConcurrencyLimitConfig` using the [AIMD](ConcurrencyLimitAlgorithm::Aimd) algorithm.
+    pub fn aimd() -> Self {
+        Self::new(ConcurrencyLimitAlgorithm::Aimd)
+    }
+
+    /// Creates a `ConcurrencyLimitConfig` using the [gradient](ConcurrencyLimitAlgorithm::Gradient) algorithm.
+    pub fn gradient() -> Self {
+        Self::new(ConcurrencyLimitAlgorithm::Gradient)
+    }
+
+    /// Set the number of in-flight attempts allowed before any latency has been observed.
+    pub fn with_initial_limit(mut self, initial_limit: usize) -> Self {
+        self.initial_limit = initial_limit;
+        self
+    }
+
+    /// Set the lowest value the limit can be reduced to. This value must be greater than zero.
+    pub fn with_min_limit(mut self, min_limit: usize) -> Self {
+        self.min_limit = min_limit;
+        self
+    }
+
+    /// Set the highest value the limit can grow to.
+    pub fn with_max_limit(mut self, max_limit: usize) -> Self {
+        self.max_limit = max_limit;
+        self
+    }
+
+    /// Set the latency above which an attempt is treated as a sign of overload.
+    ///
+    /// Only used by the [AIMD](ConcurrencyLimitAlgorithm::Aimd) algorithm.
+    pub fn with_latency_threshold(mut self, latency_threshold: Duration) -> Self {
+        self.latency_threshold = latency_threshold;
+        self
+    }
+
+    /// Set the factor the limit is multiplied by when overload is detected. This value must be
+    /// between zero and one.
+    ///
+    /// Only used by the [AIMD](ConcurrencyLimitAlgorithm::Aimd) algorithm.
+    pub fn with_backoff_ratio(mut self, backoff_ratio: f64) -> Self {
+        self.backoff_ratio = backoff_ratio;
+        self
+    }
+
+    /// Set the longest time an attempt may be delayed while waiting for the number of in-flight
+    /// attempts to drop below the limit. Attempts that would have to wait longer are rejected.
+    ///
+    /// Setting this to zero rejects attempts as soon as the limit is reached.
+    pub fn with_max_queue_time(mut self, max_queue_time: Duration) -> Self {
+        self.max_queue_time = max_queue_time;
+        self
+    }
+
+    /// Returns the algorithm used to adjust the limit.
+    pub fn algorithm(&self) -> ConcurrencyLimitAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the number of in-flight attempts allowed before any latency has been observed.
+    pub fn initial_limit(&self) -> usize {
+        self.initial_limit
+    }
+
+    /// Returns the lowest value the limit can be reduced to.
+    pub fn min_limit(&self) -> usize {
+        self.min_limit
+    }
+
+    /// Returns the highest value the limit can grow to.
+    pub fn max_limit(&self) -> usize {
+        self.max_limit
+    }
+
+    /// Returns the latency above which an attempt is treated as a sign of overload.
+    pub fn latency_threshold(&self) -> Duration {
+        self.latency_threshold
+    }
+
+    /// Returns the factor the limit is multiplied by when overload is detected.
+    pub fn backoff_ratio(&self) -> f64 {
+        self.backoff_ratio
+    }
+
+    /// Returns the longest time an attempt may be delayed while waiting for the limit.
+    pub fn max_queue_time(&self) -> Duration {
+        self.max_queue_time
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::retry::{RetryConfigBuilder, RetryMode};
+    use crate::retry::{ConcurrencyLimitConfig, RetryConfigBuilder, RetryMode};
     use std::str::FromStr;
 
     #[test]
@@ -471,6 +666,20 @@ mod tests {
         assert_eq!(retry_config.mode, RetryMode::Adaptive);
     }
 
+    #[test]
+    fn retry_config_builder_merges_concurrency_limit() {
+        let self_builder = RetryConfigBuilder::new().max_attempts(1);
+        let other_builder =
+            RetryConfigBuilder::new().concurrency_limit(ConcurrencyLimitConfig::aimd());
+        let retry_config = self_builder.take_unset_from(other_builder).build();
+
+        assert_eq!(
+            retry_config.concurrency_limit(),
+            Some(&ConcurrencyLimitConfig::aimd())
+        );
+        assert_eq!(RetryConfigBuilder::new().build().concurrency_limit(), None);
+    }
+
     #[test]
     fn retry_mode_from_str_parses_valid_strings_regardless_of_casing() {
         assert_eq!(
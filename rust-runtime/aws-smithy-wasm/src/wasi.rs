@@ -4,6 +4,8 @@
  */
 
 //! WASI HTTP Adapter
+use crate::wasi::body::WasiResponseBody;
+use crate::wasi::poll::Subscription;
 use aws_smithy_http::header::ParseError;
 use aws_smithy_runtime_api::{
     client::{
@@ -19,11 +21,22 @@ use aws_smithy_runtime_api::{
     shared::IntoShared,
 };
 use aws_smithy_types::body::SdkBody;
-use bytes::{Bytes, BytesMut};
+use http_body::Body;
+use std::future::poll_fn;
+use std::pin::Pin;
 use wasi::http::{
     outgoing_handler,
-    types::{self as wasi_http, OutgoingBody, RequestOptions},
+    types::{self as wasi_http, ErrorCode, OutgoingBody, RequestOptions},
 };
+use wasi::io::{error::Error as IoError, streams::OutputStream};
+
+mod body;
+mod poll;
+mod sleep;
+mod time;
+
+pub use sleep::WasiSleep;
+pub use time::WasiTimeSource;
 
 /// Builder for [`WasiHttpClient`]. Currently empty, but allows for future
 /// config options to be added in a backwards compatible manner.
@@ -47,6 +60,11 @@ impl WasiHttpClientBuilder {
 /// An HTTP client that can be used during instantiation of the client SDK in
 /// order to route the HTTP requests through the WebAssembly host. The host must
 /// support the WASI HTTP proposal as defined in the Preview 2 specification.
+///
+/// Request and response bodies are streamed through the host rather than being
+/// buffered in memory. The connect timeout from [`HttpConnectorSettings`] is used as
+/// the WASI connect timeout, and the read timeout is used as both the first byte and
+/// the between bytes timeouts.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct WasiHttpClient {}
@@ -74,24 +92,40 @@ impl HttpConnector for WasiHttpConnector {
     fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
         tracing::trace!("WasiHttpConnector: sending request {request:?}");
 
-        let client = WasiDefaultClient::new(self.options.clone());
-        let http_req = request.try_into_http1x().expect("Http request invalid");
-        let converted_req = http_req.map(|body| match body.bytes() {
-            Some(value) => Bytes::copy_from_slice(value),
-            None => Bytes::new(),
-        });
-
-        let fut_result = client.handle(converted_req);
-
+        let options = self.options.clone();
         HttpConnectorFuture::new(async move {
-            let fut = fut_result?;
-            let response = fut.map(|body| {
-                if body.is_empty() {
-                    SdkBody::empty()
-                } else {
-                    SdkBody::from(body)
-                }
-            });
+            let http_req = request
+                .try_into_http1x()
+                .map_err(|err| ConnectorError::user(err.into()))?;
+            let (parts, body) = http_req.into_parts();
+            let request =
+                WasiRequest::try_from(parts).map_err(|err| ConnectorError::user(err.into()))?;
+
+            // The body must be taken before the request is handed over to the host, but it
+            // is only written afterwards so that it can be streamed while the host sends it.
+            let outgoing_body = request.0.body().map_err(|_| {
+                ConnectorError::other("request body accessed more than once".into(), None)
+            })?;
+            let incoming =
+                outgoing_handler::handle(request.0, options.0).map_err(to_connector_error)?;
+            write_body(outgoing_body, body).await?;
+
+            // The FutureIncomingResponse .get() method returns a
+            // Option<Result<Result<IncomingResponse, ErrorCode>, ()>>.
+            // The outer Option indicates readiness, so we wait on the subscription until it is Some
+            // The outer Result is just a singleton enforcer so we can only get the response once
+            // The inner Result indicates whether the HTTP call was sent/received successfully (not the 200 succes of the call)
+            Subscription::new(incoming.subscribe()).ready().await;
+            let incoming_res = incoming
+                .get()
+                .expect("Http response not ready")
+                .map_err(|_| {
+                    ConnectorError::other("response accessed more than once".into(), None)
+                })?
+                .map_err(to_connector_error)?;
+
+            let response = http::Response::try_from(WasiResponse(incoming_res))
+                .map_err(|err| ConnectorError::other(err.into(), None))?;
             tracing::trace!("WasiHttpConnector: response received {response:?}");
 
             let sdk_res = Response::try_from(response)
@@ -102,45 +136,119 @@ impl HttpConnector for WasiHttpConnector {
     }
 }
 
-/// WASI HTTP client containing the options passed to the outgoing_handler
-struct WasiDefaultClient {
-    options: WasiRequestOptions,
+/// Writes the request body to the WASI outgoing body, streaming it as the host accepts data.
+async fn write_body(outgoing: OutgoingBody, mut body: SdkBody) -> Result<(), ConnectorError> {
+    let stream = outgoing
+        .write()
+        .map_err(|_| ConnectorError::other("output stream accessed more than once".into(), None))?;
+
+    let mut trailers = None;
+    while let Some(frame) = poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let frame = frame.map_err(|err| ConnectorError::other(err, None))?;
+        match frame.into_data() {
+            Ok(data) => write_all(&stream, &data).await?,
+            Err(frame) => {
+                if let Ok(headers) = frame.into_trailers() {
+                    let headers = WasiHeaders::try_from(&headers)
+                        .map_err(|err| ConnectorError::user(err.into()))?;
+                    trailers = Some(headers.0);
+                }
+            }
+        }
+    }
+    flush(&stream).await?;
+
+    // The OutputStream is a child resource: it must be dropped
+    // before the parent OutgoingBody resource is dropped (or finished),
+    // otherwise the OutgoingBody drop or finish will trap.
+    drop(stream);
+
+    OutgoingBody::finish(outgoing, trailers).map_err(to_connector_error)
 }
 
-impl WasiDefaultClient {
-    /// Create a new WASI HTTP client.
-    fn new(options: WasiRequestOptions) -> Self {
-        Self { options }
+/// Writes all of `data` to the stream, waiting whenever the host is not ready to accept more.
+async fn write_all(stream: &OutputStream, mut data: &[u8]) -> Result<(), ConnectorError> {
+    while !data.is_empty() {
+        let permitted = stream.check_write().map_err(stream_error)?;
+        if permitted == 0 {
+            Subscription::new(stream.subscribe()).ready().await;
+            continue;
+        }
+        let len = usize::try_from(permitted)
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        let (chunk, rest) = data.split_at(len);
+        stream.write(chunk).map_err(stream_error)?;
+        data = rest;
+    }
+    Ok(())
+}
+
+/// Flushes the stream and waits until the host has completed the flush.
+async fn flush(stream: &OutputStream) -> Result<(), ConnectorError> {
+    stream.flush().map_err(stream_error)?;
+    Subscription::new(stream.subscribe()).ready().await;
+    // A completed flush is reported as the stream being writable again; any error
+    // encountered by the host while flushing is surfaced here.
+    stream.check_write().map_err(stream_error)?;
+    Ok(())
+}
+
+/// Converts a failed WASI stream operation into a [`ConnectorError`].
+fn stream_error(err: wasi::io::streams::StreamError) -> ConnectorError {
+    match err {
+        wasi::io::streams::StreamError::LastOperationFailed(err) => io_error(err),
+        wasi::io::streams::StreamError::Closed => {
+            ConnectorError::io("stream closed by the host".into())
+        }
+    }
+}
+
+/// Converts a WASI I/O error into a [`ConnectorError`], using the HTTP error code when the
+/// host provides one.
+pub(crate) fn io_error(err: IoError) -> ConnectorError {
+    match wasi_http::http_error_code(&err) {
+        Some(code) => to_connector_error(code),
+        None => ConnectorError::io(err.to_debug_string().into()),
     }
+}
 
-    /// Make outgoing HTTP request in a WASI environment
-    fn handle(&self, req: http::Request<Bytes>) -> Result<http::Response<Bytes>, ConnectorError> {
-        let req =
-            WasiRequest::try_from(req).map_err(|err| ConnectorError::other(err.into(), None))?;
-
-        let res = outgoing_handler::handle(req.0, self.options.clone().0)
-            .map_err(|err| ConnectorError::other(err.into(), None))?;
-
-        // Right now only synchronous calls can be made through WASI, so we subscribe and
-        // block on the FutureIncomingResponse
-        let subscription = res.subscribe();
-        subscription.block();
-
-        //The FutureIncomingResponse .get() method returns a
-        //Option<Result<Result<IncomingResponse, ErrorCode>, ()>>.
-        //The outer Option ensures readiness which we know is Some because we .block() waiting for it
-        //The outer Result is just a singleton enforcer so we can only get the response once
-        //The inner Result indicates whether the HTTP call was sent/received successfully (not the 200 succes of the call)
-        let incoming_res = res
-            .get()
-            .expect("Http response not ready")
-            .expect("Http response accessed more than once")
-            .map_err(|err| ConnectorError::other(err.into(), None))?;
-
-        let response = http::Response::try_from(WasiResponse(incoming_res))
-            .map_err(|err| ConnectorError::other(err.into(), None))?;
-
-        Ok(response)
+/// Classifies a WASI HTTP [`ErrorCode`] into the matching [`ConnectorError`] kind.
+///
+/// Timeouts are reported as timeout errors, failures to reach or talk to the destination
+/// as IO errors (which are retried as transient errors), and problems with the request
+/// itself as user errors.
+pub(crate) fn to_connector_error(code: ErrorCode) -> ConnectorError {
+    use ErrorCode::*;
+    match code {
+        DnsTimeout
+        | ConnectionTimeout
+        | ConnectionReadTimeout
+        | ConnectionWriteTimeout
+        | HttpResponseTimeout => ConnectorError::timeout(code.into()),
+        DnsError(_)
+        | DestinationNotFound
+        | DestinationUnavailable
+        | DestinationIpProhibited
+        | DestinationIpUnroutable
+        | ConnectionRefused
+        | ConnectionTerminated
+        | ConnectionLimitReached
+        | TlsProtocolError
+        | TlsCertificateError
+        | TlsAlertReceived(_)
+        | HttpResponseIncomplete => ConnectorError::io(code.into()),
+        HttpRequestDenied
+        | HttpRequestLengthRequired
+        | HttpRequestBodySize(_)
+        | HttpRequestMethodInvalid
+        | HttpRequestUriInvalid
+        | HttpRequestUriTooLong
+        | HttpRequestHeaderSectionSize(_)
+        | HttpRequestHeaderSize(_)
+        | HttpRequestTrailerSectionSize(_)
+        | HttpRequestTrailerSize(_) => ConnectorError::user(code.into()),
+        _ => ConnectorError::other(code.into(), None),
     }
 }
 
@@ -150,9 +258,9 @@ struct WasiRequestOptions(Option<outgoing_handler::RequestOptions>);
 
 impl From<&HttpConnectorSettings> for WasiRequestOptions {
     fn from(value: &HttpConnectorSettings) -> Self {
-        //The WASI Duration is nanoseconds represented as u64
-        //Note: that the HttpConnectorSettings provides nanoseconds as u128
-        //so here we are clamping to u64::MAX if the value is above that
+        // The WASI Duration is nanoseconds represented as u64
+        // Note: that the HttpConnectorSettings provides nanoseconds as u128
+        // so here we are clamping to u64::MAX if the value is above that
         let connect_timeout = value
             .connect_timeout()
             .map(|dur| u64::try_from(dur.as_nanos()).unwrap_or(u64::MAX));
@@ -160,37 +268,36 @@ impl From<&HttpConnectorSettings> for WasiRequestOptions {
             .read_timeout()
             .map(|dur| u64::try_from(dur.as_nanos()).unwrap_or(u64::MAX));
 
-        //Note: these only fail if setting this particular type of timeout is not
-        //supported. Spec compliant runtimes should always support these so it is
-        //unlikely to be an issue.
+        // Note: these only fail if setting this particular type of timeout is not
+        // supported by the host, in which case the host default is used instead.
         let wasi_http_opts = wasi_http::RequestOptions::new();
-        wasi_http_opts
-            .set_connect_timeout(connect_timeout)
-            .expect("Connect timeout not supported");
-        wasi_http_opts
-            .set_first_byte_timeout(read_timeout)
-            .expect("Read timeout not supported");
+        if wasi_http_opts.set_connect_timeout(connect_timeout).is_err() {
+            tracing::warn!("connect timeout is not supported by the WASI host");
+        }
+        if wasi_http_opts.set_first_byte_timeout(read_timeout).is_err() {
+            tracing::warn!("first byte timeout is not supported by the WASI host");
+        }
+        if wasi_http_opts
+            .set_between_bytes_timeout(read_timeout)
+            .is_err()
+        {
+            tracing::warn!("between bytes timeout is not supported by the WASI host");
+        }
 
         WasiRequestOptions(Some(wasi_http_opts))
     }
 }
-//The WASI RequestOptions type doesn't impl copy or clone but the outgoing_handler::handle method
-//takes ownership, so we impl it on this wrapper type
+// The WASI RequestOptions type doesn't impl copy or clone but the outgoing_handler::handle method
+// takes ownership, so we impl it on this wrapper type
 impl Clone for WasiRequestOptions {
     fn clone(&self) -> Self {
-        //Note none of the expects here should ever trigger since all of the values passed in are from
-        //the existing RequestOptions that is being cloned and should be valid
+        // Errors are ignored here: any timeout the host doesn't support was already
+        // reported (and left unset) when the original RequestOptions were created
         let new_opts = if let Some(opts) = &self.0 {
             let new_opts = RequestOptions::new();
-            new_opts
-                .set_between_bytes_timeout(opts.between_bytes_timeout())
-                .expect("Between bytes timeout");
-            new_opts
-                .set_connect_timeout(opts.connect_timeout())
-                .expect("Connect timeout");
-            new_opts
-                .set_first_byte_timeout(opts.first_byte_timeout())
-                .expect("First byte timeout");
+            let _ = new_opts.set_between_bytes_timeout(opts.between_bytes_timeout());
+            let _ = new_opts.set_connect_timeout(opts.connect_timeout());
+            let _ = new_opts.set_first_byte_timeout(opts.first_byte_timeout());
 
             Some(new_opts)
         } else {
@@ -205,14 +312,13 @@ impl Clone for WasiRequestOptions {
 #[derive(Debug)]
 struct WasiRequest(outgoing_handler::OutgoingRequest);
 
-impl TryFrom<http::Request<Bytes>> for WasiRequest {
+impl TryFrom<http::request::Parts> for WasiRequest {
     type Error = ParseError;
 
-    fn try_from(value: http::Request<Bytes>) -> Result<Self, Self::Error> {
-        let (parts, body) = value.into_parts();
+    fn try_from(parts: http::request::Parts) -> Result<Self, Self::Error> {
         let method = WasiMethod::try_from(parts.method)?;
         let path_with_query = parts.uri.path_and_query().map(|path| path.as_str());
-        let headers = WasiHeaders::try_from(parts.headers)?;
+        let headers = WasiHeaders::try_from(&parts.headers)?;
         let scheme = match parts.uri.scheme_str().unwrap_or("") {
             "http" => Some(&wasi_http::Scheme::Http),
             "https" => Some(&wasi_http::Scheme::Https),
@@ -234,24 +340,6 @@ impl TryFrom<http::Request<Bytes>> for WasiRequest {
             .set_authority(authority)
             .map_err(|_| ParseError::new("Failed to set HTTP authority"))?;
 
-        let request_body = request.body().expect("Body accessed more than once");
-
-        let request_stream = request_body
-            .write()
-            .expect("Output stream accessed more than once");
-
-        request_stream
-            .blocking_write_and_flush(&body)
-            .map_err(|_| ParseError::new("Failed to write HTTP body"))?;
-
-        //The OutputStream is a child resource: it must be dropped
-        //before the parent OutgoingBody resource is dropped (or finished),
-        //otherwise the OutgoingBody drop or finish will trap.
-        drop(request_stream);
-
-        OutgoingBody::finish(request_body, None)
-            .map_err(|_| ParseError::new("Failed to finalize HTTP body"))?;
-
         Ok(WasiRequest(request))
     }
 }
@@ -281,7 +369,7 @@ impl TryFrom<http::Method> for WasiMethod {
 /// Wrapper to allow converting between HTTP Response types and WASI Response types
 struct WasiResponse(wasi_http::IncomingResponse);
 
-impl TryFrom<WasiResponse> for http::Response<Bytes> {
+impl TryFrom<WasiResponse> for http::Response<SdkBody> {
     type Error = ParseError;
 
     fn try_from(value: WasiResponse) -> Result<Self, Self::Error> {
@@ -289,8 +377,8 @@ impl TryFrom<WasiResponse> for http::Response<Bytes> {
 
         let status = response.status();
 
-        //This headers resource is a child: it must be dropped before the parent incoming-response is dropped.
-        //The drop happens via the consuming iterator used below
+        // This headers resource is a child: it must be dropped before the parent incoming-response is dropped.
+        // The drop happens via the consuming iterator used below
         let headers = response.headers().entries();
 
         let res_build = headers
@@ -300,25 +388,11 @@ impl TryFrom<WasiResponse> for http::Response<Bytes> {
             });
 
         let body_incoming = response.consume().expect("Consume called more than once");
-
-        //The input-stream resource is a child: it must be dropped before the parent
-        //incoming-body is dropped, or consumed by incoming-body.finish.
-        //That drop is done explicitly below
-        let body_stream = body_incoming
-            .stream()
-            .expect("Stream accessed more than once");
-
-        let mut body = BytesMut::new();
-
-        //blocking_read blocks until at least one byte is available
-        while let Ok(stream_bytes) = body_stream.blocking_read(u64::MAX) {
-            body.extend_from_slice(stream_bytes.as_slice())
-        }
-
-        drop(body_stream);
+        let body = WasiResponseBody::new(body_incoming)
+            .map_err(|_| ParseError::new("Response body stream accessed more than once"))?;
 
         let res = res_build
-            .body(body.freeze())
+            .body(SdkBody::from_body_1_x(body))
             .map_err(|err| ParseError::new(err.to_string()))?;
 
         Ok(res)
@@ -328,18 +402,13 @@ impl TryFrom<WasiResponse> for http::Response<Bytes> {
 /// Wrapper to allow converting between HTTP headers and WASI headers
 struct WasiHeaders(wasi_http::Fields);
 
-impl TryFrom<http::HeaderMap> for WasiHeaders {
+impl TryFrom<&http::HeaderMap> for WasiHeaders {
     type Error = ParseError;
 
-    fn try_from(headers: http::HeaderMap) -> Result<Self, Self::Error> {
+    fn try_from(headers: &http::HeaderMap) -> Result<Self, Self::Error> {
         let entries = headers
             .iter()
-            .map(|(name, value)| {
-                (
-                    name.to_string(),
-                    value.to_str().unwrap().as_bytes().to_vec(),
-                )
-            })
+            .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
             .collect::<Vec<_>>();
 
         let fields = wasi_http::Fields::from_list(&entries)
@@ -348,3 +417,61 @@ impl TryFrom<http::HeaderMap> for WasiHeaders {
         Ok(Self(fields))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::to_connector_error;
+    use wasi::http::types::{DnsErrorPayload, ErrorCode};
+
+    #[test]
+    fn timeouts_are_classified_as_timeout_errors() {
+        for code in [
+            ErrorCode::DnsTimeout,
+            ErrorCode::ConnectionTimeout,
+            ErrorCode::ConnectionReadTimeout,
+            ErrorCode::ConnectionWriteTimeout,
+            ErrorCode::HttpResponseTimeout,
+        ] {
+            assert!(to_connector_error(code).is_timeout());
+        }
+    }
+
+    #[test]
+    fn connection_failures_are_classified_as_io_errors() {
+        for code in [
+            ErrorCode::DnsError(DnsErrorPayload {
+                rcode: Some("NXDOMAIN".into()),
+                info_code: None,
+            }),
+            ErrorCode::DestinationUnavailable,
+            ErrorCode::ConnectionRefused,
+            ErrorCode::ConnectionTerminated,
+            ErrorCode::TlsCertificateError,
+            ErrorCode::HttpResponseIncomplete,
+        ] {
+            assert!(to_connector_error(code).is_io());
+        }
+    }
+
+    #[test]
+    fn invalid_requests_are_classified_as_user_errors() {
+        for code in [
+            ErrorCode::HttpRequestMethodInvalid,
+            ErrorCode::HttpRequestUriInvalid,
+            ErrorCode::HttpRequestBodySize(Some(10)),
+            ErrorCode::HttpRequestHeaderSectionSize(None),
+        ] {
+            assert!(to_connector_error(code).is_user());
+        }
+    }
+
+    #[test]
+    fn other_failures_are_classified_as_other_errors() {
+        for code in [
+            ErrorCode::HttpProtocolError,
+            ErrorCode::InternalError(Some("boom".into())),
+        ] {
+            assert!(to_connector_error(code).is_other());
+        }
+    }
+}
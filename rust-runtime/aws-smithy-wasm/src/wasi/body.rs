@@ -0,0 +1,146 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Streaming response body backed by a WASI incoming body
+
+use super::poll::Subscription;
+use super::{io_error, to_connector_error};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use bytes::Bytes;
+use http::{HeaderMap, HeaderName, HeaderValue};
+use http_body::{Body, Frame};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use wasi::http::types::{FutureTrailers, IncomingBody};
+use wasi::io::streams::{InputStream, StreamError};
+
+/// Maximum number of bytes requested from the host per read.
+const READ_SIZE: u64 = 64 * 1024;
+
+/// A response body that reads from the WASI input stream as data is polled.
+pub(super) struct WasiResponseBody {
+    state: State,
+}
+
+enum State {
+    Streaming {
+        // The input-stream resource is a child: it must be dropped before the parent
+        // incoming-body is dropped, or consumed by incoming-body.finish, and its pollable
+        // must be dropped before it.
+        // Fields are dropped in declaration order, so this order must be kept.
+        subscription: Option<Subscription>,
+        stream: InputStream,
+        body: IncomingBody,
+    },
+    Trailers {
+        // The pollable is a child of the future-trailers resource, so it must stay first.
+        subscription: Option<Subscription>,
+        trailers: FutureTrailers,
+    },
+    Done,
+}
+
+impl WasiResponseBody {
+    pub(super) fn new(body: IncomingBody) -> Result<Self, ()> {
+        let stream = body.stream()?;
+        Ok(Self {
+            state: State::Streaming {
+                subscription: None,
+                stream,
+                body,
+            },
+        })
+    }
+}
+
+impl Body for WasiResponseBody {
+    type Data = Bytes;
+    type Error = ConnectorError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        loop {
+            match &mut self.state {
+                State::Streaming {
+                    subscription,
+                    stream,
+                    ..
+                } => match stream.read(READ_SIZE) {
+                    Ok(bytes) if bytes.is_empty() => {
+                        // No data is available yet, so wait until the stream can be read again
+                        let subscription = subscription
+                            .get_or_insert_with(|| Subscription::new(stream.subscribe()));
+                        if subscription.poll_ready(cx).is_pending() {
+                            return Poll::Pending;
+                        }
+                    }
+                    Ok(bytes) => return Poll::Ready(Some(Ok(Frame::data(Bytes::from(bytes))))),
+                    Err(StreamError::Closed) => {
+                        let State::Streaming {
+                            subscription,
+                            stream,
+                            body,
+                        } = std::mem::replace(&mut self.state, State::Done)
+                        else {
+                            unreachable!()
+                        };
+                        drop(subscription);
+                        drop(stream);
+                        self.state = State::Trailers {
+                            subscription: None,
+                            trailers: IncomingBody::finish(body),
+                        };
+                    }
+                    Err(StreamError::LastOperationFailed(err)) => {
+                        self.state = State::Done;
+                        return Poll::Ready(Some(Err(io_error(err))));
+                    }
+                },
+                State::Trailers {
+                    subscription,
+                    trailers,
+                } => {
+                    let subscription =
+                        subscription.get_or_insert_with(|| Subscription::new(trailers.subscribe()));
+                    if subscription.poll_ready(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                    let result = trailers.get().expect("trailers are ready");
+                    self.state = State::Done;
+                    return match result {
+                        Ok(Ok(Some(trailers))) => Poll::Ready(Some(
+                            to_header_map(trailers.entries()).map(Frame::trailers),
+                        )),
+                        Ok(Ok(None)) => Poll::Ready(None),
+                        Ok(Err(code)) => Poll::Ready(Some(Err(to_connector_error(code)))),
+                        Err(()) => Poll::Ready(Some(Err(ConnectorError::other(
+                            "response trailers accessed more than once".into(),
+                            None,
+                        )))),
+                    };
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+}
+
+fn to_header_map(entries: Vec<(String, Vec<u8>)>) -> Result<HeaderMap, ConnectorError> {
+    let mut headers = HeaderMap::with_capacity(entries.len());
+    for (name, value) in entries {
+        let name =
+            HeaderName::try_from(name).map_err(|err| ConnectorError::other(err.into(), None))?;
+        let value =
+            HeaderValue::try_from(value).map_err(|err| ConnectorError::other(err.into(), None))?;
+        headers.append(name, value);
+    }
+    Ok(headers)
+}
@@ -0,0 +1,130 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! Waiting on WASI pollables without busy-polling
+
+use std::cell::RefCell;
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll, Waker};
+use wasi::io::poll::{poll, Pollable};
+
+thread_local! {
+    // The pollables that futures of this thread are waiting on, with the wakers of their tasks.
+    static WAITING: RefCell<Vec<Waiting>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Waiting {
+    // Only a weak reference is kept, so that the pollable is still dropped with its future:
+    // pollables can be children of other resources, which trap if they're dropped first.
+    pollable: Weak<Pollable>,
+    waker: Waker,
+}
+
+/// A WASI [`Pollable`] that futures can wait on.
+///
+/// WASI Preview 2 has no way to register a waker with the host. Instead, a pollable that isn't
+/// ready is registered, with the waker of its task, in a list of waiting pollables shared by every
+/// future of the thread, and its task is woken once, so that the other futures of the executor
+/// get the chance to be polled and register their own pollables. If the pollable still isn't ready
+/// when it's polled again, nothing can make progress until the host reports that one of the
+/// waiting pollables is ready, so the component blocks on all of them at once with
+/// [`wasi::io::poll::poll`], and the tasks of the pollables that are ready are woken. As every
+/// waiting pollable is included, timeouts keep firing while a request is in flight.
+pub(super) struct Subscription {
+    pollable: Arc<Pollable>,
+    registered: bool,
+}
+
+impl Subscription {
+    pub(super) fn new(pollable: Pollable) -> Self {
+        Self {
+            pollable: Arc::new(pollable),
+            registered: false,
+        }
+    }
+
+    /// Waits until the pollable is ready.
+    pub(super) async fn ready(&mut self) {
+        std::future::poll_fn(|cx| self.poll_ready(cx)).await
+    }
+
+    /// Polls the pollable, blocking on the waiting pollables when the executor is idle.
+    pub(super) fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if !self.pollable.ready() && self.registered {
+            block_until_ready();
+        }
+        if self.pollable.ready() {
+            if self.registered {
+                self.registered = false;
+                unregister(&self.pollable);
+            }
+            return Poll::Ready(());
+        }
+        register(&self.pollable, cx.waker());
+        self.registered = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if self.registered {
+            unregister(&self.pollable);
+        }
+    }
+}
+
+fn register(pollable: &Arc<Pollable>, waker: &Waker) {
+    let pollable = Arc::downgrade(pollable);
+    WAITING.with(|waiting| {
+        let mut waiting = waiting.borrow_mut();
+        match waiting
+            .iter_mut()
+            .find(|waiting| waiting.pollable.ptr_eq(&pollable))
+        {
+            Some(waiting) => waiting.waker.clone_from(waker),
+            None => waiting.push(Waiting {
+                pollable,
+                waker: waker.clone(),
+            }),
+        }
+    });
+}
+
+fn unregister(pollable: &Arc<Pollable>) {
+    let pollable = Arc::downgrade(pollable);
+    // The list may already be gone if the subscription is dropped while the thread exits.
+    let _ = WAITING.try_with(|waiting| {
+        waiting
+            .borrow_mut()
+            .retain(|waiting| !waiting.pollable.ptr_eq(&pollable))
+    });
+}
+
+/// Blocks until at least one of the waiting pollables is ready, and wakes the tasks waiting on
+/// the pollables that are ready.
+fn block_until_ready() {
+    let ready: Vec<Waker> = WAITING.with(|waiting| {
+        let mut waiting = waiting.borrow_mut();
+        let pollables: Vec<Arc<Pollable>> = waiting
+            .iter()
+            .filter_map(|waiting| waiting.pollable.upgrade())
+            .collect();
+        waiting.retain(|waiting| waiting.pollable.strong_count() > 0);
+        if pollables.is_empty() {
+            return Vec::new();
+        }
+        let mut ready = poll(&pollables.iter().map(Arc::as_ref).collect::<Vec<_>>());
+        // Removing the pollables from the last one keeps the indices of the others valid.
+        ready.sort_unstable_by(|a, b| b.cmp(a));
+        ready
+            .into_iter()
+            .map(|index| waiting.remove(index as usize).waker)
+            .collect()
+    });
+    // The wakers are called once the list is released, in case waking a task registers pollables.
+    ready.into_iter().for_each(Waker::wake);
+}
@@ -0,0 +1,34 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! WASI implementation of [`AsyncSleep`]
+
+use super::poll::Subscription;
+use aws_smithy_async::rt::sleep::{AsyncSleep, Sleep};
+use std::time::Duration;
+use wasi::clocks::monotonic_clock;
+
+/// Implementation of [`AsyncSleep`] using the monotonic clock of the WASI host.
+///
+/// This allows clients to retry and enforce timeouts without depending on Tokio.
+#[non_exhaustive]
+#[derive(Debug, Default)]
+pub struct WasiSleep;
+
+impl WasiSleep {
+    /// Create a new [`AsyncSleep`] implementation using the WASI monotonic clock
+    pub fn new() -> WasiSleep {
+        Default::default()
+    }
+}
+
+impl AsyncSleep for WasiSleep {
+    fn sleep(&self, duration: Duration) -> Sleep {
+        // The WASI Duration is nanoseconds represented as u64, clamp if the value is above that
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        let mut subscription = Subscription::new(monotonic_clock::subscribe_duration(nanos));
+        Sleep::new(async move { subscription.ready().await })
+    }
+}
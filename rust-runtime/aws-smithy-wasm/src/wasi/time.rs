@@ -0,0 +1,29 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ */
+
+//! WASI implementation of [`TimeSource`]
+
+use aws_smithy_async::time::TimeSource;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wasi::clocks::wall_clock;
+
+/// Implementation of [`TimeSource`] using the wall clock of the WASI host.
+#[non_exhaustive]
+#[derive(Debug, Default)]
+pub struct WasiTimeSource;
+
+impl WasiTimeSource {
+    /// Creates a new WasiTimeSource
+    pub fn new() -> Self {
+        WasiTimeSource
+    }
+}
+
+impl TimeSource for WasiTimeSource {
+    fn now(&self) -> SystemTime {
+        let now = wall_clock::now();
+        UNIX_EPOCH + Duration::new(now.seconds, now.nanoseconds)
+    }
+}